@@ -97,7 +97,8 @@ impl TryFrom<&[u8]> for AssetCatalog {
             HashMap::with_capacity(asset_id_to_info_num as usize);
 
         let asset_id_to_info_ref_size = std::mem::size_of::<AssetIdToInfoRef>() as u64;
-        let mut asset_id_to_info_data = vec![0u8; asset_id_to_info_num as usize];
+        let mut asset_id_to_info_data =
+            vec![0u8; asset_id_to_info_ref_size as usize * asset_id_to_info_num as usize];
         data.read_exact(&mut asset_id_to_info_data)?;
 
         let mut guid_data = vec![0u8; 16 * asset_id_to_info_num as usize];
@@ -261,6 +262,35 @@ impl TryFrom<&[u8]> for AssetCatalog {
 }
 
 impl AssetCatalog {
+    /// Parses `data` into the global catalog. Later calls return the catalog from the first one.
+    pub fn init(data: &[u8]) -> io::Result<&'static AssetCatalog> {
+        if let Some(catalog) = CATALOG.get() {
+            return Ok(catalog);
+        }
+        let catalog = AssetCatalog::try_from(data)?;
+        Ok(CATALOG.get_or_init(|| catalog))
+    }
+
+    pub fn get() -> Option<&'static AssetCatalog> {
+        CATALOG.get()
+    }
+
+    pub fn version(&self) -> u32 {
+        self.version
+    }
+
+    pub fn len(&self) -> usize {
+        self.asset_infos.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.asset_infos.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &AssetInfo> {
+        self.asset_infos.iter()
+    }
+
     pub fn get_asset_info_by_id<T>(&'static self, id: T) -> io::Result<&AssetInfo>
     where
         T: AsRef<AssetId>,
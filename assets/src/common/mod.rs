@@ -1,4 +1,4 @@
-use std::{array::TryFromSliceError, collections::HashMap, path::PathBuf};
+use std::{array::TryFromSliceError, collections::HashMap, path::PathBuf, str::FromStr};
 use uuid::Uuid;
 
 pub struct ProductDependancy {
@@ -14,6 +14,42 @@ pub struct AssetId {
 
 impl AssetId {}
 
+impl AsRef<AssetId> for AssetId {
+    fn as_ref(&self) -> &AssetId {
+        self
+    }
+}
+
+/// Parses `{GUID}` or `{GUID}:subId`, the sub id defaulting to 0.
+impl FromStr for AssetId {
+    type Err = std::io::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = |e: &dyn std::fmt::Display| {
+            std::io::Error::new(std::io::ErrorKind::InvalidInput, format!("{s}: {e}"))
+        };
+        let (guid, sub_id) = match s.rsplit_once(':') {
+            Some((guid, sub_id)) => (guid, sub_id.trim().parse().map_err(|e| invalid(&e))?),
+            None => (s, 0),
+        };
+        Ok(Self {
+            guid: Uuid::parse_str(guid.trim()).map_err(|e| invalid(&e))?,
+            sub_id,
+        })
+    }
+}
+
+impl std::fmt::Display for AssetId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}:{}",
+            self.guid.braced().encode_upper(&mut Uuid::encode_buffer()),
+            self.sub_id
+        )
+    }
+}
+
 impl TryFrom<&[u8]> for AssetId {
     type Error = TryFromSliceError;
 
@@ -4,6 +4,8 @@ mod assetmanager;
 mod assetregistry;
 mod common;
 
+pub use common::{AssetId, AssetInfo};
+
 #[cfg(test)]
 mod test {
     use std::io::Cursor;
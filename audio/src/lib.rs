@@ -0,0 +1,238 @@
+//! Wwise `.wem` audio and `.bnk` soundbanks.
+
+use std::io::{self, Cursor};
+
+mod ogg;
+mod vorbis;
+
+pub use vorbis::{to_ogg, Codebooks};
+
+const CODEC_PCM: u16 = 0x0001;
+const CODEC_EXTENSIBLE: u16 = 0xFFFE;
+const CODEC_VORBIS: u16 = 0xFFFF;
+
+/// Whether `buf` starts like a `.wem` file.
+pub fn is_wem(buf: &[u8]) -> bool {
+    buf.starts_with(b"RIFF") && buf.get(8..12) == Some(b"WAVE")
+}
+
+/// Whether `buf` starts like a `.bnk` soundbank.
+pub fn is_soundbank(buf: &[u8]) -> bool {
+    buf.starts_with(b"BKHD")
+}
+
+/// The format and audio data of a `.wem` file.
+pub struct Wem<'a> {
+    pub codec: u16,
+    pub channels: u16,
+    pub sample_rate: u32,
+    pub avg_bytes_per_second: u32,
+    pub bits_per_sample: u16,
+    /// Wwise Vorbis setup, either its own chunk or the tail of an extended `fmt ` chunk.
+    vorb: Option<&'a [u8]>,
+    data: &'a [u8],
+}
+
+impl<'a> Wem<'a> {
+    pub fn from_bytes(buf: &'a [u8]) -> io::Result<Self> {
+        if !is_wem(buf) {
+            return Err(invalid("missing RIFF/WAVE signature"));
+        }
+
+        let (mut fmt, mut vorb, mut data) = (None, None, None);
+        for (id, chunk) in chunks(&buf[12..]) {
+            match id {
+                b"fmt " => fmt = Some(chunk),
+                b"vorb" => vorb = Some(chunk),
+                b"data" => data = Some(chunk),
+                _ => {}
+            }
+        }
+        let fmt = fmt
+            .filter(|fmt| fmt.len() >= 0x10)
+            .ok_or_else(|| invalid("missing fmt chunk"))?;
+        let data = data.ok_or_else(|| invalid("missing data chunk"))?;
+
+        let u16_at = |offset: usize| u16::from_le_bytes([fmt[offset], fmt[offset + 1]]);
+        let u32_at =
+            |offset: usize| u32::from_le_bytes(fmt[offset..offset + 4].try_into().unwrap());
+        if fmt.len() == 0x42 {
+            vorb = Some(&fmt[0x18..]);
+        }
+
+        Ok(Self {
+            codec: u16_at(0x00),
+            channels: u16_at(0x02),
+            sample_rate: u32_at(0x04),
+            avg_bytes_per_second: u32_at(0x08),
+            bits_per_sample: u16_at(0x0E),
+            vorb,
+            data,
+        })
+    }
+
+    pub fn is_vorbis(&self) -> bool {
+        self.codec == CODEC_VORBIS
+    }
+
+    /// Converts the audio to a standard `.wav`, decoding Vorbis with `codebooks`.
+    pub fn to_wav(&self, codebooks: Option<&Codebooks>) -> io::Result<Vec<u8>> {
+        match self.codec {
+            CODEC_PCM | CODEC_EXTENSIBLE => Ok(wav(
+                self.channels,
+                self.sample_rate,
+                self.bits_per_sample,
+                self.data,
+            )),
+            CODEC_VORBIS => {
+                let codebooks = codebooks.ok_or_else(|| invalid("Vorbis needs codebooks"))?;
+                decode(to_ogg(self, codebooks)?)
+            }
+            codec => Err(invalid(format!("unsupported wem codec {codec:#06x}"))),
+        }
+    }
+}
+
+/// Decodes an Ogg Vorbis stream into a 16 bit `.wav`.
+fn decode(ogg: Vec<u8>) -> io::Result<Vec<u8>> {
+    let mut reader =
+        lewton::inside_ogg::OggStreamReader::new(Cursor::new(ogg)).map_err(io::Error::other)?;
+    let channels = reader.ident_hdr.audio_channels as u16;
+    let sample_rate = reader.ident_hdr.audio_sample_rate;
+
+    let mut samples = vec![];
+    while let Some(packet) = reader.read_dec_packet_itl().map_err(io::Error::other)? {
+        samples.extend(packet.into_iter().flat_map(i16::to_le_bytes));
+    }
+    Ok(wav(channels, sample_rate, 16, &samples))
+}
+
+fn wav(channels: u16, sample_rate: u32, bits_per_sample: u16, data: &[u8]) -> Vec<u8> {
+    let block_align = channels * bits_per_sample / 8;
+
+    let mut out = Vec::with_capacity(44 + data.len());
+    out.extend_from_slice(b"RIFF");
+    out.extend((36 + data.len() as u32).to_le_bytes());
+    out.extend_from_slice(b"WAVEfmt ");
+    out.extend(16u32.to_le_bytes());
+    out.extend(CODEC_PCM.to_le_bytes());
+    out.extend(channels.to_le_bytes());
+    out.extend(sample_rate.to_le_bytes());
+    out.extend((sample_rate * block_align as u32).to_le_bytes());
+    out.extend(block_align.to_le_bytes());
+    out.extend(bits_per_sample.to_le_bytes());
+    out.extend_from_slice(b"data");
+    out.extend((data.len() as u32).to_le_bytes());
+    out.extend_from_slice(data);
+    out
+}
+
+/// The `.wem` files embedded in a `.bnk` soundbank, by id. Banks that only reference streamed
+/// files have none.
+pub fn soundbank(buf: &[u8]) -> io::Result<Vec<(u32, &[u8])>> {
+    if !is_soundbank(buf) {
+        return Err(invalid("missing BKHD section"));
+    }
+
+    let (mut index, mut data) = (None, None);
+    for (id, section) in chunks(buf) {
+        match id {
+            b"DIDX" => index = Some(section),
+            b"DATA" => data = Some(section),
+            _ => {}
+        }
+    }
+    let (Some(index), Some(data)) = (index, data) else {
+        return Ok(vec![]);
+    };
+
+    index
+        .chunks_exact(12)
+        .map(|entry| {
+            let field = |i: usize| u32::from_le_bytes(entry[i..i + 4].try_into().unwrap());
+            let (id, offset, size) = (field(0), field(4) as usize, field(8) as usize);
+            let wem = data
+                .get(offset..offset + size)
+                .ok_or_else(|| invalid(format!("embedded wem {id} is out of bounds")))?;
+            Ok((id, wem))
+        })
+        .collect()
+}
+
+/// The `(id, contents)` of consecutive RIFF style chunks, stopping at the first truncated one.
+fn chunks(mut buf: &[u8]) -> impl Iterator<Item = (&[u8; 4], &[u8])> {
+    std::iter::from_fn(move || {
+        let id = buf.get(..4)?.try_into().ok()?;
+        let size = u32::from_le_bytes(buf.get(4..8)?.try_into().ok()?) as usize;
+        let chunk = buf.get(8..8 + size)?;
+        buf = &buf[8 + size..];
+        Some((id, chunk))
+    })
+}
+
+fn invalid(message: impl Into<String>) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn riff(chunks: &[(&[u8; 4], &[u8])]) -> Vec<u8> {
+        let body = chunks
+            .iter()
+            .flat_map(|(id, data)| {
+                let mut chunk = id.to_vec();
+                chunk.extend((data.len() as u32).to_le_bytes());
+                chunk.extend_from_slice(data);
+                chunk
+            })
+            .collect::<Vec<_>>();
+        let mut out = b"RIFF".to_vec();
+        out.extend((4 + body.len() as u32).to_le_bytes());
+        out.extend_from_slice(b"WAVE");
+        out.extend(body);
+        out
+    }
+
+    #[test]
+    fn pcm_wem_to_wav() {
+        let mut fmt = vec![];
+        fmt.extend(CODEC_EXTENSIBLE.to_le_bytes());
+        fmt.extend(2u16.to_le_bytes());
+        fmt.extend(48000u32.to_le_bytes());
+        fmt.extend(192000u32.to_le_bytes());
+        fmt.extend(4u16.to_le_bytes());
+        fmt.extend(16u16.to_le_bytes());
+        let samples = [1, 2, 3, 4, 5, 6, 7, 8];
+        let wem = riff(&[(b"fmt ", &fmt), (b"data", &samples)]);
+
+        let wem = Wem::from_bytes(&wem).unwrap();
+        assert!(!wem.is_vorbis());
+        let wav = wem.to_wav(None).unwrap();
+        assert_eq!(&wav[..4], b"RIFF");
+        assert_eq!(u16::from_le_bytes([wav[20], wav[21]]), CODEC_PCM);
+        assert_eq!(u16::from_le_bytes([wav[32], wav[33]]), 4);
+        assert_eq!(&wav[44..], &samples);
+    }
+
+    #[test]
+    fn unpacks_soundbank() {
+        let mut bank = b"BKHD".to_vec();
+        bank.extend(4u32.to_le_bytes());
+        bank.extend(0x8Cu32.to_le_bytes());
+        bank.extend_from_slice(b"DIDX");
+        bank.extend(24u32.to_le_bytes());
+        for (id, offset, size) in [(7u32, 0u32, 3u32), (9, 3, 2)] {
+            bank.extend(id.to_le_bytes());
+            bank.extend(offset.to_le_bytes());
+            bank.extend(size.to_le_bytes());
+        }
+        bank.extend_from_slice(b"DATA");
+        bank.extend(5u32.to_le_bytes());
+        bank.extend_from_slice(b"abcde");
+
+        let wems = soundbank(&bank).unwrap();
+        assert_eq!(wems, vec![(7, &b"abc"[..]), (9, &b"de"[..])]);
+    }
+}
@@ -0,0 +1,191 @@
+use std::io;
+
+/// Reads bits least significant first, the order both Wwise and Vorbis pack them in.
+pub(crate) struct BitReader<'a> {
+    data: &'a [u8],
+    bit: usize,
+}
+
+impl<'a> BitReader<'a> {
+    pub fn new(data: &'a [u8]) -> Self {
+        Self { data, bit: 0 }
+    }
+
+    pub fn read(&mut self, bits: u32) -> io::Result<u32> {
+        let mut value = 0;
+        for i in 0..bits {
+            let byte = self
+                .data
+                .get(self.bit / 8)
+                .ok_or(io::ErrorKind::UnexpectedEof)?;
+            if byte & (1 << (self.bit % 8)) != 0 {
+                value |= 1 << i;
+            }
+            self.bit += 1;
+        }
+        Ok(value)
+    }
+
+    pub fn bits_read(&self) -> usize {
+        self.bit
+    }
+}
+
+/// Writes bits least significant first, padding the last byte with zeros.
+#[derive(Default)]
+pub(crate) struct BitWriter {
+    buf: Vec<u8>,
+    bit: usize,
+}
+
+impl BitWriter {
+    pub fn write(&mut self, value: u32, bits: u32) {
+        for i in 0..bits {
+            let shift = self.bit % 8;
+            if shift == 0 {
+                self.buf.push(0);
+            }
+            if value & (1 << i) != 0 {
+                *self.buf.last_mut().unwrap() |= 1 << shift;
+            }
+            self.bit += 1;
+        }
+    }
+
+    pub fn bytes(&mut self, bytes: &[u8]) {
+        bytes.iter().for_each(|byte| self.write(*byte as u32, 8));
+    }
+
+    pub fn finish(self) -> Vec<u8> {
+        self.buf
+    }
+}
+
+const CRC_TABLE: [u32; 256] = crc_table();
+
+const fn crc_table() -> [u32; 256] {
+    let mut table = [0; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut crc = (i as u32) << 24;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 0x8000_0000 != 0 {
+                (crc << 1) ^ 0x04C1_1DB7
+            } else {
+                crc << 1
+            };
+            bit += 1;
+        }
+        table[i] = crc;
+        i += 1;
+    }
+    table
+}
+
+/// The page checksum: CRC32 with the 0x04C11DB7 polynomial, unreflected and without a final xor.
+fn crc(data: &[u8]) -> u32 {
+    data.iter().fold(0, |crc, byte| {
+        (crc << 8) ^ CRC_TABLE[((crc >> 24) as u8 ^ byte) as usize]
+    })
+}
+
+/// Writes a single logical Ogg stream, every packet starting on a new page.
+#[derive(Default)]
+pub(crate) struct OggWriter {
+    out: Vec<u8>,
+    sequence: u32,
+}
+
+impl OggWriter {
+    /// Appends `packet` ending at sample `granule`, spilling over as many pages as needed.
+    pub fn packet(&mut self, packet: &[u8], granule: u64, last: bool) {
+        let mut lacing = vec![255u8; packet.len() / 255];
+        lacing.push((packet.len() % 255) as u8);
+
+        let mut data = packet;
+        let pages = lacing.chunks(255).collect::<Vec<_>>();
+        for (index, segments) in pages.iter().enumerate() {
+            let len = segments.iter().map(|&s| s as usize).sum::<usize>();
+            let (payload, rest) = data.split_at(len);
+            data = rest;
+
+            let ends_packet = index == pages.len() - 1;
+            let mut flags = 0;
+            if index > 0 {
+                flags |= 0x01;
+            }
+            if self.sequence == 0 {
+                flags |= 0x02;
+            }
+            if last && ends_packet {
+                flags |= 0x04;
+            }
+
+            let start = self.out.len();
+            self.out.extend_from_slice(b"OggS");
+            self.out.push(0);
+            self.out.push(flags);
+            let granule = if ends_packet { granule } else { u64::MAX };
+            self.out.extend(granule.to_le_bytes());
+            self.out.extend(1u32.to_le_bytes());
+            self.out.extend(self.sequence.to_le_bytes());
+            self.out.extend(0u32.to_le_bytes());
+            self.out.push(segments.len() as u8);
+            self.out.extend_from_slice(segments);
+            self.out.extend_from_slice(payload);
+
+            let checksum = crc(&self.out[start..]);
+            self.out[start + 22..start + 26].copy_from_slice(&checksum.to_le_bytes());
+            self.sequence += 1;
+        }
+    }
+
+    pub fn finish(self) -> Vec<u8> {
+        self.out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bits_round_trip() {
+        let mut writer = BitWriter::default();
+        writer.write(0b101, 3);
+        writer.write(0x3FF, 10);
+        writer.write(1, 1);
+        let buf = writer.finish();
+        assert_eq!(buf.len(), 2);
+
+        let mut reader = BitReader::new(&buf);
+        assert_eq!(reader.read(3).unwrap(), 0b101);
+        assert_eq!(reader.read(10).unwrap(), 0x3FF);
+        assert_eq!(reader.read(1).unwrap(), 1);
+        assert_eq!(reader.bits_read(), 14);
+    }
+
+    #[test]
+    fn splits_long_packets_into_pages() {
+        let mut ogg = OggWriter::default();
+        ogg.packet(&[0; 255 * 255], 42, true);
+        let out = ogg.finish();
+
+        // 255 full segments on the first page, the terminating empty one on the second
+        assert_eq!(&out[..4], b"OggS");
+        assert_eq!(out[5], 0x02);
+        assert_eq!(out[6..14], u64::MAX.to_le_bytes());
+        let second = 27 + 255 + 255 * 255;
+        assert_eq!(&out[second..second + 4], b"OggS");
+        assert_eq!(out[second + 5], 0x01 | 0x04);
+        assert_eq!(out[second + 6..second + 14], 42u64.to_le_bytes());
+        assert_eq!(out.len(), second + 28);
+    }
+
+    #[test]
+    fn page_checksum() {
+        // CRC-32/POSIX check value without its final xor
+        assert_eq!(crc(b"123456789"), 0x765E_7680 ^ 0xFFFF_FFFF);
+    }
+}
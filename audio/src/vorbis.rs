@@ -0,0 +1,424 @@
+//! Rebuilds the standard Vorbis headers Wwise strips from its `.wem` files, following ww2ogg.
+
+use std::io;
+
+use crate::{
+    invalid,
+    ogg::{BitReader, BitWriter, OggWriter},
+    Wem,
+};
+
+const VENDOR: &str = "converted from Audiokinetic Wwise by nwtools";
+
+/// The shared codebooks Wwise refers to by id instead of storing them in every file, as shipped
+/// with ww2ogg in `packed_codebooks_aoTuV_603.bin`.
+pub struct Codebooks {
+    data: Vec<u8>,
+    offsets: Vec<usize>,
+}
+
+impl Codebooks {
+    pub fn from_bytes(mut data: Vec<u8>) -> io::Result<Self> {
+        let table = data
+            .len()
+            .checked_sub(4)
+            .map(|end| u32::from_le_bytes(data[end..].try_into().unwrap()) as usize)
+            .filter(|&table| table <= data.len())
+            .ok_or_else(|| invalid("truncated codebook library"))?;
+
+        let offsets = data[table..]
+            .chunks_exact(4)
+            .map(|offset| u32::from_le_bytes(offset.try_into().unwrap()) as usize)
+            .collect();
+        data.truncate(table);
+
+        Ok(Self { data, offsets })
+    }
+
+    fn get(&self, id: u32) -> io::Result<&[u8]> {
+        let id = id as usize;
+        match (self.offsets.get(id), self.offsets.get(id + 1)) {
+            (Some(&start), Some(&end)) if start <= end && end <= self.data.len() => {
+                Ok(&self.data[start..end])
+            }
+            _ => Err(invalid(format!("no codebook {id} in the codebook library"))),
+        }
+    }
+
+    /// Expands the packed codebook `id` into a full Vorbis codebook.
+    fn rebuild(&self, id: u32, out: &mut BitWriter) -> io::Result<()> {
+        let codebook = self.get(id)?;
+        let mut bits = BitReader::new(codebook);
+
+        let dimensions = bits.read(4)?;
+        let entries = bits.read(14)?;
+        out.write(0x564342, 24);
+        out.write(dimensions, 16);
+        out.write(entries, 24);
+
+        let ordered = bits.read(1)?;
+        out.write(ordered, 1);
+        if ordered != 0 {
+            let initial_length = bits.read(5)?;
+            out.write(initial_length, 5);
+
+            let mut entry = 0;
+            while entry < entries {
+                let width = ilog(entries - entry);
+                let count = bits.read(width)?;
+                out.write(count, width);
+                entry += count;
+            }
+            if entry > entries {
+                return Err(invalid(format!("codebook {id} has too many entries")));
+            }
+        } else {
+            let length_bits = bits.read(3)?;
+            let sparse = bits.read(1)?;
+            if length_bits == 0 || length_bits > 5 {
+                return Err(invalid(format!("codebook {id} has a bad length width")));
+            }
+            out.write(sparse, 1);
+
+            for _ in 0..entries {
+                let present = match sparse {
+                    0 => 1,
+                    _ => bits.read(1)?,
+                };
+                if sparse != 0 {
+                    out.write(present, 1);
+                }
+                if present != 0 {
+                    out.write(bits.read(length_bits)?, 5);
+                }
+            }
+        }
+
+        let lookup_type = bits.read(1)?;
+        out.write(lookup_type, 4);
+        if lookup_type == 1 {
+            let min = bits.read(32)?;
+            let max = bits.read(32)?;
+            let value_length = bits.read(4)?;
+            let sequence = bits.read(1)?;
+            out.write(min, 32);
+            out.write(max, 32);
+            out.write(value_length, 4);
+            out.write(sequence, 1);
+
+            for _ in 0..quantvals(entries, dimensions) {
+                out.write(bits.read(value_length + 1)?, value_length + 1);
+            }
+        }
+
+        // the packed codebook ends with at least one padding bit
+        if bits.bits_read() / 8 + 1 != codebook.len() {
+            return Err(invalid(format!("codebook {id} size mismatch")));
+        }
+        Ok(())
+    }
+}
+
+/// Converts a Wwise Vorbis `.wem` into an Ogg Vorbis stream.
+pub fn to_ogg(wem: &Wem, codebooks: &Codebooks) -> io::Result<Vec<u8>> {
+    let vorb = wem
+        .vorb
+        .filter(|vorb| vorb.len() >= 0x2A)
+        .ok_or_else(|| invalid("unsupported Wwise Vorbis header"))?;
+    let u32_at = |offset: usize| u32::from_le_bytes(vorb[offset..offset + 4].try_into().unwrap());
+    let sample_count = u32_at(0x00) as u64;
+    // these values are seen in files whose audio packets keep their first byte intact
+    let mod_packets = !matches!(u32_at(0x04), 0x4A | 0x4B | 0x69 | 0x70);
+    let setup_offset = u32_at(0x10) as usize;
+    let audio_offset = u32_at(0x14) as usize;
+    let blocksize_pows = [vorb[0x28], vorb[0x29]];
+
+    let mut ogg = OggWriter::default();
+
+    let mut packet = header(1);
+    packet.write(0, 32);
+    packet.write(wem.channels as u32, 8);
+    packet.write(wem.sample_rate, 32);
+    packet.write(0, 32);
+    packet.write(wem.avg_bytes_per_second * 8, 32);
+    packet.write(0, 32);
+    packet.write(blocksize_pows[0] as u32, 4);
+    packet.write(blocksize_pows[1] as u32, 4);
+    packet.write(1, 1);
+    ogg.packet(&packet.finish(), 0, false);
+
+    let mut packet = header(3);
+    packet.write(VENDOR.len() as u32, 32);
+    packet.bytes(VENDOR.as_bytes());
+    packet.write(0, 32);
+    packet.write(1, 1);
+    ogg.packet(&packet.finish(), 0, false);
+
+    let setup = packet_at(wem.data, setup_offset)?;
+    let mut packet = header(5);
+    let modes = setup_header(setup, wem.channels as u32, codebooks, &mut packet)?;
+    ogg.packet(&packet.finish(), 0, false);
+
+    let mode_bits = ilog(modes.len() as u32 - 1);
+    let mode_of = |byte: u8| {
+        modes
+            .get((byte & ((1 << mode_bits) - 1) as u8) as usize)
+            .copied()
+    };
+    let blocksizes = [1u64 << blocksize_pows[0], 1u64 << blocksize_pows[1]];
+
+    let mut offset = audio_offset;
+    let mut granule = 0;
+    let mut previous: Option<bool> = None;
+    while offset < wem.data.len() {
+        let payload = packet_at(wem.data, offset)?;
+        offset += 2 + payload.len();
+        let Some((&first, rest)) = payload.split_first() else {
+            return Err(invalid("empty audio packet"));
+        };
+
+        let mut packet = BitWriter::default();
+        let long = if mod_packets {
+            // restore the packet type and window flags Wwise dropped from the first byte
+            let long = mode_of(first).ok_or_else(|| invalid("audio packet with unknown mode"))?;
+            packet.write(0, 1);
+            packet.write(first as u32, mode_bits);
+            if long {
+                let next = packet_at(wem.data, offset)
+                    .ok()
+                    .and_then(|next| next.first().copied())
+                    .and_then(mode_of)
+                    .unwrap_or(false);
+                packet.write(previous.unwrap_or(false) as u32, 1);
+                packet.write(next as u32, 1);
+            }
+            packet.write(first as u32 >> mode_bits, 8 - mode_bits);
+            long
+        } else {
+            packet.write(first as u32, 8);
+            mode_of(first >> 1).ok_or_else(|| invalid("audio packet with unknown mode"))?
+        };
+        packet.bytes(rest);
+
+        // every packet but the first completes the overlapping halves of two windows
+        let blocksize = blocksizes[long as usize];
+        if let Some(previous) = previous {
+            granule += blocksizes[previous as usize] / 4 + blocksize / 4;
+        }
+        previous = Some(long);
+
+        let last = offset >= wem.data.len();
+        let granule = match last && sample_count > 0 {
+            true => granule.min(sample_count),
+            false => granule,
+        };
+        ogg.packet(&packet.finish(), granule, last);
+    }
+
+    Ok(ogg.finish())
+}
+
+/// A Vorbis header packet of `kind`, started with its type and `vorbis` signature.
+fn header(kind: u32) -> BitWriter {
+    let mut packet = BitWriter::default();
+    packet.write(kind, 8);
+    packet.bytes(b"vorbis");
+    packet
+}
+
+/// The payload of the Wwise packet at `offset`, prefixed by its 16 bit size.
+fn packet_at(data: &[u8], offset: usize) -> io::Result<&[u8]> {
+    let size = data
+        .get(offset..offset + 2)
+        .map(|size| u16::from_le_bytes(size.try_into().unwrap()) as usize)
+        .ok_or_else(|| invalid("truncated packet header"))?;
+    data.get(offset + 2..offset + 2 + size)
+        .ok_or_else(|| invalid("truncated packet"))
+}
+
+/// Rewrites the stripped Wwise setup packet as a Vorbis setup header, returning the block flag of
+/// every mode.
+fn setup_header(
+    setup: &[u8],
+    channels: u32,
+    codebooks: &Codebooks,
+    out: &mut BitWriter,
+) -> io::Result<Vec<bool>> {
+    let mut bits = BitReader::new(setup);
+    let copy = |bits: &mut BitReader, width: u32, out: &mut BitWriter| -> io::Result<u32> {
+        let value = bits.read(width)?;
+        out.write(value, width);
+        Ok(value)
+    };
+    let check = |value: u32, count: u32, what: &str| match value < count {
+        true => Ok(()),
+        false => Err(invalid(format!("setup refers to missing {what} {value}"))),
+    };
+
+    let codebook_count = copy(&mut bits, 8, out)? + 1;
+    for _ in 0..codebook_count {
+        codebooks.rebuild(bits.read(10)?, out)?;
+    }
+
+    // a single placeholder time domain transform
+    out.write(0, 6);
+    out.write(0, 16);
+
+    let floor_count = copy(&mut bits, 6, out)? + 1;
+    for _ in 0..floor_count {
+        out.write(1, 16);
+        let partitions = copy(&mut bits, 5, out)?;
+        let classes = (0..partitions)
+            .map(|_| copy(&mut bits, 4, out))
+            .collect::<io::Result<Vec<_>>>()?;
+
+        // a class is read even without partitions
+        let class_count = classes.iter().copied().max().unwrap_or(0) + 1;
+        let mut dimensions = Vec::with_capacity(class_count as usize);
+        for _ in 0..class_count {
+            dimensions.push(copy(&mut bits, 3, out)? + 1);
+            let subclasses = copy(&mut bits, 2, out)?;
+            if subclasses != 0 {
+                check(copy(&mut bits, 8, out)?, codebook_count, "codebook")?;
+            }
+            for _ in 0..1 << subclasses {
+                let book = copy(&mut bits, 8, out)?;
+                if book > 0 {
+                    check(book - 1, codebook_count, "codebook")?;
+                }
+            }
+        }
+
+        copy(&mut bits, 2, out)?;
+        let range_bits = copy(&mut bits, 4, out)?;
+        for class in classes {
+            for _ in 0..dimensions[class as usize] {
+                copy(&mut bits, range_bits, out)?;
+            }
+        }
+    }
+
+    let residue_count = copy(&mut bits, 6, out)? + 1;
+    for _ in 0..residue_count {
+        let residue_type = bits.read(2)?;
+        if residue_type > 2 {
+            return Err(invalid(format!("unknown residue type {residue_type}")));
+        }
+        out.write(residue_type, 16);
+        // begin, end and partition size
+        for _ in 0..3 {
+            copy(&mut bits, 24, out)?;
+        }
+        let classifications = copy(&mut bits, 6, out)? + 1;
+        check(copy(&mut bits, 8, out)?, codebook_count, "codebook")?;
+
+        let mut cascades = Vec::with_capacity(classifications as usize);
+        for _ in 0..classifications {
+            let low = copy(&mut bits, 3, out)?;
+            let high = match copy(&mut bits, 1, out)? {
+                0 => 0,
+                _ => copy(&mut bits, 5, out)?,
+            };
+            cascades.push(high * 8 + low);
+        }
+        for cascade in cascades {
+            for bit in 0..8 {
+                if cascade & (1 << bit) != 0 {
+                    check(copy(&mut bits, 8, out)?, codebook_count, "codebook")?;
+                }
+            }
+        }
+    }
+
+    let mapping_count = copy(&mut bits, 6, out)? + 1;
+    for _ in 0..mapping_count {
+        out.write(0, 16);
+        let submaps = match copy(&mut bits, 1, out)? {
+            0 => 1,
+            _ => copy(&mut bits, 4, out)? + 1,
+        };
+        if copy(&mut bits, 1, out)? != 0 {
+            let coupling_steps = copy(&mut bits, 8, out)? + 1;
+            let width = ilog(channels - 1);
+            for _ in 0..coupling_steps {
+                let magnitude = copy(&mut bits, width, out)?;
+                let angle = copy(&mut bits, width, out)?;
+                if magnitude == angle || magnitude >= channels || angle >= channels {
+                    return Err(invalid("bad channel coupling"));
+                }
+            }
+        }
+        if copy(&mut bits, 2, out)? != 0 {
+            return Err(invalid("mapping reserved field is set"));
+        }
+        if submaps > 1 {
+            for _ in 0..channels {
+                check(copy(&mut bits, 4, out)?, submaps, "submap")?;
+            }
+        }
+        for _ in 0..submaps {
+            // unused time configuration
+            copy(&mut bits, 8, out)?;
+            check(copy(&mut bits, 8, out)?, floor_count, "floor")?;
+            check(copy(&mut bits, 8, out)?, residue_count, "residue")?;
+        }
+    }
+
+    let mode_count = copy(&mut bits, 6, out)? + 1;
+    let mut modes = Vec::with_capacity(mode_count as usize);
+    for _ in 0..mode_count {
+        modes.push(copy(&mut bits, 1, out)? != 0);
+        // window and transform type
+        out.write(0, 16);
+        out.write(0, 16);
+        check(copy(&mut bits, 8, out)?, mapping_count, "mapping")?;
+    }
+    out.write(1, 1);
+
+    if bits.bits_read().div_ceil(8) != setup.len() {
+        return Err(invalid("setup packet size mismatch"));
+    }
+    Ok(modes)
+}
+
+/// Number of bits needed to store `value`.
+fn ilog(value: u32) -> u32 {
+    u32::BITS - value.leading_zeros()
+}
+
+/// Number of distinct values in a lookup type 1 codebook of `entries` and `dimensions`.
+fn quantvals(entries: u32, dimensions: u32) -> u32 {
+    if dimensions == 0 {
+        return 0;
+    }
+    let mut vals = (entries as f64).powf(1.0 / dimensions as f64).floor() as u64;
+    let entries = entries as u64;
+    loop {
+        let acc = vals.saturating_pow(dimensions);
+        let acc1 = (vals + 1).saturating_pow(dimensions);
+        if acc <= entries && acc1 > entries {
+            return vals as u32;
+        }
+        if acc > entries {
+            vals -= 1;
+        } else {
+            vals += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn quantvals_is_the_largest_exact_root() {
+        assert_eq!(quantvals(81, 4), 3);
+        assert_eq!(quantvals(80, 4), 2);
+        assert_eq!(quantvals(1, 2), 1);
+        assert_eq!(quantvals(625, 4), 5);
+        assert_eq!(ilog(0), 0);
+        assert_eq!(ilog(7), 3);
+        assert_eq!(ilog(8), 4);
+    }
+}
@@ -0,0 +1,21 @@
+use clap::Parser;
+
+use crate::common::{filter::Filter, input::Input};
+
+#[derive(Debug, Parser)]
+pub struct Bench {
+    #[command(flatten)]
+    pub input: Input,
+    #[command(flatten)]
+    pub filter: Filter,
+    #[arg(short = 'n', long, default_value_t = 50)]
+    /// Entries decompressed of every compression method and file type
+    pub sample: usize,
+    #[arg(short, long, value_delimiter = ',')]
+    /// Comma separated thread counts to run the sample with, e.g. `4,8,16`, the number of CPUs
+    /// when not given. Compare them to pick `extract --jobs`
+    pub jobs: Vec<usize>,
+    #[arg(long)]
+    /// Print the results as json
+    pub json: bool,
+}
@@ -0,0 +1,16 @@
+use clap::Parser;
+use std::path::PathBuf;
+
+use crate::common::input::Input;
+
+#[derive(Debug, Parser)]
+pub struct Cat {
+    /// Virtual path of the entry, e.g. `sharedassets/springboardentitites/datatables/javelindata_affixstats.datasheet`
+    pub entry: PathBuf,
+    #[command(flatten)]
+    pub input: Input,
+    #[arg(long)]
+    /// Format the entry is converted to, e.g. `pretty`, `yaml` or `png`, instead of the format
+    /// configured for its kind
+    pub format: Option<String>,
+}
@@ -0,0 +1,15 @@
+use clap::Parser;
+
+use crate::common::input::Input;
+
+#[derive(Debug, Parser)]
+pub struct Catalog {
+    #[command(flatten)]
+    pub input: Input,
+    /// Asset ids (`{GUID}` or `{GUID}:subId`) to resolve to paths, or paths to resolve to ids
+    #[arg(required = true)]
+    pub queries: Vec<String>,
+    #[arg(long)]
+    /// Print the matches as json lines
+    pub json: bool,
+}
@@ -0,0 +1,20 @@
+use clap::Parser;
+use std::path::PathBuf;
+
+use crate::common::input::Input;
+
+#[derive(Debug, Parser)]
+pub struct Checksum {
+    #[command(flatten)]
+    pub input: Input,
+    #[arg(short, long, default_value = "fingerprint.json")]
+    /// File the size and CRC32 of every pak are written to
+    pub output: PathBuf,
+    #[arg(long)]
+    /// Also record the CRC32 and size of every entry from the central directories, so `--since`
+    /// can list changed entries too
+    pub entries: bool,
+    #[arg(long)]
+    /// Fingerprint of an earlier run, prints the paks changed since
+    pub since: Option<PathBuf>,
+}
@@ -0,0 +1,45 @@
+use clap::{Parser, Subcommand};
+use std::path::PathBuf;
+
+use crate::common::{filter::Filter, input::Input};
+
+#[derive(Debug, Parser)]
+pub struct Codegen {
+    #[command(subcommand)]
+    pub commands: CodegenCommands,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum CodegenCommands {
+    /// Serde structs for the rows of datasheets exported as JSON, one per table type, and a
+    /// `load` function reading an export
+    Rust(CodegenArgs),
+    /// TypeScript `.d.ts` interfaces for the rows of datasheets exported as JSON, one per table
+    /// type, and a union of their localization keys
+    Ts(CodegenArgs),
+}
+
+impl CodegenCommands {
+    pub fn args(&self) -> &CodegenArgs {
+        match self {
+            CodegenCommands::Rust(args) | CodegenCommands::Ts(args) => args,
+        }
+    }
+
+    pub fn args_mut(&mut self) -> &mut CodegenArgs {
+        match self {
+            CodegenCommands::Rust(args) | CodegenCommands::Ts(args) => args,
+        }
+    }
+}
+
+#[derive(Debug, Parser)]
+pub struct CodegenArgs {
+    #[command(flatten)]
+    pub input: Input,
+    #[command(flatten)]
+    pub filter: Filter,
+    #[arg(short, long)]
+    /// File the code is written to, stdout when not given
+    pub output: Option<PathBuf>,
+}
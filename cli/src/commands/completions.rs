@@ -0,0 +1,21 @@
+use clap::{CommandFactory, Parser};
+use clap_complete::Shell;
+use std::io;
+
+use crate::Args;
+
+#[derive(Debug, Parser)]
+pub struct Completions {
+    #[arg(value_enum)]
+    /// Shell the completions are written for
+    pub shell: Shell,
+}
+
+impl Completions {
+    /// Writes the completion script of every command and flag for the shell to `writer`.
+    pub fn write<W: io::Write>(&self, writer: &mut W) {
+        let mut command = Args::command();
+        let bin = command.get_name().to_string();
+        clap_complete::generate(self.shell, &mut command, bin, writer);
+    }
+}
@@ -0,0 +1,29 @@
+use clap::{Parser, ValueEnum};
+use std::path::PathBuf;
+
+use crate::common::{filter::Filter, input::Input};
+
+#[derive(Debug, Parser)]
+pub struct Components {
+    #[command(flatten)]
+    pub input: Input,
+    #[command(flatten)]
+    pub filter: Filter,
+    #[arg(long = "type", required = true)]
+    /// Component type to export, by name like `SpawnerComponent` or by type id for types missing
+    /// from the hash dictionary. Repeat it for several types
+    pub types: Vec<String>,
+    #[arg(long, value_enum, default_value_t)]
+    pub format: TableFormat,
+    #[arg(short, long)]
+    /// File the table is written to, stdout when not given
+    pub output: Option<PathBuf>,
+}
+
+#[derive(ValueEnum, Debug, Clone, Default, PartialEq, Eq)]
+pub enum TableFormat {
+    #[default]
+    CSV,
+    /// An array of row objects
+    JSON,
+}
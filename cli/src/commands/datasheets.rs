@@ -0,0 +1,61 @@
+use clap::Parser;
+use std::io;
+
+use crate::{
+    common::{
+        configs,
+        datasheet::{DatasheetConfig, DatasheetFormat},
+        CommonConfig,
+    },
+    interactive,
+    traits::IArgs,
+};
+
+use super::extract::Extract;
+
+#[derive(Debug, Parser)]
+pub struct Datasheets {
+    #[command(flatten)]
+    pub common: CommonConfig,
+    #[command(flatten)]
+    pub datasheet: DatasheetConfig,
+    #[arg(short, long)]
+    /// Number of datasheets converted at once, adjusted to the disk and CPU when not set
+    pub jobs: Option<usize>,
+    #[arg(long)]
+    /// Skip datasheets whose CRC32 and size match the last extraction into the output directory
+    pub incremental: bool,
+    #[arg(long)]
+    /// Keep extracting when a datasheet fails
+    pub continue_on_error: bool,
+}
+
+impl Datasheets {
+    /// The extraction this runs: only `.datasheet` entries, every other format left at its
+    /// default as none of them are read.
+    pub fn extract(&self) -> Extract {
+        let mut extract = Extract::parse_from(["extract"]);
+        extract.common = self.common.clone();
+        extract.common.filter.extensions = vec!["datasheet".to_string()];
+        extract.datasheet = self.datasheet.clone();
+        extract.jobs = self.jobs;
+        extract.incremental = self.incremental;
+        extract.continue_on_error = self.continue_on_error;
+        extract
+    }
+}
+
+impl<'a> IArgs<'a> for Datasheets {
+    type Value = ();
+
+    fn configure(&mut self, _: Self::Value) -> io::Result<()> {
+        let conn = configs();
+        self.common.configure(&conn)?;
+
+        if self.datasheet.datasheet == DatasheetFormat::BYTES && interactive() {
+            self.datasheet.datasheet = DatasheetFormat::prompt()?;
+        }
+        conn.close().unwrap();
+        Ok(())
+    }
+}
@@ -0,0 +1,27 @@
+use clap::{Parser, ValueEnum};
+use std::path::PathBuf;
+
+use crate::common::{filter::Filter, input::Input};
+
+#[derive(Debug, Parser)]
+pub struct Deps {
+    #[command(flatten)]
+    pub input: Input,
+    #[command(flatten)]
+    pub filter: Filter,
+    #[arg(long = "root")]
+    /// Only follow references reachable from these entries, e.g. a single slice
+    pub roots: Vec<PathBuf>,
+    #[arg(long, default_value = "dot")]
+    pub format: GraphFormat,
+    #[arg(short, long)]
+    /// Where the graph is written, defaults to stdout
+    pub output: Option<PathBuf>,
+}
+
+#[derive(ValueEnum, Debug, Clone, Default, PartialEq, Eq)]
+pub enum GraphFormat {
+    #[default]
+    DOT,
+    JSON,
+}
@@ -0,0 +1,21 @@
+use clap::Parser;
+use std::path::PathBuf;
+
+use crate::common::filter::Filter;
+
+#[derive(Debug, Parser)]
+pub struct Diff {
+    /// Old game installation or extraction directory
+    pub old: PathBuf,
+    /// New game installation or extraction directory
+    pub new: PathBuf,
+    #[command(flatten)]
+    pub filter: Filter,
+    #[arg(long)]
+    /// Also compare the rows, cells and columns of changed datasheets, matching rows by their
+    /// first column. Loose datasheets must have been extracted as bytes
+    pub datasheets: bool,
+    #[arg(long)]
+    /// Print the diff as JSON
+    pub json: bool,
+}
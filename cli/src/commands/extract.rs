@@ -1,18 +1,30 @@
 use clap::Parser;
 use rusqlite::params;
-use std::io;
+use std::{io, path::PathBuf};
 
 use crate::{
     common::{
+        audio::AudioConfig,
+        compression::OutputCompression,
+        configs,
         datasheet::{DatasheetConfig, DatasheetFormat, DatasheetOutputMode},
         dds::DDSConfig,
         distribution::{DistributionConfig, DistributionFormat},
+        escape::PathEscape,
+        link::LinkMode,
+        lua::LuaConfig,
+        material::MaterialConfig,
+        mesh::MeshConfig,
         objectstream::{ObjectStreamConfig, ObjectStreamFormat},
+        order::ExtractOrder,
+        parse_bytes,
+        terrain::TerrainConfig,
         vshapec::VShapeConfig,
         CommonConfig,
     },
+    interactive,
     traits::IArgs,
-    BYTES, CSV, MINI, PRETTY, SQL, XML, YAML,
+    BYTES, CSV, ENTITIES, GEOJSON, HTML, MINI, PRETTY, SQLITE, XLSX, XML, YAML,
 };
 
 #[derive(Debug, Parser)]
@@ -29,34 +41,131 @@ pub struct Extract {
     pub vshapec: VShapeConfig,
     #[command(flatten)]
     pub dds: DDSConfig,
+    #[command(flatten)]
+    pub luac: LuaConfig,
+    #[command(flatten)]
+    pub mesh: MeshConfig,
+    #[command(flatten)]
+    pub audio: AudioConfig,
+    #[command(flatten)]
+    pub terrain: TerrainConfig,
+    #[command(flatten)]
+    pub material: MaterialConfig,
+    #[arg(long)]
+    /// Print a json plan of the entries that would be extracted, where to and as what, and exit
+    pub dry_run: bool,
+    #[arg(long)]
+    /// Write entries into a single .zip, .tar, .tar.gz or .tar.zst archive instead of loose files
+    pub output_archive: Option<PathBuf>,
+    #[arg(short, long)]
+    /// Number of entries converted at once, adjusted to the disk and CPU when not set
+    pub jobs: Option<usize>,
+    #[arg(long)]
+    /// Skip entries whose CRC32 and size match the last extraction into the output directory
+    pub incremental: bool,
     #[arg(long)]
-    pub luac: bool,
+    /// Continue a cancelled or crashed extraction into the output directory where it stopped
+    pub resume: bool,
+    #[arg(long)]
+    /// Keep running and re-extract paks changed by a game update
+    pub watch: bool,
+    #[arg(long, default_value_t = 60)]
+    /// Seconds between checks for changed paks in watch mode
+    pub watch_interval: u64,
+    #[arg(long, conflicts_with = "output_archive")]
+    /// After extracting, check that every selected asset in the asset catalog was written and
+    /// list files in the output directory no entry wrote. Fails when either is found
+    pub verify: bool,
+    #[arg(long, value_enum, conflicts_with = "output_archive")]
+    /// Link entries whose bytes are the same as one already written during the run to its output
+    /// instead of writing them again. Only applies to entries written unconverted, e.g. textures
+    pub link: Option<LinkMode>,
+    #[arg(long)]
+    /// Keep extracting when an entry fails. Failed entries are listed at the end and written to
+    /// errors.json in the output directory either way
+    pub continue_on_error: bool,
+    #[arg(long, value_parser = parse_bytes)]
+    /// Most bytes written per second on average, e.g. `50M`, to leave the disk usable for other
+    /// programs
+    pub max_throughput: Option<u64>,
+    #[arg(long, value_parser = parse_bytes)]
+    /// Most bytes of entries held in memory at once, e.g. `2G`. Workers wait for others to
+    /// finish before reading an entry that doesn't fit, one larger than this is read on its own
+    pub max_memory: Option<u64>,
+    #[arg(long, default_value_t = 64)]
+    /// Most paks kept open at once, the least recently used is closed to open another. Lower it
+    /// for low file descriptor limits, raise it when paks are reopened over a slow network share
+    pub max_open_paks: usize,
+    #[arg(long)]
+    /// Run in the background: at most two entries at once, each followed by a pause as long as
+    /// it took to extract
+    pub nice_io: bool,
+    #[arg(long, value_enum, default_value_t)]
+    /// The order entries are extracted in
+    pub order: ExtractOrder,
+    #[arg(long, value_enum, default_value_t)]
+    /// How names Windows can't create are written, like `aux.lua`, `con` or ones with a `:`
+    pub path_escape: PathEscape,
+    #[arg(long)]
+    /// Keep parsed object streams on disk by CRC32, so exporting them again in another format
+    /// skips parsing entries that didn't change
+    pub parse_cache: bool,
+    #[arg(long, requires = "parse_cache")]
+    /// Directory of the parse cache instead of nwtools in the user's cache directory
+    pub parse_cache_dir: Option<PathBuf>,
+    #[arg(long)]
+    /// Only extract entries whose CRC32 or size differ from the ones in this manifest.json, or an
+    /// output directory holding one, and entries it doesn't list
+    pub since: Option<PathBuf>,
+    #[arg(long, requires = "since", conflicts_with = "output_archive")]
+    /// Write the entries changed since `--since` into a patch directory of the output directory
+    /// instead of over the earlier extraction
+    pub patch: bool,
+    #[arg(long, conflicts_with = "output_archive")]
+    /// Extract into a directory of the output directory named after the game build, next to the
+    /// builds extracted before. See the `workspace` command
+    pub workspace: bool,
+    #[arg(long, requires = "workspace")]
+    /// Name of the workspace version instead of the game build
+    pub label: Option<String>,
+    #[arg(long)]
+    /// Also write locales.sqlite, a full-text index of the strings of the --inline-locale locales
+    /// (`en` without any) and of the datasheets referencing each key, for `locale search`
+    pub locale_index: bool,
+    #[arg(long, value_name = "ADDR")]
+    /// Serve Prometheus metrics of the extraction, like throughput, bytes written, active tasks
+    /// and errors, on http://ADDR/metrics while it runs, e.g. `127.0.0.1:9100`
+    pub metrics: Option<String>,
+    #[arg(long)]
+    /// Print the entries, bytes read and written, compression ratio, decode throughput and
+    /// errors of every pak at the end, and write them to pak-stats.json in the output directory
+    pub pak_stats: bool,
+    #[arg(long, value_name = "FILE")]
+    /// Rhai script whose `transform(entry)` is called with every output before it's written, to
+    /// rename, rewrite or skip it. `entry` holds the `entry`, `kind`, `format`, output `path` and
+    /// `content`; return it changed, or `()` to skip the output
+    pub script: Option<PathBuf>,
+    #[arg(long, value_enum)]
+    /// Compress text outputs like JSON, XML, CSV and SQL, adding `.gz` or `.zst` to their names
+    pub compress_output: Option<OutputCompression>,
 }
 
 impl<'a> IArgs<'a> for Extract {
     type Value = ();
 
     fn configure(&mut self, _: Self::Value) -> io::Result<()> {
-        let config_dir = dirs::config_local_dir().unwrap().join(".nwtools");
-        let conn = rusqlite::Connection::open(config_dir).unwrap();
-
-        conn.pragma_update_and_check(None, "journal_mode", "WAL", |_res| Ok(()))
-            .unwrap();
-
-        conn.pragma_update(None, "synchronous", 1).unwrap();
-
-        conn
-        .execute(
-            "create table if not exists configs (name text primary key, value text) strict, without rowid",
-            params![],
-        )
-        .unwrap();
-
+        let conn = configs();
         self.common.configure(&conn)?;
 
         if self.common.filter.filter.is_none()
+            && self.common.filter.exclude.is_empty()
+            && self.common.filter.filter_file.is_none()
+            && self.common.filter.filter_regex.is_none()
+            && self.common.filter.types.is_empty()
+            && self.common.filter.files.is_none()
             && self.objectstream.objectstream == ObjectStreamFormat::BYTES
             && self.datasheet.datasheet == DatasheetFormat::BYTES
+            && interactive()
         {
             let is_default = cliclack::confirm("Use defaults?")
                 .initial_value(true)
@@ -72,8 +181,8 @@ impl<'a> IArgs<'a> for Extract {
                         "datasheet",
                         "Datasheet Format",
                         &format!(
-                            "{} = default | {} | {} | {} | {}",
-                            BYTES, MINI, PRETTY, YAML, CSV
+                            "{} = default | {} | {} | {} | {} | {} | {} | {} | {}",
+                            BYTES, MINI, PRETTY, XML, YAML, CSV, SQLITE, XLSX, HTML
                         ),
                     ),
                     (
@@ -89,7 +198,10 @@ impl<'a> IArgs<'a> for Extract {
                     (
                         "objectstream",
                         "ObjectStream",
-                        &format!("{} = default | {} | {} | {}", BYTES, XML, MINI, PRETTY),
+                        &format!(
+                            "{} = default | {} | {} | {} | {}",
+                            BYTES, XML, MINI, PRETTY, YAML
+                        ),
                     ),
                 ])
                 .interact()?;
@@ -116,6 +228,8 @@ impl<'a> IArgs<'a> for Extract {
                             (XML, "XML", ""),
                             (PRETTY, "JSON Pretty", ""),
                             (MINI, "JSON Minified", ""),
+                            (YAML, "YAML", ""),
+                            (ENTITIES, "Slice Entities", "json"),
                         ])
                         .initial_value("bytes")
                         .interact()?;
@@ -124,30 +238,14 @@ impl<'a> IArgs<'a> for Extract {
                         XML => ObjectStreamFormat::XML,
                         MINI => ObjectStreamFormat::MINI,
                         PRETTY => ObjectStreamFormat::PRETTY,
+                        YAML => ObjectStreamFormat::YAML,
+                        ENTITIES => ObjectStreamFormat::ENTITIES,
                         _ => ObjectStreamFormat::BYTES,
                     };
                 }
 
                 if options.contains(&"datasheet") {
-                    let datasheet = cliclack::Select::new("Datasheet Format")
-                        .items(&[
-                            (BYTES, "Binary", "default"),
-                            (MINI, "JSON Minified", ""),
-                            (PRETTY, "JSON Pretty", ""),
-                            (CSV, "CSV", ""),
-                            (YAML, "YAML", "vomit"),
-                        ])
-                        .initial_value("bytes")
-                        .interact()?;
-
-                    self.datasheet.datasheet = match datasheet {
-                        MINI => DatasheetFormat::MINI,
-                        PRETTY => DatasheetFormat::PRETTY,
-                        CSV => DatasheetFormat::CSV,
-                        YAML => DatasheetFormat::YAML,
-                        SQL => DatasheetFormat::SQL,
-                        _ => DatasheetFormat::BYTES,
-                    };
+                    self.datasheet.datasheet = DatasheetFormat::prompt()?;
                 }
 
                 if options.contains(&"datasheet-output-mode") {
@@ -173,6 +271,8 @@ impl<'a> IArgs<'a> for Extract {
                             (BYTES, "Binary", "default"),
                             (MINI, "JSON Minified", ""),
                             (PRETTY, "JSON Pretty", ""),
+                            (GEOJSON, "GeoJSON", "world coordinates"),
+                            (CSV, "CSV", "world coordinates"),
                         ])
                         .initial_value("bytes")
                         .interact()?;
@@ -180,6 +280,8 @@ impl<'a> IArgs<'a> for Extract {
                     self.distribution.distribution = match dist {
                         MINI => DistributionFormat::MINI,
                         PRETTY => DistributionFormat::PRETTY,
+                        GEOJSON => DistributionFormat::GEOJSON,
+                        CSV => DistributionFormat::CSV,
                         _ => DistributionFormat::BYTES,
                     };
                 }
@@ -0,0 +1,19 @@
+use clap::Parser;
+
+use crate::common::{filter::Filter, input::Input};
+
+#[derive(Debug, Parser)]
+pub struct Grep {
+    /// Regex searched for in the converted contents of every entry
+    pub pattern: String,
+    #[command(flatten)]
+    pub input: Input,
+    #[command(flatten)]
+    pub filter: Filter,
+    #[arg(long)]
+    /// Match the pattern case insensitively
+    pub ignore_case: bool,
+    #[arg(short = 'l', long)]
+    /// Only print the paths of entries that matched
+    pub files_with_matches: bool,
+}
@@ -0,0 +1,20 @@
+use clap::{Parser, Subcommand};
+use std::path::PathBuf;
+
+#[derive(Debug, Parser)]
+pub struct Hashes {
+    #[command(subcommand)]
+    pub commands: HashesCommands,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum HashesCommands {
+    /// Scan a Lumberyard/O3DE source tree for type and field names and write their CRCs
+    Build {
+        /// Root of the Lumberyard/O3DE source tree, e.g. `lumberyard/dev`
+        source: PathBuf,
+        #[arg(short, long)]
+        /// Where the dictionary is written, defaults to the one loaded on startup
+        output: Option<PathBuf>,
+    },
+}
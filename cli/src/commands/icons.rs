@@ -0,0 +1,19 @@
+use clap::Parser;
+use std::path::PathBuf;
+
+use crate::common::{filter::Filter, input::Input};
+
+#[derive(Debug, Parser)]
+pub struct Icons {
+    #[command(flatten)]
+    pub input: Input,
+    #[command(flatten)]
+    pub filter: Filter,
+    #[arg(short, long, default_value = "icons")]
+    /// Directory the icons are written to, one `<item id>.<format>` each, with an `icons.json`
+    /// mapping item ids to their files
+    pub output: PathBuf,
+    #[arg(long, default_value = "png")]
+    /// Format the icons are converted to, e.g. `png` or `webp`, or `bytes` to keep the `.dds`
+    pub format: String,
+}
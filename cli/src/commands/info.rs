@@ -0,0 +1,14 @@
+use clap::Parser;
+
+use crate::common::{filter::Filter, input::Input};
+
+#[derive(Debug, Parser)]
+pub struct Info {
+    #[command(flatten)]
+    pub input: Input,
+    #[command(flatten)]
+    pub filter: Filter,
+    #[arg(long)]
+    /// Decompress every entry to report the distribution of detected file types
+    pub types: bool,
+}
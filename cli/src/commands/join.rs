@@ -0,0 +1,48 @@
+use clap::{Parser, Subcommand};
+use std::path::PathBuf;
+
+use crate::common::{datasheet::Localization, filter::Filter, input::Input};
+
+#[derive(Debug, Parser)]
+pub struct Join {
+    #[command(subcommand)]
+    pub commands: JoinCommands,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum JoinCommands {
+    /// Crafting recipes with their output and ingredients resolved to named items, and the
+    /// items of each ingredient category
+    Recipes(JoinArgs),
+    /// Loot tables as trees, with their conditions and probabilities, nested tables and loot
+    /// buckets inlined and items resolved to their names
+    Loot(JoinArgs),
+}
+
+impl JoinCommands {
+    pub fn args(&self) -> &JoinArgs {
+        match self {
+            JoinCommands::Recipes(args) | JoinCommands::Loot(args) => args,
+        }
+    }
+
+    pub fn args_mut(&mut self) -> &mut JoinArgs {
+        match self {
+            JoinCommands::Recipes(args) | JoinCommands::Loot(args) => args,
+        }
+    }
+}
+
+#[derive(Debug, Parser)]
+pub struct JoinArgs {
+    #[command(flatten)]
+    pub input: Input,
+    #[command(flatten)]
+    pub filter: Filter,
+    #[arg(short, long)]
+    /// File the JSON is written to, stdout when not given
+    pub output: Option<PathBuf>,
+    #[arg(long, value_enum, default_value = "en")]
+    /// Locale names are written in
+    pub locale: Localization,
+}
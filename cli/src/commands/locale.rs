@@ -0,0 +1,84 @@
+use clap::{Parser, Subcommand, ValueEnum};
+use std::path::PathBuf;
+
+use crate::common::{datasheet::Localization, input::Input};
+
+#[derive(Debug, Parser)]
+pub struct Locale {
+    #[command(subcommand)]
+    pub commands: LocaleCommands,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum LocaleCommands {
+    /// Dump the localization string tables of each locale
+    Export {
+        #[command(flatten)]
+        input: Input,
+        #[arg(short, long)]
+        /// Directory the string tables are written to, one file per locale
+        output: PathBuf,
+        #[arg(long, value_enum, value_delimiter = ',', default_value = "en")]
+        /// Locales to export, several separated by commas or `all`
+        locale: Vec<Localization>,
+        #[arg(long, value_enum, default_value_t)]
+        format: LocaleFormat,
+    },
+    /// Print the strings of localization keys, side by side for each locale
+    Get {
+        #[command(flatten)]
+        input: Input,
+        #[arg(required = true)]
+        /// Keys to look up, with or without their leading `@`, in any case
+        keys: Vec<String>,
+        #[arg(long, value_enum, value_delimiter = ',', default_value = "en")]
+        /// Locales to print, several separated by commas or `all`
+        locale: Vec<Localization>,
+        #[arg(long)]
+        /// Print the strings as JSON, by key then locale
+        json: bool,
+    },
+    /// List the localization keys whose string contains some text in any of the locales
+    Find {
+        #[command(flatten)]
+        input: Input,
+        /// Text to find, ignoring case
+        text: String,
+        #[arg(long, value_enum, value_delimiter = ',', default_value = "en")]
+        /// Locales to search and print, several separated by commas or `all`
+        locale: Vec<Localization>,
+        #[arg(long)]
+        /// Also match the text against the keys
+        keys: bool,
+        #[arg(long)]
+        /// Print the strings as JSON, by key then locale
+        json: bool,
+    },
+    /// Find the localization strings containing some text, with the datasheets referencing them,
+    /// in the index written by `extract --locale-index`
+    Search {
+        /// Words to find, in any order. The last one matches as a prefix
+        text: String,
+        #[arg(short, long, default_value = "locales.sqlite")]
+        /// The index, or the extraction directory holding it
+        index: PathBuf,
+        #[arg(long)]
+        /// Only strings of this locale, e.g. `en-us`
+        locale: Option<String>,
+        #[arg(short = 'n', long, default_value_t = 20)]
+        /// Most strings listed
+        limit: usize,
+        #[arg(long)]
+        /// Print the matches as JSON
+        json: bool,
+    },
+}
+
+#[derive(ValueEnum, Debug, Clone, Default, PartialEq, Eq)]
+pub enum LocaleFormat {
+    #[default]
+    JSON,
+    CSV,
+    /// gettext PO keyed by the localization keys
+    PO,
+}
@@ -0,0 +1,8 @@
+use clap::Parser;
+
+#[derive(Debug, Parser)]
+pub struct Locate {
+    #[arg(long)]
+    /// Print every install found, one per line, instead of the first
+    pub all: bool,
+}
@@ -0,0 +1,19 @@
+use clap::Parser;
+use regex::Regex;
+use std::path::PathBuf;
+
+use crate::common::{filter::Filter, input::Input};
+
+#[derive(Debug, Parser)]
+pub struct Markers {
+    #[command(flatten)]
+    pub input: Input,
+    #[command(flatten)]
+    pub filter: Filter,
+    #[arg(long, value_parser = Regex::new, default_value = "(?i)poi|spawn|territory|marker|area")]
+    /// Only export entities whose name or a component type name matches this regex
+    pub matches: Regex,
+    #[arg(short, long)]
+    /// File the GeoJSON is written to, stdout when not given
+    pub output: Option<PathBuf>,
+}
@@ -1,12 +1,111 @@
+use bench::Bench;
+use cat::Cat;
+use catalog::Catalog;
+use checksum::Checksum;
 use clap::Subcommand;
+use codegen::Codegen;
+use completions::Completions;
+use components::Components;
+use datasheets::Datasheets;
+use deps::Deps;
+use diff::Diff;
 use extract::Extract;
+use grep::Grep;
+use hashes::Hashes;
+use icons::Icons;
+use info::Info;
+use join::Join;
+use locale::Locale;
+use locate::Locate;
+use markers::Markers;
+use pack::Pack;
+use positions::Positions;
+use serve::Serve;
 use test::Test;
+use tree::Tree;
+use validate::Validate;
+use workspace::Workspace;
 
+pub mod bench;
+pub mod cat;
+pub mod catalog;
+pub mod checksum;
+pub mod codegen;
+pub mod completions;
+pub mod components;
+pub mod datasheets;
+pub mod deps;
+pub mod diff;
 pub mod extract;
+pub mod grep;
+pub mod hashes;
+pub mod icons;
+pub mod info;
+pub mod join;
+pub mod locale;
+pub mod locate;
+pub mod markers;
+pub mod pack;
+pub mod positions;
+pub mod serve;
 pub mod test;
+pub mod tree;
+pub mod validate;
+pub mod workspace;
 
 #[derive(Subcommand, Debug)]
 pub enum Commands {
     Extract(Extract),
+    /// Extract only the datasheets, straight to the chosen format or database
+    Datasheets(Datasheets),
     Test(Test),
+    /// Search the converted contents of entries with a regex
+    Grep(Grep),
+    /// Print the entries of every pak as one directory tree, with counts and sizes
+    Tree(Tree),
+    /// Summarize entry counts, sizes and compression per pak
+    Info(Info),
+    /// Decompress every entry and verify its CRC32 without writing anything
+    Validate(Validate),
+    /// Measure decompression throughput on a sample of entries without writing anything
+    Bench(Bench),
+    /// Compare the entries of two game installations or extractions
+    Diff(Diff),
+    /// List, prune and compare the game builds extracted with `extract --workspace`
+    Workspace(Workspace),
+    /// Build a pak from a directory of loose files
+    Pack(Pack),
+    /// Write a single entry, converted, to stdout
+    Cat(Cat),
+    /// Serve entries over HTTP, converting them on demand
+    Serve(Serve),
+    /// Work with the localization string tables
+    Locale(Locale),
+    /// Manage the CRC and type name dictionary used to resolve object streams
+    Hashes(Hashes),
+    /// Resolve asset ids to paths and paths to asset ids with the asset catalog
+    Catalog(Catalog),
+    /// Generate code for the rows of datasheets exported as JSON
+    Codegen(Codegen),
+    /// Join related datasheets into denormalized JSON, like recipes with their items
+    Join(Join),
+    /// Convert only the icons item definitions reference, named after their item ids
+    Icons(Icons),
+    /// Export the named points of interest, spawn areas and territory boundaries placed by
+    /// slices as GeoJSON in world coordinates
+    Markers(Markers),
+    /// Export the fields of every component of the given types placed by slices as one table,
+    /// a row per component with its entity
+    Components(Components),
+    /// Export the world position and rotation of every entity placed by slices as one table,
+    /// with the assets it references
+    Positions(Positions),
+    /// Export the graph of assets referenced by object streams as DOT or JSON
+    Deps(Deps),
+    /// Fingerprint every pak by its size and CRC32, to tell which paks an update changed
+    Checksum(Checksum),
+    /// Print the detected New World install directory
+    Locate(Locate),
+    /// Print shell completions for bash, zsh, fish, powershell or elvish
+    Completions(Completions),
 }
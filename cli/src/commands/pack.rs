@@ -0,0 +1,24 @@
+use clap::{Parser, ValueEnum};
+use std::path::PathBuf;
+
+#[derive(Debug, Parser)]
+pub struct Pack {
+    /// Directory of loose files. Entry names are relative to this directory.
+    pub input: PathBuf,
+    /// Pak file to write
+    pub output: PathBuf,
+    #[arg(long, value_enum, default_value_t)]
+    /// Compression method used for every entry
+    pub method: PackMethod,
+}
+
+#[derive(ValueEnum, Debug, Clone, Default, PartialEq, Eq)]
+pub enum PackMethod {
+    /// Deflate everything except already compressed or tiny entries
+    #[default]
+    AUTO,
+    STORED,
+    DEFLATE,
+    /// Oodle Kraken as the game's own paks, except already compressed or tiny entries
+    OODLE,
+}
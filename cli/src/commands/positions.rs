@@ -0,0 +1,22 @@
+use clap::Parser;
+use regex::Regex;
+use std::path::PathBuf;
+
+use crate::commands::components::TableFormat;
+use crate::common::{filter::Filter, input::Input};
+
+#[derive(Debug, Parser)]
+pub struct Positions {
+    #[command(flatten)]
+    pub input: Input,
+    #[command(flatten)]
+    pub filter: Filter,
+    #[arg(long, value_parser = Regex::new)]
+    /// Only export entities whose name or a component type name matches this regex
+    pub matches: Option<Regex>,
+    #[arg(long, value_enum, default_value_t)]
+    pub format: TableFormat,
+    #[arg(short, long)]
+    /// File the table is written to, stdout when not given
+    pub output: Option<PathBuf>,
+}
@@ -0,0 +1,22 @@
+use clap::Parser;
+use std::path::PathBuf;
+
+use crate::common::input::Input;
+
+#[derive(Debug, Parser)]
+pub struct Serve {
+    #[command(flatten)]
+    pub input: Input,
+    #[arg(long, default_value = "127.0.0.1")]
+    /// Address the server listens on
+    pub host: String,
+    #[arg(short, long, default_value_t = 8080)]
+    /// Port the server listens on
+    pub port: u16,
+    #[arg(long)]
+    /// Also answer GraphQL queries over every datasheet at /graphql, parsing them on startup
+    pub graphql: bool,
+    #[arg(long, requires = "graphql")]
+    /// Resolve GraphQL queries from a datasheets.sqlite export instead of the paks
+    pub graphql_sqlite: Option<PathBuf>,
+}
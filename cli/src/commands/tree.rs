@@ -0,0 +1,14 @@
+use clap::Parser;
+
+use crate::common::{filter::Filter, input::Input};
+
+#[derive(Debug, Parser)]
+pub struct Tree {
+    #[command(flatten)]
+    pub input: Input,
+    #[command(flatten)]
+    pub filter: Filter,
+    #[arg(short, long)]
+    /// Only show directories and entries this many levels deep, deeper ones are still counted
+    pub depth: Option<usize>,
+}
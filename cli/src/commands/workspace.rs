@@ -0,0 +1,40 @@
+use clap::{Parser, Subcommand};
+use std::path::PathBuf;
+
+#[derive(Debug, Parser)]
+pub struct Workspace {
+    #[command(subcommand)]
+    pub commands: WorkspaceCommands,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum WorkspaceCommands {
+    /// List the versions extracted into a workspace, oldest first
+    List {
+        /// Output directory of the extractions run with `--workspace`
+        dir: PathBuf,
+    },
+    /// Delete all but the newest versions of a workspace
+    Prune {
+        /// Output directory of the extractions run with `--workspace`
+        dir: PathBuf,
+        #[arg(long, default_value_t = 2)]
+        /// Number of versions kept
+        keep: usize,
+        #[arg(long)]
+        /// Only print the versions that would be deleted
+        dry_run: bool,
+    },
+    /// Compare the manifests of two versions of a workspace
+    Diff {
+        /// Output directory of the extractions run with `--workspace`
+        dir: PathBuf,
+        /// Label of the old version
+        old: String,
+        /// Label of the new version
+        new: String,
+        #[arg(long)]
+        /// Print the diff as JSON
+        json: bool,
+    },
+}
@@ -0,0 +1,29 @@
+use clap::{Parser, ValueEnum};
+use std::path::PathBuf;
+
+use crate::traits::IArgs;
+
+#[derive(Debug, Parser)]
+pub struct AudioConfig {
+    /// Convert `.wem` files, and the ones embedded in `.bnk` soundbanks, into playable audio
+    #[arg(long, default_value = "bytes")]
+    pub audio_format: AudioFormat,
+    /// ww2ogg's `packed_codebooks_aoTuV_603.bin`, needed to convert Wwise Vorbis
+    #[arg(long)]
+    pub audio_codebooks: Option<PathBuf>,
+}
+
+impl<'a> IArgs<'a> for AudioConfig {
+    type Value = ();
+    fn configure(&mut self, _: Self::Value) -> std::io::Result<()> {
+        todo!()
+    }
+}
+
+#[derive(ValueEnum, Debug, Clone, Default, PartialEq, Eq)]
+pub enum AudioFormat {
+    #[default]
+    BYTES,
+    OGG,
+    WAV,
+}
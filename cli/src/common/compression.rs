@@ -0,0 +1,10 @@
+use clap::ValueEnum;
+
+/// How converted text outputs are compressed.
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputCompression {
+    /// `.gz`, readable by most tools
+    GZIP,
+    /// `.zst`, faster and smaller
+    ZSTD,
+}
@@ -1,11 +1,12 @@
 use std::fmt::Display;
 
 use clap::{Parser, ValueEnum};
+use datasheet::Predicate;
 use rusqlite::Connection;
 
-use crate::{traits::IArgs, BYTES, CSV, MINI, PRETTY, SQL, XML, YAML};
+use crate::{traits::IArgs, BYTES, CSV, HTML, MINI, PRETTY, SQL, SQLITE, XLSX, XML, YAML};
 
-#[derive(Debug, Parser)]
+#[derive(Debug, Parser, Clone)]
 pub struct DatasheetConfig {
     #[arg(long, value_enum, default_value_t)]
     pub datasheet: DatasheetFormat,
@@ -14,8 +15,51 @@ pub struct DatasheetConfig {
     pub datasheet_filenames: DatasheetOutputMode,
     #[arg(long)]
     pub with_meta: bool,
+    #[arg(long)]
+    /// Write a JSON Schema of each exported datasheet next to it as .schema.json
+    pub with_schema: bool,
+    #[arg(long, value_enum, value_delimiter = ',', alias = "locale")]
+    /// Locales inlined into datasheets, several separated by commas or `all`. With
+    /// `--locale-output table` they are written as side tables instead
+    pub inline_locale: Vec<Localization>,
+    #[arg(long, value_enum, default_value_t)]
+    /// How datasheets are written when inlining more than one locale
+    pub locale_output: LocaleOutput,
+    #[arg(long, conflicts_with = "locale_output")]
+    /// Keep the localization keys of datasheets and add the text of every --inline-locale locale,
+    /// `en` without any, in a `<Column>_<locale>` column right after each column of keys
+    pub keep_locale_keys: bool,
+    #[arg(long, value_delimiter = ',')]
+    /// Only emit these datasheet columns, in order. Sheets with none of them are left whole
+    pub datasheet_columns: Vec<String>,
+    #[arg(long)]
+    /// Only emit datasheet rows matching a predicate like `Tier>=3`, `ItemType=Weapon` or `ItemID~sword`
+    pub datasheet_where: Option<Predicate>,
+    #[arg(long, value_enum, default_value_t)]
+    /// Database the `sql` datasheet format is written for
+    pub sql_dialect: SqlDialect,
+    #[arg(long)]
+    /// Rows per INSERT statement of the `sql` datasheet format, 500 for sqlite and 1000 otherwise
+    pub sql_batch: Option<usize>,
     #[arg(long, value_enum)]
-    pub inline_locale: Option<Localization>,
+    /// Resolve cells referencing rows of other datasheets in JSON and XML output
+    pub datasheet_resolve: Option<DatasheetResolve>,
+}
+
+#[derive(ValueEnum, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SqlDialect {
+    POSTGRES,
+    MYSQL,
+    #[default]
+    SQLITE,
+}
+
+#[derive(ValueEnum, Debug, Clone, PartialEq, Eq)]
+pub enum DatasheetResolve {
+    /// Replace the reference with the referenced row
+    INLINE,
+    /// Add a field naming the referenced datasheet next to the reference
+    ANNOTATE,
 }
 
 #[derive(ValueEnum, Debug, Clone, Default, PartialEq, Eq)]
@@ -29,6 +73,41 @@ pub enum Localization {
     FR,
     PL,
     BR,
+    /// Every locale above
+    ALL,
+}
+
+impl Localization {
+    /// Expands `ALL` into every locale and drops duplicates, keeping the given order.
+    pub fn expand(locales: &[Localization]) -> Vec<Localization> {
+        if locales.contains(&Localization::ALL) {
+            return Localization::value_variants()
+                .iter()
+                .filter(|locale| **locale != Localization::ALL)
+                .cloned()
+                .collect();
+        }
+
+        let mut expanded: Vec<Localization> = Vec::with_capacity(locales.len());
+        for locale in locales {
+            if !expanded.contains(locale) {
+                expanded.push(locale.to_owned());
+            }
+        }
+        expanded
+    }
+}
+
+#[derive(ValueEnum, Debug, Clone, Default, PartialEq, Eq)]
+pub enum LocaleOutput {
+    #[default]
+    /// One datasheet per locale, with the locale added before the extension
+    SEPARATE,
+    /// A `<Column>_<locale>` column per locale next to every localized column
+    COLUMNS,
+    /// Datasheets keep their `@` keys and a table per locale maps the keys, lowercase without the
+    /// `@`, to their text. Written to `locales/` or to datasheets.sqlite as `locale_<locale>`
+    TABLE,
 }
 
 impl Display for Localization {
@@ -42,6 +121,7 @@ impl Display for Localization {
             Localization::FR => "fr-fr",
             Localization::PL => "pl-pl",
             Localization::BR => "pt-br",
+            Localization::ALL => "all",
         };
         write!(f, "{}", value)
     }
@@ -67,6 +147,46 @@ pub enum DatasheetFormat {
     CSV,
     YAML,
     SQL,
+    /// Every datasheet as a table in a single datasheets.sqlite database
+    SQLITE,
+    /// An Excel workbook per datasheet, with a header row and typed cells
+    XLSX,
+    /// A standalone HTML page per datasheet with sortable columns and a row filter
+    HTML,
+}
+
+impl DatasheetFormat {
+    /// Asks which format datasheets are written as.
+    pub(crate) fn prompt() -> std::io::Result<Self> {
+        let datasheet = cliclack::Select::new("Datasheet Format")
+            .items(&[
+                (BYTES, "Binary", "default"),
+                (MINI, "JSON Minified", ""),
+                (PRETTY, "JSON Pretty", ""),
+                (XML, "XML", ""),
+                (CSV, "CSV", ""),
+                (YAML, "YAML", "vomit"),
+                (SQL, "SQL", "statements per datasheet"),
+                (SQLITE, "SQLite", "single database"),
+                (XLSX, "Excel", "workbook per datasheet"),
+                (HTML, "HTML", "sortable table per datasheet"),
+            ])
+            .initial_value("bytes")
+            .interact()?;
+
+        Ok(match datasheet {
+            MINI => DatasheetFormat::MINI,
+            PRETTY => DatasheetFormat::PRETTY,
+            XML => DatasheetFormat::XML,
+            CSV => DatasheetFormat::CSV,
+            YAML => DatasheetFormat::YAML,
+            SQL => DatasheetFormat::SQL,
+            SQLITE => DatasheetFormat::SQLITE,
+            XLSX => DatasheetFormat::XLSX,
+            HTML => DatasheetFormat::HTML,
+            _ => DatasheetFormat::BYTES,
+        })
+    }
 }
 
 impl Display for DatasheetFormat {
@@ -79,6 +199,9 @@ impl Display for DatasheetFormat {
             DatasheetFormat::CSV => CSV,
             DatasheetFormat::YAML => YAML,
             DatasheetFormat::SQL => SQL,
+            DatasheetFormat::SQLITE => SQLITE,
+            DatasheetFormat::XLSX => XLSX,
+            DatasheetFormat::HTML => HTML,
         };
         write!(f, "{}", value)
     }
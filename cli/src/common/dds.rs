@@ -4,8 +4,13 @@ use crate::traits::IArgs;
 
 #[derive(Debug, Parser)]
 pub struct DDSConfig {
-    #[arg(long, default_value = "bytes")]
+    /// Convert `.dds` textures, merging their mip streams and decoding BC1 to BC7 into an image
+    #[arg(long, visible_alias = "texture-format", default_value = "bytes")]
     pub dds: DDSFormat,
+    /// Also cut UI atlases with a `.sprite` layout beside them into one PNG per cell, written to
+    /// a directory named after the texture. Needs an image `--dds` format
+    #[arg(long)]
+    pub dds_sprites: bool,
 }
 
 impl<'a> IArgs<'a> for DDSConfig {
@@ -22,6 +22,9 @@ pub enum DistributionFormat {
     // XML,
     MINI,
     PRETTY,
-    // CSV,
     YAML,
+    /// Gatherable points in world coordinates, for interactive maps
+    GEOJSON,
+    /// `slice,variant,x,y` rows in world coordinates
+    CSV,
 }
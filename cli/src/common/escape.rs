@@ -0,0 +1,15 @@
+use clap::ValueEnum;
+
+/// How output paths are changed for names Windows can't create, like `aux.lua` or `a:b`.
+#[derive(ValueEnum, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum PathEscape {
+    /// Underscores on Windows, nothing elsewhere
+    #[default]
+    AUTO,
+    /// Write names as they are in the paks
+    NONE,
+    /// Replace invalid characters with `_` and add `_` to reserved names, `aux_.lua`
+    UNDERSCORE,
+    /// Replace invalid characters and the first letter of reserved names with `%XX`, `%61ux.lua`
+    PERCENT,
+}
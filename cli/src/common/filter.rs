@@ -1,13 +1,117 @@
-use clap::Parser;
+use clap::{Parser, ValueEnum};
+use regex::Regex;
 use rusqlite::{params, OptionalExtension};
+use std::{
+    collections::HashSet,
+    io::{self, Read},
+    path::PathBuf,
+    sync::OnceLock,
+};
 
 use crate::traits::{IArgs, IDatabase};
 
 #[derive(Debug, Parser, Clone)]
 pub struct Filter {
     #[arg(short, long)]
-    /// Filter file names with a glob
+    /// Filter file names with comma separated globs, those starting with `!` exclude
     pub filter: Option<String>,
+    #[arg(short = 'x', long)]
+    /// Exclude file names matching a glob, can be repeated
+    pub exclude: Vec<String>,
+    #[arg(long)]
+    /// Read globs from a file, one per line. Lines starting with `!` exclude, `#` comments
+    pub filter_file: Option<PathBuf>,
+    #[arg(long)]
+    /// Only keep the exact entry paths listed in a file, one per line, or on stdin with `-`
+    pub files: Option<PathBuf>,
+    #[arg(long, value_parser = Regex::new)]
+    /// Only keep entries whose `/` separated path matches this regex. An entry is kept when it
+    /// matches an include glob (or none are given), this regex, and no exclude glob
+    pub filter_regex: Option<Regex>,
+    #[arg(long = "type", value_delimiter = ',')]
+    /// Only keep entries whose contents are of these comma separated types, detected from
+    /// their magic bytes. Requires reading the head of every entry matching the other filters
+    pub types: Vec<ContentType>,
+    #[arg(long, value_enum, default_value_t)]
+    /// Case of entry paths, both when matching the filters and in output paths. `lower` merges
+    /// directories the paks spell differently, like `Textures` and `textures`
+    pub path_case: PathCase,
+    #[arg(skip)]
+    /// Only keep entries with one of these extensions, set by commands working on a single file
+    /// type
+    pub extensions: Vec<String>,
+}
+
+#[derive(ValueEnum, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum PathCase {
+    /// Paths as the paks spell them
+    #[default]
+    PRESERVE,
+    /// Lowercase paths, globs and `--filter-regex` match them regardless of case
+    LOWER,
+}
+
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ContentType {
+    LUAC,
+    OBJECTSTREAM,
+    DATASHEET,
+    DISTRIBUTION,
+    VSHAPEC,
+    DDS,
+    MESH,
+    AUDIO,
+    TERRAIN,
+    MATERIAL,
+    OTHER,
+}
+
+impl Filter {
+    /// Combines `--filter`, `--exclude` and `--filter-file` into one comma separated pattern
+    /// list, `None` when nothing filters.
+    pub fn patterns(&self) -> io::Result<Option<String>> {
+        let mut patterns = self.filter.iter().cloned().collect::<Vec<_>>();
+        patterns.extend(self.exclude.iter().map(|pattern| format!("!{pattern}")));
+        if let Some(path) = &self.filter_file {
+            let file = std::fs::read_to_string(path)?;
+            patterns.extend(
+                file.lines()
+                    .map(str::trim)
+                    .filter(|line| !line.is_empty() && !line.starts_with('#'))
+                    .map(str::to_string),
+            );
+        }
+        Ok((!patterns.is_empty()).then(|| patterns.join(",")))
+    }
+
+    /// The entry paths listed by `--files`, with `/` separators and lowercased, `None` when not
+    /// given. Stdin is only read the first time.
+    pub fn files(&self) -> io::Result<Option<HashSet<String>>> {
+        static STDIN: OnceLock<String> = OnceLock::new();
+
+        let Some(path) = &self.files else {
+            return Ok(None);
+        };
+        let list = if path.as_os_str() == "-" {
+            match STDIN.get() {
+                Some(list) => list.to_owned(),
+                None => {
+                    let mut list = String::new();
+                    io::stdin().read_to_string(&mut list)?;
+                    STDIN.get_or_init(|| list).to_owned()
+                }
+            }
+        } else {
+            std::fs::read_to_string(path)?
+        };
+        Ok(Some(
+            list.lines()
+                .map(str::trim)
+                .filter(|line| !line.is_empty())
+                .map(|line| line.replace('\\', "/").to_lowercase())
+                .collect(),
+        ))
+    }
 }
 
 impl<'a> IArgs<'a> for Filter {
@@ -1,4 +1,5 @@
 use crate::{
+    interactive, locate,
     traits::{IArgs, IDatabase},
     STEAM_DIR,
 };
@@ -11,6 +12,7 @@ use super::validate_path;
 #[derive(Debug, Parser, Clone)]
 pub struct Input {
     /// New World root directory. Needs to be root, not ./assets as it looks for the bin for parsing strings.
+    /// Detected from the Steam libraries and the Amazon Games library when not given
     #[arg(short, long, value_parser = validate_path)]
     pub input: Option<PathBuf>,
 }
@@ -19,9 +21,23 @@ impl<'a> IArgs<'a> for Input {
     type Value = Option<String>;
 
     fn configure(&mut self, value: Self::Value) -> std::io::Result<()> {
-        if self.input.is_none() {
+        if self.input.is_none() && !interactive() {
+            let input = value
+                .and_then(|path| validate_path(&path).ok())
+                .or_else(locate::install);
+            self.input = Some(input.ok_or_else(|| {
+                std::io::Error::new(
+                    std::io::ErrorKind::InvalidInput,
+                    "--input is required when not prompting and no install was found",
+                )
+            })?);
+        } else if self.input.is_none() {
             let input: PathBuf = cliclack::input("New World Directory")
-                .default_input(&value.unwrap_or_else(|| STEAM_DIR.to_string()))
+                .default_input(&value.unwrap_or_else(|| {
+                    locate::install()
+                        .map(|path| path.to_string_lossy().into_owned())
+                        .unwrap_or_else(|| STEAM_DIR.to_string())
+                }))
                 .validate_interactively(|path: &String| match PathBuf::from_str(path) {
                     Ok(p) => {
                         if p.join(r"Bin64\NewWorld.exe").exists() && p.join(r"assets").exists() {
@@ -0,0 +1,10 @@
+use clap::ValueEnum;
+
+/// How entries identical to one already written are linked to its output.
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LinkMode {
+    /// Hard links, the output directory has to be on a single file system
+    HARD,
+    /// Symbolic links relative to the link, Windows needs developer mode or admin rights
+    SYMBOLIC,
+}
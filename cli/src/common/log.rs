@@ -0,0 +1,14 @@
+use clap::ValueEnum;
+
+/// The most verbose events logged.
+#[derive(ValueEnum, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum LogLevel {
+    ERROR,
+    WARN,
+    #[default]
+    INFO,
+    /// Also times every entry extracted
+    DEBUG,
+    /// Also times decompressing and converting every entry
+    TRACE,
+}
@@ -5,7 +5,20 @@ use crate::traits::IArgs;
 #[derive(Debug, Parser)]
 pub struct LuaConfig {
     #[arg(long, default_value = "bytes")]
-    pub distribution: LuaFormat,
+    /// How `.luac` scripts are written
+    pub luac_format: LuaFormat,
+    #[arg(long, conflicts_with = "luac_format")]
+    /// Decompile `.luac` scripts back to Lua source, same as `--luac-format lua`
+    pub luac: bool,
+}
+
+impl LuaConfig {
+    pub fn format(&self) -> &LuaFormat {
+        match self.luac {
+            true => &LuaFormat::LUA,
+            false => &self.luac_format,
+        }
+    }
 }
 
 impl<'a> IArgs<'a> for LuaConfig {
@@ -19,5 +32,8 @@ impl<'a> IArgs<'a> for LuaConfig {
 pub enum LuaFormat {
     #[default]
     BYTES,
+    /// Decompiled Lua source
     LUA,
+    /// Disassembly listing of the bytecode
+    ASM,
 }
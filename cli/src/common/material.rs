@@ -0,0 +1,24 @@
+use clap::{Parser, ValueEnum};
+
+use crate::traits::IArgs;
+
+#[derive(Debug, Parser)]
+pub struct MaterialConfig {
+    /// Convert `.mtl` materials into json mapping each slot to its extracted textures
+    #[arg(long, default_value = "bytes")]
+    pub material: MaterialFormat,
+}
+
+impl<'a> IArgs<'a> for MaterialConfig {
+    type Value = ();
+    fn configure(&mut self, _: Self::Value) -> std::io::Result<()> {
+        todo!()
+    }
+}
+
+#[derive(ValueEnum, Debug, Clone, Default, PartialEq, Eq)]
+pub enum MaterialFormat {
+    #[default]
+    BYTES,
+    JSON,
+}
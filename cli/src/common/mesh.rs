@@ -0,0 +1,24 @@
+use clap::{Parser, ValueEnum};
+
+use crate::traits::IArgs;
+
+#[derive(Debug, Parser)]
+pub struct MeshConfig {
+    /// Convert `.cgf`, `.cga`, `.skin` and `.chr` geometry, with skeletons where present
+    #[arg(long, default_value = "bytes")]
+    pub mesh: MeshFormat,
+}
+
+impl<'a> IArgs<'a> for MeshConfig {
+    type Value = ();
+    fn configure(&mut self, _: Self::Value) -> std::io::Result<()> {
+        todo!()
+    }
+}
+
+#[derive(ValueEnum, Debug, Clone, Default, PartialEq, Eq)]
+pub enum MeshFormat {
+    #[default]
+    BYTES,
+    GLB,
+}
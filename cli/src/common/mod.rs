@@ -1,14 +1,24 @@
+pub mod audio;
+pub mod compression;
 pub mod datasheet;
 pub mod dds;
 pub mod distribution;
+pub mod escape;
 pub mod filter;
 pub mod input;
+pub mod link;
+pub mod log;
 pub mod lua;
+pub mod material;
+pub mod mesh;
 pub mod objectstream;
+pub mod order;
 pub mod output;
+pub mod progress;
+pub mod terrain;
 pub mod vshapec;
 
-use clap::Parser;
+use clap::{Parser, ValueEnum};
 use filter::Filter;
 use input::Input;
 use output::Output;
@@ -49,6 +59,34 @@ impl<'a> IArgs<'a> for CommonConfig {
     }
 }
 
+/// The database of values remembered between runs, created on first use.
+pub(crate) fn configs() -> Connection {
+    let config_dir = dirs::config_local_dir().unwrap().join(".nwtools");
+    let conn = rusqlite::Connection::open(config_dir).unwrap();
+
+    conn.pragma_update_and_check(None, "journal_mode", "WAL", |_res| Ok(()))
+        .unwrap();
+
+    conn.pragma_update(None, "synchronous", 1).unwrap();
+
+    conn
+        .execute(
+            "create table if not exists configs (name text primary key, value text) strict, without rowid",
+            [],
+        )
+        .unwrap();
+    conn
+}
+
+/// Looks up the `'static` variant of a format enum by its command line name.
+pub fn variant<T: ValueEnum + 'static>(name: &str) -> Option<&'static T> {
+    T::value_variants().iter().find(|value| {
+        value
+            .to_possible_value()
+            .is_some_and(|value| value.matches(name, true))
+    })
+}
+
 fn validate_path(path: &str) -> Result<PathBuf, String> {
     let path = PathBuf::from(path);
     if path.join(r"Bin64\NewWorld.exe").exists() && path.join(r"assets").exists() {
@@ -60,6 +98,28 @@ fn validate_path(path: &str) -> Result<PathBuf, String> {
     }
 }
 
+/// Parses a byte count like `1048576`, `512K`, `50MB` or `1.5G`, in powers of 1024.
+pub(crate) fn parse_bytes(value: &str) -> Result<u64, String> {
+    let value = value.trim();
+    let split = value
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .unwrap_or(value.len());
+    let (number, unit) = value.split_at(split);
+    let number = number
+        .parse::<f64>()
+        .map_err(|_| format!("{value} is not a byte count"))?;
+    let unit = unit.trim().to_ascii_uppercase();
+    let exponent = match unit.trim_end_matches("IB").trim_end_matches('B') {
+        "" => 0,
+        "K" => 1,
+        "M" => 2,
+        "G" => 3,
+        "T" => 4,
+        _ => return Err(format!("unknown unit {unit}, use K, M, G or T")),
+    };
+    Ok((number * 1024f64.powi(exponent)) as u64)
+}
+
 fn nw_type(input: &PathBuf) -> &'static str {
     if input
         .to_str()
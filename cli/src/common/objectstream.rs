@@ -6,6 +6,9 @@ use crate::traits::IArgs;
 pub struct ObjectStreamConfig {
     #[arg(long, default_value = "bytes")]
     pub objectstream: ObjectStreamFormat,
+    /// Add the catalog path of each asset reference to json and yaml object streams
+    #[arg(long)]
+    pub resolve_assets: bool,
 }
 
 impl<'a> IArgs<'a> for ObjectStreamConfig {
@@ -23,5 +26,8 @@ pub enum ObjectStreamFormat {
     MINI,
     PRETTY,
     // CSV,
-    // YAML,
+    YAML,
+    /// Entities of `.slice` and `.dynamicslice` files with their hierarchy, transforms and
+    /// components, other object streams as pretty json
+    ENTITIES,
 }
@@ -0,0 +1,15 @@
+use clap::ValueEnum;
+
+/// The order entries are extracted in.
+#[derive(ValueEnum, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ExtractOrder {
+    /// Every pak front to back, paks taking turns, for disk locality
+    #[default]
+    PAK,
+    /// Smallest entries first, for steady visible progress
+    SMALLEST,
+    /// Largest entries first, so no big entry is left running at the end
+    LARGEST,
+    /// Entries in the order of the asset catalog first, then the others
+    CATALOG,
+}
@@ -4,7 +4,10 @@ use clap::Parser;
 use dirs::document_dir;
 use rusqlite::{params, OptionalExtension};
 
-use crate::traits::{IArgs, IDatabase};
+use crate::{
+    interactive,
+    traits::{IArgs, IDatabase},
+};
 
 #[derive(Debug, Parser, Clone)]
 pub struct Output {
@@ -19,15 +22,20 @@ impl<'a> IArgs<'a> for Output {
         let docs_dir = document_dir().unwrap();
 
         if self.output.is_none() {
-            let output: PathBuf = cliclack::input("Extract Directory")
-                .default_input(&value.0.unwrap_or_else(|| {
-                    docs_dir
-                        .join(r"nw\".to_owned() + value.1)
-                        .to_str()
-                        .unwrap_or_default()
-                        .to_string()
-                }))
-                .interact()?;
+            let default = value.0.unwrap_or_else(|| {
+                docs_dir
+                    .join(r"nw\".to_owned() + value.1)
+                    .to_str()
+                    .unwrap_or_default()
+                    .to_string()
+            });
+            let output: PathBuf = if interactive() {
+                cliclack::input("Extract Directory")
+                    .default_input(&default)
+                    .interact()?
+            } else {
+                PathBuf::from(default)
+            };
             self.output = Some(output);
         }
         Ok(())
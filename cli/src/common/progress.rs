@@ -0,0 +1,15 @@
+use clap::ValueEnum;
+
+/// How extraction reports its progress.
+#[derive(ValueEnum, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ProgressFormat {
+    /// Progress bars for people watching the terminal
+    #[default]
+    BAR,
+    /// Newline delimited json events for programs driving nwtools
+    JSON,
+    /// A plain log line every few seconds, for CI and pipes
+    PLAIN,
+    /// A full screen interface with the progress of every pak, the throughput and the errors
+    TUI,
+}
@@ -0,0 +1,24 @@
+use clap::{Parser, ValueEnum};
+
+use crate::traits::IArgs;
+
+#[derive(Debug, Parser)]
+pub struct TerrainConfig {
+    /// Convert region rasters such as heightmaps, writing tile metadata beside each image
+    #[arg(long, default_value = "bytes")]
+    pub terrain: TerrainFormat,
+}
+
+impl<'a> IArgs<'a> for TerrainConfig {
+    type Value = ();
+    fn configure(&mut self, _: Self::Value) -> std::io::Result<()> {
+        todo!()
+    }
+}
+
+#[derive(ValueEnum, Debug, Clone, Default, PartialEq, Eq)]
+pub enum TerrainFormat {
+    #[default]
+    BYTES,
+    PNG,
+}
@@ -0,0 +1,201 @@
+use clap::{Arg, Command, CommandFactory};
+use std::fmt::Write;
+
+use crate::Args;
+
+/// What the values of format flags produce and how they're used, beyond their one line help.
+/// `{bin}` is replaced by the name of the binary.
+const DETAILS: &[(&str, &str)] = &[
+    (
+        "datasheet",
+        "Datasheets are the game's data tables, like items, perks and loot buckets. `bytes` \
+         copies them unchanged, the others convert every row with its column names:
+
+  xml       one element per row
+  mini      a json array of row objects on one line
+  pretty    the same json, indented
+  csv       a header row of column names, then one line per row
+  yaml      a list of row mappings
+  sql       CREATE TABLE and INSERT statements per datasheet, see --sql-dialect
+  sqlite    every datasheet as a table of a single datasheets.sqlite
+
+Examples:
+  {bin} extract --datasheet pretty
+  {bin} datasheets --datasheet csv --datasheet-columns ItemID,Tier
+  {bin} datasheets --datasheet sql --sql-dialect postgres --sql-batch 250",
+    ),
+    (
+        "datasheet-filenames",
+        "`original` keeps the path of the datasheet in the paks, `typename` writes it to \
+         datatables/<Table Type>/<Table Name> so sheets of one type end up together.
+
+Examples:
+  {bin} datasheets --datasheet pretty --datasheet-filenames typename",
+    ),
+    (
+        "locale-output",
+        "Only matters when --inline-locale is given more than one locale.
+
+Examples:
+  {bin} datasheets --datasheet pretty --inline-locale en,de --locale-output columns
+  {bin} datasheets --datasheet sqlite --inline-locale all --locale-output table",
+    ),
+    (
+        "sql-dialect",
+        "Changes quoting, column types and how existing rows are replaced, so the statements \
+         load into that database as they are.
+
+Examples:
+  {bin} datasheets --datasheet sql --sql-dialect mysql",
+    ),
+    (
+        "objectstream",
+        "Object streams are serialized component data: slices, dynamic slices, entities and \
+         most game configuration. `bytes` copies them unchanged, the others resolve every \
+         element's type and field names:
+
+  xml       the element tree as written by the engine
+  mini      a json object per element on one line
+  pretty    the same json, indented
+  yaml      the element tree as yaml
+  entities  the entities of slices with their hierarchy, transforms and components
+
+Examples:
+  {bin} extract --objectstream pretty --filter \"**/*.dynamicslice\"
+  {bin} extract --objectstream entities --resolve-assets",
+    ),
+    (
+        "distribution",
+        "Distributions place gatherables and other spawns across the world's regions.
+
+Examples:
+  {bin} extract --distribution geojson --filter \"**/*.distribution\"
+  {bin} extract --distribution csv",
+    ),
+    (
+        "vshapec",
+        "Vegetation shapes outline where vegetation is placed in a region.
+
+Examples:
+  {bin} extract --vshapec pretty --filter \"**/*.vshapec\"",
+    ),
+    (
+        "dds",
+        "`flat` writes the merged .dds with its mips in one file, the image formats decode its \
+         largest mip.
+
+Examples:
+  {bin} extract --dds png --filter \"**/icons/**\"
+  {bin} extract --texture-format webp",
+    ),
+    (
+        "dds-sprites",
+        "UI atlases in lyshineui come with a .sprite layout naming each cell. The texture is \
+         still written whole, its cells go to a directory beside it.
+
+Examples:
+  {bin} extract --dds png --dds-sprites --filter \"lyshineui/**\"",
+    ),
+    (
+        "luac-format",
+        "Examples:
+  {bin} extract --luac-format lua --filter \"**/*.luac\"
+  {bin} extract --luac-format asm",
+    ),
+    (
+        "mesh",
+        "`glb` writes binary glTF with the geometry, material slots and skeleton of each mesh.
+
+Examples:
+  {bin} extract --mesh glb --filter \"**/*.cgf\"",
+    ),
+    (
+        "audio-format",
+        "Wwise Vorbis needs --audio-codebooks to convert to `ogg` or `wav`.
+
+Examples:
+  {bin} extract --audio-format ogg --audio-codebooks packed_codebooks_aoTuV_603.bin",
+    ),
+    (
+        "order",
+        "Examples:
+  {bin} extract --order largest --jobs 8",
+    ),
+    (
+        "compress-output",
+        "Binary outputs like textures and meshes are written uncompressed either way.
+
+Examples:
+  {bin} extract --datasheet pretty --compress-output zstd",
+    ),
+];
+
+/// Extended help for `flag`, with or without its leading dashes: its help, its values with their
+/// help, the commands taking it and, for format flags, what each value produces with examples.
+/// `None` when no command takes the flag.
+pub(crate) fn explain(flag: &str) -> Option<String> {
+    let flag = flag.trim_start_matches('-');
+    let mut command = Args::command();
+    command.build();
+    let bin = command.get_name().to_string();
+
+    let mut found = vec![];
+    find(&command, flag, &bin, &mut found);
+    let (_, arg) = found.first()?;
+    let long = arg.get_long().unwrap_or(flag);
+
+    let mut text = format!("--{long}");
+    if let Some(help) = arg.get_long_help().or_else(|| arg.get_help()) {
+        write!(text, "\n\n{help}").unwrap();
+    }
+
+    let defaults = arg.get_default_values();
+    let values = arg.get_possible_values();
+    if !values.is_empty() {
+        text.push_str("\n\nValues:");
+        let width = values.iter().map(|v| v.get_name().len()).max().unwrap_or(0);
+        for value in values.iter().filter(|value| !value.is_hide_set()) {
+            write!(text, "\n  {:width$}", value.get_name()).unwrap();
+            if let Some(help) = value.get_help() {
+                write!(text, "  {help}").unwrap();
+            }
+            if defaults
+                .iter()
+                .any(|default| value.matches(&default.to_string_lossy(), true))
+            {
+                text.push_str(" (default)");
+            }
+        }
+    }
+
+    if let Some((_, details)) = DETAILS.iter().find(|(name, _)| *name == long) {
+        write!(text, "\n\n{}", details.replace("{bin}", &bin)).unwrap();
+    }
+
+    let commands = found
+        .iter()
+        .map(|(path, _)| path.as_str())
+        .collect::<Vec<_>>();
+    write!(text, "\n\nUsed by: {}", commands.join(", ")).unwrap();
+    Some(text)
+}
+
+/// Collects every command under `command`, by its full name, taking `flag` by its long name or
+/// one of its aliases.
+fn find<'a>(command: &'a Command, flag: &str, path: &str, found: &mut Vec<(String, &'a Arg)>) {
+    if let Some(arg) = command.get_arguments().find(|arg| {
+        arg.get_long() == Some(flag)
+            || arg
+                .get_all_aliases()
+                .is_some_and(|aliases| aliases.contains(&flag))
+    }) {
+        // global flags are propagated to every subcommand, only list where they're declared
+        if !(arg.is_global_set() && found.iter().any(|(_, found)| found.is_global_set())) {
+            found.push((path.to_string(), arg));
+        }
+    }
+    for subcommand in command.get_subcommands() {
+        let path = format!("{path} {}", subcommand.get_name());
+        find(subcommand, flag, &path, found);
+    }
+}
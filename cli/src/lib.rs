@@ -1,48 +1,217 @@
 pub mod commands;
 pub mod common;
+mod explain;
+pub mod locate;
+mod profile;
 mod traits;
 
-use clap::{self, Parser};
-use commands::Commands;
-use std::{io, sync::LazyLock};
+use clap::{self, error::ErrorKind, CommandFactory, Parser};
+use commands::{locale::LocaleCommands, Commands};
+use common::{log::LogLevel, progress::ProgressFormat};
+use std::{
+    io::{self, IsTerminal},
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        LazyLock,
+    },
+};
 use traits::IArgs;
 
 pub static ARGS: LazyLock<Args> = LazyLock::new(|| match cli() {
     Ok(args) => args,
-    Err(_) => std::process::exit(0),
+    Err(e) if e.kind() == io::ErrorKind::Interrupted => std::process::exit(0),
+    Err(e) => {
+        eprintln!("error: {e}");
+        std::process::exit(2)
+    }
 });
 
+/// Whether prompts can be shown, set once the arguments are parsed.
+static INTERACTIVE: AtomicBool = AtomicBool::new(true);
+
+/// Whether the arguments are parsed and configured. Interrupts are left to the command after.
+static PARSED: AtomicBool = AtomicBool::new(false);
+
+/// Whether prompts can be shown. Missing arguments fall back to the last used or default values
+/// otherwise.
+pub(crate) fn interactive() -> bool {
+    INTERACTIVE.load(Ordering::Relaxed)
+}
+
 const STEAM_DIR: &str = r#"C:\Program Files (x86)\Steam\steamapps\common\New World"#;
 const PRETTY: &str = "json";
 const MINI: &str = "mini";
 const XML: &str = "xml";
 const CSV: &str = "csv";
+const HTML: &str = "html";
 const SQL: &str = "sql";
+const SQLITE: &str = "sqlite";
+const XLSX: &str = "xlsx";
 const BYTES: &str = "bytes";
 const YAML: &str = "yaml";
+const ENTITIES: &str = "entities";
+const GEOJSON: &str = "geojson";
 
 #[derive(Debug, Parser)]
-#[command(version, about, long_about = None)]
+#[command(version, about, long_about = None, args_override_self = true)]
 pub struct Args {
     #[command(subcommand)]
     pub command: Commands,
+    #[arg(long, global = true)]
+    /// Read paks through memory maps instead of buffered file reads
+    pub mmap: bool,
+    #[arg(long, global = true)]
+    /// Take arguments from this profile in nwtools.toml, those given on the command line win.
+    /// Uses the `default` profile when it exists and none is given
+    pub profile: Option<String>,
+    #[arg(long, global = true)]
+    /// Profiles file, nwtools.toml in the working directory or the config directory by default
+    pub config: Option<PathBuf>,
+    #[arg(long, global = true, value_enum, default_value_t)]
+    /// How extraction reports progress. `json` writes newline delimited events with the files and
+    /// bytes processed, the current pak and the ETA for other programs to display
+    pub progress: ProgressFormat,
+    #[arg(long, global = true)]
+    /// Send `--progress json` events to this TCP address, e.g. `127.0.0.1:9000`, instead of stdout
+    pub progress_addr: Option<String>,
+    #[arg(short, long, global = true)]
+    /// Print nothing but errors and never prompt
+    pub quiet: bool,
+    #[arg(long, global = true)]
+    /// Never prompt and log progress as plain lines instead of progress bars. Implied when stderr
+    /// isn't a terminal
+    pub no_tty: bool,
+    #[arg(long, global = true, value_enum, default_value_t)]
+    /// The most verbose events logged
+    pub log_level: LogLevel,
+    #[arg(long, global = true)]
+    /// Log to this file instead of stderr, with the time spent in every span when it closes
+    pub log_file: Option<PathBuf>,
+    #[arg(long, global = true, value_name = "FLAG")]
+    /// Print what a flag does, its values and examples, e.g. `extract --explain datasheet`
+    pub explain: Option<String>,
+}
+
+impl Args {
+    /// Whether prompts and progress bars can be shown.
+    pub fn interactive(&self) -> bool {
+        !self.quiet && !self.no_tty && io::stderr().is_terminal()
+    }
+
+    /// `--progress`, falling back to plain lines when progress bars or the full screen interface
+    /// can't be shown.
+    pub fn progress(&self) -> ProgressFormat {
+        match self.progress {
+            ProgressFormat::BAR | ProgressFormat::TUI if !self.interactive() => {
+                ProgressFormat::PLAIN
+            }
+            progress => progress,
+        }
+    }
 }
 
 fn cli() -> io::Result<Args> {
     ctrlc::set_handler(move || {
-        cliclack::outro_cancel("Operation cancelled.").unwrap();
+        if PARSED.load(Ordering::Relaxed) {
+            return;
+        }
+        if interactive() {
+            cliclack::outro_cancel("Operation cancelled.").unwrap();
+        }
         std::process::exit(0);
     })
     .expect("setting Ctrl-C handler");
     let mut args = Args::parse();
+    let argv = std::env::args_os().collect::<Vec<_>>();
+    match profile::with_profile(&argv, &args) {
+        Ok(Some(argv)) => args = Args::parse_from(argv),
+        Ok(None) => {}
+        Err(e) => Args::command().error(ErrorKind::InvalidValue, e).exit(),
+    }
 
-    cliclack::clear_screen()?;
-    cliclack::intro("New World Tools")?;
+    if let Some(flag) = &args.explain {
+        match explain::explain(flag) {
+            Some(text) => println!("{text}"),
+            None => Args::command()
+                .error(
+                    ErrorKind::InvalidValue,
+                    format!("no command takes `--{}`", flag.trim_start_matches('-')),
+                )
+                .exit(),
+        }
+        std::process::exit(0);
+    }
+    if let Commands::Completions(completions) = &args.command {
+        completions.write(&mut io::stdout());
+        std::process::exit(0);
+    }
+
+    INTERACTIVE.store(args.interactive(), Ordering::Relaxed);
+    if args.interactive() {
+        cliclack::clear_screen()?;
+        cliclack::intro("New World Tools")?;
+    }
 
     match &mut args.command {
         Commands::Extract(ext) => ext.configure(())?,
+        Commands::Datasheets(datasheets) => datasheets.configure(())?,
         Commands::Test(_) => {}
+        Commands::Locale(locale) => match &mut locale.commands {
+            LocaleCommands::Export { input, .. }
+            | LocaleCommands::Get { input, .. }
+            | LocaleCommands::Find { input, .. } => input.configure(None)?,
+            LocaleCommands::Search { .. } => {}
+        },
+        Commands::Grep(grep) => grep.input.configure(None)?,
+        Commands::Info(info) => info.input.configure(None)?,
+        Commands::Tree(tree) => tree.input.configure(None)?,
+        Commands::Validate(validate) => validate.input.configure(None)?,
+        Commands::Bench(bench) => bench.input.configure(None)?,
+        Commands::Serve(serve) => serve.input.configure(None)?,
+        Commands::Cat(cat) => cat.input.configure(None)?,
+        Commands::Catalog(catalog) => catalog.input.configure(None)?,
+        Commands::Deps(deps) => deps.input.configure(None)?,
+        Commands::Codegen(codegen) => {
+            let args = codegen.commands.args_mut();
+            args.filter.extensions = vec!["datasheet".to_string()];
+            args.input.configure(None)?
+        }
+        Commands::Join(join) => {
+            let args = join.commands.args_mut();
+            args.filter.extensions = vec!["datasheet".to_string()];
+            args.input.configure(None)?
+        }
+        Commands::Icons(icons) => {
+            icons.filter.extensions = vec!["datasheet".to_string()];
+            icons.input.configure(None)?
+        }
+        Commands::Markers(markers) => {
+            markers.filter.extensions = vec!["slice".to_string(), "dynamicslice".to_string()];
+            markers.input.configure(None)?
+        }
+        Commands::Components(components) => {
+            components.filter.extensions = vec!["slice".to_string(), "dynamicslice".to_string()];
+            components.input.configure(None)?
+        }
+        Commands::Positions(positions) => {
+            positions.filter.extensions = vec!["slice".to_string(), "dynamicslice".to_string()];
+            positions.input.configure(None)?
+        }
+        Commands::Checksum(checksum) => checksum.input.configure(None)?,
+        Commands::Diff(_)
+        | Commands::Pack(_)
+        | Commands::Hashes(_)
+        | Commands::Locate(_)
+        | Commands::Completions(_)
+        | Commands::Workspace(_) => {}
     };
 
+    // everything downstream reads its settings from `extract`
+    if let Commands::Datasheets(datasheets) = &args.command {
+        args.command = Commands::Extract(datasheets.extract());
+    }
+
+    PARSED.store(true, Ordering::Relaxed);
     Ok(args)
 }
@@ -0,0 +1,88 @@
+//! Finds New World installs in the Steam libraries and the Amazon Games library.
+
+use std::{
+    collections::HashSet,
+    path::{Path, PathBuf},
+};
+
+/// Steam app id of New World.
+const APP_ID: &str = "1063730";
+const AMAZON_GAMES_DIRS: [&str; 2] = [
+    r"C:\Amazon Games\Library\New World",
+    r"C:\Program Files\Amazon Games\Library\New World",
+];
+
+/// Every New World install found, Steam libraries first.
+pub fn installs() -> Vec<PathBuf> {
+    let mut seen = HashSet::new();
+    steam_dirs()
+        .iter()
+        .flat_map(|steam| libraries(steam))
+        .filter_map(|library| {
+            let steamapps = library.join("steamapps");
+            let manifest =
+                std::fs::read_to_string(steamapps.join(format!("appmanifest_{APP_ID}.acf")))
+                    .ok()?;
+            Some(
+                steamapps
+                    .join("common")
+                    .join(value(&manifest, "installdir")?),
+            )
+        })
+        .chain(AMAZON_GAMES_DIRS.iter().map(PathBuf::from))
+        .filter(|path| is_install(path))
+        // ~/.steam/steam usually links to ~/.local/share/Steam
+        .filter(|path| seen.insert(path.canonicalize().unwrap_or_else(|_| path.clone())))
+        .collect()
+}
+
+/// The first New World install found.
+pub fn install() -> Option<PathBuf> {
+    installs().into_iter().next()
+}
+
+fn is_install(path: &Path) -> bool {
+    path.join("Bin64").join("NewWorld.exe").is_file() && path.join("assets").is_dir()
+}
+
+/// Where Steam may be installed, including under Proton on Linux.
+fn steam_dirs() -> Vec<PathBuf> {
+    let mut dirs = vec![
+        PathBuf::from(r"C:\Program Files (x86)\Steam"),
+        PathBuf::from(r"C:\Program Files\Steam"),
+    ];
+    if let Some(home) = dirs::home_dir() {
+        dirs.push(home.join(".steam").join("steam"));
+        dirs.push(home.join(".local").join("share").join("Steam"));
+    }
+    dirs.retain(|dir| dir.is_dir());
+    dirs
+}
+
+/// The library folders listed in Steam's `libraryfolders.vdf`, the Steam directory itself when
+/// there is none.
+fn libraries(steam: &Path) -> Vec<PathBuf> {
+    let vdf = steam.join("steamapps").join("libraryfolders.vdf");
+    let mut libraries = std::fs::read_to_string(vdf)
+        .map(|vdf| {
+            vdf.lines()
+                .filter_map(|line| value(line, "path"))
+                .map(PathBuf::from)
+                .collect::<Vec<_>>()
+        })
+        .unwrap_or_default();
+    if !libraries.iter().any(|library| library == steam) {
+        libraries.insert(0, steam.to_path_buf());
+    }
+    libraries
+}
+
+/// The value of the first `"key" "value"` pair for `key` in a Valve KeyValues text, unescaped.
+fn value(text: &str, key: &str) -> Option<String> {
+    let quoted = format!("\"{key}\"");
+    text.lines().find_map(|line| {
+        let rest = line.trim().strip_prefix(&quoted)?.trim();
+        let value = rest.strip_prefix('"')?.strip_suffix('"')?;
+        Some(value.replace(r"\\", r"\"))
+    })
+}
@@ -0,0 +1,133 @@
+//! Named profiles of arguments kept in `nwtools.toml`:
+//!
+//! ```toml
+//! [profiles.default]
+//! input = 'C:\Program Files (x86)\Steam\steamapps\common\New World'
+//!
+//! [profiles.datasheets]
+//! input = 'C:\Program Files (x86)\Steam\steamapps\common\New World'
+//! output = 'D:\nw\datasheets'
+//! filter = "**/*.datasheet"
+//! datasheet = "csv"
+//! with-meta = true
+//! ```
+//!
+//! Keys are long flag names. Keys the subcommand doesn't take are skipped, so one profile can
+//! serve several subcommands.
+
+use crate::Args;
+use clap::CommandFactory;
+use std::{
+    collections::HashSet,
+    ffi::OsString,
+    io,
+    path::{Path, PathBuf},
+};
+use toml::{Table, Value};
+
+const FILE_NAME: &str = "nwtools.toml";
+const DEFAULT: &str = "default";
+
+/// `argv` with the arguments of the selected profile inserted right after the subcommand, so the
+/// ones given on the command line override them. `None` when no profile applies.
+pub(crate) fn with_profile(argv: &[OsString], args: &Args) -> io::Result<Option<Vec<OsString>>> {
+    let Some(path) = config_path(args.config.as_deref()) else {
+        return match &args.profile {
+            Some(_) => Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("--profile needs a {FILE_NAME}"),
+            )),
+            None => Ok(None),
+        };
+    };
+    let table = std::fs::read_to_string(&path)?
+        .parse::<Table>()
+        .map_err(|e| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("{}: {e}", path.display()),
+            )
+        })?;
+
+    let name = args.profile.as_deref().unwrap_or(DEFAULT);
+    let Some(profile) = table
+        .get("profiles")
+        .and_then(|profiles| profiles.get(name))
+        .and_then(Value::as_table)
+    else {
+        return match &args.profile {
+            Some(_) => Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("no profile {name} in {}", path.display()),
+            )),
+            None => Ok(None),
+        };
+    };
+
+    let command = Args::command();
+    let Some((index, subcommand)) = argv.iter().enumerate().skip(1).find_map(|(i, arg)| {
+        let arg = arg.to_str()?;
+        command
+            .find_subcommand(arg)
+            .filter(|_| !is_value_of_global(&argv[i - 1]))
+            .map(|subcommand| (i, subcommand))
+    }) else {
+        return Ok(None);
+    };
+    let accepted = subcommand
+        .get_arguments()
+        .chain(command.get_arguments())
+        .filter_map(|arg| arg.get_long())
+        .collect::<HashSet<_>>();
+
+    let mut argv = argv.to_vec();
+    argv.splice(index + 1..index + 1, to_args(profile, &accepted));
+    Ok(Some(argv))
+}
+
+/// The config from `--config`, or `nwtools.toml` in the working directory, then the config
+/// directory.
+fn config_path(config: Option<&Path>) -> Option<PathBuf> {
+    if let Some(config) = config {
+        return Some(config.to_path_buf());
+    }
+    [
+        Some(PathBuf::from(FILE_NAME)),
+        dirs::config_dir().map(|dir| dir.join("nwtools").join(FILE_NAME)),
+    ]
+    .into_iter()
+    .flatten()
+    .find(|path| path.exists())
+}
+
+fn is_value_of_global(arg: &OsString) -> bool {
+    arg == "--profile" || arg == "--config"
+}
+
+/// Turns `key = value` pairs into `--key value` arguments. `true` is a bare flag, `false` is left
+/// out and arrays repeat the flag.
+fn to_args(profile: &Table, accepted: &HashSet<&str>) -> Vec<OsString> {
+    let mut args = vec![];
+    for (key, value) in profile {
+        let key = key.replace('_', "-");
+        if !accepted.contains(key.as_str()) {
+            continue;
+        }
+        let values = match value {
+            Value::Array(values) => values.iter().collect(),
+            value => vec![value],
+        };
+        for value in values {
+            let value = match value {
+                Value::Boolean(false) => continue,
+                Value::Boolean(true) => None,
+                Value::String(value) => Some(value.clone()),
+                Value::Integer(_) | Value::Float(_) => Some(value.to_string()),
+                _ => continue,
+            };
+            args.push(format!("--{key}").into());
+            args.extend(value.map(OsString::from));
+        }
+    }
+    args
+}
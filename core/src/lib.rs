@@ -0,0 +1,251 @@
+//! Pak indexing, decompression and conversion for programs that embed extraction, configured
+//! through [`Extractor::builder`] instead of the command line.
+
+use file_system::{
+    decompressor::{Decompressor, Metadata},
+    handle_extension, index,
+    pak::PakReader,
+    FileType,
+};
+use rayon::prelude::*;
+use std::{
+    collections::HashMap,
+    fs,
+    io::{self, Write},
+    path::{Path, PathBuf},
+};
+use zip::ZipArchive;
+
+pub use file_system::{
+    handler::{register, FileHandler},
+    FileKind,
+};
+
+/// Configures an [`Extractor`].
+#[derive(Debug, Default)]
+pub struct ExtractorBuilder {
+    game_dir: Option<PathBuf>,
+    out_dir: Option<PathBuf>,
+    filter: Option<String>,
+    formats: HashMap<FileKind, String>,
+    mmap: bool,
+}
+
+impl ExtractorBuilder {
+    /// The New World install, the directory holding `assets`.
+    pub fn game_dir<P: Into<PathBuf>>(mut self, path: P) -> Self {
+        self.game_dir = Some(path.into());
+        self
+    }
+
+    /// Where [`Extractor::extract`] writes to.
+    pub fn out_dir<P: Into<PathBuf>>(mut self, path: P) -> Self {
+        self.out_dir = Some(path.into());
+        self
+    }
+
+    /// Comma separated globs of the entries to index, `!` excluding, as with `--filter`.
+    pub fn filter<S: Into<String>>(mut self, globs: S) -> Self {
+        self.filter = Some(globs.into());
+        self
+    }
+
+    /// Converts entries of `kind` to `format`, e.g. `pretty` or `png`. Kinds without a format are
+    /// kept as they are in the paks.
+    pub fn format<S: Into<String>>(mut self, kind: FileKind, format: S) -> Self {
+        self.formats.insert(kind, format.into());
+        self
+    }
+
+    /// Memory maps paks instead of reading them through a buffer.
+    pub fn mmap(mut self, mmap: bool) -> Self {
+        self.mmap = mmap;
+        self
+    }
+
+    /// Checks the formats and the game dir, and indexes the paks.
+    pub fn build(self) -> io::Result<Extractor> {
+        let game_dir = self
+            .game_dir
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "no game dir set"))?;
+        if let Some((kind, format)) = self
+            .formats
+            .iter()
+            .find(|(kind, format)| FileType::with_format(**kind, format).is_none())
+        {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("unknown format {format} for {kind:?}"),
+            ));
+        }
+
+        if !game_dir.join("assets").is_dir() {
+            return Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("no assets directory in {}", game_dir.display()),
+            ));
+        }
+
+        file_system::embed();
+        Ok(Extractor {
+            entries: index(&game_dir, self.filter.as_ref()),
+            out_dir: self.out_dir,
+            formats: self.formats,
+            mmap: self.mmap,
+        })
+    }
+}
+
+/// Reads and converts the entries of an indexed install.
+#[derive(Debug)]
+pub struct Extractor {
+    entries: HashMap<PathBuf, (PathBuf, String)>,
+    out_dir: Option<PathBuf>,
+    formats: HashMap<FileKind, String>,
+    mmap: bool,
+}
+
+impl Extractor {
+    pub fn builder() -> ExtractorBuilder {
+        ExtractorBuilder::default()
+    }
+
+    /// Paths of the indexed entries, relative to `assets`.
+    pub fn entries(&self) -> impl Iterator<Item = &Path> {
+        self.entries.keys().map(PathBuf::as_path)
+    }
+
+    /// The decompressed contents of `entry`.
+    pub fn read<P: AsRef<Path>>(&self, entry: P) -> io::Result<Vec<u8>> {
+        self.with_entry(entry.as_ref(), |decompressor| {
+            let mut buf = Vec::new();
+            decompressor.write_as(&FileType::Other, &mut buf)?;
+            Ok(buf)
+        })
+    }
+
+    /// `entry` converted to the format configured for its kind, with the path it's written to
+    /// relative to the output directory.
+    pub fn convert<P: AsRef<Path>>(&self, entry: P) -> io::Result<(PathBuf, Vec<u8>)> {
+        let entry = entry.as_ref();
+        self.with_entry(entry, |decompressor| {
+            let file_type = self.file_type(&decompressor);
+            let mut buf = Vec::new();
+            let meta = decompressor.write_as(&file_type, &mut buf)?;
+            Ok((output_path(&file_type, entry, meta.as_ref()), buf))
+        })
+    }
+
+    /// Converts every indexed entry into the output directory, returning how many were written.
+    pub fn extract(&self) -> io::Result<usize> {
+        let out_dir = self
+            .out_dir
+            .as_ref()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "no output dir set"))?;
+
+        let mut by_pak = HashMap::<&PathBuf, Vec<(&PathBuf, &String)>>::new();
+        for (entry, (pak, name)) in &self.entries {
+            by_pak.entry(pak).or_default().push((entry, name));
+        }
+
+        by_pak
+            .into_par_iter()
+            .map(|(pak, entries)| {
+                let mut archive = ZipArchive::new(PakReader::with_mmap(pak, self.mmap)?)?;
+                for (entry, name) in &entries {
+                    let mut zip = archive.by_name(name)?;
+                    let decompressor = Decompressor::try_new(&mut zip, None)?;
+                    let file_type = self.file_type(&decompressor);
+                    let mut buf = Vec::new();
+                    let meta = decompressor.write_as(&file_type, &mut buf)?;
+                    let path = out_dir.join(output_path(&file_type, entry, meta.as_ref()));
+                    write(&path, &buf)?;
+                    if let Some(meta) = &meta {
+                        for (sidecar, buf) in meta.sidecars(&path)? {
+                            write(&sidecar, &buf)?;
+                        }
+                    }
+                }
+                Ok(entries.len())
+            })
+            .sum()
+    }
+
+    fn with_entry<T>(
+        &self,
+        entry: &Path,
+        f: impl FnOnce(Decompressor) -> io::Result<T>,
+    ) -> io::Result<T> {
+        let (pak, name) = self.entries.get(entry).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("{} isn't indexed", entry.display()),
+            )
+        })?;
+        let mut archive = ZipArchive::new(PakReader::with_mmap(pak, self.mmap)?)?;
+        let mut zip = archive.by_name(name)?;
+        f(Decompressor::try_new(&mut zip, None)?)
+    }
+
+    /// The type `decompressor` is written as, a registered [`FileHandler`] detecting it before
+    /// the configured formats.
+    fn file_type(&self, decompressor: &Decompressor) -> FileType {
+        if let Ok(FileType::Custom(handler)) = decompressor.file_type() {
+            return FileType::Custom(handler);
+        }
+        let kind = decompressor.kind();
+        let format = self.formats.get(&kind).map_or("bytes", String::as_str);
+        FileType::with_format(kind, format).unwrap_or_default()
+    }
+}
+
+fn output_path(file_type: &FileType, entry: &Path, meta: Option<&Metadata>) -> PathBuf {
+    match entry.extension() {
+        Some(_) => handle_extension(file_type, entry.to_path_buf(), meta),
+        None => entry.to_path_buf(),
+    }
+}
+
+fn write(path: &Path, buf: &[u8]) -> io::Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::File::create(path)?.write_all(buf)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn build_rejects_unknown_formats() {
+        let err = Extractor::builder()
+            .game_dir("missing")
+            .format(FileKind::DDS, "gif")
+            .build()
+            .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+
+        let err = Extractor::builder().build().unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+    }
+
+    #[test]
+    fn build_requires_an_assets_directory() {
+        let err = Extractor::builder()
+            .game_dir("missing")
+            .build()
+            .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::NotFound);
+
+        let dir = std::env::temp_dir().join(format!("nwtools-core-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let err = Extractor::builder().game_dir(&dir).build().unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::NotFound);
+
+        fs::create_dir_all(dir.join("assets")).unwrap();
+        let extractor = Extractor::builder().game_dir(&dir).build().unwrap();
+        assert_eq!(extractor.entries().count(), 0);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
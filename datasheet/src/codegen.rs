@@ -0,0 +1,468 @@
+use indexmap::IndexMap;
+use std::{
+    collections::{BTreeMap, BTreeSet, HashSet},
+    fmt::Write,
+};
+
+use crate::{Datasheet, DatasheetCell};
+
+/// The columns of a datasheet type, merged over every datasheet of that type.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Schema {
+    /// The table type, e.g. `ItemDefinitionData`.
+    pub name: String,
+    /// Names of the datasheets of this type, sorted.
+    pub sheets: Vec<String>,
+    pub columns: Vec<Column>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Column {
+    pub name: String,
+    pub kind: ColumnKind,
+    /// Missing from some of the datasheets of the type.
+    pub optional: bool,
+    /// A string column of localization keys, see [`localization_keys`].
+    pub localized: bool,
+}
+
+/// Type of the values of a column in the JSON export.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ColumnKind {
+    Boolean,
+    Integer,
+    Number,
+    String,
+}
+
+impl Schema {
+    /// One schema per table type of `sheets`, sorted by type. A number column is an integer when
+    /// every value of it in every sheet is whole.
+    pub fn merge<'a, 'b: 'a, I>(sheets: I) -> Vec<Schema>
+    where
+        I: IntoIterator<Item = &'a Datasheet<'b>>,
+    {
+        let mut types: BTreeMap<&str, Vec<&Datasheet>> = BTreeMap::new();
+        for sheet in sheets {
+            types.entry(&sheet._type).or_default().push(sheet);
+        }
+
+        types
+            .into_iter()
+            .map(|(name, sheets)| {
+                // kind, number of sheets with the column, and whether its cells are all keys and
+                // any of them is one
+                let mut columns: IndexMap<&str, (ColumnKind, usize, bool, bool)> = IndexMap::new();
+                for sheet in &sheets {
+                    for (i, header) in sheet.header.iter().enumerate() {
+                        let cells = || sheet.rows.iter().map(|row| &row[i]);
+                        let kind = kind(header._type, cells());
+                        let column = columns
+                            .entry(&header.text)
+                            .or_insert((kind, 0, true, false));
+                        column.0 = column.0.max(kind);
+                        column.1 += 1;
+                        column.2 &= kind == ColumnKind::String
+                            && cells().all(|cell| key(cell).is_some() || is_empty(cell));
+                        column.3 |= cells().any(|cell| key(cell).is_some());
+                    }
+                }
+
+                let mut names = sheets
+                    .iter()
+                    .map(|sheet| sheet.name.to_owned())
+                    .collect::<Vec<_>>();
+                names.sort_unstable();
+                names.dedup();
+                Schema {
+                    name: name.to_string(),
+                    sheets: names,
+                    columns: columns
+                        .into_iter()
+                        .map(|(name, (kind, count, keys, any))| Column {
+                            name: name.to_string(),
+                            kind,
+                            optional: count < sheets.len(),
+                            localized: keys && any,
+                        })
+                        .collect(),
+                }
+            })
+            .collect()
+    }
+}
+
+/// The `@` prefixed localization keys of a string cell.
+fn key(cell: &DatasheetCell) -> Option<&str> {
+    match cell {
+        DatasheetCell::String(value) if value.starts_with('@') => Some(value),
+        _ => None,
+    }
+}
+
+fn is_empty(cell: &DatasheetCell) -> bool {
+    matches!(cell, DatasheetCell::String(value) if value.is_empty())
+}
+
+/// Every localization key in the localized columns of `schemas`, read from `sheets`. These are
+/// the values of those columns in JSON exported without inlined locales.
+pub fn localization_keys<'a, 'b: 'a, I>(schemas: &[Schema], sheets: I) -> BTreeSet<String>
+where
+    I: IntoIterator<Item = &'a Datasheet<'b>>,
+{
+    let localized = schemas
+        .iter()
+        .flat_map(|schema| {
+            schema
+                .columns
+                .iter()
+                .filter(|column| column.localized)
+                .map(|column| (schema.name.as_str(), column.name.as_str()))
+        })
+        .collect::<HashSet<_>>();
+
+    let mut keys = BTreeSet::new();
+    for sheet in sheets {
+        for (i, header) in sheet.header.iter().enumerate() {
+            if !localized.contains(&(sheet._type.as_str(), header.text.as_str())) {
+                continue;
+            }
+            keys.extend(
+                sheet
+                    .rows
+                    .iter()
+                    .filter_map(|row| key(&row[i]))
+                    .map(str::to_string),
+            );
+        }
+    }
+    keys
+}
+
+/// Kind of a column from its header type, numbers being integers when every cell is whole.
+fn kind<'a, I>(_type: u32, mut cells: I) -> ColumnKind
+where
+    I: Iterator<Item = &'a DatasheetCell>,
+{
+    match _type {
+        1 => ColumnKind::String,
+        3 => ColumnKind::Boolean,
+        _ if cells.all(|cell| matches!(cell, DatasheetCell::Number(v) if v.fract() == 0.0)) => {
+            ColumnKind::Integer
+        }
+        _ => ColumnKind::Number,
+    }
+}
+
+const RUST_KEYWORDS: &[&str] = &[
+    "as", "async", "await", "break", "const", "continue", "crate", "dyn", "else", "enum", "extern",
+    "false", "fn", "for", "if", "impl", "in", "let", "loop", "match", "mod", "move", "mut", "pub",
+    "ref", "return", "static", "struct", "trait", "true", "type", "unsafe", "use", "where",
+    "while", "abstract", "become", "box", "do", "final", "gen", "macro", "override", "priv", "try",
+    "typeof", "unsized", "virtual", "yield",
+];
+
+/// A Rust module with a serde struct per schema, deserializing the rows of the datasheets
+/// exported as JSON, and a `load` function reading such an export.
+pub fn rust(schemas: &[Schema]) -> String {
+    let mut out = String::from(
+        "// Generated by nwtools from the datasheets of the game, don't edit.\n\n\
+         use serde::{Deserialize, Serialize};\n\n\
+         /// Reads the rows of a datasheet exported as JSON.\n\
+         pub fn load<T: serde::de::DeserializeOwned>(\n    \
+             path: impl AsRef<std::path::Path>,\n\
+         ) -> std::io::Result<Vec<T>> {\n    \
+             let reader = std::io::BufReader::new(std::fs::File::open(path)?);\n    \
+             Ok(serde_json::from_reader(reader)?)\n\
+         }\n",
+    );
+
+    for schema in schemas {
+        writeln!(out).unwrap();
+        writeln!(out, "/// A row of {}.", sheet_list(&schema.sheets)).unwrap();
+        writeln!(
+            out,
+            "#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]"
+        )
+        .unwrap();
+        writeln!(out, "pub struct {} {{", pascal_case(&schema.name)).unwrap();
+
+        let mut fields = vec![];
+        for column in &schema.columns {
+            let mut field = snake_case(&column.name);
+            if RUST_KEYWORDS.contains(&field.as_str()) {
+                field = format!("r#{field}");
+            }
+            while fields.contains(&field) {
+                field.push('_');
+            }
+            let _type = match column.kind {
+                ColumnKind::Boolean => "bool",
+                ColumnKind::Integer => "i64",
+                ColumnKind::Number => "f64",
+                ColumnKind::String => "String",
+            };
+
+            if column.optional {
+                writeln!(
+                    out,
+                    "    #[serde(rename = {:?}, default, skip_serializing_if = \"Option::is_none\")]",
+                    column.name
+                )
+                .unwrap();
+                writeln!(out, "    pub {field}: Option<{_type}>,").unwrap();
+            } else {
+                writeln!(out, "    #[serde(rename = {:?})]", column.name).unwrap();
+                writeln!(out, "    pub {field}: {_type},").unwrap();
+            }
+            fields.push(field);
+        }
+        writeln!(out, "}}").unwrap();
+    }
+    out
+}
+
+/// TypeScript declarations with an interface per schema for the rows of the datasheets exported
+/// as JSON, a `Datasheets` interface mapping every datasheet to its rows and a `LocalizationKey`
+/// union of `keys`, the type of localized columns.
+pub fn typescript(schemas: &[Schema], keys: &BTreeSet<String>) -> String {
+    let mut out =
+        String::from("// Generated by nwtools from the datasheets of the game, don't edit.\n\n");
+
+    writeln!(
+        out,
+        "/** Localization keys of the datasheets, as exported without inlined locales. */"
+    )
+    .unwrap();
+    if keys.is_empty() {
+        writeln!(out, "export type LocalizationKey = never;").unwrap();
+    } else {
+        writeln!(out, "export type LocalizationKey =").unwrap();
+        for key in keys {
+            writeln!(out, "  | {}", quote(key)).unwrap();
+        }
+        out.pop();
+        writeln!(out, ";").unwrap();
+    }
+
+    for schema in schemas {
+        writeln!(out).unwrap();
+        writeln!(out, "/** A row of {}. */", sheet_list(&schema.sheets)).unwrap();
+        writeln!(out, "export interface {} {{", pascal_case(&schema.name)).unwrap();
+        for column in &schema.columns {
+            let _type = match column.kind {
+                ColumnKind::Boolean => "boolean",
+                ColumnKind::Integer | ColumnKind::Number => "number",
+                ColumnKind::String if column.localized => "LocalizationKey | \"\"",
+                ColumnKind::String => "string",
+            };
+            let name = if is_identifier(&column.name) {
+                column.name.to_owned()
+            } else {
+                quote(&column.name)
+            };
+            let optional = if column.optional { "?" } else { "" };
+            writeln!(out, "  {name}{optional}: {_type};").unwrap();
+        }
+        writeln!(out, "}}").unwrap();
+    }
+
+    writeln!(out).unwrap();
+    writeln!(out, "/** The rows of every datasheet by its name. */").unwrap();
+    writeln!(out, "export interface Datasheets {{").unwrap();
+    let mut sheets = schemas
+        .iter()
+        .flat_map(|schema| schema.sheets.iter().map(move |sheet| (sheet, &schema.name)))
+        .collect::<Vec<_>>();
+    sheets.sort_unstable();
+    for (sheet, name) in sheets {
+        writeln!(out, "  {}: {}[];", quote(sheet), pascal_case(name)).unwrap();
+    }
+    writeln!(out, "}}").unwrap();
+    out
+}
+
+/// A double quoted JavaScript string literal.
+fn quote(value: &str) -> String {
+    let mut quoted = String::with_capacity(value.len() + 2);
+    quoted.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+fn is_identifier(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_' || c == '$')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '$')
+}
+
+/// `a`, `a and b` or `a, b and 3 more` for doc comments.
+fn sheet_list(sheets: &[String]) -> String {
+    match sheets {
+        [] => String::new(),
+        [sheet] => format!("`{sheet}`"),
+        [a, b] => format!("`{a}` and `{b}`"),
+        [a, b, rest @ ..] => format!("`{a}`, `{b}` and {} more", rest.len()),
+    }
+}
+
+/// Words of a column or type name, split on separators and case changes, e.g. `ItemID_2H` into
+/// `Item`, `ID`, `2`, `H`.
+fn words(name: &str) -> Vec<String> {
+    let chars = name.chars().collect::<Vec<_>>();
+    let mut words = vec![];
+    let mut word = String::new();
+    for (i, &c) in chars.iter().enumerate() {
+        if !c.is_ascii_alphanumeric() {
+            if !word.is_empty() {
+                words.push(std::mem::take(&mut word));
+            }
+            continue;
+        }
+        if let Some(prev) = word.chars().last() {
+            let next = chars.get(i + 1).copied().unwrap_or_default();
+            let boundary = (c.is_ascii_uppercase() && prev.is_ascii_lowercase())
+                || (c.is_ascii_uppercase()
+                    && prev.is_ascii_uppercase()
+                    && next.is_ascii_lowercase())
+                || (c.is_ascii_digit() != prev.is_ascii_digit());
+            if boundary {
+                words.push(std::mem::take(&mut word));
+            }
+        }
+        word.push(c);
+    }
+    if !word.is_empty() {
+        words.push(word);
+    }
+    words
+}
+
+pub(crate) fn snake_case(name: &str) -> String {
+    let field = words(name)
+        .iter()
+        .map(|word| word.to_ascii_lowercase())
+        .collect::<Vec<_>>()
+        .join("_");
+    match field.chars().next() {
+        None => "_".to_string(),
+        Some(c) if c.is_ascii_digit() => format!("_{field}"),
+        Some(_) => field,
+    }
+}
+
+/// `PascalCase` identifier of a name, prefixed with `_` when it would start with a digit.
+pub fn pascal_case(name: &str) -> String {
+    let name = words(name)
+        .iter()
+        .map(|word| {
+            let mut chars = word.chars();
+            let first = chars.next().unwrap().to_ascii_uppercase();
+            first.to_string() + &chars.as_str().to_ascii_lowercase()
+        })
+        .collect::<String>();
+    match name.chars().next() {
+        None => "Unnamed".to_string(),
+        Some(c) if c.is_ascii_digit() => format!("_{name}"),
+        Some(_) => name,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ColumnType;
+
+    #[test]
+    fn merges_columns_of_sheets_of_a_type() {
+        let mut common = Datasheet::from_columns(
+            "javelindata_itemdefinitions_master_common",
+            &[("ItemID", ColumnType::String), ("Tier", ColumnType::Number)],
+            vec![vec![
+                DatasheetCell::String("a".into()),
+                DatasheetCell::Number(2.0),
+            ]],
+        );
+        let mut named = Datasheet::from_columns(
+            "javelindata_itemdefinitions_master_named",
+            &[
+                ("ItemID", ColumnType::String),
+                ("Tier", ColumnType::Number),
+                ("type", ColumnType::Boolean),
+            ],
+            vec![vec![
+                DatasheetCell::String("b".into()),
+                DatasheetCell::Number(2.5),
+                DatasheetCell::Boolean(true),
+            ]],
+        );
+        common._type = "ItemDefinitionData".into();
+        named._type = "ItemDefinitionData".into();
+
+        let schemas = Schema::merge([&common, &named]);
+        assert_eq!(schemas.len(), 1);
+        let columns = &schemas[0].columns;
+        assert_eq!(columns[0].kind, ColumnKind::String);
+        assert_eq!(columns[1].kind, ColumnKind::Number);
+        assert!(!columns[1].optional);
+        assert!(columns[2].optional);
+
+        let code = rust(&schemas);
+        assert!(code.contains("pub struct ItemDefinitionData {"));
+        assert!(code.contains("    #[serde(rename = \"ItemID\")]\n    pub item_id: String,"));
+        assert!(code.contains("    pub tier: f64,"));
+        assert!(code.contains("    pub r#type: Option<bool>,"));
+    }
+
+    #[test]
+    fn writes_typescript_with_localization_keys() {
+        let mut sheet = Datasheet::from_columns(
+            "javelindata_itemdefinitions_master_common",
+            &[
+                ("ItemID", ColumnType::String),
+                ("Name", ColumnType::String),
+                ("Gear Score", ColumnType::Number),
+            ],
+            vec![vec![
+                DatasheetCell::String("a".into()),
+                DatasheetCell::String("@item_a".into()),
+                DatasheetCell::Number(500.0),
+            ]],
+        );
+        sheet._type = "ItemDefinitionData".into();
+
+        let schemas = Schema::merge([&sheet]);
+        assert!(schemas[0].columns[1].localized);
+        assert!(!schemas[0].columns[0].localized);
+        let keys = localization_keys(&schemas, [&sheet]);
+        assert_eq!(keys, BTreeSet::from(["@item_a".to_string()]));
+
+        let code = typescript(&schemas, &keys);
+        assert!(code.contains("export type LocalizationKey =\n  | \"@item_a\";\n"));
+        assert!(code.contains("  ItemID: string;\n"));
+        assert!(code.contains("  Name: LocalizationKey | \"\";\n"));
+        assert!(code.contains("  \"Gear Score\": number;\n"));
+        assert!(code
+            .contains("  \"javelindata_itemdefinitions_master_common\": ItemDefinitionData[];\n"));
+    }
+
+    #[test]
+    fn converts_names_to_identifiers() {
+        assert_eq!(snake_case("ItemID"), "item_id");
+        assert_eq!(snake_case("MaxStackSize"), "max_stack_size");
+        assert_eq!(snake_case("GS Bonus 2H"), "gs_bonus_2_h");
+        assert_eq!(snake_case("2HDamage"), "_2_h_damage");
+        assert_eq!(pascal_case("ItemDefinitionData"), "ItemDefinitionData");
+        assert_eq!(pascal_case("loot_buckets"), "LootBuckets");
+    }
+}
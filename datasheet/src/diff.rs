@@ -0,0 +1,213 @@
+use indexmap::IndexMap;
+use serde::Serialize;
+use serde_json::{Number, Value};
+
+use crate::{Datasheet, DatasheetCell, DatasheetRow};
+
+/// Differences between two versions of a datasheet. Rows are matched by their key, the first
+/// column, and cells by column name.
+#[derive(Debug, Default, Serialize)]
+pub struct DatasheetDiff {
+    pub name: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub added_columns: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub removed_columns: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub retyped_columns: Vec<Retyped>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub added_rows: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub removed_rows: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub changed_rows: Vec<ChangedRow>,
+}
+
+/// A column kept under the same name with a different type.
+#[derive(Debug, Serialize)]
+pub struct Retyped {
+    pub column: String,
+    pub old: &'static str,
+    pub new: &'static str,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ChangedRow {
+    pub key: String,
+    pub cells: Vec<ChangedCell>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ChangedCell {
+    pub column: String,
+    pub old: Value,
+    pub new: Value,
+}
+
+impl DatasheetDiff {
+    /// Compares `old` and `new` in the order of their rows. Only the first row of a repeated key
+    /// is compared.
+    pub fn between(old: &Datasheet, new: &Datasheet) -> Self {
+        let columns = |sheet: &Datasheet| -> IndexMap<String, (usize, u32)> {
+            sheet
+                .header
+                .iter()
+                .enumerate()
+                .map(|(i, header)| (header.text.to_owned(), (i, header._type)))
+                .collect()
+        };
+        let (old_columns, new_columns) = (columns(old), columns(new));
+        let (old_rows, new_rows) = (rows(old), rows(new));
+
+        let shared = new_columns
+            .iter()
+            .filter_map(|(column, (new_i, _))| {
+                let (old_i, _) = old_columns.get(column)?;
+                Some((column, *old_i, *new_i))
+            })
+            .collect::<Vec<_>>();
+
+        Self {
+            name: new.name.to_owned(),
+            added_columns: new_columns
+                .keys()
+                .filter(|column| !old_columns.contains_key(*column))
+                .cloned()
+                .collect(),
+            removed_columns: old_columns
+                .keys()
+                .filter(|column| !new_columns.contains_key(*column))
+                .cloned()
+                .collect(),
+            retyped_columns: new_columns
+                .iter()
+                .filter_map(|(column, (_, new_type))| {
+                    let (_, old_type) = old_columns.get(column)?;
+                    (old_type != new_type).then(|| Retyped {
+                        column: column.to_owned(),
+                        old: type_name(*old_type),
+                        new: type_name(*new_type),
+                    })
+                })
+                .collect(),
+            added_rows: new_rows
+                .keys()
+                .filter(|key| !old_rows.contains_key(*key))
+                .cloned()
+                .collect(),
+            removed_rows: old_rows
+                .keys()
+                .filter(|key| !new_rows.contains_key(*key))
+                .cloned()
+                .collect(),
+            changed_rows: new_rows
+                .iter()
+                .filter_map(|(key, new_row)| {
+                    let old_row = old_rows.get(key)?;
+                    let cells = shared
+                        .iter()
+                        .filter_map(|(column, old_i, new_i)| {
+                            let old = value(old_row.get(*old_i)?);
+                            let new = value(new_row.get(*new_i)?);
+                            (old != new).then(|| ChangedCell {
+                                column: column.to_string(),
+                                old,
+                                new,
+                            })
+                        })
+                        .collect::<Vec<_>>();
+                    (!cells.is_empty()).then(|| ChangedRow {
+                        key: key.to_owned(),
+                        cells,
+                    })
+                })
+                .collect(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.added_columns.is_empty()
+            && self.removed_columns.is_empty()
+            && self.retyped_columns.is_empty()
+            && self.added_rows.is_empty()
+            && self.removed_rows.is_empty()
+            && self.changed_rows.is_empty()
+    }
+}
+
+fn rows<'a>(sheet: &'a Datasheet) -> IndexMap<String, &'a DatasheetRow> {
+    let mut rows = IndexMap::with_capacity(sheet.rows.len());
+    for row in &sheet.rows {
+        let key = match row.first().map(value) {
+            Some(Value::String(key)) => key,
+            Some(key) => key.to_string(),
+            None => continue,
+        };
+        rows.entry(key).or_insert(row);
+    }
+    rows
+}
+
+fn value(cell: &DatasheetCell) -> Value {
+    match cell {
+        DatasheetCell::String(v) => Value::String(v.to_owned()),
+        DatasheetCell::Number(v) => Number::from_f64(*v).map_or(Value::Null, Value::Number),
+        DatasheetCell::Boolean(v) => Value::Bool(*v),
+    }
+}
+
+fn type_name(column_type: u32) -> &'static str {
+    match column_type {
+        1 => "string",
+        2 => "number",
+        3 => "boolean",
+        _ => "unknown",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ColumnType;
+
+    #[test]
+    fn matches_rows_by_key_and_cells_by_column() {
+        let string = |v: &str| DatasheetCell::String(v.into());
+        let old = Datasheet::from_columns(
+            "Items",
+            &[
+                ("ItemID", ColumnType::String),
+                ("Tier", ColumnType::Number),
+                ("Icon", ColumnType::String),
+            ],
+            vec![
+                vec![string("sword"), DatasheetCell::Number(2.0), string("a")],
+                vec![string("axe"), DatasheetCell::Number(3.0), string("b")],
+            ],
+        );
+        let new = Datasheet::from_columns(
+            "Items",
+            &[
+                ("ItemID", ColumnType::String),
+                ("Weight", ColumnType::Number),
+                ("Tier", ColumnType::String),
+            ],
+            vec![
+                vec![string("bow"), DatasheetCell::Number(1.0), string("1")],
+                vec![string("sword"), DatasheetCell::Number(1.0), string("5")],
+            ],
+        );
+
+        let diff = DatasheetDiff::between(&old, &new);
+        assert_eq!(diff.added_columns, ["Weight"]);
+        assert_eq!(diff.removed_columns, ["Icon"]);
+        assert_eq!(diff.retyped_columns[0].new, "string");
+        assert_eq!(diff.added_rows, ["bow"]);
+        assert_eq!(diff.removed_rows, ["axe"]);
+        assert_eq!(diff.changed_rows.len(), 1);
+        assert_eq!(diff.changed_rows[0].cells[0].column, "Tier");
+        assert_eq!(diff.changed_rows[0].cells[0].new, Value::String("5".into()));
+
+        assert!(DatasheetDiff::between(&old, &old).is_empty());
+    }
+}
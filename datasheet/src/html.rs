@@ -0,0 +1,113 @@
+//! A standalone HTML page of a datasheet, its columns sorted by clicking their header and its
+//! rows filtered by a search box, with no external scripts or styles.
+
+use crate::DatasheetCell;
+
+const STYLE: &str = r#"body{font-family:system-ui,sans-serif;margin:1rem}
+input{margin-bottom:.5rem;padding:.25rem;width:20rem}
+table{border-collapse:collapse;font-size:.875rem}
+th,td{border:1px solid #ccc;padding:.25rem .5rem;white-space:nowrap}
+th{background:#eee;cursor:pointer;position:sticky;top:0;user-select:none}
+th[data-dir=asc]::after{content:" \25B2"}
+th[data-dir=desc]::after{content:" \25BC"}
+td.n{text-align:right}
+tr:nth-child(even) td{background:#f8f8f8}"#;
+
+const SCRIPT: &str = r#"const table = document.querySelector("table");
+const body = table.tBodies[0];
+const rows = Array.from(body.rows);
+const count = document.getElementById("count");
+table.tHead.addEventListener("click", (event) => {
+  const th = event.target.closest("th");
+  if (!th) return;
+  const dir = th.dataset.dir === "asc" ? "desc" : "asc";
+  for (const other of th.parentNode.children) delete other.dataset.dir;
+  th.dataset.dir = dir;
+  const i = th.cellIndex;
+  const key = (row) => row.cells[i] ? row.cells[i].textContent : "";
+  const sign = dir === "asc" ? 1 : -1;
+  rows.sort((a, b) => {
+    const [x, y] = [key(a), key(b)];
+    const [nx, ny] = [Number(x), Number(y)];
+    if (x !== "" && y !== "" && !isNaN(nx) && !isNaN(ny)) return (nx - ny) * sign;
+    return x.localeCompare(y, undefined, { numeric: true }) * sign;
+  });
+  body.append(...rows);
+});
+document.getElementById("filter").addEventListener("input", (event) => {
+  const query = event.target.value.toLowerCase();
+  let shown = 0;
+  for (const row of rows) {
+    const match = row.textContent.toLowerCase().includes(query);
+    row.hidden = !match;
+    shown += match;
+  }
+  count.textContent = shown + " of " + rows.length + " rows";
+});
+count.textContent = rows.length + " rows";"#;
+
+/// A page titled `name` with a table of `header` and `rows`.
+pub fn page(name: &str, header: &[&str], rows: &[Vec<DatasheetCell>]) -> String {
+    let name = escape(name);
+    let mut html = format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{name}</title>\n\
+         <style>\n{STYLE}\n</style>\n</head>\n<body>\n<h1>{name}</h1>\n\
+         <input id=\"filter\" type=\"search\" placeholder=\"Filter rows\"> <span id=\"count\"></span>\n\
+         <table>\n<thead><tr>"
+    );
+    for text in header {
+        html.push_str(&format!("<th>{}</th>", escape(text)));
+    }
+    html.push_str("</tr></thead>\n<tbody>\n");
+    for row in rows {
+        html.push_str("<tr>");
+        for cell in row {
+            match cell {
+                DatasheetCell::String(value) => {
+                    html.push_str(&format!("<td>{}</td>", escape(value)))
+                }
+                DatasheetCell::Number(value) => {
+                    let value = match value.fract() == 0.0 {
+                        true => (*value as i64).to_string(),
+                        false => value.to_string(),
+                    };
+                    html.push_str(&format!("<td class=\"n\">{value}</td>"))
+                }
+                DatasheetCell::Boolean(value) => html.push_str(&format!("<td>{value}</td>")),
+            }
+        }
+        html.push_str("</tr>\n");
+    }
+    html.push_str(&format!(
+        "</tbody>\n</table>\n<script>\n{SCRIPT}\n</script>\n</body>\n</html>\n"
+    ));
+    html
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn escapes_cells() {
+        let rows = vec![vec![
+            DatasheetCell::String("<script>".into()),
+            DatasheetCell::Number(3.0),
+            DatasheetCell::Boolean(false),
+        ]];
+        let html = page("Items & More", &["ItemID", "Tier", "CanSalvage"], &rows);
+        assert!(html.contains("<title>Items &amp; More</title>"));
+        assert!(html.contains("<th>ItemID</th><th>Tier</th><th>CanSalvage</th>"));
+        assert!(
+            html.contains("<tr><td>&lt;script&gt;</td><td class=\"n\">3</td><td>false</td></tr>")
+        );
+        assert_eq!(html.matches("<script>").count(), 1);
+    }
+}
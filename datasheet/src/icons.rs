@@ -0,0 +1,87 @@
+use std::collections::BTreeMap;
+
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::{recipes::string, Datasheet};
+
+/// The icon an item shows, as referenced by its definition.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ItemIcon {
+    pub id: String,
+    /// The path as written in the `IconPath` column, usually of the source `.png`.
+    pub path: String,
+}
+
+/// The icon of every item among `datasheets` with one, sorted by item id. Items come from the
+/// item definition sheets, those with an `ItemID` or `HouseItemID` and an `IconPath` column; the
+/// first definition of an id wins.
+pub fn item_icons<'a, 'b: 'a, I>(datasheets: I) -> Vec<ItemIcon>
+where
+    I: IntoIterator<Item = &'a Datasheet<'b>>,
+{
+    let mut icons = BTreeMap::new();
+    for datasheet in datasheets {
+        let Value::Array(json) = datasheet.to_json() else {
+            continue;
+        };
+        for row in json {
+            let Value::Object(row) = row else {
+                continue;
+            };
+            let Some(id) = string(&row, "ItemID").or_else(|| string(&row, "HouseItemID")) else {
+                continue;
+            };
+            if let Some(path) = string(&row, "IconPath") {
+                icons
+                    .entry(id.to_owned())
+                    .or_insert_with(|| path.to_owned());
+            }
+        }
+    }
+    icons
+        .into_iter()
+        .map(|(id, path)| ItemIcon { id, path })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn icons_by_item_id() {
+        let items = Datasheet::from_strings(
+            "items",
+            &["ItemID", "IconPath"],
+            &[
+                [
+                    "1hSwordT5",
+                    "lyshineui/images/icons/items/weapon/1hswordt5.png",
+                ],
+                ["NoIcon", ""],
+                ["1hSwordT5", "lyshineui/images/icons/items/weapon/other.png"],
+            ],
+        );
+        let housing = Datasheet::from_strings(
+            "housingitems",
+            &["HouseItemID", "IconPath"],
+            &[["Chair", "lyshineui/images/icons/housing/chair.png"]],
+        );
+
+        let icons = item_icons([&items, &housing]);
+        assert_eq!(
+            icons,
+            [
+                ItemIcon {
+                    id: "1hSwordT5".into(),
+                    path: "lyshineui/images/icons/items/weapon/1hswordt5.png".into(),
+                },
+                ItemIcon {
+                    id: "Chair".into(),
+                    path: "lyshineui/images/icons/housing/chair.png".into(),
+                },
+            ]
+        );
+    }
+}
@@ -1,10 +1,27 @@
-use std::io::{self, Cursor, Read, Seek, SeekFrom};
+pub mod codegen;
+pub mod diff;
+pub mod html;
+pub mod icons;
+pub mod loot;
+pub mod recipes;
+pub mod resolve;
+pub mod sql;
+pub mod xlsx;
+
+use std::{
+    collections::HashSet,
+    io::{self, Cursor, Read, Seek, SeekFrom},
+    str::FromStr,
+};
 
 use dashmap::DashMap;
 use indexmap::IndexMap;
+use resolve::Resolver;
+use rusqlite::{params_from_iter, types::Value as SqlValue, Connection};
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Number, Value};
 use simd_json::OwnedValue;
+use sql::SqlDialect;
 
 const MAGIC: [u8; 4] = [0x11, 0x00, 0x00, 0x00];
 const VERSION: usize = 0x00;
@@ -59,6 +76,7 @@ pub struct Datasheet<'a> {
     header: Vec<HeaderCell>,
     rows: Vec<DatasheetRow>,
     localization: Option<&'a DashMap<String, Option<String>>>,
+    resolver: Option<&'a Resolver>,
 }
 
 #[derive(Debug, Clone)]
@@ -76,6 +94,152 @@ pub enum DatasheetCell {
     Boolean(bool),
 }
 
+/// A `column op value` predicate over datasheet rows, e.g. `Tier>=3`, `ItemType=Weapon` or
+/// `ItemID~sword`. `~` is a case insensitive substring match.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Predicate {
+    column: String,
+    op: Op,
+    value: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Op {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Contains,
+}
+
+impl FromStr for Predicate {
+    type Err = io::Error;
+
+    fn from_str(s: &str) -> io::Result<Self> {
+        const OPS: [(&str, Op); 8] = [
+            ("!=", Op::Ne),
+            ("<=", Op::Le),
+            (">=", Op::Ge),
+            ("==", Op::Eq),
+            ("=", Op::Eq),
+            ("<", Op::Lt),
+            (">", Op::Gt),
+            ("~", Op::Contains),
+        ];
+
+        let invalid = || {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("Invalid datasheet predicate: {}", s),
+            )
+        };
+
+        // the earliest operator wins, preferring two character ones at the same position
+        let (i, token, op) = OPS
+            .iter()
+            .filter_map(|(token, op)| s.find(token).map(|i| (i, *token, *op)))
+            .min_by_key(|(i, token, _)| (*i, std::cmp::Reverse(token.len())))
+            .ok_or_else(invalid)?;
+
+        let column = s[..i].trim();
+        if column.is_empty() {
+            return Err(invalid());
+        }
+
+        Ok(Self {
+            column: column.to_string(),
+            op,
+            value: s[i + token.len()..].trim().to_string(),
+        })
+    }
+}
+
+impl Predicate {
+    pub fn column(&self) -> &str {
+        &self.column
+    }
+
+    /// A `WHERE` condition with one parameter, matching the rows of a table written by
+    /// [`Datasheet::to_sqlite`] that [`Predicate::matches`] would. `boolean` when the column
+    /// holds booleans, stored as integers.
+    pub fn to_sqlite(&self, boolean: bool) -> (String, String) {
+        let column = format!("\"{}\"", self.column.replace('"', "\"\""));
+        let value = match self.value.parse::<bool>() {
+            Ok(value) if boolean => (value as u8).to_string(),
+            _ => self.value.to_owned(),
+        };
+        let op = match self.op {
+            Op::Eq => "=",
+            Op::Ne => "!=",
+            Op::Lt => "<",
+            Op::Le => "<=",
+            Op::Gt => ">",
+            Op::Ge => ">=",
+            Op::Contains => return (format!("instr(lower({column}), lower(?)) > 0"), value),
+        };
+        (format!("{column} {op} ?"), value)
+    }
+
+    fn matches(&self, cell: &DatasheetCell) -> bool {
+        let ordering = match cell {
+            DatasheetCell::String(v) => {
+                if self.op == Op::Contains {
+                    return v.to_lowercase().contains(&self.value.to_lowercase());
+                }
+                Some(v.as_str().cmp(&self.value))
+            }
+            DatasheetCell::Number(v) => self
+                .value
+                .parse::<f64>()
+                .ok()
+                .and_then(|value| v.partial_cmp(&value)),
+            DatasheetCell::Boolean(v) => self.value.parse::<bool>().ok().map(|value| v.cmp(&value)),
+        };
+
+        let Some(ordering) = ordering else {
+            return self.op == Op::Ne;
+        };
+
+        match self.op {
+            Op::Eq => ordering.is_eq(),
+            Op::Ne => ordering.is_ne(),
+            Op::Lt => ordering.is_lt(),
+            Op::Le => ordering.is_le(),
+            Op::Gt => ordering.is_gt(),
+            Op::Ge => ordering.is_ge(),
+            Op::Contains => false,
+        }
+    }
+}
+
+/// XML form of a datasheet, with one `Row` element per row holding an element per column.
+#[derive(Debug, Serialize)]
+#[serde(rename = "Datasheet")]
+pub struct XMLDatasheet {
+    #[serde(rename = "@name")]
+    name: String,
+    #[serde(rename = "@type")]
+    _type: String,
+    #[serde(rename = "Row")]
+    rows: Vec<Value>,
+}
+
+impl<'a> From<&Datasheet<'a>> for XMLDatasheet {
+    fn from(value: &Datasheet<'a>) -> Self {
+        let rows = match value.to_json() {
+            Value::Array(rows) => rows,
+            _ => vec![],
+        };
+        Self {
+            name: value.name.to_owned(),
+            _type: value._type.to_owned(),
+            rows,
+        }
+    }
+}
+
 #[derive(Default, Debug, Clone)]
 pub struct Metadata {
     crc32: u32,
@@ -87,6 +251,43 @@ impl<'a> Datasheet<'a> {
         self.localization = localization;
     }
 
+    pub fn with_resolver(&mut self, resolver: Option<&'a Resolver>) {
+        self.resolver = resolver;
+    }
+
+    fn column(&self, name: &str) -> Option<usize> {
+        self.header
+            .iter()
+            .position(|header| header.text.eq_ignore_ascii_case(name))
+    }
+
+    /// Keeps only the rows matching `predicate`. Sheets without its column are left untouched.
+    pub fn retain(&mut self, predicate: &Predicate) {
+        let Some(i) = self.column(&predicate.column) else {
+            return;
+        };
+        self.rows.retain(|row| predicate.matches(&row[i]));
+        self.row_count = self.rows.len();
+    }
+
+    /// Keeps only `columns`, in the given order. Sheets with none of them are left untouched.
+    pub fn project<S: AsRef<str>>(&mut self, columns: &[S]) {
+        let indices = columns
+            .iter()
+            .filter_map(|column| self.column(column.as_ref()))
+            .collect::<Vec<_>>();
+        if indices.is_empty() {
+            return;
+        }
+
+        self.header = indices.iter().map(|&i| self.header[i].clone()).collect();
+        self.rows = std::mem::take(&mut self.rows)
+            .into_iter()
+            .map(|row| indices.iter().map(|&i| row[i].clone()).collect())
+            .collect();
+        self.column_count = indices.len();
+    }
+
     pub fn meta(&self) -> Value {
         serde_json::json!({
             "type": self._type,
@@ -105,71 +306,269 @@ impl<'a> Datasheet<'a> {
         })
     }
 
+    /// JSON Schema of the rows emitted by [`Datasheet::to_json`], inferred from those rows so it
+    /// covers resolved references and localized columns. Every column is required, as empty cells
+    /// are written as empty strings, and other properties are allowed as resolving adds them.
+    pub fn schema(&self) -> Value {
+        let json = self.to_json();
+        let rows = json.as_array().map(Vec::as_slice).unwrap_or_default();
+
+        let mut properties = self
+            .header
+            .iter()
+            .map(|field| (field.text.as_str(), vec![]))
+            .collect::<IndexMap<_, Vec<&str>>>();
+        for (key, value) in rows.iter().filter_map(Value::as_object).flatten() {
+            let types = properties.entry(key.as_str()).or_default();
+            let _type = json_type(value);
+            if !types.contains(&_type) {
+                types.push(_type);
+            }
+        }
+        // columns of empty sheets fall back to their declared type
+        for field in &self.header {
+            let types = &mut properties[field.text.as_str()];
+            if types.is_empty() {
+                types.push(match field._type {
+                    1 => "string",
+                    2 => "number",
+                    _ => "boolean",
+                });
+            }
+        }
+
+        json!({
+            "$schema": "http://json-schema.org/draft-07/schema#",
+            "$id": self.name,
+            "title": self._type,
+            "type": "array",
+            "items": {
+                "type": "object",
+                "properties": properties.iter().map(|(key, types)| {
+                    let _type = match types.as_slice() {
+                        [_type] => json!(_type),
+                        // a column of both whole and fractional numbers
+                        _ if types.iter().all(|t| ["integer", "number"].contains(t)) => json!("number"),
+                        types => json!(types),
+                    };
+                    (key, json!({ "type": _type }))
+                }).collect::<IndexMap<_, _>>(),
+                "required": self.header.iter().map(|field| &field.text).collect::<Vec<_>>(),
+            },
+        })
+    }
+
     fn parse_localization(&self, key: String) -> String {
-        if !key.starts_with("@") {
-            return key;
+        match self.localization {
+            Some(map) => localize(map, key),
+            None => key,
         }
+    }
 
-        let Some(map) = self.localization else {
-            return key;
+    /// The column and key of every cell holding a localization key, the key lowercased and
+    /// without its `@` like the keys of the string tables.
+    pub fn localization_references(&self) -> impl Iterator<Item = (&str, String)> + '_ {
+        self.rows.iter().flat_map(move |row| {
+            row.iter()
+                .zip(&self.header)
+                .filter_map(|(cell, header)| match cell {
+                    DatasheetCell::String(v) if v.starts_with('@') => {
+                        Some((header.text.as_str(), v[1..].to_lowercase()))
+                    }
+                    _ => None,
+                })
+        })
+    }
+
+    /// Adds a `<Column>_<locale>` column per locale for every string column holding localization
+    /// keys, right after it when `adjacent` and after the existing columns otherwise. The keys
+    /// themselves are left in place.
+    pub fn localize_columns(
+        &mut self,
+        locales: &[(String, DashMap<String, Option<String>>)],
+        adjacent: bool,
+    ) {
+        let is_keys = |i: usize| {
+            self.header[i]._type == ColumnType::String as u32
+                && self
+                    .rows
+                    .iter()
+                    .any(|row| matches!(&row[i], DatasheetCell::String(v) if v.starts_with('@')))
         };
+        let keys = (0..self.header.len())
+            .filter(|&i| is_keys(i))
+            .collect::<Vec<_>>();
 
-        match map.get(&key.to_lowercase()[1..]) {
-            Some(v) => v.clone().unwrap_or(key),
-            None => key,
+        // the column each output column is taken from, with the locale it's localized to
+        let localized = |i: usize| (0..locales.len()).map(move |locale| (i, Some(locale)));
+        let columns = match adjacent {
+            true => (0..self.header.len())
+                .flat_map(|i| {
+                    let localized = keys.contains(&i).then(|| localized(i));
+                    std::iter::once((i, None)).chain(localized.into_iter().flatten())
+                })
+                .collect::<Vec<_>>(),
+            false => (0..self.header.len())
+                .map(|i| (i, None))
+                .chain(keys.iter().flat_map(|&i| localized(i)))
+                .collect(),
+        };
+
+        self.header = columns
+            .iter()
+            .map(|&(i, locale)| match locale {
+                Some(locale) => HeaderCell {
+                    text: format!("{}_{}", self.header[i].text, locales[locale].0),
+                    _type: ColumnType::String as u32,
+                },
+                None => self.header[i].clone(),
+            })
+            .collect();
+        for row in self.rows.iter_mut() {
+            *row = columns
+                .iter()
+                .map(|&(i, locale)| match (locale, &row[i]) {
+                    (Some(locale), DatasheetCell::String(key)) => {
+                        DatasheetCell::String(localize(&locales[locale].1, key.to_owned()))
+                    }
+                    (Some(_), _) => DatasheetCell::String(String::new()),
+                    (None, cell) => cell.clone(),
+                })
+                .collect();
         }
+        self.column_count = self.header.len();
     }
 
-    pub fn to_sql(&self) -> String {
-        let create = format!(
-            "CREATE TABLE '{}'(\n\t{}\n);\n",
-            self.name,
+    /// A `CREATE TABLE` and upserting `INSERT`s of `batch` rows each, or the dialect's default,
+    /// for `dialect`. The first column is the primary key.
+    pub fn to_sql(&self, dialect: SqlDialect, batch: Option<usize>) -> io::Result<String> {
+        let table = dialect.ident(&self.name);
+        let columns = self
+            .header
+            .iter()
+            .map(|header| dialect.ident(&header.text))
+            .collect::<Vec<_>>();
+
+        let mut sql = format!(
+            "CREATE TABLE IF NOT EXISTS {table}(\n\t{}\n);\n",
             self.header
                 .iter()
+                .zip(&columns)
                 .enumerate()
-                .map(|(i, header)| format!(
-                    "'{}' {}{}",
-                    header.text,
-                    match header._type {
-                        1 => "TEXT",
-                        2 => "REAL",
-                        3 => "INT",
-                        _ => unreachable!("type not supported"),
-                    },
-                    match i {
-                        0 => " PRIMARY KEY",
-                        _ => "",
-                    }
-                ))
-                .collect::<Vec<_>>()
+                .map(|(i, (header, column))| {
+                    let column_type = dialect
+                        .column_type(header._type, i == 0)
+                        .map_err(|e| io::Error::new(e.kind(), format!("{}: {e}", header.text)))?;
+                    Ok(format!(
+                        "{column} {column_type}{}",
+                        match i {
+                            0 => " PRIMARY KEY",
+                            _ => "",
+                        }
+                    ))
+                })
+                .collect::<io::Result<Vec<_>>>()?
                 .join(",\n\t")
         );
 
-        let insert = format!(
-            "INSERT INTO '{}' ({}) VALUES\n\t({});\n",
-            self.name,
-            self.header
-                .iter()
-                .map(|header| format!("'{}'", header.text.to_owned()))
-                .collect::<Vec<_>>()
-                .join(","),
-            self.rows
-                .iter()
-                .map(|row| row
-                    .iter()
-                    .map(|cell| match cell {
-                        DatasheetCell::String(v) =>
-                            format!("'{}'", self.parse_localization(v.to_owned())),
-                        DatasheetCell::Number(v) => v.to_owned().to_string(),
-                        DatasheetCell::Boolean(v) => (*v as u32).to_owned().to_string(),
-                    })
+        let upsert = dialect.upsert(&columns);
+        let batch = batch.unwrap_or(dialect.batch_size()).max(1);
+        for rows in self.rows.chunks(batch) {
+            sql.push_str(&format!(
+                "\nINSERT INTO {table} ({}) VALUES\n\t({})\n{upsert};\n",
+                columns.join(","),
+                rows.iter()
+                    .map(|row| row
+                        .iter()
+                        .map(|cell| match cell {
+                            DatasheetCell::String(v) =>
+                                dialect.string(&self.parse_localization(v.to_owned())),
+                            DatasheetCell::Number(v) if v.is_finite() => v.to_string(),
+                            DatasheetCell::Number(_) => "NULL".to_string(),
+                            DatasheetCell::Boolean(v) => dialect.boolean(*v).to_string(),
+                        })
+                        .collect::<Vec<_>>()
+                        .join(","))
                     .collect::<Vec<_>>()
-                    .join(","))
-                .collect::<Vec<_>>()
-                .join("),\n\t(")
-        );
+                    .join("),\n\t(")
+            ));
+        }
+        Ok(sql)
+    }
 
-        format!("{}\n\n{}", create, insert)
+    /// Writes the datasheet into `conn` as a table named after the sheet, replacing any previous
+    /// one, with typed columns and an index on every ID column. `written` holds the tables already
+    /// written by the same export, lowercased as SQLite compares them. A sheet sharing its name
+    /// with one of those is written as `<name>_2`, `<name>_3` and so on instead of replacing it.
+    /// The name is only taken once the table is committed. Returns the name of the table.
+    pub fn to_sqlite(
+        &self,
+        conn: &mut Connection,
+        written: &mut HashSet<String>,
+    ) -> rusqlite::Result<String> {
+        let quote = |ident: &str| format!("\"{}\"", ident.replace('"', "\"\""));
+        let columns = self
+            .header
+            .iter()
+            .map(|header| {
+                let column_type = match header._type {
+                    1 => "TEXT",
+                    2 => "REAL",
+                    3 => "INTEGER",
+                    _type => {
+                        return Err(rusqlite::Error::ToSqlConversionFailure(
+                            format!("{}: unsupported column type {_type}", header.text).into(),
+                        ))
+                    }
+                };
+                Ok(format!("{} {column_type}", quote(&header.text)))
+            })
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+
+        let name = (1..)
+            .map(|i| match i {
+                1 => self.name.to_owned(),
+                i => format!("{}_{i}", self.name),
+            })
+            .find(|name| !written.contains(&name.to_lowercase()))
+            .unwrap_or_default();
+        let table = quote(&name);
+
+        let tx = conn.transaction()?;
+        tx.execute_batch(&format!(
+            "DROP TABLE IF EXISTS {table};\nCREATE TABLE {table}(\n\t{}\n);",
+            columns.join(",\n\t")
+        ))?;
+
+        {
+            let mut insert = tx.prepare(&format!(
+                "INSERT INTO {table} VALUES ({})",
+                vec!["?"; self.header.len()].join(",")
+            ))?;
+            for row in &self.rows {
+                insert.execute(params_from_iter(row.iter().map(|cell| match cell {
+                    DatasheetCell::String(v) => SqlValue::Text(self.parse_localization(v.into())),
+                    DatasheetCell::Number(v) => SqlValue::Real(*v),
+                    DatasheetCell::Boolean(v) => SqlValue::Integer(*v as i64),
+                })))?;
+            }
+        }
+
+        for header in self
+            .header
+            .iter()
+            .filter(|header| header.text.ends_with("ID") || header.text.ends_with("Id"))
+        {
+            tx.execute_batch(&format!(
+                "CREATE INDEX {} ON {table}({});",
+                quote(&format!("{}_{}", name, header.text)),
+                quote(&header.text)
+            ))?;
+        }
+
+        tx.commit()?;
+        written.insert(name.to_lowercase());
+        Ok(name)
     }
 
     pub fn json_value(&self) -> OwnedValue {
@@ -201,7 +600,7 @@ impl<'a> Datasheet<'a> {
     }
 
     pub fn to_json(&self) -> Value {
-        json!(self
+        let mut json = json!(self
             .rows
             .iter()
             .map(|row| {
@@ -225,7 +624,12 @@ impl<'a> Datasheet<'a> {
                     })
                     .collect::<IndexMap<_, _>>()
             })
-            .collect::<Vec<_>>())
+            .collect::<Vec<_>>());
+
+        if let Some(resolver) = self.resolver {
+            resolver.resolve(self, &mut json);
+        }
+        json
     }
 
     pub fn to_json_simd(&self, pretty: bool) -> Result<String, simd_json::Error> {
@@ -300,6 +704,40 @@ impl<'a> Datasheet<'a> {
         csv
     }
 
+    /// The datasheet as an Excel workbook with a sheet named after it, localized like CSV.
+    pub fn to_xlsx(&self) -> io::Result<Vec<u8>> {
+        xlsx::workbook(&self.name, &self.header_texts(), &self.localized_rows())
+    }
+
+    /// The datasheet as a standalone HTML page with sortable columns, localized like CSV.
+    pub fn to_html(&self) -> String {
+        html::page(&self.name, &self.header_texts(), &self.localized_rows())
+    }
+
+    fn header_texts(&self) -> Vec<&str> {
+        self.header
+            .iter()
+            .map(|header| header.text.as_str())
+            .collect()
+    }
+
+    /// The rows with their localization keys replaced by the text.
+    fn localized_rows(&self) -> Vec<DatasheetRow> {
+        self.rows
+            .iter()
+            .map(|row| {
+                row.iter()
+                    .map(|cell| match cell {
+                        DatasheetCell::String(value) => {
+                            DatasheetCell::String(self.parse_localization(value.into()))
+                        }
+                        cell => cell.clone(),
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
     pub fn to_yaml(&self) -> String {
         let mut rows = Vec::new();
         for row in &self.rows {
@@ -333,6 +771,59 @@ impl<'a> Datasheet<'a> {
     }
 }
 
+#[cfg(test)]
+impl Datasheet<'static> {
+    /// A datasheet named `name`, of the type of the same name, with a column of each
+    /// `(text, type)` of `columns`.
+    pub(crate) fn from_columns(
+        name: &str,
+        columns: &[(&str, ColumnType)],
+        rows: Vec<DatasheetRow>,
+    ) -> Self {
+        Self {
+            version: 0,
+            name: name.into(),
+            _type: name.into(),
+            column_count: columns.len(),
+            row_count: rows.len(),
+            header: columns
+                .iter()
+                .map(|(text, _type)| HeaderCell {
+                    text: text.to_string(),
+                    _type: *_type as u32,
+                })
+                .collect(),
+            rows,
+            localization: None,
+            resolver: None,
+        }
+    }
+
+    /// [`Datasheet::from_columns`] with only string columns, each row given as the text of its
+    /// cells.
+    pub(crate) fn from_strings<R: AsRef<[&'static str]>>(
+        name: &str,
+        columns: &[&str],
+        rows: &[R],
+    ) -> Self {
+        Self::from_columns(
+            name,
+            &columns
+                .iter()
+                .map(|text| (*text, ColumnType::String))
+                .collect::<Vec<_>>(),
+            rows.iter()
+                .map(|row| {
+                    row.as_ref()
+                        .iter()
+                        .map(|cell| DatasheetCell::String(cell.to_string()))
+                        .collect()
+                })
+                .collect(),
+        )
+    }
+}
+
 impl<'a> TryFrom<Vec<u8>> for Datasheet<'a> {
     fn try_from(value: Vec<u8>) -> io::Result<Self> {
         let mut data = Cursor::new(value);
@@ -453,6 +944,7 @@ impl<'a> TryFrom<Vec<u8>> for Datasheet<'a> {
             name,
             _type,
             localization: None,
+            resolver: None,
         })
     }
 
@@ -580,9 +1072,21 @@ fn from_reader<R: Read>(data: &mut R) -> io::Result<Datasheet> {
         name,
         _type,
         localization: None,
+        resolver: None,
     })
 }
 
+fn localize(map: &DashMap<String, Option<String>>, key: String) -> String {
+    if !key.starts_with("@") {
+        return key;
+    }
+
+    match map.get(&key.to_lowercase()[1..]) {
+        Some(v) => v.clone().unwrap_or(key),
+        None => key,
+    }
+}
+
 fn read_string<R: Read + Seek>(data: &mut R) -> io::Result<String> {
     let mut string = vec![];
     let mut buf = [0u8; 1];
@@ -596,3 +1100,217 @@ fn read_string<R: Read + Seek>(data: &mut R) -> io::Result<String> {
     }
     String::from_utf8(string).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
 }
+
+/// The JSON Schema type of `value`.
+fn json_type(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(n) if n.is_f64() => "number",
+        Value::Number(_) => "integer",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_predicates() {
+        let predicate: Predicate = "Tier >= 3".parse().unwrap();
+        assert_eq!(predicate.column, "Tier");
+        assert_eq!(predicate.op, Op::Ge);
+        assert_eq!(predicate.value, "3");
+
+        let predicate: Predicate = "ItemID~sword".parse().unwrap();
+        assert_eq!(predicate.op, Op::Contains);
+        assert!(predicate.matches(&DatasheetCell::String("1hSword_T5".into())));
+
+        assert!("Tier".parse::<Predicate>().is_err());
+        assert!("=3".parse::<Predicate>().is_err());
+    }
+
+    #[test]
+    fn writes_sql_for_each_dialect() {
+        let datasheet = Datasheet::from_columns(
+            "Items",
+            &[
+                ("ItemID", ColumnType::String),
+                ("CanSalvage", ColumnType::Boolean),
+            ],
+            vec![
+                vec![
+                    DatasheetCell::String("it's".into()),
+                    DatasheetCell::Boolean(true),
+                ],
+                vec![
+                    DatasheetCell::String("b".into()),
+                    DatasheetCell::Boolean(false),
+                ],
+                vec![
+                    DatasheetCell::String("c".into()),
+                    DatasheetCell::Boolean(false),
+                ],
+            ],
+        );
+
+        let sql = datasheet.to_sql(SqlDialect::Postgres, Some(2)).unwrap();
+        assert!(sql.contains("\"ItemID\" TEXT PRIMARY KEY"));
+        assert!(sql.contains("('it''s',TRUE)"));
+        assert!(sql.contains("ON CONFLICT (\"ItemID\") DO UPDATE SET"));
+        assert_eq!(sql.matches("INSERT INTO").count(), 2);
+
+        let sql = datasheet.to_sql(SqlDialect::Mysql, None).unwrap();
+        assert!(sql.contains("`ItemID` VARCHAR(255) PRIMARY KEY"));
+        assert!(sql.contains("`CanSalvage` = VALUES(`CanSalvage`)"));
+        assert_eq!(sql.matches("INSERT INTO").count(), 1);
+    }
+
+    #[test]
+    fn suffixes_sqlite_tables_sharing_a_name() {
+        let datasheet = Datasheet::from_strings("Items", &["ItemID"], &[["a"]]);
+
+        let mut conn = Connection::open_in_memory().unwrap();
+        let mut written = HashSet::new();
+        assert_eq!(
+            datasheet.to_sqlite(&mut conn, &mut written).unwrap(),
+            "Items"
+        );
+        assert_eq!(
+            datasheet.to_sqlite(&mut conn, &mut written).unwrap(),
+            "Items_2"
+        );
+        let count = |table: &str| {
+            conn.query_row(&format!("SELECT COUNT(*) FROM {table}"), [], |row| {
+                row.get::<_, i64>(0)
+            })
+            .unwrap()
+        };
+        assert_eq!(count("Items"), 1);
+        assert_eq!(count("Items_2"), 1);
+
+        let mut unsupported = datasheet;
+        unsupported.header[0]._type = 9;
+        assert!(unsupported.to_sql(SqlDialect::Sqlite, None).is_err());
+        assert!(unsupported.to_sqlite(&mut conn, &mut written).is_err());
+
+        let mut duplicate = unsupported;
+        duplicate.name = "Perks".into();
+        duplicate.header[0]._type = ColumnType::String as u32;
+        duplicate.header.push(duplicate.header[0].clone());
+        assert!(duplicate.to_sqlite(&mut conn, &mut written).is_err());
+        duplicate.header.pop();
+        assert_eq!(
+            duplicate.to_sqlite(&mut conn, &mut written).unwrap(),
+            "Perks"
+        );
+    }
+
+    #[test]
+    fn schemas_require_every_column() {
+        let datasheet = Datasheet::from_columns(
+            "Items",
+            &[
+                ("ItemID", ColumnType::String),
+                ("Weight", ColumnType::Number),
+            ],
+            vec![
+                vec![DatasheetCell::String("".into()), DatasheetCell::Number(1.0)],
+                vec![
+                    DatasheetCell::String("b".into()),
+                    DatasheetCell::Number(0.5),
+                ],
+            ],
+        );
+
+        let schema = datasheet.schema();
+        let items = &schema["items"];
+        assert_eq!(items["required"], json!(["ItemID", "Weight"]));
+        assert_eq!(items["properties"]["ItemID"]["type"], "string");
+        assert_eq!(items["properties"]["Weight"]["type"], "number");
+        assert!(items.get("additionalProperties").is_none());
+    }
+
+    #[test]
+    fn localizes_columns_next_to_keys() {
+        let datasheet = Datasheet::from_strings(
+            "Items",
+            &["Name", "ItemID", "Description"],
+            &[
+                ["@sword_name", "sword", "@sword_desc"],
+                ["@missing", "shield", ""],
+            ],
+        );
+        let map = DashMap::new();
+        map.insert("sword_name".to_string(), Some("Sword".to_string()));
+        map.insert("sword_desc".to_string(), Some("Sharp".to_string()));
+        let locales = [("en-us".to_string(), map)];
+        let texts = |datasheet: &Datasheet| {
+            datasheet
+                .header
+                .iter()
+                .map(|header| header.text.to_owned())
+                .collect::<Vec<_>>()
+        };
+
+        let mut adjacent = datasheet.clone();
+        adjacent.localize_columns(&locales, true);
+        assert_eq!(
+            texts(&adjacent),
+            [
+                "Name",
+                "Name_en-us",
+                "ItemID",
+                "Description",
+                "Description_en-us"
+            ]
+        );
+        assert!(matches!(&adjacent.rows[0][1], DatasheetCell::String(v) if v == "Sword"));
+        assert!(matches!(&adjacent.rows[0][0], DatasheetCell::String(v) if v == "@sword_name"));
+        assert!(matches!(&adjacent.rows[1][1], DatasheetCell::String(v) if v == "@missing"));
+        assert_eq!(adjacent.column_count, 5);
+
+        let mut appended = datasheet;
+        appended.localize_columns(&locales, false);
+        assert_eq!(
+            texts(&appended),
+            [
+                "Name",
+                "ItemID",
+                "Description",
+                "Name_en-us",
+                "Description_en-us"
+            ]
+        );
+        assert!(matches!(&appended.rows[0][4], DatasheetCell::String(v) if v == "Sharp"));
+    }
+
+    #[test]
+    fn compares_cells() {
+        let predicate: Predicate = "Tier>=3".parse().unwrap();
+        assert!(predicate.matches(&DatasheetCell::Number(5.0)));
+        assert!(!predicate.matches(&DatasheetCell::Number(2.0)));
+
+        let predicate: Predicate = "IsSalvageable!=true".parse().unwrap();
+        assert!(predicate.matches(&DatasheetCell::Boolean(false)));
+    }
+
+    #[test]
+    fn writes_sqlite_conditions() {
+        let predicate: Predicate = "IsSalvageable!=true".parse().unwrap();
+        assert_eq!(
+            predicate.to_sqlite(true),
+            ("\"IsSalvageable\" != ?".to_string(), "1".to_string())
+        );
+        assert_eq!(predicate.to_sqlite(false).1, "true");
+
+        let predicate: Predicate = "ItemID~sword".parse().unwrap();
+        assert_eq!(
+            predicate.to_sqlite(false).0,
+            "instr(lower(\"ItemID\"), lower(?)) > 0"
+        );
+    }
+}
@@ -0,0 +1,321 @@
+use std::collections::HashMap;
+
+use serde::Serialize;
+use serde_json::{Map, Value};
+
+use crate::{
+    recipes::{string, Item},
+    Datasheet,
+};
+
+/// Prefix of loot table cells dropping from another loot table.
+const TABLE: &str = "[LTID]";
+/// Prefix of loot table cells dropping from a loot bucket.
+const BUCKET: &str = "[LBID]";
+
+/// A loot table with its drops resolved: items to their definitions, buckets to their items and
+/// nested tables to their own resolved trees.
+#[derive(Debug, Serialize)]
+pub struct LootTable {
+    pub id: String,
+    /// `AND` when every drop is rolled, `OR` when one is picked.
+    pub and_or: Option<String>,
+    pub conditions: Vec<String>,
+    pub max_roll: Option<f64>,
+    pub entries: Vec<LootEntry>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct LootEntry {
+    /// The roll needed for the drop, from the `_Probs` row of the table.
+    pub probability: Option<f64>,
+    /// Quantity or range like `1-3`, from the `_Qty` row of the table.
+    pub quantity: Option<String>,
+    #[serde(flatten)]
+    pub drop: LootDrop,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum LootDrop {
+    Item {
+        item: Item,
+    },
+    /// `None` when the table doesn't exist or contains itself.
+    Table {
+        id: String,
+        table: Option<Box<LootTable>>,
+    },
+    Bucket {
+        id: String,
+        items: Vec<BucketItem>,
+    },
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BucketItem {
+    pub item: Item,
+    pub quantity: Option<String>,
+    pub tags: Vec<String>,
+}
+
+/// A row of a loot table with the matching cells of its `_Probs` and `_Qty` rows.
+#[derive(Default)]
+struct Rows {
+    table: Option<Map<String, Value>>,
+    probs: Option<Map<String, Value>>,
+    qty: Option<Map<String, Value>>,
+}
+
+/// The text of a cell written as a string or a number.
+fn text(row: &Map<String, Value>, column: &str) -> Option<String> {
+    match row.get(column)? {
+        Value::String(value) if !value.is_empty() => Some(value.to_owned()),
+        Value::Number(value) => Some(value.to_string()),
+        _ => None,
+    }
+}
+
+fn list(row: &Map<String, Value>, column: &str) -> Vec<String> {
+    string(row, column)
+        .unwrap_or_default()
+        .split(',')
+        .map(str::trim)
+        .filter(|value| !value.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// `Item<N>` style columns of `row` in order, with their number.
+fn numbered<'a>(
+    row: &'a Map<String, Value>,
+    prefix: &'a str,
+) -> impl Iterator<Item = (usize, &'a str)> + 'a {
+    let mut columns = row
+        .keys()
+        .filter_map(|column| {
+            let n = column.strip_prefix(prefix)?.parse::<usize>().ok()?;
+            Some((n, column.as_str()))
+        })
+        .collect::<Vec<_>>();
+    columns.sort_unstable();
+    columns.into_iter()
+}
+
+/// Resolves every loot table among `datasheets`, sorted by id. Loot tables are the rows of sheets
+/// with a `LootTableID` and `MaxRoll` column, loot buckets the sheets with a `RowPlaceholders`
+/// column whose `FIRSTROW` names the bucket of each `LootBucket<N>` column, and items come from
+/// the item definition sheets.
+pub fn loot_tables<'a, 'b: 'a, I>(datasheets: I) -> Vec<LootTable>
+where
+    I: IntoIterator<Item = &'a Datasheet<'b>>,
+{
+    let mut tables: HashMap<String, Rows> = HashMap::new();
+    let mut bucket_rows = vec![];
+    let mut items = HashMap::new();
+
+    for datasheet in datasheets {
+        let Value::Array(json) = datasheet.to_json() else {
+            continue;
+        };
+        let rows = json.into_iter().filter_map(|row| match row {
+            Value::Object(row) => Some(row),
+            _ => None,
+        });
+
+        let mut names = HashMap::new();
+        for row in rows {
+            if let Some(id) = string(&row, "LootTableID").filter(|_| row.contains_key("MaxRoll")) {
+                let id = id.to_lowercase();
+                if let Some(id) = id.strip_suffix("_probs") {
+                    tables.entry(id.to_owned()).or_default().probs = Some(row);
+                } else if let Some(id) = id.strip_suffix("_qty") {
+                    tables.entry(id.to_owned()).or_default().qty = Some(row);
+                } else {
+                    tables.entry(id).or_default().table = Some(row);
+                }
+            } else if row.contains_key("RowPlaceholders") {
+                if string(&row, "RowPlaceholders") == Some("FIRSTROW") {
+                    names = numbered(&row, "LootBucket")
+                        .filter_map(|(n, column)| Some((n, string(&row, column)?.to_owned())))
+                        .collect();
+                } else {
+                    bucket_rows.push((names.clone(), row));
+                }
+            } else if let Some(item) = Item::from_row(&row) {
+                items.insert(item.id.to_lowercase(), item);
+            }
+        }
+    }
+
+    let mut buckets: HashMap<String, Vec<BucketItem>> = HashMap::new();
+    for (names, row) in &bucket_rows {
+        for (n, column) in numbered(row, "Item") {
+            let (Some(name), Some(id)) = (names.get(&n), string(row, column)) else {
+                continue;
+            };
+            buckets
+                .entry(name.to_lowercase())
+                .or_default()
+                .push(BucketItem {
+                    item: Item::find(&items, id),
+                    quantity: text(row, &format!("Quantity{n}")),
+                    tags: list(row, &format!("Tags{n}")),
+                });
+        }
+    }
+
+    let resolver = Resolver {
+        tables: &tables,
+        buckets: &buckets,
+        items: &items,
+    };
+    let mut resolved = tables
+        .iter()
+        .filter(|(_, rows)| rows.table.is_some())
+        .filter_map(|(id, _)| resolver.table(id, &mut vec![]))
+        .collect::<Vec<_>>();
+    resolved.sort_by(|a, b| a.id.cmp(&b.id));
+    resolved
+}
+
+struct Resolver<'a> {
+    tables: &'a HashMap<String, Rows>,
+    buckets: &'a HashMap<String, Vec<BucketItem>>,
+    items: &'a HashMap<String, Item>,
+}
+
+impl Resolver<'_> {
+    /// The table `id` with its drops resolved, `None` when it's missing or already in `stack`,
+    /// the tables being resolved above it.
+    fn table(&self, id: &str, stack: &mut Vec<String>) -> Option<LootTable> {
+        let key = id.to_lowercase();
+        if stack.contains(&key) {
+            return None;
+        }
+        let rows = self.tables.get(&key)?;
+        let row = rows.table.as_ref()?;
+
+        stack.push(key);
+        let entries = numbered(row, "Item")
+            .filter_map(|(_, column)| {
+                let cell = string(row, column)?;
+                let drop = if let Some(id) = cell.strip_prefix(TABLE) {
+                    LootDrop::Table {
+                        id: id.to_owned(),
+                        table: self.table(id, stack).map(Box::new),
+                    }
+                } else if let Some(id) = cell.strip_prefix(BUCKET) {
+                    LootDrop::Bucket {
+                        id: id.to_owned(),
+                        items: self
+                            .buckets
+                            .get(&id.to_lowercase())
+                            .cloned()
+                            .unwrap_or_default(),
+                    }
+                } else {
+                    LootDrop::Item {
+                        item: Item::find(self.items, cell),
+                    }
+                };
+                Some(LootEntry {
+                    probability: rows
+                        .probs
+                        .as_ref()
+                        .and_then(|probs| text(probs, column))
+                        .and_then(|prob| prob.parse().ok()),
+                    quantity: rows.qty.as_ref().and_then(|qty| text(qty, column)),
+                    drop,
+                })
+            })
+            .collect();
+        stack.pop();
+
+        Some(LootTable {
+            id: string(row, "LootTableID").unwrap_or(id).to_owned(),
+            and_or: string(row, "AND/OR").map(str::to_string),
+            conditions: list(row, "Conditions"),
+            max_roll: text(row, "MaxRoll").and_then(|max| max.parse().ok()),
+            entries,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resolves_nested_tables_and_buckets() {
+        let tables = Datasheet::from_strings(
+            "loottable",
+            &[
+                "LootTableID",
+                "AND/OR",
+                "Conditions",
+                "MaxRoll",
+                "Item1",
+                "Item2",
+            ],
+            &[
+                [
+                    "Chest",
+                    "AND",
+                    "Level, Enemy",
+                    "100000",
+                    "[LTID]Gems",
+                    "[LBID]Ores",
+                ],
+                ["Chest_Probs", "", "", "", "0", "50000"],
+                ["Gems", "OR", "", "0", "GemT1", "[LTID]Chest"],
+                ["Gems_Qty", "", "", "", "1-2", "1"],
+            ],
+        );
+        let buckets = Datasheet::from_strings(
+            "lootbuckets",
+            &[
+                "RowPlaceholders",
+                "LootBucket1",
+                "Item1",
+                "Quantity1",
+                "Tags1",
+            ],
+            &[
+                ["FIRSTROW", "Ores", "", "", ""],
+                ["", "", "OreT1", "3", "Mining"],
+            ],
+        );
+        let items = Datasheet::from_strings("items", &["ItemID", "Name"], &[["GemT1", "@gem_t1"]]);
+
+        let tables = loot_tables([&tables, &buckets, &items]);
+        assert_eq!(tables.len(), 2);
+        let chest = &tables[0];
+        assert_eq!(chest.id, "Chest");
+        assert_eq!(chest.conditions, ["Level", "Enemy"]);
+        assert_eq!(chest.entries[1].probability, Some(50000.0));
+
+        let LootDrop::Table {
+            table: Some(gems), ..
+        } = &chest.entries[0].drop
+        else {
+            panic!("expected the gems table");
+        };
+        let LootDrop::Item { item } = &gems.entries[0].drop else {
+            panic!("expected an item");
+        };
+        assert_eq!(item.name.as_deref(), Some("@gem_t1"));
+        assert_eq!(gems.entries[0].quantity.as_deref(), Some("1-2"));
+        // the chest drops from itself through the gems table
+        assert!(matches!(
+            &gems.entries[1].drop,
+            LootDrop::Table { table: None, .. }
+        ));
+
+        let LootDrop::Bucket { items, .. } = &chest.entries[1].drop else {
+            panic!("expected the ores bucket");
+        };
+        assert_eq!(items[0].item.id, "OreT1");
+        assert_eq!(items[0].tags, ["Mining"]);
+    }
+}
@@ -0,0 +1,271 @@
+use std::collections::HashMap;
+
+use serde::Serialize;
+use serde_json::{Map, Value};
+
+use crate::Datasheet;
+
+/// Most ingredients a recipe lists, in its `Ingredient<N>`, `Type<N>` and `Qty<N>` columns.
+const INGREDIENTS: usize = 7;
+
+/// A crafting recipe with its output and ingredients resolved to items.
+#[derive(Debug, Serialize)]
+pub struct Recipe {
+    pub id: String,
+    pub tradeskill: Option<String>,
+    pub level: Option<f64>,
+    pub category: Option<String>,
+    /// `None` for recipes whose output is rolled, like procedural gear.
+    pub output: Option<Item>,
+    pub quantity: f64,
+    pub ingredients: Vec<Ingredient>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Item {
+    pub id: String,
+    /// The localized name, or its key when the datasheets weren't localized.
+    pub name: Option<String>,
+    pub tier: Option<f64>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IngredientKind {
+    Item,
+    /// Any item of an ingredient category, e.g. any wood.
+    Category,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Ingredient {
+    pub kind: IngredientKind,
+    pub id: String,
+    pub name: Option<String>,
+    pub quantity: f64,
+    /// Items of a category ingredient, sorted by id.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub items: Vec<Item>,
+}
+
+impl Item {
+    /// The item defined by a row of an item definition sheet, one with an `ItemID` or
+    /// `HouseItemID` and a `Name` column.
+    pub(crate) fn from_row(row: &Map<String, Value>) -> Option<Self> {
+        let id = string(row, "ItemID")
+            .or_else(|| string(row, "HouseItemID"))
+            .filter(|_| row.contains_key("Name"))?;
+        Some(Self {
+            id: id.to_owned(),
+            name: string(row, "Name").map(str::to_string),
+            tier: row.get("Tier").and_then(Value::as_f64),
+        })
+    }
+
+    /// The item `id` in `items`, keyed by lowercase id, or one with only the id when missing.
+    pub(crate) fn find(items: &HashMap<String, Item>, id: &str) -> Self {
+        items.get(&id.to_lowercase()).cloned().unwrap_or(Self {
+            id: id.to_owned(),
+            name: None,
+            tier: None,
+        })
+    }
+}
+
+pub(crate) fn string<'a>(row: &'a Map<String, Value>, column: &str) -> Option<&'a str> {
+    row.get(column)
+        .and_then(Value::as_str)
+        .filter(|value| !value.is_empty())
+}
+
+/// Joins the crafting recipes among `datasheets` with the item definitions and ingredient
+/// categories, sorted by recipe id. Sheets are recognized by their columns: recipes by `RecipeID`
+/// and `Ingredient1`, items by `ItemID` or `HouseItemID` and `Name`, categories by `CategoryID`
+/// and `DisplayText`. Items are matched to categories through their `IngredientCategories`.
+pub fn recipes<'a, 'b: 'a, I>(datasheets: I) -> Vec<Recipe>
+where
+    I: IntoIterator<Item = &'a Datasheet<'b>>,
+{
+    let mut rows = vec![];
+    let mut items = HashMap::new();
+    let mut categories = HashMap::new();
+    let mut category_items: HashMap<String, Vec<Item>> = HashMap::new();
+
+    for datasheet in datasheets {
+        let Value::Array(json) = datasheet.to_json() else {
+            continue;
+        };
+        for row in json {
+            let Value::Object(row) = row else {
+                continue;
+            };
+
+            if row.contains_key("RecipeID") && row.contains_key("Ingredient1") {
+                rows.push(row);
+            } else if let Some(item) = Item::from_row(&row) {
+                for category in string(&row, "IngredientCategories")
+                    .unwrap_or_default()
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|category| !category.is_empty())
+                {
+                    category_items
+                        .entry(category.to_lowercase())
+                        .or_default()
+                        .push(item.clone());
+                }
+                items.insert(item.id.to_lowercase(), item);
+            } else if let (Some(id), Some(name)) =
+                (string(&row, "CategoryID"), string(&row, "DisplayText"))
+            {
+                categories.insert(id.to_lowercase(), name.to_owned());
+            }
+        }
+    }
+    for items in category_items.values_mut() {
+        items.sort_by(|a, b| a.id.cmp(&b.id));
+    }
+
+    let mut recipes = rows
+        .iter()
+        .filter_map(|row| {
+            let id = string(row, "RecipeID")?;
+            let ingredients = (1..=INGREDIENTS)
+                .filter_map(|i| {
+                    let id = string(row, &format!("Ingredient{i}"))?;
+                    let quantity = row
+                        .get(&format!("Qty{i}"))
+                        .and_then(Value::as_f64)
+                        .unwrap_or(1.0);
+                    let key = id.to_lowercase();
+                    Some(match string(row, &format!("Type{i}")) {
+                        Some(kind) if kind.starts_with("Category") => Ingredient {
+                            kind: IngredientKind::Category,
+                            id: id.to_owned(),
+                            name: categories.get(&key).cloned(),
+                            quantity,
+                            items: category_items.get(&key).cloned().unwrap_or_default(),
+                        },
+                        _ => Ingredient {
+                            kind: IngredientKind::Item,
+                            id: id.to_owned(),
+                            name: items.get(&key).and_then(|item| item.name.clone()),
+                            quantity,
+                            items: vec![],
+                        },
+                    })
+                })
+                .collect();
+
+            Some(Recipe {
+                id: id.to_owned(),
+                tradeskill: string(row, "Tradeskill").map(str::to_string),
+                level: row.get("RecipeLevel").and_then(Value::as_f64),
+                category: string(row, "CraftingCategory").map(str::to_string),
+                output: string(row, "ItemID").map(|output| Item::find(&items, output)),
+                quantity: row.get("OutputQty").and_then(Value::as_f64).unwrap_or(1.0),
+                ingredients,
+            })
+        })
+        .collect::<Vec<_>>();
+    recipes.sort_by(|a, b| a.id.cmp(&b.id));
+    recipes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ColumnType, DatasheetCell};
+
+    fn string(value: &str) -> DatasheetCell {
+        DatasheetCell::String(value.into())
+    }
+
+    #[test]
+    fn resolves_items_and_categories() {
+        let items = Datasheet::from_columns(
+            "items",
+            &[
+                ("ItemID", ColumnType::String),
+                ("Name", ColumnType::String),
+                ("Tier", ColumnType::Number),
+                ("IngredientCategories", ColumnType::String),
+            ],
+            vec![
+                vec![
+                    string("IngotT2"),
+                    string("@ingot_t2"),
+                    DatasheetCell::Number(2.0),
+                    string(""),
+                ],
+                vec![
+                    string("WoodT1"),
+                    string("@wood_t1"),
+                    DatasheetCell::Number(1.0),
+                    string("Wood,Fuel"),
+                ],
+                vec![
+                    string("Hatchet"),
+                    string("@hatchet"),
+                    DatasheetCell::Number(2.0),
+                    string(""),
+                ],
+            ],
+        );
+        let categories = Datasheet::from_columns(
+            "categories",
+            &[
+                ("CategoryID", ColumnType::String),
+                ("DisplayText", ColumnType::String),
+            ],
+            vec![vec![string("Wood"), string("@category_wood")]],
+        );
+        let crafting = Datasheet::from_columns(
+            "crafting",
+            &[
+                ("RecipeID", ColumnType::String),
+                ("ItemID", ColumnType::String),
+                ("OutputQty", ColumnType::Number),
+                ("Ingredient1", ColumnType::String),
+                ("Type1", ColumnType::String),
+                ("Qty1", ColumnType::Number),
+                ("Ingredient2", ColumnType::String),
+                ("Type2", ColumnType::String),
+                ("Qty2", ColumnType::Number),
+            ],
+            vec![vec![
+                string("hatchet"),
+                string("hatchet"),
+                DatasheetCell::Number(1.0),
+                string("ingott2"),
+                string("Item"),
+                DatasheetCell::Number(4.0),
+                string("Wood"),
+                string("Category_Only"),
+                DatasheetCell::Number(2.0),
+            ]],
+        );
+
+        let recipes = recipes([&items, &categories, &crafting]);
+        assert_eq!(recipes.len(), 1);
+        let recipe = &recipes[0];
+        assert_eq!(recipe.output.as_ref().unwrap().id, "Hatchet");
+        assert_eq!(
+            recipe.output.as_ref().unwrap().name.as_deref(),
+            Some("@hatchet")
+        );
+
+        let [ingot, wood] = &recipe.ingredients[..] else {
+            panic!("expected two ingredients");
+        };
+        assert_eq!(ingot.kind, IngredientKind::Item);
+        assert_eq!(
+            (ingot.name.as_deref(), ingot.quantity),
+            (Some("@ingot_t2"), 4.0)
+        );
+        assert_eq!(wood.kind, IngredientKind::Category);
+        assert_eq!(wood.name.as_deref(), Some("@category_wood"));
+        assert_eq!(wood.items.len(), 1);
+        assert_eq!(wood.items[0].id, "WoodT1");
+    }
+}
@@ -0,0 +1,88 @@
+use std::collections::HashMap;
+
+use serde_json::Value;
+
+use crate::{Datasheet, DatasheetCell};
+
+/// How string cells referencing rows of other datasheets are written during export.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Resolution {
+    /// Replace the cell with the referenced row.
+    Inline,
+    /// Keep the cell and add a `<Column>@datasheet` field naming the referenced sheet.
+    Annotate,
+}
+
+/// Index of every datasheet row by its key, the first column, used to resolve string cells that
+/// reference rows in other sheets. Only keys found in exactly one sheet are resolved, and inlined
+/// rows are not resolved any further.
+#[derive(Debug)]
+pub struct Resolver {
+    resolution: Resolution,
+    rows: HashMap<String, Vec<(String, Value)>>,
+}
+
+impl Resolver {
+    pub fn new<'a, I>(resolution: Resolution, datasheets: I) -> Self
+    where
+        I: IntoIterator<Item = Datasheet<'a>>,
+    {
+        let mut rows: HashMap<String, Vec<(String, Value)>> = HashMap::new();
+        for datasheet in datasheets {
+            let Value::Array(json) = datasheet.to_json() else {
+                continue;
+            };
+            for (row, json) in datasheet.rows.iter().zip(json) {
+                let Some(DatasheetCell::String(key)) = row.first() else {
+                    continue;
+                };
+                if key.is_empty() {
+                    continue;
+                }
+                rows.entry(key.to_lowercase())
+                    .or_default()
+                    .push((datasheet.name.to_owned(), json));
+            }
+        }
+
+        Self { resolution, rows }
+    }
+
+    fn get(&self, key: &str, sheet: &str) -> Option<&(String, Value)> {
+        match self.rows.get(&key.to_lowercase())?.as_slice() {
+            [row] if row.0 != sheet => Some(row),
+            _ => None,
+        }
+    }
+
+    /// Resolves the references in `json`, the output of [`Datasheet::to_json`] for `datasheet`.
+    pub fn resolve(&self, datasheet: &Datasheet, json: &mut Value) {
+        let Value::Array(rows) = json else {
+            return;
+        };
+
+        for (row, json) in datasheet.rows.iter().zip(rows) {
+            let Value::Object(json) = json else {
+                continue;
+            };
+            for (i, cell) in row.iter().enumerate().skip(1) {
+                let DatasheetCell::String(key) = cell else {
+                    continue;
+                };
+                let Some((sheet, referenced)) = self.get(key, &datasheet.name) else {
+                    continue;
+                };
+
+                let column = &datasheet.header[i].text;
+                match self.resolution {
+                    Resolution::Inline => {
+                        json.insert(column.to_owned(), referenced.to_owned());
+                    }
+                    Resolution::Annotate => {
+                        json.insert(format!("{}@datasheet", column), Value::String(sheet.into()));
+                    }
+                }
+            }
+        }
+    }
+}
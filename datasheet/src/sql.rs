@@ -0,0 +1,98 @@
+use std::io;
+
+/// The database a SQL export is written for, deciding quoting, column types, upserts and how
+/// many rows go in one `INSERT`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SqlDialect {
+    Postgres,
+    Mysql,
+    #[default]
+    Sqlite,
+}
+
+impl SqlDialect {
+    /// Rows per `INSERT` unless told otherwise. SQLite builds before 3.8.8 refuse more than 500.
+    pub fn batch_size(self) -> usize {
+        match self {
+            SqlDialect::Sqlite => 500,
+            SqlDialect::Postgres | SqlDialect::Mysql => 1000,
+        }
+    }
+
+    pub fn ident(self, ident: &str) -> String {
+        match self {
+            SqlDialect::Mysql => format!("`{}`", ident.replace('`', "``")),
+            SqlDialect::Postgres | SqlDialect::Sqlite => {
+                format!("\"{}\"", ident.replace('"', "\"\""))
+            }
+        }
+    }
+
+    pub fn string(self, value: &str) -> String {
+        let value = value.replace('\'', "''");
+        match self {
+            // backslashes are escapes in MySQL string literals
+            SqlDialect::Mysql => format!("'{}'", value.replace('\\', "\\\\")),
+            SqlDialect::Postgres | SqlDialect::Sqlite => format!("'{value}'"),
+        }
+    }
+
+    pub fn boolean(self, value: bool) -> &'static str {
+        match (self, value) {
+            (SqlDialect::Postgres, true) => "TRUE",
+            (SqlDialect::Postgres, false) => "FALSE",
+            (_, true) => "1",
+            (_, false) => "0",
+        }
+    }
+
+    /// The type of a datasheet column, `key` being the primary key column.
+    pub(crate) fn column_type(self, column_type: u32, key: bool) -> io::Result<&'static str> {
+        Ok(match (self, column_type) {
+            // MySQL can't index TEXT without a prefix length
+            (SqlDialect::Mysql, 1) if key => "VARCHAR(255)",
+            (_, 1) => "TEXT",
+            (SqlDialect::Postgres, 2) => "DOUBLE PRECISION",
+            (SqlDialect::Mysql, 2) => "DOUBLE",
+            (SqlDialect::Sqlite, 2) => "REAL",
+            (SqlDialect::Sqlite, 3) => "INTEGER",
+            (_, 3) => "BOOLEAN",
+            _ => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("unsupported column type {column_type}"),
+                ))
+            }
+        })
+    }
+
+    /// Clause ending an `INSERT` of quoted `columns` so rows whose key, the first column, already
+    /// exists replace it.
+    pub(crate) fn upsert(self, columns: &[String]) -> String {
+        let Some((key, rest)) = columns.split_first() else {
+            return String::new();
+        };
+        match self {
+            SqlDialect::Mysql if rest.is_empty() => {
+                format!("ON DUPLICATE KEY UPDATE {key} = {key}")
+            }
+            SqlDialect::Mysql => format!(
+                "ON DUPLICATE KEY UPDATE {}",
+                rest.iter()
+                    .map(|column| format!("{column} = VALUES({column})"))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+            SqlDialect::Postgres | SqlDialect::Sqlite if rest.is_empty() => {
+                format!("ON CONFLICT ({key}) DO NOTHING")
+            }
+            SqlDialect::Postgres | SqlDialect::Sqlite => format!(
+                "ON CONFLICT ({key}) DO UPDATE SET {}",
+                rest.iter()
+                    .map(|column| format!("{column} = excluded.{column}"))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+        }
+    }
+}
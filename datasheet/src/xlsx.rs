@@ -0,0 +1,191 @@
+//! A minimal Excel workbook writer: one sheet with a bold, frozen and filterable header row and
+//! typed cells, which is all datasheets need.
+
+use std::io::{self, Cursor, Write};
+
+use zip::{write::SimpleFileOptions, ZipWriter};
+
+use crate::DatasheetCell;
+
+const CONTENT_TYPES: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<Types xmlns="http://schemas.openxmlformats.org/package/2006/content-types"><Default Extension="rels" ContentType="application/vnd.openxmlformats-package.relationships+xml"/><Default Extension="xml" ContentType="application/xml"/><Override PartName="/xl/workbook.xml" ContentType="application/vnd.openxmlformats-officedocument.spreadsheetml.sheet.main+xml"/><Override PartName="/xl/worksheets/sheet1.xml" ContentType="application/vnd.openxmlformats-officedocument.spreadsheetml.worksheet+xml"/><Override PartName="/xl/styles.xml" ContentType="application/vnd.openxmlformats-officedocument.spreadsheetml.styles+xml"/></Types>"#;
+
+const RELS: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships"><Relationship Id="rId1" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/officeDocument" Target="xl/workbook.xml"/></Relationships>"#;
+
+const WORKBOOK_RELS: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships"><Relationship Id="rId1" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/worksheet" Target="worksheets/sheet1.xml"/><Relationship Id="rId2" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/styles" Target="styles.xml"/></Relationships>"#;
+
+/// Style 0 is the default, style 1 the bold header.
+const STYLES: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<styleSheet xmlns="http://schemas.openxmlformats.org/spreadsheetml/2006/main"><fonts count="2"><font><sz val="11"/><name val="Calibri"/></font><font><b/><sz val="11"/><name val="Calibri"/></font></fonts><fills count="2"><fill><patternFill patternType="none"/></fill><fill><patternFill patternType="gray125"/></fill></fills><borders count="1"><border><left/><right/><top/><bottom/><diagonal/></border></borders><cellStyleXfs count="1"><xf numFmtId="0" fontId="0" fillId="0" borderId="0"/></cellStyleXfs><cellXfs count="2"><xf numFmtId="0" fontId="0" fillId="0" borderId="0" xfId="0"/><xf numFmtId="0" fontId="1" fillId="0" borderId="0" xfId="0" applyFont="1"/></cellXfs><cellStyles count="1"><cellStyle name="Normal" xfId="0" builtinId="0"/></cellStyles></styleSheet>"#;
+
+/// A workbook with a single sheet called `name` holding `header` and `rows`. Strings are written
+/// inline rather than shared, numbers and booleans as typed cells.
+pub fn workbook(name: &str, header: &[&str], rows: &[Vec<DatasheetCell>]) -> io::Result<Vec<u8>> {
+    let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+    let options = SimpleFileOptions::default();
+    let parts = [
+        ("[Content_Types].xml", CONTENT_TYPES.to_string()),
+        ("_rels/.rels", RELS.to_string()),
+        ("xl/workbook.xml", workbook_xml(name)),
+        ("xl/_rels/workbook.xml.rels", WORKBOOK_RELS.to_string()),
+        ("xl/styles.xml", STYLES.to_string()),
+        ("xl/worksheets/sheet1.xml", sheet_xml(header, rows)),
+    ];
+    for (path, xml) in parts {
+        zip.start_file(path, options)?;
+        zip.write_all(xml.as_bytes())?;
+    }
+    Ok(zip.finish()?.into_inner())
+}
+
+fn workbook_xml(name: &str) -> String {
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<workbook xmlns="http://schemas.openxmlformats.org/spreadsheetml/2006/main" xmlns:r="http://schemas.openxmlformats.org/officeDocument/2006/relationships"><sheets><sheet name="{}" sheetId="1" r:id="rId1"/></sheets></workbook>"#,
+        escape(&sheet_name(name))
+    )
+}
+
+fn sheet_xml(header: &[&str], rows: &[Vec<DatasheetCell>]) -> String {
+    let mut xml = String::from(
+        r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<worksheet xmlns="http://schemas.openxmlformats.org/spreadsheetml/2006/main">"#,
+    );
+    if !header.is_empty() {
+        xml.push_str(r#"<sheetViews><sheetView workbookViewId="0"><pane ySplit="1" topLeftCell="A2" activePane="bottomLeft" state="frozen"/></sheetView></sheetViews>"#);
+    }
+
+    xml.push_str("<sheetData>");
+    xml.push_str(r#"<row r="1">"#);
+    for (i, text) in header.iter().enumerate() {
+        xml.push_str(&format!(
+            r#"<c r="{}1" t="inlineStr" s="1">{}</c>"#,
+            column(i),
+            inline(text)
+        ));
+    }
+    xml.push_str("</row>");
+    for (r, row) in rows.iter().enumerate() {
+        let r = r + 2;
+        xml.push_str(&format!(r#"<row r="{r}">"#));
+        for (i, cell) in row.iter().enumerate() {
+            let reference = format!("{}{r}", column(i));
+            match cell {
+                DatasheetCell::String(value) => xml.push_str(&format!(
+                    r#"<c r="{reference}" t="inlineStr">{}</c>"#,
+                    inline(value)
+                )),
+                DatasheetCell::Number(value) if value.is_finite() => {
+                    let value = match value.fract() == 0.0 {
+                        true => (*value as i64).to_string(),
+                        false => value.to_string(),
+                    };
+                    xml.push_str(&format!(r#"<c r="{reference}"><v>{value}</v></c>"#))
+                }
+                // Excel has no NaN or infinity
+                DatasheetCell::Number(_) => {}
+                DatasheetCell::Boolean(value) => xml.push_str(&format!(
+                    r#"<c r="{reference}" t="b"><v>{}</v></c>"#,
+                    *value as u8
+                )),
+            }
+        }
+        xml.push_str("</row>");
+    }
+    xml.push_str("</sheetData>");
+
+    if !header.is_empty() {
+        xml.push_str(&format!(
+            r#"<autoFilter ref="A1:{}{}"/>"#,
+            column(header.len() - 1),
+            rows.len() + 1
+        ));
+    }
+    xml.push_str("</worksheet>");
+    xml
+}
+
+/// The letters of the `i`th column, `A` to `Z`, then `AA` and on.
+fn column(mut i: usize) -> String {
+    let mut letters = vec![];
+    loop {
+        letters.push(b'A' + (i % 26) as u8);
+        if i < 26 {
+            break;
+        }
+        i = i / 26 - 1;
+    }
+    letters.iter().rev().map(|&b| b as char).collect()
+}
+
+/// Excel refuses sheet names over 31 characters or with any of `[]:*?/\`.
+fn sheet_name(name: &str) -> String {
+    let name = name
+        .chars()
+        .filter(|c| !matches!(c, '[' | ']' | ':' | '*' | '?' | '/' | '\\'))
+        .take(31)
+        .collect::<String>();
+    match name.is_empty() {
+        true => "Sheet1".to_string(),
+        false => name,
+    }
+}
+
+fn inline(text: &str) -> String {
+    match text.starts_with(char::is_whitespace) || text.ends_with(char::is_whitespace) {
+        true => format!(r#"<is><t xml:space="preserve">{}</t></is>"#, escape(text)),
+        false => format!("<is><t>{}</t></is>", escape(text)),
+    }
+}
+
+/// Escapes `text` for XML, dropping the control characters XML can't hold.
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\t' | '\n' | '\r' => escaped.push(c),
+            c if c.is_control() && (c as u32) < 0x20 => {}
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn names_columns() {
+        assert_eq!(column(0), "A");
+        assert_eq!(column(25), "Z");
+        assert_eq!(column(26), "AA");
+        assert_eq!(column(701), "ZZ");
+        assert_eq!(column(702), "AAA");
+    }
+
+    #[test]
+    fn writes_typed_cells() {
+        let rows = vec![vec![
+            DatasheetCell::String(" <b> & c".into()),
+            DatasheetCell::Number(2.5),
+            DatasheetCell::Boolean(true),
+        ]];
+        let xml = sheet_xml(&["Name", "Weight", "Salvage"], &rows);
+        assert!(xml.contains(r#"<c r="A1" t="inlineStr" s="1"><is><t>Name</t></is></c>"#));
+        assert!(xml.contains(
+            r#"<c r="A2" t="inlineStr"><is><t xml:space="preserve"> &lt;b&gt; &amp; c</t></is></c>"#
+        ));
+        assert!(xml.contains(r#"<c r="B2"><v>2.5</v></c>"#));
+        assert!(xml.contains(r#"<c r="C2" t="b"><v>1</v></c>"#));
+        assert!(xml.contains(r#"<autoFilter ref="A1:C2"/>"#));
+
+        assert_eq!(sheet_name("Items: [Weapons]"), "Items Weapons");
+    }
+}
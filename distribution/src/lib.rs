@@ -47,7 +47,8 @@ impl SlicesData {
             value.read_exact(&mut string)?;
             assert_eq!(len as usize, string.len());
 
-            let string = String::from_utf8(string).unwrap();
+            let string = String::from_utf8(string)
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
             slices.push(string);
         }
         for _ in 0..len {
@@ -58,7 +59,8 @@ impl SlicesData {
             value.read_exact(&mut string)?;
             assert_eq!(len as usize, string.len());
 
-            let string = String::from_utf8(string).unwrap();
+            let string = String::from_utf8(string)
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
             variants.push(string);
         }
 
@@ -183,7 +185,103 @@ impl TryFrom<&[u8; 4]> for Position {
     }
 }
 
+/// The width of a region in world units. Positions are quantized over it.
+pub const REGION_SIZE: f32 = 2048.0;
+
+/// The world position of the corner of the region a file belongs to, from the `r_+XX_+YY`
+/// directory in its path.
+pub fn region_origin(path: &str) -> Option<[f32; 2]> {
+    path.split(['/', '\\']).find_map(|part| {
+        let (x, y) = part.strip_prefix("r_")?.split_once('_')?;
+        let (x, y) = (x.parse::<i32>().ok()?, y.parse::<i32>().ok()?);
+        Some([x as f32 * REGION_SIZE, y as f32 * REGION_SIZE])
+    })
+}
+
+/// A gatherable or spawn with the slice it instantiates and its world position.
+#[derive(Debug, Serialize)]
+pub struct Placement<'a> {
+    pub slice: &'a str,
+    pub variant: &'a str,
+    pub x: f32,
+    pub y: f32,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(tag = "type", rename = "FeatureCollection")]
+pub struct FeatureCollection<'a> {
+    pub features: Vec<Feature<'a>>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(tag = "type", rename = "Feature")]
+pub struct Feature<'a> {
+    pub geometry: Point,
+    pub properties: Properties<'a>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(tag = "type", rename = "Point")]
+pub struct Point {
+    pub coordinates: [f32; 2],
+}
+
+#[derive(Debug, Serialize)]
+pub struct Properties<'a> {
+    pub slice: &'a str,
+    pub variant: &'a str,
+}
+
 impl Distribution {
+    /// Every gatherable in world coordinates, offset by the `origin` of its region.
+    pub fn placements(&self, origin: [f32; 2]) -> impl Iterator<Item = Placement<'_>> {
+        let scale = REGION_SIZE / 65536.0;
+        let gatherables = &self.gatherables;
+        gatherables.indices.iter().zip(&gatherables.positions).map(
+            move |(index, Position(x, y))| {
+                let index = *index as usize;
+                Placement {
+                    slice: self.slices.slices.get(index).map_or("", String::as_str),
+                    variant: self.slices.variants.get(index).map_or("", String::as_str),
+                    x: origin[0] + *x as f32 * scale,
+                    y: origin[1] + *y as f32 * scale,
+                }
+            },
+        )
+    }
+
+    /// The gatherables as GeoJSON points, for dropping onto maps.
+    pub fn to_geojson(&self, origin: [f32; 2]) -> FeatureCollection<'_> {
+        let features = self
+            .placements(origin)
+            .map(|placement| Feature {
+                geometry: Point {
+                    coordinates: [placement.x, placement.y],
+                },
+                properties: Properties {
+                    slice: placement.slice,
+                    variant: placement.variant,
+                },
+            })
+            .collect();
+        FeatureCollection { features }
+    }
+
+    /// The gatherables as `slice,variant,x,y` rows under a header.
+    pub fn to_csv(&self, origin: [f32; 2]) -> String {
+        let mut csv = String::from("slice,variant,x,y\n");
+        for placement in self.placements(origin) {
+            csv.push_str(&format!(
+                "{},{},{},{}\n",
+                csv_field(placement.slice),
+                csv_field(placement.variant),
+                placement.x,
+                placement.y
+            ));
+        }
+        csv
+    }
+
     pub fn from_reader<R: Read>(value: &mut R) -> Result<Self, MyError> {
         let slices = SlicesData::from_reader(value);
         let Ok(slices) = slices else {
@@ -214,3 +312,53 @@ impl Distribution {
         })
     }
 }
+
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_owned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn world_positions() {
+        assert_eq!(
+            region_origin("coatlicue/newworld_vitaeeterna/regions/r_+03_+05/region.distribution"),
+            Some([3.0 * REGION_SIZE, 5.0 * REGION_SIZE])
+        );
+        assert_eq!(region_origin("slices/world.distribution"), None);
+
+        let distribution = Distribution {
+            slices: SlicesData {
+                slices: vec!["slices/ore, iron".into()],
+                variants: vec!["rich".into()],
+            },
+            gatherables: GatherablesData {
+                indices: vec![0],
+                positions: vec![Position(32768, 16384)],
+                extra1: vec![0],
+                extra2: vec![0],
+                extra3: vec![0],
+            },
+            unknown1: Unknown {
+                positions: vec![],
+                extra: vec![],
+            },
+            unknown2: Unknown {
+                positions: vec![],
+                extra: vec![],
+            },
+        };
+        assert_eq!(
+            distribution.to_csv([2048.0, 0.0]),
+            "slice,variant,x,y\n\"slices/ore, iron\",rich,3072,512\n"
+        );
+        let geojson = distribution.to_geojson([0.0, 0.0]);
+        assert_eq!(geojson.features[0].geometry.coordinates, [1024.0, 512.0]);
+    }
+}
@@ -0,0 +1,50 @@
+/* C interface to the nwtools extraction engine, implemented in ffi/src/lib.rs.
+ *
+ * Functions returning an int return 0 on success and -1 on failure, with the reason in
+ * nwtools_last_error(). Strings are UTF-8 and nul terminated. A panic inside the library is
+ * caught before it reaches the caller and reported the same way, as -1 or NULL with a message
+ * starting with "panicked: ". */
+
+#ifndef NWTOOLS_H
+#define NWTOOLS_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+typedef struct NwExtractor NwExtractor;
+
+typedef void (*NwEntryCallback)(const char *entry, void *user);
+typedef void (*NwDataCallback)(const uint8_t *data, size_t len, void *user);
+
+/* Message of the last call that failed on this thread, or NULL. Valid until the next failure. */
+const char *nwtools_last_error(void);
+
+/* Indexes the paks under game_dir, keeping entries matching the comma separated filter globs, or
+ * all of them when filter is NULL. Returns NULL on failure. */
+NwExtractor *nwtools_open(const char *game_dir, const char *filter);
+
+/* Frees an extractor. NULL is ignored. */
+void nwtools_close(NwExtractor *extractor);
+
+/* Calls callback with the path of every indexed entry. The path is only valid during the call. */
+int nwtools_entries(const NwExtractor *extractor, NwEntryCallback callback, void *user);
+
+/* Decompresses entry into a new buffer, freed with nwtools_free. */
+int nwtools_read(const NwExtractor *extractor, const char *entry, uint8_t **data, size_t *len);
+
+/* Frees a buffer returned by nwtools_read. NULL is ignored. */
+void nwtools_free(uint8_t *data, size_t len);
+
+/* Decompresses entry and passes it to callback. The data is only valid during the call. */
+int nwtools_read_with(const NwExtractor *extractor, const char *entry, NwDataCallback callback,
+                      void *user);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif
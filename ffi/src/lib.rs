@@ -0,0 +1,247 @@
+//! C ABI over [`nwtools_core`], declared in `include/nwtools.h`. Functions returning an `int`
+//! return 0 on success and -1 on failure, with the reason in [`nwtools_last_error`]. Panics are
+//! caught at the boundary and reported as failures, unwinding into C being undefined.
+
+use nwtools_core::Extractor;
+use std::{
+    cell::RefCell,
+    ffi::{c_char, c_int, c_void, CStr, CString},
+    fmt::Display,
+    io,
+    panic::{self, AssertUnwindSafe},
+    ptr,
+};
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+fn set_error(e: impl Display) {
+    LAST_ERROR.with(|last| *last.borrow_mut() = CString::new(e.to_string()).ok());
+}
+
+/// Converts `result` to the `int` status, recording the error.
+fn status(result: io::Result<()>) -> c_int {
+    match result {
+        Ok(()) => 0,
+        Err(e) => {
+            set_error(e);
+            -1
+        }
+    }
+}
+
+/// Runs `f`, turning a panic into an error so it doesn't unwind into the caller.
+fn catch<T>(f: impl FnOnce() -> io::Result<T>) -> io::Result<T> {
+    panic::catch_unwind(AssertUnwindSafe(f)).unwrap_or_else(|panic| {
+        let message = panic
+            .downcast_ref::<&str>()
+            .map(|message| message.to_string())
+            .or_else(|| panic.downcast_ref::<String>().cloned())
+            .unwrap_or_default();
+        Err(io::Error::other(format!("panicked: {message}")))
+    })
+}
+
+/// Borrows the nul terminated string at `ptr`, `None` when null.
+///
+/// # Safety
+/// `ptr` must be null or point to a nul terminated string that outlives `'a`.
+unsafe fn str_arg<'a>(ptr: *const c_char) -> io::Result<Option<&'a str>> {
+    if ptr.is_null() {
+        return Ok(None);
+    }
+    CStr::from_ptr(ptr)
+        .to_str()
+        .map(Some)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))
+}
+
+/// # Safety
+/// `extractor` must be null or returned by [`nwtools_open`] and not yet closed.
+unsafe fn extractor_arg<'a>(extractor: *const Extractor) -> io::Result<&'a Extractor> {
+    extractor
+        .as_ref()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "null extractor"))
+}
+
+/// # Safety
+/// `entry` must be null or point to a nul terminated string.
+unsafe fn entry_arg<'a>(entry: *const c_char) -> io::Result<&'a str> {
+    str_arg(entry)?.ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "null entry"))
+}
+
+/// The message of the last call that failed on this thread, or null. It stays valid until the
+/// next call on this thread fails.
+#[no_mangle]
+pub extern "C" fn nwtools_last_error() -> *const c_char {
+    LAST_ERROR.with(|last| last.borrow().as_ref().map_or(ptr::null(), |e| e.as_ptr()))
+}
+
+/// Indexes the paks of the install at `game_dir`, keeping the entries matching the comma
+/// separated `filter` globs, or all of them when `filter` is null. Returns null on failure.
+///
+/// # Safety
+/// `game_dir` and `filter` must be null or nul terminated strings.
+#[no_mangle]
+pub unsafe extern "C" fn nwtools_open(
+    game_dir: *const c_char,
+    filter: *const c_char,
+) -> *mut Extractor {
+    match catch(|| open(game_dir, filter)) {
+        Ok(extractor) => Box::into_raw(Box::new(extractor)),
+        Err(e) => {
+            set_error(e);
+            ptr::null_mut()
+        }
+    }
+}
+
+unsafe fn open(game_dir: *const c_char, filter: *const c_char) -> io::Result<Extractor> {
+    let mut builder = Extractor::builder();
+    if let Some(game_dir) = str_arg(game_dir)? {
+        builder = builder.game_dir(game_dir);
+    }
+    if let Some(filter) = str_arg(filter)? {
+        builder = builder.filter(filter);
+    }
+    builder.build()
+}
+
+/// Frees an extractor returned by [`nwtools_open`]. Null is ignored.
+///
+/// # Safety
+/// `extractor` must be null or returned by [`nwtools_open`] and not yet closed.
+#[no_mangle]
+pub unsafe extern "C" fn nwtools_close(extractor: *mut Extractor) {
+    if !extractor.is_null() {
+        drop(Box::from_raw(extractor));
+    }
+}
+
+/// Calls `callback` with the path of every indexed entry and `user`. The path is only valid during
+/// the call.
+///
+/// # Safety
+/// `extractor` must be returned by [`nwtools_open`] and not yet closed.
+#[no_mangle]
+pub unsafe extern "C" fn nwtools_entries(
+    extractor: *const Extractor,
+    callback: Option<extern "C" fn(*const c_char, *mut c_void)>,
+    user: *mut c_void,
+) -> c_int {
+    status(catch(|| entries(extractor, callback, user)))
+}
+
+unsafe fn entries(
+    extractor: *const Extractor,
+    callback: Option<extern "C" fn(*const c_char, *mut c_void)>,
+    user: *mut c_void,
+) -> io::Result<()> {
+    let extractor = extractor_arg(extractor)?;
+    let callback =
+        callback.ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "null callback"))?;
+    for entry in extractor.entries() {
+        let Ok(entry) = CString::new(entry.to_string_lossy().into_owned()) else {
+            continue;
+        };
+        callback(entry.as_ptr(), user);
+    }
+    Ok(())
+}
+
+/// Decompresses `entry` into a new buffer stored in `data` and `len`, to be freed with
+/// [`nwtools_free`].
+///
+/// # Safety
+/// `extractor` must be returned by [`nwtools_open`] and not yet closed, `entry` a nul terminated
+/// string and `data` and `len` valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn nwtools_read(
+    extractor: *const Extractor,
+    entry: *const c_char,
+    data: *mut *mut u8,
+    len: *mut usize,
+) -> c_int {
+    status(catch(|| read(extractor, entry, data, len)))
+}
+
+unsafe fn read(
+    extractor: *const Extractor,
+    entry: *const c_char,
+    data: *mut *mut u8,
+    len: *mut usize,
+) -> io::Result<()> {
+    if data.is_null() || len.is_null() {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, "null output"));
+    }
+    let buf = extractor_arg(extractor)?
+        .read(entry_arg(entry)?)?
+        .into_boxed_slice();
+    *len = buf.len();
+    *data = Box::into_raw(buf).cast();
+    Ok(())
+}
+
+/// Frees a buffer returned by [`nwtools_read`]. Null is ignored.
+///
+/// # Safety
+/// `data` and `len` must be null or as returned by [`nwtools_read`], and not yet freed.
+#[no_mangle]
+pub unsafe extern "C" fn nwtools_free(data: *mut u8, len: usize) {
+    if !data.is_null() {
+        drop(Box::from_raw(ptr::slice_from_raw_parts_mut(data, len)));
+    }
+}
+
+/// Decompresses `entry` and passes its contents to `callback` along with `user`, without handing
+/// ownership of the buffer over. The contents are only valid during the call.
+///
+/// # Safety
+/// `extractor` must be returned by [`nwtools_open`] and not yet closed and `entry` a nul
+/// terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn nwtools_read_with(
+    extractor: *const Extractor,
+    entry: *const c_char,
+    callback: Option<extern "C" fn(*const u8, usize, *mut c_void)>,
+    user: *mut c_void,
+) -> c_int {
+    status(catch(|| read_with(extractor, entry, callback, user)))
+}
+
+unsafe fn read_with(
+    extractor: *const Extractor,
+    entry: *const c_char,
+    callback: Option<extern "C" fn(*const u8, usize, *mut c_void)>,
+    user: *mut c_void,
+) -> io::Result<()> {
+    let callback =
+        callback.ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "null callback"))?;
+    let buf = extractor_arg(extractor)?.read(entry_arg(entry)?)?;
+    callback(buf.as_ptr(), buf.len(), user);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn failures_set_last_error() {
+        let extractor = unsafe { nwtools_open(ptr::null(), ptr::null()) };
+        assert!(extractor.is_null());
+        let error = unsafe { CStr::from_ptr(nwtools_last_error()) };
+        assert_eq!(error.to_str().unwrap(), "no game dir set");
+
+        let status = unsafe { nwtools_entries(ptr::null(), None, ptr::null_mut()) };
+        assert_eq!(status, -1);
+    }
+
+    #[test]
+    fn panics_become_errors() {
+        let result = catch(|| -> io::Result<()> { panic!("corrupt entry") });
+        assert_eq!(result.unwrap_err().to_string(), "panicked: corrupt entry");
+        assert_eq!(status(catch(|| panic!("{}", 1))), -1);
+    }
+}
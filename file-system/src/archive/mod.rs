@@ -0,0 +1,190 @@
+use flate2::{write::GzEncoder, Compression};
+use std::{
+    fs::File,
+    io::{self, BufWriter, Write},
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+use zip::{write::SimpleFileOptions, CompressionMethod, ZipWriter};
+
+/// Single archive that extracted entries are written into instead of loose files, picked by
+/// the extension of its path: `.zip`, `.tar`, `.tar.gz`/`.tgz` or `.tar.zst`/`.tzst`.
+pub struct ArchiveWriter {
+    /// Output directory the entry paths are made relative to.
+    root: PathBuf,
+    kind: Kind,
+}
+
+enum Kind {
+    Tar(tar::Builder<BufWriter<File>>),
+    TarGz(tar::Builder<GzEncoder<BufWriter<File>>>),
+    TarZst(tar::Builder<zstd::stream::write::Encoder<'static, BufWriter<File>>>),
+    Zip(ZipWriter<BufWriter<File>>),
+}
+
+/// Archive formats by the extension of their name.
+#[derive(Clone, Copy)]
+enum Format {
+    Tar,
+    TarGz,
+    TarZst,
+    Zip,
+}
+
+impl ArchiveWriter {
+    pub fn create<P>(path: P, root: &Path) -> io::Result<Self>
+    where
+        P: AsRef<Path>,
+    {
+        let path = path.as_ref();
+        let name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_lowercase())
+            .unwrap_or_default();
+        // checked before anything is created, an unsupported name leaves no file behind
+        let format = if name.ends_with(".zip") {
+            Format::Zip
+        } else if name.ends_with(".tar.zst") || name.ends_with(".tzst") {
+            Format::TarZst
+        } else if name.ends_with(".tar.gz") || name.ends_with(".tgz") {
+            Format::TarGz
+        } else if name.ends_with(".tar") {
+            Format::Tar
+        } else {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("Unsupported archive type: {}", path.display()),
+            ));
+        };
+
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let file = BufWriter::new(File::create(path)?);
+        let kind = match format {
+            Format::Tar => Kind::Tar(tar::Builder::new(file)),
+            Format::TarGz => Kind::TarGz(tar::Builder::new(GzEncoder::new(
+                file,
+                Compression::default(),
+            ))),
+            Format::TarZst => Kind::TarZst(tar::Builder::new(zstd::stream::write::Encoder::new(
+                file, 0,
+            )?)),
+            Format::Zip => Kind::Zip(ZipWriter::new(file)),
+        };
+
+        Ok(Self {
+            // entries are written under the long form of the output directory
+            root: crate::paths::long(root),
+            kind,
+        })
+    }
+
+    /// Adds a file at `path`, a path under the output directory.
+    pub fn append(&mut self, path: &Path, data: &[u8]) -> io::Result<()> {
+        let name = path
+            .strip_prefix(&self.root)
+            .unwrap_or(path)
+            .to_string_lossy()
+            .replace('\\', "/");
+
+        match &mut self.kind {
+            Kind::Tar(builder) => append_tar(builder, &name, data),
+            Kind::TarGz(builder) => append_tar(builder, &name, data),
+            Kind::TarZst(builder) => append_tar(builder, &name, data),
+            Kind::Zip(zip) => {
+                let options = SimpleFileOptions::default()
+                    .compression_method(CompressionMethod::Deflated)
+                    .large_file(data.len() as u64 >= u32::MAX as u64);
+                zip.start_file(name, options)?;
+                zip.write_all(data)
+            }
+        }
+    }
+
+    /// Writes the end of the archive, finishes its compression and flushes it to disk. Each
+    /// step's errors are returned rather than lost when the writers are dropped.
+    pub fn finish(self) -> io::Result<()> {
+        match self.kind {
+            Kind::Tar(builder) => builder.into_inner()?.flush(),
+            Kind::TarGz(builder) => builder.into_inner()?.finish()?.flush(),
+            Kind::TarZst(builder) => builder.into_inner()?.finish()?.flush(),
+            Kind::Zip(zip) => zip.finish()?.flush(),
+        }
+    }
+}
+
+fn append_tar<W: Write>(builder: &mut tar::Builder<W>, name: &str, data: &[u8]) -> io::Result<()> {
+    let mut header = tar::Header::new_gnu();
+    header.set_size(data.len() as u64);
+    header.set_mode(0o644);
+    header.set_mtime(
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |time| time.as_secs()),
+    );
+    header.set_cksum();
+    builder.append_data(&mut header, name, data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+
+    #[test]
+    fn names_entries_under_the_long_output_directory() {
+        let dir = std::env::temp_dir().join(format!("nwtools-archive-{}", std::process::id()));
+        let out_dir = dir.join("out");
+        let path = dir.join("entries.zip");
+
+        let mut writer = ArchiveWriter::create(&path, &out_dir).unwrap();
+        let entry = crate::paths::long(&out_dir)
+            .join("sharedassets")
+            .join("a.json");
+        writer.append(&entry, b"{}").unwrap();
+        writer.finish().unwrap();
+
+        let mut archive = zip::ZipArchive::new(File::open(&path).unwrap()).unwrap();
+        let mut data = vec![];
+        archive
+            .by_name("sharedassets/a.json")
+            .unwrap()
+            .read_to_end(&mut data)
+            .unwrap();
+        assert_eq!(data, b"{}");
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn unsupported_names_create_nothing() {
+        let path = std::env::temp_dir().join(format!("nwtools-archive-{}.rar", std::process::id()));
+        assert!(ArchiveWriter::create(&path, Path::new("out")).is_err());
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn finishes_compressed_tars() {
+        let dir = std::env::temp_dir().join(format!("nwtools-tar-{}", std::process::id()));
+        for name in ["entries.tar.gz", "entries.tar.zst"] {
+            let path = dir.join(name);
+            let mut writer = ArchiveWriter::create(&path, &dir).unwrap();
+            writer.append(&dir.join("a.json"), b"{}").unwrap();
+            writer.finish().unwrap();
+
+            let file = File::open(&path).unwrap();
+            let reader: Box<dyn Read> = if name.ends_with(".gz") {
+                Box::new(flate2::read::GzDecoder::new(file))
+            } else {
+                Box::new(zstd::stream::read::Decoder::new(file).unwrap())
+            };
+            let mut archive = tar::Archive::new(reader);
+            let mut entry = archive.entries().unwrap().next().unwrap().unwrap();
+            assert_eq!(entry.path().unwrap(), Path::new("a.json"));
+            let mut data = vec![];
+            entry.read_to_end(&mut data).unwrap();
+            assert_eq!(data, b"{}");
+        }
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -1,7 +1,26 @@
-use flate2::{read::ZlibDecoder, Decompress};
-use std::io::{self, BufReader, Cursor, Read};
+use flate2::{read::ZlibDecoder, Compress, Compression, FlushCompress, Status};
+use object_stream::ObjectStream;
+use std::io::{self, Read, Write};
 
 const AZCS_SIGNATURE: &[u8; 4] = b"AZCS";
+/// Compressor ids are the `AZ_CRC` of the lowercased compressor name, the CRC32 of `"zlib"`,
+/// `"lz4"` and `"zstd"`, the way AzCore's `CompressorZLib` and `CompressorZStd` derive their
+/// `TypeId`.
+const ZLIB_ID: u32 = 0x73887d3a;
+const LZ4_ID: u32 = 0x4ae88e9f;
+const ZSTD_ID: u32 = 0x72fd505e;
+/// Signature, compressor id and uncompressed size, then the seek point count.
+const HEADER_SIZE: usize = 4 + 4 + 8 + 4;
+/// Uncompressed bytes between zlib seek points, where the stream is fully flushed so reading can
+/// start there.
+const SEEK_POINT_INTERVAL: usize = 256 * 1024;
+
+/// Compression of an AZCS stream written by [`compress`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Compressor {
+    Zlib,
+    Lz4,
+}
 
 const UNCOMPRESSED_SIGNATURES: [[u8; 5]; 3] = [
     [0x00, 0x00, 0x00, 0x00, 0x03],
@@ -38,17 +57,15 @@ impl<R: Read> From<&mut R> for Header {
     }
 }
 
-pub fn decompress<R>(reader: &mut R) -> io::Result<impl Read + Unpin + '_>
+pub fn decompress<R>(reader: &mut R) -> io::Result<Box<dyn Read + Unpin + '_>>
 where
     R: Read + Unpin,
 {
     let header = { Header::from(&mut *reader) };
     match &header.compressor_id {
-        0x73887d3a => handle_zlib(reader),
-        0x72fd505e => Err(io::Error::new(
-            io::ErrorKind::Other,
-            "zstd is not implemented",
-        )),
+        &ZLIB_ID => Ok(Box::new(handle_zlib(reader)?)),
+        &LZ4_ID => Ok(Box::new(handle_lz4(reader)?)),
+        &ZSTD_ID => Ok(Box::new(handle_zstd(reader, header.uncompressed_size)?)),
         _ => {
             dbg!(&header);
             Err(io::Error::new(
@@ -59,6 +76,81 @@ where
     }
 }
 
+/// Writes `data` as an AZCS stream the engine reads back: the header, the seek point count and
+/// the compressed payload, followed for zlib by its seek points as big endian
+/// `(compressed offset, uncompressed offset)` pairs.
+pub fn compress<W: Write>(data: &[u8], compressor: Compressor, writer: &mut W) -> io::Result<()> {
+    let id = match compressor {
+        Compressor::Zlib => ZLIB_ID,
+        Compressor::Lz4 => LZ4_ID,
+    };
+    writer.write_all(AZCS_SIGNATURE)?;
+    writer.write_all(&id.to_be_bytes())?;
+    writer.write_all(&(data.len() as u64).to_be_bytes())?;
+
+    match compressor {
+        Compressor::Zlib => write_zlib(data, SEEK_POINT_INTERVAL, writer),
+        Compressor::Lz4 => {
+            writer.write_all(&0u32.to_be_bytes())?;
+            let mut encoder = lz4_flex::frame::FrameEncoder::new(writer);
+            encoder.write_all(data)?;
+            encoder.finish().map_err(io::Error::other)?;
+            Ok(())
+        }
+    }
+}
+
+/// Writes `stream` the way the game stores object streams, as binary AZCS compressed with zlib.
+pub fn write_object_stream<W: Write>(stream: &ObjectStream, writer: &mut W) -> io::Result<()> {
+    let mut buf = vec![];
+    stream.to_writer(&mut buf)?;
+    compress(&buf, Compressor::Zlib, writer)
+}
+
+/// One zlib stream, fully flushed every `interval` bytes, with a seek point at each flush.
+fn write_zlib<W: Write>(data: &[u8], interval: usize, writer: &mut W) -> io::Result<()> {
+    let chunks = data.chunks(interval).collect::<Vec<_>>();
+    let count = chunks.len().max(1);
+    let mut compress = Compress::new(Compression::default(), true);
+    let mut out = Vec::with_capacity(data.len() / 2 + 64);
+    let mut seek_points = Vec::with_capacity(count);
+
+    for i in 0..count {
+        let chunk = chunks.get(i).copied().unwrap_or_default();
+        let last = i + 1 == count;
+        seek_points.push(((HEADER_SIZE + out.len()) as u64, (i * interval) as u64));
+
+        let start = compress.total_in();
+        loop {
+            out.reserve(chunk.len() / 2 + 1024);
+            let consumed = (compress.total_in() - start) as usize;
+            let flush = if last {
+                FlushCompress::Finish
+            } else {
+                FlushCompress::Full
+            };
+            let status = compress
+                .compress_vec(&chunk[consumed..], &mut out, flush)
+                .map_err(io::Error::other)?;
+            let drained = compress.total_in() - start == chunk.len() as u64;
+            // a flush is complete once it leaves room in the output
+            match status {
+                Status::StreamEnd => break,
+                _ if !last && drained && out.len() < out.capacity() => break,
+                _ => {}
+            }
+        }
+    }
+
+    writer.write_all(&(seek_points.len() as u32).to_be_bytes())?;
+    writer.write_all(&out)?;
+    for (compressed, uncompressed) in seek_points {
+        writer.write_all(&compressed.to_be_bytes())?;
+        writer.write_all(&uncompressed.to_be_bytes())?;
+    }
+    Ok(())
+}
+
 pub fn is_azcs(sig: &mut [u8; 4]) -> bool {
     sig.eq(&AZCS_SIGNATURE)
 }
@@ -93,6 +185,29 @@ where
     Ok(zr)
 }
 
+/// AzCore ships no LZ4 compressor, so LZ4 streams are read with the layout its zlib and zstd
+/// compressors share, a seek point count after the header followed by the payload, here a single
+/// LZ4 frame as [`compress`] writes it.
+pub fn handle_lz4<R>(mut reader: R) -> io::Result<impl Read + Unpin>
+where
+    R: Read + Unpin,
+{
+    let mut buf = [0; 4];
+    reader.read_exact(&mut buf)?;
+    Ok(lz4_flex::frame::FrameDecoder::new(reader))
+}
+
+/// A seek point count followed by zstd frames. Seek points can follow the payload, so reading
+/// stops at the uncompressed size instead of taking them for another frame.
+pub fn handle_zstd<R>(mut reader: R, uncompressed_size: u64) -> io::Result<impl Read + Unpin>
+where
+    R: Read + Unpin,
+{
+    let mut buf = [0; 4];
+    reader.read_exact(&mut buf)?;
+    Ok(zstd::stream::read::Decoder::new(reader)?.take(uncompressed_size))
+}
+
 pub fn is_uncompressed(data: &[u8]) -> bool {
     for &uncompressed_signature in UNCOMPRESSED_SIGNATURES.iter() {
         if data.len() >= uncompressed_signature.len() && data.starts_with(&uncompressed_signature) {
@@ -109,3 +224,119 @@ pub fn is_compressed(data: &[u8]) -> bool {
 
     data.starts_with(AZCS_SIGNATURE)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    fn round_trip(data: &[u8], compressor: Compressor) -> Vec<u8> {
+        let mut buf = vec![];
+        compress(data, compressor, &mut buf).unwrap();
+        assert!(is_compressed(&buf));
+
+        let mut reader = buf.as_slice();
+        let mut decompressed = vec![];
+        decompress(&mut reader)
+            .unwrap()
+            .read_to_end(&mut decompressed)
+            .unwrap();
+        decompressed
+    }
+
+    #[test]
+    fn compresses_what_it_decompresses() {
+        let data = (0..600_000u32).map(|i| (i % 251) as u8).collect::<Vec<_>>();
+        assert_eq!(round_trip(&data, Compressor::Zlib), data);
+        assert_eq!(round_trip(&data, Compressor::Lz4), data);
+        assert!(round_trip(&[], Compressor::Zlib).is_empty());
+    }
+
+    #[test]
+    fn compressor_ids_are_crcs_of_their_names() {
+        assert_eq!(crc32fast::hash(b"zlib"), ZLIB_ID);
+        assert_eq!(crc32fast::hash(b"lz4"), LZ4_ID);
+        assert_eq!(crc32fast::hash(b"zstd"), ZSTD_ID);
+    }
+
+    #[test]
+    fn reads_lz4_streams() {
+        let data = b"sharedassets/springboardentitites/".repeat(200);
+        let mut encoder = lz4_flex::frame::FrameEncoder::new(vec![]);
+        encoder.write_all(&data).unwrap();
+
+        let mut stream = AZCS_SIGNATURE.to_vec();
+        stream.extend_from_slice(&LZ4_ID.to_be_bytes());
+        stream.extend_from_slice(&(data.len() as u64).to_be_bytes());
+        stream.extend_from_slice(&0u32.to_be_bytes());
+        stream.extend_from_slice(&encoder.finish().unwrap());
+
+        let mut decompressed = vec![];
+        decompress(&mut stream.as_slice())
+            .unwrap()
+            .read_to_end(&mut decompressed)
+            .unwrap();
+        assert_eq!(decompressed, data);
+    }
+
+    #[test]
+    fn reads_zstd_streams() {
+        let data = b"sharedassets/springboardentitites/".repeat(200);
+
+        let mut stream = AZCS_SIGNATURE.to_vec();
+        stream.extend_from_slice(&ZSTD_ID.to_be_bytes());
+        stream.extend_from_slice(&(data.len() as u64).to_be_bytes());
+        stream.extend_from_slice(&1u32.to_be_bytes());
+        stream.extend_from_slice(&zstd::encode_all(data.as_slice(), 0).unwrap());
+        stream.extend_from_slice(&(HEADER_SIZE as u64).to_be_bytes());
+        stream.extend_from_slice(&0u64.to_be_bytes());
+
+        let mut decompressed = vec![];
+        decompress(&mut stream.as_slice())
+            .unwrap()
+            .read_to_end(&mut decompressed)
+            .unwrap();
+        assert_eq!(decompressed, data);
+    }
+
+    #[test]
+    fn writes_object_streams_compressed() {
+        let json = r#"{"name":"ObjectStream","version":3,"Objects":[]}"#;
+        let json: object_stream::JSONObjectStream = serde_json::from_str(json).unwrap();
+        let stream = ObjectStream::try_from(json).unwrap();
+
+        let mut buf = vec![];
+        write_object_stream(&stream, &mut buf).unwrap();
+        assert!(is_compressed(&buf));
+
+        let mut binary = vec![];
+        decompress(&mut buf.as_slice())
+            .unwrap()
+            .read_to_end(&mut binary)
+            .unwrap();
+        let mut expected = vec![];
+        stream.to_writer(&mut expected).unwrap();
+        assert_eq!(binary, expected);
+        assert!(object_stream::from_reader(&mut Cursor::new(&binary), None).is_ok());
+    }
+
+    #[test]
+    fn seek_points_start_flushed_chunks() {
+        let data = b"0123456789".repeat(10);
+        let mut buf = vec![];
+        write_zlib(&data, 40, &mut buf).unwrap();
+        assert_eq!(u32::from_be_bytes(buf[..4].try_into().unwrap()), 3);
+
+        // the last seek point resumes raw deflate at byte 80 after a full flush
+        let table = &buf[buf.len() - 16..];
+        let compressed = u64::from_be_bytes(table[..8].try_into().unwrap()) as usize;
+        let uncompressed = u64::from_be_bytes(table[8..].try_into().unwrap()) as usize;
+        assert_eq!(uncompressed, 80);
+        let payload = &buf[compressed - HEADER_SIZE + 4..buf.len() - 3 * 16];
+        let mut rest = vec![];
+        flate2::read::DeflateDecoder::new(payload)
+            .read_to_end(&mut rest)
+            .unwrap();
+        assert_eq!(rest, &data[80..]);
+    }
+}
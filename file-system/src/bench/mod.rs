@@ -0,0 +1,216 @@
+//! Decompression throughput of a sample of entries, without writing anything.
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    io,
+    path::PathBuf,
+    time::{Duration, Instant},
+};
+
+use rayon::{prelude::*, ThreadPoolBuilder};
+use serde::Serialize;
+use zip::ZipArchive;
+
+use crate::{
+    decompressor::{detect, Decompressor},
+    pak::{self, method_name, PakReader},
+    FileKind,
+};
+
+/// An entry picked for the benchmark.
+#[derive(Debug, Clone)]
+pub struct BenchEntry {
+    pub entry: &'static PathBuf,
+    pub pak: &'static PathBuf,
+    pub name: &'static str,
+    pub method: &'static str,
+    /// The kind by the entry's name, contents aren't read while sampling.
+    pub kind: FileKind,
+    pub compressed_size: u64,
+    pub size: u64,
+}
+
+/// How fast the entries of one compression method and file type decompressed.
+#[derive(Debug, Serialize)]
+pub struct GroupResult {
+    pub method: &'static str,
+    pub kind: FileKind,
+    pub entries: usize,
+    pub compressed_size: u64,
+    pub size: u64,
+    /// Time spent decompressing, summed over every thread.
+    pub seconds: f64,
+    /// Uncompressed bytes per second of `seconds`, the speed of a single thread.
+    pub bytes_per_sec: f64,
+}
+
+/// A run of the benchmark on `threads` threads.
+#[derive(Debug, Serialize)]
+pub struct BenchResult {
+    pub threads: usize,
+    pub entries: usize,
+    pub errors: usize,
+    pub size: u64,
+    /// Wall time of the run, including opening the paks.
+    pub seconds: f64,
+    /// Uncompressed bytes per second of wall time.
+    pub bytes_per_sec: f64,
+    pub groups: Vec<GroupResult>,
+}
+
+/// Picks up to `per_group` entries of every compression method and file type among `map`,
+/// spread evenly over the entries of each in path order so runs on the same paks pick the same
+/// entries.
+pub fn sample(
+    map: HashMap<&'static PathBuf, &'static (PathBuf, String)>,
+    per_group: usize,
+) -> io::Result<Vec<BenchEntry>> {
+    let mut paks: HashMap<&'static PathBuf, Vec<(&'static PathBuf, &'static str)>> = HashMap::new();
+    for (entry, (pak, name)) in map {
+        paks.entry(pak).or_default().push((entry, name.as_str()));
+    }
+
+    let entries = paks
+        .into_par_iter()
+        .map(|(pak_path, entries)| {
+            let mut archive = pak::archive(pak_path)?;
+            let mut sampled = Vec::with_capacity(entries.len());
+            for (entry, name) in entries {
+                let Some(index) = archive.index_for_name(name) else {
+                    continue;
+                };
+                let zip = archive.by_index_raw(index)?;
+                sampled.push(BenchEntry {
+                    entry,
+                    pak: pak_path,
+                    name,
+                    method: method_name(zip.compression()),
+                    kind: detect(&[], name),
+                    compressed_size: zip.compressed_size(),
+                    size: zip.size(),
+                });
+            }
+            Ok(sampled)
+        })
+        .collect::<io::Result<Vec<_>>>()?;
+
+    let mut groups: BTreeMap<(&str, FileKind), Vec<BenchEntry>> = BTreeMap::new();
+    for entry in entries.into_iter().flatten() {
+        groups
+            .entry((entry.method, entry.kind))
+            .or_default()
+            .push(entry);
+    }
+    Ok(groups
+        .into_values()
+        .flat_map(|mut entries| {
+            entries.sort_unstable_by(|a, b| a.entry.cmp(b.entry));
+            spread(entries, per_group)
+        })
+        .collect())
+}
+
+/// `count` of `items` at even steps, all of them when there aren't more.
+fn spread<T>(items: Vec<T>, count: usize) -> Vec<T> {
+    let len = items.len();
+    if len <= count {
+        return items;
+    }
+    items
+        .into_iter()
+        .enumerate()
+        .filter(|(i, _)| (i * count) % len < count)
+        .map(|(_, item)| item)
+        .collect()
+}
+
+/// Decompresses every entry on a pool of `threads` threads. Each thread opens a pak once, as
+/// extraction does, and only the decompression of entries counts towards the group times.
+pub fn run(entries: &[BenchEntry], threads: usize) -> io::Result<BenchResult> {
+    let pool = ThreadPoolBuilder::new()
+        .num_threads(threads)
+        .build()
+        .map_err(io::Error::other)?;
+
+    let started = Instant::now();
+    let timings = pool.install(|| {
+        entries
+            .par_iter()
+            .map_init(
+                HashMap::<&PathBuf, ZipArchive<PakReader>>::new,
+                |archives, entry| {
+                    if !archives.contains_key(entry.pak) {
+                        archives.insert(entry.pak, pak::archive(entry.pak).ok()?);
+                    }
+                    let archive = archives.get_mut(entry.pak)?;
+                    let index = archive.index_for_name(entry.name)?;
+                    let started = Instant::now();
+                    let mut zip = archive.by_index_raw(index).ok()?;
+                    Decompressor::try_new(&mut zip, None).ok()?;
+                    Some(started.elapsed())
+                },
+            )
+            .collect::<Vec<_>>()
+    });
+    let elapsed = started.elapsed();
+
+    let mut groups: BTreeMap<(&'static str, FileKind), (usize, u64, u64, Duration)> =
+        BTreeMap::new();
+    let mut errors = 0;
+    for (entry, timing) in entries.iter().zip(timings) {
+        let Some(timing) = timing else {
+            errors += 1;
+            continue;
+        };
+        let group = groups.entry((entry.method, entry.kind)).or_default();
+        group.0 += 1;
+        group.1 += entry.compressed_size;
+        group.2 += entry.size;
+        group.3 += timing;
+    }
+
+    let groups = groups
+        .into_iter()
+        .map(
+            |((method, kind), (entries, compressed_size, size, busy))| GroupResult {
+                method,
+                kind,
+                entries,
+                compressed_size,
+                size,
+                seconds: busy.as_secs_f64(),
+                bytes_per_sec: per_sec(size, busy),
+            },
+        )
+        .collect::<Vec<_>>();
+    let size = groups.iter().map(|group| group.size).sum();
+    Ok(BenchResult {
+        threads,
+        entries: entries.len(),
+        errors,
+        size,
+        seconds: elapsed.as_secs_f64(),
+        bytes_per_sec: per_sec(size, elapsed),
+        groups,
+    })
+}
+
+fn per_sec(bytes: u64, time: Duration) -> f64 {
+    match time.as_secs_f64() {
+        seconds if seconds > 0.0 => bytes as f64 / seconds,
+        _ => 0.0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn spreads_evenly() {
+        assert_eq!(spread((0..10).collect(), 3), [0, 4, 7]);
+        assert_eq!(spread((0..10).collect(), 5), [0, 2, 4, 6, 8]);
+        assert_eq!(spread((0..2).collect(), 5), [0, 1]);
+        assert_eq!(spread((0..10).collect(), 0), Vec::<i32>::new());
+    }
+}
@@ -0,0 +1,114 @@
+//! Object streams parsed by earlier runs, with their class and field names resolved, kept on disk
+//! by the CRC32 of their contents for `extract --parse-cache`.
+
+use std::{
+    io,
+    path::{Path, PathBuf},
+    sync::OnceLock,
+};
+
+use cli::commands::Commands;
+use object_stream::ObjectStream;
+
+use crate::command;
+
+/// Bumped whenever the cached form of [`ObjectStream`] changes.
+const VERSION: u32 = 1;
+
+/// A directory of parsed object streams.
+#[derive(Debug)]
+pub struct ParseCache {
+    dir: PathBuf,
+}
+
+impl ParseCache {
+    /// A cache in `dir`. Names are resolved with the hash dictionary, so `dictionary` tells apart
+    /// streams parsed with different ones, e.g. the number of names it holds.
+    pub fn new(dir: &Path, dictionary: &str) -> Self {
+        Self {
+            dir: dir.join(format!("objectstreams-v{VERSION}-{dictionary}")),
+        }
+    }
+
+    /// The stream of contents with `crc32` and `size` when it was cached, `None` when it wasn't
+    /// or the file can't be read.
+    pub fn get(&self, crc32: u32, size: usize) -> Option<ObjectStream> {
+        let buf = std::fs::read(self.path(crc32, size)).ok()?;
+        let buf = zstd::decode_all(buf.as_slice()).ok()?;
+        rmp_serde::from_slice(&buf).ok()
+    }
+
+    /// Caches `stream`, parsed from contents with `crc32` and `size`. The file is written next to
+    /// its final name first so a concurrent run never reads it half written.
+    pub fn insert(&self, crc32: u32, size: usize, stream: &ObjectStream) -> io::Result<()> {
+        let buf = rmp_serde::to_vec(stream).map_err(io::Error::other)?;
+        let buf = zstd::encode_all(buf.as_slice(), 1)?;
+        std::fs::create_dir_all(&self.dir)?;
+        let path = self.path(crc32, size);
+        let partial = path.with_extension(format!("{}.part", std::process::id()));
+        std::fs::write(&partial, buf)?;
+        std::fs::rename(&partial, &path).inspect_err(|_| {
+            let _ = std::fs::remove_file(&partial);
+        })
+    }
+
+    fn path(&self, crc32: u32, size: usize) -> PathBuf {
+        self.dir.join(format!("{crc32:08x}-{size:x}.msgpack.zst"))
+    }
+}
+
+/// The cache of `--parse-cache`, in `--parse-cache-dir` or the user's cache directory.
+pub fn parse_cache() -> Option<&'static ParseCache> {
+    static CACHE: OnceLock<Option<ParseCache>> = OnceLock::new();
+    CACHE
+        .get_or_init(|| {
+            let Some(Commands::Extract(cmd)) = command() else {
+                return None;
+            };
+            if !cmd.parse_cache {
+                return None;
+            }
+            let dir = cmd
+                .parse_cache_dir
+                .to_owned()
+                .or_else(|| dirs::cache_dir().map(|dir| dir.join("nwtools")))?;
+            let hashes = &crate::FILESYSTEM.get()?.hashes;
+            let dictionary = format!("{}-{}", hashes.crcs.len(), hashes.uuids.len());
+            Some(ParseCache::new(&dir, &dictionary))
+        })
+        .as_ref()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use object_stream::{from_reader, JSONObjectStream};
+
+    use super::*;
+
+    #[test]
+    fn round_trips_parsed_streams() {
+        let dir = std::env::temp_dir().join("nwtools-parse-cache-test");
+        let _ = std::fs::remove_dir_all(&dir);
+        let cache = ParseCache::new(&dir, "0-0");
+
+        // an int with a 4 byte inline value
+        let mut binary = vec![0x00, 0x00, 0x00, 0x00, 0x03, (1 << 3) | (1 << 4) | 4];
+        binary.extend(utils::types::INT.as_u128().to_be_bytes());
+        binary.extend(100i32.to_be_bytes());
+        binary.extend([0, 0]);
+        let crc32 = crc32fast::hash(&binary);
+
+        assert!(cache.get(crc32, binary.len()).is_none());
+        let stream = from_reader(&mut Cursor::new(&binary), None).unwrap();
+        cache.insert(crc32, binary.len(), &stream).unwrap();
+
+        let cached = cache.get(crc32, binary.len()).unwrap();
+        assert!(cache.get(crc32, binary.len() + 1).is_none());
+        assert_eq!(
+            serde_json::to_string(&JSONObjectStream::from(cached)).unwrap(),
+            serde_json::to_string(&JSONObjectStream::from(stream)).unwrap()
+        );
+    }
+}
@@ -0,0 +1,104 @@
+//! Threads converting buffered entries, apart from the workers reading and writing them.
+
+use std::{
+    any::Any,
+    panic::{self, AssertUnwindSafe},
+    sync::{mpsc, Arc, Mutex},
+    thread::{self, Scope},
+};
+
+type Job<'scope> = Box<dyn FnOnce() + Send + 'scope>;
+
+/// A fixed number of threads running jobs one at a time, in the order they were queued.
+///
+/// Workers hand a conversion over with [`CpuPool::run`] and block until it's done. Unlike a rayon
+/// pool, a worker waiting for its job doesn't pick up other entries meanwhile, which could
+/// otherwise leave it stuck behind a throttle while still holding the permit of the entry it
+/// started with.
+pub struct CpuPool<'scope> {
+    jobs: mpsc::Sender<Job<'scope>>,
+}
+
+impl<'scope> CpuPool<'scope> {
+    /// Starts `threads` threads in `scope`. They stop once the pool is dropped and the queued
+    /// jobs are done.
+    pub fn new<'env>(scope: &'scope Scope<'scope, 'env>, threads: usize) -> Self {
+        let (jobs, queue) = mpsc::channel::<Job<'scope>>();
+        let queue = Arc::new(Mutex::new(queue));
+        for i in 0..threads.max(1) {
+            let queue = queue.clone();
+            std::thread::Builder::new()
+                .name(format!("convert-{i}"))
+                .spawn_scoped(scope, move || loop {
+                    let job = queue.lock().unwrap().recv();
+                    match job {
+                        Ok(job) => job(),
+                        Err(_) => break,
+                    }
+                })
+                .expect("couldn't start a conversion thread");
+        }
+        Self { jobs }
+    }
+
+    /// Runs `job` on a thread of the pool and returns what it returned. A panic in `job` is
+    /// returned as the error, for the caller to record as a failed entry or resume.
+    pub fn run<F, T>(&self, job: F) -> thread::Result<T>
+    where
+        F: FnOnce() -> T + Send + 'scope,
+        T: Send + 'scope,
+    {
+        let (done, result) = mpsc::sync_channel(1);
+        self.jobs
+            .send(Box::new(move || {
+                let _ = done.send(panic::catch_unwind(AssertUnwindSafe(job)));
+            }))
+            .expect("conversion threads stopped");
+        result.recv().expect("conversion thread stopped")
+    }
+}
+
+/// The message a panic was raised with, when it was given one.
+pub fn panic_message(payload: &(dyn Any + Send)) -> &str {
+    match (
+        payload.downcast_ref::<&str>(),
+        payload.downcast_ref::<String>(),
+    ) {
+        (Some(message), _) => message,
+        (_, Some(message)) => message,
+        _ => "panicked",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn runs_jobs_on_its_threads() {
+        let outer = vec![1, 2, 3];
+        std::thread::scope(|scope| {
+            let pool = CpuPool::new(scope, 2);
+            let outer = &outer;
+            let name = pool
+                .run(|| std::thread::current().name().map(str::to_string))
+                .unwrap();
+            assert!(name.is_some_and(|name| name.starts_with("convert-")));
+
+            let owned = vec![4, 5];
+            let (owned, sum) = pool
+                .run(move || {
+                    let sum = outer.iter().chain(&owned).sum::<i32>();
+                    (owned, sum)
+                })
+                .unwrap();
+            assert_eq!((owned.len(), sum), (2, 15));
+
+            let panicked = pool.run(|| panic!("bad")).unwrap_err();
+            assert_eq!(panic_message(&*panicked), "bad");
+            let panicked = pool.run(|| panic!("bad {}", 1)).unwrap_err();
+            assert_eq!(panic_message(&*panicked), "bad 1");
+            assert_eq!(pool.run(|| 1).unwrap(), 1);
+        });
+    }
+}
@@ -1,51 +1,107 @@
 use crate::{
     azcs::{self, is_azcs},
-    FileType, FILESYSTEM,
+    cache::parse_cache,
+    command, handle_extension, handler, material, sprite, terrain, AssetResolver, FileKind,
+    FileType, ASSETS, FILESYSTEM,
 };
 use cli::{
     commands::Commands,
     common::{
-        datasheet::DatasheetFormat, dds::DDSFormat, distribution::DistributionFormat,
-        objectstream::ObjectStreamFormat, vshapec::VShapeFormat,
+        audio::AudioFormat,
+        datasheet::{DatasheetFormat, SqlDialect},
+        dds::DDSFormat,
+        distribution::DistributionFormat,
+        lua::LuaFormat,
+        material::MaterialFormat,
+        mesh::MeshFormat,
+        objectstream::ObjectStreamFormat,
+        terrain::TerrainFormat,
+        vshapec::VShapeFormat,
     },
-    ARGS,
 };
 use dashmap::DashMap;
-use datasheet::Datasheet;
+use datasheet::{resolve::Resolver, Datasheet, XMLDatasheet};
 use flate2::Decompress;
 use image_dds::ImageFormat;
-use luac_parser::*;
-use object_stream::{from_reader, JSONObjectStream, XMLObjectStream};
+use object_stream::{from_reader, JSONObjectStream, ObjectStream, XMLObjectStream};
 use quick_xml::se::Serializer;
 use rayon::prelude::*;
 use serde::Serialize;
-use std::io::{self, Cursor, Read, Seek, Write};
+use serde_json::Value;
+use std::{
+    borrow::Cow,
+    io::{self, Cursor, Read, Write},
+    path::{Path, PathBuf},
+    sync::LazyLock,
+};
 use tracing::Instrument;
 use vshapec;
 use zip::{read::ZipFile, CompressionMethod};
 
+/// The zip method id LZ4 entries are read under. APPNOTE registers no id for LZ4, so this takes
+/// one it leaves unassigned.
+pub(crate) const LZ4_METHOD: u16 = 21;
+/// Leading bytes decompressed to detect the kind of a streamed entry.
+const HEAD_SIZE: u64 = 64;
+
+/// Result of [`Decompressor::try_stream`].
+pub enum Streamed<'a> {
+    /// The entry was written unchanged, with the number of bytes written.
+    Written(u64),
+    Buffered(Decompressor<'a>),
+}
+
 #[derive()]
-pub struct Decompressor<'a, 'b> {
+pub struct Decompressor<'a> {
     localization: Option<&'a DashMap<String, Option<String>>>,
-    zip: &'a mut ZipFile<'b>,
+    locales: Option<&'a [(String, DashMap<String, Option<String>>)]>,
+    resolver: Option<&'a Resolver>,
+    /// Name of the entry in its pak.
+    name: String,
+    /// Size and CRC32 of the entry as recorded in the pak's central directory. Only these are
+    /// kept of the zip entry once it's read, so a decompressor can be converted on another
+    /// thread.
+    size: u64,
+    expected: u32,
     buf: Vec<u8>,
+    crc32: u32,
 }
 
-impl<'a, 'b> Decompressor<'a, 'b> {
+impl<'a> Decompressor<'a> {
     /// Creates a new [`Decompressor`].
     pub fn try_new(
-        zip: &'a mut ZipFile<'b>,
+        zip: &mut ZipFile<'_>,
         localization: Option<&'a DashMap<String, Option<String>>>,
     ) -> io::Result<Self> {
-        let size = zip.size() as usize;
         let mut value = Self {
             localization,
-            zip,
-            buf: Vec::with_capacity(size),
+            locales: None,
+            resolver: None,
+            name: zip.name().to_owned(),
+            size: zip.size(),
+            expected: zip.crc32(),
+            buf: Vec::with_capacity(zip.size() as usize),
+            crc32: 0,
         };
-        value.decompress()?;
+        value.decompress(zip)?;
         Ok(value)
     }
+
+    /// A decompressor of an entry already read, `buf` being its decompressed contents.
+    pub fn from_buf(name: &str, buf: Vec<u8>) -> Self {
+        let crc32 = crc32fast::hash(&buf);
+        Self {
+            localization: None,
+            locales: None,
+            resolver: None,
+            name: name.to_owned(),
+            size: buf.len() as u64,
+            expected: crc32,
+            buf,
+            crc32,
+        }
+    }
+
     // pub fn with_buf(
     //     zip: &'a mut ZipFile<'b>,
     //     localization: &'a Option<DashMap<String, Option<String>>>,
@@ -58,57 +114,107 @@ impl<'a, 'b> Decompressor<'a, 'b> {
     //         buf,
     //     }
     // }
-    pub fn decompress(&mut self) -> io::Result<()> {
-        if self.zip.size() == 0 {
+    pub fn with_localization(&mut self, localization: Option<&'a DashMap<String, Option<String>>>) {
+        self.localization = localization;
+    }
+
+    /// Adds a column per locale to datasheets instead of inlining a single locale.
+    pub fn with_locales(
+        &mut self,
+        locales: Option<&'a [(String, DashMap<String, Option<String>>)]>,
+    ) {
+        self.locales = locales;
+    }
+
+    /// Resolves references between datasheets with `resolver` when converting them.
+    pub fn with_resolver(&mut self, resolver: Option<&'a Resolver>) {
+        self.resolver = resolver;
+    }
+
+    #[tracing::instrument(level = "trace", skip_all)]
+    fn decompress(&mut self, zip: &mut ZipFile<'_>) -> io::Result<()> {
+        if self.size == 0 {
             return Ok(());
         }
 
-        match self.zip.compression() {
-            CompressionMethod::Stored => std::io::copy(&mut self.zip, &mut self.buf),
-            CompressionMethod::Deflated => {
-                let mut bytes = [0; 2];
-                self.zip.read_exact(&mut bytes)?;
-                if [0x78, 0xda] == bytes {
-                    let mut zip = flate2::read::ZlibDecoder::new_with_decompress(
-                        Cursor::new(bytes).chain(&mut self.zip),
-                        Decompress::new(true),
-                    );
-                    std::io::copy(&mut zip, &mut self.buf)
-                } else {
-                    let mut zip =
-                        flate2::read::DeflateDecoder::new(Cursor::new(bytes).chain(&mut self.zip));
-                    std::io::copy(&mut zip, &mut self.buf)
-                }
-            }
-            #[allow(deprecated)]
-            CompressionMethod::Unsupported(15) => {
-                let mut compressed = vec![];
-                std::io::copy(self.zip, &mut compressed)?;
-                self.buf.resize(self.zip.size() as usize, 0);
-
-                oodle_safe::decompress(
-                    &compressed,
-                    &mut self.buf,
-                    None,
-                    None,
-                    None,
-                    Some(oodle_safe::DecodeThreadPhase::All),
-                )
-                .map(|size| size as u64)
-                .map_err(|_| io::Error::other(format!("Error with oodle_safe::decompress.",)))
+        std::io::copy(&mut reader(zip)?, &mut self.buf)?;
+        self.finish()
+    }
+
+    /// Writes entries that are not converted straight from the pak to `writer` in chunks instead
+    /// of buffering them whole. Entries that need converting, or AZCS decompression to tell
+    /// their kind, are buffered as usual and returned without writing anything.
+    pub fn try_stream<W: Write>(zip: &mut ZipFile<'_>, writer: &mut W) -> io::Result<Streamed<'a>> {
+        if zip.size() == 0 {
+            return Self::try_new(zip, None).map(Streamed::Buffered);
+        }
+
+        let name = zip.name().to_owned();
+        let size = zip.size() as usize;
+        let mut buf = Vec::with_capacity(HEAD_SIZE as usize);
+        {
+            let mut reader = reader(zip)?;
+            (&mut reader).take(HEAD_SIZE).read_to_end(&mut buf)?;
+
+            if !azcs::is_compressed(&buf)
+                && !scripted()
+                && file_type_for(&buf, &name).is_passthrough()
+            {
+                writer.write_all(&buf)?;
+                let rest = std::io::copy(&mut reader, writer)?;
+                return Ok(Streamed::Written(buf.len() as u64 + rest));
             }
-            _ => Err(io::Error::new(
-                io::ErrorKind::Other,
-                "CompressionMethod not supported",
-            )),
-        }?;
+            buf.reserve(size.saturating_sub(buf.len()));
+            reader.read_to_end(&mut buf)?;
+        }
 
-        let mut sig = self.buf[..4].try_into().unwrap();
+        let mut value = Self {
+            localization: None,
+            locales: None,
+            resolver: None,
+            name,
+            size: size as u64,
+            expected: zip.crc32(),
+            buf,
+            crc32: 0,
+        };
+        value.finish()?;
+        Ok(Streamed::Buffered(value))
+    }
+
+    /// Detects the kind of an entry from its leading bytes without decompressing all of it.
+    pub fn peek(zip: &mut ZipFile<'_>) -> io::Result<FileKind> {
+        let name = zip.name().to_owned();
+        if zip.size() == 0 {
+            return Ok(detect(&[], &name));
+        }
+
+        let mut reader = reader(zip)?;
+        let mut head = Vec::with_capacity(HEAD_SIZE as usize);
+        (&mut reader).take(HEAD_SIZE).read_to_end(&mut head)?;
+        if azcs::is_compressed(&head) {
+            let mut inner = Cursor::new(head).chain(reader);
+            let mut azcs = azcs::decompress(&mut inner)?;
+            let mut head = Vec::with_capacity(HEAD_SIZE as usize);
+            (&mut azcs).take(HEAD_SIZE).read_to_end(&mut head)?;
+            return Ok(detect(&head, &name));
+        }
+        Ok(detect(&head, &name))
+    }
+
+    /// Checksums the decompressed entry and unwraps AZCS compressed ones.
+    fn finish(&mut self) -> io::Result<()> {
+        self.crc32 = crc32fast::hash(&self.buf);
+
+        let Some(sig) = self.buf.get(..4) else {
+            return Ok(());
+        };
+        let mut sig = sig.try_into().unwrap();
         if is_azcs(&mut sig) {
-            let mut tmp = Vec::with_capacity(self.zip.size() as usize);
+            let mut tmp = Vec::with_capacity(self.size as usize);
             {
                 let mut slice = &mut self.buf.as_slice();
-                let mut reader = azcs::decompress(&mut slice).unwrap();
+                let mut reader = azcs::decompress(&mut slice)?;
                 std::io::copy(&mut reader, &mut tmp)?;
             }
             self.buf = tmp;
@@ -117,242 +223,255 @@ impl<'a, 'b> Decompressor<'a, 'b> {
         Ok(())
     }
 
+    /// The texture with the mips streamed from its siblings put back in place. The entry holds
+    /// the header followed by the smallest mips, which stay resident, while `<name>.1` to
+    /// `<name>.N` hold the larger ones, the highest numbered the largest. An attached alpha is
+    /// split the same way into `<name>.a` and `<name>.1a` to `<name>.Na`, and follows the
+    /// texture. Without a [`FileSystem`](crate::FileSystem) to read the siblings from, as when
+    /// embedded, the entry is returned as is.
+    fn merged(&self) -> io::Result<Vec<u8>> {
+        let Some(fs) = FILESYSTEM.get() else {
+            return Ok(self.buf.clone());
+        };
+        let name = self.name.as_str();
+        let streams = fs
+            .files(Some(&format!("{}.*", name)))
+            .into_keys()
+            .filter_map(|path| {
+                let part = path.to_str()?.strip_prefix(name)?.strip_prefix('.')?;
+                Some((part.to_owned(), path))
+            })
+            .collect::<Vec<_>>();
+
+        let mut buf = with_mips(&self.buf, split_mips(&streams, "")?);
+        if let Some((_, alpha)) = streams.iter().find(|(part, _)| part == "a") {
+            buf.extend(with_mips(&fs.open(alpha)?, split_mips(&streams, "a")?));
+        }
+        Ok(buf)
+    }
+
+    /// Decodes the DDS texture (BC1 to BC7 and uncompressed) and encodes its top mip as `format`.
+    /// With `--dds-sprites`, the cells of an atlas with a `.sprite` layout are cut out into
+    /// `extra`.
+    fn texture<W>(
+        &self,
+        format: image::ImageFormat,
+        writer: &mut W,
+        extra: &mut Option<Metadata<'_>>,
+    ) -> io::Result<u64>
+    where
+        W: Write,
+    {
+        let buf = self.merged()?;
+        let dds = ddsfile::Dds::read(&mut buf.as_slice()).map_err(io::Error::other)?;
+        let image = image_dds::image_from_dds(&dds, 0).map_err(io::Error::other)?;
+
+        let image = image::DynamicImage::ImageRgba8(image);
+        if let Some(layout) = self.sprite_layout()? {
+            let cells = sprite::cells(&layout, image.width(), image.height())?;
+            *extra = Some(Metadata::Sprites(sprite::slice(&image, &cells)?));
+        }
+
+        let mut buf = Cursor::new(Vec::with_capacity(image.as_bytes().len()));
+        image.write_to(&mut buf, format).map_err(io::Error::other)?;
+        buf.set_position(0);
+        std::io::copy(&mut buf, writer)
+    }
+
+    /// The `.sprite` layout beside the texture when `--dds-sprites` is set.
+    fn sprite_layout(&self) -> io::Result<Option<Vec<u8>>> {
+        let (Some(Commands::Extract(cmd)), Some(fs)) = (command(), FILESYSTEM.get()) else {
+            return Ok(None);
+        };
+        if !cmd.dds.dds_sprites {
+            return Ok(None);
+        }
+        let layout = Path::new(&self.name).with_extension("sprite");
+        if fs.location(&layout).is_none() {
+            return Ok(None);
+        }
+        fs.open(layout).map(Some)
+    }
+
     pub fn size(&mut self) {}
 
     pub fn compressed_size(&mut self) {}
 
+    /// CRC32 of the entry as stored in the pak, computed before any AZCS decompression.
+    pub fn crc32(&self) -> u32 {
+        self.crc32
+    }
+
+    /// Checks the decompressed entry against the CRC32 recorded in the pak's central directory.
+    pub fn verify(&self) -> io::Result<()> {
+        let expected = self.expected;
+        if self.crc32 != expected {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "CRC mismatch: expected 0x{:08x}, got 0x{:08x}",
+                    expected, self.crc32
+                ),
+            ));
+        }
+        Ok(())
+    }
+
+    pub fn kind(&self) -> FileKind {
+        detect(&self.buf, &self.name)
+    }
+
+    /// The entry parsed as an object stream, from `--parse-cache` when an earlier run parsed it.
+    fn object_stream(&self) -> io::Result<ObjectStream> {
+        let cache = parse_cache();
+        if let Some(obj_stream) = cache.and_then(|cache| cache.get(self.crc32, self.buf.len())) {
+            return Ok(obj_stream);
+        }
+        let hashes = FILESYSTEM.get().map(|fs| &fs.hashes);
+        let obj_stream = from_reader(&mut self.buf.as_slice(), hashes)?;
+        if let Some(cache) = cache {
+            if let Err(e) = cache.insert(self.crc32, self.buf.len(), &obj_stream) {
+                tracing::warn!(
+                    "{}: couldn't cache the parsed object stream: {e}",
+                    self.name
+                );
+            }
+        }
+        Ok(obj_stream)
+    }
+
+    /// The output type of the entry, a registered [`handler`] detecting it before the formats
+    /// configured on the command line.
     pub fn file_type(&self) -> io::Result<FileType> {
-        let _type = match (self.buf.as_slice(), self.zip.name()) {
-            ([0x04, 0x00, 0x1B, 0x4C, 0x75, ..], _) => match &ARGS.command {
-                Commands::Extract(cmd) => FileType::Luac(cmd.luac),
-                _ => unreachable!(),
-            },
-            ([0x00, 0x00, 0x00, 0x00, 0x03, ..], _) => match &ARGS.command {
-                Commands::Extract(extract) => {
-                    FileType::ObjectStream(&extract.objectstream.objectstream)
-                }
-                _ => unreachable!(),
-            },
-            ([0x11, 0x00, 0x00, 0x00, ..], _) => match &ARGS.command {
-                Commands::Extract(extract) => FileType::Datasheet(&extract.datasheet.datasheet),
-                _ => unreachable!(),
-            },
-            (_, n) if n.ends_with(".distribution") => match &ARGS.command {
-                Commands::Extract(cmd) => FileType::Distribution(&cmd.distribution.distribution),
-                _ => unreachable!(),
-            },
-            (_, n) if n.ends_with(".vshapec") => match &ARGS.command {
-                Commands::Extract(cmd) => FileType::VShapeC(&cmd.vshapec.vshapec),
-                _ => unreachable!(),
-            },
-            (_, n) if n.ends_with(".dds") => match &ARGS.command {
-                Commands::Extract(cmd) => FileType::DDS(&cmd.dds.dds),
-                _ => unreachable!(),
-            },
-            _ => FileType::default(),
-        };
+        Ok(file_type_for(&self.buf, &self.name))
+    }
 
-        Ok(_type)
+    pub fn to_writer<W: Write>(&self, writer: &'_ mut W) -> io::Result<Option<Metadata<'a>>> {
+        self.write_as(&self.file_type()?, writer)
     }
 
-    pub fn to_writer<W: Write>(&self, writer: &'_ mut W) -> io::Result<Option<Metadata<'_>>> {
-        let file_type = self.file_type()?;
+    /// Converts the entry as `file_type` regardless of the format configured on the command line.
+    #[tracing::instrument(level = "trace", skip_all, fields(format = %file_type.format_name()))]
+    pub fn write_as<W: Write>(
+        &self,
+        file_type: &FileType,
+        writer: &'_ mut W,
+    ) -> io::Result<Option<Metadata<'a>>> {
         let mut extra = None;
 
-        let _size = match &file_type {
-            FileType::Luac(b) => {
+        let _size = match file_type {
+            FileType::Luac(fmt) => {
                 let mut buf = &self.buf[2..];
-                match b {
-                    true => {
-                        // let mut byte_code = luac_parser::parse(buf).unwrap();
-
-                        // let msg_pack = byte_code.to_msgpack().unwrap();
-                        // let mut pack = msg_pack.as_slice();
-                        std::io::copy(&mut buf, writer)
-                    }
-                    false => std::io::copy(&mut buf, writer),
+                let text = match fmt {
+                    LuaFormat::BYTES => None,
+                    LuaFormat::LUA => luac::decompile(buf)
+                        .inspect_err(|e| {
+                            tracing::warn!(
+                                "{}: couldn't decompile, writing the bytecode: {e}",
+                                self.name
+                            )
+                        })
+                        .ok(),
+                    LuaFormat::ASM => luac::disassemble(buf)
+                        .inspect_err(|e| {
+                            tracing::warn!(
+                                "{}: couldn't disassemble, writing the bytecode: {e}",
+                                self.name
+                            )
+                        })
+                        .ok(),
+                };
+                match text {
+                    Some(text) => writer.write_all(text.as_bytes()).map(|_| text.len() as u64),
+                    None => std::io::copy(&mut buf, writer),
                 }
             }
             FileType::DDS(fmt) => match fmt {
-                DDSFormat::BYTES => std::io::copy(&mut self.buf.as_slice(), writer),
-                DDSFormat::PNG => {
-                    let fs = FILESYSTEM.get().unwrap();
-                    let mut files = fs
-                        .files(Some(&format!("{}.*", self.zip.name())))
-                        .into_iter()
-                        .collect::<Vec<_>>();
-
-                    files.sort_unstable_by(|(s, _), (s2, _)| {
-                        let s_is_a = s.to_str().map_or(false, |name| name.ends_with(".a"));
-                        let s2_is_a = s2.to_str().map_or(false, |name| name.ends_with(".a"));
-
-                        match (s_is_a, s2_is_a) {
-                            (true, false) => std::cmp::Ordering::Less,
-                            (false, true) => std::cmp::Ordering::Greater,
-                            _ => {
-                                natord::compare(s.to_str().expect("msg"), s2.to_str().expect("msg"))
-                            }
-                        }
-                    });
-
-                    let mut buf = self.buf.clone();
-
-                    buf.extend(
-                        files
-                            .into_par_iter()
-                            .map(|(p, _)| fs.open(p).unwrap())
-                            .flatten()
-                            .collect::<Vec<u8>>()
-                            .into_iter(),
-                    );
-
-                    let mut buf = Cursor::new(buf);
-                    let dds = ddsfile::Dds::read(&mut buf).unwrap();
-                    let mut image = image_dds::image_from_dds(&dds, 0).unwrap();
-
-                    let mut buf = Cursor::new(Vec::with_capacity(image.len()));
-                    image.write_to(&mut buf, image::ImageFormat::Png);
-                    buf.set_position(0);
-                    std::io::copy(&mut buf, writer)
+                DDSFormat::BYTES | DDSFormat::FLAT => {
+                    std::io::copy(&mut self.merged()?.as_slice(), writer)
                 }
-                DDSFormat::JPEG => {
-                    let fs = FILESYSTEM.get().unwrap();
-                    let mut files = fs
-                        .files(Some(&format!("{}.*", self.zip.name())))
-                        .into_iter()
-                        .collect::<Vec<_>>();
-
-                    files.sort_unstable_by(|(s, _), (s2, _)| {
-                        let s_is_a = s.to_str().map_or(false, |name| name.ends_with(".a"));
-                        let s2_is_a = s2.to_str().map_or(false, |name| name.ends_with(".a"));
-
-                        match (s_is_a, s2_is_a) {
-                            (true, false) => std::cmp::Ordering::Less,
-                            (false, true) => std::cmp::Ordering::Greater,
-                            _ => {
-                                natord::compare(s.to_str().expect("msg"), s2.to_str().expect("msg"))
-                            }
-                        }
-                    });
-
-                    let mut buf = self.buf.clone();
-
-                    buf.extend(
-                        files
-                            .into_par_iter()
-                            .map(|(p, _)| fs.open(p).unwrap())
-                            .flatten()
-                            .collect::<Vec<u8>>()
-                            .into_iter(),
-                    );
-
-                    let mut buf = Cursor::new(buf);
-                    let dds = ddsfile::Dds::read(&mut buf).unwrap();
-                    let mut image = image_dds::image_from_dds(&dds, 0).unwrap();
-
-                    let mut buf = Cursor::new(Vec::with_capacity(image.len()));
-                    image.write_to(&mut buf, image::ImageFormat::Jpeg);
-                    buf.set_position(0);
-                    std::io::copy(&mut buf, writer)
+                DDSFormat::PNG => self.texture(image::ImageFormat::Png, writer, &mut extra),
+                DDSFormat::JPEG => self.texture(image::ImageFormat::Jpeg, writer, &mut extra),
+                DDSFormat::WEBP => self.texture(image::ImageFormat::WebP, writer, &mut extra),
+            },
+            FileType::Mesh(fmt) => match fmt {
+                MeshFormat::BYTES => std::io::copy(&mut self.buf.as_slice(), writer),
+                MeshFormat::GLB => {
+                    let model = mesh::Model::from_bytes(&self.buf)?;
+                    let buf = mesh::to_glb(&model)?;
+                    writer.write_all(&buf).map(|_| buf.len() as u64)
                 }
-                DDSFormat::WEBP => {
-                    let fs = FILESYSTEM.get().unwrap();
-                    let mut files = fs
-                        .files(Some(&format!("{}.*", self.zip.name())))
-                        .into_iter()
-                        .collect::<Vec<_>>();
-
-                    files.sort_unstable_by(|(s, _), (s2, _)| {
-                        let s_is_a = s.to_str().map_or(false, |name| name.ends_with(".a"));
-                        let s2_is_a = s2.to_str().map_or(false, |name| name.ends_with(".a"));
-
-                        match (s_is_a, s2_is_a) {
-                            (true, false) => std::cmp::Ordering::Less,
-                            (false, true) => std::cmp::Ordering::Greater,
-                            _ => {
-                                natord::compare(s.to_str().expect("msg"), s2.to_str().expect("msg"))
-                            }
-                        }
-                    });
-
-                    let mut buf = self.buf.clone();
-
-                    buf.extend(
-                        files
-                            .into_par_iter()
-                            .map(|(p, _)| fs.open(p).unwrap())
-                            .flatten()
-                            .collect::<Vec<u8>>()
-                            .into_iter(),
-                    );
-
-                    let mut buf = Cursor::new(buf);
-                    let dds = ddsfile::Dds::read(&mut buf).unwrap();
-                    let mut image = image_dds::image_from_dds(&dds, 0).unwrap();
-
-                    let mut buf = Cursor::new(Vec::with_capacity(image.len()));
-                    image.write_to(&mut buf, image::ImageFormat::WebP);
-                    buf.set_position(0);
-                    std::io::copy(&mut buf, writer)
+            },
+            FileType::Material(fmt) => match fmt {
+                MaterialFormat::BYTES => std::io::copy(&mut self.buf.as_slice(), writer),
+                MaterialFormat::JSON => {
+                    let materials = material::from_bytes(&self.buf, extracted_texture)?;
+                    let buf = serde_json::to_vec_pretty(&serde_json::json!({
+                        "materials": materials,
+                    }))?;
+                    writer.write_all(&buf).map(|_| buf.len() as u64)
+                }
+            },
+            FileType::Terrain(fmt) => match fmt {
+                TerrainFormat::BYTES => std::io::copy(&mut self.buf.as_slice(), writer),
+                TerrainFormat::PNG => {
+                    let (png, tile) = terrain::to_png(&self.buf, &self.name)?;
+                    extra = Some(Metadata::Tile(tile));
+                    writer.write_all(&png).map(|_| png.len() as u64)
                 }
-                DDSFormat::FLAT => {
-                    let fs = FILESYSTEM.get().unwrap();
-                    let mut files = fs
-                        .files(Some(&format!("{}.*", self.zip.name())))
+            },
+            FileType::Audio(fmt) => match (fmt, audio::is_soundbank(&self.buf)) {
+                (AudioFormat::BYTES, _) => std::io::copy(&mut self.buf.as_slice(), writer),
+                // the bank itself stays as is, its embedded files are written beside it
+                (fmt, true) => {
+                    let files = audio::soundbank(&self.buf)?
                         .into_iter()
-                        .collect::<Vec<_>>();
-
-                    files.sort_unstable_by(|(s, _), (s2, _)| {
-                        let s_is_a = s.to_str().map_or(false, |name| name.ends_with(".a"));
-                        let s2_is_a = s2.to_str().map_or(false, |name| name.ends_with(".a"));
-
-                        match (s_is_a, s2_is_a) {
-                            (true, false) => std::cmp::Ordering::Less,
-                            (false, true) => std::cmp::Ordering::Greater,
-                            _ => {
-                                natord::compare(s.to_str().expect("msg"), s2.to_str().expect("msg"))
-                            }
-                        }
-                        // natord::compare(s.to_str().expect("msg"), s2.to_str().expect("msg"))
-                    });
-                    files.reverse();
-
-                    let mut buf = self.buf.clone();
-
-                    buf.extend(
-                        files
-                            .into_par_iter()
-                            .map(|(p, _)| fs.open(p).unwrap())
-                            .flatten()
-                            .collect::<Vec<u8>>()
-                            .into_iter(),
-                    );
-
-                    let mut buf = Cursor::new(buf);
-                    std::io::copy(&mut buf, writer)
+                        .map(|(id, wem)| {
+                            let (ext, buf) = convert_wem(fmt, wem)?;
+                            Ok((format!("{id}.{ext}"), buf))
+                        })
+                        .collect::<io::Result<Vec<_>>>()?;
+                    extra = Some(Metadata::Soundbank(files));
+                    std::io::copy(&mut self.buf.as_slice(), writer)
+                }
+                (fmt, false) => {
+                    let (ext, buf) = convert_wem(fmt, &self.buf)?;
+                    extra = Some(Metadata::Audio(ext));
+                    writer.write_all(&buf).map(|_| buf.len() as u64)
                 }
             },
             FileType::Distribution(fmt) => match fmt {
                 DistributionFormat::MINI => {
-                    let dist =
-                        distribution::Distribution::from_reader(&mut self.buf.as_slice()).unwrap();
-                    let mut buf = serde_json::to_vec(&dist).unwrap();
+                    let dist = distribution::Distribution::from_reader(&mut self.buf.as_slice())
+                        .map_err(io::Error::other)?;
+                    let mut buf = serde_json::to_vec(&dist)?;
                     std::io::copy(&mut buf.as_slice(), writer)
                 }
                 DistributionFormat::PRETTY => {
-                    let dist =
-                        distribution::Distribution::from_reader(&mut self.buf.as_slice()).unwrap();
-                    let mut buf = serde_json::to_vec_pretty(&dist).unwrap();
+                    let dist = distribution::Distribution::from_reader(&mut self.buf.as_slice())
+                        .map_err(io::Error::other)?;
+                    let mut buf = serde_json::to_vec_pretty(&dist)?;
 
                     std::io::copy(&mut buf.as_slice(), writer)
                 }
                 DistributionFormat::YAML => {
-                    let dist =
-                        distribution::Distribution::from_reader(&mut self.buf.as_slice()).unwrap();
-                    let mut buf = serde_yml::to_string(&dist).unwrap();
+                    let dist = distribution::Distribution::from_reader(&mut self.buf.as_slice())
+                        .map_err(io::Error::other)?;
+                    let mut buf = serde_yml::to_string(&dist).map_err(io::Error::other)?;
 
                     std::io::copy(&mut buf.as_bytes(), writer)
                 }
+                DistributionFormat::GEOJSON | DistributionFormat::CSV => {
+                    let dist = distribution::Distribution::from_reader(&mut self.buf.as_slice())
+                        .map_err(io::Error::other)?;
+                    // files outside a region directory are kept in region local coordinates
+                    let origin = distribution::region_origin(&self.name).unwrap_or_default();
+                    let buf = match fmt {
+                        DistributionFormat::CSV => dist.to_csv(origin).into_bytes(),
+                        _ => serde_json::to_vec(&dist.to_geojson(origin))?,
+                    };
+                    writer.write_all(&buf).map(|_| buf.len() as u64)
+                }
                 _ => std::io::copy(&mut self.buf.as_slice(), writer),
             },
             FileType::VShapeC(fmt) => match fmt {
@@ -381,8 +500,7 @@ impl<'a, 'b> Decompressor<'a, 'b> {
                     std::io::copy(&mut self.buf.as_slice(), writer)?;
                     return Ok(None);
                 };
-                let hashes = FILESYSTEM.get().map(|fs| &fs.hashes);
-                let Ok(obj_stream) = from_reader(&mut self.buf.as_slice(), hashes) else {
+                let Ok(obj_stream) = self.object_stream() else {
                     std::io::copy(&mut self.buf.as_slice(), writer)?;
                     return Ok(None);
                 };
@@ -396,24 +514,52 @@ impl<'a, 'b> Decompressor<'a, 'b> {
                         std::io::copy(&mut buf.as_bytes(), writer)
                     }
                     ObjectStreamFormat::MINI => {
-                        let obj_stream = JSONObjectStream::from(obj_stream);
+                        let obj_stream = resolved(JSONObjectStream::from(obj_stream))?;
                         let string = serde_json::to_string(&obj_stream)
                             .expect("couldnt parse object stream to json");
                         std::io::copy(&mut string.as_bytes(), writer)
                     }
                     ObjectStreamFormat::PRETTY => {
-                        let obj_stream = JSONObjectStream::from(obj_stream);
+                        let obj_stream = resolved(JSONObjectStream::from(obj_stream))?;
                         let string = serde_json::to_string_pretty(&obj_stream)
                             .expect("couldnt parse object stream to json");
                         std::io::copy(&mut string.as_bytes(), writer)
                     }
+                    ObjectStreamFormat::YAML => {
+                        let obj_stream = resolved(JSONObjectStream::from(obj_stream))?;
+                        let string = serde_yml::to_string(&obj_stream).map_err(io::Error::other)?;
+                        std::io::copy(&mut string.as_bytes(), writer)
+                    }
+                    ObjectStreamFormat::ENTITIES => {
+                        let value = match object_stream::entities::to_entities(&obj_stream) {
+                            Some(entities) => annotated(entities),
+                            None => resolved(JSONObjectStream::from(obj_stream))?,
+                        };
+                        let string = serde_json::to_string_pretty(&value)?;
+                        std::io::copy(&mut string.as_bytes(), writer)
+                    }
                     _ => std::io::copy(&mut self.buf.as_slice(), writer),
                 }
             }
             FileType::Datasheet(fmt) => {
-                let mut datasheet = Datasheet::try_from(self.buf.to_owned()).unwrap();
+                let mut datasheet = Datasheet::try_from(self.buf.to_owned())?;
 
                 datasheet.with_localization(self.localization);
+                datasheet.with_resolver(self.resolver);
+                if let Some(locales) = self.locales {
+                    let adjacent = match command() {
+                        Some(Commands::Extract(cmd)) => cmd.datasheet.keep_locale_keys,
+                        _ => false,
+                    };
+                    datasheet.localize_columns(locales, adjacent);
+                }
+
+                if let Some(Commands::Extract(cmd)) = command() {
+                    if let Some(predicate) = &cmd.datasheet.datasheet_where {
+                        datasheet.retain(predicate);
+                    }
+                    datasheet.project(&cmd.datasheet.datasheet_columns);
+                }
 
                 // if **fmt == DatasheetFormat::BYTES {
                 //     return Ok((
@@ -444,13 +590,43 @@ impl<'a, 'b> Decompressor<'a, 'b> {
                         std::io::copy(&mut string.as_bytes(), writer)
                     }
                     DatasheetFormat::BYTES => std::io::copy(&mut self.buf.as_slice(), writer),
-                    DatasheetFormat::XML => todo!(),
+                    DatasheetFormat::XML => {
+                        let mut buf = String::new();
+                        let mut ser = Serializer::new(&mut buf);
+                        ser.indent('\t', 2);
+                        XMLDatasheet::from(&datasheet)
+                            .serialize(ser)
+                            .map_err(io::Error::other)?;
+                        std::io::copy(&mut buf.as_bytes(), writer)
+                    }
                     DatasheetFormat::SQL => {
-                        let string = datasheet.to_sql();
+                        let (dialect, batch) = match command() {
+                            Some(Commands::Extract(cmd)) => {
+                                (cmd.datasheet.sql_dialect, cmd.datasheet.sql_batch)
+                            }
+                            _ => (SqlDialect::default(), None),
+                        };
+                        let dialect = match dialect {
+                            SqlDialect::POSTGRES => datasheet::sql::SqlDialect::Postgres,
+                            SqlDialect::MYSQL => datasheet::sql::SqlDialect::Mysql,
+                            SqlDialect::SQLITE => datasheet::sql::SqlDialect::Sqlite,
+                        };
+                        let string = datasheet.to_sql(dialect, batch)?;
                         std::io::copy(&mut string.as_bytes(), writer)
                     }
+                    DatasheetFormat::XLSX => {
+                        let buf = datasheet.to_xlsx()?;
+                        std::io::copy(&mut buf.as_slice(), writer)
+                    }
+                    DatasheetFormat::HTML => {
+                        let string = datasheet.to_html();
+                        std::io::copy(&mut string.as_bytes(), writer)
+                    }
+                    // written into the shared database from the metadata instead
+                    DatasheetFormat::SQLITE => Ok(0),
                 }
             }
+            FileType::Custom(handler) => handler.convert(&self.name, &self.buf, writer),
             _ => std::io::copy(&mut self.buf.as_slice(), writer),
         }?;
 
@@ -458,6 +634,322 @@ impl<'a, 'b> Decompressor<'a, 'b> {
     }
 }
 
+/// Whether `--script` sees every output, so none can be written past it unbuffered.
+fn scripted() -> bool {
+    matches!(command(), Some(Commands::Extract(cmd)) if cmd.script.is_some())
+}
+
+/// The output type of the entry at `name` starting with `head`, as found by the [`handler`]
+/// registry.
+fn file_type_for(head: &[u8], name: &str) -> FileType {
+    match handler::find(head, name) {
+        Some(handler) if handler.kind() == FileKind::Other => FileType::Custom(handler),
+        Some(handler) => file_type_of(handler.kind()),
+        None => FileType::Other,
+    }
+}
+
+/// The output type of an entry of `kind` under the formats configured on the command line.
+pub(crate) fn file_type_of(kind: FileKind) -> FileType {
+    match (kind, command()) {
+        (FileKind::Luac, Some(Commands::Extract(cmd))) => FileType::Luac(cmd.luac.format()),
+        (FileKind::Luac, _) => FileType::Luac(&LuaFormat::BYTES),
+        (FileKind::ObjectStream, Some(Commands::Extract(extract))) => {
+            FileType::ObjectStream(&extract.objectstream.objectstream)
+        }
+        (FileKind::ObjectStream, Some(Commands::Grep(_))) => {
+            FileType::ObjectStream(&ObjectStreamFormat::PRETTY)
+        }
+        (FileKind::ObjectStream, _) => FileType::ObjectStream(&ObjectStreamFormat::BYTES),
+        (FileKind::Datasheet, Some(Commands::Extract(extract))) => {
+            FileType::Datasheet(&extract.datasheet.datasheet)
+        }
+        (FileKind::Datasheet, Some(Commands::Grep(_))) => {
+            FileType::Datasheet(&DatasheetFormat::PRETTY)
+        }
+        (FileKind::Datasheet, _) => FileType::Datasheet(&DatasheetFormat::BYTES),
+        (FileKind::Distribution, Some(Commands::Extract(cmd))) => {
+            FileType::Distribution(&cmd.distribution.distribution)
+        }
+        (FileKind::Distribution, Some(Commands::Grep(_))) => {
+            FileType::Distribution(&DistributionFormat::PRETTY)
+        }
+        (FileKind::Distribution, _) => FileType::Distribution(&DistributionFormat::BYTES),
+        (FileKind::VShapeC, Some(Commands::Extract(cmd))) => {
+            FileType::VShapeC(&cmd.vshapec.vshapec)
+        }
+        (FileKind::VShapeC, Some(Commands::Grep(_))) => FileType::VShapeC(&VShapeFormat::PRETTY),
+        (FileKind::VShapeC, _) => FileType::VShapeC(&VShapeFormat::BYTES),
+        (FileKind::DDS, Some(Commands::Extract(cmd))) => FileType::DDS(&cmd.dds.dds),
+        (FileKind::DDS, _) => FileType::DDS(&DDSFormat::BYTES),
+        (FileKind::Mesh, Some(Commands::Extract(cmd))) => FileType::Mesh(&cmd.mesh.mesh),
+        (FileKind::Mesh, _) => FileType::Mesh(&MeshFormat::BYTES),
+        (FileKind::Audio, Some(Commands::Extract(cmd))) => FileType::Audio(&cmd.audio.audio_format),
+        (FileKind::Audio, _) => FileType::Audio(&AudioFormat::BYTES),
+        (FileKind::Terrain, Some(Commands::Extract(cmd))) => {
+            FileType::Terrain(&cmd.terrain.terrain)
+        }
+        (FileKind::Terrain, _) => FileType::Terrain(&TerrainFormat::BYTES),
+        (FileKind::Material, Some(Commands::Extract(cmd))) => {
+            FileType::Material(&cmd.material.material)
+        }
+        (FileKind::Material, _) => FileType::Material(&MaterialFormat::BYTES),
+        (FileKind::Other, _) => FileType::default(),
+    }
+}
+
+/// Reads the decompressed contents of a raw zip entry. Oodle blocks can't be decoded
+/// incrementally, so those are decompressed whole up front.
+fn reader<'z>(zip: &'z mut ZipFile<'_>) -> io::Result<Box<dyn Read + 'z>> {
+    match zip.compression() {
+        CompressionMethod::Stored => Ok(Box::new(zip)),
+        CompressionMethod::Deflated => {
+            let mut bytes = [0; 2];
+            zip.read_exact(&mut bytes)?;
+            if [0x78, 0xda] == bytes {
+                Ok(Box::new(flate2::read::ZlibDecoder::new_with_decompress(
+                    Cursor::new(bytes).chain(zip),
+                    Decompress::new(true),
+                )))
+            } else {
+                Ok(Box::new(flate2::read::DeflateDecoder::new(
+                    Cursor::new(bytes).chain(zip),
+                )))
+            }
+        }
+        CompressionMethod::Zstd => Ok(Box::new(zstd::stream::read::Decoder::new(zip)?)),
+        #[allow(deprecated)]
+        CompressionMethod::Unsupported(15) => {
+            let mut buf = vec![0; zip.size() as usize];
+            let mut compressed = vec![];
+            std::io::copy(zip, &mut compressed)?;
+
+            oodle_safe::decompress(
+                &compressed,
+                &mut buf,
+                None,
+                None,
+                None,
+                Some(oodle_safe::DecodeThreadPhase::All),
+            )
+            .map_err(|_| io::Error::other(format!("Error with oodle_safe::decompress.",)))?;
+            Ok(Box::new(Cursor::new(buf)))
+        }
+        #[allow(deprecated)]
+        CompressionMethod::Unsupported(LZ4_METHOD) => {
+            Ok(Box::new(lz4_flex::frame::FrameDecoder::new(zip)))
+        }
+        _ => Err(io::Error::new(
+            io::ErrorKind::Other,
+            "CompressionMethod not supported",
+        )),
+    }
+}
+
+/// Converts an object stream to json, adding the catalog path of every asset reference when
+/// `--resolve-assets` is set.
+fn resolved(obj_stream: JSONObjectStream) -> io::Result<Value> {
+    Ok(annotated(serde_json::to_value(&obj_stream)?))
+}
+
+/// Adds the catalog paths of asset references when `--resolve-assets` is set.
+fn annotated(mut value: Value) -> Value {
+    if let (Some(Commands::Extract(cmd)), Some(assets)) = (command(), ASSETS.get()) {
+        if cmd.objectstream.resolve_assets {
+            annotate_assets(&mut value, assets);
+        }
+    }
+    value
+}
+
+fn annotate_assets(value: &mut Value, assets: &AssetResolver) {
+    match value {
+        Value::Object(map) => {
+            let path = asset_id(map).and_then(|(guid, sub_id)| assets(&guid, sub_id));
+            match path {
+                Some(path) => {
+                    map.insert(
+                        "path".to_string(),
+                        Value::String(path.to_string_lossy().replace('\\', "/")),
+                    );
+                }
+                None => map
+                    .values_mut()
+                    .for_each(|value| annotate_assets(value, assets)),
+            }
+        }
+        Value::Array(values) => values
+            .iter_mut()
+            .for_each(|value| annotate_assets(value, assets)),
+        _ => {}
+    }
+}
+
+/// Collects the guid and sub id of every asset reference in a json object stream.
+pub fn asset_references(value: &Value, references: &mut Vec<(uuid::Uuid, u32)>) {
+    match value {
+        Value::Object(map) => match asset_id(map) {
+            Some(id) => references.push(id),
+            None => map
+                .values()
+                .for_each(|value| asset_references(value, references)),
+        },
+        Value::Array(values) => values
+            .iter()
+            .for_each(|value| asset_references(value, references)),
+        _ => {}
+    }
+}
+
+fn asset_id(map: &serde_json::Map<String, Value>) -> Option<(uuid::Uuid, u32)> {
+    let id = map.get("assetId")?;
+    let guid = uuid::Uuid::parse_str(id.get("guid")?.as_str()?).ok()?;
+    let sub_id = id.get("subId")?.as_u64()? as u32;
+    Some((guid, sub_id))
+}
+
+/// Where a texture referenced by a material is extracted to under the current `--dds` format,
+/// if it's in the paks.
+fn extracted_texture(file: &str) -> Option<String> {
+    let entry = PathBuf::from(material::texture_entry(file)?);
+    FILESYSTEM.get()?.location(&entry)?;
+    let path = handle_extension(&file_type_of(FileKind::DDS), entry, None);
+    Some(path.to_string_lossy().replace('\\', "/"))
+}
+
+/// The length of the DDS magic and header, including the DX10 extension when present.
+fn dds_header_len(buf: &[u8]) -> usize {
+    match buf.get(84..88) {
+        Some(b"DX10") => 4 + 124 + 20,
+        _ => 4 + 124,
+    }
+}
+
+/// The `.1<suffix>` to `.N<suffix>` mip streams among the `streams` of a texture, keyed by what
+/// follows its name, read largest mip first.
+fn split_mips(streams: &[(String, &'static PathBuf)], suffix: &str) -> io::Result<Vec<Vec<u8>>> {
+    let Some(fs) = FILESYSTEM.get() else {
+        return Ok(vec![]);
+    };
+    let mut mips = streams
+        .iter()
+        .filter_map(|(part, path)| Some((part.strip_suffix(suffix)?.parse::<u32>().ok()?, *path)))
+        .collect::<Vec<_>>();
+    mips.sort_unstable_by(|(n, _), (n2, _)| n2.cmp(n));
+    mips.into_par_iter()
+        .map(|(_, path)| fs.open(path))
+        .collect()
+}
+
+/// `head`, a DDS header followed by the resident mips, with the larger `mips` put back between
+/// the two.
+fn with_mips(head: &[u8], mips: Vec<Vec<u8>>) -> Vec<u8> {
+    let header = dds_header_len(head).min(head.len());
+    let len = head.len() + mips.iter().map(Vec::len).sum::<usize>();
+    let mut buf = Vec::with_capacity(len);
+    buf.extend_from_slice(&head[..header]);
+    mips.into_iter().for_each(|mip| buf.extend(mip));
+    buf.extend_from_slice(&head[header..]);
+    buf
+}
+
+/// Whether `entry` is one of the parts a texture streams its larger mips from, `.dds.1` to
+/// `.dds.N`, or its attached alpha from, `.dds.a` and `.dds.1a` to `.dds.Na`, which are merged
+/// back into the texture when it's written.
+pub fn is_split_mip(entry: &Path) -> bool {
+    entry
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| {
+            let mip = ext.strip_suffix('a').unwrap_or(ext);
+            ext == "a" || !mip.is_empty() && mip.bytes().all(|b| b.is_ascii_digit())
+        })
+        && entry
+            .with_extension("")
+            .extension()
+            .is_some_and(|ext| ext == "dds")
+}
+
+/// Detects the kind of an entry from its decompressed leading bytes, falling back to its name.
+pub fn detect(buf: &[u8], name: &str) -> FileKind {
+    handler::kind(buf, name)
+}
+
 pub enum Metadata<'a> {
     Datasheet(Datasheet<'a>),
+    /// The extension of the converted audio, which falls back to wav for non Vorbis files.
+    Audio(&'static str),
+    /// The converted files embedded in a soundbank, by file name.
+    Soundbank(Vec<(String, Vec<u8>)>),
+    /// Where a converted region raster sits in the world.
+    Tile(terrain::Tile),
+    /// The cells of a UI atlas as PNGs, by file name.
+    Sprites(Vec<(String, Vec<u8>)>),
+}
+
+impl Metadata<'_> {
+    /// Files written beside the output at `path`: the contents of a soundbank and the cells of an
+    /// atlas in a directory named after it, the metadata of a region raster as json and the JSON
+    /// Schema of a datasheet with `--with-schema`.
+    pub fn sidecars(&self, path: &Path) -> io::Result<Vec<(PathBuf, Cow<'_, [u8]>)>> {
+        Ok(match self {
+            Metadata::Datasheet(datasheet) if with_schema() => {
+                let json = serde_json::to_vec_pretty(&datasheet.schema())?;
+                // items.datasheet.json is described by items.schema.json
+                let path = path.with_extension("").with_extension("schema.json");
+                vec![(path, Cow::Owned(json))]
+            }
+            Metadata::Soundbank(files) | Metadata::Sprites(files) => {
+                let dir = path.with_extension("");
+                files
+                    .iter()
+                    .map(|(name, buf)| (dir.join(name), Cow::Borrowed(buf.as_slice())))
+                    .collect()
+            }
+            Metadata::Tile(tile) => {
+                let json = serde_json::to_vec_pretty(tile)?;
+                vec![(path.with_extension("json"), Cow::Owned(json))]
+            }
+            _ => vec![],
+        })
+    }
+}
+
+fn with_schema() -> bool {
+    matches!(command(), Some(Commands::Extract(cmd)) if cmd.datasheet.with_schema)
+}
+
+/// The Vorbis codebooks from `--audio-codebooks`, read once.
+static CODEBOOKS: LazyLock<Option<Result<audio::Codebooks, String>>> = LazyLock::new(|| {
+    let Some(Commands::Extract(cmd)) = command() else {
+        return None;
+    };
+    let path = cmd.audio.audio_codebooks.as_ref()?;
+    Some(
+        std::fs::read(path)
+            .and_then(audio::Codebooks::from_bytes)
+            .map_err(|e| format!("{}: {e}", path.display())),
+    )
+});
+
+/// Converts a `.wem` to `fmt`, returning the extension of what was written.
+fn convert_wem(fmt: &AudioFormat, buf: &[u8]) -> io::Result<(&'static str, Vec<u8>)> {
+    let wem = audio::Wem::from_bytes(buf)?;
+    let codebooks = match CODEBOOKS.as_ref() {
+        Some(Ok(codebooks)) => Some(codebooks),
+        Some(Err(e)) if wem.is_vorbis() => return Err(io::Error::other(e.clone())),
+        None if wem.is_vorbis() => {
+            return Err(io::Error::other(
+                "converting Wwise Vorbis needs --audio-codebooks",
+            ))
+        }
+        _ => None,
+    };
+
+    match (fmt, codebooks) {
+        (AudioFormat::OGG, Some(codebooks)) if wem.is_vorbis() => {
+            Ok(("ogg", audio::to_ogg(&wem, codebooks)?))
+        }
+        _ => Ok(("wav", wem.to_wav(codebooks)?)),
+    }
 }
@@ -0,0 +1,247 @@
+use crate::{
+    decompressor::{detect, Decompressor},
+    pak::EntryInfo,
+    FileKind, FileType, Globs,
+};
+use cli::common::{datasheet::DatasheetFormat, filter::Filter};
+use datasheet::{diff::DatasheetDiff, Datasheet};
+use rayon::prelude::*;
+use serde::Serialize;
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs::File,
+    io::{self, BufReader, Read},
+    path::{Path, PathBuf},
+};
+use walkdir::WalkDir;
+
+#[derive(Debug, Default, Serialize)]
+pub struct Diff {
+    pub added: Vec<PathBuf>,
+    pub removed: Vec<PathBuf>,
+    pub changed: Vec<Changed>,
+    /// Rows, cells and columns of the changed datasheets, see [`Diff::with_datasheets`].
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub datasheets: BTreeMap<PathBuf, DatasheetDiff>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Changed {
+    pub path: PathBuf,
+    pub old: EntryInfo,
+    pub new: EntryInfo,
+}
+
+impl Diff {
+    /// Compares two entry tables by CRC32 and size.
+    pub fn between(old: &HashMap<PathBuf, EntryInfo>, new: &HashMap<PathBuf, EntryInfo>) -> Self {
+        let mut diff = Diff {
+            added: new
+                .keys()
+                .filter(|path| !old.contains_key(*path))
+                .cloned()
+                .collect(),
+            removed: old
+                .keys()
+                .filter(|path| !new.contains_key(*path))
+                .cloned()
+                .collect(),
+            changed: old
+                .iter()
+                .filter_map(|(path, old)| {
+                    let new = new.get(path)?;
+                    (old != new).then(|| Changed {
+                        path: path.to_owned(),
+                        old: *old,
+                        new: *new,
+                    })
+                })
+                .collect(),
+            datasheets: BTreeMap::new(),
+        };
+        diff.added.par_sort_unstable();
+        diff.removed.par_sort_unstable();
+        diff.changed
+            .par_sort_unstable_by(|a, b| a.path.cmp(&b.path));
+        diff
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+
+    /// Compares the rows, cells and columns of every changed datasheet, read from the `old` and
+    /// `new` roots the entry tables were indexed from. Datasheets that can't be read or parsed on
+    /// either side, like ones extracted as something else than bytes, are left out.
+    pub fn with_datasheets<P>(mut self, old: P, new: P) -> Self
+    where
+        P: AsRef<Path>,
+    {
+        let (old, new) = (Root::new(old.as_ref()), Root::new(new.as_ref()));
+        self.datasheets = self
+            .changed
+            .par_iter()
+            .filter(|changed| {
+                changed
+                    .path
+                    .extension()
+                    .is_some_and(|ext| ext.eq_ignore_ascii_case("datasheet"))
+            })
+            .filter_map(|changed| {
+                let old = old.datasheet(&changed.path)?;
+                let new = new.datasheet(&changed.path)?;
+                let diff = DatasheetDiff::between(&old, &new);
+                (!diff.is_empty()).then(|| (changed.path.to_owned(), diff))
+            })
+            .collect();
+        self
+    }
+}
+
+/// Where entries are read from, a game installation or a directory of loose files.
+enum Root<'a> {
+    Paks(HashMap<PathBuf, (PathBuf, String)>),
+    Loose(&'a Path),
+}
+
+impl<'a> Root<'a> {
+    fn new(root: &'a Path) -> Self {
+        if root.join("assets").is_dir() {
+            Root::Paks(crate::map(&root))
+        } else {
+            Root::Loose(root)
+        }
+    }
+
+    fn datasheet(&self, path: &Path) -> Option<Datasheet<'static>> {
+        let buf = match self {
+            Root::Paks(map) => {
+                let (pak, name) = map.get(path)?;
+                let mut archive = crate::pak::archive(pak).ok()?;
+                let index = archive.index_for_name(name)?;
+                let mut zip = archive.by_index_raw(index).ok()?;
+                let de = Decompressor::try_new(&mut zip, None).ok()?;
+                let mut buf = vec![];
+                de.write_as(&FileType::Datasheet(&DatasheetFormat::BYTES), &mut buf)
+                    .ok()?;
+                buf
+            }
+            Root::Loose(root) => std::fs::read(root.join(path)).ok()?,
+        };
+        Datasheet::try_from(buf).ok()
+    }
+}
+
+/// Indexes either a game installation (paks under `assets`) or a directory of loose files,
+/// keyed by virtual path and limited to entries matching `filter`.
+pub fn index<P>(root: P, filter: &Filter) -> io::Result<HashMap<PathBuf, EntryInfo>>
+where
+    P: AsRef<Path>,
+{
+    let matchers = Globs::from_filter(filter)?;
+    let kinds = crate::kinds(filter);
+    let assets = root.as_ref().join("assets");
+    if assets.is_dir() {
+        index_paks(&assets, &matchers, &kinds)
+    } else {
+        index_loose(root.as_ref(), &matchers, &kinds)
+    }
+}
+
+fn index_paks(
+    assets: &Path,
+    matchers: &Globs,
+    kinds: &[FileKind],
+) -> io::Result<HashMap<PathBuf, EntryInfo>> {
+    let paks = WalkDir::new(assets)
+        .into_iter()
+        .filter_map(|e| e.ok())
+        .filter(|path| {
+            path.file_type().is_file()
+                && path.path().extension().and_then(|ext| ext.to_str()) == Some("pak")
+        })
+        .collect::<Vec<_>>();
+
+    let entries = paks
+        .par_iter()
+        .map(|dir| -> io::Result<Vec<_>> {
+            let mut archive = crate::pak::archive(dir.path())?;
+            let parent = dir
+                .path()
+                .strip_prefix(assets)
+                .unwrap()
+                .parent()
+                .unwrap()
+                .to_path_buf();
+
+            let mut entries = vec![];
+            for i in 0..archive.len() {
+                let mut zip = archive.by_index_raw(i)?;
+                let path = parent.join(zip.name());
+                if !matchers.is_match(&path) {
+                    continue;
+                }
+                let info = EntryInfo {
+                    crc32: zip.crc32(),
+                    size: zip.size(),
+                };
+                if !kinds.is_empty() {
+                    let kind = Decompressor::peek(&mut zip)
+                        .unwrap_or_else(|_| detect(&[], &path.to_string_lossy()));
+                    if !kinds.contains(&kind) {
+                        continue;
+                    }
+                }
+                entries.push((path, info));
+            }
+            Ok(entries)
+        })
+        .collect::<io::Result<Vec<_>>>()?;
+
+    Ok(entries.into_iter().flatten().collect())
+}
+
+fn index_loose(
+    root: &Path,
+    matchers: &Globs,
+    kinds: &[FileKind],
+) -> io::Result<HashMap<PathBuf, EntryInfo>> {
+    WalkDir::new(root)
+        .into_iter()
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_type().is_file())
+        .filter(|e| matchers.is_match(&e.path().strip_prefix(root).unwrap()))
+        .par_bridge()
+        .filter_map(|e| -> Option<io::Result<_>> {
+            let index = || -> io::Result<_> {
+                let mut reader = BufReader::new(File::open(e.path())?);
+                let mut hasher = crc32fast::Hasher::new();
+                let mut buf = [0u8; 64 * 1024];
+                let mut size = 0;
+                loop {
+                    let read = reader.read(&mut buf)?;
+                    if read == 0 {
+                        break;
+                    }
+                    if size == 0
+                        && !kinds.is_empty()
+                        && !kinds.contains(&detect(&buf[..read], &e.path().to_string_lossy()))
+                    {
+                        return Ok(None);
+                    }
+                    hasher.update(&buf[..read]);
+                    size += read as u64;
+                }
+
+                Ok(Some((
+                    e.path().strip_prefix(root).unwrap().to_path_buf(),
+                    EntryInfo {
+                        crc32: hasher.finalize(),
+                        size,
+                    },
+                )))
+            };
+            index().transpose()
+        })
+        .collect()
+}
@@ -0,0 +1,227 @@
+use crate::pak::{self, EntryInfo};
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs::File,
+    io::{self, BufReader, BufWriter, Read, Write},
+    path::{Path, PathBuf},
+};
+
+/// Size and CRC32 of every pak of an installation, to tell which paks a game update changed
+/// without indexing their entries.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Fingerprint {
+    /// Keyed by the path of the pak relative to `assets`.
+    pub paks: BTreeMap<PathBuf, PakFingerprint>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PakFingerprint {
+    pub size: u64,
+    /// CRC32 of the whole pak file.
+    pub crc32: u32,
+    /// Central directory metadata of every entry by its virtual path, only when asked for.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub entries: BTreeMap<PathBuf, EntryInfo>,
+}
+
+/// Paks added, removed or changed between two fingerprints, each sorted.
+#[derive(Debug, Default, Serialize)]
+pub struct Changes {
+    pub added: Vec<PathBuf>,
+    pub removed: Vec<PathBuf>,
+    pub changed: Vec<PathBuf>,
+}
+
+impl Changes {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
+impl Fingerprint {
+    /// Hashes every pak under `game_dir`/assets, reading the central directory of each as well
+    /// when `entries` is set.
+    pub fn of<P>(game_dir: P, entries: bool) -> io::Result<Self>
+    where
+        P: AsRef<Path>,
+    {
+        let assets = game_dir.as_ref().join("assets");
+        let paks = crate::paks(&assets).collect::<Vec<_>>();
+        let paks = paks
+            .par_iter()
+            .map(|dir| -> io::Result<_> {
+                let relative = dir.path().strip_prefix(&assets).unwrap().to_path_buf();
+                let (size, crc32) = checksum(dir.path())?;
+                let entries = if entries {
+                    pak_entries(dir.path(), relative.parent().unwrap())?
+                } else {
+                    BTreeMap::new()
+                };
+                Ok((
+                    relative,
+                    PakFingerprint {
+                        size,
+                        crc32,
+                        entries,
+                    },
+                ))
+            })
+            .collect::<io::Result<_>>()?;
+        Ok(Self { paks })
+    }
+
+    pub fn read<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let reader = BufReader::new(File::open(path)?);
+        Ok(serde_json::from_reader(reader)?)
+    }
+
+    /// Writes the fingerprint as minified json.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        serde_json::to_writer(&mut writer, self)?;
+        writer.flush()
+    }
+
+    /// Paks of `newer` that aren't in this fingerprint, ones missing from it, and ones whose size
+    /// or CRC32 differ.
+    pub fn changes(&self, newer: &Fingerprint) -> Changes {
+        Changes {
+            added: newer
+                .paks
+                .keys()
+                .filter(|path| !self.paks.contains_key(*path))
+                .cloned()
+                .collect(),
+            removed: self
+                .paks
+                .keys()
+                .filter(|path| !newer.paks.contains_key(*path))
+                .cloned()
+                .collect(),
+            changed: self
+                .paks
+                .iter()
+                .filter(|(path, old)| {
+                    newer
+                        .paks
+                        .get(*path)
+                        .is_some_and(|new| (old.size, old.crc32) != (new.size, new.crc32))
+                })
+                .map(|(path, _)| path.to_owned())
+                .collect(),
+        }
+    }
+
+    /// The entries of every pak, `None` unless each pak was fingerprinted with its entries.
+    pub fn entries(&self) -> Option<HashMap<PathBuf, EntryInfo>> {
+        if self.paks.values().any(|pak| pak.entries.is_empty()) {
+            return None;
+        }
+        Some(
+            self.paks
+                .values()
+                .flat_map(|pak| {
+                    pak.entries
+                        .iter()
+                        .map(|(path, info)| (path.to_owned(), *info))
+                })
+                .collect(),
+        )
+    }
+}
+
+/// Size and CRC32 of the file at `path`.
+fn checksum(path: &Path) -> io::Result<(u64, u32)> {
+    let mut reader = File::open(path)?;
+    let mut hasher = crc32fast::Hasher::new();
+    let mut buf = vec![0u8; 1024 * 1024];
+    let mut size = 0;
+    loop {
+        let read = reader.read(&mut buf)?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buf[..read]);
+        size += read as u64;
+    }
+    Ok((size, hasher.finalize()))
+}
+
+fn pak_entries(pak: &Path, parent: &Path) -> io::Result<BTreeMap<PathBuf, EntryInfo>> {
+    let mut archive = pak::archive(pak)?;
+    (0..archive.len())
+        .map(|i| {
+            let zip = archive.by_index_raw(i)?;
+            Ok((
+                parent.join(zip.name()),
+                EntryInfo {
+                    crc32: zip.crc32(),
+                    size: zip.size(),
+                },
+            ))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lists_added_removed_and_changed_paks() {
+        let pak = |size, crc32| PakFingerprint {
+            size,
+            crc32,
+            entries: BTreeMap::new(),
+        };
+        let old = Fingerprint {
+            paks: BTreeMap::from([
+                ("DataStrm-part1.pak".into(), pak(10, 1)),
+                ("DataStrm-part2.pak".into(), pak(10, 2)),
+                ("levels/old.pak".into(), pak(5, 3)),
+            ]),
+        };
+        let new = Fingerprint {
+            paks: BTreeMap::from([
+                ("DataStrm-part1.pak".into(), pak(10, 1)),
+                ("DataStrm-part2.pak".into(), pak(10, 4)),
+                ("levels/new.pak".into(), pak(5, 3)),
+            ]),
+        };
+
+        let changes = old.changes(&new);
+        assert_eq!(changes.added, [PathBuf::from("levels/new.pak")]);
+        assert_eq!(changes.removed, [PathBuf::from("levels/old.pak")]);
+        assert_eq!(changes.changed, [PathBuf::from("DataStrm-part2.pak")]);
+        assert!(old.changes(&old).is_empty());
+        assert!(old.entries().is_none());
+    }
+
+    #[test]
+    fn round_trips_through_json() {
+        let path = std::env::temp_dir().join("nwtools-fingerprint-test.json");
+        let fingerprint = Fingerprint {
+            paks: BTreeMap::from([(
+                "DataStrm-part1.pak".into(),
+                PakFingerprint {
+                    size: 10,
+                    crc32: 1,
+                    entries: BTreeMap::from([(
+                        "sharedassets/icon.dds".into(),
+                        EntryInfo { crc32: 2, size: 3 },
+                    )]),
+                },
+            )]),
+        };
+        fingerprint.save(&path).unwrap();
+
+        let read = Fingerprint::read(&path).unwrap();
+        assert_eq!(
+            read.entries().unwrap()[Path::new("sharedassets/icon.dds")],
+            EntryInfo { crc32: 2, size: 3 }
+        );
+        std::fs::remove_file(path).unwrap();
+    }
+}
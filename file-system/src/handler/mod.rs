@@ -0,0 +1,264 @@
+//! Registry of the handlers entries are detected and converted by. The built in kinds are
+//! registered by default, and programs embedding the crate register handlers of their own formats,
+//! which are checked first so new formats don't need changes to the decompressor.
+
+use std::{
+    fmt,
+    io::{self, Write},
+    sync::{Arc, LazyLock, RwLock},
+};
+
+use crate::{
+    decompressor::{file_type_of, Decompressor},
+    terrain, FileKind,
+};
+
+/// Detects and converts entries of one format.
+pub trait FileHandler: Send + Sync {
+    /// Name of the handler, shown as the format of the entries it converts, e.g. in dry run plans.
+    fn name(&self) -> &str;
+
+    /// Whether the handler converts the entry at `name` in its pak. Streamed entries are checked
+    /// with only their leading 64 bytes before being read whole, so `head` may be cut short and
+    /// the decision should rest on the first bytes or the name.
+    fn detect(&self, head: &[u8], name: &str) -> bool;
+
+    /// The built in kind the handler detects. Those are converted by the decompressor in the format
+    /// given on the command line, with their metadata and sidecars. Handlers of other formats keep
+    /// [`FileKind::Other`] and are converted with [`FileHandler::convert`].
+    fn kind(&self) -> FileKind {
+        FileKind::Other
+    }
+
+    /// Extension added to the names of converted entries, e.g. `json` for `foo.bar.json`. `None`
+    /// keeps the names as they are in the paks.
+    fn extension(&self) -> Option<&str> {
+        None
+    }
+
+    /// Writes the conversion of the decompressed entry `buf` at `name` to `writer`, returning the
+    /// number of bytes written.
+    fn convert(&self, name: &str, buf: &[u8], writer: &mut dyn Write) -> io::Result<u64>;
+}
+
+impl fmt::Debug for dyn FileHandler {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("FileHandler").field(&self.name()).finish()
+    }
+}
+
+/// One of the formats the crate converts itself.
+struct BuiltIn {
+    name: &'static str,
+    kind: FileKind,
+    detect: fn(&[u8], &str) -> bool,
+}
+
+impl FileHandler for BuiltIn {
+    fn name(&self) -> &str {
+        self.name
+    }
+
+    fn detect(&self, head: &[u8], name: &str) -> bool {
+        (self.detect)(head, name)
+    }
+
+    fn kind(&self) -> FileKind {
+        self.kind
+    }
+
+    /// Converts the entry on its own, without the metadata the decompressor writes beside it.
+    fn convert(&self, name: &str, buf: &[u8], writer: &mut dyn Write) -> io::Result<u64> {
+        let mut converted = vec![];
+        Decompressor::from_buf(name, buf.to_vec())
+            .write_as(&file_type_of(self.kind), &mut converted)?;
+        writer.write_all(&converted)?;
+        Ok(converted.len() as u64)
+    }
+}
+
+/// The built in kinds, in the order they're detected.
+const BUILT_IN: [BuiltIn; 10] = [
+    BuiltIn {
+        name: "luac",
+        kind: FileKind::Luac,
+        detect: |head, _| head.starts_with(&[0x04, 0x00, 0x1B, 0x4C, 0x75]),
+    },
+    BuiltIn {
+        name: "objectstream",
+        kind: FileKind::ObjectStream,
+        detect: |head, _| head.starts_with(&[0x00, 0x00, 0x00, 0x00, 0x03]),
+    },
+    BuiltIn {
+        name: "datasheet",
+        kind: FileKind::Datasheet,
+        detect: |head, _| head.starts_with(&[0x11, 0x00, 0x00, 0x00]),
+    },
+    BuiltIn {
+        name: "distribution",
+        kind: FileKind::Distribution,
+        detect: |_, name| name.ends_with(".distribution"),
+    },
+    BuiltIn {
+        name: "vshapec",
+        kind: FileKind::VShapeC,
+        detect: |_, name| name.ends_with(".vshapec"),
+    },
+    BuiltIn {
+        name: "dds",
+        kind: FileKind::DDS,
+        detect: |_, name| name.ends_with(".dds"),
+    },
+    BuiltIn {
+        name: "mesh",
+        kind: FileKind::Mesh,
+        detect: |head, name| {
+            mesh::is_chunk_file(head)
+                && [".cgf", ".cga", ".skin", ".chr"]
+                    .iter()
+                    .any(|ext| name.ends_with(ext))
+        },
+    },
+    BuiltIn {
+        name: "audio",
+        kind: FileKind::Audio,
+        detect: |head, name| {
+            (audio::is_wem(head) && name.ends_with(".wem"))
+                || (audio::is_soundbank(head) && name.ends_with(".bnk"))
+        },
+    },
+    BuiltIn {
+        name: "terrain",
+        kind: FileKind::Terrain,
+        detect: |_, name| terrain::is_region_raster(name),
+    },
+    BuiltIn {
+        name: "material",
+        kind: FileKind::Material,
+        detect: |_, name| name.ends_with(".mtl"),
+    },
+];
+
+static HANDLERS: LazyLock<RwLock<Vec<Arc<dyn FileHandler>>>> = LazyLock::new(|| {
+    RwLock::new(
+        BUILT_IN
+            .into_iter()
+            .map(|handler| Arc::new(handler) as Arc<dyn FileHandler>)
+            .collect(),
+    )
+});
+
+/// Adds `handler` to the ones checked before the built in formats, in the order they were
+/// registered.
+pub fn register<H: FileHandler + 'static>(handler: H) {
+    let mut handlers = HANDLERS.write().unwrap_or_else(|e| e.into_inner());
+    let built_in = handlers
+        .iter()
+        .position(|handler| handler.kind() != FileKind::Other)
+        .unwrap_or(handlers.len());
+    handlers.insert(built_in, Arc::new(handler));
+}
+
+/// The first handler detecting the entry at `name`, registered ones before the built in kinds.
+pub fn find(head: &[u8], name: &str) -> Option<Arc<dyn FileHandler>> {
+    let handlers = HANDLERS.read().unwrap_or_else(|e| e.into_inner());
+    handlers
+        .iter()
+        .find(|handler| handler.detect(head, name))
+        .cloned()
+}
+
+/// The built in kind of the entry at `name`, regardless of registered handlers.
+pub fn kind(head: &[u8], name: &str) -> FileKind {
+    BUILT_IN
+        .iter()
+        .find(|handler| handler.detect(head, name))
+        .map_or(FileKind::Other, |handler| handler.kind)
+}
+
+/// A registered handler taking the entry at `name` over from its built in kind.
+pub fn custom(head: &[u8], name: &str) -> Option<Arc<dyn FileHandler>> {
+    find(head, name).filter(|handler| handler.kind() == FileKind::Other)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Upper;
+
+    impl FileHandler for Upper {
+        fn name(&self) -> &str {
+            "upper"
+        }
+
+        fn detect(&self, _: &[u8], name: &str) -> bool {
+            name.ends_with(".upper-test")
+        }
+
+        fn extension(&self) -> Option<&str> {
+            Some("txt")
+        }
+
+        fn convert(&self, _: &str, buf: &[u8], writer: &mut dyn Write) -> io::Result<u64> {
+            let upper = buf.to_ascii_uppercase();
+            writer.write_all(&upper)?;
+            Ok(upper.len() as u64)
+        }
+    }
+
+    #[test]
+    fn finds_registered_handlers() {
+        assert!(find(b"abc", "notes.upper-test").is_none());
+        assert_eq!(
+            find(b"abc", "notes.upper-test.mtl").unwrap().kind(),
+            FileKind::Material
+        );
+        register(Upper);
+
+        let handler = find(b"abc", "notes.upper-test").unwrap();
+        assert_eq!(handler.name(), "upper");
+        assert!(find(b"abc", "notes.txt").is_none());
+
+        let mut buf = vec![];
+        assert_eq!(
+            handler
+                .convert("notes.upper-test", b"abc", &mut buf)
+                .unwrap(),
+            3
+        );
+        assert_eq!(buf, b"ABC");
+    }
+
+    struct Materials;
+
+    impl FileHandler for Materials {
+        fn name(&self) -> &str {
+            "materials"
+        }
+
+        fn detect(&self, _: &[u8], name: &str) -> bool {
+            name.ends_with(".custom.mtl")
+        }
+
+        fn convert(&self, _: &str, buf: &[u8], writer: &mut dyn Write) -> io::Result<u64> {
+            writer.write_all(buf)?;
+            Ok(buf.len() as u64)
+        }
+    }
+
+    #[test]
+    fn registered_handlers_come_before_built_in_kinds() {
+        crate::embed();
+        register(Materials);
+        let handler = custom(b"<Material/>", "a.custom.mtl").unwrap();
+        assert_eq!(handler.name(), "materials");
+        assert_eq!(kind(b"<Material/>", "a.custom.mtl"), FileKind::Material);
+
+        let handler = find(b"<Material/>", "a.mtl").unwrap();
+        assert_eq!(handler.kind(), FileKind::Material);
+        let mut buf = vec![];
+        handler.convert("a.mtl", b"<Material/>", &mut buf).unwrap();
+        assert_eq!(buf, b"<Material/>");
+    }
+}
@@ -0,0 +1,185 @@
+use crate::pak::EntryInfo;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::{self, BufRead, BufReader, BufWriter, Write},
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+pub(crate) const FILE_NAME: &str = ".nwtools-state.json";
+/// Entries extracted since the state was last saved, one json line each, so a crashed or
+/// cancelled run can be resumed.
+pub(crate) const JOURNAL_NAME: &str = ".nwtools-state.jsonl";
+
+/// What the last extraction into an output directory wrote, so unchanged entries can be skipped.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct ExtractState {
+    /// Output settings of the run, a change invalidates every entry.
+    settings: String,
+    entries: HashMap<PathBuf, Extracted>,
+    #[serde(skip)]
+    journal: Option<BufWriter<File>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Extracted {
+    #[serde(flatten)]
+    info: EntryInfo,
+    outputs: Vec<PathBuf>,
+}
+
+impl ExtractState {
+    /// Loads the state file of `out_dir` along with the journal of an interrupted run, starting
+    /// over when they are missing, unreadable or were written with different `settings`.
+    pub fn load(out_dir: &Path, settings: String) -> io::Result<Mutex<Self>> {
+        let mut state = std::fs::read(out_dir.join(FILE_NAME))
+            .ok()
+            .and_then(|buf| serde_json::from_slice::<Self>(&buf).ok())
+            .filter(|state| state.settings == settings)
+            .unwrap_or_else(|| Self {
+                settings,
+                ..Default::default()
+            });
+
+        let resumed = state.replay(out_dir);
+        if resumed > 0 {
+            state.save(out_dir)?;
+        }
+
+        std::fs::create_dir_all(out_dir)?;
+        let mut journal = BufWriter::new(File::create(out_dir.join(JOURNAL_NAME))?);
+        serde_json::to_writer(&mut journal, &state.settings)?;
+        journal.write_all(b"\n")?;
+        journal.flush()?;
+        state.journal = Some(journal);
+
+        Ok(Mutex::new(state))
+    }
+
+    /// The state saved by the last extraction into `out_dir`, whatever its settings.
+    pub fn read(out_dir: &Path) -> io::Result<Self> {
+        let buf = std::fs::read(out_dir.join(FILE_NAME))?;
+        Ok(serde_json::from_slice(&buf)?)
+    }
+
+    /// Applies the journal left behind by an interrupted run, returning how many entries it held.
+    fn replay(&mut self, out_dir: &Path) -> usize {
+        let Ok(file) = File::open(out_dir.join(JOURNAL_NAME)) else {
+            return 0;
+        };
+        let mut lines = BufReader::new(file).lines().map_while(Result::ok);
+        let settings = lines
+            .next()
+            .and_then(|line| serde_json::from_str::<String>(&line).ok());
+        if settings.as_ref() != Some(&self.settings) {
+            return 0;
+        }
+
+        // a crash can leave the last line half written
+        lines
+            .filter_map(|line| serde_json::from_str::<(PathBuf, Extracted)>(&line).ok())
+            .map(|(entry, extracted)| self.entries.insert(entry, extracted))
+            .count()
+    }
+
+    /// Writes the full state and drops the journal it now contains.
+    pub fn save(&mut self, out_dir: &Path) -> io::Result<()> {
+        std::fs::create_dir_all(out_dir)?;
+        let buf = serde_json::to_vec(self)?;
+        std::fs::write(out_dir.join(FILE_NAME), buf)?;
+        if self.journal.take().is_some() || out_dir.join(JOURNAL_NAME).exists() {
+            std::fs::remove_file(out_dir.join(JOURNAL_NAME))?;
+        }
+        Ok(())
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Whether `entry` was extracted with the same CRC32 and size and all of its outputs exist.
+    pub fn is_unchanged(&self, entry: &Path, info: EntryInfo) -> bool {
+        self.entries.get(entry).is_some_and(|extracted| {
+            extracted.info == info && extracted.outputs.iter().all(|path| path.exists())
+        })
+    }
+
+    /// Files written for `entry`, `None` when it wasn't extracted.
+    pub fn outputs(&self, entry: &Path) -> Option<&[PathBuf]> {
+        self.entries
+            .get(entry)
+            .map(|extracted| extracted.outputs.as_slice())
+    }
+
+    /// Files written for every entry.
+    pub fn all_outputs(&self) -> impl Iterator<Item = &PathBuf> {
+        self.entries
+            .values()
+            .flat_map(|extracted| &extracted.outputs)
+    }
+
+    pub fn insert(&mut self, entry: PathBuf, info: EntryInfo, outputs: Vec<PathBuf>) {
+        let extracted = Extracted { info, outputs };
+        if let Some(journal) = &mut self.journal {
+            // flushed line by line so a crash or a cancelled run loses nothing, and losing the
+            // journal only costs re-extracting these entries on resume
+            let _ = serde_json::to_writer(&mut *journal, &(&entry, &extracted))
+                .map_err(io::Error::from)
+                .and_then(|_| journal.write_all(b"\n"))
+                .and_then(|_| journal.flush());
+        }
+        self.entries.insert(entry, extracted);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn skips_only_matching_entries_with_outputs() {
+        let dir = std::env::temp_dir().join("nwtools-incremental-test");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let output = dir.join("a.json");
+        std::fs::write(&output, b"{}").unwrap();
+
+        let info = EntryInfo { crc32: 1, size: 2 };
+        let mut state = ExtractState::load(&dir, String::new())
+            .unwrap()
+            .into_inner()
+            .unwrap();
+        state.insert("a".into(), info, vec![output.clone()]);
+        state.insert("b".into(), info, vec![dir.join("missing.json")]);
+
+        assert!(state.is_unchanged(Path::new("a"), info));
+        assert!(!state.is_unchanged(Path::new("a"), EntryInfo { crc32: 3, size: 2 }));
+        assert!(!state.is_unchanged(Path::new("b"), info));
+        assert!(!state.is_unchanged(Path::new("c"), info));
+
+        // dropped without saving or flushing, as after a crash
+        let (_, unflushed) = state.journal.take().unwrap().into_parts();
+        assert!(unflushed.unwrap().is_empty());
+        drop(state);
+        let resumed = ExtractState::load(&dir, String::new())
+            .unwrap()
+            .into_inner()
+            .unwrap();
+        assert!(resumed.is_unchanged(Path::new("a"), info));
+        assert_eq!(resumed.len(), 2);
+
+        let other = ExtractState::load(&dir, "other".into())
+            .unwrap()
+            .into_inner()
+            .unwrap();
+        assert!(other.is_empty());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
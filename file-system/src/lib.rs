@@ -1,72 +1,264 @@
+use archive::ArchiveWriter;
 use cli::commands::Commands;
+use cli::common::audio::AudioFormat;
 use cli::common::dds::DDSFormat;
 use cli::common::distribution::DistributionFormat;
+use cli::common::escape::PathEscape;
+use cli::common::lua::LuaFormat;
+use cli::common::material::MaterialFormat;
+use cli::common::mesh::MeshFormat;
+use cli::common::terrain::TerrainFormat;
 use cli::common::vshapec::VShapeFormat;
 use cli::common::{
-    datasheet::{DatasheetFormat, DatasheetOutputMode},
+    compression::OutputCompression,
+    datasheet::{DatasheetFormat, DatasheetOutputMode, DatasheetResolve, LocaleOutput},
+    filter::{ContentType, Filter, PathCase},
+    link::LinkMode,
     objectstream::ObjectStreamFormat,
+    order::ExtractOrder,
+    variant,
 };
 use cli::ARGS;
 use core::panic;
+use cpu::CpuPool;
 use dashmap::DashMap;
-use decompressor::{Decompressor, Metadata};
+use datasheet::{
+    resolve::{Resolution, Resolver},
+    Datasheet,
+};
+use decompressor::{is_split_mip, Decompressor, Metadata, Streamed};
 use globset::{GlobBuilder, GlobMatcher};
+use handler::FileHandler;
+use incremental::ExtractState;
 use localization::Localization;
+use manifest::{Manifest, ManifestEntry};
 use memmap2::Mmap;
+use pak::{Conflict, ConflictSource, EntryError, EntryInfo, PakStats};
 use pelite::pe::{Pe, PeFile};
 use pelite::FileMap;
+use pool::ArchivePool;
 use rayon::{prelude::*, ThreadPoolBuilder};
+use regex::{Regex, RegexBuilder};
+use retry::retry;
+use script::Script;
+use serde::Serialize;
 use simd_json::prelude::ArrayTrait;
+use std::borrow::Cow;
+use std::cmp::Reverse;
 use std::collections::HashSet;
+use std::ffi::{OsStr, OsString};
 use std::fmt::Debug;
 use std::io::{self, Cursor, Write};
 use std::sync::RwLock;
-use std::sync::{atomic::Ordering, Mutex, OnceLock};
+use std::sync::{
+    atomic::{AtomicBool, AtomicU64, Ordering},
+    Mutex, OnceLock,
+};
+use std::time::{Instant, SystemTime};
 use std::{
     collections::HashMap,
-    path::{Path, PathBuf},
+    path::{Component, Path, PathBuf},
     sync::{atomic::AtomicUsize, Arc},
 };
+use throttle::{MemoryBudget, RateLimit, Throttle};
 use tokio::runtime::Handle;
 use tokio_util::sync::CancellationToken;
+use tree::Tree;
 use utils::{crc32, lumberyard::LumberyardSource};
 use uuid::Uuid;
 use walkdir::WalkDir;
 use zip::read::ZipArchive;
 
+pub mod archive;
 pub mod azcs;
+pub mod bench;
+pub mod cache;
+pub mod cpu;
 pub mod decompressor;
-mod pak;
+pub mod diff;
+pub mod fingerprint;
+pub mod handler;
+pub mod incremental;
+pub mod manifest;
+pub mod material;
+pub mod packer;
+pub mod pak;
+pub mod paths;
+pub mod pool;
+pub mod report;
+pub mod retry;
+pub mod script;
+pub mod sprite;
+pub mod terrain;
+pub mod throttle;
+pub mod tree;
+pub mod verify;
+pub mod workspace;
 
 pub static FILESYSTEM: OnceLock<FileSystem> = OnceLock::new();
 
+/// Entries that failed during the last extraction into an output directory, as json.
+pub const ERRORS_FILE_NAME: &str = "errors.json";
+
+/// Directory of the output the string tables of `--locale-output table` are written to.
+pub const LOCALE_TABLES_DIR: &str = "locales";
+
+/// Outputs `--compress-output` applies to, the other formats are binary and mostly compressed
+/// already.
+const COMPRESSIBLE_EXTENSIONS: [&str; 9] = [
+    "json", "xml", "yaml", "csv", "sql", "lua", "geojson", "txt", "html",
+];
+
+/// Entries extracted at once with `--nice-io`.
+const NICE_JOBS: usize = 2;
+
+/// Maps an asset guid and sub id to its virtual path, set once the asset catalog is loaded.
+pub type AssetResolver = Box<dyn Fn(&Uuid, u32) -> Option<PathBuf> + Send + Sync>;
+
+pub static ASSETS: OnceLock<AssetResolver> = OnceLock::new();
+
+/// Position of every asset in the asset catalog by its lowercase `/` separated path, for
+/// `--order catalog`.
+pub static PRIORITY: OnceLock<HashMap<String, usize>> = OnceLock::new();
+
 #[derive(Debug)]
 pub struct FileSystem {
-    #[allow(dead_code)]
     cwd: &'static PathBuf,
     out_dir: &'static PathBuf,
-    path_to_pak: HashMap<PathBuf, (PathBuf, String)>,
+    /// Entries as first indexed, which the updated index points into while they're unchanged.
+    indexed: &'static HashMap<PathBuf, (PathBuf, String)>,
+    /// Entries new or moved to another pak since first indexed, each kept once however many
+    /// updates still have it.
+    added: Mutex<HashSet<&'static (PathBuf, (PathBuf, String))>>,
+    /// The pak every entry is in and its name there, updated in place by
+    /// [`FileSystem::reindex`].
+    path_to_pak: RwLock<HashMap<&'static PathBuf, &'static (PathBuf, String)>>,
+    conflicts: Vec<Conflict>,
     pub hashes: LumberyardSource,
     cancel: CancellationToken,
 }
 
-enum Globs {
-    Include(GlobMatcher),
-    Exclude(GlobMatcher),
+/// Comma separated glob patterns, those starting with `!` exclude. Entries match when they match
+/// any include pattern, or there are none, and no exclude pattern.
+#[derive(Default)]
+struct Globs {
+    include: Vec<GlobMatcher>,
+    exclude: Vec<GlobMatcher>,
+    /// `--filter-regex`, matched against the `/` separated entry path.
+    regex: Option<Regex>,
+    extensions: Vec<String>,
+    /// `--files`, the only entry paths kept when set.
+    files: Option<HashSet<String>>,
+    /// `--path-case lower`, paths are lowercased before matching.
+    lower: bool,
 }
 
 impl Globs {
+    fn from_filter(filter: &Filter) -> io::Result<Self> {
+        let lower = filter.path_case == PathCase::LOWER;
+        let mut matchers = globs_with_case(filter.patterns()?.as_ref(), lower);
+        matchers.regex = match &filter.filter_regex {
+            Some(regex) if lower => Some(
+                RegexBuilder::new(regex.as_str())
+                    .case_insensitive(true)
+                    .build()
+                    .map_err(io::Error::other)?,
+            ),
+            regex => regex.clone(),
+        };
+        matchers.extensions = filter.extensions.clone();
+        matchers.files = filter.files()?;
+        matchers.lower = lower;
+        Ok(matchers)
+    }
+
     fn is_match<P>(&self, path: &P) -> bool
     where
         P: AsRef<Path>,
     {
-        match self {
-            Globs::Include(glob) => glob.is_match(path),
-            Globs::Exclude(glob) => !glob.is_match(path),
+        if self.lower {
+            let lowered = path.as_ref().to_string_lossy().to_lowercase();
+            return self.matches(&lowered);
         }
+        self.matches(path)
+    }
+
+    fn matches<P>(&self, path: &P) -> bool
+    where
+        P: AsRef<Path>,
+    {
+        (self.include.is_empty() || self.include.iter().any(|glob| glob.is_match(path)))
+            && !self.exclude.iter().any(|glob| glob.is_match(path))
+            && self.regex.as_ref().map_or(true, |regex| {
+                regex.is_match(&path.as_ref().to_string_lossy().replace('\\', "/"))
+            })
+            && (self.extensions.is_empty()
+                || path.as_ref().extension().is_some_and(|ext| {
+                    self.extensions
+                        .iter()
+                        .any(|extension| ext.eq_ignore_ascii_case(extension))
+                }))
+            && self.files.as_ref().map_or(true, |files| {
+                files.contains(
+                    &path
+                        .as_ref()
+                        .to_string_lossy()
+                        .replace('\\', "/")
+                        .to_lowercase(),
+                )
+            })
     }
 }
 
+/// Set when embedded as a library, so nothing falls back to parsing the command line.
+static EMBEDDED: AtomicBool = AtomicBool::new(false);
+
+/// Stops the crate from reading the command line, for programs that configure everything through
+/// the API instead.
+pub fn embed() {
+    EMBEDDED.store(true, Ordering::Relaxed);
+}
+
+/// The command being run, or `None` when embedded.
+pub(crate) fn command() -> Option<&'static Commands> {
+    (!EMBEDDED.load(Ordering::Relaxed)).then(|| &ARGS.command)
+}
+
+/// Whether paks are memory mapped with `--mmap`, never when embedded.
+pub(crate) fn mmap() -> bool {
+    !EMBEDDED.load(Ordering::Relaxed) && ARGS.mmap
+}
+
+fn globs(string: Option<&String>) -> Globs {
+    globs_with_case(string, false)
+}
+
+/// The globs of `string`, matching regardless of case with `case_insensitive`.
+fn globs_with_case(string: Option<&String>, case_insensitive: bool) -> Globs {
+    let mut matchers = Globs::default();
+    if let Some(patterns) = string {
+        patterns
+            .split(',')
+            .map(str::trim)
+            .filter(|pattern| !pattern.is_empty())
+            .for_each(|pattern| {
+                let (list, pattern) = match pattern.strip_prefix('!') {
+                    Some(pattern) => (&mut matchers.exclude, pattern),
+                    None => (&mut matchers.include, pattern),
+                };
+                list.push(
+                    GlobBuilder::new(pattern)
+                        .literal_separator(true)
+                        .case_insensitive(case_insensitive)
+                        .build()
+                        .unwrap()
+                        .compile_matcher(),
+                );
+            })
+    };
+    matchers
+}
+
 impl FileSystem {
     pub async fn init(
         cwd: &'static PathBuf,
@@ -81,11 +273,15 @@ impl FileSystem {
                     panic!("Not a correct directory");
                 }
                 let hashes = handle.block_on(async { parse_strings(&cwd).await.unwrap() });
-                let path_to_pak = map(&cwd);
+                let (indexed, conflicts) = dedupe(entries(&cwd));
+                let indexed: &'static HashMap<_, _> = Box::leak(Box::new(indexed));
                 FileSystem {
                     cwd,
                     out_dir,
-                    path_to_pak,
+                    indexed,
+                    added: Mutex::default(),
+                    path_to_pak: RwLock::new(indexed.iter().collect()),
+                    conflicts,
                     hashes,
                     cancel,
                 }
@@ -99,53 +295,134 @@ impl FileSystem {
         &'static self,
         string: Option<&String>,
     ) -> HashMap<&'static PathBuf, &'static (PathBuf, String)> {
-        let mut matchers = vec![];
-        if let Some(patterns) = string {
-            patterns.split(',').for_each(|pattern| {
-                let pattern = pattern.trim();
-                if let Some(pattern) = pattern.strip_prefix('!') {
-                    matchers.push(Globs::Exclude(
-                        GlobBuilder::new(pattern)
-                            .literal_separator(true)
-                            .build()
-                            .unwrap()
-                            .compile_matcher(),
-                    ))
-                } else {
-                    matchers.push(Globs::Include(
-                        GlobBuilder::new(pattern)
-                            .literal_separator(true)
-                            .build()
-                            .unwrap()
-                            .compile_matcher(),
-                    ))
-                }
-            })
-        };
+        let matchers = globs(string);
 
         self.path_to_pak
+            .read()
+            .unwrap()
             .iter()
-            .filter(|(name, _)| matchers.iter().any(|glob| glob.is_match(name)))
+            .filter(|(name, _)| matchers.is_match(name))
+            .map(|(name, location)| (*name, *location))
             .collect()
     }
 
+    /// The pak `entry` is in and its name there.
+    pub(crate) fn location(&self, entry: &Path) -> Option<&'static (PathBuf, String)> {
+        self.path_to_pak
+            .read()
+            .unwrap()
+            .get(&entry.to_path_buf())
+            .copied()
+    }
+
+    /// Entries matching the globs, excludes and regex of `filter`.
+    pub fn filtered(
+        &'static self,
+        filter: &Filter,
+    ) -> io::Result<HashMap<&'static PathBuf, &'static (PathBuf, String)>> {
+        let matchers = Globs::from_filter(filter)?;
+        let files = self
+            .path_to_pak
+            .read()
+            .unwrap()
+            .iter()
+            .filter(|(name, _)| matchers.is_match(name))
+            .map(|(name, location)| (*name, *location))
+            .collect();
+
+        Ok(of_kinds(files, &kinds(filter)))
+    }
+
+    pub fn out_dir(&self) -> &Path {
+        self.out_dir
+    }
+
+    /// The game directory the paks were found in.
+    pub fn cwd(&self) -> &Path {
+        self.cwd
+    }
+
+    /// Entries found in several paks with different contents.
+    pub fn conflicts(&self) -> &[Conflict] {
+        &self.conflicts
+    }
+
+    /// Modification time and size of every pak, used to detect game updates.
+    pub fn snapshot(&self) -> HashMap<PathBuf, (Option<SystemTime>, u64)> {
+        paks(&self.cwd.join("assets"))
+            .filter_map(|dir| {
+                let meta = dir.metadata().ok()?;
+                Some((dir.into_path(), (meta.modified().ok(), meta.len())))
+            })
+            .collect()
+    }
+
+    /// Re-reads the entry tables of every pak after a game update and updates the index in place.
+    /// Returns the entries matching `filter` whose kept copy is in one of the `changed` paks or
+    /// moved to another pak, entries of unchanged paks can still be patched by changed ones.
+    pub fn reindex(
+        &'static self,
+        changed: &[PathBuf],
+        filter: &Filter,
+    ) -> io::Result<HashMap<&'static PathBuf, &'static (PathBuf, String)>> {
+        let matchers = Globs::from_filter(filter)?;
+        let latest = latest(sources(entries(&self.cwd)));
+
+        let mut path_to_pak = self.path_to_pak.write().unwrap();
+        let mut added = self.added.lock().unwrap();
+        path_to_pak.retain(|path, _| latest.contains_key(*path));
+
+        let mut files = HashMap::new();
+        for (path, location) in latest {
+            if !changed.contains(&location.0)
+                && path_to_pak
+                    .get(&path)
+                    .is_some_and(|kept| **kept == location)
+            {
+                continue;
+            }
+            let (path, location) = match self.indexed.get_key_value(&path) {
+                Some(indexed) if *indexed.1 == location => indexed,
+                _ => {
+                    let entry = (path, location);
+                    let entry = match added.get(&entry) {
+                        Some(entry) => *entry,
+                        None => {
+                            let entry: &'static _ = Box::leak(Box::new(entry));
+                            added.insert(entry);
+                            entry
+                        }
+                    };
+                    (&entry.0, &entry.1)
+                }
+            };
+            path_to_pak.insert(path, location);
+            if matchers.is_match(path) {
+                files.insert(path, location);
+            }
+        }
+        drop(path_to_pak);
+
+        Ok(of_kinds(files, &kinds(filter)))
+    }
+
     pub fn open<P>(&'static self, entry: P) -> std::io::Result<Vec<u8>>
     where
         P: AsRef<Path>,
     {
-        match self.path_to_pak.get(entry.as_ref()) {
-            Some((path, _str)) => {
-                let file = std::fs::File::open(path)?;
-                let mut archive = ZipArchive::new(file)?;
+        match self.location(entry.as_ref()) {
+            Some((path, name)) => {
+                let mut archive = pak::archive(path)?;
 
+                // paks in subdirectories of `assets` don't hold the directory in their names
                 let index = archive
-                    .index_for_path(entry)
+                    .index_for_name(name)
                     .ok_or_else(|| std::io::Error::other("No Index"))?;
                 let mut entry = archive.by_index_raw(index)?;
 
                 let mut buf = vec![];
-                let mut decompressor = Decompressor::try_new(&mut entry, None)?;
-                decompressor.to_writer(&mut buf).unwrap();
+                let decompressor = Decompressor::try_new(&mut entry, None)?;
+                decompressor.to_writer(&mut buf)?;
 
                 Ok(buf)
             }
@@ -176,12 +453,325 @@ impl FileSystem {
         }
     }
 
+    /// Decompresses and converts a single entry, as `format` when given instead of the format
+    /// configured on the command line. Returns the converted bytes along with the entry path
+    /// carrying the extension of the converted format.
+    pub fn convert<P>(
+        &'static self,
+        entry: P,
+        format: Option<&str>,
+    ) -> io::Result<(Vec<u8>, PathBuf)>
+    where
+        P: AsRef<Path>,
+    {
+        let Some((pak, name)) = self.location(entry.as_ref()) else {
+            return Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                "Entry not found in the paks",
+            ));
+        };
+
+        let mut archive = pak::archive(pak)?;
+        let index = archive
+            .index_for_name(name)
+            .ok_or_else(|| io::Error::other("No Index"))?;
+        let mut zip = archive.by_index_raw(index)?;
+        let de = Decompressor::try_new(&mut zip, None)?;
+
+        let file_type = match format {
+            Some(format) => FileType::with_format(de.kind(), format).ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("Format {} is not supported for {:?}", format, de.kind()),
+                )
+            })?,
+            None => de.file_type()?,
+        };
+
+        let mut buf = vec![];
+        let metadata = de.write_as(&file_type, &mut buf)?;
+        let path = handle_extension(&file_type, entry.as_ref().to_path_buf(), metadata.as_ref());
+        Ok((buf, path))
+    }
+
+    /// Guid and sub id of every asset referenced by an object stream entry, deduplicated. Entries
+    /// of other kinds reference nothing.
+    pub fn asset_references<P>(&'static self, entry: P) -> io::Result<Vec<(Uuid, u32)>>
+    where
+        P: AsRef<Path>,
+    {
+        let Some((pak, name)) = self.location(entry.as_ref()) else {
+            return Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                "Entry not found in the paks",
+            ));
+        };
+
+        let mut archive = pak::archive(pak)?;
+        let index = archive
+            .index_for_name(name)
+            .ok_or_else(|| io::Error::other("No Index"))?;
+        let mut zip = archive.by_index_raw(index)?;
+        let de = Decompressor::try_new(&mut zip, None)?;
+        if de.kind() != FileKind::ObjectStream {
+            return Ok(vec![]);
+        }
+
+        let mut buf = vec![];
+        de.write_as(&FileType::ObjectStream(&ObjectStreamFormat::MINI), &mut buf)?;
+        let Ok(value) = serde_json::from_slice(&buf) else {
+            return Ok(vec![]);
+        };
+
+        let mut references = vec![];
+        decompressor::asset_references(&value, &mut references);
+        references.sort_unstable();
+        references.dedup();
+        Ok(references)
+    }
+
+    /// Loads the string table of `locale`, e.g. `en-us`.
+    pub async fn localization(&'static self, locale: String) -> DashMap<String, Option<String>> {
+        load_localization(&self.files(None), locale).await
+    }
+
+    pub fn stats(
+        &'static self,
+        map: HashMap<&'static PathBuf, &'static (PathBuf, String)>,
+        types: bool,
+    ) -> io::Result<Vec<PakStats>> {
+        let mut paks: HashMap<&PathBuf, Vec<&str>> = HashMap::new();
+        map.iter().for_each(|(_, (pak, name))| {
+            paks.entry(pak).or_default().push(name);
+        });
+
+        let mut stats = paks
+            .into_par_iter()
+            .map(|(pak, names)| PakStats::from_entries(pak, &names, types))
+            .collect::<io::Result<Vec<_>>>()?;
+
+        stats.par_sort_unstable_by(|a, b| {
+            natord::compare(
+                &a.path.file_stem().unwrap_or_default().to_string_lossy(),
+                &b.path.file_stem().unwrap_or_default().to_string_lossy(),
+            )
+        });
+        Ok(stats)
+    }
+
+    /// Parses the datasheets among the given entries, leaving out ones that can't be read.
+    pub fn datasheets(
+        &'static self,
+        map: HashMap<&'static PathBuf, &'static (PathBuf, String)>,
+    ) -> Vec<Datasheet<'static>> {
+        read_datasheets(
+            map.into_iter()
+                .filter(|(entry, _)| {
+                    entry
+                        .extension()
+                        .is_some_and(|ext| ext.eq_ignore_ascii_case("datasheet"))
+                })
+                .map(|(_, location)| location),
+        )
+    }
+
+    /// The given entries as a directory tree with the uncompressed size of each.
+    pub fn tree(
+        &'static self,
+        map: HashMap<&'static PathBuf, &'static (PathBuf, String)>,
+    ) -> io::Result<Tree> {
+        let mut paks: HashMap<&PathBuf, Vec<(&PathBuf, &str)>> = HashMap::new();
+        map.iter().for_each(|(entry, (pak, name))| {
+            paks.entry(pak).or_default().push((entry, name));
+        });
+
+        let sizes = paks
+            .into_par_iter()
+            .map(|(pak, entries)| -> io::Result<Vec<_>> {
+                let mut archive = pak::archive(pak)?;
+                entries
+                    .into_iter()
+                    .map(|(entry, name)| {
+                        let index = archive
+                            .index_for_name(name)
+                            .ok_or_else(|| io::Error::other("No Index"))?;
+                        Ok((entry, archive.by_index_raw(index)?.size()))
+                    })
+                    .collect()
+            })
+            .collect::<io::Result<Vec<_>>>()?;
+
+        let mut tree = Tree::default();
+        for (entry, size) in sizes.into_iter().flatten() {
+            tree.insert(entry, size);
+        }
+        Ok(tree)
+    }
+
+    /// Entries whose CRC32 or size differ from the ones `manifest` lists, or that it doesn't
+    /// list, read from the central directories without decompressing anything.
+    pub fn changed_since(
+        &'static self,
+        map: HashMap<&'static PathBuf, &'static (PathBuf, String)>,
+        manifest: &Manifest,
+    ) -> io::Result<HashMap<&'static PathBuf, &'static (PathBuf, String)>> {
+        let mut paks: HashMap<&PathBuf, Vec<(&'static PathBuf, &'static (PathBuf, String))>> =
+            HashMap::new();
+        map.into_iter().for_each(|(entry, location)| {
+            paks.entry(&location.0).or_default().push((entry, location));
+        });
+
+        let changed = paks
+            .into_par_iter()
+            .map(|(pak, entries)| -> io::Result<Vec<_>> {
+                let mut archive = pak::archive(pak)?;
+                let mut changed = vec![];
+                for (entry, location) in entries {
+                    let index = archive
+                        .index_for_name(&location.1)
+                        .ok_or_else(|| io::Error::other("No Index"))?;
+                    let zip = archive.by_index_raw(index)?;
+                    let info = EntryInfo {
+                        crc32: zip.crc32(),
+                        size: zip.size(),
+                    };
+                    if !manifest.is_unchanged(entry, info) {
+                        changed.push((entry, location));
+                    }
+                }
+                Ok(changed)
+            })
+            .collect::<io::Result<Vec<_>>>()?;
+        Ok(changed.into_iter().flatten().collect())
+    }
+
+    /// Works out what extracting the given entries would write, detecting the kind of each entry
+    /// from its leading bytes, without writing anything.
+    pub fn plan(
+        &'static self,
+        map: HashMap<&'static PathBuf, &'static (PathBuf, String)>,
+    ) -> Vec<PlannedEntry> {
+        let mut paks: HashMap<&PathBuf, Vec<(&PathBuf, &str)>> = HashMap::new();
+        map.iter().for_each(|(entry, (pak, name))| {
+            paks.entry(pak).or_default().push((entry, name));
+        });
+
+        let (compression, escape, case) = match command() {
+            Some(Commands::Extract(cmd)) => (
+                cmd.compress_output,
+                cmd.path_escape,
+                cmd.common.filter.path_case,
+            ),
+            _ => (None, PathEscape::default(), PathCase::default()),
+        };
+        let mut plan = paks
+            .into_par_iter()
+            .flat_map_iter(|(pak, entries)| {
+                let Ok(mut archive) = pak::archive(pak) else {
+                    return vec![];
+                };
+                entries
+                    .into_iter()
+                    .filter_map(|(entry, name)| {
+                        let index = archive.index_for_name(name)?;
+                        let mut zip = archive.by_index_raw(index).ok()?;
+                        let (size, compressed_size) = (zip.size(), zip.compressed_size());
+                        let kind = Decompressor::peek(&mut zip)
+                            .unwrap_or_else(|_| decompressor::detect(&[], name));
+                        // handlers are only asked by name, the head isn't kept by peek
+                        let file_type = match handler::custom(&[], name) {
+                            Some(handler) => FileType::Custom(handler),
+                            None => decompressor::file_type_of(kind),
+                        };
+                        Some(PlannedEntry {
+                            entry: entry.to_path_buf(),
+                            pak: pak.to_path_buf(),
+                            kind,
+                            format: file_type.format_name(),
+                            output: {
+                                let output = handle_extension(
+                                    &file_type,
+                                    paths::output(self.out_dir, entry, escape, case),
+                                    None,
+                                );
+                                compressed_path(&output, compression).unwrap_or(output)
+                            },
+                            size,
+                            compressed_size,
+                        })
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+
+        plan.par_sort_unstable_by(|a, b| a.entry.cmp(&b.entry));
+        plan
+    }
+
+    /// Decompresses every entry in memory and verifies its CRC32 without writing anything.
+    pub fn validate<F>(
+        &'static self,
+        map: HashMap<&'static PathBuf, &'static (PathBuf, String)>,
+        cb: F,
+    ) -> Vec<EntryError>
+    where
+        F: Fn(&PathBuf) + Send + Sync,
+    {
+        let mut paks: HashMap<&PathBuf, Vec<(&PathBuf, &str)>> = HashMap::new();
+        map.iter().for_each(|(entry, (pak, name))| {
+            paks.entry(pak).or_default().push((entry, name));
+        });
+
+        paks.into_par_iter()
+            .flat_map_iter(|(pak, entries)| {
+                let error = |entry: &PathBuf, error: String| EntryError {
+                    entry: entry.to_path_buf(),
+                    pak: pak.to_path_buf(),
+                    offset: None,
+                    error,
+                };
+
+                let mut archive = match pak::archive(pak) {
+                    Ok(archive) => archive,
+                    Err(e) => {
+                        return entries
+                            .iter()
+                            .map(|(entry, _)| error(entry, e.to_string()))
+                            .collect::<Vec<_>>();
+                    }
+                };
+
+                let mut errors = vec![];
+                for (entry, name) in entries {
+                    if self.cancel.is_cancelled() {
+                        break;
+                    }
+                    let result = match archive.index_for_name(name) {
+                        Some(index) => archive
+                            .by_index_raw(index)
+                            .map_err(io::Error::from)
+                            .and_then(|mut zip| {
+                                let de = Decompressor::try_new(&mut zip, None)?;
+                                de.verify()
+                            }),
+                        None => Err(io::Error::other("No Index")),
+                    };
+                    if let Err(e) = result {
+                        errors.push(error(entry, e.to_string()));
+                    }
+                    cb(entry);
+                }
+                errors
+            })
+            .collect()
+    }
+
     pub async fn all<F>(
         &'static self,
         map: HashMap<&'static PathBuf, &'static (PathBuf, String)>,
         state: Arc<RwLock<State>>,
         cb: F,
-    ) -> tokio::io::Result<()>
+    ) -> tokio::io::Result<Vec<EntryError>>
     where
         F: Fn(Arc<&PathBuf>, &PathBuf, usize, usize, u64) -> io::Result<()>
             + Send
@@ -189,10 +779,15 @@ impl FileSystem {
             + Clone
             + 'static,
     {
+        // split mips are written as part of their texture when it's extracted too
         let mut paks: HashMap<&PathBuf, Vec<(&PathBuf, &str)>> = HashMap::new();
-        map.iter().for_each(|(entry, path)| {
-            paks.entry(&path.0).or_default().push((entry, &path.1));
-        });
+        map.iter()
+            .filter(|(entry, _)| {
+                !(is_split_mip(entry) && map.contains_key(&entry.with_extension("")))
+            })
+            .for_each(|(entry, path)| {
+                paks.entry(&path.0).or_default().push((entry, &path.1));
+            });
 
         let mut paks: Vec<(&PathBuf, Vec<(&PathBuf, &str)>)> = paks.into_iter().collect();
         paks.par_sort_unstable_by(|(s, _), (s2, _)| {
@@ -202,92 +797,764 @@ impl FileSystem {
             )
         });
 
-        let locale = match &ARGS.command {
-            Commands::Extract(cmd) => match cmd.datasheet.inline_locale {
-                Some(ref v) => {
-                    let v = v.to_string();
-                    Some(load_localization(&self.path_to_pak, v).await)
-                }
-                None => None,
-            },
-            Commands::Test(_) => unreachable!(),
+        let (mut locales, locale_output, keep_locale_keys) = match &ARGS.command {
+            Commands::Extract(cmd) => (
+                cli::common::datasheet::Localization::expand(&cmd.datasheet.inline_locale),
+                &cmd.datasheet.locale_output,
+                cmd.datasheet.keep_locale_keys,
+            ),
+            _ => unreachable!(),
         };
+        if keep_locale_keys && locales.is_empty() {
+            locales.push(cli::common::datasheet::Localization::EN);
+        }
+
+        let index = self.files(None);
+        let mut locale = Vec::with_capacity(locales.len());
+        for v in locales {
+            let v = v.to_string();
+            let map = load_localization(&index, v.to_owned()).await;
+            locale.push((v, map));
+        }
 
         let locale = Arc::new(locale);
 
+        let resolver = match &ARGS.command {
+            Commands::Extract(cmd) => cmd
+                .datasheet
+                .datasheet_resolve
+                .as_ref()
+                .map(|resolve| load_resolver(&index, resolve)),
+            _ => unreachable!(),
+        };
+        let resolver = Arc::new(resolver);
+
+        let database = match &ARGS.command {
+            Commands::Extract(cmd) if cmd.datasheet.datasheet == DatasheetFormat::SQLITE => {
+                std::fs::create_dir_all(self.out_dir)?;
+                // every run starts from an empty database so the tables suffixed by an earlier
+                // one don't pile up next to the ones written now
+                let path = self.out_dir.join("datasheets.sqlite");
+                for suffix in ["", "-wal", "-shm"] {
+                    let mut file = path.clone().into_os_string();
+                    file.push(suffix);
+                    match std::fs::remove_file(file) {
+                        Err(e) if e.kind() != io::ErrorKind::NotFound => return Err(e),
+                        _ => {}
+                    }
+                }
+                let conn = rusqlite::Connection::open(path).map_err(io::Error::other)?;
+                conn.pragma_update(None, "journal_mode", "WAL")
+                    .map_err(io::Error::other)?;
+                conn.pragma_update(None, "synchronous", 0)
+                    .map_err(io::Error::other)?;
+                Some(Arc::new(Mutex::new(Database {
+                    conn,
+                    tables: HashSet::new(),
+                })))
+            }
+            _ => None,
+        };
+
+        if *locale_output == LocaleOutput::TABLE {
+            let format = match &ARGS.command {
+                Commands::Extract(cmd) => &cmd.datasheet.datasheet,
+                _ => unreachable!(),
+            };
+            write_locale_tables(self.out_dir, &locale, format, database.as_deref())?;
+        }
+
+        if let Commands::Extract(cmd) = &ARGS.command {
+            if cmd.locale_index {
+                let en;
+                let locales = if locale.is_empty() {
+                    let v = cli::common::datasheet::Localization::EN.to_string();
+                    en = [(v.to_owned(), load_localization(&index, v).await)];
+                    &en[..]
+                } else {
+                    &locale[..]
+                };
+                write_locale_index(self.out_dir, &index, locales)?;
+            }
+        }
+
+        let output_archive = match &ARGS.command {
+            Commands::Extract(cmd) => cmd
+                .output_archive
+                .as_ref()
+                .map(|path| ArchiveWriter::create(path, self.out_dir))
+                .transpose()?
+                .map(|writer| Arc::new(Mutex::new(writer))),
+            _ => None,
+        };
+        let output_archive_clone = output_archive.clone();
+
+        // every run records what it wrote so a later one can skip it, except sqlite exports and
+        // archives which are rebuilt on every run
+        let (incremental, skip_unchanged) = match &ARGS.command {
+            Commands::Extract(cmd) if database.is_none() && output_archive.is_none() => (
+                Some(Arc::new(ExtractState::load(
+                    self.out_dir,
+                    format!(
+                        "{:?}",
+                        (
+                            &cmd.datasheet,
+                            &cmd.objectstream,
+                            &cmd.distribution,
+                            &cmd.vshapec,
+                            &cmd.dds,
+                            &cmd.luac,
+                            &cmd.mesh,
+                            &cmd.audio,
+                            &cmd.terrain,
+                            &cmd.material,
+                            &cmd.compress_output,
+                            &cmd.script
+                        )
+                    ),
+                )?)),
+                // watching only writes what the update changed
+                cmd.incremental || cmd.resume || cmd.watch,
+            ),
+            _ => (None, false),
+        };
+        let incremental_clone = incremental.clone();
+
+        let manifest = Arc::new(Mutex::new(Manifest::load(self.out_dir)));
+        let manifest_clone = manifest.clone();
+
+        // outputs of entries written unconverted, by contents and extension, for `--link`
+        let link = match &ARGS.command {
+            Commands::Extract(cmd) if output_archive.is_none() => cmd.link,
+            _ => None,
+        };
+        let written: Arc<DashMap<(EntryInfo, Option<OsString>), PathBuf>> = Arc::default();
+
+        let keep_going = match &ARGS.command {
+            Commands::Extract(cmd) => cmd.continue_on_error,
+            _ => false,
+        };
+        let compression = match &ARGS.command {
+            Commands::Extract(cmd) => cmd.compress_output,
+            _ => None,
+        };
+        let script = match &ARGS.command {
+            Commands::Extract(cmd) => cmd.script.as_deref().map(Script::from_file).transpose()?,
+            _ => None,
+        };
+        let errors: Arc<Mutex<Vec<EntryError>>> = Arc::default();
+        let errors_clone = errors.clone();
+
+        // without --jobs twice as many workers as cores are started, letting the throttle find
+        // the concurrency where the disk or the CPU saturates
+        let (jobs, nice_io, rate, memory) = match &ARGS.command {
+            Commands::Extract(cmd) => (
+                cmd.jobs.filter(|jobs| *jobs > 0),
+                cmd.nice_io,
+                cmd.max_throughput
+                    .map(|rate| Arc::new(RateLimit::new(rate))),
+                cmd.max_memory
+                    .map(|bytes| Arc::new(MemoryBudget::new(bytes))),
+            ),
+            _ => (None, false, None, None),
+        };
+        let order = match &ARGS.command {
+            Commands::Extract(cmd) => cmd.order,
+            _ => ExtractOrder::default(),
+        };
+
+        // --nice-io keeps to two entries at once
+        let jobs = match (jobs, nice_io) {
+            (jobs, true) => Some(jobs.unwrap_or(NICE_JOBS).min(NICE_JOBS)),
+            (jobs, false) => jobs,
+        };
+        let (threads, throttle) = match jobs {
+            Some(jobs) => (jobs, Throttle::fixed(jobs)),
+            None => {
+                let cores = std::thread::available_parallelism().map_or(4, |n| n.get());
+                (cores * 2, Throttle::adaptive(cores * 2))
+            }
+        };
+        let throttle = Arc::new(throttle);
+        // paks are opened when first needed and closed again once others were used since,
+        // instead of every one staying open for the whole extraction
+        let archives = Arc::new(ArchivePool::new(match &ARGS.command {
+            Commands::Extract(cmd) => cmd.max_open_paks,
+            _ => pool::DEFAULT_OPEN,
+        }));
+
         let cb = Arc::new(cb);
-        let out_dir = Arc::new(self.out_dir.to_owned());
+        let (escape, case) = match &ARGS.command {
+            Commands::Extract(cmd) => (cmd.path_escape, cmd.common.filter.path_case),
+            _ => (PathEscape::default(), PathCase::default()),
+        };
+        let out_dir = Arc::new(paths::long(self.out_dir));
 
         if let Err(e) = tokio::task::spawn_blocking(move || {
-            let pool = ThreadPoolBuilder::new().build().unwrap();
-            pool.scope(|p| {
-                paks.into_par_iter().for_each(|(pak_path, entries)| {
-                    let pak_path = Arc::new(pak_path);
-                    let len = entries.len();
-                    let idx = Arc::new(AtomicUsize::new(0));
-                    let file = std::fs::OpenOptions::new()
-                        .read(true)
-                        .open(pak_path.as_ref())
-                        .unwrap();
-                    let mmap = unsafe { Mmap::map(&file).expect("couldn't map file") };
-
-                    let archive = Arc::new(Mutex::new(ZipArchive::new(Cursor::new(mmap)).unwrap()));
-
-                    for (entry, name) in entries {
+            let pool = ThreadPoolBuilder::new()
+                .num_threads(threads)
+                .build()
+                .unwrap();
+            // every pak is opened and the sizes of its entries read before anything is
+            // scheduled, so entries can be ordered across paks
+            let jobs = pool.install(|| {
+                paks.into_par_iter()
+                    .filter_map(|(pak_path, entries)| {
+                        let pak_path = Arc::new(pak_path);
+                        let len = entries.len();
+                        let archive = match archives.archive(pak_path.as_ref()) {
+                            Ok(archive) => archive,
+                            Err(e) => {
+                                tracing::error!("{}: {e}", pak_path.display());
+                                let mut errors = errors.lock().unwrap();
+                                let report = state.read().unwrap().report.clone();
+                                for (idx, (entry, _)) in entries.iter().enumerate() {
+                                    report.error(pak_path.as_path());
+                                    errors.push(EntryError {
+                                        entry: entry.to_path_buf(),
+                                        pak: pak_path.to_path_buf(),
+                                        offset: None,
+                                        error: e.to_string(),
+                                    });
+                                    if cb(pak_path.clone(), entry, len, idx + 1, 0).is_err() {
+                                        self.cancel.cancel();
+                                    }
+                                }
+                                if !keep_going {
+                                    self.cancel.cancel();
+                                }
+                                return None;
+                            }
+                        };
+                        let mut archive = archive.lock().unwrap();
+                        let mut entries = entries
+                            .into_iter()
+                            .map(|(entry, name)| {
+                                let index = archive.index_for_name(name);
+                                let size = index
+                                    .and_then(|index| archive.by_index_raw(index).ok())
+                                    .map_or(0, |zip| zip.size());
+                                (entry, name, index.unwrap_or(usize::MAX), size)
+                            })
+                            .collect::<Vec<_>>();
+                        // in archive order each pak is read front to back
+                        entries.sort_unstable_by_key(|(_, _, index, _)| *index);
+                        let job = (pak_path, len, Arc::new(AtomicUsize::new(0)));
+                        Some((job, entries))
+                    })
+                    .collect::<Vec<_>>()
+            });
+
+            // (pak, position in the pak, size, entry, name)
+            let mut schedule = jobs
+                .iter()
+                .enumerate()
+                .flat_map(|(job, (_, entries))| {
+                    entries
+                        .iter()
+                        .enumerate()
+                        .map(move |(rank, (entry, name, _, size))| {
+                            (job, rank, *size, *entry, *name)
+                        })
+                })
+                .collect::<Vec<_>>();
+            state.read().unwrap().total_bytes.store(
+                schedule.iter().map(|(_, _, size, ..)| size).sum(),
+                Ordering::Relaxed,
+            );
+            match order {
+                // paks take turns so each has an entry in flight
+                ExtractOrder::PAK => schedule.sort_by_key(|(job, rank, ..)| (*rank, *job)),
+                ExtractOrder::SMALLEST => {
+                    schedule.sort_by_key(|(job, rank, size, ..)| (*size, *rank, *job))
+                }
+                ExtractOrder::LARGEST => {
+                    schedule.sort_by_key(|(job, rank, size, ..)| (Reverse(*size), *rank, *job))
+                }
+                ExtractOrder::CATALOG => {
+                    let priority = PRIORITY.get();
+                    schedule.sort_by_cached_key(|(job, rank, _, entry, _)| {
+                        let key = entry.to_string_lossy().replace('\\', "/").to_lowercase();
+                        let position = priority.and_then(|priority| priority.get(&key));
+                        (position.copied().unwrap_or(usize::MAX), *rank, *job)
+                    })
+                }
+            }
+
+            // serializing runs on threads of its own so converting doesn't stall reading and
+            // writing, and the other way around
+            let cores = std::thread::available_parallelism().map_or(4, |n| n.get());
+            std::thread::scope(|scope| {
+                let cpu = &CpuPool::new(scope, cores);
+                // borrowed rather than cloned per entry, conversions on the cpu threads keep them
+                let (locale, resolver, script) = (&locale, &resolver, &script);
+                pool.scope_fifo(|p| {
+                    for (job, _, size, entry, name) in schedule {
                         if self.cancel.is_cancelled() {
                             return;
                         }
+                        let ((pak_path, len, idx), _) = &jobs[job];
+                        let len = *len;
                         let out_dir = out_dir.clone();
                         let idx = idx.clone();
                         let cb = cb.clone();
-                        let archive = archive.clone();
+                        let archives = archives.clone();
                         let pak_path = pak_path.clone();
                         let state = state.clone();
                         // let mmap = mmap.clone();
-                        let locale = locale.clone();
-
-                        p.spawn(move |_| {
+                        let database = database.clone();
+                        let incremental = incremental.clone();
+                        let throttle = throttle.clone();
+                        let output_archive = output_archive.clone();
+                        let manifest = manifest.clone();
+                        let written = written.clone();
+                        let errors = errors.clone();
+                        let rate = rate.clone();
+                        let memory = memory.clone();
+
+                        p.spawn_fifo(move |_| {
                             if self.cancel.is_cancelled() {
                                 return;
                             }
+                            let _span = tracing::debug_span!(
+                                "entry",
+                                pak = %pak_path.display(),
+                                entry = %entry.display()
+                            )
+                            .entered();
+                            let _permit = throttle.acquire();
+                            // the whole entry is buffered while it's decompressed and converted
+                            let _memory = memory.as_ref().map(|memory| memory.reserve(size));
+                            let started = Instant::now();
 
                             let state = state.read().unwrap();
+                            // counted however the entry ends, so progress by bytes keeps up with
+                            // skipped and failed entries too
+                            let _read = scopeguard::guard((), |_| {
+                                state.read_bytes.fetch_add(size, Ordering::Relaxed);
+                            });
 
                             let c = state.active.fetch_add(1, Ordering::Relaxed) + 1;
                             state.max.fetch_max(c, Ordering::Relaxed);
 
+                            // records the error and, with --continue-on-error, moves on to the
+                            // next entry instead of cancelling the extraction
+                            let fail = |error: String, offset: Option<u64>| {
+                                tracing::error!("{error}");
+                                state.report.error(pak_path.as_path());
+                                errors.lock().unwrap().push(EntryError {
+                                    entry: entry.to_path_buf(),
+                                    pak: pak_path.to_path_buf(),
+                                    offset,
+                                    error,
+                                });
+                                if !keep_going {
+                                    self.cancel.cancel();
+                                    return;
+                                }
+                                state.active.fetch_sub(1, Ordering::Relaxed);
+                                let idx = idx.fetch_add(1, Ordering::Relaxed) + 1;
+                                if cb(pak_path.clone(), entry, len, idx, 0).is_err() {
+                                    self.cancel.cancel();
+                                }
+                            };
+
+                            let archive = match archives.archive(pak_path.as_ref()) {
+                                Ok(archive) => archive,
+                                Err(e) => {
+                                    fail(e.to_string(), None);
+                                    return;
+                                }
+                            };
                             let Ok(mut archive) = archive.lock() else {
                                 self.cancel.cancel();
                                 return;
                             };
-                            let index = archive.index_for_path(name).unwrap();
-                            let mut zip = archive.by_index_raw(index).unwrap();
-
-                            let path = out_dir.join(entry.to_path_buf());
+                            let Some(index) = archive.index_for_path(name) else {
+                                fail("No Index".to_string(), None);
+                                return;
+                            };
+                            let mut zip = match archive.by_index_raw(index) {
+                                Ok(zip) => zip,
+                                Err(e) => {
+                                    fail(e.to_string(), None);
+                                    return;
+                                }
+                            };
+                            let offset = Some(zip.header_start());
+                            let zip_size = zip.size() as usize;
+                            let compressed_size = zip.compressed_size();
+                            let info = EntryInfo {
+                                crc32: zip.crc32(),
+                                size: zip.size(),
+                            };
 
-                            let mut buf = Vec::with_capacity(zip.size() as usize);
-                            let mut de =
-                                Decompressor::try_new(&mut zip, locale.as_ref().into()).unwrap();
+                            let path = paths::output(&out_dir, entry, escape, case);
+                            let record = |format: String, outputs: &[PathBuf]| ManifestEntry {
+                                path: entry.to_path_buf(),
+                                pak: pak_path
+                                    .strip_prefix(self.cwd)
+                                    .unwrap_or(&pak_path)
+                                    .to_path_buf(),
+                                info,
+                                format,
+                                outputs: outputs
+                                    .iter()
+                                    .map(|output| {
+                                        output
+                                            .strip_prefix(out_dir.as_path())
+                                            .unwrap_or(output)
+                                            .to_path_buf()
+                                    })
+                                    .collect(),
+                            };
 
-                            let metadata = match de.to_writer(&mut buf) {
-                                Ok(res) => res,
-                                Err(_) => {
+                            if skip_unchanged
+                                && incremental.as_ref().is_some_and(|incremental| {
+                                    incremental.lock().unwrap().is_unchanged(entry, info)
+                                })
+                            {
+                                state.active.fetch_sub(1, Ordering::Relaxed);
+                                state.skipped.fetch_add(1, Ordering::Relaxed);
+                                let outputs = incremental
+                                    .as_ref()
+                                    .and_then(|incremental| {
+                                        incremental
+                                            .lock()
+                                            .unwrap()
+                                            .outputs(entry)
+                                            .map(<[_]>::to_vec)
+                                    })
+                                    .unwrap_or_default();
+                                let mut manifest = manifest.lock().unwrap();
+                                let format = match manifest.get(entry) {
+                                    Some(previous) => previous.format.to_owned(),
+                                    None => {
+                                        decompressor::file_type_of(decompressor::detect(&[], name))
+                                            .format_name()
+                                    }
+                                };
+                                manifest.insert(record(format, &outputs));
+                                drop(manifest);
+                                if cb(
+                                    pak_path,
+                                    entry,
+                                    len,
+                                    idx.fetch_add(1, Ordering::Relaxed) + 1,
+                                    0,
+                                )
+                                .is_err()
+                                {
                                     self.cancel.cancel();
-                                    return;
                                 }
-                            };
+                                return;
+                            }
+
+                            // the same bytes with the same extension are converted the same way
+                            let key = (info, entry.extension().map(OsStr::to_os_string));
+                            if let Some(mode) = link {
+                                let target = written.get(&key).map(|target| target.to_owned());
+                                if let Some(target) = target.filter(|target| *target != path) {
+                                    if link_output(&target, &path, mode).is_ok() {
+                                        let outputs = vec![path.to_owned()];
+                                        manifest.lock().unwrap().insert(record(
+                                            FileType::Other.format_name(),
+                                            &outputs,
+                                        ));
+                                        if let Some(incremental) = &incremental {
+                                            incremental.lock().unwrap().insert(
+                                                entry.to_path_buf(),
+                                                info,
+                                                outputs,
+                                            );
+                                        }
+                                        state.active.fetch_sub(1, Ordering::Relaxed);
+                                        if cb(
+                                            pak_path,
+                                            entry,
+                                            len,
+                                            idx.fetch_add(1, Ordering::Relaxed) + 1,
+                                            0,
+                                        )
+                                        .is_err()
+                                        {
+                                            self.cancel.cancel();
+                                        }
+                                        return;
+                                    }
+                                }
+                                // writing through a link left by an earlier run would change
+                                // the file it points to
+                                let _ = std::fs::remove_file(&path);
+                            }
 
-                            let file_type = de.file_type().unwrap();
-                            let path = handle_extension(&file_type, path, metadata.as_ref());
-                            let Some(parent) = path.parent() else { return };
-                            std::fs::create_dir_all(parent).expect("failed to create directory");
-                            let mut file = std::fs::File::create(&path).unwrap();
+                            // entries written unchanged go straight to disk without buffering
+                            let mut output =
+                                OutputFile::new(path.to_owned(), output_archive.as_ref());
+                            let (bytes, outputs, format) =
+                                match Decompressor::try_stream(&mut zip, &mut output) {
+                                    Err(e) => {
+                                        fail(e.to_string(), offset);
+                                        return;
+                                    }
+                                    Ok(Streamed::Written(bytes)) => match output.finish() {
+                                        Ok(path) => {
+                                            if link.is_some() {
+                                                written.entry(key).or_insert(path.to_owned());
+                                            }
+                                            (bytes, vec![path], FileType::Other.format_name())
+                                        }
+                                        Err(e) => {
+                                            fail(e.to_string(), offset);
+                                            return;
+                                        }
+                                    },
+                                    Ok(Streamed::Buffered(mut de)) => {
+                                        // the entry is in memory, other entries of the pak can be
+                                        // read while it's converted
+                                        drop(zip);
+                                        drop(archive);
+                                        de.with_resolver(resolver.as_ref().as_ref());
+                                        let format = de
+                                            .file_type()
+                                            .map(|file_type| file_type.format_name())
+                                            .unwrap_or_default();
+
+                                        // one pass per locale when writing separate
+                                        // per-locale datasheets, otherwise a single pass
+                                        // with the first locale inlined or the keys kept
+                                        let passes = match (locale_output, de.kind()) {
+                                            (_, FileKind::Datasheet) if keep_locale_keys => {
+                                                de.with_locales(Some(locale.as_slice()));
+                                                vec![(None, None)]
+                                            }
+                                            (LocaleOutput::SEPARATE, FileKind::Datasheet)
+                                                if locale.len() > 1 =>
+                                            {
+                                                locale
+                                                    .iter()
+                                                    .map(|(name, map)| {
+                                                        (Some(name.as_str()), Some(map))
+                                                    })
+                                                    .collect::<Vec<_>>()
+                                            }
+                                            (LocaleOutput::TABLE, _) => vec![(None, None)],
+                                            (LocaleOutput::COLUMNS, _) if locale.len() > 1 => {
+                                                de.with_locales(Some(locale.as_slice()));
+                                                vec![(None, None)]
+                                            }
+                                            _ => vec![(None, locale.first().map(|(_, map)| map))],
+                                        };
+
+                                        let mut bytes = 0;
+                                        let mut outputs = vec![];
+                                        for (suffix, localization) in passes {
+                                            de.with_localization(localization);
+
+                                            let converted = cpu.run(move || {
+                                                let mut buf = Vec::with_capacity(zip_size);
+                                                let metadata = de.to_writer(&mut buf);
+                                                (de, buf, metadata)
+                                            });
+                                            let (converted, buf, metadata) = match converted {
+                                                Ok(converted) => converted,
+                                                // a corrupt entry can still panic deep in a
+                                                // converter, which only fails that entry
+                                                Err(panic) if keep_going => {
+                                                    fail(
+                                                        format!(
+                                                            "conversion panicked: {}",
+                                                            cpu::panic_message(&*panic)
+                                                        ),
+                                                        offset,
+                                                    );
+                                                    return;
+                                                }
+                                                Err(panic) => std::panic::resume_unwind(panic),
+                                            };
+                                            de = converted;
+                                            let metadata = match metadata {
+                                                Ok(res) => res,
+                                                Err(e) => {
+                                                    fail(e.to_string(), offset);
+                                                    return;
+                                                }
+                                            };
+
+                                            bytes += match (&database, &metadata) {
+                                                (
+                                                    Some(database),
+                                                    Some(Metadata::Datasheet(datasheet)),
+                                                ) => {
+                                                    let mut datasheet = datasheet.to_owned();
+                                                    if let Some(suffix) = suffix {
+                                                        datasheet.name = format!(
+                                                            "{}_{}",
+                                                            datasheet.name, suffix
+                                                        );
+                                                    }
+                                                    let Ok(mut database) = database.lock() else {
+                                                        self.cancel.cancel();
+                                                        return;
+                                                    };
+                                                    let Database { conn, tables } = &mut *database;
+                                                    match datasheet.to_sqlite(conn, tables) {
+                                                        Ok(table) if table != datasheet.name => {
+                                                            tracing::warn!(
+                                                                "{}: another datasheet is named {}, written as {table}",
+                                                                entry.display(),
+                                                                datasheet.name
+                                                            );
+                                                        }
+                                                        Ok(_) => {}
+                                                        Err(e) => {
+                                                            fail(e.to_string(), offset);
+                                                            return;
+                                                        }
+                                                    }
+                                                    0
+                                                }
+                                                _ => {
+                                                    let file_type = de.file_type().unwrap();
+                                                    let mut path = handle_extension(
+                                                        &file_type,
+                                                        path.to_owned(),
+                                                        metadata.as_ref(),
+                                                    );
+                                                    if let Some(suffix) = suffix {
+                                                        let ext =
+                                                            path.extension().unwrap_or_default();
+                                                        let ext = format!(
+                                                            "{}.{}",
+                                                            suffix,
+                                                            ext.to_string_lossy()
+                                                        );
+                                                        path.set_extension(ext);
+                                                    }
+                                                    let (path, buf) = match script {
+                                                        Some(script) => {
+                                                            let relative = path
+                                                                .strip_prefix(out_dir.as_path())
+                                                                .unwrap_or(&path);
+                                                            match script.transform(
+                                                                entry,
+                                                                de.kind(),
+                                                                &file_type.format_name(),
+                                                                relative,
+                                                                buf,
+                                                            ) {
+                                                                Ok(Some((relative, buf))) => (
+                                                                    paths::output(
+                                                                        &out_dir, &relative,
+                                                                        escape, case,
+                                                                    ),
+                                                                    buf,
+                                                                ),
+                                                                Ok(None) => continue,
+                                                                Err(e) => {
+                                                                    fail(e.to_string(), offset);
+                                                                    return;
+                                                                }
+                                                            }
+                                                        }
+                                                        None => (path, buf),
+                                                    };
+                                                    let (target, data) = match compress_output(
+                                                        path.to_owned(),
+                                                        Cow::Borrowed(&buf),
+                                                        compression,
+                                                    ) {
+                                                        Ok(output) => output,
+                                                        Err(e) => {
+                                                            fail(e.to_string(), offset);
+                                                            return;
+                                                        }
+                                                    };
+                                                    let mut file = OutputFile::new(
+                                                        target,
+                                                        output_archive.as_ref(),
+                                                    );
+                                                    let target = match file
+                                                        .write_all(&data)
+                                                        .and_then(|_| file.finish())
+                                                    {
+                                                        Ok(target) => target,
+                                                        Err(e) => {
+                                                            fail(e.to_string(), offset);
+                                                            return;
+                                                        }
+                                                    };
+                                                    let mut written = data.len() as u64;
+                                                    // named after the uncompressed output
+                                                    let sidecars = match &metadata {
+                                                        Some(metadata) => {
+                                                            match metadata.sidecars(&path) {
+                                                                Ok(sidecars) => sidecars,
+                                                                Err(e) => {
+                                                                    fail(e.to_string(), offset);
+                                                                    return;
+                                                                }
+                                                            }
+                                                        }
+                                                        None => vec![],
+                                                    };
+                                                    for (sidecar, buf) in sidecars {
+                                                        let (sidecar, buf) = match compress_output(
+                                                            sidecar,
+                                                            buf,
+                                                            compression,
+                                                        ) {
+                                                            Ok(output) => output,
+                                                            Err(e) => {
+                                                                fail(e.to_string(), offset);
+                                                                return;
+                                                            }
+                                                        };
+                                                        let mut file = OutputFile::new(
+                                                            sidecar,
+                                                            output_archive.as_ref(),
+                                                        );
+                                                        match file
+                                                            .write_all(&buf)
+                                                            .and_then(|_| file.finish())
+                                                        {
+                                                            Ok(path) => outputs.push(path),
+                                                            Err(e) => {
+                                                                fail(e.to_string(), offset);
+                                                                return;
+                                                            }
+                                                        };
+                                                        written += buf.len() as u64;
+                                                    }
+                                                    outputs.push(target);
+                                                    written
+                                                }
+                                            };
+                                        }
+                                        (bytes, outputs, format)
+                                    }
+                                };
 
-                            let bytes = std::io::copy(&mut Cursor::new(buf), &mut file).unwrap();
+                            manifest.lock().unwrap().insert(record(format, &outputs));
+
+                            if let Some(incremental) = &incremental {
+                                incremental.lock().unwrap().insert(
+                                    entry.to_path_buf(),
+                                    info,
+                                    outputs,
+                                );
+                            }
 
                             state.active.fetch_sub(1, Ordering::Relaxed);
                             state.max.load(Ordering::Relaxed);
                             state.size.store(bytes as usize, Ordering::Relaxed);
+                            state.report.entry(
+                                pak_path.as_path(),
+                                compressed_size,
+                                bytes,
+                                started.elapsed(),
+                            );
+                            throttle.record(bytes);
 
                             if cb(
                                 pak_path,
@@ -300,6 +1567,14 @@ impl FileSystem {
                             {
                                 self.cancel.cancel();
                             }
+
+                            // the permit is held while waiting so no other entry takes its place
+                            if let Some(rate) = &rate {
+                                rate.consume(bytes);
+                            }
+                            if nice_io {
+                                std::thread::sleep(started.elapsed());
+                            }
                         });
                     }
                 });
@@ -311,24 +1586,320 @@ impl FileSystem {
             return Err(tokio::io::Error::other(e));
         };
 
-        Ok(())
+        if let Some(writer) = output_archive_clone {
+            let writer = Arc::into_inner(writer)
+                .and_then(|writer| writer.into_inner().ok())
+                .ok_or_else(|| io::Error::other("archive writer still in use"))?;
+            writer.finish()?;
+        }
+
+        Arc::into_inner(manifest_clone)
+            .and_then(|manifest| manifest.into_inner().ok())
+            .ok_or_else(|| io::Error::other("manifest still in use"))?
+            .save(self.out_dir)?;
+
+        // saved even when cancelled so the entries written so far are skipped next time
+        if let Some(incremental) = incremental_clone {
+            incremental.lock().unwrap().save(self.out_dir)?;
+        }
+
+        let mut errors = Arc::into_inner(errors_clone)
+            .and_then(|errors| errors.into_inner().ok())
+            .ok_or_else(|| io::Error::other("errors still in use"))?;
+        errors.sort_unstable_by(|a, b| a.entry.cmp(&b.entry));
+        let path = self.out_dir.join(ERRORS_FILE_NAME);
+        if errors.is_empty() {
+            if path.exists() {
+                std::fs::remove_file(path)?;
+            }
+        } else {
+            std::fs::create_dir_all(self.out_dir)?;
+            std::fs::write(path, serde_json::to_vec_pretty(&errors)?)?;
+        }
+        Ok(errors)
     }
 }
 
+/// The `datasheets.sqlite` of `--datasheet sqlite`, with the tables written to it by this run,
+/// lowercased.
+struct Database {
+    conn: rusqlite::Connection,
+    tables: HashSet<String>,
+}
+
+/// Writes the string table of every locale for `--locale-output table`, into the sqlite database
+/// when there is one, otherwise as a file per locale in the format datasheets are written as.
+fn write_locale_tables(
+    out_dir: &Path,
+    locales: &[(String, DashMap<String, Option<String>>)],
+    format: &DatasheetFormat,
+    database: Option<&Mutex<Database>>,
+) -> io::Result<()> {
+    let table = |locale: &str| format!("locale_{}", locale.replace('-', "_"));
+
+    if let Some(database) = database {
+        let mut database = database
+            .lock()
+            .map_err(|_| io::Error::other("database lock poisoned"))?;
+        let conn = &mut database.conn;
+        for (locale, map) in locales {
+            let table = table(locale);
+            let tx = conn.transaction().map_err(io::Error::other)?;
+            tx.execute_batch(&format!(
+                "DROP TABLE IF EXISTS {table};\nCREATE TABLE {table}(key TEXT PRIMARY KEY, text TEXT);"
+            ))
+            .map_err(io::Error::other)?;
+            {
+                let mut insert = tx
+                    .prepare(&format!("INSERT INTO {table} VALUES (?, ?)"))
+                    .map_err(io::Error::other)?;
+                for entry in map.iter() {
+                    insert
+                        .execute((entry.key(), entry.value()))
+                        .map_err(io::Error::other)?;
+                }
+            }
+            tx.commit().map_err(io::Error::other)?;
+        }
+        return Ok(());
+    }
+
+    let dir = out_dir.join(LOCALE_TABLES_DIR);
+    std::fs::create_dir_all(&dir)?;
+    for (locale, map) in locales {
+        let ext = match format {
+            DatasheetFormat::CSV => "csv",
+            DatasheetFormat::SQL => "sql",
+            _ => "json",
+        };
+        let file = std::fs::File::create(dir.join(locale).with_extension(ext))?;
+        let mut writer = io::BufWriter::new(file);
+        match format {
+            DatasheetFormat::CSV => localization::export::to_csv(map, &mut writer)?,
+            DatasheetFormat::SQL => localization::export::to_sql(map, &table(locale), &mut writer)?,
+            _ => localization::export::to_json(map, &mut writer)?,
+        }
+        writer.flush()?;
+    }
+    Ok(())
+}
+
+/// Writes the full-text index of the strings of `locales`, with the datasheet columns referencing
+/// each key, for `--locale-index`.
+fn write_locale_index(
+    out_dir: &Path,
+    path_to_pak: &HashMap<&PathBuf, &(PathBuf, String)>,
+    locales: &[(String, DashMap<String, Option<String>>)],
+) -> io::Result<()> {
+    std::fs::create_dir_all(out_dir)?;
+    let mut index =
+        localization::search::Index::create(out_dir.join(localization::search::FILE_NAME))
+            .map_err(io::Error::other)?;
+    for (locale, map) in locales {
+        index.insert_locale(locale, map).map_err(io::Error::other)?;
+    }
+
+    let datasheets = read_datasheets(
+        path_to_pak
+            .iter()
+            .filter(|(entry, _)| {
+                entry
+                    .extension()
+                    .is_some_and(|ext| ext.eq_ignore_ascii_case("datasheet"))
+            })
+            .map(|(_, location)| *location),
+    );
+    for datasheet in &datasheets {
+        index
+            .insert_references(&datasheet.name, datasheet.localization_references())
+            .map_err(io::Error::other)?;
+    }
+    Ok(())
+}
+
 pub struct State {
     pub active: Arc<AtomicUsize>,
     pub max: Arc<AtomicUsize>,
     pub size: Arc<AtomicUsize>,
+    /// Entries skipped because they are unchanged since the last incremental extraction.
+    pub skipped: Arc<AtomicUsize>,
+    /// Totals of the entries written and failed, per pak.
+    pub report: Arc<report::Report>,
+    /// Uncompressed size of every entry scheduled, known once the paks are indexed.
+    pub total_bytes: Arc<AtomicU64>,
+    /// Uncompressed size of the entries processed so far, written, skipped or failed.
+    pub read_bytes: Arc<AtomicU64>,
+}
+
+/// An entry as extraction would write it, see [`FileSystem::plan`].
+#[derive(Debug, Serialize)]
+pub struct PlannedEntry {
+    pub entry: PathBuf,
+    pub pak: PathBuf,
+    pub kind: FileKind,
+    pub format: String,
+    pub output: PathBuf,
+    pub size: u64,
+    pub compressed_size: u64,
+}
+
+/// Output file created on the first write, so nothing is left behind when an entry turns out to
+/// need converting before it is written. With `--output-archive` the contents are collected and
+/// added to the archive instead.
+struct OutputFile {
+    path: PathBuf,
+    file: Option<std::fs::File>,
+    archive: Option<(Arc<Mutex<ArchiveWriter>>, Vec<u8>)>,
+}
+
+impl OutputFile {
+    fn new(path: PathBuf, archive: Option<&Arc<Mutex<ArchiveWriter>>>) -> Self {
+        Self {
+            path,
+            file: None,
+            archive: archive.map(|archive| (archive.clone(), vec![])),
+        }
+    }
+
+    fn file(&mut self) -> io::Result<&mut std::fs::File> {
+        if self.file.is_none() {
+            if let Some(parent) = self.path.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            self.file = Some(retry(|| std::fs::File::create(&self.path))?);
+        }
+        Ok(self.file.as_mut().unwrap())
+    }
+
+    /// Creates the file even when nothing was written and returns its path.
+    fn finish(mut self) -> io::Result<PathBuf> {
+        match self.archive.take() {
+            Some((archive, buf)) => archive
+                .lock()
+                .map_err(|_| io::Error::other("archive writer poisoned"))?
+                .append(&self.path, &buf)?,
+            None => self.file()?.flush()?,
+        }
+        Ok(self.path)
+    }
+}
+
+/// Where `path` is written with `--compress-output`, `None` when it isn't set or the output
+/// isn't text.
+fn compressed_path(path: &Path, compression: Option<OutputCompression>) -> Option<PathBuf> {
+    let ext = path.extension()?.to_str()?;
+    if !COMPRESSIBLE_EXTENSIONS
+        .iter()
+        .any(|compressible| ext.eq_ignore_ascii_case(compressible))
+    {
+        return None;
+    }
+    let mut path = path.as_os_str().to_owned();
+    path.push(match compression? {
+        OutputCompression::GZIP => ".gz",
+        OutputCompression::ZSTD => ".zst",
+    });
+    Some(PathBuf::from(path))
+}
+
+/// `buf` compressed as `--compress-output` asks along with the path it's written to, see
+/// [`compressed_path`].
+fn compress_output<'a>(
+    path: PathBuf,
+    buf: Cow<'a, [u8]>,
+    compression: Option<OutputCompression>,
+) -> io::Result<(PathBuf, Cow<'a, [u8]>)> {
+    let (Some(compressed), Some(compression)) = (compressed_path(&path, compression), compression)
+    else {
+        return Ok((path, buf));
+    };
+
+    let buf = match compression {
+        OutputCompression::GZIP => {
+            let mut encoder = flate2::write::GzEncoder::new(
+                Vec::with_capacity(buf.len() / 4),
+                flate2::Compression::default(),
+            );
+            encoder.write_all(&buf)?;
+            encoder.finish()?
+        }
+        OutputCompression::ZSTD => zstd::encode_all(buf.as_ref(), 0)?,
+    };
+    Ok((compressed, Cow::Owned(buf)))
+}
+
+/// Links `path` to `target`, an output written earlier with the same contents.
+fn link_output(target: &Path, path: &Path, mode: LinkMode) -> io::Result<()> {
+    let parent = path.parent().unwrap_or(Path::new(""));
+    std::fs::create_dir_all(parent)?;
+    match std::fs::remove_file(path) {
+        Err(e) if e.kind() != io::ErrorKind::NotFound => return Err(e),
+        _ => {}
+    }
+    retry(|| match mode {
+        LinkMode::HARD => std::fs::hard_link(target, path),
+        LinkMode::SYMBOLIC => symlink(&relative(target, parent), path),
+    })
+}
+
+#[cfg(unix)]
+fn symlink(target: &Path, path: &Path) -> io::Result<()> {
+    std::os::unix::fs::symlink(target, path)
+}
+
+#[cfg(windows)]
+fn symlink(target: &Path, path: &Path) -> io::Result<()> {
+    std::os::windows::fs::symlink_file(target, path)
+}
+
+/// `target` relative to `dir`, so links keep working when the output directory is moved.
+fn relative(target: &Path, dir: &Path) -> PathBuf {
+    let common = target
+        .components()
+        .zip(dir.components())
+        .take_while(|(a, b)| a == b)
+        .count();
+    dir.components()
+        .skip(common)
+        .map(|_| Component::ParentDir)
+        .chain(target.components().skip(common))
+        .collect()
 }
 
-fn handle_extension(file_type: &FileType, mut path: PathBuf, meta: Option<&Metadata>) -> PathBuf {
+impl Write for OutputFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match &mut self.archive {
+            Some((_, data)) => data.write(buf),
+            None => self.file()?.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match &mut self.file {
+            Some(file) => file.flush(),
+            None => Ok(()),
+        }
+    }
+}
+
+/// The output path of the entry at `path` when written as `file_type`.
+pub fn handle_extension(
+    file_type: &FileType,
+    mut path: PathBuf,
+    meta: Option<&Metadata>,
+) -> PathBuf {
     let mut ext = path.extension().unwrap().to_os_string();
     match file_type {
-        FileType::Luac(fmt) => {
-            if *fmt {
+        FileType::Luac(fmt) => match fmt {
+            LuaFormat::BYTES => {}
+            LuaFormat::LUA => {
                 path.set_extension("lua");
             }
-        }
+            LuaFormat::ASM => {
+                ext.push(".asm");
+                path.set_extension(ext);
+            }
+        },
         FileType::DDS(fmt) => match fmt {
             DDSFormat::BYTES | DDSFormat::FLAT => {}
             DDSFormat::PNG => {
@@ -350,6 +1921,49 @@ fn handle_extension(file_type: &FileType, mut path: PathBuf, meta: Option<&Metad
                 }
             }
         },
+        FileType::Mesh(fmt) => match fmt {
+            MeshFormat::BYTES => {}
+            MeshFormat::GLB => {
+                ext.push(".glb");
+                path.set_extension(ext);
+            }
+        },
+        FileType::Material(fmt) => match fmt {
+            MaterialFormat::BYTES => {}
+            MaterialFormat::JSON => {
+                ext.push(".json");
+                path.set_extension(ext);
+            }
+        },
+        FileType::Terrain(fmt) => match fmt {
+            TerrainFormat::BYTES => {}
+            TerrainFormat::PNG => {
+                ext.push(".png");
+                path.set_extension(ext);
+            }
+        },
+        FileType::Custom(handler) => {
+            if let Some(extension) = handler.extension() {
+                ext.push(".");
+                ext.push(extension);
+                path.set_extension(ext);
+            }
+        }
+        FileType::Audio(fmt) => {
+            // the container actually written, a planned entry assumes the requested one
+            let extension = match (fmt, meta) {
+                (AudioFormat::BYTES, _) => None,
+                _ if ext == "bnk" => None,
+                (_, Some(Metadata::Audio(extension))) => Some(*extension),
+                (AudioFormat::OGG, _) => Some("ogg"),
+                (AudioFormat::WAV, _) => Some("wav"),
+            };
+            if let Some(extension) = extension {
+                ext.push(".");
+                ext.push(extension);
+                path.set_extension(ext);
+            }
+        }
         FileType::VShapeC(fmt) => match fmt {
             VShapeFormat::PRETTY | VShapeFormat::MINI => {
                 if ext != "json" {
@@ -378,6 +1992,14 @@ fn handle_extension(file_type: &FileType, mut path: PathBuf, meta: Option<&Metad
                     path = path.with_extension(ext);
                 }
             }
+            DistributionFormat::GEOJSON => {
+                ext.push(".geojson");
+                path.set_extension(ext);
+            }
+            DistributionFormat::CSV => {
+                ext.push(".csv");
+                path.set_extension(ext);
+            }
             _ => {}
         },
         FileType::ObjectStream(fmt) => match fmt {
@@ -387,17 +2009,25 @@ fn handle_extension(file_type: &FileType, mut path: PathBuf, meta: Option<&Metad
                     path.set_extension(ext);
                 }
             }
-            ObjectStreamFormat::MINI | ObjectStreamFormat::PRETTY => {
+            ObjectStreamFormat::MINI
+            | ObjectStreamFormat::PRETTY
+            | ObjectStreamFormat::ENTITIES => {
                 if ext != "json" {
                     ext.push(".json");
                     path.set_extension(ext);
                 }
             }
+            ObjectStreamFormat::YAML => {
+                if ext != "yaml" {
+                    ext.push(".yaml");
+                    path = path.with_extension(ext);
+                }
+            }
             _ => {}
         },
         FileType::Datasheet(fmt) => {
-            match &ARGS.command {
-                Commands::Extract(extract) => {
+            match command() {
+                Some(Commands::Extract(extract)) => {
                     if extract.datasheet.datasheet_filenames == DatasheetOutputMode::TYPENAME {
                         if let Some(meta) = &meta {
                             match meta {
@@ -413,11 +2043,12 @@ fn handle_extension(file_type: &FileType, mut path: PathBuf, meta: Option<&Metad
                                         .join(format!("{}/{}", datasheet._type, datasheet.name));
                                     path = path.with_extension(&ext);
                                 }
+                                _ => {}
                             }
                         }
                     }
                 }
-                Commands::Test(_) => unreachable!(),
+                _ => {}
             };
             match fmt {
                 DatasheetFormat::BYTES => {}
@@ -432,9 +2063,9 @@ fn handle_extension(file_type: &FileType, mut path: PathBuf, meta: Option<&Metad
                         ext.push(".json");
                         path.set_extension(ext);
                     }
-                    let with_meta = match &ARGS.command {
-                        Commands::Extract(cmd) => cmd.datasheet.with_meta,
-                        Commands::Test(_) => unreachable!(),
+                    let with_meta = match command() {
+                        Some(Commands::Extract(cmd)) => cmd.datasheet.with_meta,
+                        _ => false,
                     };
 
                     if let Some(meta) = &meta {
@@ -462,6 +2093,7 @@ fn handle_extension(file_type: &FileType, mut path: PathBuf, meta: Option<&Metad
                                     .unwrap();
                                     // datasheet.to_json_simd(pretty)
                                 }
+                                _ => {}
                             }
                         };
                     }
@@ -484,6 +2116,19 @@ fn handle_extension(file_type: &FileType, mut path: PathBuf, meta: Option<&Metad
                         path.set_extension(ext);
                     }
                 }
+                DatasheetFormat::XLSX => {
+                    if ext != "xlsx" {
+                        ext.push(".xlsx");
+                        path.set_extension(ext);
+                    }
+                }
+                DatasheetFormat::HTML => {
+                    if ext != "html" {
+                        ext.push(".html");
+                        path.set_extension(ext);
+                    }
+                }
+                DatasheetFormat::SQLITE => {}
             }
         }
         _ => {}
@@ -499,6 +2144,15 @@ async fn parse_strings<P: AsRef<Path>>(dir: &P) -> io::Result<LumberyardSource>
     ly.crcs.extend(crcs);
     ly.uuids.extend(uuids);
 
+    // names scanned from a source tree with `hashes build`
+    if let Some(user) = utils::lumberyard::dictionary_path()
+        .and_then(|path| std::fs::read(path).ok())
+        .and_then(|buf| serde_json::from_slice::<LumberyardSource>(&buf).ok())
+    {
+        ly.crcs.extend(user.crcs);
+        ly.uuids.extend(user.uuids);
+    }
+
     let path = dir.as_ref().join("Bin64/NewWorld.exe");
 
     let file_map = FileMap::open(&path).unwrap();
@@ -563,68 +2217,334 @@ async fn parse_strings<P: AsRef<Path>>(dir: &P) -> io::Result<LumberyardSource>
     Ok(ly)
 }
 
-fn map<P: AsRef<Path>>(path: &P) -> HashMap<PathBuf, (PathBuf, String)> {
-    let assets_dir = path.as_ref().join("assets").to_path_buf();
-
-    WalkDir::new(assets_dir.to_path_buf())
+fn paks<P: AsRef<Path>>(assets_dir: &P) -> impl Iterator<Item = walkdir::DirEntry> {
+    WalkDir::new(assets_dir.as_ref().to_path_buf())
         .into_iter()
         .filter_map(|e| e.ok())
         .filter(|path| {
             path.file_type().is_file()
                 && path.path().extension().and_then(|ext| ext.to_str()) == Some("pak")
         })
+}
+
+fn pak_entries(assets_dir: &Path, pak: &Path) -> Vec<(PathBuf, (PathBuf, String))> {
+    let file = retry(|| std::fs::File::open(pak)).unwrap();
+    let mmap = unsafe { Mmap::map(&file).expect("couldn't map file") };
+    drop(file);
+    let mmap = Cursor::new(mmap);
+    let archive = ZipArchive::new(mmap).unwrap();
+
+    let file_names = archive
+        .file_names()
+        // .iter()
+        .map(|name| {
+            let full_name = pak
+                .strip_prefix(assets_dir)
+                .unwrap()
+                .to_path_buf()
+                .parent()
+                .unwrap()
+                .join(name);
+
+            (full_name, (pak.to_path_buf(), name.to_string()))
+        })
+        .collect::<Vec<(PathBuf, (PathBuf, String))>>();
+
+    file_names
+}
+
+/// Every entry in the paks under `game_dir` matching the comma separated `filter` globs, keyed by
+/// its full path, with the pak it's in and its name there.
+pub fn index<P: AsRef<Path>>(
+    game_dir: &P,
+    filter: Option<&String>,
+) -> HashMap<PathBuf, (PathBuf, String)> {
+    let globs = globs(filter);
+    map(game_dir)
+        .into_iter()
+        .filter(|(path, _)| globs.is_match(path))
+        .collect()
+}
+
+fn map<P: AsRef<Path>>(path: &P) -> HashMap<PathBuf, (PathBuf, String)> {
+    dedupe(entries(path)).0
+}
+
+/// Every entry of every pak, including the ones in several paks.
+fn entries<P: AsRef<Path>>(path: &P) -> Vec<(PathBuf, (PathBuf, String))> {
+    let assets_dir = path.as_ref().join("assets").to_path_buf();
+
+    paks(&assets_dir)
         .par_bridge()
-        .map(|dir| {
-            let file = std::fs::File::open(dir.path()).unwrap();
-            let mmap = unsafe { Mmap::map(&file).expect("couldn't map file") };
-            drop(file);
-            let mmap = Cursor::new(mmap);
-            let archive = ZipArchive::new(mmap).unwrap();
-
-            let file_names = archive
-                .file_names()
-                // .iter()
-                .map(|name| {
-                    let full_name = dir
-                        .to_owned()
-                        .into_path()
-                        .strip_prefix(assets_dir.to_path_buf())
-                        .unwrap()
-                        .to_path_buf()
-                        .parent()
-                        .unwrap()
-                        .join(name);
+        .map(|dir| pak_entries(&assets_dir, dir.path()))
+        .flatten()
+        .collect()
+}
+
+/// Keeps one copy of entries found in several paks, the one in the pak sorting last as patch paks
+/// sort after the ones they patch. Copies with different CRC32s or sizes are returned as
+/// conflicts.
+fn dedupe(
+    entries: Vec<(PathBuf, (PathBuf, String))>,
+) -> (HashMap<PathBuf, (PathBuf, String)>, Vec<Conflict>) {
+    let sources = sources(entries);
+    let conflicts = conflicts(&sources);
+    (latest(sources), conflicts)
+}
+
+/// Every copy of each entry, in pak order.
+fn sources(entries: Vec<(PathBuf, (PathBuf, String))>) -> HashMap<PathBuf, Vec<(PathBuf, String)>> {
+    let mut sources: HashMap<PathBuf, Vec<(PathBuf, String)>> =
+        HashMap::with_capacity(entries.len());
+    for (path, source) in entries {
+        sources.entry(path).or_default().push(source);
+    }
+    for copies in sources.values_mut().filter(|copies| copies.len() > 1) {
+        copies
+            .sort_by(|(a, _), (b, _)| natord::compare(&a.to_string_lossy(), &b.to_string_lossy()));
+    }
+    sources
+}
+
+/// The copy of each entry in the pak sorting last.
+fn latest(
+    sources: HashMap<PathBuf, Vec<(PathBuf, String)>>,
+) -> HashMap<PathBuf, (PathBuf, String)> {
+    sources
+        .into_iter()
+        .filter_map(|(path, mut copies)| Some((path, copies.pop()?)))
+        .collect()
+}
 
-                    (full_name, (dir.path().to_path_buf(), name.to_string()))
+fn conflicts(sources: &HashMap<PathBuf, Vec<(PathBuf, String)>>) -> Vec<Conflict> {
+    let mut duplicates: HashMap<&PathBuf, Vec<&str>> = HashMap::new();
+    for (pak, name) in sources.values().filter(|copies| copies.len() > 1).flatten() {
+        duplicates.entry(pak).or_default().push(name);
+    }
+    // only the paks holding duplicates are read again for their CRC32s
+    let infos = duplicates
+        .into_par_iter()
+        .flat_map_iter(|(pak, names)| {
+            let Ok(mut archive) = pak::archive(pak) else {
+                return vec![];
+            };
+            names
+                .into_iter()
+                .filter_map(|name| {
+                    let index = archive.index_for_name(name)?;
+                    let zip = archive.by_index_raw(index).ok()?;
+                    let info = EntryInfo {
+                        crc32: zip.crc32(),
+                        size: zip.size(),
+                    };
+                    Some(((pak, name), info))
                 })
-                .collect::<Vec<(PathBuf, (PathBuf, String))>>();
+                .collect::<Vec<_>>()
+        })
+        .collect::<HashMap<_, _>>();
 
-            file_names
+    let mut conflicts = sources
+        .iter()
+        .filter(|(_, copies)| copies.len() > 1)
+        .filter_map(|(path, copies)| {
+            let sources = copies
+                .iter()
+                .filter_map(|(pak, name)| {
+                    Some(ConflictSource {
+                        pak: pak.to_path_buf(),
+                        info: *infos.get(&(pak, name.as_str()))?,
+                    })
+                })
+                .collect::<Vec<_>>();
+            let first = sources.first()?.info;
+            if sources.iter().all(|source| source.info == first) {
+                return None;
+            }
+            Some(Conflict {
+                entry: path.to_path_buf(),
+                sources,
+            })
         })
-        .flatten()
-        .collect()
+        .collect::<Vec<_>>();
+    conflicts.sort_unstable_by(|a, b| a.entry.cmp(&b.entry));
+    conflicts
 }
 
 #[derive(Default, Debug)]
 pub enum FileType {
-    Luac(bool),
+    Luac(&'static LuaFormat),
     ObjectStream(&'static ObjectStreamFormat),
     Datasheet(&'static DatasheetFormat),
     Distribution(&'static DistributionFormat),
     VShapeC(&'static VShapeFormat),
     DDS(&'static DDSFormat),
+    Mesh(&'static MeshFormat),
+    Audio(&'static AudioFormat),
+    Terrain(&'static TerrainFormat),
+    Material(&'static MaterialFormat),
+    /// Converted by a handler registered with [`handler::register`].
+    Custom(Arc<dyn FileHandler>),
     #[default]
     Other,
 }
+
+impl FileType {
+    /// Command line name of the format, e.g. `pretty`, or `bytes` for entries that aren't converted.
+    pub fn format_name(&self) -> String {
+        let name = match self {
+            FileType::Luac(fmt) => format!("{:?}", fmt),
+            FileType::ObjectStream(fmt) => format!("{:?}", fmt),
+            FileType::Datasheet(fmt) => format!("{:?}", fmt),
+            FileType::Distribution(fmt) => format!("{:?}", fmt),
+            FileType::VShapeC(fmt) => format!("{:?}", fmt),
+            FileType::DDS(fmt) => format!("{:?}", fmt),
+            FileType::Mesh(fmt) => format!("{:?}", fmt),
+            FileType::Audio(fmt) => format!("{:?}", fmt),
+            FileType::Terrain(fmt) => format!("{:?}", fmt),
+            FileType::Material(fmt) => format!("{:?}", fmt),
+            FileType::Custom(handler) => handler.name().to_string(),
+            FileType::Other => "bytes".to_string(),
+        };
+        name.to_lowercase()
+    }
+
+    /// Whether entries of this type are written exactly as decompressed.
+    pub fn is_passthrough(&self) -> bool {
+        matches!(
+            self,
+            FileType::ObjectStream(ObjectStreamFormat::BYTES)
+                | FileType::Distribution(DistributionFormat::BYTES)
+                | FileType::VShapeC(VShapeFormat::BYTES)
+                | FileType::Mesh(MeshFormat::BYTES)
+                | FileType::Audio(AudioFormat::BYTES)
+                | FileType::Terrain(TerrainFormat::BYTES)
+                | FileType::Material(MaterialFormat::BYTES)
+                | FileType::Other
+        )
+    }
+
+    /// Resolves a format name such as `pretty` or `png` for the given kind of entry.
+    pub fn with_format(kind: FileKind, format: &str) -> Option<Self> {
+        let file_type = match kind {
+            FileKind::Luac => FileType::Luac(variant(format)?),
+            FileKind::ObjectStream => FileType::ObjectStream(variant(format)?),
+            FileKind::Datasheet => FileType::Datasheet(variant(format)?),
+            FileKind::Distribution => FileType::Distribution(variant(format)?),
+            FileKind::VShapeC => FileType::VShapeC(variant(format)?),
+            FileKind::DDS => FileType::DDS(variant(format)?),
+            FileKind::Mesh => FileType::Mesh(variant(format)?),
+            FileKind::Audio => FileType::Audio(variant(format)?),
+            FileKind::Terrain => FileType::Terrain(variant(format)?),
+            FileKind::Material => FileType::Material(variant(format)?),
+            FileKind::Other => FileType::Other,
+        };
+        Some(file_type)
+    }
+}
+
+/// The kind of an entry as detected from its contents, independent of the requested output format.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize)]
+pub enum FileKind {
+    Luac,
+    ObjectStream,
+    Datasheet,
+    Distribution,
+    VShapeC,
+    DDS,
+    Mesh,
+    Audio,
+    Terrain,
+    Material,
+    #[default]
+    Other,
+}
+
+impl From<ContentType> for FileKind {
+    fn from(value: ContentType) -> Self {
+        match value {
+            ContentType::LUAC => FileKind::Luac,
+            ContentType::OBJECTSTREAM => FileKind::ObjectStream,
+            ContentType::DATASHEET => FileKind::Datasheet,
+            ContentType::DISTRIBUTION => FileKind::Distribution,
+            ContentType::VSHAPEC => FileKind::VShapeC,
+            ContentType::DDS => FileKind::DDS,
+            ContentType::MESH => FileKind::Mesh,
+            ContentType::AUDIO => FileKind::Audio,
+            ContentType::TERRAIN => FileKind::Terrain,
+            ContentType::MATERIAL => FileKind::Material,
+            ContentType::OTHER => FileKind::Other,
+        }
+    }
+}
+
+impl std::str::FromStr for FileKind {
+    type Err = io::Error;
+
+    /// Parses a content type as given to `--type`, e.g. `datasheet`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        variant::<ContentType>(s)
+            .map(|kind| FileKind::from(*kind))
+            .ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("unknown content type {s}"),
+                )
+            })
+    }
+}
+
+/// The kinds selected with `--type`, empty when every kind is kept.
+fn kinds(filter: &Filter) -> Vec<FileKind> {
+    filter.types.iter().copied().map(FileKind::from).collect()
+}
+
+/// Keeps the entries whose contents are one of `kinds`, probing the head of each entry one pak
+/// at a time. Returns `map` untouched when `kinds` is empty.
+fn of_kinds(
+    map: HashMap<&'static PathBuf, &'static (PathBuf, String)>,
+    kinds: &[FileKind],
+) -> HashMap<&'static PathBuf, &'static (PathBuf, String)> {
+    if kinds.is_empty() {
+        return map;
+    }
+
+    let mut paks: HashMap<&PathBuf, Vec<_>> = HashMap::new();
+    map.into_iter().for_each(|(entry, value)| {
+        paks.entry(&value.0).or_default().push((entry, value));
+    });
+
+    paks.into_par_iter()
+        .flat_map_iter(|(pak, entries)| {
+            let Ok(mut archive) = pak::archive(pak) else {
+                return vec![];
+            };
+            entries
+                .into_iter()
+                .filter(|(_, (_, name))| {
+                    let Some(mut zip) = archive
+                        .index_for_name(name)
+                        .and_then(|index| archive.by_index_raw(index).ok())
+                    else {
+                        return false;
+                    };
+                    let kind = Decompressor::peek(&mut zip)
+                        .unwrap_or_else(|_| decompressor::detect(&[], name));
+                    kinds.contains(&kind)
+                })
+                .collect::<Vec<_>>()
+        })
+        .collect()
+}
+
 pub async fn load_localization(
-    paths: &HashMap<PathBuf, (PathBuf, String)>,
+    paths: &HashMap<&PathBuf, &(PathBuf, String)>,
     locale: String,
 ) -> DashMap<String, Option<String>> {
     let locale_path = PathBuf::from(format!("localization/{}", locale));
     let files = paths
         .iter()
         .filter(|(_, (_, name))| name.starts_with(locale_path.to_str().unwrap()))
-        .map(|(_, v)| v)
+        .map(|(_, v)| *v)
         .collect::<Vec<_>>();
 
     files
@@ -633,8 +2553,7 @@ pub async fn load_localization(
         .collect::<HashSet<_>>()
         .par_iter()
         .map(|path| {
-            let file = std::fs::File::open(path).unwrap();
-            let mut archive = ZipArchive::new(file).unwrap();
+            let mut archive = pak::archive(path).unwrap();
 
             files
                 .iter()
@@ -662,6 +2581,57 @@ pub async fn load_localization(
         .collect::<DashMap<_, _>>()
 }
 
+/// Loads every datasheet to index its rows for resolving references between datasheets.
+pub fn load_resolver(
+    paths: &HashMap<&PathBuf, &(PathBuf, String)>,
+    resolve: &DatasheetResolve,
+) -> Resolver {
+    let datasheets = read_datasheets(
+        paths
+            .values()
+            .copied()
+            .filter(|(_, name)| name.ends_with(".datasheet")),
+    );
+
+    let resolution = match resolve {
+        DatasheetResolve::INLINE => Resolution::Inline,
+        DatasheetResolve::ANNOTATE => Resolution::Annotate,
+    };
+    Resolver::new(resolution, datasheets)
+}
+
+/// Parses the datasheets at the given pak and name pairs, leaving out ones that can't be read.
+fn read_datasheets<'a, I>(entries: I) -> Vec<Datasheet<'static>>
+where
+    I: IntoIterator<Item = &'a (PathBuf, String)>,
+{
+    let mut paks: HashMap<&PathBuf, Vec<&str>> = HashMap::new();
+    entries
+        .into_iter()
+        .for_each(|(pak, name)| paks.entry(pak).or_default().push(name));
+
+    paks.into_par_iter()
+        .flat_map_iter(|(pak, names)| {
+            let Ok(mut archive) = pak::archive(pak) else {
+                return vec![];
+            };
+
+            names
+                .into_iter()
+                .filter_map(|name| {
+                    let index = archive.index_for_name(name)?;
+                    let mut zip = archive.by_index_raw(index).ok()?;
+                    let de = Decompressor::try_new(&mut zip, None).ok()?;
+                    let mut buf = vec![];
+                    de.write_as(&FileType::Datasheet(&DatasheetFormat::BYTES), &mut buf)
+                        .ok()?;
+                    Datasheet::try_from(buf).ok()
+                })
+                .collect::<Vec<_>>()
+        })
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
 
@@ -679,6 +2649,126 @@ mod tests {
     //     Ok(())
     // }
 
+    #[test]
+    fn globs_combine_includes_and_excludes() {
+        let matchers = globs(Some(
+            &"**/*.datasheet, sharedassets/**, !**/*.dds".to_string(),
+        ));
+        assert!(matchers.is_match(&"datatables/javelindata_items.datasheet"));
+        assert!(matchers.is_match(&"sharedassets/genericassets/rarity.json"));
+        assert!(!matchers.is_match(&"sharedassets/textures/icon.dds"));
+        assert!(!matchers.is_match(&"slices/world.dynamicslice"));
+
+        let matchers = globs(Some(&"!**/*.dds".to_string()));
+        assert!(matchers.is_match(&"slices/world.dynamicslice"));
+        assert!(!matchers.is_match(&"textures/icon.dds"));
+        assert!(globs(None).is_match(&"anything"));
+    }
+
+    #[test]
+    fn regex_matches_the_slash_separated_path() {
+        let mut matchers = globs(Some(&"!**/*.dds".to_string()));
+        matchers.regex = Some(Regex::new(r"^sharedassets/.*_\d+\.").unwrap());
+        assert!(matchers.is_match(&"sharedassets\\icons\\icon_01.png"));
+        assert!(!matchers.is_match(&"sharedassets/icons/icon_01.dds"));
+        assert!(!matchers.is_match(&"sharedassets/icons/icon.png"));
+    }
+
+    #[test]
+    fn files_keep_only_the_listed_entries() {
+        let mut matchers = globs(None);
+        matchers.files = Some(HashSet::from(
+            ["sharedassets/icons/icon_01.png".to_string()],
+        ));
+        assert!(matchers.is_match(&"SharedAssets\\icons\\icon_01.png"));
+        assert!(!matchers.is_match(&"sharedassets/icons/icon_02.png"));
+    }
+
+    #[test]
+    fn lower_path_case_matches_regardless_of_case() {
+        let mut matchers = globs_with_case(Some(&"Textures/**".to_string()), true);
+        matchers.lower = true;
+        assert!(matchers.is_match(&"textures/icon.dds"));
+        assert!(matchers.is_match(&"TEXTURES/Icon.dds"));
+        assert!(!globs(Some(&"Textures/**".to_string())).is_match(&"textures/icon.dds"));
+    }
+
+    #[test]
+    fn dedupe_keeps_the_last_pak() {
+        let entry = |pak: &str| {
+            (
+                PathBuf::from("sharedassets/icon.dds"),
+                (PathBuf::from(pak), "sharedassets/icon.dds".to_string()),
+            )
+        };
+        let (map, conflicts) = dedupe(vec![
+            entry("assets/DataStrm-part10.pak"),
+            entry("assets/DataStrm-part2.pak"),
+            entry("assets/DataStrm-part9.pak"),
+        ]);
+        assert_eq!(map.len(), 1);
+        assert_eq!(
+            map[Path::new("sharedassets/icon.dds")].0,
+            Path::new("assets/DataStrm-part10.pak")
+        );
+        // missing paks can't be compared
+        assert!(conflicts.is_empty());
+    }
+
+    #[test]
+    fn compresses_only_text_outputs() {
+        let gzip = Some(OutputCompression::GZIP);
+        assert_eq!(
+            compressed_path(Path::new("out/items.datasheet.json"), gzip),
+            Some(PathBuf::from("out/items.datasheet.json.gz"))
+        );
+        assert_eq!(compressed_path(Path::new("out/icon.png"), gzip), None);
+        assert_eq!(compressed_path(Path::new("out/items.json"), None), None);
+
+        let (path, buf) = compress_output(
+            "a.json".into(),
+            Cow::Borrowed(b"{}"),
+            Some(OutputCompression::ZSTD),
+        )
+        .unwrap();
+        assert_eq!(path, Path::new("a.json.zst"));
+        assert_eq!(zstd::decode_all(buf.as_ref()).unwrap(), b"{}");
+    }
+
+    #[test]
+    fn links_are_relative_to_their_directory() {
+        assert_eq!(
+            relative(Path::new("out/a/b/icon.dds"), Path::new("out/a/c")),
+            Path::new("../b/icon.dds")
+        );
+        assert_eq!(
+            relative(Path::new("out/a/icon.dds"), Path::new("out/a")),
+            Path::new("icon.dds")
+        );
+    }
+
+    #[test]
+    fn split_mips() {
+        assert!(is_split_mip(Path::new("textures/rock_ddna.dds.1")));
+        assert!(is_split_mip(Path::new("textures/rock_ddna.dds.12")));
+        assert!(!is_split_mip(Path::new("textures/rock_ddna.dds")));
+        assert!(is_split_mip(Path::new("textures/rock_ddna.dds.a")));
+        assert!(is_split_mip(Path::new("textures/rock_ddna.dds.3a")));
+        assert!(!is_split_mip(Path::new("textures/rock_ddna.dds.a.1")));
+        assert!(!is_split_mip(Path::new("textures/rock_ddna.dds.b")));
+        assert!(!is_split_mip(Path::new("objects/rock.cgf.1")));
+    }
+
+    #[test]
+    fn kind_from_content_type() {
+        assert_eq!(
+            "datasheet".parse::<FileKind>().unwrap(),
+            FileKind::Datasheet
+        );
+        assert_eq!("DDS".parse::<FileKind>().unwrap(), FileKind::DDS);
+        assert!("texture".parse::<FileKind>().is_err());
+    }
+
     #[test]
     fn pak_map() {
         let root = "C:/Program Files (x86)/Steam/steamapps/common/New World";
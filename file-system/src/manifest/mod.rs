@@ -0,0 +1,119 @@
+use crate::pak::EntryInfo;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    fs::File,
+    io::{self, BufReader, BufWriter, Write},
+    path::{Path, PathBuf},
+};
+
+pub const FILE_NAME: &str = "manifest.json";
+
+/// An extracted entry as listed in `manifest.json`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ManifestEntry {
+    /// Path of the entry in the virtual file system of the game.
+    pub path: PathBuf,
+    /// Pak the entry was read from, relative to the game directory.
+    pub pak: PathBuf,
+    #[serde(flatten)]
+    pub info: EntryInfo,
+    /// What the entry was written as, e.g. `csv`, `bytes` when it wasn't converted.
+    pub format: String,
+    /// Files written for the entry, relative to the output directory.
+    pub outputs: Vec<PathBuf>,
+}
+
+/// Every entry extracted into an output directory, updated by each run.
+#[derive(Debug, Default)]
+pub struct Manifest {
+    entries: BTreeMap<PathBuf, ManifestEntry>,
+}
+
+impl Manifest {
+    /// The manifest of `out_dir`, empty when there is none yet or it can't be read.
+    pub fn load(out_dir: &Path) -> Self {
+        Self::read(out_dir.join(FILE_NAME)).unwrap_or_default()
+    }
+
+    pub fn read<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let reader = BufReader::new(File::open(path)?);
+        let entries = serde_json::from_reader::<_, Vec<ManifestEntry>>(reader)?;
+        Ok(Self {
+            entries: entries
+                .into_iter()
+                .map(|entry| (entry.path.to_owned(), entry))
+                .collect(),
+        })
+    }
+
+    /// Writes the entries sorted by path as a json array.
+    pub fn save(&self, out_dir: &Path) -> io::Result<()> {
+        std::fs::create_dir_all(out_dir)?;
+        let mut writer = BufWriter::new(File::create(out_dir.join(FILE_NAME))?);
+        serde_json::to_writer_pretty(&mut writer, &self.entries.values().collect::<Vec<_>>())?;
+        writer.flush()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn get(&self, path: &Path) -> Option<&ManifestEntry> {
+        self.entries.get(path)
+    }
+
+    /// Whether the entry at `path` is listed with the same CRC32 and size.
+    pub fn is_unchanged(&self, path: &Path, info: EntryInfo) -> bool {
+        self.get(path).is_some_and(|entry| entry.info == info)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &ManifestEntry> {
+        self.entries.values()
+    }
+
+    pub fn insert(&mut self, entry: ManifestEntry) {
+        self.entries.insert(entry.path.to_owned(), entry);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trips_through_the_output_directory() {
+        let dir = std::env::temp_dir().join("nwtools-manifest-test");
+        let _ = std::fs::remove_dir_all(&dir);
+
+        let mut manifest = Manifest::load(&dir);
+        assert!(manifest.is_empty());
+        for path in ["b.datasheet", "a.datasheet"] {
+            manifest.insert(ManifestEntry {
+                path: path.into(),
+                pak: "assets/DataSheets.pak".into(),
+                info: EntryInfo { crc32: 1, size: 2 },
+                format: "csv".to_string(),
+                outputs: vec![format!("{path}.csv").into()],
+            });
+        }
+        manifest.save(&dir).unwrap();
+
+        let loaded = Manifest::load(&dir);
+        assert_eq!(loaded.len(), 2);
+        assert_eq!(
+            loaded.iter().map(|entry| &entry.path).collect::<Vec<_>>(),
+            [Path::new("a.datasheet"), Path::new("b.datasheet")]
+        );
+        assert_eq!(loaded.get(Path::new("a.datasheet")).unwrap().format, "csv");
+        assert!(loaded.is_unchanged(Path::new("a.datasheet"), EntryInfo { crc32: 1, size: 2 }));
+        assert!(!loaded.is_unchanged(Path::new("a.datasheet"), EntryInfo { crc32: 3, size: 2 }));
+        assert!(!loaded.is_unchanged(Path::new("c.datasheet"), EntryInfo { crc32: 1, size: 2 }));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
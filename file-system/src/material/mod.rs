@@ -0,0 +1,150 @@
+//! CryEngine `.mtl` materials and the textures they reference.
+
+use std::io;
+
+use serde::{Deserialize, Serialize};
+
+/// A material slot, indexed the same as the material ids of mesh subsets.
+#[derive(Debug, Serialize)]
+pub struct Material {
+    pub slot: usize,
+    pub name: String,
+    pub shader: String,
+    pub textures: Vec<Texture>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Texture {
+    /// The shader input, such as `Diffuse` or `Bumpmap`.
+    pub map: String,
+    /// The path as written in the material.
+    pub file: String,
+    /// Where the texture is extracted to, missing when it isn't in the paks.
+    pub path: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct XmlMaterial {
+    #[serde(rename = "@Name", default)]
+    name: String,
+    #[serde(rename = "@Shader", default)]
+    shader: String,
+    #[serde(rename = "Textures", default)]
+    textures: Option<XmlTextures>,
+    #[serde(rename = "SubMaterials", default)]
+    sub_materials: Option<XmlSubMaterials>,
+}
+
+#[derive(Deserialize)]
+struct XmlTextures {
+    #[serde(rename = "Texture", default)]
+    textures: Vec<XmlTexture>,
+}
+
+#[derive(Deserialize)]
+struct XmlTexture {
+    #[serde(rename = "@Map", default)]
+    map: String,
+    #[serde(rename = "@File", default)]
+    file: String,
+}
+
+#[derive(Deserialize)]
+struct XmlSubMaterials {
+    #[serde(rename = "Material", default)]
+    materials: Vec<XmlMaterial>,
+}
+
+/// Parses the slots of a material, its sub materials or the material itself when it has none,
+/// resolving each texture with `resolve`.
+pub fn from_bytes<F>(buf: &[u8], resolve: F) -> io::Result<Vec<Material>>
+where
+    F: Fn(&str) -> Option<String>,
+{
+    let text = std::str::from_utf8(buf).map_err(io::Error::other)?;
+    let text = text.trim_start_matches('\u{feff}');
+    let root: XmlMaterial = quick_xml::de::from_str(text).map_err(io::Error::other)?;
+
+    let slots = match root.sub_materials {
+        Some(sub_materials) if !sub_materials.materials.is_empty() => sub_materials.materials,
+        _ => vec![XmlMaterial {
+            sub_materials: None,
+            ..root
+        }],
+    };
+
+    Ok(slots
+        .into_iter()
+        .enumerate()
+        .map(|(slot, material)| Material {
+            slot,
+            name: material.name,
+            shader: material.shader,
+            textures: material
+                .textures
+                .map(|textures| textures.textures)
+                .unwrap_or_default()
+                .into_iter()
+                .map(|texture| Texture {
+                    path: resolve(&texture.file),
+                    map: texture.map,
+                    file: texture.file,
+                })
+                .collect(),
+        })
+        .collect())
+}
+
+/// The pak entry a texture reference compiles to. Source images are referenced by their
+/// original extension but stored as `.dds`, and `$` names are engine placeholders.
+pub fn texture_entry(file: &str) -> Option<String> {
+    let file = file.trim().replace('\\', "/").to_lowercase();
+    let file = file.trim_start_matches("./").trim_start_matches('/');
+    if file.is_empty() || file.starts_with('$') {
+        return None;
+    }
+    match file.rsplit_once('.') {
+        Some((stem, "tif" | "tga" | "png" | "bmp" | "jpg")) => Some(format!("{stem}.dds")),
+        Some(_) => Some(file.to_owned()),
+        None => Some(format!("{file}.dds")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn material_slots() {
+        let mtl = br#"<Material MtlFlags="524544">
+ <SubMaterials>
+  <Material Name="bark" Shader="Illum">
+   <Textures>
+    <Texture Map="Diffuse" File="Objects/Trees/Bark_diff.tif"/>
+    <Texture Map="Environment" File="$NearestCubeMap"/>
+   </Textures>
+  </Material>
+  <Material Name="leaves" Shader="Vegetation"/>
+ </SubMaterials>
+</Material>"#;
+
+        let materials = from_bytes(mtl, texture_entry).unwrap();
+        assert_eq!(materials.len(), 2);
+        assert_eq!(materials[0].name, "bark");
+        assert_eq!(
+            materials[0].textures[0].path.as_deref(),
+            Some("objects/trees/bark_diff.dds")
+        );
+        assert_eq!(materials[0].textures[1].path, None);
+        assert_eq!(materials[1].slot, 1);
+        assert!(materials[1].textures.is_empty());
+    }
+
+    #[test]
+    fn single_material() {
+        let mtl = br#"<Material Name="rock" Shader="Illum"><Textures><Texture Map="Bumpmap" File="textures/rock_ddna.dds"/></Textures></Material>"#;
+        let materials = from_bytes(mtl, |_| None).unwrap();
+        assert_eq!(materials.len(), 1);
+        assert_eq!(materials[0].textures[0].map, "Bumpmap");
+    }
+}
@@ -0,0 +1,279 @@
+use crate::azcs::{self, is_compressed, is_uncompressed, Compressor};
+use cli::commands::pack::PackMethod;
+use std::{
+    io::{self, Cursor, Seek, Write},
+    path::Path,
+};
+use zip::{write::SimpleFileOptions, CompressionMethod, ZipArchive, ZipWriter};
+
+/// Entries smaller than this are always stored, compressing them only adds overhead.
+const MIN_COMPRESSED_SIZE: usize = 64;
+
+/// The zip method id the game's paks mark Oodle entries with, deprecated as `Tokenize` by zip.
+const OODLE_METHOD: u16 = 15;
+#[allow(deprecated)]
+const OODLE: CompressionMethod = CompressionMethod::Unsupported(OODLE_METHOD);
+
+/// Extensions of formats that are already compressed and gain nothing from compressing again.
+const STORED_EXTENSIONS: [&str; 7] = ["png", "jpg", "jpeg", "webp", "wem", "bnk", "ogg"];
+
+pub struct Packer<W: Write + Seek> {
+    zip: ZipWriter<W>,
+    method: &'static PackMethod,
+}
+
+impl<W: Write + Seek> Packer<W> {
+    /// Creates a new [`Packer`].
+    pub fn new(writer: W, method: &'static PackMethod) -> Self {
+        Self {
+            zip: ZipWriter::new(writer),
+            method,
+        }
+    }
+
+    /// Writes a single entry, returning the compression method it was written with. Object
+    /// streams, which extraction leaves AZCS decompressed, are compressed back into the AZCS
+    /// stream the engine reads.
+    pub fn add(&mut self, name: &str, data: &[u8]) -> io::Result<CompressionMethod> {
+        let name = name.replace('\\', "/");
+        let mut encoded = vec![];
+        let data = if is_uncompressed(data) {
+            azcs::compress(data, Compressor::Zlib, &mut encoded)?;
+            encoded.as_slice()
+        } else {
+            data
+        };
+        let method = self.compression(name.as_str(), data);
+        if method == OODLE {
+            return self.add_oodle(&name, data);
+        }
+        let options = SimpleFileOptions::default().compression_method(method);
+
+        self.zip.start_file(name, options)?;
+        self.zip.write_all(data)?;
+        Ok(method)
+    }
+
+    /// zip can't write Oodle entries itself, so each is built as a single entry archive and its
+    /// raw entry copied over. Entries Oodle doesn't shrink are stored instead.
+    fn add_oodle(&mut self, name: &str, data: &[u8]) -> io::Result<CompressionMethod> {
+        let compressed = oodle(data)?;
+        if compressed.len() >= data.len() {
+            let options =
+                SimpleFileOptions::default().compression_method(CompressionMethod::Stored);
+            self.zip.start_file(name, options)?;
+            self.zip.write_all(data)?;
+            return Ok(CompressionMethod::Stored);
+        }
+
+        let entry = single_entry(
+            name,
+            OODLE_METHOD,
+            &compressed,
+            crc32fast::hash(data),
+            data.len(),
+        )?;
+        let mut archive = ZipArchive::new(Cursor::new(entry))?;
+        self.zip.raw_copy_file(archive.by_index_raw(0)?)?;
+        Ok(OODLE)
+    }
+
+    pub fn finish(self) -> io::Result<W> {
+        Ok(self.zip.finish()?)
+    }
+
+    fn compression(&self, name: &str, data: &[u8]) -> CompressionMethod {
+        match self.method {
+            PackMethod::STORED => CompressionMethod::Stored,
+            PackMethod::DEFLATE => CompressionMethod::Deflated,
+            PackMethod::AUTO | PackMethod::OODLE => {
+                let ext = Path::new(name)
+                    .extension()
+                    .and_then(|ext| ext.to_str())
+                    .map(|ext| ext.to_lowercase());
+                if data.len() < MIN_COMPRESSED_SIZE
+                    || is_compressed(data)
+                    || ext.is_some_and(|ext| STORED_EXTENSIONS.contains(&ext.as_str()))
+                {
+                    CompressionMethod::Stored
+                } else if *self.method == PackMethod::OODLE {
+                    OODLE
+                } else {
+                    CompressionMethod::Deflated
+                }
+            }
+        }
+    }
+}
+
+/// Compresses `data` with Oodle Kraken as the game's paks are.
+fn oodle(data: &[u8]) -> io::Result<Vec<u8>> {
+    // Kraken's worst case expansion is well under an eighth of the input plus a block
+    let mut buf = vec![0; data.len() + data.len() / 8 + 65536];
+    let len = oodle_safe::compress(
+        oodle_safe::Compressor::Kraken,
+        data,
+        &mut buf,
+        oodle_safe::CompressionLevel::Normal,
+        None,
+        None,
+        None,
+    )
+    .map_err(|_| io::Error::other("Error with oodle_safe::compress."))?;
+    buf.truncate(len);
+    Ok(buf)
+}
+
+/// A zip archive holding only `name`, already compressed with `method`.
+fn single_entry(
+    name: &str,
+    method: u16,
+    compressed: &[u8],
+    crc32: u32,
+    size: usize,
+) -> io::Result<Vec<u8>> {
+    let too_large = || io::Error::other(format!("{name} is too large for a pak entry"));
+    let compressed_size = u32::try_from(compressed.len()).map_err(|_| too_large())?;
+    let size = u32::try_from(size).map_err(|_| too_large())?;
+    let name_len = u16::try_from(name.len()).map_err(|_| too_large())?;
+    // version 2.0, no flags, the method, and a 1980-01-01 00:00 timestamp
+    let fields = |buf: &mut Vec<u8>| {
+        for value in [20u16, 0, method, 0, 0x21] {
+            buf.extend_from_slice(&value.to_le_bytes());
+        }
+        for value in [crc32, compressed_size, size] {
+            buf.extend_from_slice(&value.to_le_bytes());
+        }
+        buf.extend_from_slice(&name_len.to_le_bytes());
+    };
+
+    let mut buf = Vec::with_capacity(compressed.len() + 2 * name.len() + 98);
+    buf.extend_from_slice(&0x04034b50u32.to_le_bytes());
+    fields(&mut buf);
+    buf.extend_from_slice(&0u16.to_le_bytes());
+    buf.extend_from_slice(name.as_bytes());
+    buf.extend_from_slice(compressed);
+
+    let central_directory = buf.len() as u32;
+    buf.extend_from_slice(&0x02014b50u32.to_le_bytes());
+    buf.extend_from_slice(&20u16.to_le_bytes());
+    fields(&mut buf);
+    // extra and comment lengths, disk, attributes and the local header offset
+    for value in [0u16, 0, 0, 0] {
+        buf.extend_from_slice(&value.to_le_bytes());
+    }
+    buf.extend_from_slice(&0u32.to_le_bytes());
+    buf.extend_from_slice(&0u32.to_le_bytes());
+    buf.extend_from_slice(name.as_bytes());
+
+    let central_directory_size = buf.len() as u32 - central_directory;
+    buf.extend_from_slice(&0x06054b50u32.to_le_bytes());
+    for value in [0u16, 0, 1, 1] {
+        buf.extend_from_slice(&value.to_le_bytes());
+    }
+    buf.extend_from_slice(&central_directory_size.to_le_bytes());
+    buf.extend_from_slice(&central_directory.to_le_bytes());
+    buf.extend_from_slice(&0u16.to_le_bytes());
+    Ok(buf)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn single_entries_keep_their_method() {
+        let entry = single_entry("a/b.json", OODLE_METHOD, b"compressed", 0x1234, 42).unwrap();
+        let mut archive = ZipArchive::new(Cursor::new(entry)).unwrap();
+        let file = archive.by_index_raw(0).unwrap();
+        assert_eq!(file.name(), "a/b.json");
+        assert_eq!(file.compression(), OODLE);
+        assert_eq!(file.crc32(), 0x1234);
+        assert_eq!(file.size(), 42);
+        assert_eq!(file.compressed_size(), 10);
+    }
+
+    #[test]
+    fn oodle_entries_read_back() {
+        let data = b"sharedassets/springboardentitites/".repeat(200);
+
+        let mut packer = Packer::new(Cursor::new(vec![]), &PackMethod::OODLE);
+        assert_eq!(packer.add("a/b.json", &data).unwrap(), OODLE);
+        let mut archive = ZipArchive::new(packer.finish().unwrap()).unwrap();
+
+        let mut zip = archive.by_name("a/b.json").unwrap();
+        let de = crate::decompressor::Decompressor::try_new(&mut zip, None).unwrap();
+        let mut read = vec![];
+        de.write_as(&crate::FileType::Other, &mut read).unwrap();
+        assert_eq!(read, data);
+    }
+
+    #[test]
+    fn zstd_entries_read_back() {
+        let data = b"sharedassets/springboardentitites/".repeat(200);
+
+        let mut zip = ZipWriter::new(Cursor::new(vec![]));
+        zip.start_file(
+            "a/b.json",
+            SimpleFileOptions::default().compression_method(CompressionMethod::Zstd),
+        )
+        .unwrap();
+        zip.write_all(&data).unwrap();
+        let mut archive = ZipArchive::new(zip.finish().unwrap()).unwrap();
+
+        let mut zip = archive.by_index_raw(0).unwrap();
+        assert_eq!(zip.compression(), CompressionMethod::Zstd);
+        let de = crate::decompressor::Decompressor::try_new(&mut zip, None).unwrap();
+        let mut read = vec![];
+        de.write_as(&crate::FileType::Other, &mut read).unwrap();
+        assert_eq!(read, data);
+    }
+
+    #[test]
+    fn lz4_entries_read_back() {
+        let data = b"sharedassets/springboardentitites/".repeat(200);
+        let mut encoder = lz4_flex::frame::FrameEncoder::new(vec![]);
+        encoder.write_all(&data).unwrap();
+        let compressed = encoder.finish().unwrap();
+
+        let entry = single_entry(
+            "a/b.json",
+            crate::decompressor::LZ4_METHOD,
+            &compressed,
+            crc32fast::hash(&data),
+            data.len(),
+        )
+        .unwrap();
+        let mut archive = ZipArchive::new(Cursor::new(entry)).unwrap();
+
+        let mut zip = archive.by_index_raw(0).unwrap();
+        let de = crate::decompressor::Decompressor::try_new(&mut zip, None).unwrap();
+        let mut read = vec![];
+        de.write_as(&crate::FileType::Other, &mut read).unwrap();
+        assert_eq!(read, data);
+    }
+
+    #[test]
+    fn object_streams_are_packed_as_azcs() {
+        let mut stream = vec![0x00, 0x00, 0x00, 0x00, 0x03];
+        stream.extend((0..200u8).cycle().take(1000));
+
+        let mut packer = Packer::new(Cursor::new(vec![]), &PackMethod::AUTO);
+        assert_eq!(
+            packer.add("slices\\camp.slice", &stream).unwrap(),
+            CompressionMethod::Stored
+        );
+        let mut archive = ZipArchive::new(packer.finish().unwrap()).unwrap();
+
+        let mut raw = vec![];
+        std::io::copy(&mut archive.by_index_raw(0).unwrap(), &mut raw).unwrap();
+        assert!(is_compressed(&raw));
+
+        let mut zip = archive.by_name("slices/camp.slice").unwrap();
+        let de = crate::decompressor::Decompressor::try_new(&mut zip, None).unwrap();
+        let mut extracted = vec![];
+        de.write_as(&crate::FileType::Other, &mut extracted)
+            .unwrap();
+        assert_eq!(extracted, stream);
+    }
+}
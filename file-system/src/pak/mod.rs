@@ -1,7 +1,178 @@
-use std::fs::File;
-use zip::ZipArchive;
+use crate::{
+    decompressor::{detect, Decompressor},
+    retry::retry,
+    FileKind,
+};
+use memmap2::Mmap;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    fs::File,
+    io::{self, BufReader, Cursor, Read, Seek, SeekFrom},
+    path::{Path, PathBuf},
+};
+use zip::{CompressionMethod, ZipArchive};
+
+/// Reader over a pak file, memory mapped with `--mmap` so entries are decompressed straight from
+/// the mapped pages instead of being copied through a read buffer first.
+pub enum PakReader {
+    Buffered(BufReader<File>),
+    Mapped(Cursor<Mmap>),
+}
+
+impl PakReader {
+    pub fn open<P>(path: P) -> io::Result<Self>
+    where
+        P: AsRef<Path>,
+    {
+        Self::with_mmap(path, crate::mmap())
+    }
+
+    /// Opens the pak at `path`, memory mapping it when `mmap` is set.
+    pub fn with_mmap<P>(path: P, mmap: bool) -> io::Result<Self>
+    where
+        P: AsRef<Path>,
+    {
+        let file = retry(|| File::open(path.as_ref()))?;
+        if mmap {
+            // SAFETY: paks are only read, a game update rewriting one mid-run is not supported
+            let mmap = unsafe { Mmap::map(&file)? };
+            Ok(Self::Mapped(Cursor::new(mmap)))
+        } else {
+            Ok(Self::Buffered(BufReader::new(file)))
+        }
+    }
+}
+
+impl Read for PakReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self {
+            Self::Buffered(reader) => reader.read(buf),
+            Self::Mapped(reader) => reader.read(buf),
+        }
+    }
+}
+
+impl Seek for PakReader {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        match self {
+            Self::Buffered(reader) => reader.seek(pos),
+            Self::Mapped(reader) => reader.seek(pos),
+        }
+    }
+}
+
+/// Opens the zip archive of a pak with the reader selected on the command line.
+pub fn archive<P>(path: P) -> io::Result<ZipArchive<PakReader>>
+where
+    P: AsRef<Path>,
+{
+    Ok(ZipArchive::new(PakReader::open(path)?)?)
+}
 
 pub struct Pak {
     file: File,
     archive: ZipArchive<File>,
 }
+
+#[derive(Debug, Default, Serialize)]
+pub struct PakStats {
+    pub path: PathBuf,
+    pub entries: usize,
+    pub compressed_size: u64,
+    pub uncompressed_size: u64,
+    pub methods: BTreeMap<&'static str, usize>,
+    pub file_types: BTreeMap<FileKind, usize>,
+}
+
+impl PakStats {
+    /// Collects statistics for the given entries of a pak. Sizes and compression methods come from
+    /// the central directory, file types require decompressing every entry.
+    pub fn from_entries<P>(pak: P, names: &[&str], types: bool) -> io::Result<Self>
+    where
+        P: AsRef<Path>,
+    {
+        let mut archive = archive(pak.as_ref())?;
+        let mut stats = PakStats {
+            path: pak.as_ref().to_path_buf(),
+            ..Default::default()
+        };
+
+        for name in names {
+            let Some(index) = archive.index_for_name(name) else {
+                continue;
+            };
+            let mut zip = archive.by_index_raw(index)?;
+
+            stats.entries += 1;
+            stats.compressed_size += zip.compressed_size();
+            stats.uncompressed_size += zip.size();
+            *stats
+                .methods
+                .entry(method_name(zip.compression()))
+                .or_default() += 1;
+
+            if types {
+                let kind = match Decompressor::try_new(&mut zip, None) {
+                    Ok(de) => de.kind(),
+                    Err(_) => detect(&[], name),
+                };
+                *stats.file_types.entry(kind).or_default() += 1;
+            }
+        }
+
+        Ok(stats)
+    }
+
+    pub fn ratio(&self) -> f64 {
+        if self.uncompressed_size == 0 {
+            return 1.0;
+        }
+        self.compressed_size as f64 / self.uncompressed_size as f64
+    }
+}
+
+/// Central directory metadata of a single entry.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct EntryInfo {
+    pub crc32: u32,
+    pub size: u64,
+}
+
+/// An entry that failed to be read, decompressed or verified.
+#[derive(Debug, Clone, Serialize)]
+pub struct EntryError {
+    pub entry: PathBuf,
+    pub pak: PathBuf,
+    /// Offset of the local header in the pak, when the entry was found.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub offset: Option<u64>,
+    pub error: String,
+}
+
+/// An entry found in several paks with different contents.
+#[derive(Debug, Clone, Serialize)]
+pub struct Conflict {
+    pub entry: PathBuf,
+    /// Every copy in pak order, the last is the one extracted.
+    pub sources: Vec<ConflictSource>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ConflictSource {
+    pub pak: PathBuf,
+    #[serde(flatten)]
+    pub info: EntryInfo,
+}
+
+#[allow(deprecated)]
+pub fn method_name(method: CompressionMethod) -> &'static str {
+    match method {
+        CompressionMethod::Stored => "Stored",
+        CompressionMethod::Deflated => "Deflate",
+        CompressionMethod::Unsupported(15) => "Oodle",
+        CompressionMethod::Unsupported(crate::decompressor::LZ4_METHOD) => "Lz4",
+        CompressionMethod::Zstd => "Zstd",
+        _ => "Other",
+    }
+}
@@ -0,0 +1,159 @@
+//! Output paths of entries, in the case of `--path-case` and escaped for names Windows can't
+//! create.
+
+use std::path::{Component, Path, PathBuf};
+
+use cli::common::{escape::PathEscape, filter::PathCase};
+
+/// Device names Windows reserves in every directory, with any extension.
+const RESERVED: [&str; 22] = [
+    "CON", "PRN", "AUX", "NUL", "COM1", "COM2", "COM3", "COM4", "COM5", "COM6", "COM7", "COM8",
+    "COM9", "LPT1", "LPT2", "LPT3", "LPT4", "LPT5", "LPT6", "LPT7", "LPT8", "LPT9",
+];
+
+/// Where `entry` is written in `out_dir`, every component of it in `case` and escaped with
+/// `escape`.
+pub fn output(out_dir: &Path, entry: &Path, escape: PathEscape, case: PathCase) -> PathBuf {
+    let mut path = out_dir.to_path_buf();
+    for component in entry.components() {
+        match component {
+            Component::Normal(name) => {
+                let name = name.to_string_lossy();
+                let name = match case {
+                    PathCase::PRESERVE => name,
+                    PathCase::LOWER => name.to_lowercase().into(),
+                };
+                path.push(escape_name(&name, escape))
+            }
+            // entries can't leave the output directory
+            Component::Prefix(_)
+            | Component::RootDir
+            | Component::CurDir
+            | Component::ParentDir => {}
+        }
+    }
+    path
+}
+
+/// `name` changed so Windows can create it, as is with [`PathEscape::NONE`].
+pub fn escape_name(name: &str, escape: PathEscape) -> String {
+    let escape = match escape {
+        PathEscape::AUTO if cfg!(windows) => PathEscape::UNDERSCORE,
+        PathEscape::AUTO => PathEscape::NONE,
+        escape => escape,
+    };
+    if escape == PathEscape::NONE {
+        return name.to_owned();
+    }
+
+    let mut escaped = String::with_capacity(name.len());
+    for c in name.chars() {
+        match (c, escape) {
+            ('<' | '>' | ':' | '"' | '|' | '?' | '*' | '\0'..='\x1f', PathEscape::PERCENT) => {
+                escaped.push_str(&format!("%{:02X}", c as u32))
+            }
+            ('<' | '>' | ':' | '"' | '|' | '?' | '*' | '\0'..='\x1f', _) => escaped.push('_'),
+            (c, _) => escaped.push(c),
+        }
+    }
+
+    // trailing dots and spaces are dropped by Windows, which would merge names
+    let trimmed = escaped.trim_end_matches(['.', ' ']).len();
+    if trimmed < escaped.len() {
+        let trailing = escaped.split_off(trimmed);
+        match escape {
+            PathEscape::PERCENT => trailing
+                .chars()
+                .for_each(|c| escaped.push_str(&format!("%{:02X}", c as u32))),
+            _ => {
+                escaped.push_str(&trailing);
+                escaped.push('_');
+            }
+        }
+    }
+
+    if is_reserved(&escaped) {
+        match escape {
+            PathEscape::PERCENT => {
+                let first = escaped.remove(0);
+                escaped.insert_str(0, &format!("%{:02X}", first as u32));
+            }
+            _ => {
+                let stem = escaped.find('.').unwrap_or(escaped.len());
+                escaped.insert(stem, '_');
+            }
+        }
+    }
+    escaped
+}
+
+/// Whether `name` is a device name, which Windows reserves with or without an extension.
+fn is_reserved(name: &str) -> bool {
+    let stem = name.split('.').next().unwrap_or_default().trim_end();
+    RESERVED
+        .iter()
+        .any(|reserved| reserved.eq_ignore_ascii_case(stem))
+}
+
+/// `path` in the `\\?\` form on Windows, which lifts the `MAX_PATH` limit of 260 characters.
+/// Such paths are used as written, so it's made absolute with its separators normalized first.
+/// Elsewhere `path` is returned as it is.
+pub fn long(path: &Path) -> PathBuf {
+    #[cfg(windows)]
+    {
+        let path = std::path::absolute(path).unwrap_or_else(|_| path.to_path_buf());
+        let text = path.to_string_lossy();
+        if text.starts_with(r"\\?\") {
+            return path;
+        }
+        let normalized = path.components().collect::<PathBuf>();
+        let normalized = normalized.to_string_lossy();
+        match normalized.strip_prefix(r"\\") {
+            Some(unc) => PathBuf::from(format!(r"\\?\UNC\{unc}")),
+            None if path.is_absolute() => PathBuf::from(format!(r"\\?\{normalized}")),
+            None => path,
+        }
+    }
+    #[cfg(not(windows))]
+    path.to_path_buf()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn escapes_names_windows_cant_create() {
+        use PathEscape::*;
+        assert_eq!(escape_name("aux.lua", NONE), "aux.lua");
+        assert_eq!(escape_name("aux.lua", UNDERSCORE), "aux_.lua");
+        assert_eq!(escape_name("CON", UNDERSCORE), "CON_");
+        assert_eq!(escape_name("Com1.tar.gz", UNDERSCORE), "Com1_.tar.gz");
+        assert_eq!(escape_name("console.lua", UNDERSCORE), "console.lua");
+        assert_eq!(escape_name("a:b?.txt", UNDERSCORE), "a_b_.txt");
+        assert_eq!(escape_name("name. ", UNDERSCORE), "name. _");
+
+        assert_eq!(escape_name("aux.lua", PERCENT), "%61ux.lua");
+        assert_eq!(escape_name("a:b.txt", PERCENT), "a%3Ab.txt");
+        assert_eq!(escape_name("name.", PERCENT), "name%2E");
+    }
+
+    #[test]
+    fn outputs_stay_in_the_output_directory() {
+        let path = output(
+            Path::new("out"),
+            Path::new("../Scripts/AUX.lua"),
+            PathEscape::UNDERSCORE,
+            PathCase::LOWER,
+        );
+        assert_eq!(path, Path::new("out").join("scripts").join("aux_.lua"));
+
+        let path = output(
+            Path::new("out"),
+            Path::new("Scripts/Init.lua"),
+            PathEscape::NONE,
+            PathCase::PRESERVE,
+        );
+        assert_eq!(path, Path::new("out").join("Scripts").join("Init.lua"));
+    }
+}
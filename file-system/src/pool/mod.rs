@@ -0,0 +1,125 @@
+//! Paks opened on first use, of which only the most recently used stay open.
+
+use std::{
+    io,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
+
+use zip::ZipArchive;
+
+use crate::pak::{self, PakReader};
+
+/// How many paks extraction keeps open without `--max-open-paks`.
+pub const DEFAULT_OPEN: usize = 64;
+
+/// Open values by path, at most `capacity` of them, the least recently used closed first.
+///
+/// A value closed while a worker still holds it stays open until the worker is done with it, so
+/// a pool shared by `n` threads has at most `capacity + n` open at once.
+#[derive(Debug)]
+pub struct Pool<T> {
+    capacity: usize,
+    /// Least recently used first.
+    open: Mutex<Vec<(PathBuf, Arc<Mutex<T>>)>>,
+}
+
+/// Pak archives, reopened when needed again after they were closed.
+pub type ArchivePool = Pool<ZipArchive<PakReader>>;
+
+impl<T> Pool<T> {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity: capacity.max(1),
+            open: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// The value of `path`, opened with `open` when it isn't open. Opening happens outside the
+    /// lock so paks open in parallel, when two threads race the first one to finish is kept.
+    pub fn get<F>(&self, path: &Path, open: F) -> io::Result<Arc<Mutex<T>>>
+    where
+        F: FnOnce(&Path) -> io::Result<T>,
+    {
+        if let Some(value) = self.touch(&mut self.open.lock().unwrap(), path) {
+            return Ok(value);
+        }
+
+        let value = Arc::new(Mutex::new(open(path)?));
+        let mut open = self.open.lock().unwrap();
+        if let Some(value) = self.touch(&mut open, path) {
+            return Ok(value);
+        }
+        if open.len() >= self.capacity {
+            open.remove(0);
+        }
+        open.push((path.to_path_buf(), value.clone()));
+        Ok(value)
+    }
+
+    /// How many values are open.
+    pub fn len(&self) -> usize {
+        self.open.lock().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Moves the value of `path` to the most recently used end.
+    fn touch(
+        &self,
+        open: &mut Vec<(PathBuf, Arc<Mutex<T>>)>,
+        path: &Path,
+    ) -> Option<Arc<Mutex<T>>> {
+        let position = open.iter().position(|(open, _)| open == path)?;
+        let entry = open.remove(position);
+        let value = entry.1.clone();
+        open.push(entry);
+        Some(value)
+    }
+}
+
+impl ArchivePool {
+    /// The archive of the pak at `path`.
+    pub fn archive(&self, path: &Path) -> io::Result<Arc<Mutex<ZipArchive<PakReader>>>> {
+        self.get(path, |path| pak::archive(path))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+
+    use super::*;
+
+    #[test]
+    fn closes_least_recently_used() {
+        let pool = Pool::new(2);
+        let opened = Cell::new(0);
+        let open = |path: &Path| {
+            opened.set(opened.get() + 1);
+            Ok(path.to_string_lossy().into_owned())
+        };
+        let (a, b, c) = (Path::new("a.pak"), Path::new("b.pak"), Path::new("c.pak"));
+
+        assert_eq!(*pool.get(a, open).unwrap().lock().unwrap(), "a.pak");
+        pool.get(b, open).unwrap();
+        pool.get(a, open).unwrap();
+        assert_eq!(opened.get(), 2);
+
+        // b is the least recently used
+        pool.get(c, open).unwrap();
+        assert_eq!(pool.len(), 2);
+        pool.get(a, open).unwrap();
+        assert_eq!(opened.get(), 3);
+        pool.get(b, open).unwrap();
+        assert_eq!(opened.get(), 4);
+
+        let missing = pool.get(Path::new("missing.pak"), |_| {
+            Err::<String, _>(io::Error::from(io::ErrorKind::NotFound))
+        });
+        assert!(missing.is_err());
+        assert_eq!(pool.len(), 2);
+    }
+}
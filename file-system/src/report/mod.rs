@@ -0,0 +1,133 @@
+//! Totals of an extraction per pak, collected while entries are written.
+
+use std::{
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use dashmap::DashMap;
+use serde::Serialize;
+
+/// Name of the per pak statistics `extract --pak-stats` writes into the output directory.
+pub const FILE_NAME: &str = "pak-stats.json";
+
+/// Running totals of every pak entries were extracted from.
+#[derive(Debug, Default)]
+pub struct Report {
+    paks: DashMap<PathBuf, Totals>,
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+struct Totals {
+    entries: u64,
+    bytes_in: u64,
+    bytes_out: u64,
+    errors: u64,
+    busy: Duration,
+}
+
+/// What an extraction did with the entries of one pak.
+#[derive(Debug, Serialize)]
+pub struct PakReport {
+    pub pak: PathBuf,
+    /// Entries written, converted or not.
+    pub entries: u64,
+    pub errors: u64,
+    /// Compressed bytes read from the pak.
+    pub bytes_in: u64,
+    pub bytes_out: u64,
+    /// Bytes written per byte read.
+    pub ratio: f64,
+    /// Time spent reading, converting and writing entries, summed over every thread.
+    pub seconds: f64,
+    /// Bytes written per second of `seconds`, how fast the entries of the pak decode on a single
+    /// thread.
+    pub bytes_per_sec: f64,
+}
+
+impl Report {
+    /// Records an entry of `pak` read from `bytes_in` compressed bytes into `bytes_out`, which
+    /// took `busy`.
+    pub fn entry(&self, pak: &Path, bytes_in: u64, bytes_out: u64, busy: Duration) {
+        let mut totals = self.paks.entry(pak.to_path_buf()).or_default();
+        totals.entries += 1;
+        totals.bytes_in += bytes_in;
+        totals.bytes_out += bytes_out;
+        totals.busy += busy;
+    }
+
+    /// Records an entry of `pak` that failed.
+    pub fn error(&self, pak: &Path) {
+        self.paks.entry(pak.to_path_buf()).or_default().errors += 1;
+    }
+
+    /// Entries that failed across every pak.
+    pub fn errors(&self) -> u64 {
+        self.paks.iter().map(|entry| entry.errors).sum()
+    }
+
+    /// The totals of every pak, in natural order of their names.
+    pub fn paks(&self) -> Vec<PakReport> {
+        let mut paks = self
+            .paks
+            .iter()
+            .map(|entry| {
+                let totals = entry.value();
+                let seconds = totals.busy.as_secs_f64();
+                PakReport {
+                    pak: entry.key().to_owned(),
+                    entries: totals.entries,
+                    errors: totals.errors,
+                    bytes_in: totals.bytes_in,
+                    bytes_out: totals.bytes_out,
+                    ratio: match totals.bytes_in {
+                        0 => 0.0,
+                        bytes_in => totals.bytes_out as f64 / bytes_in as f64,
+                    },
+                    seconds,
+                    bytes_per_sec: match seconds > 0.0 {
+                        true => totals.bytes_out as f64 / seconds,
+                        false => 0.0,
+                    },
+                }
+            })
+            .collect::<Vec<_>>();
+        paks.sort_by(|a, b| {
+            natord::compare(
+                &a.pak.file_stem().unwrap_or_default().to_string_lossy(),
+                &b.pak.file_stem().unwrap_or_default().to_string_lossy(),
+            )
+        });
+        paks
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn totals_per_pak() {
+        let report = Report::default();
+        let (pak10, pak2) = (
+            Path::new("assets/DataStrm-part10.pak"),
+            Path::new("assets/DataStrm-part2.pak"),
+        );
+        report.entry(pak10, 100, 400, Duration::from_secs(2));
+        report.entry(pak10, 100, 200, Duration::from_secs(1));
+        report.error(pak10);
+        report.entry(pak2, 50, 50, Duration::ZERO);
+
+        let paks = report.paks();
+        assert_eq!(report.errors(), 1);
+        assert_eq!(paks.len(), 2);
+        assert_eq!(paks[0].pak, pak2);
+        assert_eq!(paks[0].bytes_per_sec, 0.0);
+
+        let pak = &paks[1];
+        assert_eq!((pak.entries, pak.errors), (2, 1));
+        assert_eq!((pak.bytes_in, pak.bytes_out), (200, 600));
+        assert_eq!(pak.ratio, 3.0);
+        assert_eq!(pak.bytes_per_sec, 200.0);
+    }
+}
@@ -0,0 +1,74 @@
+use std::{io, thread, time::Duration};
+
+/// Attempts made before an error is returned.
+const ATTEMPTS: u32 = 5;
+/// Wait before the first retry, doubled after every attempt.
+const BACKOFF: Duration = Duration::from_millis(100);
+
+/// Windows errors raised while another process, usually an antivirus scanning a file that was just
+/// written, holds it open.
+#[cfg(windows)]
+const LOCKED: [i32; 3] = [
+    5,  // ERROR_ACCESS_DENIED
+    32, // ERROR_SHARING_VIOLATION
+    33, // ERROR_LOCK_VIOLATION
+];
+
+/// Runs `f` until it succeeds, fails with an error that isn't transient or runs out of attempts,
+/// sleeping between attempts.
+pub fn retry<T, F>(mut f: F) -> io::Result<T>
+where
+    F: FnMut() -> io::Result<T>,
+{
+    let mut backoff = BACKOFF;
+    let mut attempt = 1;
+    loop {
+        match f() {
+            Err(e) if attempt < ATTEMPTS && is_transient(&e) => {
+                tracing::warn!("{e}, retrying in {}ms", backoff.as_millis());
+                thread::sleep(backoff);
+                backoff *= 2;
+                attempt += 1;
+            }
+            result => return result,
+        }
+    }
+}
+
+/// Whether `e` is likely gone after a short wait.
+pub fn is_transient(e: &io::Error) -> bool {
+    #[cfg(windows)]
+    if e.raw_os_error().is_some_and(|code| LOCKED.contains(&code)) {
+        return true;
+    }
+    matches!(
+        e.kind(),
+        io::ErrorKind::Interrupted | io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn retries_only_transient_errors() {
+        let mut calls = 0;
+        let result = retry(|| {
+            calls += 1;
+            match calls {
+                1 => Err(io::Error::from(io::ErrorKind::Interrupted)),
+                _ => Ok(calls),
+            }
+        });
+        assert_eq!(result.unwrap(), 2);
+
+        calls = 0;
+        let result: io::Result<()> = retry(|| {
+            calls += 1;
+            Err(io::Error::from(io::ErrorKind::NotFound))
+        });
+        assert!(result.is_err());
+        assert_eq!(calls, 1);
+    }
+}
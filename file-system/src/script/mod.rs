@@ -0,0 +1,209 @@
+//! `extract --script`: a Rhai script whose `transform` function sees every converted entry before
+//! it's written and can rename, rewrite or skip it.
+//!
+//! `transform` is called with a map of
+//! - `entry`, the path of the entry in the paks, e.g. `sharedassets/springboardentitites/...`
+//! - `kind`, its content type as given to `--type`, e.g. `datasheet`
+//! - `format`, the format it was converted to, e.g. `pretty`
+//! - `path`, where it's written relative to the output directory
+//! - `content`, the converted entry: parsed JSON for `.json` and `.geojson` outputs, a string for
+//!   other UTF-8 text and a blob otherwise
+//!
+//! and returns the map, with `path` or `content` changed to rename or rewrite the output, or `()`
+//! to skip it. Content returned as a map, array or number is written as JSON, with the keys of maps
+//! sorted.
+
+use std::{
+    io,
+    path::{Path, PathBuf},
+};
+
+use rhai::{Dynamic, Engine, Map, Scope, AST};
+use serde_json::Value;
+
+use crate::FileKind;
+
+const FUNCTION: &str = "transform";
+
+/// A compiled transform script.
+pub struct Script {
+    engine: Engine,
+    ast: AST,
+}
+
+impl Script {
+    /// Compiles the script at `path`.
+    pub fn from_file(path: &Path) -> io::Result<Self> {
+        let source = std::fs::read_to_string(path)?;
+        Self::compile(&source)
+            .map_err(|e| io::Error::new(e.kind(), format!("{}: {e}", path.display())))
+    }
+
+    /// Compiles `source`, which must define `transform(entry)`.
+    pub fn compile(source: &str) -> io::Result<Self> {
+        let mut engine = Engine::new();
+        // stdout belongs to the progress bars
+        engine.on_print(|text| tracing::info!("{text}"));
+        engine.on_debug(|text, _, pos| tracing::debug!("{pos}: {text}"));
+
+        let ast = engine
+            .compile(source)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e.to_string()))?;
+        if !ast
+            .iter_functions()
+            .any(|f| f.name == FUNCTION && f.params.len() == 1)
+        {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("the script doesn't define {FUNCTION}(entry)"),
+            ));
+        }
+        Ok(Self { engine, ast })
+    }
+
+    /// Runs `transform` on the output of `entry` converted to `buf` and written to `path`, relative
+    /// to the output directory. Returns the path and contents to write instead, `None` when the
+    /// script skips the output.
+    pub fn transform(
+        &self,
+        entry: &Path,
+        kind: FileKind,
+        format: &str,
+        path: &Path,
+        buf: Vec<u8>,
+    ) -> io::Result<Option<(PathBuf, Vec<u8>)>> {
+        let json = path
+            .extension()
+            .is_some_and(|ext| ext == "json" || ext == "geojson");
+        let content = match json
+            .then(|| serde_json::from_slice::<Value>(&buf).ok())
+            .flatten()
+        {
+            Some(value) => rhai::serde::to_dynamic(value).map_err(error)?,
+            None => match String::from_utf8(buf) {
+                Ok(text) => Dynamic::from(text),
+                Err(e) => Dynamic::from_blob(e.into_bytes()),
+            },
+        };
+
+        let mut map = Map::new();
+        map.insert("entry".into(), slashed(entry).into());
+        map.insert("kind".into(), format!("{kind:?}").to_lowercase().into());
+        map.insert("format".into(), format.into());
+        map.insert("path".into(), slashed(path).into());
+        map.insert("content".into(), content);
+
+        let result: Dynamic = self
+            .engine
+            .call_fn(
+                &mut Scope::new(),
+                &self.ast,
+                FUNCTION,
+                (Dynamic::from_map(map),),
+            )
+            .map_err(error)?;
+        if result.is_unit() {
+            return Ok(None);
+        }
+        let Some(mut map) = result.try_cast::<Map>() else {
+            return Err(io::Error::other(format!(
+                "{FUNCTION} must return the entry or ()"
+            )));
+        };
+
+        let path = match map.remove("path") {
+            Some(path) => PathBuf::from(
+                path.into_string()
+                    .map_err(|t| io::Error::other(format!("path must be a string, not {t}")))?,
+            ),
+            None => path.to_path_buf(),
+        };
+        let content = map.remove("content").unwrap_or_default();
+        let buf = if content.is_blob() {
+            content.into_blob().map_err(io::Error::other)?
+        } else if content.is_string() {
+            content
+                .into_string()
+                .map_err(io::Error::other)?
+                .into_bytes()
+        } else {
+            let value: Value = rhai::serde::from_dynamic(&content).map_err(error)?;
+            match format {
+                "mini" => serde_json::to_vec(&value)?,
+                _ => serde_json::to_vec_pretty(&value)?,
+            }
+        };
+        Ok(Some((path, buf)))
+    }
+}
+
+fn slashed(path: &Path) -> String {
+    path.to_string_lossy().replace('\\', "/")
+}
+
+fn error(e: Box<rhai::EvalAltResult>) -> io::Error {
+    io::Error::other(format!("script: {e}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SCRIPT: &str = r#"
+        fn transform(entry) {
+            if entry.kind == "other" {
+                return ();
+            }
+            if entry.path.ends_with(".json") {
+                entry.content.rows = entry.content.rows.len();
+            } else {
+                entry.content.make_upper();
+            }
+            entry.path = "renamed/" + entry.path;
+            entry
+        }
+    "#;
+
+    #[test]
+    fn renames_rewrites_and_skips() {
+        let script = Script::compile(SCRIPT).unwrap();
+
+        let (path, buf) = script
+            .transform(
+                Path::new("sharedassets/items.datasheet"),
+                FileKind::Datasheet,
+                "mini",
+                Path::new("sharedassets/items.datasheet.json"),
+                br#"{"rows":[1,2,3]}"#.to_vec(),
+            )
+            .unwrap()
+            .unwrap();
+        assert_eq!(path, Path::new("renamed/sharedassets/items.datasheet.json"));
+        assert_eq!(buf, br#"{"rows":3}"#);
+
+        let (_, buf) = script
+            .transform(
+                Path::new("scripts/a.luac"),
+                FileKind::Luac,
+                "lua",
+                Path::new("scripts/a.lua"),
+                b"print(1)".to_vec(),
+            )
+            .unwrap()
+            .unwrap();
+        assert_eq!(buf, b"PRINT(1)");
+
+        let skipped = script
+            .transform(
+                Path::new("a.bin"),
+                FileKind::Other,
+                "bytes",
+                Path::new("a.bin"),
+                vec![0xff],
+            )
+            .unwrap();
+        assert!(skipped.is_none());
+
+        assert!(Script::compile("fn other(entry) { entry }").is_err());
+    }
+}
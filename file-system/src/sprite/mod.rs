@@ -0,0 +1,190 @@
+//! LyShine `.sprite` layouts, which name the cells of a UI texture atlas.
+
+use std::{
+    collections::HashMap,
+    io::{self, Cursor},
+};
+
+use image::{DynamicImage, ImageFormat};
+use serde::Deserialize;
+
+/// A named region of an atlas, in pixels.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Cell {
+    pub name: String,
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+#[derive(Deserialize)]
+struct XmlSprite {
+    #[serde(rename = "SpriteSheet", default)]
+    sheet: Option<XmlSheet>,
+}
+
+#[derive(Deserialize)]
+struct XmlSheet {
+    #[serde(rename = "Cell", default)]
+    cells: Vec<XmlCell>,
+}
+
+#[derive(Deserialize)]
+struct XmlCell {
+    #[serde(rename = "@alias", alias = "@Alias", default)]
+    alias: Option<String>,
+    #[serde(rename = "UVRect")]
+    uv: XmlRect,
+}
+
+/// Corners of a cell as fractions of the atlas size.
+#[derive(Deserialize)]
+struct XmlRect {
+    #[serde(rename = "@left", default)]
+    left: f32,
+    #[serde(rename = "@top", default)]
+    top: f32,
+    #[serde(rename = "@right", default)]
+    right: f32,
+    #[serde(rename = "@bottom", default)]
+    bottom: f32,
+}
+
+/// The cells of a sprite sheet layout for an atlas of `width` by `height` pixels, clamped to the
+/// atlas. Cells without an alias are named by their index, empty ones are left out.
+pub fn cells(buf: &[u8], width: u32, height: u32) -> io::Result<Vec<Cell>> {
+    let text = std::str::from_utf8(buf).map_err(io::Error::other)?;
+    let text = text.trim_start_matches('\u{feff}');
+    let sprite: XmlSprite = quick_xml::de::from_str(text).map_err(io::Error::other)?;
+
+    let pixels = |uv: f32, size: u32| (uv.clamp(0.0, 1.0) * size as f32).round() as u32;
+    Ok(sprite
+        .sheet
+        .map(|sheet| sheet.cells)
+        .unwrap_or_default()
+        .into_iter()
+        .enumerate()
+        .filter_map(|(i, cell)| {
+            let (x, y) = (pixels(cell.uv.left, width), pixels(cell.uv.top, height));
+            let right = pixels(cell.uv.right, width);
+            let bottom = pixels(cell.uv.bottom, height);
+            if right <= x || bottom <= y {
+                return None;
+            }
+            let name = cell
+                .alias
+                .filter(|alias| !alias.trim().is_empty())
+                .unwrap_or_else(|| i.to_string());
+            Some(Cell {
+                name: file_name(&name),
+                x,
+                y,
+                width: right - x,
+                height: bottom - y,
+            })
+        })
+        .collect())
+}
+
+/// Cuts every cell out of `image` as a PNG named after it. Cells sharing an alias get a numbered
+/// suffix so none overwrites another.
+pub fn slice(image: &DynamicImage, cells: &[Cell]) -> io::Result<Vec<(String, Vec<u8>)>> {
+    let mut names = HashMap::new();
+    cells
+        .iter()
+        .map(|cell| {
+            let count = names.entry(cell.name.to_lowercase()).or_insert(0);
+            let name = match *count {
+                0 => format!("{}.png", cell.name),
+                n => format!("{}_{n}.png", cell.name),
+            };
+            *count += 1;
+
+            let mut buf = Cursor::new(vec![]);
+            image
+                .crop_imm(cell.x, cell.y, cell.width, cell.height)
+                .write_to(&mut buf, ImageFormat::Png)
+                .map_err(io::Error::other)?;
+            Ok((name, buf.into_inner()))
+        })
+        .collect()
+}
+
+/// An alias made safe to use as a file name.
+fn file_name(alias: &str) -> String {
+    alias
+        .trim()
+        .chars()
+        .map(|c| match c {
+            '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '_',
+            c => c,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sprite_sheet_cells() {
+        let sprite = br#"<Sprite>
+ <Borders left="0" right="0" top="0" bottom="0"/>
+ <SpriteSheet>
+  <Cell alias="icon_gold">
+   <UVRect left="0" top="0" right="0.5" bottom="0.25"/>
+  </Cell>
+  <Cell>
+   <UVRect left="0.5" top="0" right="1" bottom="0.25"/>
+  </Cell>
+  <Cell alias="empty">
+   <UVRect left="0.5" top="0.5" right="0.5" bottom="1"/>
+  </Cell>
+  <Cell alias="a/b">
+   <UVRect left="0" top="0.25" right="0.5" bottom="0.5"/>
+  </Cell>
+ </SpriteSheet>
+</Sprite>"#;
+
+        let cells = cells(sprite, 64, 128).unwrap();
+        assert_eq!(
+            cells,
+            [
+                Cell {
+                    name: "icon_gold".into(),
+                    x: 0,
+                    y: 0,
+                    width: 32,
+                    height: 32
+                },
+                Cell {
+                    name: "1".into(),
+                    x: 32,
+                    y: 0,
+                    width: 32,
+                    height: 32
+                },
+                Cell {
+                    name: "a_b".into(),
+                    x: 0,
+                    y: 32,
+                    width: 32,
+                    height: 32
+                },
+            ]
+        );
+
+        let image = DynamicImage::new_rgba8(64, 128);
+        let mut cells = cells;
+        cells[1].name = "icon_gold".into();
+        let files = slice(&image, &cells).unwrap();
+        let names = files
+            .iter()
+            .map(|(name, _)| name.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(names, ["icon_gold.png", "icon_gold_1.png", "a_b.png"]);
+        let png = image::load_from_memory(&files[0].1).unwrap();
+        assert_eq!((png.width(), png.height()), (32, 32));
+    }
+}
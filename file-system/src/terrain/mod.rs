@@ -0,0 +1,105 @@
+//! Region rasters, such as heightmaps, stored under `regions/r_+XX_+YY/`.
+
+use std::io::{self, Cursor};
+
+use image::{DynamicImage, ImageBuffer, ImageFormat, Luma};
+use serde::Serialize;
+
+/// Whether `name` is a raster belonging to a region.
+pub fn is_region_raster(name: &str) -> bool {
+    let name = name.replace('\\', "/").to_lowercase();
+    name.contains("regions/r_") && (name.ends_with(".tif") || name.ends_with(".tiff"))
+}
+
+/// Where a raster sits in the world and how its pixels map back to the original samples.
+#[derive(Debug, Serialize)]
+pub struct Tile {
+    /// World position of the region's corner, missing outside a region directory.
+    pub origin: Option<[f32; 2]>,
+    pub size: f32,
+    pub width: u32,
+    pub height: u32,
+    /// A pixel is `min + value / 65535 * (max - min)`.
+    pub min: f32,
+    pub max: f32,
+}
+
+/// Decodes a region raster and stretches its samples over a 16 bit grayscale PNG.
+pub fn to_png(buf: &[u8], name: &str) -> io::Result<(Vec<u8>, Tile)> {
+    let image =
+        image::load_from_memory_with_format(buf, ImageFormat::Tiff).map_err(io::Error::other)?;
+    let (width, height) = (image.width(), image.height());
+    let samples = match &image {
+        DynamicImage::ImageLuma16(luma) => luma.pixels().map(|p| p.0[0] as f32).collect(),
+        image => image.to_luma32f().into_raw(),
+    };
+
+    let (min, max) = range(&samples);
+    let png = stretch(&samples, width, height, min, max)?;
+    let tile = Tile {
+        origin: distribution::region_origin(name),
+        size: distribution::REGION_SIZE,
+        width,
+        height,
+        min,
+        max,
+    };
+    Ok((png, tile))
+}
+
+fn range(samples: &[f32]) -> (f32, f32) {
+    if samples.is_empty() {
+        return (0.0, 0.0);
+    }
+    samples.iter().fold((f32::MAX, f32::MIN), |(min, max), &v| {
+        (min.min(v), max.max(v))
+    })
+}
+
+fn stretch(samples: &[f32], width: u32, height: u32, min: f32, max: f32) -> io::Result<Vec<u8>> {
+    let scale = if max > min {
+        65535.0 / (max - min)
+    } else {
+        0.0
+    };
+    let pixels = samples
+        .iter()
+        .map(|v| ((v - min) * scale).round() as u16)
+        .collect::<Vec<_>>();
+    let image = ImageBuffer::<Luma<u16>, _>::from_raw(width, height, pixels)
+        .ok_or_else(|| io::Error::other("raster is smaller than its dimensions"))?;
+
+    let mut png = Cursor::new(vec![]);
+    image
+        .write_to(&mut png, ImageFormat::Png)
+        .map_err(io::Error::other)?;
+    Ok(png.into_inner())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn region_rasters() {
+        assert!(is_region_raster(
+            "coatlicue/newworld_vitaeeterna/regions/r_+00_+03/region.heightmap.tif"
+        ));
+        assert!(!is_region_raster("textures/rock.tif"));
+        assert!(!is_region_raster(
+            "coatlicue/newworld_vitaeeterna/regions/r_+00_+03/region.distribution"
+        ));
+    }
+
+    #[test]
+    fn stretches_samples() {
+        let samples = [10.0, 20.0, 15.0, 10.0];
+        let (min, max) = range(&samples);
+        assert_eq!((min, max), (10.0, 20.0));
+
+        let png = stretch(&samples, 2, 2, min, max).unwrap();
+        let decoded = image::load_from_memory(&png).unwrap().into_luma16();
+        assert_eq!(decoded.dimensions(), (2, 2));
+        assert_eq!(decoded.into_raw(), vec![0, 65535, 32768, 0]);
+    }
+}
@@ -0,0 +1,263 @@
+use std::{
+    sync::{Condvar, Mutex},
+    time::{Duration, Instant},
+};
+
+/// How often the limit of an adaptive [`Throttle`] is re-evaluated.
+const WINDOW: Duration = Duration::from_secs(1);
+
+/// Caps how many entries are converted at once. An adaptive throttle raises its limit while
+/// throughput keeps improving and lowers it once it drops, e.g. when the disk saturates.
+#[derive(Debug)]
+pub struct Throttle {
+    max: usize,
+    adaptive: bool,
+    state: Mutex<ThrottleState>,
+    available: Condvar,
+}
+
+#[derive(Debug)]
+struct ThrottleState {
+    limit: usize,
+    active: usize,
+    /// Whether the last adjustment raised the limit.
+    raised: bool,
+    started: Instant,
+    bytes: u64,
+    throughput: f64,
+}
+
+/// Held while an entry is converted, frees its slot when dropped.
+pub struct Permit<'a>(&'a Throttle);
+
+impl Throttle {
+    /// A throttle that always allows `jobs` entries at once.
+    pub fn fixed(jobs: usize) -> Self {
+        Self::new(jobs.max(1), jobs.max(1), false)
+    }
+
+    /// A throttle starting at half of `max` and adjusting between 1 and `max`.
+    pub fn adaptive(max: usize) -> Self {
+        Self::new(max.max(1), (max / 2).max(1), true)
+    }
+
+    fn new(max: usize, limit: usize, adaptive: bool) -> Self {
+        Self {
+            max,
+            adaptive,
+            state: Mutex::new(ThrottleState {
+                limit,
+                active: 0,
+                raised: true,
+                started: Instant::now(),
+                bytes: 0,
+                throughput: 0.0,
+            }),
+            available: Condvar::new(),
+        }
+    }
+
+    pub fn limit(&self) -> usize {
+        self.state.lock().unwrap().limit
+    }
+
+    /// Blocks until fewer than `limit` entries are being converted.
+    pub fn acquire(&self) -> Permit<'_> {
+        let mut state = self.state.lock().unwrap();
+        while state.active >= state.limit {
+            state = self.available.wait(state).unwrap();
+        }
+        state.active += 1;
+        Permit(self)
+    }
+
+    /// Counts bytes written towards the throughput of the current window.
+    pub fn record(&self, bytes: u64) {
+        if !self.adaptive {
+            return;
+        }
+        let mut state = self.state.lock().unwrap();
+        state.bytes += bytes;
+        let elapsed = state.started.elapsed();
+        if elapsed < WINDOW {
+            return;
+        }
+
+        let throughput = state.bytes as f64 / elapsed.as_secs_f64();
+        // keep going in the same direction while it helps, turn around once it stops helping
+        let improved = throughput >= state.throughput;
+        let raise = state.raised == improved;
+        state.limit = if raise {
+            (state.limit + 1).min(self.max)
+        } else {
+            state.limit.saturating_sub(1).max(1)
+        };
+        state.raised = raise;
+        state.throughput = throughput;
+        state.bytes = 0;
+        state.started = Instant::now();
+        self.available.notify_all();
+    }
+}
+
+/// Keeps the average bytes per second written since the start under a limit, by making workers
+/// that pushed it over wait.
+#[derive(Debug)]
+pub struct RateLimit {
+    bytes_per_sec: u64,
+    /// When counting started and the bytes counted since.
+    state: Mutex<(Instant, u64)>,
+}
+
+impl RateLimit {
+    pub fn new(bytes_per_sec: u64) -> Self {
+        Self {
+            bytes_per_sec: bytes_per_sec.max(1),
+            state: Mutex::new((Instant::now(), 0)),
+        }
+    }
+
+    /// Counts `bytes` and sleeps until the average is back under the limit.
+    pub fn consume(&self, bytes: u64) {
+        let wait = self.wait(bytes);
+        if !wait.is_zero() {
+            std::thread::sleep(wait);
+        }
+    }
+
+    fn wait(&self, bytes: u64) -> Duration {
+        let mut state = self.state.lock().unwrap();
+        state.1 += bytes;
+        let due = Duration::from_secs_f64(state.1 as f64 / self.bytes_per_sec as f64);
+        due.saturating_sub(state.0.elapsed())
+    }
+}
+
+/// Caps the bytes of entries held in memory at once, by making workers wait before reading an
+/// entry until the ones in flight leave room for it.
+#[derive(Debug)]
+pub struct MemoryBudget {
+    bytes: u64,
+    in_flight: Mutex<u64>,
+    freed: Condvar,
+}
+
+/// Bytes of a [`MemoryBudget`] held while an entry is in memory, given back when dropped.
+pub struct Reservation<'a> {
+    budget: &'a MemoryBudget,
+    bytes: u64,
+}
+
+impl MemoryBudget {
+    pub fn new(bytes: u64) -> Self {
+        Self {
+            bytes,
+            in_flight: Mutex::new(0),
+            freed: Condvar::new(),
+        }
+    }
+
+    /// Blocks until `bytes` fit into the budget. An entry larger than the budget is let through
+    /// once nothing else is in flight, so it can't wait forever.
+    pub fn reserve(&self, bytes: u64) -> Reservation<'_> {
+        let mut in_flight = self.in_flight.lock().unwrap();
+        while *in_flight > 0 && in_flight.saturating_add(bytes) > self.bytes {
+            in_flight = self.freed.wait(in_flight).unwrap();
+        }
+        *in_flight += bytes;
+        Reservation {
+            budget: self,
+            bytes,
+        }
+    }
+
+    /// Bytes reserved by the entries in flight.
+    pub fn in_flight(&self) -> u64 {
+        *self.in_flight.lock().unwrap()
+    }
+}
+
+impl Drop for Reservation<'_> {
+    fn drop(&mut self) {
+        *self.budget.in_flight.lock().unwrap() -= self.bytes;
+        self.budget.freed.notify_all();
+    }
+}
+
+impl Drop for Permit<'_> {
+    fn drop(&mut self) {
+        self.0.state.lock().unwrap().active -= 1;
+        self.0.available.notify_one();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn adaptive_limit_follows_throughput() {
+        let throttle = Throttle::adaptive(8);
+        assert_eq!(throttle.limit(), 4);
+
+        let window = |throttle: &Throttle, throughput: f64| {
+            let mut state = throttle.state.lock().unwrap();
+            state.started = Instant::now() - WINDOW;
+            state.bytes = 0;
+            drop(state);
+            throttle.record((throughput * WINDOW.as_secs_f64()) as u64);
+        };
+
+        window(&throttle, 100.0);
+        assert_eq!(throttle.limit(), 5);
+        window(&throttle, 200.0);
+        assert_eq!(throttle.limit(), 6);
+        // throughput dropped, back off
+        window(&throttle, 150.0);
+        assert_eq!(throttle.limit(), 5);
+        // backing off helped, keep backing off
+        window(&throttle, 180.0);
+        assert_eq!(throttle.limit(), 4);
+    }
+
+    #[test]
+    fn rate_limit_waits_for_the_average() {
+        let limit = RateLimit::new(1000);
+        assert!(limit.wait(0).is_zero());
+        let wait = limit.wait(2000);
+        assert!(wait > Duration::from_millis(1900) && wait <= Duration::from_secs(2));
+    }
+
+    #[test]
+    fn memory_budget_waits_for_room() {
+        let budget = MemoryBudget::new(100);
+        let first = budget.reserve(60);
+        assert_eq!(budget.in_flight(), 60);
+
+        std::thread::scope(|scope| {
+            let waiting = scope.spawn(|| {
+                let _second = budget.reserve(60);
+                budget.in_flight()
+            });
+            std::thread::sleep(Duration::from_millis(50));
+            assert!(!waiting.is_finished());
+            drop(first);
+            assert_eq!(waiting.join().unwrap(), 60);
+        });
+        assert_eq!(budget.in_flight(), 0);
+
+        // too large for the budget, but nothing else is in flight
+        let _large = budget.reserve(500);
+        assert_eq!(budget.in_flight(), 500);
+    }
+
+    #[test]
+    fn fixed_limit_never_changes() {
+        let throttle = Throttle::fixed(3);
+        let permits = (0..3).map(|_| throttle.acquire()).collect::<Vec<_>>();
+        throttle.record(u64::MAX / 2);
+        assert_eq!(throttle.limit(), 3);
+        drop(permits);
+        assert_eq!(throttle.state.lock().unwrap().active, 0);
+    }
+}
@@ -0,0 +1,123 @@
+use serde::Serialize;
+use std::{
+    collections::BTreeMap,
+    io::{self, Write},
+    path::Path,
+};
+use utils::format_bytes;
+
+/// A directory or entry of the virtual file system, with the number and uncompressed size of the
+/// entries under it.
+#[derive(Debug, Default, Serialize)]
+pub struct Tree {
+    pub entries: usize,
+    pub size: u64,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub children: BTreeMap<String, Tree>,
+}
+
+impl Tree {
+    /// Adds the entry at `path`, counting it in every directory above it.
+    pub fn insert(&mut self, path: &Path, size: u64) {
+        let mut node = self;
+        node.entries += 1;
+        node.size += size;
+        for component in path.iter() {
+            node = node
+                .children
+                .entry(component.to_string_lossy().into_owned())
+                .or_default();
+            node.entries += 1;
+            node.size += size;
+        }
+    }
+
+    /// Whether this is a directory, entries have no children.
+    pub fn is_dir(&self) -> bool {
+        !self.children.is_empty()
+    }
+
+    /// Writes the tree with box drawing lines, directories first, down to `depth` levels below
+    /// the root when given. Directories show their entry count and size, entries their size.
+    pub fn render<W: Write>(&self, depth: Option<usize>, writer: &mut W) -> io::Result<()> {
+        writeln!(
+            writer,
+            ". ({} entries, {})",
+            self.entries,
+            format_bytes(self.size as f64)
+        )?;
+        self.render_children("", 1, depth, writer)
+    }
+
+    fn render_children<W: Write>(
+        &self,
+        prefix: &str,
+        level: usize,
+        depth: Option<usize>,
+        writer: &mut W,
+    ) -> io::Result<()> {
+        if depth.is_some_and(|depth| level > depth) {
+            return Ok(());
+        }
+
+        let mut children = self.children.iter().collect::<Vec<_>>();
+        children.sort_by_key(|(_, node)| !node.is_dir());
+        for (i, (name, node)) in children.iter().enumerate() {
+            let last = i + 1 == children.len();
+            let branch = if last { "└── " } else { "├── " };
+            if node.is_dir() {
+                writeln!(
+                    writer,
+                    "{prefix}{branch}{name}/ ({} entries, {})",
+                    node.entries,
+                    format_bytes(node.size as f64)
+                )?;
+                let prefix = format!("{prefix}{}", if last { "    " } else { "│   " });
+                node.render_children(&prefix, level + 1, depth, writer)?;
+            } else {
+                writeln!(
+                    writer,
+                    "{prefix}{branch}{name} ({})",
+                    format_bytes(node.size as f64)
+                )?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counts_entries_in_every_directory() {
+        let mut tree = Tree::default();
+        tree.insert(Path::new("sharedassets/datatables/a.datasheet"), 10);
+        tree.insert(Path::new("sharedassets/datatables/b.datasheet"), 20);
+        tree.insert(Path::new("sharedassets/icon.dds"), 5);
+        tree.insert(Path::new("levels/newworld/region.json"), 1);
+
+        assert_eq!((tree.entries, tree.size), (4, 36));
+        let shared = &tree.children["sharedassets"];
+        assert_eq!((shared.entries, shared.size), (3, 35));
+        assert!(!shared.children["icon.dds"].is_dir());
+
+        let mut out = vec![];
+        tree.render(Some(1), &mut out).unwrap();
+        let out = String::from_utf8(out).unwrap();
+        assert_eq!(
+            out,
+            ". (4 entries, 36.00 B)\n\
+             ├── levels/ (1 entries, 1.00 B)\n\
+             └── sharedassets/ (3 entries, 35.00 B)\n"
+        );
+
+        let mut out = vec![];
+        tree.render(Some(2), &mut out).unwrap();
+        let out = String::from_utf8(out).unwrap();
+        assert!(
+            out.contains("    ├── datatables/ (2 entries, 30.00 B)\n    └── icon.dds (5.00 B)\n")
+        );
+    }
+}
@@ -0,0 +1,160 @@
+use crate::{
+    incremental::{ExtractState, FILE_NAME, JOURNAL_NAME},
+    manifest, ERRORS_FILE_NAME, LOCALE_TABLES_DIR,
+};
+use rayon::prelude::*;
+use serde::Serialize;
+use std::{
+    collections::HashSet,
+    ffi::OsStr,
+    io,
+    path::{Path, PathBuf},
+};
+use walkdir::WalkDir;
+
+/// Outputs of an extraction checked against the entries it should have written.
+#[derive(Debug, Default, Serialize)]
+pub struct Verification {
+    /// Expected entries with no output, or with outputs that are gone.
+    pub missing: Vec<PathBuf>,
+    /// Files in the output directory that no extracted entry wrote.
+    pub orphaned: Vec<PathBuf>,
+}
+
+impl Verification {
+    /// Checks `expected` entries against the outputs recorded for `out_dir`, and every file there
+    /// against the recorded outputs. Needs the state loose file extractions save.
+    pub fn of<'a, I>(out_dir: &Path, expected: I) -> io::Result<Self>
+    where
+        I: IntoIterator<Item = &'a PathBuf>,
+    {
+        let state = ExtractState::read(out_dir).map_err(|e| {
+            io::Error::new(
+                e.kind(),
+                format!("no extraction state in {}: {e}", out_dir.display()),
+            )
+        })?;
+
+        let mut missing = expected
+            .into_iter()
+            .filter(|entry| {
+                !state.outputs(entry).is_some_and(|outputs| {
+                    !outputs.is_empty() && outputs.iter().all(|path| path.exists())
+                })
+            })
+            .cloned()
+            .collect::<Vec<_>>();
+
+        let outputs = state.all_outputs().collect::<HashSet<_>>();
+        // `with-meta` schemas are written beside their datasheet without being recorded
+        let stems = outputs
+            .iter()
+            .map(|path| stem(path))
+            .collect::<HashSet<_>>();
+        let mut orphaned = WalkDir::new(out_dir)
+            .into_iter()
+            .filter_map(|e| e.ok())
+            .filter(|e| e.file_type().is_file())
+            .map(|e| e.into_path())
+            .filter(|path| {
+                let name = path.file_name().unwrap_or_default();
+                name != OsStr::new(FILE_NAME)
+                    && name != OsStr::new(JOURNAL_NAME)
+                    && name != OsStr::new(manifest::FILE_NAME)
+                    && name != OsStr::new(ERRORS_FILE_NAME)
+                    && !outputs.contains(path)
+                    && !path.starts_with(out_dir.join(LOCALE_TABLES_DIR))
+                    && !(name.to_string_lossy().ends_with(".meta.json")
+                        && stems.contains(&stem(path)))
+            })
+            .collect::<Vec<_>>();
+
+        missing.par_sort_unstable();
+        orphaned.par_sort_unstable();
+        Ok(Self { missing, orphaned })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.missing.is_empty() && self.orphaned.is_empty()
+    }
+}
+
+/// `path` up to the first `.` of its file name.
+fn stem(path: &Path) -> PathBuf {
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    let stem = name.split('.').next().unwrap_or_default();
+    path.with_file_name(stem)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pak::EntryInfo;
+
+    #[test]
+    fn reports_missing_and_orphaned_outputs() {
+        let dir = std::env::temp_dir().join("nwtools-verify-test");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        for name in ["a.datasheet.json", "a.meta.json", "stray.txt"] {
+            std::fs::write(dir.join(name), b"{}").unwrap();
+        }
+
+        let info = EntryInfo { crc32: 1, size: 2 };
+        let mut state = ExtractState::load(&dir, String::new())
+            .unwrap()
+            .into_inner()
+            .unwrap();
+        state.insert(
+            "a.datasheet".into(),
+            info,
+            vec![dir.join("a.datasheet.json")],
+        );
+        state.insert(
+            "b.datasheet".into(),
+            info,
+            vec![dir.join("b.datasheet.json")],
+        );
+        state.save(&dir).unwrap();
+
+        let expected = ["a.datasheet", "b.datasheet", "c.datasheet"].map(PathBuf::from);
+        let verification = Verification::of(&dir, &expected).unwrap();
+        assert_eq!(
+            verification.missing,
+            ["b.datasheet", "c.datasheet"].map(PathBuf::from)
+        );
+        assert_eq!(verification.orphaned, [dir.join("stray.txt")]);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn verifies_the_entries_a_since_run_extracted() {
+        let dir = std::env::temp_dir().join("nwtools-verify-since-test");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("b.datasheet.json"), b"{}").unwrap();
+
+        // a `--since` run only writes, and records, the entries that changed
+        let mut state = ExtractState::load(&dir, String::new())
+            .unwrap()
+            .into_inner()
+            .unwrap();
+        state.insert(
+            "b.datasheet".into(),
+            EntryInfo { crc32: 1, size: 2 },
+            vec![dir.join("b.datasheet.json")],
+        );
+        state.save(&dir).unwrap();
+
+        let changed = [PathBuf::from("b.datasheet")];
+        assert!(Verification::of(&dir, &changed).unwrap().is_empty());
+        let selected = ["a.datasheet", "b.datasheet"].map(PathBuf::from);
+        assert_eq!(
+            Verification::of(&dir, &selected).unwrap().missing,
+            [PathBuf::from("a.datasheet")]
+        );
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
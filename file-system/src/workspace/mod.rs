@@ -0,0 +1,189 @@
+use crate::{diff::Diff, manifest::Manifest};
+use pelite::pe64::{Pe, PeFile};
+use pelite::FileMap;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::{self, BufReader, BufWriter, Write},
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+/// File describing the version extracted into a directory of a workspace.
+pub const FILE_NAME: &str = "version.json";
+
+/// A game build extracted into its own directory of a workspace.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Version {
+    /// Name of the directory, the game build unless labelled otherwise.
+    pub label: String,
+    /// Product version of `Bin64/NewWorld.exe`, when it could be read.
+    pub build: Option<String>,
+    /// Seconds since the Unix epoch when the version was first extracted.
+    pub created: u64,
+    #[serde(skip)]
+    pub path: PathBuf,
+}
+
+/// Product version of the game executable, e.g. `2.4.0.12345`.
+pub fn build<P>(game_dir: P) -> io::Result<String>
+where
+    P: AsRef<Path>,
+{
+    let file_map = FileMap::open(&game_dir.as_ref().join("Bin64/NewWorld.exe"))?;
+    let pe = PeFile::from_bytes(&file_map).map_err(io::Error::other)?;
+    let resources = pe.resources().map_err(io::Error::other)?;
+    let version = resources.version_info().map_err(io::Error::other)?;
+    let fixed = version
+        .fixed()
+        .ok_or_else(|| io::Error::other("NewWorld.exe has no fixed version info"))?;
+    Ok(fixed.dwProductVersion.to_string())
+}
+
+/// Directory of the version of `game_dir` in the workspace at `root`, labelled `label` or the
+/// game build, created along with its [`FILE_NAME`] when it doesn't exist yet.
+pub fn start<P, G>(root: P, game_dir: G, label: Option<&str>) -> io::Result<PathBuf>
+where
+    P: AsRef<Path>,
+    G: AsRef<Path>,
+{
+    let build = build(&game_dir).ok();
+    let label = match (label, &build) {
+        (Some(label), _) => label.to_string(),
+        (None, Some(build)) => build.to_owned(),
+        (None, None) => {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "Couldn't read the game build, give the version a --label",
+            ))
+        }
+    };
+
+    let dir = root.as_ref().join(&label);
+    if !dir.join(FILE_NAME).exists() {
+        std::fs::create_dir_all(&dir)?;
+        let version = Version {
+            label,
+            build,
+            created: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |since| since.as_secs()),
+            path: dir.to_owned(),
+        };
+        let mut writer = BufWriter::new(File::create(dir.join(FILE_NAME))?);
+        serde_json::to_writer_pretty(&mut writer, &version)?;
+        writer.flush()?;
+    }
+    Ok(dir)
+}
+
+/// Every version in the workspace at `root`, oldest first.
+pub fn versions<P>(root: P) -> io::Result<Vec<Version>>
+where
+    P: AsRef<Path>,
+{
+    let mut versions = vec![];
+    for dir in std::fs::read_dir(root)? {
+        let path = dir?.path();
+        let Ok(file) = File::open(path.join(FILE_NAME)) else {
+            continue;
+        };
+        let mut version: Version = serde_json::from_reader(BufReader::new(file))?;
+        version.path = path;
+        versions.push(version);
+    }
+    versions.sort_by(|a, b| a.created.cmp(&b.created).then(a.label.cmp(&b.label)));
+    Ok(versions)
+}
+
+/// Deletes all but the `keep` newest versions, returning the deleted ones. Nothing is deleted
+/// when `dry_run` is set.
+pub fn prune<P>(root: P, keep: usize, dry_run: bool) -> io::Result<Vec<Version>>
+where
+    P: AsRef<Path>,
+{
+    let mut versions = versions(root)?;
+    let pruned = versions.len().saturating_sub(keep);
+    let pruned = versions.drain(..pruned).collect::<Vec<_>>();
+    if !dry_run {
+        for version in &pruned {
+            std::fs::remove_dir_all(&version.path)?;
+        }
+    }
+    Ok(pruned)
+}
+
+/// Compares the manifests of the `old` and `new` versions of the workspace at `root`.
+pub fn diff<P>(root: P, old: &str, new: &str) -> io::Result<Diff>
+where
+    P: AsRef<Path>,
+{
+    let entries = |label: &str| -> io::Result<HashMap<_, _>> {
+        let dir = root.as_ref().join(label);
+        if !dir.join(FILE_NAME).exists() {
+            return Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("No version {label} in {}", root.as_ref().display()),
+            ));
+        }
+        Ok(Manifest::load(&dir)
+            .iter()
+            .map(|entry| (entry.path.to_owned(), entry.info))
+            .collect())
+    };
+    Ok(Diff::between(&entries(old)?, &entries(new)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{manifest::ManifestEntry, pak::EntryInfo};
+
+    #[test]
+    fn lists_prunes_and_diffs_versions() {
+        let root = std::env::temp_dir().join("nwtools-workspace-test");
+        let _ = std::fs::remove_dir_all(&root);
+
+        for (i, label) in ["1.0", "1.1", "1.2"].into_iter().enumerate() {
+            let dir = start(&root, &root, Some(label)).unwrap();
+            let mut version: Version =
+                serde_json::from_slice(&std::fs::read(dir.join(FILE_NAME)).unwrap()).unwrap();
+            version.created = i as u64;
+            std::fs::write(dir.join(FILE_NAME), serde_json::to_vec(&version).unwrap()).unwrap();
+
+            let mut manifest = Manifest::load(&dir);
+            manifest.insert(ManifestEntry {
+                path: "a.datasheet".into(),
+                pak: "assets/DataSheets.pak".into(),
+                info: EntryInfo {
+                    crc32: i as u32,
+                    size: 2,
+                },
+                format: "bytes".to_string(),
+                outputs: vec!["a.datasheet".into()],
+            });
+            manifest.save(&dir).unwrap();
+        }
+        std::fs::create_dir_all(root.join("not-a-version")).unwrap();
+
+        let labels = |versions: Vec<Version>| {
+            versions
+                .into_iter()
+                .map(|version| version.label)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(labels(versions(&root).unwrap()), ["1.0", "1.1", "1.2"]);
+
+        let report = diff(&root, "1.0", "1.2").unwrap();
+        assert_eq!(report.changed.len(), 1);
+        assert!(diff(&root, "1.0", "0.9").is_err());
+
+        assert_eq!(labels(prune(&root, 2, true).unwrap()), ["1.0"]);
+        assert!(root.join("1.0").exists());
+        assert_eq!(labels(prune(&root, 2, false).unwrap()), ["1.0"]);
+        assert_eq!(labels(versions(&root).unwrap()), ["1.1", "1.2"]);
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -0,0 +1,95 @@
+use std::{
+    collections::BTreeMap,
+    io::{self, Write},
+};
+
+use dashmap::DashMap;
+
+fn sorted(map: &DashMap<String, Option<String>>) -> BTreeMap<String, Option<String>> {
+    map.iter()
+        .map(|entry| (entry.key().to_owned(), entry.value().to_owned()))
+        .collect()
+}
+
+/// Writes the string table as a JSON object sorted by key.
+pub fn to_json<W: Write>(map: &DashMap<String, Option<String>>, writer: &mut W) -> io::Result<()> {
+    serde_json::to_writer_pretty(writer, &sorted(map)).map_err(io::Error::other)
+}
+
+/// Writes the string table as `key,value` CSV rows sorted by key.
+pub fn to_csv<W: Write>(map: &DashMap<String, Option<String>>, writer: &mut W) -> io::Result<()> {
+    let quote = |field: &str| {
+        if field.contains([',', '"', '\n', '\r']) {
+            format!("\"{}\"", field.replace('"', "\"\""))
+        } else {
+            field.to_string()
+        }
+    };
+
+    writeln!(writer, "key,value")?;
+    for (key, value) in sorted(map) {
+        writeln!(
+            writer,
+            "{},{}",
+            quote(&key),
+            quote(value.as_deref().unwrap_or_default())
+        )?;
+    }
+    Ok(())
+}
+
+/// Writes the string table as a `key`/`text` SQL table named `table`, sorted by key.
+pub fn to_sql<W: Write>(
+    map: &DashMap<String, Option<String>>,
+    table: &str,
+    writer: &mut W,
+) -> io::Result<()> {
+    let quote = |text: &str| format!("'{}'", text.replace('\'', "''"));
+
+    writeln!(
+        writer,
+        "CREATE TABLE '{table}'(\n\t'key' TEXT PRIMARY KEY,\n\t'text' TEXT\n);\n"
+    )?;
+    let rows = sorted(map);
+    if rows.is_empty() {
+        return Ok(());
+    }
+    writeln!(writer, "INSERT INTO '{table}' ('key','text') VALUES")?;
+    let last = rows.len() - 1;
+    for (i, (key, value)) in rows.into_iter().enumerate() {
+        let value = value.as_deref().map_or("NULL".to_string(), quote);
+        let end = if i == last { ";" } else { "," };
+        writeln!(writer, "\t({},{}){}", quote(&key), value, end)?;
+    }
+    Ok(())
+}
+
+/// Writes the string table as a gettext PO file using the keys as message ids.
+pub fn to_po<W: Write>(
+    map: &DashMap<String, Option<String>>,
+    locale: &str,
+    writer: &mut W,
+) -> io::Result<()> {
+    let escape = |text: &str| {
+        text.replace('\\', "\\\\")
+            .replace('"', "\\\"")
+            .replace('\n', "\\n")
+            .replace('\r', "\\r")
+            .replace('\t', "\\t")
+    };
+
+    writeln!(writer, "msgid \"\"")?;
+    writeln!(writer, "msgstr \"\"")?;
+    writeln!(writer, "\"Language: {}\\n\"", locale)?;
+    writeln!(writer, "\"Content-Type: text/plain; charset=UTF-8\\n\"")?;
+    for (key, value) in sorted(map) {
+        writeln!(writer)?;
+        writeln!(writer, "msgid \"{}\"", escape(&key))?;
+        writeln!(
+            writer,
+            "msgstr \"{}\"",
+            escape(value.as_deref().unwrap_or_default())
+        )?;
+    }
+    Ok(())
+}
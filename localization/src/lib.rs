@@ -1,3 +1,6 @@
+pub mod export;
+pub mod search;
+
 use std::{
     collections::HashMap,
     io::{BufReader, Read},
@@ -39,6 +42,28 @@ pub struct KeyValue {
     value: Option<String>,
 }
 
+/// A key as the string tables are keyed by, lowercase and without the `@` datasheets prefix
+/// keys with.
+pub fn normalize_key(key: &str) -> String {
+    key.trim_start_matches('@').to_lowercase()
+}
+
+/// Keys of the strings of `map` containing `text`, or whose key does when `keys` is set,
+/// ignoring case.
+pub fn find(map: &DashMap<String, Option<String>>, text: &str, keys: bool) -> Vec<String> {
+    let text = text.to_lowercase();
+    map.iter()
+        .filter(|entry| {
+            (keys && entry.key().contains(&text))
+                || entry
+                    .value()
+                    .as_ref()
+                    .is_some_and(|value| value.to_lowercase().contains(&text))
+        })
+        .map(|entry| entry.key().to_owned())
+        .collect()
+}
+
 impl From<Localization> for DashMap<String, Option<String>> {
     fn from(value: Localization) -> Self {
         value
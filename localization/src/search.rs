@@ -0,0 +1,202 @@
+use std::path::Path;
+
+use dashmap::DashMap;
+use rusqlite::{params, Connection, OpenFlags};
+use serde::Serialize;
+
+/// Name of the index `extract --locale-index` writes into the output directory.
+pub const FILE_NAME: &str = "locales.sqlite";
+
+/// A SQLite FTS5 index of the strings of one or more locales, and of the datasheet columns
+/// referencing each key.
+pub struct Index {
+    conn: Connection,
+}
+
+/// A string matching a search.
+#[derive(Debug, Serialize)]
+pub struct Hit {
+    pub key: String,
+    pub locale: String,
+    pub text: String,
+    pub references: Vec<Reference>,
+}
+
+/// A datasheet column holding a localization key.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Reference {
+    pub datasheet: String,
+    pub column: String,
+}
+
+impl Index {
+    /// Creates an empty index at `path`, replacing any previous one.
+    pub fn create<P: AsRef<Path>>(path: P) -> rusqlite::Result<Self> {
+        let conn = Connection::open(path)?;
+        conn.execute_batch(
+            "DROP TABLE IF EXISTS strings;
+            DROP TABLE IF EXISTS refs;
+            CREATE VIRTUAL TABLE strings USING fts5(
+                key UNINDEXED,
+                locale UNINDEXED,
+                text,
+                tokenize = 'unicode61 remove_diacritics 2'
+            );
+            CREATE TABLE refs(
+                key TEXT NOT NULL,
+                datasheet TEXT NOT NULL,
+                column_name TEXT NOT NULL
+            );
+            CREATE INDEX refs_key ON refs(key);",
+        )?;
+        Ok(Self { conn })
+    }
+
+    pub fn open<P: AsRef<Path>>(path: P) -> rusqlite::Result<Self> {
+        let conn = Connection::open_with_flags(path, OpenFlags::SQLITE_OPEN_READ_ONLY)?;
+        Ok(Self { conn })
+    }
+
+    /// Adds every string of `locale`, leaving out keys without text.
+    pub fn insert_locale(
+        &mut self,
+        locale: &str,
+        map: &DashMap<String, Option<String>>,
+    ) -> rusqlite::Result<()> {
+        let tx = self.conn.transaction()?;
+        {
+            let mut insert = tx.prepare("INSERT INTO strings VALUES (?, ?, ?)")?;
+            for entry in map.iter() {
+                if let Some(text) = entry.value() {
+                    insert.execute(params![entry.key(), locale, text])?;
+                }
+            }
+        }
+        tx.commit()
+    }
+
+    /// Records the `(column, key)` pairs of `datasheet`, keys as in the string tables.
+    pub fn insert_references<'a, I>(
+        &mut self,
+        datasheet: &str,
+        references: I,
+    ) -> rusqlite::Result<()>
+    where
+        I: IntoIterator<Item = (&'a str, String)>,
+    {
+        let tx = self.conn.transaction()?;
+        {
+            let mut insert = tx.prepare("INSERT INTO refs VALUES (?, ?, ?)")?;
+            for (column, key) in references {
+                insert.execute(params![key, datasheet, column])?;
+            }
+        }
+        tx.commit()
+    }
+
+    /// Strings containing every word of `text`, the last one as a prefix, best matches first.
+    /// Only strings of `locale` when given.
+    pub fn search(
+        &self,
+        text: &str,
+        locale: Option<&str>,
+        limit: usize,
+    ) -> rusqlite::Result<Vec<Hit>> {
+        let Some(query) = query(text) else {
+            return Ok(vec![]);
+        };
+
+        let mut statement = self.conn.prepare(
+            "SELECT key, locale, text FROM strings
+            WHERE strings MATCH ?1 AND (?2 IS NULL OR locale = ?2)
+            ORDER BY rank LIMIT ?3",
+        )?;
+        let mut hits = statement
+            .query_map(params![query, locale, limit as i64], |row| {
+                Ok(Hit {
+                    key: row.get(0)?,
+                    locale: row.get(1)?,
+                    text: row.get(2)?,
+                    references: vec![],
+                })
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+
+        let mut references = self.conn.prepare(
+            "SELECT DISTINCT datasheet, column_name FROM refs WHERE key = ?
+            ORDER BY datasheet, column_name",
+        )?;
+        for hit in &mut hits {
+            hit.references = references
+                .query_map([&hit.key], |row| {
+                    Ok(Reference {
+                        datasheet: row.get(0)?,
+                        column: row.get(1)?,
+                    })
+                })?
+                .collect::<rusqlite::Result<_>>()?;
+        }
+        Ok(hits)
+    }
+}
+
+/// An FTS5 query matching every word of `text` in any order, each quoted so punctuation is
+/// taken literally, and the last one as a prefix so partial input matches.
+fn query(text: &str) -> Option<String> {
+    let words = text
+        .split_whitespace()
+        .map(|word| format!("\"{}\"", word.replace('"', "\"\"")))
+        .collect::<Vec<_>>();
+    if words.is_empty() {
+        return None;
+    }
+    Some(words.join(" ") + "*")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_strings_and_their_references() {
+        let path = std::env::temp_dir().join("nwtools-locale-index-test.sqlite");
+        let mut index = Index::create(&path).unwrap();
+
+        let en = DashMap::from_iter([
+            (
+                "1hsword_t5_description".to_string(),
+                Some("A sword forged in Brimstone Sands".to_string()),
+            ),
+            (
+                "1hsword_t5_name".to_string(),
+                Some("Flaming Sword".to_string()),
+            ),
+            ("empty".to_string(), None),
+        ]);
+        index.insert_locale("en", &en).unwrap();
+        index
+            .insert_references(
+                "javelindata_itemdefinitions_master_common",
+                [("Description", "1hsword_t5_description".to_string())],
+            )
+            .unwrap();
+
+        let hits = index.search("forged brim", None, 10).unwrap();
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].key, "1hsword_t5_description");
+        assert_eq!(
+            hits[0].references,
+            [Reference {
+                datasheet: "javelindata_itemdefinitions_master_common".to_string(),
+                column: "Description".to_string(),
+            }]
+        );
+
+        assert_eq!(index.search("sword", Some("en"), 10).unwrap().len(), 2);
+        assert!(index.search("sword", Some("de"), 10).unwrap().is_empty());
+        assert!(index.search("  ", None, 10).unwrap().is_empty());
+
+        drop(index);
+        std::fs::remove_file(path).unwrap();
+    }
+}
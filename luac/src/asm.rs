@@ -0,0 +1,161 @@
+use std::{fmt::Write, io};
+
+use crate::{
+    ast,
+    opcode::{OpCode, RK},
+    Bytecode, Constant, Function,
+};
+
+/// Lists the instructions, constants, locals and upvalues of every function in a chunk, in the
+/// style of `luac -l -l`.
+pub fn disassemble(buf: &[u8]) -> io::Result<String> {
+    let bytecode = Bytecode::from_reader(buf)?;
+    let mut out = String::new();
+    function(&mut out, &bytecode.main, "main");
+    Ok(out)
+}
+
+fn function(out: &mut String, f: &Function, name: &str) {
+    let source = f
+        .source
+        .as_deref()
+        .map(String::from_utf8_lossy)
+        .unwrap_or_default();
+    let _ = writeln!(
+        out,
+        "{name} <{source}:{},{}> ({} instructions)",
+        f.line_defined,
+        f.last_line_defined,
+        f.code.len()
+    );
+    let _ = writeln!(
+        out,
+        "{}{} params, {} slots, {} upvalues, {} locals, {} constants, {} functions",
+        f.param_count,
+        if f.is_vararg != 0 { "+" } else { "" },
+        f.max_stack_size,
+        f.upvalue_count,
+        f.locals.len(),
+        f.constants.len(),
+        f.prototypes.len()
+    );
+
+    for (pc, i) in f.code.iter().enumerate() {
+        let line = f
+            .line_info
+            .get(pc)
+            .map(|line| format!("[{line}]"))
+            .unwrap_or_else(|| "[-]".into());
+        let Some(op) = i.opcode() else {
+            let _ = writeln!(out, "\t{}\t{line}\t??? {:#010x}", pc + 1, i.0);
+            continue;
+        };
+
+        let (a, b, c) = (i.a(), i.b(), i.c());
+        let rk = |v: u32| match RK::from(v) {
+            RK::Register(r) => r as i64,
+            RK::Constant(k) => -1 - k as i64,
+        };
+        let (operands, comment) = match op {
+            OpCode::LoadK | OpCode::GetGlobal | OpCode::SetGlobal => {
+                (format!("{a} {}", -1 - i.bx() as i64), constant(f, i.bx()))
+            }
+            OpCode::Closure => (
+                format!("{a} {}", i.bx()),
+                Some(format!("function_{}", i.bx())),
+            ),
+            OpCode::Jmp => (
+                format!("{}", i.sbx()),
+                Some(format!("to {}", i.target(pc) + 1)),
+            ),
+            OpCode::ForLoop | OpCode::ForPrep => (
+                format!("{a} {}", i.sbx()),
+                Some(format!("to {}", i.target(pc) + 1)),
+            ),
+            OpCode::GetUpval | OpCode::SetUpval => (
+                format!("{a} {b}"),
+                f.upvalues
+                    .get(b as usize)
+                    .map(|name| String::from_utf8_lossy(name).into_owned()),
+            ),
+            OpCode::Move
+            | OpCode::LoadNil
+            | OpCode::Unm
+            | OpCode::Not
+            | OpCode::Len
+            | OpCode::Return
+            | OpCode::VarArg => (format!("{a} {b}"), None),
+            OpCode::Close => (format!("{a}"), None),
+            OpCode::Test | OpCode::TForLoop => (format!("{a} {c}"), None),
+            OpCode::GetTable | OpCode::SelfOp => {
+                (format!("{a} {b} {}", rk(c)), rk_comments(f, &[c]))
+            }
+            OpCode::SetTable
+            | OpCode::Add
+            | OpCode::Sub
+            | OpCode::Mul
+            | OpCode::Div
+            | OpCode::Mod
+            | OpCode::Pow
+            | OpCode::Eq
+            | OpCode::Lt
+            | OpCode::Le => (format!("{a} {} {}", rk(b), rk(c)), rk_comments(f, &[b, c])),
+            OpCode::LoadBool
+            | OpCode::NewTable
+            | OpCode::Concat
+            | OpCode::TestSet
+            | OpCode::Call
+            | OpCode::TailCall
+            | OpCode::SetList => (format!("{a} {b} {c}"), None),
+        };
+
+        let _ = write!(out, "\t{}\t{line}\t{:<9}\t{operands}", pc + 1, op.name());
+        if let Some(comment) = comment {
+            let _ = write!(out, "\t; {comment}");
+        }
+        out.push('\n');
+    }
+
+    let _ = writeln!(out, "constants ({})", f.constants.len());
+    for (k, _) in f.constants.iter().enumerate() {
+        let value = constant(f, k as u32).unwrap_or_default();
+        let _ = writeln!(out, "\t{}\t{value}", k + 1);
+    }
+    let _ = writeln!(out, "locals ({})", f.locals.len());
+    for (n, local) in f.locals.iter().enumerate() {
+        let name = String::from_utf8_lossy(&local.name);
+        let _ = writeln!(out, "\t{n}\t{name}\t{}\t{}", local.start + 1, local.end + 1);
+    }
+    let _ = writeln!(out, "upvalues ({})", f.upvalues.len());
+    for (n, name) in f.upvalues.iter().enumerate() {
+        let _ = writeln!(out, "\t{n}\t{}", String::from_utf8_lossy(name));
+    }
+
+    for (n, proto) in f.prototypes.iter().enumerate() {
+        out.push('\n');
+        function(out, proto, &format!("function_{n}"));
+    }
+}
+
+fn constant(f: &Function, index: u32) -> Option<String> {
+    Some(match f.constants.get(index as usize)? {
+        Constant::Nil => "nil".into(),
+        Constant::Boolean(b) => b.to_string(),
+        Constant::Number(n) => ast::number(*n),
+        Constant::String(s) => ast::string(s),
+    })
+}
+
+fn rk_comments(f: &Function, operands: &[u32]) -> Option<String> {
+    let comments = operands
+        .iter()
+        .map(|&v| match RK::from(v) {
+            RK::Constant(k) => constant(f, k),
+            RK::Register(_) => Some("-".into()),
+        })
+        .collect::<Option<Vec<_>>>()?;
+    comments
+        .iter()
+        .any(|c| c != "-")
+        .then(|| comments.join(" "))
+}
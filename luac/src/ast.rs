@@ -0,0 +1,529 @@
+use std::fmt::Write;
+
+const KEYWORDS: [&str; 21] = [
+    "and", "break", "do", "else", "elseif", "end", "false", "for", "function", "if", "in", "local",
+    "nil", "not", "or", "repeat", "return", "then", "true", "until", "while",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BinOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Mod,
+    Pow,
+    Concat,
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    And,
+    Or,
+}
+
+impl BinOp {
+    fn symbol(&self) -> &'static str {
+        match self {
+            BinOp::Add => "+",
+            BinOp::Sub => "-",
+            BinOp::Mul => "*",
+            BinOp::Div => "/",
+            BinOp::Mod => "%",
+            BinOp::Pow => "^",
+            BinOp::Concat => "..",
+            BinOp::Eq => "==",
+            BinOp::Ne => "~=",
+            BinOp::Lt => "<",
+            BinOp::Le => "<=",
+            BinOp::And => "and",
+            BinOp::Or => "or",
+        }
+    }
+
+    /// Left and right binding power, `priority` in `lparser.c`.
+    fn priority(&self) -> (u8, u8) {
+        match self {
+            BinOp::Or => (1, 1),
+            BinOp::And => (2, 2),
+            BinOp::Eq | BinOp::Ne | BinOp::Lt | BinOp::Le => (3, 3),
+            BinOp::Concat => (5, 4),
+            BinOp::Add | BinOp::Sub => (6, 6),
+            BinOp::Mul | BinOp::Div | BinOp::Mod => (7, 7),
+            BinOp::Pow => (10, 9),
+        }
+    }
+}
+
+const UNARY_PRIORITY: u8 = 8;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UnOp {
+    Neg,
+    Not,
+    Len,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expr {
+    Nil,
+    Boolean(bool),
+    Number(f64),
+    String(Vec<u8>),
+    Vararg,
+    Name(String),
+    Index(Box<Expr>, Box<Expr>),
+    Call(Box<Expr>, Vec<Expr>),
+    Method(Box<Expr>, String, Vec<Expr>),
+    /// Truncates a multiple results expression to its first value.
+    Paren(Box<Expr>),
+    Function(Box<Function>),
+    Table(Vec<(Option<Expr>, Expr)>),
+    Binary(BinOp, Box<Expr>, Box<Expr>),
+    Unary(UnOp, Box<Expr>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Stmt {
+    Local(Vec<String>, Vec<Expr>),
+    Assign(Vec<Expr>, Vec<Expr>),
+    Call(Expr),
+    Return(Vec<Expr>),
+    Break,
+    If(Expr, Vec<Stmt>, Vec<Stmt>),
+    While(Expr, Vec<Stmt>),
+    Repeat(Vec<Stmt>, Expr),
+    NumericFor(String, Expr, Expr, Option<Expr>, Vec<Stmt>),
+    GenericFor(Vec<String>, Vec<Expr>, Vec<Stmt>),
+    Comment(String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Function {
+    pub params: Vec<String>,
+    pub vararg: bool,
+    pub body: Result<Vec<Stmt>, String>,
+}
+
+impl Expr {
+    pub fn binary(op: BinOp, left: Expr, right: Expr) -> Self {
+        Expr::Binary(op, Box::new(left), Box::new(right))
+    }
+
+    pub fn not(self) -> Self {
+        match self {
+            Expr::Unary(UnOp::Not, e) => *e,
+            Expr::Binary(BinOp::Eq, l, r) => Expr::Binary(BinOp::Ne, l, r),
+            Expr::Binary(BinOp::Ne, l, r) => Expr::Binary(BinOp::Eq, l, r),
+            Expr::Boolean(b) => Expr::Boolean(!b),
+            e => Expr::Unary(UnOp::Not, Box::new(e)),
+        }
+    }
+
+    pub fn and(self, other: Expr) -> Self {
+        match (self, other) {
+            (Expr::Boolean(true), e) | (e, Expr::Boolean(true)) => e,
+            (l, r) => Expr::binary(BinOp::And, l, r),
+        }
+    }
+
+    pub fn or(self, other: Expr) -> Self {
+        match (self, other) {
+            (Expr::Boolean(false), e) | (e, Expr::Boolean(false)) => e,
+            (l, r) => Expr::binary(BinOp::Or, l, r),
+        }
+    }
+
+    /// Whether evaluating the expression may run arbitrary code.
+    pub fn has_call(&self) -> bool {
+        match self {
+            Expr::Call(..) | Expr::Method(..) => true,
+            Expr::Index(t, k) => t.has_call() || k.has_call(),
+            Expr::Paren(e) | Expr::Unary(_, e) => e.has_call(),
+            Expr::Binary(_, l, r) => l.has_call() || r.has_call(),
+            Expr::Table(fields) => fields
+                .iter()
+                .any(|(k, v)| k.as_ref().is_some_and(Expr::has_call) || v.has_call()),
+            _ => false,
+        }
+    }
+
+    pub fn has_index(&self) -> bool {
+        match self {
+            Expr::Index(..) => true,
+            Expr::Call(f, args) => f.has_index() || args.iter().any(Expr::has_index),
+            Expr::Method(o, _, args) => o.has_index() || args.iter().any(Expr::has_index),
+            Expr::Paren(e) | Expr::Unary(_, e) => e.has_index(),
+            Expr::Binary(_, l, r) => l.has_index() || r.has_index(),
+            Expr::Table(fields) => fields
+                .iter()
+                .any(|(k, v)| k.as_ref().is_some_and(Expr::has_index) || v.has_index()),
+            _ => false,
+        }
+    }
+
+    pub fn references(&self, name: &str) -> bool {
+        match self {
+            Expr::Name(n) => n == name,
+            Expr::Index(t, k) => t.references(name) || k.references(name),
+            Expr::Call(f, args) => f.references(name) || args.iter().any(|a| a.references(name)),
+            Expr::Method(o, _, args) => {
+                o.references(name) || args.iter().any(|a| a.references(name))
+            }
+            Expr::Paren(e) | Expr::Unary(_, e) => e.references(name),
+            Expr::Binary(_, l, r) => l.references(name) || r.references(name),
+            Expr::Table(fields) => fields
+                .iter()
+                .any(|(k, v)| k.as_ref().is_some_and(|k| k.references(name)) || v.references(name)),
+            // closures see the variable itself, not its current value
+            _ => false,
+        }
+    }
+
+    /// Whether the expression may produce more than one value in a list.
+    pub fn is_multi(&self) -> bool {
+        matches!(self, Expr::Call(..) | Expr::Method(..) | Expr::Vararg)
+    }
+}
+
+pub fn is_identifier(name: &[u8]) -> bool {
+    let Ok(name) = std::str::from_utf8(name) else {
+        return false;
+    };
+    let mut chars = name.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+        && !KEYWORDS.contains(&name)
+}
+
+pub struct Printer {
+    out: String,
+    indent: usize,
+}
+
+impl Printer {
+    pub fn new() -> Self {
+        Self {
+            out: String::new(),
+            indent: 0,
+        }
+    }
+
+    pub fn finish(self) -> String {
+        self.out
+    }
+
+    fn line(&mut self, text: &str) {
+        for _ in 0..self.indent {
+            self.out.push_str("  ");
+        }
+        self.out.push_str(text);
+        self.out.push('\n');
+    }
+
+    pub fn block(&mut self, stmts: &[Stmt]) {
+        for (i, stmt) in stmts.iter().enumerate() {
+            let last = i + 1 == stmts.len();
+            match stmt {
+                // `return` and `break` must end a block
+                Stmt::Return(_) | Stmt::Break if !last => {
+                    self.line("do");
+                    self.indent += 1;
+                    self.stmt(stmt);
+                    self.indent -= 1;
+                    self.line("end");
+                }
+                _ => self.stmt(stmt),
+            }
+        }
+    }
+
+    fn body(&mut self, head: &str, stmts: &[Stmt], tail: &str) {
+        self.line(head);
+        self.indent += 1;
+        self.block(stmts);
+        self.indent -= 1;
+        self.line(tail);
+    }
+
+    fn stmt(&mut self, stmt: &Stmt) {
+        match stmt {
+            Stmt::Local(names, exprs) => match exprs.as_slice() {
+                [Expr::Function(function)] if names.len() == 1 => {
+                    let head = format!("local function {}", names[0]);
+                    self.function(&head, function, false);
+                }
+                [] => self.line(&format!("local {}", names.join(", "))),
+                _ => {
+                    let exprs = self.list(exprs);
+                    self.line(&format!("local {} = {}", names.join(", "), exprs));
+                }
+            },
+            Stmt::Assign(targets, exprs) => match (targets.as_slice(), exprs.as_slice()) {
+                ([target], [Expr::Function(function)]) if function_name(target).is_some() => {
+                    let mut path = function_name(target).unwrap();
+                    let method =
+                        path.len() > 1 && function.params.first().is_some_and(|p| p == "self");
+                    let mut name = path.join(".");
+                    if method {
+                        let last = path.pop().unwrap();
+                        name = format!("{}:{last}", path.join("."));
+                    }
+                    self.function(&format!("function {name}"), function, method);
+                }
+                _ => {
+                    let targets = self.list(targets);
+                    let exprs = self.list(exprs);
+                    self.line(&format!("{targets} = {exprs}"));
+                }
+            },
+            Stmt::Call(expr) => {
+                let expr = self.expr(expr);
+                self.line(&expr);
+            }
+            Stmt::Return(exprs) if exprs.is_empty() => self.line("return"),
+            Stmt::Return(exprs) => {
+                let exprs = self.list(exprs);
+                self.line(&format!("return {exprs}"));
+            }
+            Stmt::Break => self.line("break"),
+            Stmt::If(cond, then, otherwise) => {
+                let mut cond = cond;
+                let mut then = then;
+                let mut otherwise = otherwise;
+                let mut head = format!("if {} then", self.expr(cond));
+                loop {
+                    self.line(&head);
+                    self.indent += 1;
+                    self.block(then);
+                    self.indent -= 1;
+                    match otherwise.as_slice() {
+                        [] => break,
+                        [Stmt::If(c, t, o)] => {
+                            (cond, then, otherwise) = (c, t, o);
+                            head = format!("elseif {} then", self.expr(cond));
+                        }
+                        _ => {
+                            self.line("else");
+                            self.indent += 1;
+                            self.block(otherwise);
+                            self.indent -= 1;
+                            break;
+                        }
+                    }
+                }
+                self.line("end");
+            }
+            Stmt::While(cond, body) => {
+                let head = format!("while {} do", self.expr(cond));
+                self.body(&head, body, "end");
+            }
+            Stmt::Repeat(body, cond) => {
+                let tail = format!("until {}", self.expr(cond));
+                self.body("repeat", body, &tail);
+            }
+            Stmt::NumericFor(var, start, limit, step, body) => {
+                let mut head = format!("for {var} = {}, {}", self.expr(start), self.expr(limit));
+                if let Some(step) = step {
+                    let _ = write!(head, ", {}", self.expr(step));
+                }
+                head.push_str(" do");
+                self.body(&head, body, "end");
+            }
+            Stmt::GenericFor(vars, exprs, body) => {
+                let head = format!("for {} in {} do", vars.join(", "), self.list(exprs));
+                self.body(&head, body, "end");
+            }
+            Stmt::Comment(text) => {
+                for line in text.lines() {
+                    self.line(&format!("-- {line}"));
+                }
+            }
+        }
+    }
+
+    fn function(&mut self, head: &str, function: &Function, method: bool) {
+        let mut params = function
+            .params
+            .iter()
+            .skip(method as usize)
+            .cloned()
+            .collect::<Vec<_>>();
+        if function.vararg {
+            params.push("...".into());
+        }
+        let head = format!("{head}({})", params.join(", "));
+        match &function.body {
+            Ok(body) => self.body(&head, body, "end"),
+            Err(e) => {
+                let body = [Stmt::Comment(format!("decompilation failed: {e}"))];
+                self.body(&head, &body, "end")
+            }
+        }
+    }
+
+    fn list(&mut self, exprs: &[Expr]) -> String {
+        exprs
+            .iter()
+            .map(|e| self.expr(e))
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    pub fn expr(&mut self, expr: &Expr) -> String {
+        match expr {
+            Expr::Nil => "nil".into(),
+            Expr::Boolean(b) => b.to_string(),
+            Expr::Number(n) => number(*n),
+            Expr::String(s) => string(s),
+            Expr::Vararg => "...".into(),
+            Expr::Name(name) => name.clone(),
+            Expr::Index(table, key) => {
+                let table = self.prefix(table);
+                match key.as_ref() {
+                    Expr::String(s) if is_identifier(s) => {
+                        format!("{table}.{}", String::from_utf8_lossy(s))
+                    }
+                    key => format!("{table}[{}]", self.expr(key)),
+                }
+            }
+            Expr::Call(f, args) => {
+                let f = self.prefix(f);
+                format!("{f}({})", self.list(args))
+            }
+            Expr::Method(object, name, args) => {
+                let object = self.prefix(object);
+                format!("{object}:{name}({})", self.list(args))
+            }
+            Expr::Paren(e) => format!("({})", self.expr(e)),
+            Expr::Function(function) => {
+                // render the closure at the current indentation and splice it in
+                let mut printer = Printer {
+                    out: String::new(),
+                    indent: self.indent,
+                };
+                printer.function("function", function, false);
+                printer.out.trim().to_string()
+            }
+            Expr::Table(fields) if fields.is_empty() => "{}".into(),
+            Expr::Table(fields) => {
+                let fields = fields
+                    .iter()
+                    .map(|(key, value)| {
+                        let value = self.expr(value);
+                        match key {
+                            None => value,
+                            Some(Expr::String(s)) if is_identifier(s) => {
+                                format!("{} = {value}", String::from_utf8_lossy(s))
+                            }
+                            Some(key) => format!("[{}] = {value}", self.expr(key)),
+                        }
+                    })
+                    .collect::<Vec<_>>();
+                format!("{{{}}}", fields.join(", "))
+            }
+            Expr::Binary(op, left, right) => {
+                let (l, r) = op.priority();
+                // same rules `subexpr` in `lparser.c` parses them back with
+                let left = self.operand(left, |(_, child)| l > child);
+                let right = self.operand(right, |(child, _)| child <= r);
+                format!("{left} {} {right}", op.symbol())
+            }
+            Expr::Unary(op, e) => {
+                let e = self.operand(e, |(child, _)| child <= UNARY_PRIORITY);
+                match op {
+                    UnOp::Neg if e.starts_with('-') => format!("- {e}"),
+                    UnOp::Neg => format!("-{e}"),
+                    UnOp::Not => format!("not {e}"),
+                    UnOp::Len => format!("#{e}"),
+                }
+            }
+        }
+    }
+
+    fn operand(&mut self, expr: &Expr, parens: impl Fn((u8, u8)) -> bool) -> String {
+        let priority = match expr {
+            Expr::Binary(op, ..) => op.priority(),
+            Expr::Unary(..) => (u8::MAX, UNARY_PRIORITY),
+            _ => (u8::MAX, u8::MAX),
+        };
+        match parens(priority) {
+            true => format!("({})", self.expr(expr)),
+            false => self.expr(expr),
+        }
+    }
+
+    fn prefix(&mut self, expr: &Expr) -> String {
+        match expr {
+            Expr::Name(_)
+            | Expr::Index(..)
+            | Expr::Call(..)
+            | Expr::Method(..)
+            | Expr::Paren(_) => self.expr(expr),
+            e => format!("({})", self.expr(e)),
+        }
+    }
+}
+
+/// Path of `function a.b.c()` declarations.
+fn function_name(target: &Expr) -> Option<Vec<String>> {
+    match target {
+        Expr::Name(name) => Some(vec![name.clone()]),
+        Expr::Index(table, key) => match key.as_ref() {
+            Expr::String(key) if is_identifier(key) => {
+                let mut path = function_name(table)?;
+                path.push(String::from_utf8_lossy(key).into_owned());
+                Some(path)
+            }
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+pub(crate) fn number(n: f64) -> String {
+    if n.is_nan() {
+        "(0/0)".into()
+    } else if n.is_infinite() {
+        match n > 0.0 {
+            true => "math.huge".into(),
+            false => "-math.huge".into(),
+        }
+    } else if n.fract() == 0.0 && n.abs() < 1e15 {
+        format!("{}", n as i64)
+    } else {
+        format!("{n}")
+    }
+}
+
+pub(crate) fn string(bytes: &[u8]) -> String {
+    let mut out = String::from("\"");
+    match std::str::from_utf8(bytes) {
+        Ok(text) => text.chars().for_each(|c| escape(&mut out, c)),
+        // keep the exact bytes of binary strings
+        Err(_) => bytes.iter().for_each(|&b| match b.is_ascii() {
+            true => escape(&mut out, b as char),
+            false => {
+                let _ = write!(out, "\\{b:03}");
+            }
+        }),
+    }
+    out.push('"');
+    out
+}
+
+fn escape(out: &mut String, c: char) {
+    match c {
+        '\\' => out.push_str("\\\\"),
+        '"' => out.push_str("\\\""),
+        '\n' => out.push_str("\\n"),
+        '\r' => out.push_str("\\r"),
+        '\t' => out.push_str("\\t"),
+        c if c.is_ascii_control() => {
+            let _ = write!(out, "\\{:03}", c as u32);
+        }
+        c => out.push(c),
+    }
+}
@@ -0,0 +1,1291 @@
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet},
+    io,
+};
+
+use crate::{
+    ast::{is_identifier, BinOp, Expr, Function, Printer, Stmt, UnOp},
+    opcode::{OpCode, FIELDS_PER_FLUSH, RK},
+    Bytecode, Constant,
+};
+
+type Result<T> = std::result::Result<T, String>;
+
+/// Flag in `is_vararg` for functions declared with `...`.
+const VARARG_ISVARARG: u8 = 2;
+
+/// Decompiles a Lua 5.1 chunk back to source. Functions using constructs the decompiler does not
+/// understand are emitted with a comment in place of their body.
+pub fn decompile(buf: &[u8]) -> io::Result<String> {
+    let bytecode = Bytecode::from_reader(buf)?;
+    let main = Decompiler::function(&bytecode.main, Vec::new());
+
+    let mut printer = Printer::new();
+    match main.body {
+        Ok(body) => printer.block(&body),
+        Err(e) => printer.block(&[Stmt::Comment(format!("decompilation failed: {e}"))]),
+    }
+    Ok(printer.finish())
+}
+
+/// Values computed into registers but not yet used. Expressions are inlined into the
+/// instruction consuming them, or assigned to a `rN` temporary when that isn't possible.
+#[derive(Debug, Clone, Default)]
+struct State {
+    stmts: Vec<Stmt>,
+    /// Register to expression and the number of registers it fills, 0 for all up to the top.
+    pending: BTreeMap<u32, (Expr, u32)>,
+    /// Registers holding the function of a `SELF` method call.
+    methods: HashSet<u32>,
+    /// Indices of the debug locals already declared.
+    declared: HashSet<usize>,
+    /// Registers used as temporaries, declared at the top of the function.
+    temps: BTreeSet<u32>,
+}
+
+impl State {
+    fn nested(&self) -> Self {
+        Self {
+            declared: self.declared.clone(),
+            temps: self.temps.clone(),
+            ..Default::default()
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Loop {
+    /// Where a `break` jumps to.
+    exit: usize,
+    /// Where the next iteration starts.
+    next: usize,
+}
+
+struct Decompiler<'a> {
+    f: &'a crate::Function,
+    upvalues: Vec<String>,
+    /// Loop headers and the backward jump closing them.
+    headers: HashMap<usize, usize>,
+    active: HashSet<usize>,
+    loops: Vec<Loop>,
+}
+
+impl<'a> Decompiler<'a> {
+    fn function(f: &'a crate::Function, upvalues: Vec<String>) -> Function {
+        let mut headers = HashMap::new();
+        for (pc, i) in f.code.iter().enumerate() {
+            let after_tforloop = pc > 0 && f.code[pc - 1].opcode() == Some(OpCode::TForLoop);
+            if i.opcode() == Some(OpCode::Jmp) && i.sbx() < 0 && !after_tforloop {
+                let back = headers.entry(i.target(pc)).or_insert(pc);
+                *back = pc.max(*back);
+            }
+        }
+
+        let upvalues = match f.upvalues.len() == f.upvalue_count as usize {
+            true => f
+                .upvalues
+                .iter()
+                .map(|name| String::from_utf8_lossy(name).into_owned())
+                .collect(),
+            false => upvalues,
+        };
+
+        let mut this = Self {
+            f,
+            upvalues,
+            headers,
+            active: HashSet::new(),
+            loops: Vec::new(),
+        };
+
+        let mut st = State::default();
+        let params = (0..f.param_count as u32)
+            .map(|r| match f.local_at(r, 0) {
+                Some((index, local)) => {
+                    st.declared.insert(index);
+                    String::from_utf8_lossy(&local.name).into_owned()
+                }
+                None => temp(r),
+            })
+            .collect::<Vec<_>>();
+
+        let body = this.block(&mut st, 0, f.code.len(), f.code.len()).map(|_| {
+            this.flush_all(&mut st);
+            let temps = st
+                .temps
+                .iter()
+                .map(|&r| temp(r))
+                .filter(|name| !params.contains(name))
+                .collect::<Vec<_>>();
+            if !temps.is_empty() {
+                st.stmts.insert(0, Stmt::Local(temps, vec![]));
+            }
+            st.stmts
+        });
+
+        Function {
+            params,
+            vararg: f.is_vararg & VARARG_ISVARARG != 0,
+            body,
+        }
+    }
+
+    fn block(&mut self, st: &mut State, start: usize, end: usize, exit: usize) -> Result<()> {
+        let mut pc = start;
+        while pc < end {
+            self.declare(st, pc);
+            pc = match self.headers.get(&pc) {
+                Some(&back) if !self.active.contains(&pc) => {
+                    if back >= end {
+                        return Err(format!("loop at {pc} crosses its block"));
+                    }
+                    self.flush_all(st);
+                    self.active.insert(pc);
+                    let next = self.while_or_repeat(st, pc, back);
+                    self.active.remove(&pc);
+                    next?
+                }
+                _ => self.instruction(st, pc, end, exit)?,
+            };
+        }
+        Ok(())
+    }
+
+    /// Decompiles `start..end` into a new block, `exit` being where control goes after it.
+    fn nested(
+        &mut self,
+        st: &mut State,
+        start: usize,
+        end: usize,
+        exit: usize,
+    ) -> Result<Vec<Stmt>> {
+        let mut child = st.nested();
+        self.block(&mut child, start, end, exit)?;
+        self.flush_all(&mut child);
+        st.declared = child.declared;
+        st.temps = child.temps;
+        Ok(child.stmts)
+    }
+
+    fn instruction(&mut self, st: &mut State, pc: usize, end: usize, exit: usize) -> Result<usize> {
+        let i = self.f.code[pc];
+        let op = i
+            .opcode()
+            .ok_or_else(|| format!("unknown opcode {} at {pc}", i.0 & 0x3F))?;
+        let (a, b, c) = (i.a(), i.b(), i.c());
+
+        match op {
+            OpCode::Move => {
+                let e = self.read(st, b, pc);
+                self.write(st, pc, a, e, 1);
+            }
+            OpCode::LoadK => {
+                let e = self.constant(i.bx())?;
+                self.write(st, pc, a, e, 1);
+            }
+            OpCode::LoadBool if c == 0 => self.write(st, pc, a, Expr::Boolean(b != 0), 1),
+            OpCode::LoadBool => return Err(format!("unexpected LOADBOOL at {pc}")),
+            OpCode::LoadNil => {
+                for r in a..=b {
+                    self.write(st, pc, r, Expr::Nil, 1);
+                }
+            }
+            OpCode::GetUpval => {
+                let e = Expr::Name(self.upvalue(b));
+                self.write(st, pc, a, e, 1);
+            }
+            OpCode::GetGlobal => {
+                let e = self.global(i.bx())?;
+                self.write(st, pc, a, e, 1);
+            }
+            OpCode::GetTable => {
+                let table = self.read(st, b, pc);
+                let key = self.rk(st, c, pc)?;
+                self.write(st, pc, a, Expr::Index(Box::new(table), Box::new(key)), 1);
+            }
+            OpCode::SetGlobal => {
+                let value = self.read(st, a, pc);
+                let target = self.global(i.bx())?;
+                self.assign(st, vec![target], vec![value]);
+            }
+            OpCode::SetUpval => {
+                let value = self.read(st, a, pc);
+                let target = Expr::Name(self.upvalue(b));
+                self.assign(st, vec![target], vec![value]);
+            }
+            OpCode::SetTable => {
+                let key = self.rk(st, b, pc)?;
+                let value = self.rk(st, c, pc)?;
+                match st.pending.get_mut(&a) {
+                    Some((Expr::Table(fields), 1)) => fields.push((Some(key), value)),
+                    _ => {
+                        let table = self.read(st, a, pc);
+                        let target = Expr::Index(Box::new(table), Box::new(key));
+                        self.assign(st, vec![target], vec![value]);
+                    }
+                }
+            }
+            OpCode::NewTable => self.write(st, pc, a, Expr::Table(vec![]), 1),
+            OpCode::SelfOp => {
+                let mut object = self.read(st, b, pc);
+                if object.has_call() {
+                    // the object is used twice, evaluate it once
+                    self.flush_value(st, b, object);
+                    object = Expr::Name(temp(b));
+                }
+                let key = self.rk(st, c, pc)?;
+                self.write(st, pc, a + 1, object.clone(), 1);
+                self.write(st, pc, a, Expr::Index(Box::new(object), Box::new(key)), 1);
+                st.methods.insert(a);
+            }
+            OpCode::Add | OpCode::Sub | OpCode::Mul | OpCode::Div | OpCode::Mod | OpCode::Pow => {
+                let op = match op {
+                    OpCode::Add => BinOp::Add,
+                    OpCode::Sub => BinOp::Sub,
+                    OpCode::Mul => BinOp::Mul,
+                    OpCode::Div => BinOp::Div,
+                    OpCode::Mod => BinOp::Mod,
+                    _ => BinOp::Pow,
+                };
+                let left = self.rk(st, b, pc)?;
+                let right = self.rk(st, c, pc)?;
+                self.write(st, pc, a, Expr::binary(op, left, right), 1);
+            }
+            OpCode::Unm | OpCode::Not | OpCode::Len => {
+                let op = match op {
+                    OpCode::Unm => UnOp::Neg,
+                    OpCode::Not => UnOp::Not,
+                    _ => UnOp::Len,
+                };
+                let e = self.read(st, b, pc);
+                self.write(st, pc, a, Expr::Unary(op, Box::new(e)), 1);
+            }
+            OpCode::Concat => {
+                let operands = (b..=c).map(|r| self.read(st, r, pc)).collect::<Vec<_>>();
+                let e = operands
+                    .into_iter()
+                    .rev()
+                    .reduce(|right, left| Expr::binary(BinOp::Concat, left, right))
+                    .unwrap_or(Expr::String(vec![]));
+                self.write(st, pc, a, e, 1);
+            }
+            OpCode::Jmp => return self.jump(st, pc, end, exit),
+            OpCode::Eq | OpCode::Lt | OpCode::Le | OpCode::Test | OpCode::TestSet => {
+                return self.conditional(st, pc, end, exit)
+            }
+            OpCode::Call => {
+                let call = self.call(st, pc, a, b);
+                match c {
+                    1 => self.emit(st, Stmt::Call(call)),
+                    0 => self.write(st, pc, a, call, 0),
+                    c => self.write(st, pc, a, call, c - 1),
+                }
+            }
+            OpCode::TailCall => {
+                let call = self.call(st, pc, a, b);
+                self.emit(st, Stmt::Return(vec![call]));
+                if self.f.code.get(pc + 1).and_then(|i| i.opcode()) == Some(OpCode::Return) {
+                    return Ok(pc + 2);
+                }
+            }
+            OpCode::Return => {
+                let values = self.list(st, pc, a, b);
+                // every function ends with an implicit `return`
+                if pc + 1 != self.f.code.len() || !values.is_empty() {
+                    self.emit(st, Stmt::Return(values));
+                }
+            }
+            OpCode::ForPrep => return self.numeric_for(st, pc),
+            OpCode::ForLoop | OpCode::TForLoop => {
+                return Err(format!("unexpected {op} at {pc}"));
+            }
+            OpCode::SetList => {
+                let (block, next) = match c {
+                    0 => {
+                        let raw = self.f.code.get(pc + 1).ok_or("truncated SETLIST")?;
+                        (raw.0, pc + 2)
+                    }
+                    c => (c, pc + 1),
+                };
+                let values = self.list(st, pc, a + 1, if b == 0 { 0 } else { b + 1 });
+                match st.pending.get_mut(&a) {
+                    Some((Expr::Table(fields), 1)) => {
+                        fields.extend(values.into_iter().map(|v| (None, v)))
+                    }
+                    _ => {
+                        let table = self.read(st, a, pc);
+                        let first = (block.saturating_sub(1) * FIELDS_PER_FLUSH) as f64 + 1.0;
+                        for (n, value) in values.into_iter().enumerate() {
+                            let key = Expr::Number(first + n as f64);
+                            let target = Expr::Index(Box::new(table.clone()), Box::new(key));
+                            self.assign(st, vec![target], vec![value]);
+                        }
+                    }
+                }
+                return Ok(next);
+            }
+            OpCode::Close => {}
+            OpCode::Closure => {
+                let f = self.f;
+                let proto = f
+                    .prototypes
+                    .get(i.bx() as usize)
+                    .ok_or_else(|| format!("missing prototype {} at {pc}", i.bx()))?;
+                let count = proto.upvalue_count as usize;
+                let upvalues = (pc + 1..pc + 1 + count)
+                    .filter_map(|pc| self.f.code.get(pc).map(|i| (pc, *i)))
+                    .map(|(pc, i)| match i.opcode() {
+                        Some(OpCode::GetUpval) => self.upvalue(i.b()),
+                        _ => self.name(st, i.b(), pc),
+                    })
+                    .collect();
+                let function = Decompiler::function(proto, upvalues);
+                self.write(st, pc, a, Expr::Function(Box::new(function)), 1);
+                return Ok(pc + 1 + count);
+            }
+            OpCode::VarArg => match b {
+                0 => self.write(st, pc, a, Expr::Vararg, 0),
+                b => self.write(st, pc, a, Expr::Vararg, b - 1),
+            },
+        }
+
+        Ok(pc + 1)
+    }
+
+    fn jump(&mut self, st: &mut State, pc: usize, end: usize, exit: usize) -> Result<usize> {
+        let target = self.f.code[pc].target(pc);
+
+        if let Some(tforloop) = self.f.code.get(target) {
+            let back = self.f.code.get(target + 1);
+            if tforloop.opcode() == Some(OpCode::TForLoop)
+                && back.is_some_and(|i| {
+                    i.opcode() == Some(OpCode::Jmp) && i.target(target + 1) == pc + 1
+                })
+            {
+                return self.generic_for(st, pc, target);
+            }
+        }
+
+        if self.loops.last().is_some_and(|l| l.exit == target) {
+            self.emit(st, Stmt::Break);
+            return Ok(pc + 1);
+        }
+
+        let falls_through = target == end || target == exit;
+        let continues = self.loops.last().is_some_and(|l| l.next == target);
+        if pc + 1 == end && (falls_through || continues) {
+            return Ok(pc + 1);
+        }
+
+        Err(format!("unstructured jump from {pc} to {target}"))
+    }
+
+    fn while_or_repeat(&mut self, st: &mut State, start: usize, back: usize) -> Result<usize> {
+        let until = back
+            .checked_sub(1)
+            .filter(|&pc| pc >= start)
+            .and_then(|pc| self.f.code[pc].opcode())
+            .is_some_and(|op| op.is_test());
+
+        if until {
+            self.loops.push(Loop {
+                exit: back + 1,
+                next: back - 1,
+            });
+            let mut child = st.nested();
+            let body = self
+                .block(&mut child, start, back - 1, back - 1)
+                .and_then(|_| self.jump_condition(&mut child, back - 1));
+            self.loops.pop();
+
+            let cond = body?.not();
+            self.flush_all(&mut child);
+            st.declared = child.declared;
+            st.temps = child.temps;
+            self.emit(st, Stmt::Repeat(child.stmts, cond));
+            return Ok(back + 1);
+        }
+
+        self.loops.push(Loop {
+            exit: back + 1,
+            next: start,
+        });
+        let body = self.nested(st, start, back, start);
+        self.loops.pop();
+        let mut body = body?;
+
+        let stmt = match body.first() {
+            Some(Stmt::If(cond, then, otherwise))
+                if then.as_slice() == [Stmt::Break] && otherwise.is_empty() =>
+            {
+                let cond = cond.clone().not();
+                body.remove(0);
+                Stmt::While(cond, body)
+            }
+            _ => Stmt::While(Expr::Boolean(true), body),
+        };
+        self.emit(st, stmt);
+        Ok(back + 1)
+    }
+
+    fn numeric_for(&mut self, st: &mut State, pc: usize) -> Result<usize> {
+        let i = self.f.code[pc];
+        let a = i.a();
+        let forloop = i.target(pc);
+        if self.f.code.get(forloop).and_then(|i| i.opcode()) != Some(OpCode::ForLoop) {
+            return Err(format!("FORPREP at {pc} without FORLOOP"));
+        }
+
+        let start = self.read(st, a, pc);
+        let limit = self.read(st, a + 1, pc);
+        let step = match self.read(st, a + 2, pc) {
+            Expr::Number(1.0) => None,
+            step => Some(step),
+        };
+        self.flush_all(st);
+        let var = self.loop_var(st, a + 3, pc + 1);
+
+        self.loops.push(Loop {
+            exit: forloop + 1,
+            next: forloop,
+        });
+        let body = self.nested(st, pc + 1, forloop, forloop);
+        self.loops.pop();
+
+        self.emit(st, Stmt::NumericFor(var, start, limit, step, body?));
+        Ok(forloop + 1)
+    }
+
+    fn generic_for(&mut self, st: &mut State, pc: usize, tforloop: usize) -> Result<usize> {
+        let i = self.f.code[tforloop];
+        let (a, c) = (i.a(), i.c());
+
+        let exprs = match st.pending.get(&a) {
+            Some((_, 3)) | Some((_, 0)) => vec![st.pending.remove(&a).unwrap().0],
+            _ => {
+                let mut exprs = (a..a + 3).map(|r| self.read(st, r, pc)).collect::<Vec<_>>();
+                while exprs.len() > 1 && exprs.last() == Some(&Expr::Nil) {
+                    exprs.pop();
+                }
+                exprs
+            }
+        };
+        self.flush_all(st);
+        let vars = (a + 3..a + 3 + c.max(1))
+            .map(|r| self.loop_var(st, r, pc + 1))
+            .collect();
+
+        self.loops.push(Loop {
+            exit: tforloop + 2,
+            next: tforloop,
+        });
+        let body = self.nested(st, pc + 1, tforloop, tforloop);
+        self.loops.pop();
+
+        self.emit(st, Stmt::GenericFor(vars, exprs, body?));
+        Ok(tforloop + 2)
+    }
+
+    fn loop_var(&self, st: &mut State, register: u32, pc: usize) -> String {
+        match self.f.local_at(register, pc) {
+            Some((index, local)) => {
+                st.declared.insert(index);
+                String::from_utf8_lossy(&local.name).into_owned()
+            }
+            None => temp(register),
+        }
+    }
+
+    fn conditional(&mut self, st: &mut State, pc: usize, end: usize, exit: usize) -> Result<usize> {
+        let i = self.f.code[pc];
+        let op = i.opcode().unwrap();
+        let jmp = self
+            .f
+            .code
+            .get(pc + 1)
+            .filter(|i| i.opcode() == Some(OpCode::Jmp))
+            .ok_or_else(|| format!("{op} at {pc} not followed by JMP"))?;
+        let target = jmp.target(pc + 1);
+
+        // `x = a == b` loads the result with a pair of LOADBOOLs
+        if matches!(op, OpCode::Eq | OpCode::Lt | OpCode::Le) && target == pc + 3 {
+            let loads = self.f.code.get(pc + 2..pc + 4).unwrap_or_default();
+            if let [f, t] = loads {
+                if f.opcode() == Some(OpCode::LoadBool)
+                    && t.opcode() == Some(OpCode::LoadBool)
+                    && f.a() == t.a()
+                    && (f.b(), f.c(), t.b(), t.c()) == (0, 1, 1, 0)
+                {
+                    let cond = self.jump_condition(st, pc)?;
+                    self.write(st, pc + 3, f.a(), cond, 1);
+                    return Ok(pc + 4);
+                }
+            }
+        }
+
+        // `x = a and b` / `x = a or b`
+        if let Some(next) = self.logical(st, pc, target)? {
+            return Ok(next);
+        }
+        if op == OpCode::TestSet {
+            return self.test_set(st, pc, target, end);
+        }
+
+        let (cond, body, target) = self.condition_chain(st, pc)?;
+
+        if self.loops.last().is_some_and(|l| l.exit == target) {
+            self.flush_all(st);
+            self.emit(st, Stmt::If(cond.not(), vec![Stmt::Break], vec![]));
+            return Ok(body);
+        }
+
+        let then_end = match target {
+            t if t == end || t == exit => end,
+            t if t > body && t < end => t,
+            t => {
+                return Err(format!(
+                    "conditional jump from {pc} to {t} leaves its block"
+                ))
+            }
+        };
+
+        let mut otherwise = None;
+        if then_end < end && then_end > body {
+            let last = self.f.code[then_end - 1];
+            if last.opcode() == Some(OpCode::Jmp) {
+                let to = last.target(then_end - 1);
+                let breaks = self.loops.last().is_some_and(|l| l.exit == to);
+                if !breaks && (to == end || to == exit) {
+                    otherwise = Some(end);
+                } else if !breaks && to > then_end && to < end {
+                    otherwise = Some(to);
+                }
+            }
+        }
+
+        self.flush_all(st);
+        let next = otherwise.unwrap_or(then_end);
+        let child_exit = if next == end { exit } else { next };
+        let (then, otherwise) = match otherwise {
+            Some(else_end) => (
+                self.nested(st, body, then_end - 1, child_exit)?,
+                self.nested(st, then_end, else_end, child_exit)?,
+            ),
+            None => (self.nested(st, body, then_end, child_exit)?, vec![]),
+        };
+        self.emit(st, Stmt::If(cond, then, otherwise));
+        Ok(next)
+    }
+
+    /// Recognizes `a and b` and `a or b` computed into a register, returning where to continue.
+    fn logical(&mut self, st: &mut State, pc: usize, target: usize) -> Result<Option<usize>> {
+        let i = self.f.code[pc];
+        let (a, c) = (i.a(), i.c());
+
+        let mut trial = st.clone();
+        let left = match i.opcode() {
+            Some(OpCode::TestSet) => self.read(&mut trial, i.b(), pc),
+            Some(OpCode::Test) if self.f.local_at(a, pc).is_none() => {
+                match trial.pending.remove(&a) {
+                    Some((e, 1)) => e,
+                    _ => return Ok(None),
+                }
+            }
+            _ => return Ok(None),
+        };
+        if target <= pc + 2 || target > self.f.code.len() {
+            return Ok(None);
+        }
+
+        // in `a and b or c` the test of `b` jumps past `c`
+        let mut end = target;
+        if let [test, jmp] = &self.f.code[target - 2..target] {
+            let escapes = jmp.opcode() == Some(OpCode::Jmp) && jmp.target(target - 1) > target;
+            if target - 2 > pc + 1
+                && escapes
+                && matches!(test.opcode(), Some(OpCode::Test) | Some(OpCode::TestSet))
+                && test.a() == a
+            {
+                end = target - 2;
+            }
+        }
+
+        let before = trial.clone();
+        let stmts = trial.stmts.len();
+        if self.block(&mut trial, pc + 2, end, end).is_err() || trial.stmts.len() != stmts {
+            return Ok(None);
+        }
+        let Some((right, 1)) = trial.pending.remove(&a) else {
+            return Ok(None);
+        };
+        let mut untouched = before.pending;
+        untouched.remove(&a);
+        if trial.pending != untouched {
+            return Ok(None);
+        }
+
+        let value = match c {
+            0 => Expr::binary(BinOp::And, left, right),
+            _ => Expr::binary(BinOp::Or, left, right),
+        };
+        *st = trial;
+        self.write(st, end - 1, a, value, 1);
+        Ok(Some(end))
+    }
+
+    /// `TESTSET` the decompiler couldn't turn into an expression, written as the equivalent
+    /// `if b then a = b else ... end`.
+    fn test_set(&mut self, st: &mut State, pc: usize, target: usize, end: usize) -> Result<usize> {
+        let i = self.f.code[pc];
+        if target <= pc + 1 || target > end {
+            return Err(format!("unsupported TESTSET at {pc}"));
+        }
+        let mut value = self.read(st, i.b(), pc);
+        if value.has_call() {
+            self.flush_value(st, i.b(), value);
+            value = Expr::Name(temp(i.b()));
+        }
+        let cond = match i.c() {
+            0 => value.clone().not(),
+            _ => value.clone(),
+        };
+        self.flush_all(st);
+        let target_name = Expr::Name(self.name(st, i.a(), pc + 1));
+        let otherwise = self.nested(st, pc + 2, target, target)?;
+        let then = vec![Stmt::Assign(vec![target_name], vec![value])];
+        self.emit(st, Stmt::If(cond, then, otherwise));
+        Ok(target)
+    }
+
+    /// Condition under which the `JMP` after the test at `pc` is taken.
+    fn jump_condition(&mut self, st: &mut State, pc: usize) -> Result<Expr> {
+        let i = self.f.code[pc];
+        let (a, b, c) = (i.a(), i.b(), i.c());
+        let compare = |this: &mut Self, st: &mut State, op| -> Result<Expr> {
+            let left = this.rk(st, b, pc)?;
+            let right = this.rk(st, c, pc)?;
+            let cmp = Expr::binary(op, left, right);
+            Ok(match a {
+                0 => cmp.not(),
+                _ => cmp,
+            })
+        };
+
+        match i.opcode() {
+            Some(OpCode::Eq) => compare(self, st, BinOp::Eq),
+            Some(OpCode::Lt) => compare(self, st, BinOp::Lt),
+            Some(OpCode::Le) => compare(self, st, BinOp::Le),
+            Some(OpCode::Test) => {
+                let e = self.read(st, a, pc);
+                Ok(match c {
+                    0 => e.not(),
+                    _ => e,
+                })
+            }
+            op => Err(format!("expected a test at {pc}, found {op:?}")),
+        }
+    }
+
+    /// Reads the tests jumping around a block, such as `a and (b or c)`, returning the condition
+    /// for entering it, where it starts and where the tests jump when it's skipped.
+    fn condition_chain(&mut self, st: &mut State, pc: usize) -> Result<(Expr, usize, usize)> {
+        struct Pair {
+            start: usize,
+            test: usize,
+            cond: Expr,
+            target: usize,
+            state: State,
+        }
+
+        let mut pairs = Vec::new();
+        let mut state = st.clone();
+        let (mut start, mut test) = (pc, pc);
+        loop {
+            let cond = self.jump_condition(&mut state, test)?;
+            let target = self.f.code[test + 1].target(test + 1);
+            pairs.push(Pair {
+                start,
+                test,
+                cond,
+                target,
+                state: state.clone(),
+            });
+
+            // straight line code computing the operands of the next test
+            start = test + 2;
+            let mut next = start;
+            let stmts = state.stmts.len();
+            let found = loop {
+                let Some(i) = self.f.code.get(next) else {
+                    break false;
+                };
+                let declares = self.f.locals.iter().any(|l| l.start as usize == next);
+                if self.headers.contains_key(&next) || declares || state.stmts.len() != stmts {
+                    break false;
+                }
+                match i.opcode() {
+                    Some(OpCode::Eq | OpCode::Lt | OpCode::Le | OpCode::Test) => {
+                        let jumps = self.f.code.get(next + 1).and_then(|i| i.opcode());
+                        let loads = self.f.code.get(next + 2).and_then(|i| i.opcode());
+                        break jumps == Some(OpCode::Jmp) && loads != Some(OpCode::LoadBool);
+                    }
+                    Some(
+                        OpCode::Move
+                        | OpCode::LoadK
+                        | OpCode::LoadNil
+                        | OpCode::GetUpval
+                        | OpCode::GetGlobal
+                        | OpCode::GetTable
+                        | OpCode::SelfOp
+                        | OpCode::Add
+                        | OpCode::Sub
+                        | OpCode::Mul
+                        | OpCode::Div
+                        | OpCode::Mod
+                        | OpCode::Pow
+                        | OpCode::Unm
+                        | OpCode::Not
+                        | OpCode::Len
+                        | OpCode::Concat,
+                    ) => {}
+                    Some(OpCode::LoadBool) if i.c() == 0 => {}
+                    Some(OpCode::Call) if i.c() != 1 => {}
+                    _ => break false,
+                }
+                if self
+                    .instruction(&mut state, next, self.f.code.len(), self.f.code.len())
+                    .is_err()
+                {
+                    break false;
+                }
+                next += 1;
+            };
+            if !found || state.stmts.len() != stmts {
+                break;
+            }
+            test = next;
+        }
+
+        // the longest run of tests only jumping to the block, past it or to one another
+        let valid = |n: usize| {
+            let body = pairs[n - 1].test + 2;
+            let skip = pairs[n - 1].target;
+            let forward = skip > body || self.loops.last().is_some_and(|l| l.next == skip);
+            forward
+                && pairs[..n].iter().enumerate().all(|(i, pair)| {
+                    pair.target == body
+                        || pair.target == skip
+                        || pairs[i + 1..n].iter().any(|p| p.start == pair.target)
+                })
+        };
+        let n = (1..=pairs.len())
+            .rev()
+            .find(|&n| valid(n))
+            .ok_or_else(|| format!("unstructured condition at {pc}"))?;
+
+        let body = pairs[n - 1].test + 2;
+        let skip = pairs[n - 1].target;
+        let positions = pairs[..n].iter().map(|p| p.start).collect::<Vec<_>>();
+        let conds = pairs[..n]
+            .iter()
+            .map(|p| (p.cond.clone(), p.target))
+            .collect::<Vec<_>>();
+        let cond = reach(&positions, &conds, 0, n, body, body, skip)
+            .ok_or_else(|| format!("unstructured condition at {pc}"))?;
+
+        *st = pairs.swap_remove(n - 1).state;
+        Ok((cond, body, skip))
+    }
+
+    fn call(&mut self, st: &mut State, pc: usize, a: u32, b: u32) -> Expr {
+        let args = self.list(st, pc, a + 1, b);
+        let method = st.methods.remove(&a);
+        let function = self.read(st, a, pc);
+        match (method, function) {
+            (true, Expr::Index(object, key)) => match *key {
+                Expr::String(name) if is_identifier(&name) && args.first() == Some(&object) => {
+                    let name = String::from_utf8_lossy(&name).into_owned();
+                    Expr::Method(object, name, args[1..].to_vec())
+                }
+                key => Expr::Call(Box::new(Expr::Index(object, Box::new(key))), args),
+            },
+            (_, function) => Expr::Call(Box::new(function), args),
+        }
+    }
+
+    /// Reads `count - 1` registers from `first`, or up to the open multiple results when 0.
+    fn list(&mut self, st: &mut State, pc: usize, first: u32, count: u32) -> Vec<Expr> {
+        if count == 0 {
+            let Some(top) = st
+                .pending
+                .iter()
+                .find(|(&r, (_, width))| r >= first && *width == 0)
+                .map(|(&r, _)| r)
+            else {
+                return vec![];
+            };
+            return (first..=top).map(|r| self.read(st, r, pc)).collect();
+        }
+
+        let mut values = (first..first + count - 1)
+            .map(|r| self.read(st, r, pc))
+            .collect::<Vec<_>>();
+        if let Some(last) = values.pop() {
+            // a single result in the last position has to stay single
+            values.push(match last.is_multi() {
+                true => Expr::Paren(Box::new(last)),
+                false => last,
+            });
+        }
+        values
+    }
+
+    fn rk(&mut self, st: &mut State, operand: u32, pc: usize) -> Result<Expr> {
+        match RK::from(operand) {
+            RK::Register(r) => Ok(self.read(st, r, pc)),
+            RK::Constant(k) => self.constant(k),
+        }
+    }
+
+    fn constant(&self, index: u32) -> Result<Expr> {
+        let constant = self
+            .f
+            .constants
+            .get(index as usize)
+            .ok_or_else(|| format!("missing constant {index}"))?;
+        Ok(match constant {
+            Constant::Nil => Expr::Nil,
+            Constant::Boolean(b) => Expr::Boolean(*b),
+            Constant::Number(n) => Expr::Number(*n),
+            Constant::String(s) => Expr::String(s.clone()),
+        })
+    }
+
+    fn global(&self, index: u32) -> Result<Expr> {
+        Ok(match self.constant(index)? {
+            Expr::String(name) if is_identifier(&name) => {
+                Expr::Name(String::from_utf8_lossy(&name).into_owned())
+            }
+            key => Expr::Index(Box::new(Expr::Name("_G".into())), Box::new(key)),
+        })
+    }
+
+    fn upvalue(&self, index: u32) -> String {
+        self.upvalues
+            .get(index as usize)
+            .cloned()
+            .unwrap_or_else(|| format!("u{index}"))
+    }
+
+    fn name(&self, st: &mut State, register: u32, pc: usize) -> String {
+        match self.f.local_at(register, pc) {
+            Some((_, local)) => String::from_utf8_lossy(&local.name).into_owned(),
+            None => {
+                st.temps.insert(register);
+                temp(register)
+            }
+        }
+    }
+
+    fn read(&mut self, st: &mut State, register: u32, pc: usize) -> Expr {
+        if let Some((&base, &(_, width))) = st.pending.range(..register).next_back() {
+            if width > 1 && register < base + width {
+                // one of several results, give them names first
+                self.flush(st, base);
+            }
+        }
+        match st.pending.remove(&register) {
+            Some((e, width)) if width > 1 => {
+                st.pending.insert(register, (e, width));
+                self.flush(st, register);
+                Expr::Name(self.name(st, register, pc))
+            }
+            Some((e, _)) => e,
+            None => Expr::Name(self.name(st, register, pc)),
+        }
+    }
+
+    /// Stores the result of the instruction at `pc` in `register` and the `width - 1` following.
+    fn write(&mut self, st: &mut State, pc: usize, register: u32, e: Expr, width: u32) {
+        for r in register..register + width.max(1) {
+            if let Some((old, _)) = st.pending.remove(&r) {
+                if old.has_call() {
+                    st.pending.insert(r, (old, 1));
+                    self.flush(st, r);
+                }
+            }
+            st.methods.remove(&r);
+        }
+
+        // a local already in scope is assigned right away, anything else waits for its use or
+        // the declaration of its local
+        let targets = (register..register + width.max(1))
+            .map(|r| {
+                self.f
+                    .local_at(r, pc + 1)
+                    .filter(|(_, local)| local.start as usize <= pc)
+            })
+            .collect::<Vec<_>>();
+        if width > 0 && targets.iter().all(Option::is_some) {
+            let targets = targets
+                .into_iter()
+                .flatten()
+                .map(|(_, local)| Expr::Name(String::from_utf8_lossy(&local.name).into_owned()))
+                .collect();
+            self.assign(st, targets, vec![e]);
+        } else {
+            st.pending.insert(register, (e, width));
+        }
+    }
+
+    /// Declares the locals coming into scope at `pc` with the values waiting in their registers.
+    fn declare(&mut self, st: &mut State, pc: usize) {
+        let active = self
+            .f
+            .locals
+            .iter()
+            .enumerate()
+            .filter(|(_, l)| l.start as usize <= pc && pc < l.end as usize)
+            .map(|(index, _)| index)
+            .collect::<Vec<_>>();
+        let new = active
+            .iter()
+            .enumerate()
+            .filter(|(_, &index)| {
+                let local = &self.f.locals[index];
+                local.start as usize == pc
+                    && !st.declared.contains(&index)
+                    && !local.name.starts_with(b"(")
+            })
+            .map(|(register, &index)| (register as u32, index))
+            .collect::<Vec<_>>();
+        if new.is_empty() {
+            return;
+        }
+
+        let mut names = Vec::new();
+        let mut exprs = Vec::new();
+        let mut assigned = false;
+        let mut covered = new[0].0;
+        for &(register, index) in &new {
+            st.declared.insert(index);
+            names.push(String::from_utf8_lossy(&self.f.locals[index].name).into_owned());
+            if register < covered {
+                continue;
+            }
+            match st.pending.remove(&register) {
+                Some((e, width)) => {
+                    assigned = true;
+                    exprs.push(e);
+                    covered = match width {
+                        0 => u32::MAX,
+                        width => register + width,
+                    };
+                }
+                None => {
+                    assigned |= st.temps.contains(&register);
+                    exprs.push(Expr::Name(temp(register)));
+                    covered = register + 1;
+                }
+            }
+        }
+
+        // registers nobody wrote to are still nil
+        if !assigned {
+            exprs.clear();
+        }
+        while exprs.last() == Some(&Expr::Nil) {
+            exprs.pop();
+        }
+        self.emit(st, Stmt::Local(names, exprs));
+    }
+
+    fn assign(&mut self, st: &mut State, targets: Vec<Expr>, values: Vec<Expr>) {
+        // values waiting to be used must be read before they are overwritten
+        let stale = st
+            .pending
+            .iter()
+            .filter(|(_, (e, _))| {
+                targets.iter().any(|target| match target {
+                    Expr::Name(name) => e.references(name),
+                    _ => e.has_index() || e.has_call(),
+                })
+            })
+            .map(|(&r, _)| r)
+            .collect::<Vec<_>>();
+        for r in stale {
+            self.flush(st, r);
+        }
+        self.emit(st, Stmt::Assign(targets, values));
+    }
+
+    fn emit(&mut self, st: &mut State, stmt: Stmt) {
+        let calls = st
+            .pending
+            .iter()
+            .filter(|(_, (e, _))| e.has_call())
+            .map(|(&r, _)| r)
+            .collect::<Vec<_>>();
+        for r in calls {
+            self.flush(st, r);
+        }
+
+        // `local f` followed by `f = function` is a `local function f`
+        if let (Some(Stmt::Local(names, exprs)), Stmt::Assign(targets, values)) =
+            (st.stmts.last_mut(), &stmt)
+        {
+            if let ([name], [], [Expr::Name(target)], [Expr::Function(_)]) = (
+                names.as_slice(),
+                exprs.as_slice(),
+                targets.as_slice(),
+                values.as_slice(),
+            ) {
+                if name == target {
+                    exprs.push(values[0].clone());
+                    return;
+                }
+            }
+        }
+        st.stmts.push(stmt);
+    }
+
+    /// Assigns the value waiting in `register` to its temporary.
+    fn flush(&mut self, st: &mut State, register: u32) {
+        let Some((e, width)) = st.pending.remove(&register) else {
+            return;
+        };
+        let targets = (register..register + width.max(1))
+            .map(|r| {
+                st.temps.insert(r);
+                Expr::Name(temp(r))
+            })
+            .collect();
+        self.assign(st, targets, vec![e]);
+    }
+
+    fn flush_value(&mut self, st: &mut State, register: u32, e: Expr) {
+        st.pending.insert(register, (e, 1));
+        self.flush(st, register);
+    }
+
+    fn flush_all(&mut self, st: &mut State) {
+        while let Some((&r, _)) = st.pending.first_key_value() {
+            self.flush(st, r);
+        }
+        st.methods.clear();
+    }
+}
+
+/// Condition for reaching `yes` from the test starting at `positions[from]`, given each test's
+/// jump condition and target. Tests fall through to the next one, the last to `fall`.
+fn reach(
+    positions: &[usize],
+    conds: &[(Expr, usize)],
+    from: usize,
+    to: usize,
+    fall: usize,
+    yes: usize,
+    no: usize,
+) -> Option<Expr> {
+    let at = |position: usize, to: usize, yes: usize, no: usize| -> Option<Expr> {
+        if position == yes {
+            return Some(Expr::Boolean(true));
+        }
+        if position == no {
+            return Some(Expr::Boolean(false));
+        }
+        let index = positions[..to].iter().position(|&p| p == position)?;
+        reach(positions, conds, index, to, fall, yes, no)
+    };
+
+    let (jc, target) = conds[from].clone();
+    let next = positions.get(from + 1).copied().filter(|_| from + 1 < to);
+    let next = next.unwrap_or(if to == conds.len() {
+        fall
+    } else {
+        positions[to]
+    });
+    let rest = at(next, to, yes, no)?;
+
+    if target == yes {
+        return Some(jc.or(rest));
+    }
+    if target == no {
+        return Some(jc.not().and(rest));
+    }
+
+    // jumps to a later test, split the tests in between off as their own condition
+    let k = positions[..to].iter().position(|&p| p == target)?;
+    let after = at(target, to, yes, no)?;
+    if let Some(between) = at(next, k, target, no) {
+        return Some(jc.or(between).and(after));
+    }
+    let between = at(next, k, yes, target)?;
+    Some(jc.not().and(between).or(after))
+}
+
+fn temp(register: u32) -> String {
+    format!("r{register}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use OpCode::*;
+
+    fn abc(op: OpCode, a: u32, b: u32, c: u32) -> u32 {
+        op as u32 | a << 6 | c << 14 | b << 23
+    }
+
+    fn abx(op: OpCode, a: u32, bx: u32) -> u32 {
+        op as u32 | a << 6 | bx << 14
+    }
+
+    fn jmp(from: usize, to: usize) -> u32 {
+        abx(Jmp, 0, (to as i32 - from as i32 - 1 + (1 << 17) - 1) as u32)
+    }
+
+    fn string(out: &mut Vec<u8>, s: &str) {
+        out.extend((s.len() as u64 + 1).to_le_bytes());
+        out.extend(s.as_bytes());
+        out.push(0);
+    }
+
+    fn chunk(code: &[u32], constants: &[Constant], locals: &[(&str, u32, u32)]) -> Vec<u8> {
+        let mut out = b"\x1bLua\x51\x00\x01\x04\x08\x04\x08\x00".to_vec();
+        string(&mut out, "@test.lua");
+        out.extend([0u8; 8]);
+        out.extend([0, 0, VARARG_ISVARARG, 16]);
+        out.extend((code.len() as u32).to_le_bytes());
+        code.iter().for_each(|i| out.extend(i.to_le_bytes()));
+        out.extend((constants.len() as u32).to_le_bytes());
+        for constant in constants {
+            match constant {
+                Constant::Number(n) => {
+                    out.push(3);
+                    out.extend(n.to_le_bytes());
+                }
+                Constant::String(s) => {
+                    out.push(4);
+                    string(&mut out, std::str::from_utf8(s).unwrap());
+                }
+                _ => unimplemented!(),
+            }
+        }
+        out.extend(0u32.to_le_bytes());
+        out.extend(0u32.to_le_bytes());
+        out.extend((locals.len() as u32).to_le_bytes());
+        for (name, start, end) in locals {
+            string(&mut out, name);
+            out.extend(start.to_le_bytes());
+            out.extend(end.to_le_bytes());
+        }
+        out.extend(0u32.to_le_bytes());
+        out
+    }
+
+    fn k(s: &str) -> Constant {
+        Constant::String(s.as_bytes().to_vec())
+    }
+
+    const K: u32 = 256;
+
+    #[test]
+    fn locals_and_calls() {
+        let code = [
+            abx(LoadK, 0, 0),
+            abx(GetGlobal, 1, 1),
+            abc(Add, 2, 0, K + 2),
+            abc(Call, 1, 2, 1),
+            abc(Return, 0, 1, 0),
+        ];
+        let constants = [Constant::Number(1.0), k("print"), Constant::Number(2.0)];
+        let source = decompile(&chunk(&code, &constants, &[("a", 1, 5)])).unwrap();
+        assert_eq!(source, "local a = 1\nprint(a + 2)\n");
+    }
+
+    #[test]
+    fn if_else() {
+        let code = [
+            abx(GetGlobal, 0, 0),
+            abc(Test, 0, 0, 0),
+            jmp(2, 6),
+            abx(GetGlobal, 0, 1),
+            abc(Call, 0, 1, 1),
+            jmp(5, 8),
+            abx(GetGlobal, 0, 2),
+            abc(Call, 0, 1, 1),
+            abc(Return, 0, 1, 0),
+        ];
+        let source = decompile(&chunk(&code, &[k("x"), k("y"), k("z")], &[])).unwrap();
+        assert_eq!(source, "if x then\n  y()\nelse\n  z()\nend\n");
+    }
+
+    #[test]
+    fn numeric_for() {
+        let code = [
+            abx(LoadK, 0, 0),
+            abx(LoadK, 1, 1),
+            abx(LoadK, 2, 0),
+            abx(ForPrep, 0, (3 + (1 << 17) - 1) as u32),
+            abx(GetGlobal, 4, 2),
+            abc(Move, 5, 3, 0),
+            abc(Call, 4, 2, 1),
+            abx(ForLoop, 0, (-4 + (1 << 17) - 1) as u32),
+            abc(Return, 0, 1, 0),
+        ];
+        let constants = [Constant::Number(1.0), Constant::Number(10.0), k("print")];
+        let locals = [
+            ("(for index)", 3, 8),
+            ("(for limit)", 3, 8),
+            ("(for step)", 3, 8),
+            ("i", 4, 7),
+        ];
+        let source = decompile(&chunk(&code, &constants, &locals)).unwrap();
+        assert_eq!(source, "for i = 1, 10 do\n  print(i)\nend\n");
+    }
+
+    #[test]
+    fn while_and() {
+        let code = [
+            abx(GetGlobal, 0, 0),
+            abc(Test, 0, 0, 0),
+            jmp(2, 9),
+            abx(GetGlobal, 0, 1),
+            abc(Test, 0, 0, 0),
+            jmp(5, 9),
+            abx(GetGlobal, 0, 2),
+            abc(Call, 0, 1, 1),
+            jmp(8, 0),
+            abc(Return, 0, 1, 0),
+        ];
+        let source = decompile(&chunk(&code, &[k("a"), k("b"), k("f")], &[])).unwrap();
+        assert_eq!(source, "while a and b do\n  f()\nend\n");
+    }
+
+    #[test]
+    fn and_or_value() {
+        let code = [
+            abx(GetGlobal, 0, 0),
+            abc(Test, 0, 0, 0),
+            jmp(2, 6),
+            abx(GetGlobal, 0, 1),
+            abc(Test, 0, 0, 1),
+            jmp(5, 7),
+            abx(GetGlobal, 0, 2),
+            abc(Return, 0, 1, 0),
+        ];
+        let constants = [k("a"), k("b"), k("c")];
+        let source = decompile(&chunk(&code, &constants, &[("x", 7, 8)])).unwrap();
+        assert_eq!(source, "local x = a and b or c\n");
+    }
+
+    #[test]
+    fn truncated_strings_fail() {
+        let mut out = b"\x1bLua\x51\x00\x01\x04\x08\x04\x08\x00".to_vec();
+        out.extend((1u64 << 40).to_le_bytes());
+        out.extend(b"@test.lua");
+        let error = decompile(&out).unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::UnexpectedEof);
+    }
+}
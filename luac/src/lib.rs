@@ -0,0 +1,236 @@
+use std::io::{self, Read};
+
+mod asm;
+mod ast;
+mod decompile;
+mod opcode;
+
+pub use asm::disassemble;
+pub use decompile::decompile;
+pub use opcode::{Instruction, OpCode};
+
+const SIGNATURE: &[u8; 4] = b"\x1bLua";
+
+/// A precompiled Lua 5.1 chunk as written by `luac` or `string.dump`.
+#[derive(Debug, Clone)]
+pub struct Bytecode {
+    pub header: Header,
+    pub main: Function,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Header {
+    pub version: u8,
+    pub format: u8,
+    pub little_endian: bool,
+    pub int_size: u8,
+    pub size_t_size: u8,
+    pub instruction_size: u8,
+    pub number_size: u8,
+    pub integral: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct Function {
+    pub source: Option<Vec<u8>>,
+    pub line_defined: u32,
+    pub last_line_defined: u32,
+    pub upvalue_count: u8,
+    pub param_count: u8,
+    pub is_vararg: u8,
+    pub max_stack_size: u8,
+    pub code: Vec<Instruction>,
+    pub constants: Vec<Constant>,
+    pub prototypes: Vec<Function>,
+    pub line_info: Vec<u32>,
+    pub locals: Vec<Local>,
+    pub upvalues: Vec<Vec<u8>>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Constant {
+    Nil,
+    Boolean(bool),
+    Number(f64),
+    String(Vec<u8>),
+}
+
+/// Debug information for a local variable, live for the instructions in `start..end`.
+#[derive(Debug, Clone)]
+pub struct Local {
+    pub name: Vec<u8>,
+    pub start: u32,
+    pub end: u32,
+}
+
+impl Bytecode {
+    pub fn from_reader<R: Read>(mut reader: R) -> io::Result<Self> {
+        let mut buf = [0u8; 12];
+        reader.read_exact(&mut buf)?;
+        if &buf[..4] != SIGNATURE {
+            return Err(invalid("missing Lua signature"));
+        }
+
+        let header = Header {
+            version: buf[4],
+            format: buf[5],
+            little_endian: buf[6] == 1,
+            int_size: buf[7],
+            size_t_size: buf[8],
+            instruction_size: buf[9],
+            number_size: buf[10],
+            integral: buf[11] == 1,
+        };
+        if header.version != 0x51 {
+            return Err(invalid(format!(
+                "unsupported Lua version {:x}.{:x}",
+                header.version >> 4,
+                header.version & 0xF
+            )));
+        }
+        if header.instruction_size != 4 {
+            return Err(invalid("unsupported instruction size"));
+        }
+
+        let main = Parser { reader, header }.function()?;
+        Ok(Self { header, main })
+    }
+}
+
+impl Function {
+    /// Name of the local living in `register` at `pc`, if the chunk kept its debug information.
+    pub fn local_at(&self, register: u32, pc: usize) -> Option<(usize, &Local)> {
+        self.locals
+            .iter()
+            .enumerate()
+            .filter(|(_, local)| local.start as usize <= pc && pc < local.end as usize)
+            .nth(register as usize)
+    }
+}
+
+struct Parser<R> {
+    reader: R,
+    header: Header,
+}
+
+impl<R: Read> Parser<R> {
+    fn function(&mut self) -> io::Result<Function> {
+        let source = self.string()?;
+        let line_defined = self.int()?;
+        let last_line_defined = self.int()?;
+        let [upvalue_count, param_count, is_vararg, max_stack_size] = self.bytes()?;
+
+        let code = self
+            .vec(|p| p.unsigned(4))?
+            .into_iter()
+            .map(|raw| Instruction(raw as u32))
+            .collect();
+
+        let constants = self.vec(|p| {
+            let [kind] = p.bytes()?;
+            Ok(match kind {
+                0 => Constant::Nil,
+                1 => Constant::Boolean(p.bytes::<1>()?[0] != 0),
+                3 => Constant::Number(p.number()?),
+                4 => Constant::String(p.string()?.unwrap_or_default()),
+                _ => return Err(invalid(format!("unknown constant type {kind}"))),
+            })
+        })?;
+        let prototypes = self.vec(|p| p.function())?;
+
+        let line_info = self.vec(|p| p.int())?;
+        let locals = self.vec(|p| {
+            Ok(Local {
+                name: p.string()?.unwrap_or_default(),
+                start: p.int()?,
+                end: p.int()?,
+            })
+        })?;
+        let upvalues = self.vec(|p| Ok(p.string()?.unwrap_or_default()))?;
+
+        Ok(Function {
+            source,
+            line_defined,
+            last_line_defined,
+            upvalue_count,
+            param_count,
+            is_vararg,
+            max_stack_size,
+            code,
+            constants,
+            prototypes,
+            line_info,
+            locals,
+            upvalues,
+        })
+    }
+
+    fn bytes<const N: usize>(&mut self) -> io::Result<[u8; N]> {
+        let mut buf = [0u8; N];
+        self.reader.read_exact(&mut buf)?;
+        Ok(buf)
+    }
+
+    fn unsigned(&mut self, size: u8) -> io::Result<u64> {
+        if size > 8 {
+            return Err(invalid(format!("unsupported integer size {size}")));
+        }
+        let mut buf = [0u8; 8];
+        let bytes = &mut buf[..size as usize];
+        self.reader.read_exact(bytes)?;
+        if !self.header.little_endian {
+            bytes.reverse();
+        }
+        Ok(u64::from_le_bytes(buf))
+    }
+
+    fn int(&mut self) -> io::Result<u32> {
+        let size = self.header.int_size;
+        Ok(self.unsigned(size)? as u32)
+    }
+
+    fn number(&mut self) -> io::Result<f64> {
+        let size = self.header.number_size;
+        let raw = self.unsigned(size)?;
+        Ok(match (self.header.integral, size) {
+            (true, _) => raw as i64 as f64,
+            (false, 4) => f32::from_bits(raw as u32) as f64,
+            (false, 8) => f64::from_bits(raw),
+            _ => return Err(invalid(format!("unsupported number size {size}"))),
+        })
+    }
+
+    fn string(&mut self) -> io::Result<Option<Vec<u8>>> {
+        let size = self.header.size_t_size;
+        let len = self.unsigned(size)? as usize;
+        if len == 0 {
+            return Ok(None);
+        }
+        // read up to the length rather than allocating it, a corrupt chunk would abort
+        let mut buf = Vec::with_capacity(len.min(1 << 16));
+        (&mut self.reader).take(len as u64).read_to_end(&mut buf)?;
+        if buf.len() != len {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                format!("string of {len} bytes runs past the end of the chunk"),
+            ));
+        }
+        // strings are stored with their terminating nul
+        buf.pop();
+        Ok(Some(buf))
+    }
+
+    fn vec<T>(&mut self, mut f: impl FnMut(&mut Self) -> io::Result<T>) -> io::Result<Vec<T>> {
+        let len = self.int()? as usize;
+        // don't trust the length for the allocation, a corrupt chunk would abort
+        let mut vec = Vec::with_capacity(len.min(1 << 16));
+        for _ in 0..len {
+            vec.push(f(self)?);
+        }
+        Ok(vec)
+    }
+}
+
+fn invalid<E: Into<Box<dyn std::error::Error + Send + Sync>>>(error: E) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, error)
+}
@@ -0,0 +1,201 @@
+use std::fmt;
+
+/// Number of `SETLIST` items flushed per instruction, `LFIELDS_PER_FLUSH` in `lopcodes.h`.
+pub const FIELDS_PER_FLUSH: u32 = 50;
+
+const MAX_ARG_SBX: i32 = (1 << 17) - 1;
+const BIT_RK: u32 = 1 << 8;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OpCode {
+    Move,
+    LoadK,
+    LoadBool,
+    LoadNil,
+    GetUpval,
+    GetGlobal,
+    GetTable,
+    SetGlobal,
+    SetUpval,
+    SetTable,
+    NewTable,
+    SelfOp,
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Mod,
+    Pow,
+    Unm,
+    Not,
+    Len,
+    Concat,
+    Jmp,
+    Eq,
+    Lt,
+    Le,
+    Test,
+    TestSet,
+    Call,
+    TailCall,
+    Return,
+    ForLoop,
+    ForPrep,
+    TForLoop,
+    SetList,
+    Close,
+    Closure,
+    VarArg,
+}
+
+const OPCODES: [OpCode; 38] = [
+    OpCode::Move,
+    OpCode::LoadK,
+    OpCode::LoadBool,
+    OpCode::LoadNil,
+    OpCode::GetUpval,
+    OpCode::GetGlobal,
+    OpCode::GetTable,
+    OpCode::SetGlobal,
+    OpCode::SetUpval,
+    OpCode::SetTable,
+    OpCode::NewTable,
+    OpCode::SelfOp,
+    OpCode::Add,
+    OpCode::Sub,
+    OpCode::Mul,
+    OpCode::Div,
+    OpCode::Mod,
+    OpCode::Pow,
+    OpCode::Unm,
+    OpCode::Not,
+    OpCode::Len,
+    OpCode::Concat,
+    OpCode::Jmp,
+    OpCode::Eq,
+    OpCode::Lt,
+    OpCode::Le,
+    OpCode::Test,
+    OpCode::TestSet,
+    OpCode::Call,
+    OpCode::TailCall,
+    OpCode::Return,
+    OpCode::ForLoop,
+    OpCode::ForPrep,
+    OpCode::TForLoop,
+    OpCode::SetList,
+    OpCode::Close,
+    OpCode::Closure,
+    OpCode::VarArg,
+];
+
+impl OpCode {
+    pub fn name(&self) -> &'static str {
+        match self {
+            OpCode::Move => "MOVE",
+            OpCode::LoadK => "LOADK",
+            OpCode::LoadBool => "LOADBOOL",
+            OpCode::LoadNil => "LOADNIL",
+            OpCode::GetUpval => "GETUPVAL",
+            OpCode::GetGlobal => "GETGLOBAL",
+            OpCode::GetTable => "GETTABLE",
+            OpCode::SetGlobal => "SETGLOBAL",
+            OpCode::SetUpval => "SETUPVAL",
+            OpCode::SetTable => "SETTABLE",
+            OpCode::NewTable => "NEWTABLE",
+            OpCode::SelfOp => "SELF",
+            OpCode::Add => "ADD",
+            OpCode::Sub => "SUB",
+            OpCode::Mul => "MUL",
+            OpCode::Div => "DIV",
+            OpCode::Mod => "MOD",
+            OpCode::Pow => "POW",
+            OpCode::Unm => "UNM",
+            OpCode::Not => "NOT",
+            OpCode::Len => "LEN",
+            OpCode::Concat => "CONCAT",
+            OpCode::Jmp => "JMP",
+            OpCode::Eq => "EQ",
+            OpCode::Lt => "LT",
+            OpCode::Le => "LE",
+            OpCode::Test => "TEST",
+            OpCode::TestSet => "TESTSET",
+            OpCode::Call => "CALL",
+            OpCode::TailCall => "TAILCALL",
+            OpCode::Return => "RETURN",
+            OpCode::ForLoop => "FORLOOP",
+            OpCode::ForPrep => "FORPREP",
+            OpCode::TForLoop => "TFORLOOP",
+            OpCode::SetList => "SETLIST",
+            OpCode::Close => "CLOSE",
+            OpCode::Closure => "CLOSURE",
+            OpCode::VarArg => "VARARG",
+        }
+    }
+
+    /// Conditional instructions skip the `JMP` following them.
+    pub fn is_test(&self) -> bool {
+        matches!(
+            self,
+            OpCode::Eq | OpCode::Lt | OpCode::Le | OpCode::Test | OpCode::TestSet
+        )
+    }
+}
+
+impl fmt::Display for OpCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// A raw Lua 5.1 instruction, `op:6 a:8 c:9 b:9` or `op:6 a:8 bx:18` from the low bits up.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Instruction(pub u32);
+
+impl Instruction {
+    pub fn opcode(&self) -> Option<OpCode> {
+        OPCODES.get((self.0 & 0x3F) as usize).copied()
+    }
+
+    pub fn a(&self) -> u32 {
+        (self.0 >> 6) & 0xFF
+    }
+
+    pub fn b(&self) -> u32 {
+        self.0 >> 23
+    }
+
+    pub fn c(&self) -> u32 {
+        (self.0 >> 14) & 0x1FF
+    }
+
+    pub fn bx(&self) -> u32 {
+        self.0 >> 14
+    }
+
+    pub fn sbx(&self) -> i32 {
+        self.bx() as i32 - MAX_ARG_SBX
+    }
+
+    /// Target of a jump at `pc`, relative to the following instruction.
+    pub fn target(&self, pc: usize) -> usize {
+        (pc as i64 + 1 + self.sbx() as i64) as usize
+    }
+}
+
+/// Register or constant operand.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RK {
+    Register(u32),
+    Constant(u32),
+}
+
+impl From<u32> for RK {
+    fn from(value: u32) -> Self {
+        if value & BIT_RK != 0 {
+            RK::Constant(value & !BIT_RK)
+        } else {
+            RK::Register(value)
+        }
+    }
+}
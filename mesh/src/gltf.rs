@@ -0,0 +1,312 @@
+use std::{collections::BTreeMap, f32::consts::FRAC_1_SQRT_2, io};
+
+use serde_json::{json, Value};
+
+use crate::{Bone, Mesh, Model};
+
+const FLOAT: u32 = 5126;
+const UNSIGNED_BYTE: u32 = 5121;
+const UNSIGNED_SHORT: u32 = 5123;
+const UNSIGNED_INT: u32 = 5125;
+const ARRAY_BUFFER: u32 = 34962;
+const ELEMENT_ARRAY_BUFFER: u32 = 34963;
+
+/// Writes `model` as a binary glTF 2.0 file, converting CryEngine's Z up to glTF's Y up with a
+/// root node. Subset material ids become materials named after the id.
+pub fn to_glb(model: &Model) -> io::Result<Vec<u8>> {
+    let mut gltf = Builder::default();
+
+    let mut nodes = vec![json!({
+        "name": "root",
+        "rotation": [-FRAC_1_SQRT_2, 0.0, 0.0, FRAC_1_SQRT_2],
+    })];
+    let mut children: BTreeMap<usize, Vec<usize>> = BTreeMap::new();
+
+    let skinned = !model.bones.is_empty();
+    let skin = skinned.then(|| gltf.skeleton(&model.bones, &mut nodes, &mut children));
+
+    let meshes = model
+        .meshes
+        .iter()
+        .map(|(id, mesh)| (*id, gltf.mesh(mesh, skinned)))
+        .collect::<BTreeMap<_, _>>();
+
+    let mesh_node = |(mesh, joints): (usize, bool), node: Value| {
+        let mut node = node;
+        node["mesh"] = json!(mesh);
+        if joints {
+            node["skin"] = json!(0);
+        }
+        node
+    };
+    if model.nodes.is_empty() {
+        for (id, mesh) in &meshes {
+            children.entry(0).or_default().push(nodes.len());
+            nodes.push(mesh_node(*mesh, json!({ "name": format!("mesh_{id}") })));
+        }
+    } else {
+        let indices = model
+            .nodes
+            .iter()
+            .enumerate()
+            .map(|(index, node)| (node.id, nodes.len() + index))
+            .collect::<BTreeMap<_, _>>();
+        for node in &model.nodes {
+            let parent = node
+                .parent
+                .and_then(|parent| indices.get(&parent).copied())
+                .unwrap_or(0);
+            children.entry(parent).or_default().push(nodes.len());
+
+            let value = json!({ "name": node.name, "matrix": node.transform });
+            nodes.push(match meshes.get(&node.object) {
+                Some(mesh) => mesh_node(*mesh, value),
+                None => value,
+            });
+        }
+    }
+    for (parent, children) in children {
+        nodes[parent]["children"] = json!(children);
+    }
+
+    let mut document = json!({
+        "asset": { "version": "2.0", "generator": "nwtools" },
+        "scene": 0,
+        "scenes": [{ "nodes": [0] }],
+        "nodes": nodes,
+        "meshes": gltf.meshes,
+        "materials": gltf
+            .materials
+            .iter()
+            .map(|id| json!({ "name": format!("material_{id}") }))
+            .collect::<Vec<_>>(),
+        "accessors": gltf.accessors,
+        "bufferViews": gltf.views,
+        "buffers": [{ "byteLength": gltf.bin.len() }],
+    });
+    if let Some(skin) = skin {
+        document["skins"] = json!([skin]);
+    }
+
+    glb(&serde_json::to_vec(&document)?, &gltf.bin)
+}
+
+#[derive(Default)]
+struct Builder {
+    bin: Vec<u8>,
+    views: Vec<Value>,
+    accessors: Vec<Value>,
+    meshes: Vec<Value>,
+    /// Subset material ids, indexed by glTF material.
+    materials: Vec<u32>,
+}
+
+impl Builder {
+    /// Appends `data` as a buffer view and adds `accessor` reading it, returning its index.
+    fn accessor(&mut self, data: &[u8], target: Option<u32>, mut accessor: Value) -> usize {
+        let mut view = json!({
+            "buffer": 0,
+            "byteOffset": self.bin.len(),
+            "byteLength": data.len(),
+        });
+        if let Some(target) = target {
+            view["target"] = json!(target);
+        }
+        self.bin.extend_from_slice(data);
+        self.bin.resize(self.bin.len().next_multiple_of(4), 0);
+
+        accessor["bufferView"] = json!(self.views.len());
+        self.views.push(view);
+        self.accessors.push(accessor);
+        self.accessors.len() - 1
+    }
+
+    fn vec3(&mut self, values: &[[f32; 3]], bounds: bool) -> usize {
+        let data = values.iter().flatten().flat_map(|v| v.to_le_bytes());
+        let mut accessor = json!({
+            "componentType": FLOAT,
+            "count": values.len(),
+            "type": "VEC3",
+        });
+        if bounds {
+            let mut min = [f32::MAX; 3];
+            let mut max = [f32::MIN; 3];
+            for value in values {
+                for i in 0..3 {
+                    min[i] = min[i].min(value[i]);
+                    max[i] = max[i].max(value[i]);
+                }
+            }
+            accessor["min"] = json!(min);
+            accessor["max"] = json!(max);
+        }
+        self.accessor(&data.collect::<Vec<_>>(), Some(ARRAY_BUFFER), accessor)
+    }
+
+    /// Adds `mesh`, returning its index and whether it has joints bound to the skeleton.
+    fn mesh(&mut self, mesh: &Mesh, skinned: bool) -> (usize, bool) {
+        let vertices = mesh.positions.len();
+        let mut attributes = json!({ "POSITION": self.vec3(&mesh.positions, true) });
+        if mesh.normals.len() == vertices {
+            attributes["NORMAL"] = json!(self.vec3(&mesh.normals, false));
+        }
+        if mesh.uvs.len() == vertices {
+            let data = mesh.uvs.iter().flatten().flat_map(|v| v.to_le_bytes());
+            let accessor = json!({ "componentType": FLOAT, "count": vertices, "type": "VEC2" });
+            attributes["TEXCOORD_0"] =
+                json!(self.accessor(&data.collect::<Vec<_>>(), Some(ARRAY_BUFFER), accessor));
+        }
+        let joints = skinned && mesh.skin.len() == vertices;
+        if joints {
+            let joints = mesh.skin.iter().flat_map(|(joints, _)| joints);
+            let accessor =
+                json!({ "componentType": UNSIGNED_SHORT, "count": vertices, "type": "VEC4" });
+            attributes["JOINTS_0"] = json!(self.accessor(
+                &joints.flat_map(|j| j.to_le_bytes()).collect::<Vec<_>>(),
+                Some(ARRAY_BUFFER),
+                accessor
+            ));
+
+            let weights = mesh.skin.iter().flat_map(|(_, weights)| *weights);
+            let accessor = json!({
+                "componentType": UNSIGNED_BYTE,
+                "normalized": true,
+                "count": vertices,
+                "type": "VEC4",
+            });
+            attributes["WEIGHTS_0"] =
+                json!(self.accessor(&weights.collect::<Vec<_>>(), Some(ARRAY_BUFFER), accessor));
+        }
+
+        let mut subsets = mesh
+            .subsets
+            .iter()
+            .map(|subset| {
+                let start = subset.first_index as usize;
+                let end = start + subset.index_count as usize;
+                (
+                    subset.material,
+                    mesh.indices.get(start..end).unwrap_or_default(),
+                )
+            })
+            .collect::<Vec<_>>();
+        if subsets.is_empty() {
+            subsets.push((0, mesh.indices.as_slice()));
+        }
+
+        let primitives = subsets
+            .into_iter()
+            .map(|(material, indices)| {
+                let data = indices.iter().flat_map(|i| i.to_le_bytes());
+                let accessor = json!({
+                    "componentType": UNSIGNED_INT,
+                    "count": indices.len(),
+                    "type": "SCALAR",
+                });
+                let indices = self.accessor(
+                    &data.collect::<Vec<_>>(),
+                    Some(ELEMENT_ARRAY_BUFFER),
+                    accessor,
+                );
+                json!({
+                    "attributes": attributes,
+                    "indices": indices,
+                    "material": self.material(material),
+                })
+            })
+            .collect::<Vec<_>>();
+
+        self.meshes.push(json!({ "primitives": primitives }));
+        (self.meshes.len() - 1, joints)
+    }
+
+    fn material(&mut self, id: u32) -> usize {
+        match self.materials.iter().position(|material| *material == id) {
+            Some(index) => index,
+            None => {
+                self.materials.push(id);
+                self.materials.len() - 1
+            }
+        }
+    }
+
+    /// Adds a joint node per bone, posed relative to its parent, and returns the skin.
+    fn skeleton(
+        &mut self,
+        bones: &[Bone],
+        nodes: &mut Vec<Value>,
+        children: &mut BTreeMap<usize, Vec<usize>>,
+    ) -> Value {
+        let first = nodes.len();
+        for bone in bones {
+            let (parent, local) = match bone.parent.and_then(|parent| bones.get(parent)) {
+                Some(parent) => (
+                    first + bone.parent.unwrap(),
+                    multiply(&parent.world_to_bone, &bone.bone_to_world),
+                ),
+                None => (0, bone.bone_to_world),
+            };
+            children.entry(parent).or_default().push(nodes.len());
+            nodes.push(json!({ "name": bone.name, "matrix": column_major(&local) }));
+        }
+
+        let data = bones
+            .iter()
+            .flat_map(|bone| column_major(&bone.world_to_bone))
+            .flat_map(|v| v.to_le_bytes());
+        let accessor = json!({ "componentType": FLOAT, "count": bones.len(), "type": "MAT4" });
+        let inverse_bind_matrices = self.accessor(&data.collect::<Vec<_>>(), None, accessor);
+
+        json!({
+            "joints": (first..first + bones.len()).collect::<Vec<_>>(),
+            "inverseBindMatrices": inverse_bind_matrices,
+            "skeleton": first,
+        })
+    }
+}
+
+/// `a * b` for row major 3x4 affine transforms.
+fn multiply(a: &[[f32; 4]; 3], b: &[[f32; 4]; 3]) -> [[f32; 4]; 3] {
+    let mut out = [[0f32; 4]; 3];
+    for (i, row) in out.iter_mut().enumerate() {
+        for (j, value) in row.iter_mut().enumerate() {
+            *value = (0..3).map(|k| a[i][k] * b[k][j]).sum::<f32>();
+        }
+        row[3] += a[i][3];
+    }
+    out
+}
+
+fn column_major(m: &[[f32; 4]; 3]) -> [f32; 16] {
+    [
+        m[0][0], m[1][0], m[2][0], 0.0, //
+        m[0][1], m[1][1], m[2][1], 0.0, //
+        m[0][2], m[1][2], m[2][2], 0.0, //
+        m[0][3], m[1][3], m[2][3], 1.0,
+    ]
+}
+
+/// Packs the json document and binary buffer into a `.glb` container.
+fn glb(json: &[u8], bin: &[u8]) -> io::Result<Vec<u8>> {
+    let json_len = json.len().next_multiple_of(4);
+    let bin_len = bin.len().next_multiple_of(4);
+    let total = 12 + 8 + json_len + 8 + bin_len;
+    let total = u32::try_from(total).map_err(|_| io::Error::other("mesh exceeds 4 GiB"))?;
+
+    let mut out = Vec::with_capacity(total as usize);
+    out.extend_from_slice(b"glTF");
+    out.extend(2u32.to_le_bytes());
+    out.extend(total.to_le_bytes());
+
+    out.extend((json_len as u32).to_le_bytes());
+    out.extend_from_slice(b"JSON");
+    out.extend_from_slice(json);
+    out.resize(out.len() + json_len - json.len(), b' ');
+
+    out.extend((bin_len as u32).to_le_bytes());
+    out.extend_from_slice(b"BIN\0");
+    out.extend_from_slice(bin);
+    out.resize(out.len() + bin_len - bin.len(), 0);
+
+    Ok(out)
+}
@@ -0,0 +1,515 @@
+//! CryEngine/Lumberyard chunk files (`.cgf`, `.cga`, `.skin`, `.chr`) and their export to glTF.
+
+use std::{collections::BTreeMap, io};
+
+mod gltf;
+
+pub use gltf::to_glb;
+
+const SIGNATURE: &[u8; 4] = b"CrCh";
+const VERSION: u32 = 0x746;
+/// Node translations are stored in centimeters, vertices in meters.
+const VERTEX_SCALE: f32 = 0.01;
+
+const CHUNK_MESH: u16 = 0x1000;
+const CHUNK_NODE: u16 = 0x100B;
+const CHUNK_DATA_STREAM: u16 = 0x1016;
+const CHUNK_MESH_SUBSETS: u16 = 0x1017;
+const CHUNK_COMPILED_BONES: u16 = 0x2000;
+
+const STREAM_POSITIONS: usize = 0;
+const STREAM_NORMALS: usize = 1;
+const STREAM_TEXCOORDS: usize = 2;
+const STREAM_INDICES: usize = 5;
+const STREAM_BONE_MAPPING: usize = 9;
+const STREAM_P3S_C4B_T2S: usize = 15;
+const STREAM_COUNT: usize = 16;
+
+const MESH_IS_EMPTY: u32 = 1;
+const COMPILED_BONE_SIZE: usize = 584;
+
+/// Whether `buf` starts like a chunk file.
+pub fn is_chunk_file(buf: &[u8]) -> bool {
+    buf.starts_with(SIGNATURE)
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Chunk {
+    pub kind: u16,
+    pub version: u16,
+    pub id: u32,
+    pub size: u32,
+    pub offset: u32,
+}
+
+/// The chunk table of a version 0x746 chunk file, borrowing the file contents.
+pub struct ChunkFile<'a> {
+    data: &'a [u8],
+    chunks: Vec<Chunk>,
+}
+
+impl<'a> ChunkFile<'a> {
+    pub fn from_bytes(data: &'a [u8]) -> io::Result<Self> {
+        let mut header = Reader::new(data);
+        if &header.bytes::<4>()? != SIGNATURE {
+            return Err(invalid("missing chunk file signature"));
+        }
+        let version = header.u32()?;
+        if version != VERSION {
+            return Err(invalid(format!(
+                "unsupported chunk file version {version:x}"
+            )));
+        }
+        let count = header.u32()?;
+        let offset = header.u32()?;
+
+        let mut table = Reader::new(data.get(offset as usize..).unwrap_or_default());
+        let chunks = (0..count)
+            .map(|_| {
+                Ok(Chunk {
+                    kind: table.u16()?,
+                    version: table.u16()?,
+                    id: table.u32()?,
+                    size: table.u32()?,
+                    offset: table.u32()?,
+                })
+            })
+            .collect::<io::Result<Vec<_>>>()?;
+
+        Ok(Self { data, chunks })
+    }
+
+    pub fn chunks(&self) -> &[Chunk] {
+        &self.chunks
+    }
+
+    fn by_id(&self, id: u32) -> Option<&Chunk> {
+        self.chunks.iter().find(|chunk| chunk.id == id)
+    }
+
+    fn reader(&self, chunk: &Chunk) -> io::Result<Reader<'a>> {
+        if chunk.version & 0x8000 != 0 {
+            return Err(invalid("big endian chunks are not supported"));
+        }
+        let start = chunk.offset as usize;
+        self.data
+            .get(start..start + chunk.size as usize)
+            .map(Reader::new)
+            .ok_or_else(|| invalid(format!("chunk {} is out of bounds", chunk.id)))
+    }
+
+    fn stream(&self, id: u32) -> io::Result<Option<Stream<'a>>> {
+        let Some(chunk) = self
+            .by_id(id)
+            .filter(|chunk| chunk.kind == CHUNK_DATA_STREAM)
+        else {
+            return Ok(None);
+        };
+        let mut reader = self.reader(chunk)?;
+        let _flags = reader.u32()?;
+        let _kind = reader.u32()?;
+        if chunk.version == 0x801 {
+            let _index = reader.u32()?;
+        }
+        let count = reader.u32()? as usize;
+        let element_size = reader.u32()? as usize;
+        if element_size == 0 {
+            return Err(invalid(format!("stream {id} has no element size")));
+        }
+        reader.skip(8)?;
+        let data = reader.slice(count * element_size)?;
+
+        Ok(Some(Stream {
+            count,
+            element_size,
+            data,
+        }))
+    }
+}
+
+struct Stream<'a> {
+    count: usize,
+    element_size: usize,
+    data: &'a [u8],
+}
+
+impl Stream<'_> {
+    fn elements(&self) -> impl Iterator<Item = Reader<'_>> {
+        self.data.chunks_exact(self.element_size).map(Reader::new)
+    }
+}
+
+/// The geometry, node hierarchy and skeleton of a chunk file.
+#[derive(Debug, Default)]
+pub struct Model {
+    pub nodes: Vec<Node>,
+    /// Meshes keyed by their chunk id, which nodes refer to.
+    pub meshes: BTreeMap<u32, Mesh>,
+    pub bones: Vec<Bone>,
+}
+
+#[derive(Debug)]
+pub struct Node {
+    pub id: u32,
+    pub name: String,
+    /// Chunk id of the node's mesh, or of a helper for nodes without geometry.
+    pub object: u32,
+    pub parent: Option<u32>,
+    pub material: i32,
+    /// Column major local transform, in meters.
+    pub transform: [f32; 16],
+}
+
+#[derive(Debug, Default)]
+pub struct Mesh {
+    pub positions: Vec<[f32; 3]>,
+    pub normals: Vec<[f32; 3]>,
+    pub uvs: Vec<[f32; 2]>,
+    pub indices: Vec<u32>,
+    pub subsets: Vec<Subset>,
+    /// Up to four bone indices and weights (summing to 255) per vertex.
+    pub skin: Vec<([u16; 4], [u8; 4])>,
+}
+
+/// A range of indices drawn with one material.
+#[derive(Debug, Clone, Copy)]
+pub struct Subset {
+    pub first_index: u32,
+    pub index_count: u32,
+    pub material: u32,
+}
+
+#[derive(Debug)]
+pub struct Bone {
+    pub name: String,
+    pub parent: Option<usize>,
+    /// Row major 3x4 transforms between world and bone space in the bind pose.
+    pub world_to_bone: [[f32; 4]; 3],
+    pub bone_to_world: [[f32; 4]; 3],
+}
+
+impl Model {
+    pub fn from_bytes(data: &[u8]) -> io::Result<Self> {
+        let file = ChunkFile::from_bytes(data)?;
+        let mut model = Model::default();
+
+        for chunk in file.chunks() {
+            match (chunk.kind, chunk.version) {
+                (CHUNK_MESH, 0x801 | 0x802) => {
+                    if let Some(mesh) = mesh(&file, chunk)? {
+                        model.meshes.insert(chunk.id, mesh);
+                    }
+                }
+                (CHUNK_NODE, 0x823 | 0x824) => model.nodes.push(node(&file, chunk)?),
+                (CHUNK_COMPILED_BONES, 0x800) => model.bones = bones(&file, chunk)?,
+                _ => {}
+            }
+        }
+
+        Ok(model)
+    }
+}
+
+fn mesh(file: &ChunkFile, chunk: &Chunk) -> io::Result<Option<Mesh>> {
+    let mut reader = file.reader(chunk)?;
+    let flags = reader.u32()?;
+    let _flags2 = reader.u32()?;
+    let vertex_count = reader.u32()? as usize;
+    let _index_count = reader.u32()?;
+    let _subset_count = reader.u32()?;
+    let subsets_id = reader.u32()?;
+    let _vert_anim_id = reader.u32()?;
+    let mut streams = [0u32; STREAM_COUNT];
+    for stream in &mut streams {
+        *stream = reader.u32()?;
+        // 0x802 has room for 8 streams of each type, only the first is used
+        if chunk.version == 0x802 {
+            reader.skip(7 * 4)?;
+        }
+    }
+    if flags & MESH_IS_EMPTY != 0 || vertex_count == 0 {
+        return Ok(None);
+    }
+
+    let mut mesh = Mesh::default();
+    if let Some(stream) = file.stream(streams[STREAM_POSITIONS])? {
+        mesh.positions = match stream.element_size {
+            12 => stream
+                .elements()
+                .map(|mut e| e.vec3())
+                .collect::<io::Result<_>>()?,
+            8 => stream
+                .elements()
+                .map(|mut e| e.half3())
+                .collect::<io::Result<_>>()?,
+            size => return Err(invalid(format!("unsupported position size {size}"))),
+        };
+    }
+    if let Some(stream) = file.stream(streams[STREAM_P3S_C4B_T2S])? {
+        for mut element in stream.elements() {
+            let position = element.half3()?;
+            element.skip(2 + 4)?;
+            let uv = [element.half()?, element.half()?];
+            if mesh.positions.len() < stream.count {
+                mesh.positions.push(position);
+            }
+            mesh.uvs.push(uv);
+        }
+    }
+    if let Some(stream) = file.stream(streams[STREAM_NORMALS])? {
+        if stream.element_size == 12 {
+            mesh.normals = stream
+                .elements()
+                .map(|mut e| e.vec3())
+                .collect::<io::Result<_>>()?;
+        }
+    }
+    if let Some(stream) = file.stream(streams[STREAM_TEXCOORDS])? {
+        if stream.element_size == 8 {
+            mesh.uvs = stream
+                .elements()
+                .map(|mut e| Ok([e.f32()?, e.f32()?]))
+                .collect::<io::Result<_>>()?;
+        }
+    }
+    if let Some(stream) = file.stream(streams[STREAM_INDICES])? {
+        mesh.indices = match stream.element_size {
+            2 => stream
+                .elements()
+                .map(|mut e| Ok(e.u16()? as u32))
+                .collect::<io::Result<_>>()?,
+            4 => stream
+                .elements()
+                .map(|mut e| e.u32())
+                .collect::<io::Result<_>>()?,
+            size => return Err(invalid(format!("unsupported index size {size}"))),
+        };
+    }
+    if let Some(stream) = file.stream(streams[STREAM_BONE_MAPPING])? {
+        mesh.skin = stream
+            .elements()
+            .map(|mut e| {
+                let joints = match stream.element_size {
+                    8 => e.bytes::<4>()?.map(u16::from),
+                    12 => [e.u16()?, e.u16()?, e.u16()?, e.u16()?],
+                    size => return Err(invalid(format!("unsupported bone mapping size {size}"))),
+                };
+                Ok((joints, e.bytes::<4>()?))
+            })
+            .collect::<io::Result<_>>()?;
+    }
+    if let Some(chunk) = file
+        .by_id(subsets_id)
+        .filter(|chunk| chunk.kind == CHUNK_MESH_SUBSETS)
+    {
+        mesh.subsets = subsets(file, chunk)?;
+    }
+
+    Ok(Some(mesh))
+}
+
+fn subsets(file: &ChunkFile, chunk: &Chunk) -> io::Result<Vec<Subset>> {
+    let mut reader = file.reader(chunk)?;
+    let _flags = reader.u32()?;
+    let count = reader.u32()?;
+    reader.skip(8)?;
+
+    (0..count)
+        .map(|_| {
+            let first_index = reader.u32()?;
+            let index_count = reader.u32()?;
+            let _first_vertex = reader.u32()?;
+            let _vertex_count = reader.u32()?;
+            let material = reader.u32()?;
+            // radius and center
+            reader.skip(4 * 4)?;
+            Ok(Subset {
+                first_index,
+                index_count,
+                material,
+            })
+        })
+        .collect()
+}
+
+fn node(file: &ChunkFile, chunk: &Chunk) -> io::Result<Node> {
+    let mut reader = file.reader(chunk)?;
+    let name = reader.string(64)?;
+    let object = reader.u32()?;
+    let parent = reader.u32()?;
+    let _children = reader.u32()?;
+    let material = reader.u32()? as i32;
+    reader.skip(4)?;
+    let mut transform = [0f32; 16];
+    for value in &mut transform {
+        *value = reader.f32()?;
+    }
+    transform[12..15]
+        .iter_mut()
+        .for_each(|value| *value *= VERTEX_SCALE);
+
+    Ok(Node {
+        id: chunk.id,
+        name,
+        object,
+        parent: (parent != u32::MAX).then_some(parent),
+        material,
+        transform,
+    })
+}
+
+fn bones(file: &ChunkFile, chunk: &Chunk) -> io::Result<Vec<Bone>> {
+    let mut reader = file.reader(chunk)?;
+    reader.skip(32)?;
+
+    let count = reader.remaining() / COMPILED_BONE_SIZE;
+    (0..count)
+        .map(|index| {
+            let _controller_id = reader.u32()?;
+            // two physics geometries
+            reader.skip(2 * 104)?;
+            let _mass = reader.f32()?;
+            let world_to_bone = reader.matrix34()?;
+            let bone_to_world = reader.matrix34()?;
+            let name = reader.string(256)?;
+            let _limb_id = reader.u32()?;
+            let parent_offset = reader.u32()? as i32;
+            let _children = reader.u32()?;
+            let _child_offset = reader.u32()?;
+
+            Ok(Bone {
+                name,
+                parent: (parent_offset != 0).then(|| (index as i32 + parent_offset) as usize),
+                world_to_bone,
+                bone_to_world,
+            })
+        })
+        .collect()
+}
+
+/// A little endian cursor over a slice, failing with `UnexpectedEof` past its end.
+struct Reader<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self { data }
+    }
+
+    fn remaining(&self) -> usize {
+        self.data.len()
+    }
+
+    fn slice(&mut self, len: usize) -> io::Result<&'a [u8]> {
+        if len > self.data.len() {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+        let (head, tail) = self.data.split_at(len);
+        self.data = tail;
+        Ok(head)
+    }
+
+    fn skip(&mut self, len: usize) -> io::Result<()> {
+        self.slice(len).map(|_| ())
+    }
+
+    fn bytes<const N: usize>(&mut self) -> io::Result<[u8; N]> {
+        Ok(self.slice(N)?.try_into().unwrap())
+    }
+
+    fn u16(&mut self) -> io::Result<u16> {
+        Ok(u16::from_le_bytes(self.bytes()?))
+    }
+
+    fn u32(&mut self) -> io::Result<u32> {
+        Ok(u32::from_le_bytes(self.bytes()?))
+    }
+
+    fn f32(&mut self) -> io::Result<f32> {
+        Ok(f32::from_le_bytes(self.bytes()?))
+    }
+
+    fn half(&mut self) -> io::Result<f32> {
+        Ok(f16_to_f32(self.u16()?))
+    }
+
+    fn vec3(&mut self) -> io::Result<[f32; 3]> {
+        Ok([self.f32()?, self.f32()?, self.f32()?])
+    }
+
+    /// Three half floats followed by an unused fourth.
+    fn half3(&mut self) -> io::Result<[f32; 3]> {
+        let vec = [self.half()?, self.half()?, self.half()?];
+        self.skip(2)?;
+        Ok(vec)
+    }
+
+    fn matrix34(&mut self) -> io::Result<[[f32; 4]; 3]> {
+        let mut matrix = [[0f32; 4]; 3];
+        for value in matrix.iter_mut().flatten() {
+            *value = self.f32()?;
+        }
+        Ok(matrix)
+    }
+
+    /// A nul padded string of `len` bytes.
+    fn string(&mut self, len: usize) -> io::Result<String> {
+        let bytes = self.slice(len)?;
+        let end = bytes.iter().position(|&b| b == 0).unwrap_or(len);
+        Ok(String::from_utf8_lossy(&bytes[..end]).into_owned())
+    }
+}
+
+fn f16_to_f32(half: u16) -> f32 {
+    let sign = ((half as u32) & 0x8000) << 16;
+    let exponent = ((half >> 10) & 0x1F) as u32;
+    let mantissa = (half & 0x3FF) as u32;
+    let bits = match (exponent, mantissa) {
+        (0, 0) => sign,
+        (0, _) => {
+            // subnormal, renormalize
+            let shift = mantissa.leading_zeros() - 21;
+            sign | ((127 - 15 + 1 - shift) << 23) | ((mantissa << shift) & 0x3FF) << 13
+        }
+        (0x1F, _) => sign | 0x7F80_0000 | (mantissa << 13),
+        _ => sign | ((exponent + 127 - 15) << 23) | (mantissa << 13),
+    };
+    f32::from_bits(bits)
+}
+
+fn invalid(message: impl Into<String>) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn half_floats() {
+        assert_eq!(f16_to_f32(0x3C00), 1.0);
+        assert_eq!(f16_to_f32(0xC000), -2.0);
+        assert_eq!(f16_to_f32(0x3555), 0.33325195);
+        assert_eq!(f16_to_f32(0x0001), 5.9604645e-8);
+        assert!(f16_to_f32(0x7C00).is_infinite());
+    }
+
+    #[test]
+    fn reads_chunk_table() {
+        let mut data = b"CrCh".to_vec();
+        data.extend(VERSION.to_le_bytes());
+        data.extend(1u32.to_le_bytes());
+        data.extend(16u32.to_le_bytes());
+        data.extend(CHUNK_MESH_SUBSETS.to_le_bytes());
+        data.extend(0x800u16.to_le_bytes());
+        data.extend(7u32.to_le_bytes());
+        data.extend(0u32.to_le_bytes());
+        data.extend(32u32.to_le_bytes());
+
+        let file = ChunkFile::from_bytes(&data).unwrap();
+        assert_eq!(file.chunks().len(), 1);
+        assert_eq!(file.chunks()[0].id, 7);
+        assert!(ChunkFile::from_bytes(b"CryTek\0\0").is_err());
+    }
+}
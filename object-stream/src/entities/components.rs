@@ -0,0 +1,165 @@
+//! Components of chosen types across slices, flattened into the rows of one table.
+
+use serde_json::{Map, Value};
+
+/// Whether the `type` of a component in a [`to_entities`](super::to_entities) layout is `query`,
+/// a type name in any case or a type id with or without braces.
+pub fn is_type(component_type: &str, query: &str) -> bool {
+    let unbraced = |id: &str| id.trim_start_matches('{').trim_end_matches('}').to_owned();
+    component_type.eq_ignore_ascii_case(query)
+        || unbraced(component_type).eq_ignore_ascii_case(&unbraced(query))
+}
+
+/// A row for every component of `entities`, the output of [`to_entities`](super::to_entities),
+/// whose type `matches`: the entity's id and name, the component type and then its fields.
+///
+/// Nested classes become columns named by their path joined with `.`, lists are kept whole.
+pub fn rows<F>(entities: &Value, matches: F) -> Vec<Map<String, Value>>
+where
+    F: Fn(&str) -> bool,
+{
+    let Some(entities) = entities["entities"].as_array() else {
+        return vec![];
+    };
+    let matches = &matches;
+    entities
+        .iter()
+        .flat_map(move |entity| {
+            let components = entity["components"].as_array().into_iter().flatten();
+            components
+                .filter(move |component| component["type"].as_str().is_some_and(matches))
+                .map(move |component| {
+                    let mut row = Map::new();
+                    row.insert("entity_id".into(), entity["id"].clone());
+                    row.insert("entity".into(), entity["name"].clone());
+                    row.insert("type".into(), component["type"].clone());
+                    if let Value::Object(fields) = component {
+                        let fields = fields.iter().filter(|(key, _)| *key != "type");
+                        flatten("", fields, &mut row);
+                    }
+                    row
+                })
+        })
+        .collect()
+}
+
+fn flatten<'a>(
+    prefix: &str,
+    fields: impl Iterator<Item = (&'a String, &'a Value)>,
+    row: &mut Map<String, Value>,
+) {
+    for (key, value) in fields {
+        let column = match prefix {
+            "" => key.to_owned(),
+            prefix => format!("{prefix}.{key}"),
+        };
+        match value {
+            Value::Object(fields) if !fields.is_empty() => flatten(&column, fields.iter(), row),
+            value => {
+                row.insert(column, value.clone());
+            }
+        }
+    }
+}
+
+/// The columns of `rows` in the order they first appear.
+pub fn columns(rows: &[Map<String, Value>]) -> Vec<&str> {
+    let mut columns: Vec<&str> = vec![];
+    for key in rows.iter().flat_map(Map::keys) {
+        if !columns.contains(&key.as_str()) {
+            columns.push(key);
+        }
+    }
+    columns
+}
+
+/// `rows` as CSV with a header of their [`columns`], empty cells where a row lacks one.
+pub fn to_csv(rows: &[Map<String, Value>]) -> String {
+    let columns = columns(rows);
+    let mut csv = columns
+        .iter()
+        .map(|column| csv_field(column))
+        .collect::<Vec<_>>()
+        .join(",");
+    csv.push('\n');
+    for row in rows {
+        let cells = columns.iter().map(|column| match row.get(*column) {
+            None | Some(Value::Null) => String::new(),
+            Some(Value::String(string)) => csv_field(string),
+            Some(value) => csv_field(&value.to_string()),
+        });
+        csv.push_str(&cells.collect::<Vec<_>>().join(","));
+        csv.push('\n');
+    }
+    csv
+}
+
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_owned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn one_row_per_component() {
+        let entities = json!({
+            "entities": [
+                {
+                    "id": 1,
+                    "name": "Wolves",
+                    "components": [
+                        { "type": "TransformComponent" },
+                        {
+                            "type": "SpawnerComponent",
+                            "Count": 3,
+                            "Settings": { "Radius": 5.5, "Tags": ["a", "b"] },
+                        },
+                    ],
+                },
+                {
+                    "id": 2,
+                    "name": "Boars, elite",
+                    "components": [{ "type": "SpawnerComponent", "Count": 1, "Note": "say \"hi\"" }],
+                },
+            ]
+        });
+
+        let rows = rows(&entities, |name| is_type(name, "spawnercomponent"));
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[0]["Settings.Radius"], 5.5);
+        assert_eq!(
+            columns(&rows),
+            [
+                "entity_id",
+                "entity",
+                "type",
+                "Count",
+                "Settings.Radius",
+                "Settings.Tags",
+                "Note"
+            ]
+        );
+        assert_eq!(
+            to_csv(&rows),
+            "entity_id,entity,type,Count,Settings.Radius,Settings.Tags,Note\n\
+             1,Wolves,SpawnerComponent,3,5.5,\"[\"\"a\"\",\"\"b\"\"]\",\n\
+             2,\"Boars, elite\",SpawnerComponent,1,,,\"say \"\"hi\"\"\"\n"
+        );
+    }
+
+    #[test]
+    fn matches_type_ids() {
+        let id = "{22B10178-39B6-4C12-BB37-77DB45FDD3B6}";
+        assert!(is_type(id, "22b10178-39b6-4c12-bb37-77db45fdd3b6"));
+        assert!(is_type(id, id));
+        assert!(!is_type("TransformComponent", "Transform"));
+    }
+}
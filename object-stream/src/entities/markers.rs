@@ -0,0 +1,176 @@
+//! Named points and areas placed by slices, as GeoJSON geometries in world coordinates.
+
+use serde_json::{json, Map, Value};
+
+use super::world::{by_id, world};
+
+/// An entity of a [`to_entities`](super::to_entities) layout placed in the world, with its
+/// geometry.
+#[derive(Debug)]
+pub struct Marker<'a> {
+    pub entity: &'a Value,
+    /// A `Point` at the entity, or the `Polygon` of its shape when it has a polygon prism.
+    pub geometry: Value,
+    /// Height of the entity above the map.
+    pub elevation: f64,
+}
+
+impl Marker<'_> {
+    /// The marker as a GeoJSON feature with `properties`.
+    pub fn to_feature(&self, properties: Map<String, Value>) -> Value {
+        json!({
+            "type": "Feature",
+            "geometry": self.geometry,
+            "properties": properties,
+        })
+    }
+
+    pub fn name(&self) -> Option<&str> {
+        self.entity["name"].as_str()
+    }
+
+    /// Type names of the entity's components.
+    pub fn components(&self) -> Vec<&str> {
+        components(self.entity)
+            .filter_map(|component| component["type"].as_str())
+            .collect()
+    }
+}
+
+/// The entities of `entities`, the output of [`to_entities`](super::to_entities), whose name or
+/// a component type name `matches`.
+///
+/// Entities are placed by their [`world`] transform.
+pub fn markers<F>(entities: &Value, matches: F) -> Vec<Marker<'_>>
+where
+    F: Fn(&str) -> bool,
+{
+    let by_id = by_id(entities);
+    entities["entities"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter(|entity| {
+            entity["name"].as_str().is_some_and(&matches)
+                || components(entity)
+                    .any(|component| component["type"].as_str().is_some_and(&matches))
+        })
+        .filter_map(|entity| {
+            let world = world(entity, &by_id)?;
+            let [x, y, z] = world.translation;
+            let geometry = match components(entity).find_map(vertices) {
+                Some(vertices) => {
+                    let mut ring = vertices
+                        .into_iter()
+                        .map(|[vx, vy]| {
+                            let [x, y, _] = world.point([vx, vy, 0.0]);
+                            [x, y]
+                        })
+                        .collect::<Vec<_>>();
+                    ring.push(ring[0]);
+                    json!({ "type": "Polygon", "coordinates": [ring] })
+                }
+                None => json!({ "type": "Point", "coordinates": [x, y] }),
+            };
+            Some(Marker {
+                entity,
+                geometry,
+                elevation: z,
+            })
+        })
+        .collect()
+}
+
+fn components(entity: &Value) -> impl Iterator<Item = &Value> {
+    entity["components"].as_array().into_iter().flatten()
+}
+
+/// The local `[x, y]` vertices of a polygon shape, found as a `Vertices` list anywhere in a
+/// component, with at least three of them.
+fn vertices(value: &Value) -> Option<Vec<[f64; 2]>> {
+    match value {
+        Value::Object(map) => {
+            let found = map.get("Vertices").and_then(|vertices| {
+                let vertices = vertices
+                    .as_array()?
+                    .iter()
+                    .map(|vertex| match vertex.as_array()?.as_slice() {
+                        [x, y, ..] => Some([x.as_f64()?, y.as_f64()?]),
+                        _ => None,
+                    })
+                    .collect::<Option<Vec<_>>>()?;
+                (vertices.len() >= 3).then_some(vertices)
+            });
+            found.or_else(|| map.values().find_map(vertices))
+        }
+        Value::Array(values) => values.iter().find_map(vertices),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn places_points_and_polygons() {
+        let entities = json!({
+            "entities": [
+                {
+                    "id": 1,
+                    "name": "Region",
+                    "children": [2, 3],
+                    "transform": { "translation": [4096.0, 2048.0, 0.0], "scale": [1.0, 1.0, 1.0] },
+                    "components": [{ "type": "TransformComponent" }],
+                },
+                {
+                    "id": 2,
+                    "name": "POI_Windsward",
+                    "parent": 1,
+                    "transform": { "translation": [10.0, 20.0, 30.0], "scale": [1.0, 1.0, 1.0] },
+                    "components": [{ "type": "TransformComponent" }],
+                },
+                {
+                    "id": 3,
+                    "name": "Territory",
+                    "parent": 1,
+                    "transform": { "translation": [0.0, 0.0, 0.0], "scale": [2.0, 2.0, 1.0] },
+                    "components": [{
+                        "type": "TerritoryComponent",
+                        "Shape": { "VertexContainer": { "Vertices": [[0.0, 0.0], [1.0, 0.0], [1.0, 1.0]] } },
+                    }],
+                },
+                {
+                    "id": 4,
+                    "name": "Spawner",
+                    "world": { "translation": [1.0, 2.0, 3.0] },
+                    "transform": { "translation": [0.0, 0.0, 0.0] },
+                    "components": [{ "type": "SpawnerComponent" }],
+                },
+            ]
+        });
+
+        let markers = markers(&entities, |name| {
+            name.starts_with("POI") || name.starts_with("Territory") || name.contains("Spawner")
+        });
+        assert_eq!(markers.len(), 3);
+
+        assert_eq!(markers[0].name(), Some("POI_Windsward"));
+        assert_eq!(markers[0].geometry["coordinates"], json!([4106.0, 2068.0]));
+        assert_eq!(markers[0].elevation, 30.0);
+
+        assert_eq!(markers[1].geometry["type"], "Polygon");
+        assert_eq!(
+            markers[1].geometry["coordinates"],
+            json!([[
+                [4096.0, 2048.0],
+                [4098.0, 2048.0],
+                [4098.0, 2050.0],
+                [4096.0, 2048.0]
+            ]])
+        );
+
+        assert_eq!(markers[2].geometry["coordinates"], json!([1.0, 2.0]));
+        assert_eq!(markers[2].components(), ["SpawnerComponent"]);
+    }
+}
@@ -0,0 +1,442 @@
+//! An entity oriented layout for `.slice` and `.dynamicslice` object streams.
+
+pub mod components;
+pub mod markers;
+pub mod world;
+
+use std::collections::BTreeMap;
+
+use serde_json::{json, Map, Value};
+use utils::{
+    crc32,
+    types::{uuid_data_to_serialize, DOUBLE, FLOAT, TRANSFORM, VECTOR2, VECTOR3},
+};
+use uuid::Uuid;
+
+use crate::{Element, ObjectStream};
+
+const ENTITY: Uuid = Uuid::from_u128(0x75651658_8663_478D_9090_2432DFCAFA44);
+const ENTITY_ID: Uuid = Uuid::from_u128(0x6383F1D3_BB27_4E6B_A49A_6409B2059EAA);
+const SLICE_COMPONENT: Uuid = Uuid::from_u128(0xAFD304E4_1773_47C8_855A_8B622398934F);
+const TRANSFORM_COMPONENT: Uuid = Uuid::from_u128(0x22B10178_39B6_4C12_BB37_77DB45FDD3B6);
+const EDITOR_TRANSFORM_COMPONENT: Uuid = Uuid::from_u128(0x27F1E1A1_8D9D_4C3B_BD3A_AFB9762449C0);
+const GENERIC_COMPONENT_WRAPPER: Uuid = Uuid::from_u128(0x68D358CA_89B9_4730_8BA6_E181DEA28FDE);
+
+/// Lays out the entities of a slice with their id, name, parent, children, transforms and
+/// components, each component keyed by its field names. Returns `None` for streams without
+/// entities.
+///
+/// Types and fields are matched by their uuid and CRC, so this works without the hash
+/// dictionaries, which only supply the names of components and unknown fields.
+pub fn to_entities(stream: &ObjectStream) -> Option<Value> {
+    let slice = stream.query_elements(|element| element.id == SLICE_COMPONENT);
+    let entities = match slice {
+        Some(slice) => slice
+            .field("Entities")
+            .map(|entities| entities.elements.iter().collect::<Vec<_>>())
+            .unwrap_or_default(),
+        None => stream.elements.iter().collect(),
+    };
+    let entities = entities
+        .into_iter()
+        .filter(|element| element.id == ENTITY)
+        .map(Entity::from_element)
+        .collect::<Vec<_>>();
+    if slice.is_none() && entities.is_empty() {
+        return None;
+    }
+
+    let mut children: BTreeMap<u64, Vec<u64>> = BTreeMap::new();
+    for entity in &entities {
+        if let (Some(id), Some(parent)) = (entity.id, entity.parent) {
+            children.entry(parent).or_default().push(id);
+        }
+    }
+
+    let entities = entities
+        .into_iter()
+        .map(|entity| {
+            let children = entity.id.and_then(|id| children.remove(&id));
+            entity.to_value(children)
+        })
+        .collect::<Vec<_>>();
+
+    let mut value = json!({ "entities": entities });
+    let slices = slice
+        .and_then(|slice| slice.field("Prefabs"))
+        .map(|prefabs| prefabs.elements.iter().map(reference).collect::<Vec<_>>())
+        .unwrap_or_default();
+    if !slices.is_empty() {
+        value["slices"] = Value::Array(slices);
+    }
+    Some(value)
+}
+
+struct Entity<'a> {
+    id: Option<u64>,
+    name: Option<String>,
+    parent: Option<u64>,
+    transform: Option<Value>,
+    world: Option<Value>,
+    components: Vec<&'a Element>,
+}
+
+impl<'a> Entity<'a> {
+    fn from_element(element: &'a Element) -> Self {
+        let components = element
+            .field("Components")
+            .map(|components| components.elements.iter().map(unwrap).collect())
+            .unwrap_or_else(Vec::new);
+
+        let mut entity = Self {
+            id: element.field("Id").and_then(entity_id),
+            name: element
+                .field("Name")
+                .and_then(|name| leaf(name).as_str().map(str::to_owned)),
+            parent: None,
+            transform: None,
+            world: None,
+            components,
+        };
+
+        for component in &entity.components {
+            match component.id {
+                TRANSFORM_COMPONENT => {
+                    entity.parent = component.field("Parent").and_then(entity_id);
+                    entity.transform = component.field("LocalTransform").and_then(transform);
+                    entity.world = component.field("Transform").and_then(transform);
+                }
+                EDITOR_TRANSFORM_COMPONENT => {
+                    entity.parent = component.field("Parent Entity").and_then(entity_id);
+                    entity.transform = component.field("Transform Data").map(|data| {
+                        json!({
+                            "translation": data.field("Translate").map_or(Value::Null, leaf),
+                            "euler": data.field("Rotate").map_or(Value::Null, leaf),
+                            "scale": data.field("Scale").map_or(Value::Null, leaf),
+                        })
+                    });
+                }
+                _ => {}
+            }
+        }
+        entity
+    }
+
+    fn to_value(self, children: Option<Vec<u64>>) -> Value {
+        let mut value = Map::new();
+        value.insert("id".into(), json!(self.id));
+        value.insert("name".into(), json!(self.name));
+        if let Some(parent) = self.parent {
+            value.insert("parent".into(), json!(parent));
+        }
+        if let Some(children) = children {
+            value.insert("children".into(), json!(children));
+        }
+        if let Some(transform) = self.transform {
+            value.insert("transform".into(), transform);
+        }
+        if let Some(world) = self.world {
+            value.insert("world".into(), world);
+        }
+        let components = self
+            .components
+            .into_iter()
+            .map(|component| {
+                let mut fields = Map::new();
+                fields.insert("type".into(), json!(type_name(component)));
+                flatten(component, &mut fields);
+                Value::Object(fields)
+            })
+            .collect();
+        value.insert("components".into(), Value::Array(components));
+        Value::Object(value)
+    }
+}
+
+impl Element {
+    /// Whether the element is the field `name`, which is hashed lowercased.
+    fn is_field(&self, name: &str) -> bool {
+        match &self.field {
+            Some(field) => field.eq_ignore_ascii_case(name),
+            None => self.name_crc == Some(crc32(&name.to_lowercase())),
+        }
+    }
+
+    /// The child element for field `name`, looking through base classes.
+    fn field(&self, name: &str) -> Option<&Element> {
+        self.elements.iter().find_map(|child| {
+            if child.is_field(name) {
+                Some(child)
+            } else if is_base_class(child) {
+                child.field(name)
+            } else {
+                None
+            }
+        })
+    }
+}
+
+fn is_base_class(element: &Element) -> bool {
+    (1..=3).any(|i| element.is_field(&format!("BaseClass{i}")))
+}
+
+/// Editor components are stored wrapped, with the actual component as its template.
+fn unwrap(component: &Element) -> &Element {
+    match component.id {
+        GENERIC_COMPONENT_WRAPPER => component.field("m_template").unwrap_or(component),
+        _ => component,
+    }
+}
+
+fn type_name(element: &Element) -> String {
+    match element.name.is_empty() {
+        true => element.id.braced().to_string().to_uppercase(),
+        false => element.name.to_owned(),
+    }
+}
+
+fn entity_id(element: &Element) -> Option<u64> {
+    let id = match element.id {
+        ENTITY_ID => element.field("id")?,
+        _ => element,
+    };
+    leaf(id).as_u64()
+}
+
+/// Splits a transform stored as the three basis columns followed by the translation into its
+/// translation, rotation quaternion and per axis scale.
+fn transform(element: &Element) -> Option<Value> {
+    if element.id != TRANSFORM {
+        return None;
+    }
+    let values = floats(element.data.as_deref()?);
+    let [c0, c1, c2, translation] = [0, 3, 6, 9].map(|i| {
+        let v = values.get(i..i + 3).unwrap_or(&[0.0; 3]);
+        [v[0], v[1], v[2]]
+    });
+
+    let length = |c: [f32; 3]| (c[0] * c[0] + c[1] * c[1] + c[2] * c[2]).sqrt();
+    let scale = [length(c0), length(c1), length(c2)];
+    let normalized = |c: [f32; 3], s: f32| if s == 0.0 { c } else { c.map(|v| v / s) };
+    let columns = [
+        normalized(c0, scale[0]),
+        normalized(c1, scale[1]),
+        normalized(c2, scale[2]),
+    ];
+
+    Some(json!({
+        "translation": translation,
+        "rotation": quaternion(columns),
+        "scale": scale,
+    }))
+}
+
+/// The `[x, y, z, w]` quaternion of a rotation matrix given as columns.
+fn quaternion(c: [[f32; 3]; 3]) -> [f32; 4] {
+    let m = |row: usize, col: usize| c[col][row];
+    let trace = m(0, 0) + m(1, 1) + m(2, 2);
+    if trace > 0.0 {
+        let s = (trace + 1.0).sqrt() * 2.0;
+        [
+            (m(2, 1) - m(1, 2)) / s,
+            (m(0, 2) - m(2, 0)) / s,
+            (m(1, 0) - m(0, 1)) / s,
+            0.25 * s,
+        ]
+    } else if m(0, 0) > m(1, 1) && m(0, 0) > m(2, 2) {
+        let s = (1.0 + m(0, 0) - m(1, 1) - m(2, 2)).sqrt() * 2.0;
+        [
+            0.25 * s,
+            (m(0, 1) + m(1, 0)) / s,
+            (m(0, 2) + m(2, 0)) / s,
+            (m(2, 1) - m(1, 2)) / s,
+        ]
+    } else if m(1, 1) > m(2, 2) {
+        let s = (1.0 + m(1, 1) - m(0, 0) - m(2, 2)).sqrt() * 2.0;
+        [
+            (m(0, 1) + m(1, 0)) / s,
+            0.25 * s,
+            (m(1, 2) + m(2, 1)) / s,
+            (m(0, 2) - m(2, 0)) / s,
+        ]
+    } else {
+        let s = (1.0 + m(2, 2) - m(0, 0) - m(1, 1)).sqrt() * 2.0;
+        [
+            (m(0, 2) + m(2, 0)) / s,
+            (m(1, 2) + m(2, 1)) / s,
+            0.25 * s,
+            (m(1, 0) - m(0, 1)) / s,
+        ]
+    }
+}
+
+fn floats(data: &[u8]) -> Vec<f32> {
+    data.chunks_exact(4)
+        .map(|b| f32::from_be_bytes(b.try_into().unwrap()))
+        .collect()
+}
+
+/// A referenced slice asset with the number of times it's instanced.
+fn reference(element: &Element) -> Value {
+    json!({
+        "asset": element.field("Asset").map_or(Value::Null, value),
+        "instances": element.field("Instances").map_or(0, |instances| instances.elements.len()),
+    })
+}
+
+/// Adds the fields of `element` to `map`, inlining base classes.
+fn flatten(element: &Element, map: &mut Map<String, Value>) {
+    for child in &element.elements {
+        if is_base_class(child) {
+            flatten(child, map);
+        } else {
+            let key = match &child.field {
+                Some(field) => field.to_owned(),
+                None => child
+                    .name_crc
+                    .map(|crc| crc.to_string())
+                    .unwrap_or_default(),
+            };
+            map.insert(key, value(child));
+        }
+    }
+}
+
+/// Converts an element to plain json, containers to arrays and classes to objects.
+fn value(element: &Element) -> Value {
+    if element.id == ENTITY_ID {
+        return entity_id(element).map_or(Value::Null, Value::from);
+    }
+    if element.elements.is_empty() {
+        return leaf(element);
+    }
+    if element
+        .elements
+        .iter()
+        .all(|child| child.is_field("element"))
+    {
+        return element.elements.iter().map(value).collect();
+    }
+    let mut map = Map::new();
+    flatten(element, &mut map);
+    Value::Object(map)
+}
+
+/// The value of an element without children, with floats as numbers rather than strings.
+fn leaf(element: &Element) -> Value {
+    let Some(data) = &element.data else {
+        return Value::Null;
+    };
+    match element.id {
+        FLOAT | DOUBLE | VECTOR2 | VECTOR3 | TRANSFORM => {
+            let value = uuid_data_to_serialize(&element.id, data, true).unwrap_or_default();
+            number(value)
+        }
+        id => uuid_data_to_serialize(&id, data, true).unwrap_or_default(),
+    }
+}
+
+fn number(value: Value) -> Value {
+    match value {
+        Value::String(string) => string
+            .parse::<f64>()
+            .map_or(Value::String(string), |n| json!(n)),
+        Value::Array(values) => values.into_iter().map(number).collect(),
+        value => value,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::field_crc;
+    use utils::types::{AZ_U64, VOID};
+
+    fn element(field: &str, id: Uuid, data: Option<Vec<u8>>, elements: Vec<Element>) -> Element {
+        Element {
+            name_crc: Some(field_crc(field)),
+            field: Some(field.to_owned()),
+            id,
+            data,
+            elements,
+            ..Default::default()
+        }
+    }
+
+    fn id_field(field: &str, id: u64) -> Element {
+        // a field missing from the hash dictionary is only known by its CRC
+        let mut id = element("id", AZ_U64, Some(id.to_be_bytes().to_vec()), vec![]);
+        id.field = None;
+        element(field, ENTITY_ID, None, vec![id])
+    }
+
+    fn transform_data(translation: [f32; 3]) -> Vec<u8> {
+        [1.0, 0.0, 0.0, 0.0, 2.0, 0.0, 0.0, 0.0, 1.0]
+            .into_iter()
+            .chain(translation)
+            .flat_map(f32::to_be_bytes)
+            .collect()
+    }
+
+    fn entity(id: u64, name: &str, parent: Option<u64>) -> Element {
+        let mut transform = vec![element(
+            "LocalTransform",
+            TRANSFORM,
+            Some(transform_data([1.0, 2.0, 3.0])),
+            vec![],
+        )];
+        if let Some(parent) = parent {
+            transform.push(id_field("Parent", parent));
+        }
+        let components = element(
+            "Components",
+            VOID,
+            None,
+            vec![element("element", TRANSFORM_COMPONENT, None, transform)],
+        );
+        let name = element("Name", VOID, Some(name.as_bytes().to_vec()), vec![]);
+        element(
+            "element",
+            ENTITY,
+            None,
+            vec![id_field("Id", id), name, components],
+        )
+    }
+
+    #[test]
+    fn slice_entities() {
+        let entities = element(
+            "Entities",
+            VOID,
+            None,
+            vec![entity(1, "root", None), entity(2, "child", Some(1))],
+        );
+        let slice = element("element", SLICE_COMPONENT, None, vec![entities]);
+        let stream = ObjectStream {
+            elements: vec![slice],
+            ..Default::default()
+        };
+
+        let value = to_entities(&stream).unwrap();
+        let entities = value["entities"].as_array().unwrap();
+        assert_eq!(entities.len(), 2);
+        assert_eq!(entities[0]["name"], "root");
+        assert_eq!(entities[0]["children"], json!([2]));
+        assert_eq!(entities[1]["parent"], 1);
+        assert_eq!(
+            entities[1]["transform"]["translation"],
+            json!([1.0, 2.0, 3.0])
+        );
+        assert_eq!(entities[1]["transform"]["scale"], json!([1.0, 2.0, 1.0]));
+        assert_eq!(
+            entities[1]["transform"]["rotation"],
+            json!([0.0, 0.0, 0.0, 1.0])
+        );
+        assert_eq!(entities[1]["components"][0]["Parent"], 1);
+    }
+
+    #[test]
+    fn other_streams() {
+        assert!(to_entities(&ObjectStream::default()).is_none());
+    }
+}
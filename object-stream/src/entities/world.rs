@@ -0,0 +1,206 @@
+//! Where slice entities end up in the world, their local transforms composed with their parents'.
+
+use std::collections::HashMap;
+
+use serde_json::Value;
+
+/// A translation, rotation and per axis scale, applied scale first.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Transform {
+    pub translation: [f64; 3],
+    /// `[x, y, z, w]` quaternion.
+    pub rotation: [f64; 4],
+    pub scale: [f64; 3],
+}
+
+impl Transform {
+    /// The local transform of an entity of a [`to_entities`](super::to_entities) layout, `None`
+    /// when it has no translation. Editor entities store their rotation as Euler angles in
+    /// degrees, which are converted the way the editor does.
+    pub fn local(entity: &Value) -> Option<Self> {
+        let transform = &entity["transform"];
+        let rotation = match quaternion(&transform["rotation"]) {
+            Some(rotation) => rotation,
+            None => vector(&transform["euler"]).map_or([0.0, 0.0, 0.0, 1.0], euler_degrees),
+        };
+        Some(Self {
+            translation: vector(&transform["translation"])?,
+            rotation,
+            scale: vector(&transform["scale"]).unwrap_or([1.0; 3]),
+        })
+    }
+
+    /// The world transform the slice stored for an entity, which only runtime slices have.
+    /// Unset ones are all zero and ignored.
+    pub fn stored(entity: &Value) -> Option<Self> {
+        let world = &entity["world"];
+        Some(Self {
+            translation: vector(&world["translation"]).filter(|t| *t != [0.0; 3])?,
+            rotation: quaternion(&world["rotation"]).unwrap_or([0.0, 0.0, 0.0, 1.0]),
+            scale: vector(&world["scale"]).unwrap_or([1.0; 3]),
+        })
+    }
+
+    /// `child`, given relative to this transform, in the space this transform is relative to.
+    ///
+    /// Scales are multiplied per axis, which is exact unless a rotated child sits under a parent
+    /// scaled unevenly.
+    pub fn apply(&self, child: &Self) -> Self {
+        Self {
+            translation: self.point(child.translation),
+            rotation: multiply(self.rotation, child.rotation),
+            scale: [0, 1, 2].map(|i| self.scale[i] * child.scale[i]),
+        }
+    }
+
+    /// `point`, given relative to this transform, in the space this transform is relative to.
+    pub fn point(&self, point: [f64; 3]) -> [f64; 3] {
+        let scaled = [0, 1, 2].map(|i| point[i] * self.scale[i]);
+        let rotated = rotate(self.rotation, scaled);
+        [0, 1, 2].map(|i| self.translation[i] + rotated[i])
+    }
+}
+
+/// The entities of `entities`, the output of [`to_entities`](super::to_entities), by their id.
+pub fn by_id(entities: &Value) -> HashMap<u64, &Value> {
+    entities["entities"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|entity| Some((entity["id"].as_u64()?, entity)))
+        .collect()
+}
+
+/// World transform of `entity`: the one the slice stored, otherwise its local transform placed
+/// by its parents up to the first one with a stored world transform or without a parent.
+/// `None` when `entity` has neither.
+pub fn world(entity: &Value, by_id: &HashMap<u64, &Value>) -> Option<Transform> {
+    if let Some(world) = Transform::stored(entity) {
+        return Some(world);
+    }
+
+    let mut world = Transform::local(entity)?;
+    let mut parent = entity["parent"].as_u64();
+    // bounded by the entity count so a malformed hierarchy can't loop forever
+    for _ in 0..by_id.len() {
+        let Some(entity) = parent.and_then(|id| by_id.get(&id)) else {
+            break;
+        };
+        if let Some(stored) = Transform::stored(entity) {
+            return Some(stored.apply(&world));
+        }
+        if let Some(local) = Transform::local(entity) {
+            world = local.apply(&world);
+        }
+        parent = entity["parent"].as_u64();
+    }
+    Some(world)
+}
+
+fn vector(value: &Value) -> Option<[f64; 3]> {
+    match value.as_array()?.as_slice() {
+        [x, y, z] => Some([x.as_f64()?, y.as_f64()?, z.as_f64()?]),
+        _ => None,
+    }
+}
+
+fn quaternion(value: &Value) -> Option<[f64; 4]> {
+    match value.as_array()?.as_slice() {
+        [x, y, z, w] => Some([x.as_f64()?, y.as_f64()?, z.as_f64()?, w.as_f64()?]),
+        _ => None,
+    }
+}
+
+/// The quaternion of Euler angles in degrees, as Lumberyard's `ConvertEulerDegreesToQuaternion`.
+fn euler_degrees(degrees: [f64; 3]) -> [f64; 4] {
+    let [(sx, cx), (sy, cy), (sz, cz)] = degrees.map(|d| (d.to_radians() / 2.0).sin_cos());
+    [
+        cx * sy * sz + sx * cy * cz,
+        cx * sy * cz - sx * cy * sz,
+        cx * cy * sz + sx * sy * cz,
+        cx * cy * cz - sx * sy * sz,
+    ]
+}
+
+fn multiply(a: [f64; 4], b: [f64; 4]) -> [f64; 4] {
+    let [ax, ay, az, aw] = a;
+    let [bx, by, bz, bw] = b;
+    [
+        aw * bx + ax * bw + ay * bz - az * by,
+        aw * by - ax * bz + ay * bw + az * bx,
+        aw * bz + ax * by - ay * bx + az * bw,
+        aw * bw - ax * bx - ay * by - az * bz,
+    ]
+}
+
+fn rotate(q: [f64; 4], v: [f64; 3]) -> [f64; 3] {
+    let [x, y, z, w] = q;
+    let cross = |a: [f64; 3], b: [f64; 3]| {
+        [
+            a[1] * b[2] - a[2] * b[1],
+            a[2] * b[0] - a[0] * b[2],
+            a[0] * b[1] - a[1] * b[0],
+        ]
+    };
+    let t = cross([x, y, z], v).map(|c| 2.0 * c);
+    let u = cross([x, y, z], t);
+    [0, 1, 2].map(|i| v[i] + w * t[i] + u[i])
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn close(a: &[f64], b: &[f64]) -> bool {
+        a.iter().zip(b).all(|(a, b)| (a - b).abs() < 1e-9)
+    }
+
+    #[test]
+    fn composes_parent_transforms() {
+        let half = std::f64::consts::FRAC_1_SQRT_2;
+        let entities = json!({
+            "entities": [
+                {
+                    "id": 1,
+                    "name": "Camp",
+                    "transform": { "translation": [10.0, 0.0, 0.0], "rotation": [0.0, 0.0, half, half], "scale": [2.0, 2.0, 2.0] },
+                },
+                {
+                    "id": 2,
+                    "name": "Tent",
+                    "parent": 1,
+                    "transform": { "translation": [1.0, 0.0, 0.0], "euler": [0.0, 0.0, 90.0] },
+                },
+                {
+                    "id": 3,
+                    "name": "Chest",
+                    "parent": 2,
+                    "transform": { "translation": [1.0, 0.0, 0.5] },
+                },
+                {
+                    "id": 4,
+                    "name": "Spawned",
+                    "parent": 1,
+                    "world": { "translation": [1.0, 2.0, 3.0] },
+                    "transform": { "translation": [0.0, 0.0, 0.0] },
+                },
+            ]
+        });
+        let by_id = by_id(&entities);
+        let entity = |id| by_id[&id];
+
+        let tent = world(entity(2), &by_id).unwrap();
+        assert!(close(&tent.translation, &[10.0, 2.0, 0.0]));
+        assert!(close(&tent.rotation, &[0.0, 0.0, 1.0, 0.0]));
+        assert!(close(&tent.scale, &[2.0, 2.0, 2.0]));
+
+        // rotated half a turn and scaled twice by its parents
+        let chest = world(entity(3), &by_id).unwrap();
+        assert!(close(&chest.translation, &[8.0, 2.0, 1.0]));
+
+        let spawned = world(entity(4), &by_id).unwrap();
+        assert_eq!(spawned.translation, [1.0, 2.0, 3.0]);
+    }
+}
@@ -1,13 +1,17 @@
 mod de;
+pub mod entities;
 mod error;
 pub mod ser;
 mod types;
 
-use crc32fast::hash;
 use serde::{self, Deserialize, Serialize};
 use serde_json::Value;
 use std::io::{self, Cursor, Read, Write};
-use utils::{lumberyard::LumberyardSource, types::uuid_data_to_serialize};
+use utils::{
+    crc32,
+    lumberyard::LumberyardSource,
+    types::{uuid_data_from_value, uuid_data_to_serialize},
+};
 use uuid::{self, serde::compact, Uuid};
 
 const ST_BINARYFLAG_MASK: u8 = 0xF8;
@@ -51,14 +55,38 @@ pub struct ObjectStream {
     elements: Vec<Element>,
 }
 
-impl From<XMLObjectStream> for ObjectStream {
-    fn from(value: XMLObjectStream) -> Self {
-        Self {
+impl TryFrom<XMLObjectStream> for ObjectStream {
+    type Error = io::Error;
+
+    /// Fails on a value that doesn't parse as its type, naming its field.
+    fn try_from(value: XMLObjectStream) -> io::Result<Self> {
+        Ok(Self {
             _tag: StreamTag::BINARY,
             version: value.version,
-            elements: value.elements.into_iter().map(Element::from).collect(),
+            elements: value
+                .elements
+                .into_iter()
+                .map(Element::try_from)
+                .collect::<io::Result<_>>()?,
             ..Default::default()
-        }
+        })
+    }
+}
+
+impl TryFrom<JSONObjectStream> for ObjectStream {
+    type Error = io::Error;
+
+    /// Fails on a value that doesn't parse as its type, naming its field.
+    fn try_from(value: JSONObjectStream) -> io::Result<Self> {
+        Ok(Self {
+            _tag: StreamTag::BINARY,
+            version: value.version,
+            elements: value
+                .elements
+                .into_iter()
+                .map(Element::try_from)
+                .collect::<io::Result<_>>()?,
+        })
     }
 }
 
@@ -132,7 +160,7 @@ pub struct Element {
     #[serde(with = "compact")]
     id: Uuid,
     specialization: Option<Uuid>,
-    #[serde(skip)]
+    #[serde(default)]
     name: String,
     data_size: Option<usize>,
     data: Option<Vec<u8>>,
@@ -140,49 +168,93 @@ pub struct Element {
     field: Option<String>,
 }
 
-impl From<XMLElement> for Element {
-    fn from(value: XMLElement) -> Self {
-        Self {
+impl TryFrom<XMLElement> for Element {
+    type Error = io::Error;
+
+    fn try_from(value: XMLElement) -> io::Result<Self> {
+        let mut element = Self {
+            name_crc: value.field.as_deref().map(field_crc),
+            data: value_data(
+                &value.id,
+                value.value.as_ref(),
+                value.field.as_deref(),
+                &value.name,
+            )?,
             id: value.id,
-            name: value.name.to_owned(),
-            name_crc: {
-                if !value.name.is_empty() {
-                    Some(hash(value.name.as_bytes()))
-                } else {
-                    None
-                }
-            },
+            name: value.name,
+            field: value.field,
             version: value.version,
-            elements: value.elements.into_iter().map(Element::from).collect(),
+            specialization: value.specialization,
+            elements: value
+                .elements
+                .into_iter()
+                .map(Element::try_from)
+                .collect::<io::Result<_>>()?,
             ..Default::default()
-        }
+        };
+        element.update_flags();
+        Ok(element)
     }
 }
 
-impl From<JSONElement> for Element {
-    fn from(value: JSONElement) -> Self {
-        Self {
+impl TryFrom<JSONElement> for Element {
+    type Error = io::Error;
+
+    fn try_from(value: JSONElement) -> io::Result<Self> {
+        let mut element = Self {
+            name_crc: value.field.as_deref().map(field_crc),
+            data: value_data(
+                &value.id,
+                value.value.as_ref(),
+                value.field.as_deref(),
+                &value.name,
+            )?,
             id: value.id,
-            name: value.name.to_owned(),
-            // flags: todo!(),
-            name_crc: {
-                if !value.name.is_empty() {
-                    Some(hash(value.name.as_bytes()))
-                } else {
-                    None
-                }
-            },
+            name: value.name,
             field: value.field,
             version: value.version,
-            // data_size: todo!(),
-            // data: todo!(),
             specialization: value.specialization,
             elements: value
                 .elements
-                .map(|ele| ele.into_iter().map(Element::from).collect())
-                .unwrap_or_default(),
+                .unwrap_or_default()
+                .into_iter()
+                .map(Element::try_from)
+                .collect::<io::Result<_>>()?,
             ..Default::default()
-        }
+        };
+        element.update_flags();
+        Ok(element)
+    }
+}
+
+/// The binary data of an element's value, failing with the name of its field, or its class
+/// without one.
+fn value_data(
+    id: &Uuid,
+    value: Option<&Value>,
+    field: Option<&str>,
+    name: &str,
+) -> io::Result<Option<Vec<u8>>> {
+    value
+        .map(|value| {
+            uuid_data_from_value(id, value)
+                .map_err(|e| io::Error::new(e.kind(), format!("{}: {e}", field.unwrap_or(name))))
+        })
+        .transpose()
+}
+
+/// Field names are hashed lowercased. Fields missing from the hash dictionary are written out as
+/// their decimal CRC, which is used as is.
+fn field_crc(field: &str) -> u32 {
+    field
+        .parse::<u32>()
+        .unwrap_or_else(|_| crc32(&field.to_lowercase()))
+}
+
+fn unknown_field(element: &Element) -> Option<String> {
+    match &element.field {
+        Some(field) => Some(field.to_owned()),
+        None => element.name_crc.map(|crc| crc.to_string()),
     }
 }
 
@@ -198,6 +270,13 @@ pub struct XMLElement {
     version: Option<u8>,
     #[serde(rename = "@type", with = "uuid_braced_uppercase")]
     id: Uuid,
+    #[serde(
+        default,
+        rename = "@specialization",
+        with = "option_braced_uppercase",
+        skip_serializing_if = "Option::is_none"
+    )]
+    specialization: Option<Uuid>,
     #[serde(default, rename = "Class")]
     elements: Vec<XMLElement>,
 }
@@ -211,8 +290,8 @@ impl XMLElement {
 impl From<Element> for XMLElement {
     fn from(value: Element) -> Self {
         Self {
+            field: unknown_field(&value),
             name: value.name,
-            field: value.field,
             value: match value.data {
                 Some(data) if !data.is_empty() || value.elements.is_empty() => {
                     uuid_data_to_serialize(&value.id, &data, false).ok()
@@ -221,6 +300,7 @@ impl From<Element> for XMLElement {
             },
             version: value.version,
             id: value.id,
+            specialization: value.specialization,
             elements: value.elements.into_iter().map(XMLElement::from).collect(),
         }
     }
@@ -235,6 +315,7 @@ pub struct JSONElement {
     #[serde(rename = "typeName")]
     name: String,
     #[serde(
+        default,
         rename = "specializationTypeId",
         with = "option_braced_uppercase",
         skip_serializing_if = "Option::is_none"
@@ -251,7 +332,7 @@ pub struct JSONElement {
 impl From<Element> for JSONElement {
     fn from(value: Element) -> Self {
         Self {
-            field: value.field,
+            field: unknown_field(&value),
             id: value.id,
             name: value.name,
             specialization: value.specialization,
@@ -299,6 +380,35 @@ impl Element {
         None
     }
 
+    /// Recomputes the binary flags and data size from the element's contents.
+    fn update_flags(&mut self) {
+        let mut flags = ST_BINARYFLAG_ELEMENT_HEADER;
+        if self.name_crc.is_some() {
+            flags |= ST_BINARYFLAG_HAS_NAME;
+        }
+        if self.version.is_some() {
+            flags |= ST_BINARYFLAG_HAS_VERSION;
+        }
+
+        let size = self.data.as_ref().map_or(0, Vec::len);
+        self.data_size = None;
+        if size > 0 {
+            flags |= ST_BINARYFLAG_HAS_VALUE;
+            if size < ST_BINARY_VALUE_SIZE_MASK as usize {
+                flags |= size as u8;
+            } else {
+                flags |= ST_BINARYFLAG_EXTRA_SIZE_FIELD;
+                flags |= match size {
+                    ..=0xFF => 1,
+                    ..=0xFFFF => 2,
+                    _ => 4,
+                };
+            }
+            self.data_size = Some(size);
+        }
+        self.flags = flags;
+    }
+
     fn to_writer<W>(&self, writer: &mut W) -> io::Result<()>
     where
         W: Write,
@@ -333,9 +443,9 @@ impl Element {
             writer.write_all(data)?;
         }
 
-        self.elements
-            .iter()
-            .for_each(|ele| ele.to_writer(writer).unwrap());
+        for ele in &self.elements {
+            ele.to_writer(writer)?;
+        }
         writer.write_all(&[0])?;
 
         Ok(())
@@ -579,9 +689,9 @@ impl ObjectStream {
     {
         writer.write_all(&0u8.to_be_bytes())?;
         writer.write_all(&self.version.to_be_bytes())?;
-        self.elements
-            .iter()
-            .for_each(|ele| ele.to_writer(writer).unwrap());
+        for ele in &self.elements {
+            ele.to_writer(writer)?;
+        }
         writer.write_all(&[0])?;
 
         Ok(())
@@ -621,6 +731,56 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn binary_round_trip() -> io::Result<()> {
+        let mut binary = vec![0x00, 0x00, 0x00, 0x00, 0x03];
+        // name, version and a 4 byte inline value
+        binary.push(
+            ST_BINARYFLAG_ELEMENT_HEADER
+                | ST_BINARYFLAG_HAS_NAME
+                | ST_BINARYFLAG_HAS_VERSION
+                | ST_BINARYFLAG_HAS_VALUE
+                | 4,
+        );
+        binary.extend(crc32("test").to_be_bytes());
+        binary.push(1);
+        binary.extend(utils::types::INT.as_u128().to_be_bytes());
+        binary.extend(100i32.to_be_bytes());
+        binary.extend([0, 0]);
+
+        let object_stream = from_reader(&mut Cursor::new(&binary), None)?;
+        let json = serde_json::to_string(&JSONObjectStream::from(object_stream))?;
+        let json: JSONObjectStream = serde_json::from_str(&json)?;
+
+        let mut written = vec![];
+        ObjectStream::try_from(json)?.to_writer(&mut written)?;
+        assert_eq!(written, binary);
+        Ok(())
+    }
+
+    #[test]
+    fn assets_round_trip() -> io::Result<()> {
+        let json = r#"{"name":"ObjectStream","version":3,"Objects":[{"field":"icon","typeId":"{77A19D40-8731-4D3C-9041-1B43047366A4}","typeName":"Asset","value":{"assetId":{"guid":"{0B5A6B3C-8E57-4E4A-9D2B-0D8A1C9F2E11}","subId":1},"type":"{BCF5C79E-5E3B-4B7E-8F7E-6A2A5B3C2D10}","hint":"icons/sword.dds"}}]}"#;
+        let json: JSONObjectStream = serde_json::from_str(json)?;
+
+        let mut binary = vec![];
+        ObjectStream::try_from(json)?.to_writer(&mut binary)?;
+        let object_stream = from_reader(&mut Cursor::new(&binary), None)?;
+        let json = serde_json::to_value(JSONObjectStream::from(object_stream))?;
+        let value: Value = serde_json::from_str(json["Objects"][0]["value"].as_str().unwrap())?;
+        assert_eq!(value["assetId"]["subId"], 1);
+        assert_eq!(value["hint"], "icons/sword.dds");
+        Ok(())
+    }
+
+    #[test]
+    fn values_that_dont_encode_name_their_field() {
+        let json = r#"{"name":"ObjectStream","version":3,"Objects":[{"field":"count","typeId":"{72039442-EB38-4D42-A1AD-CB68F7E0EEF6}","typeName":"int","value":"many"}]}"#;
+        let json: JSONObjectStream = serde_json::from_str(json).unwrap();
+        let error = ObjectStream::try_from(json).unwrap_err();
+        assert!(error.to_string().starts_with("count: "));
+    }
+
     #[test]
     fn json() -> io::Result<()> {
         let json = r#"{"name":"ObjectStream","version":3,"Objects":[]}"#;
@@ -0,0 +1,89 @@
+//! Python bindings over [`nwtools_core`], built with maturin as the `nwtools` module.
+//!
+//! ```python
+//! import io, nwtools, pandas
+//!
+//! fs = nwtools.FileSystem(game_dir, filter="**/*.datasheet", formats={"datasheet": "csv"})
+//! path, data = fs.convert(fs.entries()[0])
+//! frame = pandas.read_csv(io.BytesIO(data))
+//! ```
+
+use nwtools_core::{Extractor, FileKind};
+use pyo3::{exceptions::PyValueError, prelude::*, types::PyBytes};
+use std::{collections::HashMap, path::PathBuf};
+
+/// An indexed New World install.
+#[pyclass(name = "FileSystem", frozen)]
+struct FileSystem {
+    extractor: Extractor,
+}
+
+#[pymethods]
+impl FileSystem {
+    /// Indexes the paks under `game_dir`, keeping the entries matching the comma separated
+    /// `filter` globs. `formats` maps content types, e.g. `datasheet`, to what entries of that
+    /// type are converted to, e.g. `csv`.
+    #[new]
+    #[pyo3(signature = (game_dir, filter=None, formats=None, mmap=false))]
+    fn new(
+        py: Python<'_>,
+        game_dir: PathBuf,
+        filter: Option<String>,
+        formats: Option<HashMap<String, String>>,
+        mmap: bool,
+    ) -> PyResult<Self> {
+        let mut builder = Extractor::builder().game_dir(game_dir).mmap(mmap);
+        if let Some(filter) = filter {
+            builder = builder.filter(filter);
+        }
+        for (kind, format) in formats.unwrap_or_default() {
+            builder = builder.format(kind.parse::<FileKind>()?, format);
+        }
+        let extractor = py.allow_threads(|| builder.build())?;
+        Ok(Self { extractor })
+    }
+
+    /// Paths of the indexed entries, sorted.
+    fn entries(&self) -> Vec<String> {
+        let mut entries = self
+            .extractor
+            .entries()
+            .map(|entry| entry.to_string_lossy().replace('\\', "/"))
+            .collect::<Vec<_>>();
+        entries.sort_unstable();
+        entries
+    }
+
+    /// The decompressed contents of `entry`.
+    fn read<'py>(&self, py: Python<'py>, entry: PathBuf) -> PyResult<Bound<'py, PyBytes>> {
+        let buf = py.allow_threads(|| self.extractor.read(&entry))?;
+        Ok(PyBytes::new_bound(py, &buf))
+    }
+
+    /// `entry` converted to the format configured for its type, with the path it would be
+    /// extracted to.
+    fn convert<'py>(
+        &self,
+        py: Python<'py>,
+        entry: PathBuf,
+    ) -> PyResult<(String, Bound<'py, PyBytes>)> {
+        let (path, buf) = py.allow_threads(|| self.extractor.convert(&entry))?;
+        Ok((
+            path.to_string_lossy().replace('\\', "/"),
+            PyBytes::new_bound(py, &buf),
+        ))
+    }
+
+    /// `entry` converted like [`convert`](Self::convert) to a text format, such as json or csv.
+    fn convert_text(&self, py: Python<'_>, entry: PathBuf) -> PyResult<String> {
+        let (_, buf) = py.allow_threads(|| self.extractor.convert(&entry))?;
+        String::from_utf8(buf).map_err(|e| PyValueError::new_err(e.to_string()))
+    }
+}
+
+#[pymodule]
+#[pyo3(name = "nwtools")]
+fn nwtools(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<FileSystem>()?;
+    Ok(())
+}
@@ -1,23 +1,78 @@
 mod app;
 mod events;
+mod metrics;
+mod progress;
 mod resources;
+mod serve;
+mod tui;
 
 use app::App;
-use assets::assetcatalog::AssetCatalog;
+use assets::{assetcatalog::AssetCatalog, AssetId};
+use cli::common::{
+    datasheet::Localization, filter::Filter, log::LogLevel, order::ExtractOrder,
+    progress::ProgressFormat,
+};
 use cli::{
-    commands::{test::TestCommands, Commands},
+    commands::{
+        bench::Bench,
+        cat::Cat,
+        catalog::Catalog,
+        checksum::Checksum,
+        codegen::CodegenCommands,
+        components::{Components, TableFormat},
+        deps::{Deps, GraphFormat},
+        diff::Diff,
+        extract::Extract,
+        grep::Grep,
+        hashes::HashesCommands,
+        icons::Icons,
+        info::Info,
+        join::JoinCommands,
+        locale::{LocaleCommands, LocaleFormat},
+        locate::Locate,
+        markers::Markers,
+        pack::Pack,
+        positions::Positions,
+        serve::Serve,
+        test::TestCommands,
+        tree::Tree,
+        validate::Validate,
+        workspace::WorkspaceCommands,
+        Commands,
+    },
     ARGS,
 };
-use cliclack::{spinner, ProgressBar};
+use cliclack::spinner;
+use datasheet::{
+    codegen::{self, Schema},
+    icons, loot, recipes,
+};
 use distribution::*;
-use file_system::{FileSystem, State};
+use file_system::{
+    bench,
+    decompressor::{asset_references, is_split_mip},
+    fingerprint::Fingerprint,
+    manifest::{self, Manifest},
+    material::texture_entry,
+    packer::Packer,
+    report::{self, Report},
+    verify::Verification,
+    workspace, FileSystem, State, ASSETS, PRIORITY,
+};
+use localization::export;
+use object_stream::entities::{components, markers, world};
+use progress::{outro, Progress, Snapshot, Spinner};
 use rayon::iter::{IntoParallelRefIterator, ParallelIterator};
+use regex::RegexBuilder;
+use serve::graphql;
 use std::{
-    path::PathBuf,
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque},
+    io::Write,
+    path::{Path, PathBuf},
     process::ExitCode,
     sync::{
         atomic::{AtomicU64, AtomicUsize, Ordering},
-        Arc, LazyLock, RwLock,
+        Arc, LazyLock, Mutex, RwLock,
     },
 };
 use tokio::{
@@ -26,22 +81,35 @@ use tokio::{
     task::{self},
     time::{self, Duration, Instant},
 };
-use tracing::instrument;
-use tracing_subscriber::FmtSubscriber;
+use tracing::{instrument, Level};
+use tracing_subscriber::{
+    filter::LevelFilter,
+    fmt::{self, format::FmtSpan},
+    prelude::*,
+};
+use tui::ErrorPanel;
 use utils::{format_bytes, format_duration};
 
 #[tokio::main]
 #[instrument]
 async fn main() -> tokio::io::Result<ExitCode> {
-    let subscriber = FmtSubscriber::builder().finish();
-    tracing::subscriber::set_global_default(subscriber).expect("setting default subscriber failed");
+    init_logging()?;
 
     let app = App::init();
 
     let token = app.cancel.clone();
     tokio::spawn(async move {
         ctrl_c().await.unwrap();
+        // every command polls the token to finish what it has in flight: extraction saves its
+        // manifest and checkpoint, pack writes the central directory, serve shuts down gracefully
         token.cancel();
+        if ARGS.progress() != ProgressFormat::TUI {
+            let _ = cliclack::log::warning(
+                "Finishing the work in progress, press Ctrl-C again to abort",
+            );
+        }
+        ctrl_c().await.unwrap();
+        abort();
     });
 
     run().await?;
@@ -49,19 +117,92 @@ async fn main() -> tokio::io::Result<ExitCode> {
     Ok(ExitCode::SUCCESS)
 }
 
+/// Exits right away on a second Ctrl-C, leaving the entries in flight unfinished.
+fn abort() -> ! {
+    if ARGS.interactive() {
+        let _ = cliclack::outro_cancel("Operation cancelled.");
+    }
+    std::process::exit(130)
+}
+
+/// Logs at `--log-level` to `--log-file`, timing every span, or to stderr. Warnings and errors
+/// also go to the error panel of `--progress tui`.
+fn init_logging() -> tokio::io::Result<()> {
+    let level = match ARGS.log_level {
+        LogLevel::ERROR => Level::ERROR,
+        LogLevel::WARN => Level::WARN,
+        LogLevel::INFO => Level::INFO,
+        LogLevel::DEBUG => Level::DEBUG,
+        LogLevel::TRACE => Level::TRACE,
+    };
+    let file = ARGS
+        .log_file
+        .as_ref()
+        .map(std::fs::File::create)
+        .transpose()?;
+    // the full screen interface owns stderr, warnings and errors go to its panel instead
+    let tui = ARGS.progress() == ProgressFormat::TUI;
+    let to_stderr = (file.is_none() && !tui).then(|| fmt::layer().with_writer(std::io::stderr));
+    let to_file = file.map(|file| {
+        fmt::layer()
+            .with_span_events(FmtSpan::CLOSE)
+            .with_ansi(false)
+            .with_writer(Mutex::new(file))
+    });
+    let to_panel = tui.then(|| {
+        fmt::layer()
+            .without_time()
+            .with_target(false)
+            .with_ansi(false)
+            .with_writer(|| ErrorPanel)
+            .with_filter(LevelFilter::WARN)
+    });
+
+    let subscriber = tracing_subscriber::registry()
+        .with(LevelFilter::from_level(level))
+        .with(to_stderr)
+        .with(to_file)
+        .with(to_panel);
+    tracing::subscriber::set_global_default(subscriber).expect("setting default subscriber failed");
+    Ok(())
+}
+
 #[instrument]
 async fn run() -> tokio::io::Result<()> {
     match &ARGS.command {
         Commands::Extract(extract) => {
             let cwd = extract.common.input.input.as_ref().unwrap();
-            let out = extract.common.output.output.as_ref().unwrap();
-            let filter = extract.common.filter.filter.as_ref();
-            run_extract(cwd, out, filter).await?
+            let mut out = extract.common.output.output.as_ref().unwrap();
+            if extract.workspace {
+                let dir = workspace::start(out, cwd, extract.label.as_deref())?;
+                cliclack::log::info(format!("Extracting into {}", dir.display()))?;
+                out = Box::leak(Box::new(dir));
+            }
+            if extract.patch {
+                out = Box::leak(Box::new(out.join("patch")));
+            }
+            let filter = &extract.common.filter;
+            if extract.dry_run {
+                return run_plan(cwd, out, filter).await;
+            }
+            let (failed, extracted) = run_extract(cwd, out, filter).await?;
+            if extract.verify {
+                run_verify(out, extracted).await?
+            }
+            if extract.watch {
+                run_watch(filter, extract.watch_interval).await?
+            }
+            if failed > 0 {
+                return Err(tokio::io::Error::other(format!(
+                    "{failed} entries failed to extract"
+                )));
+            }
         }
+        Commands::Datasheets(_) => unreachable!("parsed as an extraction"),
+        Commands::Completions(_) => unreachable!("written while parsing"),
         Commands::Test(test) => match &test.commands {
             TestCommands::Filter { input, filter } => {
                 let cwd = input.input.as_ref().unwrap();
-                let filter = filter.filter.as_ref();
                 run_test_filter(cwd, filter).await?
             }
             TestCommands::Distribution { input } => {
@@ -69,6 +210,137 @@ async fn run() -> tokio::io::Result<()> {
                 run_test_distribution(cwd).await?
             }
         },
+        Commands::Grep(grep) => {
+            let cwd = grep.input.input.as_ref().unwrap();
+            run_grep(cwd, grep).await?
+        }
+        Commands::Info(info) => {
+            let cwd = info.input.input.as_ref().unwrap();
+            run_info(cwd, info).await?
+        }
+        Commands::Tree(tree) => {
+            let cwd = tree.input.input.as_ref().unwrap();
+            run_tree(cwd, tree).await?
+        }
+        Commands::Validate(validate) => {
+            let cwd = validate.input.input.as_ref().unwrap();
+            run_validate(cwd, validate).await?
+        }
+        Commands::Bench(bench) => {
+            let cwd = bench.input.input.as_ref().unwrap();
+            run_bench(cwd, bench).await?
+        }
+        Commands::Diff(diff) => run_diff(diff).await?,
+        Commands::Pack(pack) => run_pack(pack).await?,
+        Commands::Serve(serve) => {
+            let cwd = serve.input.input.as_ref().unwrap();
+            run_serve(cwd, serve).await?
+        }
+        Commands::Cat(cat) => {
+            let cwd = cat.input.input.as_ref().unwrap();
+            run_cat(cwd, cat).await?
+        }
+        Commands::Locale(locale) => match &locale.commands {
+            LocaleCommands::Export {
+                input,
+                output,
+                locale,
+                format,
+            } => {
+                let cwd = input.input.as_ref().unwrap();
+                run_locale_export(cwd, output, locale, format).await?
+            }
+            LocaleCommands::Get {
+                input,
+                keys,
+                locale,
+                json,
+            } => {
+                let cwd = input.input.as_ref().unwrap();
+                let keys = keys.iter().map(|key| localization::normalize_key(key));
+                run_locale_lookup(cwd, locale, keys.collect(), None, *json).await?
+            }
+            LocaleCommands::Find {
+                input,
+                text,
+                locale,
+                keys,
+                json,
+            } => {
+                let cwd = input.input.as_ref().unwrap();
+                run_locale_lookup(
+                    cwd,
+                    locale,
+                    BTreeSet::new(),
+                    Some((text.as_str(), *keys)),
+                    *json,
+                )
+                .await?
+            }
+            LocaleCommands::Search {
+                text,
+                index,
+                locale,
+                limit,
+                json,
+            } => run_locale_search(text, index, locale.as_deref(), *limit, *json)?,
+        },
+        Commands::Workspace(workspace) => match &workspace.commands {
+            WorkspaceCommands::List { dir } => run_workspace_list(dir)?,
+            WorkspaceCommands::Prune { dir, keep, dry_run } => {
+                run_workspace_prune(dir, *keep, *dry_run)?
+            }
+            WorkspaceCommands::Diff {
+                dir,
+                old,
+                new,
+                json,
+            } => print_diff(&workspace::diff(dir, old, new)?, *json)?,
+        },
+        Commands::Hashes(hashes) => match &hashes.commands {
+            HashesCommands::Build { source, output } => {
+                run_hashes_build(source, output.as_ref()).await?
+            }
+        },
+        Commands::Catalog(catalog) => {
+            let cwd = catalog.input.input.as_ref().unwrap();
+            run_catalog(cwd, catalog).await?
+        }
+        Commands::Codegen(codegen) => {
+            let args = codegen.commands.args();
+            let cwd = args.input.input.as_ref().unwrap();
+            run_codegen(cwd, &codegen.commands).await?
+        }
+        Commands::Join(join) => {
+            let args = join.commands.args();
+            let cwd = args.input.input.as_ref().unwrap();
+            run_join(cwd, &join.commands).await?
+        }
+        Commands::Icons(icons) => {
+            let cwd = icons.input.input.as_ref().unwrap();
+            run_icons(cwd, icons).await?
+        }
+        Commands::Markers(markers) => {
+            let cwd = markers.input.input.as_ref().unwrap();
+            run_markers(cwd, markers).await?
+        }
+        Commands::Components(components) => {
+            let cwd = components.input.input.as_ref().unwrap();
+            run_components(cwd, components).await?
+        }
+        Commands::Positions(positions) => {
+            let cwd = positions.input.input.as_ref().unwrap();
+            run_positions(cwd, positions).await?
+        }
+        Commands::Deps(deps) => {
+            let cwd = deps.input.input.as_ref().unwrap();
+            run_deps(cwd, deps).await?
+        }
+        Commands::Checksum(checksum) => {
+            let cwd = checksum.input.input.as_ref().unwrap();
+            run_checksum(cwd, checksum).await?
+        }
+        Commands::Locate(locate) => run_locate(locate)?,
     };
 
     Ok(())
@@ -78,24 +350,44 @@ async fn initialize(
     cwd: &'static PathBuf,
     out: &'static PathBuf,
 ) -> tokio::io::Result<&'static FileSystem> {
-    let pb = cliclack::spinner();
-    pb.start("Initializing File System");
+    let pb = Spinner::start("Initializing File System");
     let fs = FileSystem::init(cwd, out, App::handle().cancel.clone()).await;
     pb.stop("File System Initialized");
 
-    let pb = cliclack::spinner();
-    pb.start("Initializing Asset Catalog");
+    let pb = Spinner::start("Initializing Asset Catalog");
     let data = fs.open("assetcatalog.catalog")?;
-    let _catalog = AssetCatalog::try_from(data.as_slice())?;
+    let catalog = AssetCatalog::init(data.as_slice())?;
+    ASSETS.get_or_init(|| {
+        Box::new(|guid, sub_id| {
+            catalog
+                .get_asset_info_by_id(AssetId {
+                    guid: *guid,
+                    sub_id,
+                })
+                .ok()
+                .map(|info| info.relative_path.to_owned())
+        })
+    });
+    if matches!(&ARGS.command, Commands::Extract(cmd) if cmd.order == ExtractOrder::CATALOG) {
+        PRIORITY.get_or_init(|| {
+            let mut priority = HashMap::new();
+            for (position, info) in catalog.iter().enumerate() {
+                priority
+                    .entry(normalize(&info.relative_path))
+                    .or_insert(position);
+            }
+            priority
+        });
+    }
     pb.stop("Asset Catalog Initialized");
     Ok(fs)
 }
 
 #[instrument]
-async fn run_test_filter(cwd: &'static PathBuf, filter: Option<&String>) -> tokio::io::Result<()> {
+async fn run_test_filter(cwd: &'static PathBuf, filter: &Filter) -> tokio::io::Result<()> {
     static OUT: LazyLock<PathBuf> = LazyLock::new(|| PathBuf::new());
     let fs = initialize(cwd, &OUT).await?;
-    let files = fs.files(filter);
+    let files = fs.filtered(filter)?;
     println!("Filter: {:?}", filter);
     for (file_path, (_full_path, _)) in files {
         println!("File: {}", file_path.display());
@@ -127,127 +419,1561 @@ async fn run_test_distribution(cwd: &'static PathBuf) -> tokio::io::Result<()> {
     Ok(())
 }
 
+#[instrument]
+async fn run_grep(cwd: &'static PathBuf, grep: &'static Grep) -> tokio::io::Result<()> {
+    static OUT: LazyLock<PathBuf> = LazyLock::new(|| PathBuf::new());
+    let re = RegexBuilder::new(&grep.pattern)
+        .case_insensitive(grep.ignore_case)
+        .build()
+        .map_err(tokio::io::Error::other)?;
+
+    let fs = initialize(cwd, &OUT).await?;
+    let files = fs.filtered(&grep.filter)?;
+
+    let mut matches = tokio::task::spawn_blocking(move || {
+        let matches = Mutex::new(vec![]);
+        let pb = cliclack::ProgressBar::new(files.len() as u64);
+        pb.start("Searching entries.");
+        files.par_iter().for_each(|(file_path, _)| {
+            pb.inc(1);
+            let entry = match fs.open(file_path) {
+                Ok(entry) => entry,
+                Err(e) => {
+                    tracing::warn!("{}: {e}", file_path.display());
+                    return;
+                }
+            };
+            let Ok(text) = std::str::from_utf8(&entry) else {
+                return;
+            };
+
+            let mut found = vec![];
+            for (i, line) in text.lines().enumerate() {
+                if !re.is_match(line) {
+                    continue;
+                }
+                if grep.files_with_matches {
+                    found.push(format!("{}", file_path.display()));
+                    break;
+                }
+                found.push(format!(
+                    "{}:{}: {}",
+                    file_path.display(),
+                    i + 1,
+                    line.trim()
+                ));
+            }
+
+            if !found.is_empty() {
+                matches
+                    .lock()
+                    .unwrap()
+                    .push((file_path.to_path_buf(), found));
+            }
+        });
+        pb.stop("Search Done.");
+        matches.into_inner().unwrap()
+    })
+    .await
+    .unwrap();
+
+    matches.sort_unstable_by(|(a, _), (b, _)| a.cmp(b));
+    let count = matches.len();
+    for (_, lines) in matches {
+        lines.iter().for_each(|line| println!("{line}"));
+    }
+
+    cliclack::outro(format!("{} entries matched {}", count, grep.pattern)).unwrap();
+    Ok(())
+}
+
+#[instrument]
+async fn run_info(cwd: &'static PathBuf, info: &'static Info) -> tokio::io::Result<()> {
+    static OUT: LazyLock<PathBuf> = LazyLock::new(|| PathBuf::new());
+    let fs = initialize(cwd, &OUT).await?;
+    let files = fs.filtered(&info.filter)?;
+
+    let pb = cliclack::spinner();
+    pb.start("Collecting Pak Statistics");
+    let stats = tokio::task::spawn_blocking(move || fs.stats(files, info.types))
+        .await
+        .unwrap()?;
+    pb.stop("Pak Statistics Collected");
+
+    let (mut entries, mut compressed, mut uncompressed) = (0, 0, 0);
+    for pak in &stats {
+        entries += pak.entries;
+        compressed += pak.compressed_size;
+        uncompressed += pak.uncompressed_size;
+
+        let methods = pak
+            .methods
+            .iter()
+            .map(|(method, count)| format!("{method}: {count}"))
+            .collect::<Vec<_>>()
+            .join(", ");
+        println!(
+            "{} | Entries: {} | Compressed: {} | Uncompressed: {} | Ratio: {:.2} | {}",
+            pak.path.strip_prefix(cwd).unwrap_or(&pak.path).display(),
+            pak.entries,
+            format_bytes(pak.compressed_size as f64),
+            format_bytes(pak.uncompressed_size as f64),
+            pak.ratio(),
+            methods,
+        );
+        if info.types {
+            let types = pak
+                .file_types
+                .iter()
+                .map(|(kind, count)| format!("{kind:?}: {count}"))
+                .collect::<Vec<_>>()
+                .join(", ");
+            println!("\t{types}");
+        }
+    }
+
+    cliclack::outro(format!(
+        "{} paks | {} entries | Compressed: {} | Uncompressed: {}",
+        stats.len(),
+        entries,
+        format_bytes(compressed as f64),
+        format_bytes(uncompressed as f64),
+    ))
+    .unwrap();
+    Ok(())
+}
+
+#[instrument]
+async fn run_tree(cwd: &'static PathBuf, tree: &'static Tree) -> tokio::io::Result<()> {
+    static OUT: LazyLock<PathBuf> = LazyLock::new(|| PathBuf::new());
+    let fs = initialize(cwd, &OUT).await?;
+    let files = fs.filtered(&tree.filter)?;
+
+    let pb = Spinner::start("Reading Entry Sizes");
+    let entries = tokio::task::spawn_blocking(move || fs.tree(files))
+        .await
+        .unwrap()?;
+    pb.clear();
+
+    let mut stdout = std::io::stdout().lock();
+    entries.render(tree.depth, &mut stdout)?;
+    stdout.flush()?;
+    Ok(())
+}
+
+#[instrument]
+async fn run_codegen(
+    cwd: &'static PathBuf,
+    codegen: &'static CodegenCommands,
+) -> tokio::io::Result<()> {
+    static OUT: LazyLock<PathBuf> = LazyLock::new(|| PathBuf::new());
+    let fs = initialize(cwd, &OUT).await?;
+    let args = codegen.args();
+    let files = fs.filtered(&args.filter)?;
+
+    let pb = Spinner::start("Reading Datasheets");
+    let sheets = tokio::task::spawn_blocking(move || fs.datasheets(files))
+        .await
+        .unwrap();
+    let schemas = Schema::merge(&sheets);
+    pb.stop(format!(
+        "Read {} datasheets of {} types",
+        sheets.len(),
+        schemas.len()
+    ));
+
+    let code = match codegen {
+        CodegenCommands::Rust(_) => codegen::rust(&schemas),
+        CodegenCommands::Ts(_) => {
+            codegen::typescript(&schemas, &codegen::localization_keys(&schemas, &sheets))
+        }
+    };
+    write_output(args.output.as_deref(), code.as_bytes())
+}
+
+#[instrument]
+async fn run_join(cwd: &'static PathBuf, join: &'static JoinCommands) -> tokio::io::Result<()> {
+    static OUT: LazyLock<PathBuf> = LazyLock::new(|| PathBuf::new());
+    let fs = initialize(cwd, &OUT).await?;
+    let args = join.args();
+    let files = fs.filtered(&args.filter)?;
+
+    let pb = Spinner::start("Reading Datasheets");
+    let locale = fs.localization(args.locale.to_string()).await;
+    let mut sheets = tokio::task::spawn_blocking(move || fs.datasheets(files))
+        .await
+        .unwrap();
+    for sheet in &mut sheets {
+        sheet.with_localization(Some(&locale));
+    }
+    pb.stop(format!("Read {} datasheets", sheets.len()));
+
+    let json = match join {
+        JoinCommands::Recipes(_) => serde_json::to_vec_pretty(&recipes::recipes(&sheets))?,
+        JoinCommands::Loot(_) => serde_json::to_vec_pretty(&loot::loot_tables(&sheets))?,
+    };
+    write_output(args.output.as_deref(), &json)
+}
+
+#[instrument]
+async fn run_icons(cwd: &'static PathBuf, args: &'static Icons) -> tokio::io::Result<()> {
+    static OUT: LazyLock<PathBuf> = LazyLock::new(|| PathBuf::new());
+    let fs = initialize(cwd, &OUT).await?;
+    let files = fs.filtered(&args.filter)?;
+
+    let pb = Spinner::start("Reading Datasheets");
+    let sheets = tokio::task::spawn_blocking(move || fs.datasheets(files))
+        .await
+        .unwrap();
+    let icons = icons::item_icons(&sheets);
+    pb.stop(format!("Found {} item icons", icons.len()));
+
+    std::fs::create_dir_all(&args.output)?;
+    let written = tokio::task::spawn_blocking(move || {
+        let pb = cliclack::ProgressBar::new(icons.len() as u64);
+        pb.start("Converting icons.");
+        let written = icons
+            .par_iter()
+            .filter_map(|icon| {
+                pb.inc(1);
+                let entry = texture_entry(&icon.path)?;
+                let (buf, path) = match fs.convert(&entry, Some(args.format.as_str())) {
+                    Ok(converted) => converted,
+                    Err(e) => {
+                        tracing::debug!("{}: {entry}: {e}", icon.id);
+                        return None;
+                    }
+                };
+                let ext = path.extension().unwrap_or_default().to_string_lossy();
+                let file = format!("{}.{ext}", icon.id);
+                match std::fs::write(args.output.join(&file), buf) {
+                    Ok(()) => Some((icon.id.clone(), file)),
+                    Err(e) => {
+                        tracing::warn!("{file}: {e}");
+                        None
+                    }
+                }
+            })
+            .collect::<BTreeMap<_, _>>();
+        pb.stop(format!(
+            "Converted {} of {} icons.",
+            written.len(),
+            icons.len()
+        ));
+        written
+    })
+    .await
+    .unwrap();
+
+    let index = serde_json::to_vec_pretty(&written)?;
+    std::fs::write(args.output.join("icons.json"), index)?;
+    cliclack::outro(format!("Written to {}", args.output.display()))?;
+    Ok(())
+}
+
+#[instrument]
+async fn run_markers(cwd: &'static PathBuf, args: &'static Markers) -> tokio::io::Result<()> {
+    static OUT: LazyLock<PathBuf> = LazyLock::new(|| PathBuf::new());
+    let fs = initialize(cwd, &OUT).await?;
+    let assets = AssetCatalog::get().expect("asset catalog is initialized");
+    let mut files = fs.filtered(&args.filter)?.into_keys().collect::<Vec<_>>();
+    files.sort_unstable();
+
+    let features = tokio::task::spawn_blocking(move || {
+        let pb = cliclack::ProgressBar::new(files.len() as u64);
+        pb.start("Placing markers.");
+        let features = files
+            .par_iter()
+            .flat_map_iter(|file_path| {
+                pb.inc(1);
+                let slice = file_path.to_string_lossy().replace('\\', "/");
+                let entities = fs
+                    .convert(file_path, Some("entities"))
+                    .ok()
+                    .and_then(|(buf, _)| serde_json::from_slice(&buf).ok())
+                    .unwrap_or(serde_json::Value::Null);
+
+                markers::markers(&entities, |name| args.matches.is_match(name))
+                    .into_iter()
+                    .map(|marker| {
+                        // the assets a marker's components reference, like the slice a spawner
+                        // spawns, by their path in the catalog
+                        let mut references = vec![];
+                        asset_references(&marker.entity["components"], &mut references);
+                        let references = references
+                            .into_iter()
+                            .filter_map(|(guid, sub_id)| {
+                                let info =
+                                    assets.get_asset_info_by_id(AssetId { guid, sub_id }).ok()?;
+                                Some(info.relative_path.to_string_lossy().replace('\\', "/"))
+                            })
+                            .collect::<BTreeSet<_>>();
+
+                        let mut properties = serde_json::Map::new();
+                        properties.insert("name".into(), marker.name().into());
+                        properties.insert("id".into(), marker.entity["id"].clone());
+                        properties.insert("slice".into(), slice.clone().into());
+                        properties.insert("components".into(), marker.components().into());
+                        properties.insert("elevation".into(), marker.elevation.into());
+                        properties.insert("assets".into(), references.into_iter().collect());
+                        marker.to_feature(properties)
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+        pb.stop(format!("Placed {} markers.", features.len()));
+        features
+    })
+    .await
+    .unwrap();
+
+    let collection = serde_json::json!({ "type": "FeatureCollection", "features": features });
+    write_output(
+        args.output.as_deref(),
+        &serde_json::to_vec_pretty(&collection)?,
+    )
+}
+
+#[instrument]
+async fn run_components(cwd: &'static PathBuf, args: &'static Components) -> tokio::io::Result<()> {
+    static OUT: LazyLock<PathBuf> = LazyLock::new(|| PathBuf::new());
+    let fs = initialize(cwd, &OUT).await?;
+    let mut files = fs.filtered(&args.filter)?.into_keys().collect::<Vec<_>>();
+    files.sort_unstable();
+
+    let rows = tokio::task::spawn_blocking(move || {
+        let pb = cliclack::ProgressBar::new(files.len() as u64);
+        pb.start("Collecting components.");
+        let rows = files
+            .par_iter()
+            .flat_map_iter(|file_path| {
+                pb.inc(1);
+                let slice = file_path.to_string_lossy().replace('\\', "/");
+                let entities = fs
+                    .convert(file_path, Some("entities"))
+                    .ok()
+                    .and_then(|(buf, _)| serde_json::from_slice(&buf).ok())
+                    .unwrap_or(serde_json::Value::Null);
+
+                let matches = |name: &str| args.types.iter().any(|t| components::is_type(name, t));
+                components::rows(&entities, matches)
+                    .into_iter()
+                    .map(|row| {
+                        let mut with_slice = serde_json::Map::new();
+                        with_slice.insert("slice".into(), slice.clone().into());
+                        with_slice.extend(row);
+                        with_slice
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+        pb.stop(format!("Collected {} components.", rows.len()));
+        rows
+    })
+    .await
+    .unwrap();
+
+    let buf = match args.format {
+        TableFormat::CSV => components::to_csv(&rows).into_bytes(),
+        TableFormat::JSON => serde_json::to_vec_pretty(&rows)?,
+    };
+    write_output(args.output.as_deref(), &buf)
+}
+
+#[instrument]
+async fn run_positions(cwd: &'static PathBuf, args: &'static Positions) -> tokio::io::Result<()> {
+    static OUT: LazyLock<PathBuf> = LazyLock::new(|| PathBuf::new());
+    let fs = initialize(cwd, &OUT).await?;
+    let assets = AssetCatalog::get().expect("asset catalog is initialized");
+    let mut files = fs.filtered(&args.filter)?.into_keys().collect::<Vec<_>>();
+    files.sort_unstable();
+
+    let matches = |entity: &serde_json::Value| {
+        let Some(regex) = &args.matches else {
+            return true;
+        };
+        let is_match =
+            |value: &serde_json::Value| value.as_str().is_some_and(|s| regex.is_match(s));
+        is_match(&entity["name"])
+            || entity["components"]
+                .as_array()
+                .into_iter()
+                .flatten()
+                .any(|component| is_match(&component["type"]))
+    };
+
+    let rows = tokio::task::spawn_blocking(move || {
+        let pb = cliclack::ProgressBar::new(files.len() as u64);
+        pb.start("Placing entities.");
+        let rows = files
+            .par_iter()
+            .flat_map_iter(|file_path| {
+                pb.inc(1);
+                let slice = file_path.to_string_lossy().replace('\\', "/");
+                let entities = fs
+                    .convert(file_path, Some("entities"))
+                    .ok()
+                    .and_then(|(buf, _)| serde_json::from_slice(&buf).ok())
+                    .unwrap_or(serde_json::Value::Null);
+
+                let by_id = world::by_id(&entities);
+                entities["entities"]
+                    .as_array()
+                    .into_iter()
+                    .flatten()
+                    .filter(|entity| matches(entity))
+                    .filter_map(|entity| {
+                        let world = world::world(entity, &by_id)?;
+                        let mut references = vec![];
+                        asset_references(&entity["components"], &mut references);
+                        let references = references
+                            .into_iter()
+                            .filter_map(|(guid, sub_id)| {
+                                let info =
+                                    assets.get_asset_info_by_id(AssetId { guid, sub_id }).ok()?;
+                                Some(info.relative_path.to_string_lossy().replace('\\', "/"))
+                            })
+                            .collect::<BTreeSet<_>>();
+
+                        let [x, y, z] = world.translation;
+                        let [qx, qy, qz, qw] = world.rotation;
+                        let mut row = serde_json::Map::new();
+                        row.insert("slice".into(), slice.clone().into());
+                        row.insert("entity_id".into(), entity["id"].clone());
+                        row.insert("entity".into(), entity["name"].clone());
+                        row.insert("assets".into(), references.into_iter().collect());
+                        for (column, value) in [("x", x), ("y", y), ("z", z)] {
+                            row.insert(column.into(), value.into());
+                        }
+                        for (column, value) in [("qx", qx), ("qy", qy), ("qz", qz), ("qw", qw)] {
+                            row.insert(column.into(), value.into());
+                        }
+                        Some(row)
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+        pb.stop(format!("Placed {} entities.", rows.len()));
+        rows
+    })
+    .await
+    .unwrap();
+
+    let buf = match args.format {
+        TableFormat::CSV => components::to_csv(&rows).into_bytes(),
+        TableFormat::JSON => serde_json::to_vec_pretty(&rows)?,
+    };
+    write_output(args.output.as_deref(), &buf)
+}
+
+/// Writes `buf` to `output`, or to stdout when not given.
+fn write_output(output: Option<&Path>, buf: &[u8]) -> tokio::io::Result<()> {
+    match output {
+        Some(path) => {
+            std::fs::write(path, buf)?;
+            cliclack::outro(format!("Written to {}", path.display()))?;
+        }
+        None => {
+            let mut stdout = std::io::stdout().lock();
+            stdout.write_all(buf)?;
+            stdout.flush()?;
+        }
+    }
+    Ok(())
+}
+
+#[instrument]
+async fn run_catalog(cwd: &'static PathBuf, catalog: &'static Catalog) -> tokio::io::Result<()> {
+    static OUT: LazyLock<PathBuf> = LazyLock::new(|| PathBuf::new());
+    initialize(cwd, &OUT).await?;
+    let assets = AssetCatalog::get().expect("asset catalog is initialized");
+
+    let mut found = 0;
+    for query in &catalog.queries {
+        let info = match query.parse::<AssetId>() {
+            Ok(id) => assets.get_asset_info_by_id(id),
+            Err(_) => {
+                let path = query.replace('\\', "/");
+                assets
+                    .get_asset_info_by_path(&path)
+                    .or_else(|_| assets.get_asset_info_by_path(path.to_lowercase()))
+            }
+        };
+        let Ok(info) = info else {
+            eprintln!("{query}: not found");
+            continue;
+        };
+        found += 1;
+
+        let path = info.relative_path.to_string_lossy().replace('\\', "/");
+        let mut buf = uuid::Uuid::encode_buffer();
+        let asset_type = info.asset_type.braced().encode_upper(&mut buf);
+        if catalog.json {
+            let mut buf = uuid::Uuid::encode_buffer();
+            let guid = info.asset_id.guid.braced().encode_upper(&mut buf);
+            println!(
+                "{}",
+                serde_json::json!({
+                    "assetId": { "guid": guid, "subId": info.asset_id.sub_id },
+                    "type": asset_type,
+                    "path": path,
+                    "size": info.size_bytes,
+                })
+            );
+        } else {
+            println!(
+                "{} | {} | {} | {}",
+                info.asset_id,
+                path,
+                asset_type,
+                format_bytes(info.size_bytes as f64)
+            );
+        }
+    }
+
+    cliclack::outro(format!(
+        "{found} of {} resolved | {} assets in catalog v{}",
+        catalog.queries.len(),
+        assets.len(),
+        assets.version()
+    ))
+    .unwrap();
+    Ok(())
+}
+
+#[instrument]
+async fn run_deps(cwd: &'static PathBuf, deps: &'static Deps) -> tokio::io::Result<()> {
+    static OUT: LazyLock<PathBuf> = LazyLock::new(|| PathBuf::new());
+    let fs = initialize(cwd, &OUT).await?;
+    let assets = AssetCatalog::get().expect("asset catalog is initialized");
+
+    // unresolved references keep their id so they still show up in the graph
+    let resolve = |(guid, sub_id)| {
+        let id = AssetId { guid, sub_id };
+        assets
+            .get_asset_info_by_id(id)
+            .map(|info| info.relative_path.to_string_lossy().replace('\\', "/"))
+            .unwrap_or_else(|_| id.to_string())
+    };
+
+    let files = fs.filtered(&deps.filter)?;
+    let graph = tokio::task::spawn_blocking(move || {
+        let mut graph: BTreeMap<String, BTreeSet<String>> = BTreeMap::new();
+        if deps.roots.is_empty() {
+            let pb = cliclack::ProgressBar::new(files.len() as u64);
+            pb.start("Collecting asset references.");
+            let edges = files
+                .par_iter()
+                .filter_map(|(file_path, _)| {
+                    pb.inc(1);
+                    let references = fs.asset_references(file_path).ok()?;
+                    if references.is_empty() {
+                        return None;
+                    }
+                    Some((
+                        file_path.to_string_lossy().replace('\\', "/"),
+                        references.into_iter().map(resolve).collect(),
+                    ))
+                })
+                .collect::<Vec<_>>();
+            pb.stop("Asset references collected.");
+            graph.extend(edges);
+        } else {
+            let pb = cliclack::spinner();
+            pb.start("Following asset references.");
+            let mut queue = deps
+                .roots
+                .iter()
+                .map(|root| root.to_string_lossy().replace('\\', "/"))
+                .collect::<VecDeque<_>>();
+            while let Some(entry) = queue.pop_front() {
+                if graph.contains_key(&entry) {
+                    continue;
+                }
+                let references = fs
+                    .asset_references(&entry)
+                    .unwrap_or_default()
+                    .into_iter()
+                    .map(resolve)
+                    .collect::<BTreeSet<_>>();
+                queue.extend(references.iter().cloned());
+                graph.insert(entry, references);
+            }
+            pb.stop("Asset references followed.");
+        }
+        graph
+    })
+    .await
+    .unwrap();
+
+    let string = match deps.format {
+        GraphFormat::DOT => {
+            let mut dot = String::from("digraph assets {\n");
+            for (node, references) in &graph {
+                if references.is_empty() {
+                    dot.push_str(&format!("    {:?};\n", node));
+                }
+                for reference in references {
+                    dot.push_str(&format!("    {:?} -> {:?};\n", node, reference));
+                }
+            }
+            dot.push_str("}\n");
+            dot
+        }
+        GraphFormat::JSON => serde_json::to_string_pretty(&graph)?,
+    };
+    match &deps.output {
+        Some(output) => std::fs::write(output, string)?,
+        None => print!("{string}"),
+    }
+
+    cliclack::outro(format!(
+        "{} assets | {} references",
+        graph.len(),
+        graph.values().map(BTreeSet::len).sum::<usize>()
+    ))
+    .unwrap();
+    Ok(())
+}
+
+#[instrument]
+async fn run_validate(cwd: &'static PathBuf, validate: &'static Validate) -> tokio::io::Result<()> {
+    static OUT: LazyLock<PathBuf> = LazyLock::new(|| PathBuf::new());
+    let fs = initialize(cwd, &OUT).await?;
+    let files = fs.filtered(&validate.filter)?;
+    let len = files.len();
+
+    let errors = tokio::task::spawn_blocking(move || {
+        let pb = cliclack::ProgressBar::new(len as u64);
+        pb.start("Validating entries.");
+        let errors = fs.validate(files, |entry| {
+            pb.set_message(format!("{}", entry.display()));
+            pb.inc(1);
+        });
+        pb.stop("Validation Done.");
+        errors
+    })
+    .await
+    .unwrap();
+
+    for error in &errors {
+        println!(
+            "{} ({}): {}",
+            error.entry.display(),
+            error.pak.strip_prefix(cwd).unwrap_or(&error.pak).display(),
+            error.error
+        );
+    }
+
+    if !errors.is_empty() {
+        cliclack::outro_cancel(format!(
+            "{}/{} entries failed validation",
+            errors.len(),
+            len
+        ))
+        .unwrap();
+        return Err(tokio::io::Error::other("Validation failed"));
+    }
+
+    cliclack::outro(format!("Validated {} entries", len)).unwrap();
+    Ok(())
+}
+
+#[instrument]
+async fn run_bench(cwd: &'static PathBuf, args: &'static Bench) -> tokio::io::Result<()> {
+    static OUT: LazyLock<PathBuf> = LazyLock::new(|| PathBuf::new());
+    let fs = initialize(cwd, &OUT).await?;
+    let files = fs.filtered(&args.filter)?;
+
+    let pb = Spinner::start("Sampling Entries");
+    let entries = tokio::task::spawn_blocking(move || bench::sample(files, args.sample))
+        .await
+        .unwrap()?;
+    pb.stop(format!("Sampled {} entries", entries.len()));
+
+    let jobs = match args.jobs.is_empty() {
+        true => vec![std::thread::available_parallelism().map_or(4, |n| n.get())],
+        false => args.jobs.clone(),
+    };
+    let entries = Arc::new(entries);
+    let mut results = vec![];
+    for threads in jobs {
+        let pb = Spinner::start(&format!("Decompressing on {threads} threads"));
+        let sample = Arc::clone(&entries);
+        let result = tokio::task::spawn_blocking(move || bench::run(&sample, threads))
+            .await
+            .unwrap()?;
+        pb.stop(format!(
+            "{threads} threads: {}/s",
+            format_bytes(result.bytes_per_sec)
+        ));
+        results.push(result);
+    }
+
+    if args.json {
+        println!("{}", serde_json::to_string_pretty(&results)?);
+        return Ok(());
+    }
+    for result in &results {
+        println!(
+            "{} threads | Entries: {} | Errors: {} | {} in {} | {}/s",
+            result.threads,
+            result.entries,
+            result.errors,
+            format_bytes(result.size as f64),
+            format_duration(Duration::from_secs_f64(result.seconds)),
+            format_bytes(result.bytes_per_sec),
+        );
+        for group in &result.groups {
+            println!(
+                "\t{} {:?} | Entries: {} | Compressed: {} | Uncompressed: {} | {}/s per thread",
+                group.method,
+                group.kind,
+                group.entries,
+                format_bytes(group.compressed_size as f64),
+                format_bytes(group.size as f64),
+                format_bytes(group.bytes_per_sec),
+            );
+        }
+    }
+
+    if let Some(best) = results
+        .iter()
+        .max_by(|a, b| a.bytes_per_sec.total_cmp(&b.bytes_per_sec))
+    {
+        cliclack::outro(format!(
+            "Fastest with {} threads at {}/s",
+            best.threads,
+            format_bytes(best.bytes_per_sec)
+        ))?;
+    }
+    Ok(())
+}
+
+#[instrument]
+async fn run_diff(diff: &'static Diff) -> tokio::io::Result<()> {
+    let pb = cliclack::spinner();
+    pb.start("Indexing Entries");
+    let (old, new) = tokio::task::spawn_blocking(move || {
+        rayon::join(
+            || file_system::diff::index(&diff.old, &diff.filter),
+            || file_system::diff::index(&diff.new, &diff.filter),
+        )
+    })
+    .await
+    .unwrap();
+    let (old, new) = (old?, new?);
+    pb.stop(format!(
+        "Indexed {} old and {} new entries",
+        old.len(),
+        new.len()
+    ));
+
+    let mut report = file_system::diff::Diff::between(&old, &new);
+    if diff.datasheets {
+        let pb = cliclack::spinner();
+        pb.start("Comparing Datasheets");
+        report = tokio::task::spawn_blocking(move || report.with_datasheets(&diff.old, &diff.new))
+            .await
+            .unwrap();
+        pb.stop(format!(
+            "Compared {} changed datasheets",
+            report.datasheets.len()
+        ));
+    }
+
+    print_diff(&report, diff.json)
+}
+
+/// Prints the added, removed and changed entries of `report`, and the changes of its datasheets.
+fn print_diff(report: &file_system::diff::Diff, json: bool) -> tokio::io::Result<()> {
+    if json {
+        println!("{}", serde_json::to_string_pretty(report)?);
+    } else {
+        report
+            .added
+            .iter()
+            .for_each(|path| println!("+ {}", path.display()));
+        report
+            .removed
+            .iter()
+            .for_each(|path| println!("- {}", path.display()));
+        report.changed.iter().for_each(|changed| {
+            println!(
+                "~ {} ({} -> {})",
+                changed.path.display(),
+                format_bytes(changed.old.size as f64),
+                format_bytes(changed.new.size as f64)
+            )
+        });
+        for (path, sheet) in &report.datasheets {
+            println!("\n{}", path.display());
+            sheet
+                .added_columns
+                .iter()
+                .for_each(|column| println!("  + column {column}"));
+            sheet
+                .removed_columns
+                .iter()
+                .for_each(|column| println!("  - column {column}"));
+            sheet.retyped_columns.iter().for_each(|retyped| {
+                println!(
+                    "  ~ column {} ({} -> {})",
+                    retyped.column, retyped.old, retyped.new
+                )
+            });
+            sheet
+                .added_rows
+                .iter()
+                .for_each(|key| println!("  + {key}"));
+            sheet
+                .removed_rows
+                .iter()
+                .for_each(|key| println!("  - {key}"));
+            for row in &sheet.changed_rows {
+                println!("  ~ {}", row.key);
+                row.cells.iter().for_each(|cell| {
+                    println!("      {}: {} -> {}", cell.column, cell.old, cell.new)
+                });
+            }
+        }
+    }
+
+    cliclack::outro(format!(
+        "Added: {} | Removed: {} | Changed: {}",
+        report.added.len(),
+        report.removed.len(),
+        report.changed.len()
+    ))
+    .unwrap();
+    Ok(())
+}
+
+fn run_workspace_list(dir: &Path) -> tokio::io::Result<()> {
+    for version in workspace::versions(dir)? {
+        println!(
+            "{}\t{}\t{}",
+            version.label,
+            version.build.as_deref().unwrap_or("-"),
+            version.created
+        );
+    }
+    Ok(())
+}
+
+fn run_workspace_prune(dir: &Path, keep: usize, dry_run: bool) -> tokio::io::Result<()> {
+    let pruned = workspace::prune(dir, keep, dry_run)?;
+    for version in &pruned {
+        println!("{}", version.path.display());
+    }
+    let verb = if dry_run { "Would delete" } else { "Deleted" };
+    cliclack::outro(format!("{verb} {} versions", pruned.len()))?;
+    Ok(())
+}
+
+#[instrument]
+async fn run_hashes_build(source: &PathBuf, output: Option<&PathBuf>) -> tokio::io::Result<()> {
+    let Some(output) = output.cloned().or_else(utils::lumberyard::dictionary_path) else {
+        return Err(tokio::io::Error::new(
+            tokio::io::ErrorKind::NotFound,
+            "No config directory to write the dictionary to, pass --output",
+        ));
+    };
+
+    let start = Instant::now();
+    let pb = spinner();
+    pb.start(format!("Scanning {}", source.display()));
+    let ly = utils::lumberyard::parse_lumberyard_source(source).await?;
+    pb.stop("Scanning Done.");
+
+    if let Some(parent) = output.parent() {
+        tokio::fs::create_dir_all(parent).await?;
+    }
+    let json = serde_json::to_vec_pretty(&ly)?;
+    tokio::fs::write(&output, json).await?;
+
+    cliclack::outro(format!(
+        "Wrote {} names and {} type ids to {} in {}",
+        ly.crcs.len(),
+        ly.uuids.len(),
+        output.display(),
+        format_duration(start.elapsed()),
+    ))
+    .unwrap();
+
+    Ok(())
+}
+
+#[instrument]
+async fn run_checksum(cwd: &'static PathBuf, checksum: &'static Checksum) -> tokio::io::Result<()> {
+    let since = checksum.since.as_ref().map(Fingerprint::read).transpose()?;
+
+    let pb = cliclack::spinner();
+    pb.start("Hashing Paks");
+    let fingerprint = tokio::task::spawn_blocking(move || Fingerprint::of(cwd, checksum.entries))
+        .await
+        .unwrap()?;
+    pb.stop(format!("Hashed {} paks", fingerprint.paks.len()));
+
+    if let Some(since) = since {
+        let changes = since.changes(&fingerprint);
+        changes
+            .added
+            .iter()
+            .for_each(|path| println!("+ {}", path.display()));
+        changes
+            .removed
+            .iter()
+            .for_each(|path| println!("- {}", path.display()));
+        changes
+            .changed
+            .iter()
+            .for_each(|path| println!("~ {}", path.display()));
+
+        if let (Some(old), Some(new)) = (since.entries(), fingerprint.entries()) {
+            let report = file_system::diff::Diff::between(&old, &new);
+            cliclack::log::info(format!(
+                "Entries: {} added, {} removed, {} changed",
+                report.added.len(),
+                report.removed.len(),
+                report.changed.len()
+            ))?;
+        }
+        if changes.is_empty() {
+            cliclack::log::info("No paks changed")?;
+        }
+    }
+
+    fingerprint.save(&checksum.output)?;
+    cliclack::outro(format!(
+        "Fingerprint written to {}",
+        checksum.output.display()
+    ))?;
+    Ok(())
+}
+
+fn run_locate(locate: &Locate) -> tokio::io::Result<()> {
+    let installs = cli::locate::installs();
+    if installs.is_empty() {
+        return Err(tokio::io::Error::new(
+            tokio::io::ErrorKind::NotFound,
+            "No New World install found in the Steam or Amazon Games libraries",
+        ));
+    }
+    let take = if locate.all { installs.len() } else { 1 };
+    for path in installs.iter().take(take) {
+        println!("{}", path.display());
+    }
+    Ok(())
+}
+
+#[instrument]
+async fn run_pack(pack: &'static Pack) -> tokio::io::Result<()> {
+    let mut files = walkdir::WalkDir::new(&pack.input)
+        .into_iter()
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_type().is_file())
+        .map(|e| e.into_path())
+        .collect::<Vec<_>>();
+    files.sort_unstable_by(|a, b| natord::compare(&a.to_string_lossy(), &b.to_string_lossy()));
+
+    let (len, start) = (files.len(), Instant::now());
+    let (packed, stored, bytes) = tokio::task::spawn_blocking(move || -> tokio::io::Result<_> {
+        let file = std::fs::File::create(&pack.output)?;
+        let mut packer = Packer::new(std::io::BufWriter::new(file), &pack.method);
+        let (mut packed, mut stored, mut bytes) = (0, 0, 0);
+
+        let pb = cliclack::ProgressBar::new(len as u64);
+        pb.start("Packing files.");
+        for path in files {
+            if App::handle().cancel.is_cancelled() {
+                // a pak without all of its files would pass for a complete one
+                drop(packer);
+                std::fs::remove_file(&pack.output)?;
+                pb.stop("Packing cancelled.");
+                return Ok((packed, stored, bytes));
+            }
+            let name = path.strip_prefix(&pack.input).unwrap().to_string_lossy();
+            let data = std::fs::read(&path)?;
+            if packer.add(&name, &data)? == zip::CompressionMethod::Stored {
+                stored += 1;
+            }
+            packed += 1;
+            bytes += data.len() as u64;
+            pb.set_message(format!("{name}"));
+            pb.inc(1);
+        }
+        packer.finish()?.flush()?;
+        pb.stop("Packing Done.");
+        Ok((packed, stored, bytes))
+    })
+    .await
+    .unwrap()?;
+
+    if packed < len {
+        cliclack::outro_cancel(format!(
+            "Cancelled after packing {}/{} files, removed {}",
+            packed,
+            len,
+            pack.output.display()
+        ))
+        .unwrap();
+        return Ok(());
+    }
+
+    cliclack::outro(format!(
+        "Packed {} files ({} stored) into {} in {}. Bytes: {}",
+        packed,
+        stored,
+        pack.output.display(),
+        format_duration(start.elapsed()),
+        format_bytes(bytes as f64)
+    ))
+    .unwrap();
+    Ok(())
+}
+
 #[instrument]
 async fn run_extract(
     cwd: &'static PathBuf,
     out: &'static PathBuf,
-    filter: Option<&String>,
+    filter: &Filter,
+) -> tokio::io::Result<(usize, HashSet<&'static PathBuf>)> {
+    let fs = initialize(cwd, out).await?;
+    let mut files = fs.filtered(filter)?;
+    if let Commands::Extract(Extract {
+        since: Some(since), ..
+    }) = &ARGS.command
+    {
+        let path = if since.is_dir() {
+            since.join(manifest::FILE_NAME)
+        } else {
+            since.to_owned()
+        };
+        let manifest = Manifest::read(&path)?;
+        let pb = Spinner::start("Comparing Entries");
+        let selected = files.len();
+        files = tokio::task::spawn_blocking(move || fs.changed_since(files, &manifest))
+            .await
+            .unwrap()?;
+        pb.stop(format!(
+            "{} of {selected} entries changed since {}",
+            files.len(),
+            path.display()
+        ));
+    }
+    for conflict in fs.conflicts() {
+        if !files.contains_key(&conflict.entry) {
+            continue;
+        }
+        let sources = conflict
+            .sources
+            .iter()
+            .map(|source| {
+                format!(
+                    "{} ({:08x}, {})",
+                    source
+                        .pak
+                        .strip_prefix(cwd)
+                        .unwrap_or(&source.pak)
+                        .display(),
+                    source.info.crc32,
+                    format_bytes(source.info.size as f64)
+                )
+            })
+            .collect::<Vec<_>>();
+        tracing::warn!(
+            "{} differs between paks, extracting the last of {}",
+            conflict.entry.display(),
+            sources.join(", ")
+        );
+    }
+    let extracted = files.keys().copied().collect();
+    Ok((extract(fs, files).await?, extracted))
+}
+
+#[instrument]
+async fn run_plan(
+    cwd: &'static PathBuf,
+    out: &'static PathBuf,
+    filter: &Filter,
 ) -> tokio::io::Result<()> {
     let fs = initialize(cwd, out).await?;
-    let files = fs.files(filter);
-    let len = files.len() as u64;
+    let files = fs.filtered(filter)?;
 
-    let multi_pb = Arc::new(cliclack::MultiProgress::new("Extracting Pak(s)"));
-    let all = Arc::new(multi_pb.add(ProgressBar::new(len)));
-    let stats_pb = Arc::new(multi_pb.add(spinner()));
-    let pak_pb = Arc::new(multi_pb.add(spinner()));
-    let file_pb = Arc::new(multi_pb.add(spinner()));
+    let pb = Spinner::start("Planning extraction");
+    let plan = tokio::task::spawn_blocking(move || fs.plan(files))
+        .await
+        .unwrap();
+    pb.stop("Extraction planned");
 
-    all.start("");
-    stats_pb.start("");
-    file_pb.start("");
-    pak_pb.start("");
+    println!("{}", serde_json::to_string_pretty(&plan)?);
+
+    outro(format!(
+        "{} entries | Uncompressed: {} | Compressed: {}",
+        plan.len(),
+        format_bytes(plan.iter().map(|entry| entry.size).sum::<u64>() as f64),
+        format_bytes(plan.iter().map(|entry| entry.compressed_size).sum::<u64>() as f64),
+    ));
+    Ok(())
+}
+
+/// Checks the output of an extraction against the assets in the catalog among `files`, the
+/// entries it extracted once `--since` narrowed them.
+#[instrument(skip(files))]
+async fn run_verify(
+    out: &'static PathBuf,
+    files: HashSet<&'static PathBuf>,
+) -> tokio::io::Result<()> {
+    let assets = AssetCatalog::get().expect("asset catalog is initialized");
+
+    let pb = Spinner::start("Verifying extraction");
+    let verification = tokio::task::spawn_blocking(move || {
+        let catalog = assets
+            .iter()
+            .map(|info| normalize(&info.relative_path))
+            .collect::<BTreeSet<_>>();
+        // split mips are merged into their texture
+        let expected = files.iter().copied().filter(|entry| {
+            catalog.contains(&normalize(entry))
+                && !(is_split_mip(entry) && files.contains(&&entry.with_extension("")))
+        });
+        Verification::of(out, expected)
+    })
+    .await
+    .unwrap()?;
+    pb.stop("Verification Done.");
+
+    for entry in &verification.missing {
+        println!("missing {}", entry.display());
+    }
+    for path in &verification.orphaned {
+        println!(
+            "orphaned {}",
+            path.strip_prefix(out).unwrap_or(path).display()
+        );
+    }
+
+    if !verification.is_empty() {
+        return Err(tokio::io::Error::other(format!(
+            "Verification failed: {} missing, {} orphaned",
+            verification.missing.len(),
+            verification.orphaned.len()
+        )));
+    }
+    outro("Every selected catalog asset was extracted");
+    Ok(())
+}
+
+/// Lowercase with forward slashes, as catalog paths are compared.
+fn normalize(path: &std::path::Path) -> String {
+    path.to_string_lossy().replace('\\', "/").to_lowercase()
+}
+
+/// Polls the pak directory and re-extracts the entries of paks whose modification time or size
+/// changed once they stop changing, until the app is cancelled.
+#[instrument]
+async fn run_watch(filter: &Filter, interval: u64) -> tokio::io::Result<()> {
+    let fs = file_system::FILESYSTEM.get().unwrap();
+    let cancel = &App::handle().cancel;
+    let mut settled = fs.snapshot();
+    let mut previous = settled.clone();
+
+    loop {
+        let pb = Spinner::start("Watching for game updates");
+        tokio::select! {
+            _ = cancel.cancelled() => {
+                pb.stop("Stopped watching");
+                return Ok(());
+            },
+            _ = time::sleep(Duration::from_secs(interval)) => {},
+        };
+
+        let current = fs.snapshot();
+        if current != previous {
+            previous = current;
+            pb.stop("Paks are changing, waiting for them to settle");
+            continue;
+        }
+
+        let changed = current
+            .iter()
+            .filter(|(pak, meta)| settled.get(*pak) != Some(meta))
+            .map(|(pak, _)| pak.to_owned())
+            .collect::<Vec<_>>();
+        settled = current;
+        if changed.is_empty() {
+            pb.clear();
+            continue;
+        }
+
+        pb.stop(format!("{} pak(s) changed", changed.len()));
+        let files = tokio::task::block_in_place(|| fs.reindex(&changed, filter))?;
+        extract(fs, files).await?;
+    }
+}
+
+/// Extracts `files`, returning how many failed.
+async fn extract(
+    fs: &'static FileSystem,
+    files: HashMap<&'static PathBuf, &'static (PathBuf, String)>,
+) -> tokio::io::Result<usize> {
+    let len = files.len() as u64;
+    let progress = Arc::new(Progress::new(len)?);
 
     let bytes = Arc::new(AtomicU64::new(0));
     let processed = Arc::new(AtomicU64::new(0));
-
-    let processed_clone = processed.clone();
-    let bytes_cloned = Arc::clone(&bytes);
     let start = Instant::now();
-    let all_pb = Arc::clone(&all);
     let state = Arc::new(RwLock::new(State {
         active: Arc::new(AtomicUsize::new(0)),
         max: Arc::new(AtomicUsize::new(0)),
         size: Arc::new(AtomicUsize::new(0)),
+        skipped: Arc::new(AtomicUsize::new(0)),
+        report: Arc::new(Report::default()),
+        total_bytes: Arc::new(AtomicU64::new(0)),
+        read_bytes: Arc::new(AtomicU64::new(0)),
     }));
-    let state_clone = state.clone();
-    let stats_pb_clone = stats_pb.clone();
+    let snapshot = {
+        let bytes = Arc::clone(&bytes);
+        let processed = Arc::clone(&processed);
+        let state = Arc::clone(&state);
+        move || {
+            let elapsed = start.elapsed();
+            let bytes = bytes.load(Ordering::Relaxed);
+            let processed = processed.load(Ordering::Relaxed);
+            let state = state.read().unwrap();
+            let read = state.read_bytes.load(Ordering::Relaxed);
+            let total_bytes = state.total_bytes.load(Ordering::Relaxed);
+            // by bytes, a few huge entries left take as long as thousands of small ones; counts
+            // only until the paks are indexed or when their entries are all empty
+            let eta = if total_bytes > 0 && read > 0 {
+                let remaining = total_bytes.saturating_sub(read);
+                Duration::from_secs_f64(elapsed.as_secs_f64() * remaining as f64 / read as f64)
+            } else if processed < len {
+                let remaining = len - processed;
+                let time_per_file = elapsed.as_secs_f64() / (processed + 1) as f64;
+                Duration::from_secs_f64(time_per_file * remaining as f64)
+            } else {
+                Duration::ZERO
+            };
+            Snapshot {
+                processed,
+                total: len,
+                read,
+                total_bytes,
+                bytes,
+                bytes_per_sec: bytes as f64 / elapsed.as_secs_f64(),
+                eta,
+                tasks: state.active.load(Ordering::Relaxed),
+                max_tasks: state.max.load(Ordering::Relaxed),
+                last_size: state.size.load(Ordering::Relaxed),
+            }
+        }
+    };
 
+    let done = App::handle().cancel.child_token();
+    let ticker_done = done.clone();
+    let ticker_progress = Arc::clone(&progress);
+    let ticker_snapshot = snapshot.clone();
     task::spawn(async move {
         let mut interval = time::interval(Duration::from_millis(1000 / 10));
 
         loop {
-            if App::handle().cancel.is_cancelled() {
+            if ticker_done.is_cancelled() {
                 break;
             };
 
             interval.tick().await;
-            let elapsed = start.elapsed();
-            let bytes_prcocessed = bytes_cloned.load(Ordering::Relaxed);
-            let bytes_per_sec = bytes_prcocessed as f64 / elapsed.as_secs_f64();
-            let processed_count = processed_clone.load(Ordering::Relaxed);
-            let eta = if processed_count < len {
-                let remaining = len - processed_count;
-                let time_per_file = elapsed.as_secs_f64() / (processed_count + 1) as f64;
-                Duration::from_secs_f64(time_per_file * remaining as f64)
-            } else {
-                Duration::ZERO
-            };
-
-            let state = state_clone.read().unwrap();
-
-            stats_pb_clone.set_message(format!(
-                "#Tasks: {} | Max Tasks: {} | #Last Bytes Written: {} ",
-                state.active.load(Ordering::Relaxed),
-                state.max.load(Ordering::Relaxed),
-                format_bytes(state.size.load(Ordering::Relaxed) as f64),
-            ));
-            all_pb.set_message(format!(
-                "ETA: {} | Throughput: {}/s",
-                format_duration(eta),
-                format_bytes(bytes_per_sec),
-            ));
+            ticker_progress.tick(&ticker_snapshot());
         }
     });
 
-    let multi_pb_clone = multi_pb.clone();
+    if let Commands::Extract(cmd) = &ARGS.command {
+        if let Some(addr) = &cmd.metrics {
+            let listener = tokio::net::TcpListener::bind(addr.as_str()).await?;
+            let snapshot = snapshot.clone();
+            let state = Arc::clone(&state);
+            let router = metrics::router(move || {
+                let snapshot = snapshot();
+                let state = state.read().unwrap();
+                let skipped = state.skipped.load(Ordering::Relaxed) as u64;
+                metrics::render(&snapshot, skipped, state.report.errors())
+            });
+            let metrics_done = done.clone();
+            task::spawn(async move {
+                let served = axum::serve(listener, router)
+                    .with_graceful_shutdown(async move { metrics_done.cancelled().await })
+                    .await;
+                if let Err(e) = served {
+                    tracing::warn!("metrics: {e}");
+                }
+            });
+        }
+    }
+
+    let abort_progress = Arc::clone(&progress);
     tokio::spawn(async move {
         App::handle().cancel.cancelled().await;
-        multi_pb_clone.println("Aborting...");
-        multi_pb_clone.cancel();
+        abort_progress.abort();
     });
 
-    let all_pb = Arc::clone(&all);
+    let entry_progress = Arc::clone(&progress);
     let cloned_processed = processed.clone();
     let bytes_cloned = Arc::clone(&bytes);
-    let file_pb_clone = file_pb.clone();
-    let pak_pb_clone = pak_pb.clone();
-
-    fs.all(files, state.clone(), move |pak, entry, len, idx, size| {
-        bytes_cloned.fetch_add(size, Ordering::Relaxed);
-        all_pb.inc(1);
-        pak_pb_clone.set_message(format!(
-            "{} ({idx}/{len})",
-            pak.file_name().unwrap().to_str().unwrap()
+
+    let failed = fs
+        .all(files, state.clone(), move |pak, entry, len, idx, size| {
+            bytes_cloned.fetch_add(size, Ordering::Relaxed);
+            entry_progress.entry(&pak, entry, idx, len);
+            cloned_processed.fetch_add(1, Ordering::Relaxed);
+
+            Ok(())
+        })
+        .await?;
+    done.cancel();
+
+    let skipped = state.read().unwrap().skipped.load(Ordering::Relaxed);
+    progress.finish(&snapshot(), skipped, start.elapsed());
+
+    if matches!(&ARGS.command, Commands::Extract(cmd) if cmd.pak_stats) {
+        let paks = state.read().unwrap().report.paks();
+        for pak in &paks {
+            eprintln!(
+                "{} | Entries: {} | Errors: {} | In: {} | Out: {} | Ratio: {:.2} | {}/s",
+                pak.pak.strip_prefix(fs.cwd()).unwrap_or(&pak.pak).display(),
+                pak.entries,
+                pak.errors,
+                format_bytes(pak.bytes_in as f64),
+                format_bytes(pak.bytes_out as f64),
+                pak.ratio,
+                format_bytes(pak.bytes_per_sec),
+            );
+        }
+        std::fs::create_dir_all(fs.out_dir())?;
+        std::fs::write(
+            fs.out_dir().join(report::FILE_NAME),
+            serde_json::to_vec_pretty(&paks)?,
+        )?;
+    }
+
+    for error in &failed {
+        let offset = error
+            .offset
+            .map(|offset| format!(" @ {offset:#x}"))
+            .unwrap_or_default();
+        eprintln!(
+            "{} ({}{offset}): {}",
+            error.entry.display(),
+            error.pak.display(),
+            error.error
+        );
+    }
+    if !failed.is_empty() {
+        eprintln!(
+            "{} entries failed, listed in {}",
+            failed.len(),
+            fs.out_dir().join(file_system::ERRORS_FILE_NAME).display()
+        );
+    }
+    Ok(failed.len())
+}
+
+#[instrument]
+async fn run_serve(cwd: &'static PathBuf, serve: &'static Serve) -> tokio::io::Result<()> {
+    static OUT: LazyLock<PathBuf> = LazyLock::new(|| PathBuf::new());
+    let fs = initialize(cwd, &OUT).await?;
+
+    let graphql = if serve.graphql {
+        let pb = Spinner::start("Reading Datasheets");
+        let source = match &serve.graphql_sqlite {
+            Some(path) => graphql::Source::sqlite(path)?,
+            None => graphql::Source::Memory(
+                tokio::task::spawn_blocking(move || fs.datasheets(fs.files(None)))
+                    .await
+                    .unwrap(),
+            ),
+        };
+        let schema = graphql::schema(source)?;
+        pb.stop("Read Datasheets");
+        Some(schema)
+    } else {
+        None
+    };
+
+    let listener = tokio::net::TcpListener::bind((serve.host.as_str(), serve.port)).await?;
+    cliclack::log::info(format!(
+        "Serving entries on http://{}/assets/",
+        listener.local_addr()?
+    ))?;
+    if graphql.is_some() {
+        cliclack::log::info(format!(
+            "Serving datasheets on http://{}/graphql",
+            listener.local_addr()?
+        ))?;
+    }
+
+    let cancel = App::handle().cancel.clone();
+    axum::serve(listener, serve::router(fs, graphql))
+        .with_graceful_shutdown(async move { cancel.cancelled().await })
+        .await?;
+
+    cliclack::outro("Server stopped.")?;
+    Ok(())
+}
+
+async fn run_cat(cwd: &'static PathBuf, cat: &'static Cat) -> tokio::io::Result<()> {
+    static OUT: LazyLock<PathBuf> = LazyLock::new(|| PathBuf::new());
+    let fs = initialize(cwd, &OUT).await?;
+
+    let (buf, _) =
+        tokio::task::spawn_blocking(move || fs.convert(&cat.entry, cat.format.as_deref()))
+            .await??;
+
+    // Progress and logs go to stderr, so only the entry ends up on stdout.
+    let mut stdout = std::io::stdout().lock();
+    stdout.write_all(&buf)?;
+    stdout.flush()?;
+    Ok(())
+}
+
+/// Prints the strings of `keys` in every locale, along with those of the keys whose string
+/// contains the text of `find`, or whose key does as well when its flag is set.
+async fn run_locale_lookup(
+    cwd: &'static PathBuf,
+    locales: &'static [Localization],
+    mut keys: BTreeSet<String>,
+    find: Option<(&str, bool)>,
+    json: bool,
+) -> tokio::io::Result<()> {
+    static OUT: LazyLock<PathBuf> = LazyLock::new(|| PathBuf::new());
+    let fs = initialize(cwd, &OUT).await?;
+
+    let pb = Spinner::start("Loading String Tables");
+    let mut maps = vec![];
+    for locale in Localization::expand(locales) {
+        let locale = locale.to_string();
+        let map = fs.localization(locale.to_owned()).await;
+        maps.push((locale, map));
+    }
+    pb.stop("String Tables Loaded");
+
+    if let Some((text, match_keys)) = find {
+        for (_, map) in &maps {
+            keys.extend(localization::find(map, text, match_keys));
+        }
+    }
+
+    // key, then the string of each locale, `None` when the locale lacks the key
+    let rows = keys
+        .iter()
+        .map(|key| {
+            let strings = maps
+                .iter()
+                .map(|(locale, map)| {
+                    let text = map.get(key).map(|text| text.clone().unwrap_or_default());
+                    (locale.as_str(), text)
+                })
+                .collect::<BTreeMap<_, _>>();
+            (key.as_str(), strings)
+        })
+        .collect::<BTreeMap<_, _>>();
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&rows)?);
+        return Ok(());
+    }
+    for (key, strings) in &rows {
+        println!("@{key}");
+        for (locale, text) in strings {
+            match text {
+                Some(text) => println!("    {locale}  {text}"),
+                None => println!("    {locale}  (missing)"),
+            }
+        }
+    }
+    cliclack::outro(format!("{} keys", rows.len()))?;
+    Ok(())
+}
+
+fn run_locale_search(
+    text: &str,
+    index: &Path,
+    locale: Option<&str>,
+    limit: usize,
+    json: bool,
+) -> tokio::io::Result<()> {
+    let path = if index.is_dir() {
+        index.join(localization::search::FILE_NAME)
+    } else {
+        index.to_path_buf()
+    };
+    if !path.exists() {
+        return Err(tokio::io::Error::new(
+            tokio::io::ErrorKind::NotFound,
+            format!(
+                "No index at {}, write one with `extract --locale-index`",
+                path.display()
+            ),
         ));
+    }
 
-        file_pb_clone.set_message(format!("{}", entry.display()));
+    let hits = localization::search::Index::open(&path)
+        .and_then(|index| index.search(text, locale, limit))
+        .map_err(tokio::io::Error::other)?;
 
-        let processed = Arc::clone(&cloned_processed);
-        if (processed.fetch_add(1, Ordering::Relaxed) + 1) == len as u64 {};
+    if json {
+        println!("{}", serde_json::to_string_pretty(&hits)?);
+        return Ok(());
+    }
+    for hit in &hits {
+        println!("@{} [{}] {}", hit.key, hit.locale, hit.text);
+        for reference in &hit.references {
+            println!("    {} {}", reference.datasheet, reference.column);
+        }
+    }
+    cliclack::outro(format!("{} strings found", hits.len()))?;
+    Ok(())
+}
 
-        Ok(())
-    })
-    .await?;
-
-    let all_pb = all.clone();
-    let file_pb = file_pb.clone();
-    let pak_pb = pak_pb.clone();
-    all_pb.stop("");
-    stats_pb.stop("");
-    file_pb.stop("");
-    pak_pb.stop("");
-    multi_pb.stop();
-    let processed = processed.load(Ordering::Relaxed);
-    let bytes_cloned = Arc::clone(&bytes);
+#[instrument]
+async fn run_locale_export(
+    cwd: &'static PathBuf,
+    output: &'static PathBuf,
+    locales: &'static [Localization],
+    format: &'static LocaleFormat,
+) -> tokio::io::Result<()> {
+    static OUT: LazyLock<PathBuf> = LazyLock::new(|| PathBuf::new());
+    let fs = initialize(cwd, &OUT).await?;
+    std::fs::create_dir_all(output)?;
+
+    let locales = Localization::expand(locales);
+    let pb = cliclack::ProgressBar::new(locales.len() as u64);
+    pb.start("Exporting string tables.");
+    for locale in &locales {
+        let locale = locale.to_string();
+        let map = fs.localization(locale.to_owned()).await;
+
+        let ext = match format {
+            LocaleFormat::JSON => "json",
+            LocaleFormat::CSV => "csv",
+            LocaleFormat::PO => "po",
+        };
+        let file = std::fs::File::create(output.join(&locale).with_extension(ext))?;
+        let mut writer = std::io::BufWriter::new(file);
+        match format {
+            LocaleFormat::JSON => export::to_json(&map, &mut writer)?,
+            LocaleFormat::CSV => export::to_csv(&map, &mut writer)?,
+            LocaleFormat::PO => export::to_po(&map, &locale, &mut writer)?,
+        };
+        writer.flush()?;
+        pb.inc(1);
+    }
+    pb.stop("String tables exported.");
 
     cliclack::outro(format!(
-        "Processed {}/{} files in {}. Bytes: {}",
-        processed,
-        len,
-        format_duration(start.elapsed()),
-        format_bytes(bytes_cloned.load(Ordering::Relaxed) as f64)
-    ))
-    .unwrap();
+        "Exported {} locale(s) to {}",
+        locales.len(),
+        output.display()
+    ))?;
     Ok(())
 }
@@ -0,0 +1,106 @@
+//! Prometheus metrics of a running extraction, served for `extract --metrics`.
+
+use axum::{http::header, routing::get, Router};
+use std::fmt::Write;
+
+use crate::progress::Snapshot;
+
+/// The text exposition of `snapshot`, along with the entries skipped as unchanged and those that
+/// failed so far.
+pub fn render(snapshot: &Snapshot, skipped: u64, errors: u64) -> String {
+    let metrics: [(&str, &str, &str, f64); 11] = [
+        (
+            "entries_processed_total",
+            "counter",
+            "Entries written, skipped or failed",
+            snapshot.processed as f64,
+        ),
+        (
+            "entries",
+            "gauge",
+            "Entries selected for extraction",
+            snapshot.total as f64,
+        ),
+        (
+            "entries_skipped_total",
+            "counter",
+            "Entries skipped as unchanged since the last extraction",
+            skipped as f64,
+        ),
+        (
+            "errors_total",
+            "counter",
+            "Entries that failed",
+            errors as f64,
+        ),
+        (
+            "bytes_read_total",
+            "counter",
+            "Uncompressed bytes of the entries processed",
+            snapshot.read as f64,
+        ),
+        (
+            "bytes",
+            "gauge",
+            "Uncompressed bytes of the entries selected for extraction",
+            snapshot.total_bytes as f64,
+        ),
+        (
+            "bytes_written_total",
+            "counter",
+            "Bytes written",
+            snapshot.bytes as f64,
+        ),
+        (
+            "bytes_per_second",
+            "gauge",
+            "Bytes written per second since the start",
+            snapshot.bytes_per_sec,
+        ),
+        (
+            "active_tasks",
+            "gauge",
+            "Entries being converted",
+            snapshot.tasks as f64,
+        ),
+        (
+            "max_active_tasks",
+            "gauge",
+            "Most entries converted at once",
+            snapshot.max_tasks as f64,
+        ),
+        (
+            "eta_seconds",
+            "gauge",
+            "Estimated seconds until every entry is processed",
+            snapshot.eta.as_secs_f64(),
+        ),
+    ];
+
+    let mut text = String::new();
+    for (name, kind, help, value) in metrics {
+        let _ = writeln!(text, "# HELP nwtools_{name} {help}");
+        let _ = writeln!(text, "# TYPE nwtools_{name} {kind}");
+        let _ = writeln!(text, "nwtools_{name} {value}");
+    }
+    text
+}
+
+/// Answers `/metrics` with the text `metrics` renders on every scrape.
+pub fn router<F>(metrics: F) -> Router
+where
+    F: Fn() -> String + Clone + Send + Sync + 'static,
+{
+    Router::new().route(
+        "/metrics",
+        get(move || {
+            let metrics = metrics.clone();
+            async move {
+                (
+                    [(header::CONTENT_TYPE, "text/plain; version=0.0.4")],
+                    metrics(),
+                )
+            }
+        }),
+    )
+}
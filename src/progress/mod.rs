@@ -0,0 +1,334 @@
+use crate::tui::Tui;
+use cli::{common::progress::ProgressFormat, ARGS};
+use cliclack::{spinner, MultiProgress, ProgressBar};
+use serde::Serialize;
+use std::{
+    fmt::Display,
+    io::{self, Write},
+    net::TcpStream,
+    path::Path,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+use utils::{format_bytes, format_duration};
+
+/// How often `--progress plain` logs a line.
+const PLAIN_INTERVAL: Duration = Duration::from_secs(5);
+
+/// The state of an extraction, taken on every tick.
+pub struct Snapshot {
+    pub processed: u64,
+    pub total: u64,
+    /// Uncompressed bytes of the entries processed.
+    pub read: u64,
+    /// Uncompressed bytes of every entry, 0 until the paks are indexed.
+    pub total_bytes: u64,
+    /// Bytes written.
+    pub bytes: u64,
+    pub bytes_per_sec: f64,
+    pub eta: Duration,
+    pub tasks: usize,
+    pub max_tasks: usize,
+    pub last_size: usize,
+}
+
+impl Snapshot {
+    /// How much of the extraction is done, by bytes once sizes are known and by entries before.
+    pub fn ratio(&self) -> f64 {
+        let ratio = match (self.total_bytes, self.total) {
+            (0, 0) => 1.0,
+            (0, total) => self.processed as f64 / total as f64,
+            (total_bytes, _) => self.read as f64 / total_bytes as f64,
+        };
+        ratio.clamp(0.0, 1.0)
+    }
+
+    /// Bytes processed out of the total, or files when sizes aren't known.
+    pub fn amount(&self) -> String {
+        match self.total_bytes {
+            0 => format!("{}/{} files", self.processed, self.total),
+            total_bytes => format!(
+                "{}/{} files ({}/{})",
+                self.processed,
+                self.total,
+                format_bytes(self.read as f64),
+                format_bytes(total_bytes as f64),
+            ),
+        }
+    }
+}
+
+/// A line of `--progress json`.
+#[derive(Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+enum Event<'a> {
+    Start {
+        total: u64,
+    },
+    Progress {
+        processed: u64,
+        total: u64,
+        read_bytes: u64,
+        total_bytes: u64,
+        bytes: u64,
+        bytes_per_sec: f64,
+        eta_secs: f64,
+        tasks: usize,
+        pak: &'a str,
+        entry: &'a str,
+    },
+    Done {
+        processed: u64,
+        total: u64,
+        bytes: u64,
+        skipped: usize,
+        elapsed_secs: f64,
+    },
+    Aborted,
+}
+
+/// Reports the progress of an extraction as chosen with `--progress`.
+pub enum Progress {
+    Bars {
+        multi: MultiProgress,
+        all: ProgressBar,
+        stats: ProgressBar,
+        pak: ProgressBar,
+        file: ProgressBar,
+    },
+    Json {
+        out: Mutex<Box<dyn Write + Send>>,
+        /// The pak and entry last processed.
+        current: Mutex<(String, String)>,
+    },
+    Plain {
+        /// When the last line was logged.
+        last: Mutex<Instant>,
+    },
+    Tui(Tui),
+}
+
+impl Progress {
+    pub fn new(total: u64) -> io::Result<Self> {
+        let progress = match ARGS.progress() {
+            ProgressFormat::BAR => {
+                let multi = MultiProgress::new("Extracting Pak(s)");
+                let all = multi.add(ProgressBar::new(total));
+                let stats = multi.add(spinner());
+                let pak = multi.add(spinner());
+                let file = multi.add(spinner());
+                for pb in [&all, &stats, &file, &pak] {
+                    pb.start("");
+                }
+                Self::Bars {
+                    multi,
+                    all,
+                    stats,
+                    pak,
+                    file,
+                }
+            }
+            ProgressFormat::JSON => {
+                let out: Box<dyn Write + Send> = match &ARGS.progress_addr {
+                    Some(addr) => Box::new(TcpStream::connect(addr)?),
+                    None => Box::new(io::stdout()),
+                };
+                Self::Json {
+                    out: Mutex::new(out),
+                    current: Mutex::default(),
+                }
+            }
+            ProgressFormat::PLAIN => Self::Plain {
+                last: Mutex::new(Instant::now()),
+            },
+            ProgressFormat::TUI => Self::Tui(Tui::new()?),
+        };
+        progress.emit(&Event::Start { total });
+        Ok(progress)
+    }
+
+    /// Records that `entry`, the `idx`th of the `len` being extracted from `pak`, was processed.
+    pub fn entry(&self, pak: &Path, entry: &Path, idx: usize, len: usize) {
+        let pak = pak.file_name().unwrap_or_default().to_string_lossy();
+        match self {
+            Self::Bars {
+                all,
+                pak: pak_pb,
+                file,
+                ..
+            } => {
+                all.inc(1);
+                pak_pb.set_message(format!("{pak} ({idx}/{len})"));
+                file.set_message(format!("{}", entry.display()));
+            }
+            Self::Json { current, .. } => {
+                *current.lock().unwrap() = (pak.into_owned(), entry.display().to_string());
+            }
+            Self::Plain { .. } => {}
+            Self::Tui(tui) => tui.entry(&pak, idx, len),
+        }
+    }
+
+    pub fn tick(&self, snapshot: &Snapshot) {
+        match self {
+            Self::Bars { all, stats, .. } => {
+                stats.set_message(format!(
+                    "#Tasks: {} | Max Tasks: {} | #Last Bytes Written: {} ",
+                    snapshot.tasks,
+                    snapshot.max_tasks,
+                    format_bytes(snapshot.last_size as f64),
+                ));
+                all.set_message(format!(
+                    "{:.1}% | ETA: {} | Throughput: {}/s",
+                    snapshot.ratio() * 100.0,
+                    format_duration(snapshot.eta),
+                    format_bytes(snapshot.bytes_per_sec),
+                ));
+            }
+            Self::Json { current, .. } => {
+                let (pak, entry) = current.lock().unwrap().clone();
+                self.emit(&Event::Progress {
+                    processed: snapshot.processed,
+                    total: snapshot.total,
+                    read_bytes: snapshot.read,
+                    total_bytes: snapshot.total_bytes,
+                    bytes: snapshot.bytes,
+                    bytes_per_sec: snapshot.bytes_per_sec,
+                    eta_secs: snapshot.eta.as_secs_f64(),
+                    tasks: snapshot.tasks,
+                    pak: &pak,
+                    entry: &entry,
+                });
+            }
+            Self::Plain { last } => {
+                let mut last = last.lock().unwrap();
+                if ARGS.quiet || last.elapsed() < PLAIN_INTERVAL {
+                    return;
+                }
+                *last = Instant::now();
+                eprintln!(
+                    "{} | {} | {}/s | ETA: {}",
+                    snapshot.amount(),
+                    format_bytes(snapshot.bytes as f64),
+                    format_bytes(snapshot.bytes_per_sec),
+                    format_duration(snapshot.eta),
+                );
+            }
+            Self::Tui(tui) => tui.tick(snapshot),
+        }
+    }
+
+    pub fn abort(&self) {
+        match self {
+            Self::Bars { multi, .. } => {
+                multi.println("Aborting...");
+                multi.cancel();
+            }
+            Self::Json { .. } => self.emit(&Event::Aborted),
+            Self::Plain { .. } => log("Aborting..."),
+            Self::Tui(tui) => {
+                tui.finish();
+                log("Aborting...");
+            }
+        }
+    }
+
+    pub fn finish(&self, snapshot: &Snapshot, skipped: usize, elapsed: Duration) {
+        let summary = format!(
+            "Processed {}/{} files in {}. Bytes: {}{}",
+            snapshot.processed,
+            snapshot.total,
+            format_duration(elapsed),
+            format_bytes(snapshot.bytes as f64),
+            if skipped > 0 {
+                format!(". Unchanged: {skipped}")
+            } else {
+                String::new()
+            }
+        );
+        match self {
+            Self::Bars {
+                multi,
+                all,
+                stats,
+                pak,
+                file,
+            } => {
+                for pb in [all, stats, file, pak] {
+                    pb.stop("");
+                }
+                multi.stop();
+                outro(summary);
+            }
+            Self::Json { .. } => self.emit(&Event::Done {
+                processed: snapshot.processed,
+                total: snapshot.total,
+                bytes: snapshot.bytes,
+                skipped,
+                elapsed_secs: elapsed.as_secs_f64(),
+            }),
+            Self::Plain { .. } => outro(summary),
+            Self::Tui(tui) => {
+                tui.finish();
+                outro(summary);
+            }
+        }
+    }
+
+    /// Writes `event` as a line of json. Progress is best effort, a reader that went away doesn't
+    /// stop the extraction.
+    fn emit(&self, event: &Event) {
+        if let Self::Json { out, .. } = self {
+            let mut out = out.lock().unwrap();
+            let _ = serde_json::to_writer(&mut *out, event)
+                .map_err(io::Error::from)
+                .and_then(|_| writeln!(out))
+                .and_then(|_| out.flush());
+        }
+    }
+}
+
+/// A cliclack spinner, or a plain line on stderr once done when prompts can't be shown. Nothing
+/// is shown with `--quiet`.
+pub struct Spinner(Option<ProgressBar>);
+
+impl Spinner {
+    pub fn start(message: &str) -> Self {
+        if !ARGS.interactive() {
+            return Self(None);
+        }
+        let pb = spinner();
+        pb.start(message);
+        Self(Some(pb))
+    }
+
+    pub fn stop(self, message: impl Display) {
+        match self.0 {
+            Some(pb) => pb.stop(message),
+            None => log(message),
+        }
+    }
+
+    /// Removes the spinner without a message.
+    pub fn clear(self) {
+        if let Some(pb) = self.0 {
+            pb.clear();
+        }
+    }
+}
+
+/// Ends the output with `message`, as a cliclack outro when prompts can be shown.
+pub fn outro(message: impl Display) {
+    if ARGS.interactive() {
+        cliclack::outro(message).unwrap();
+    } else {
+        log(message);
+    }
+}
+
+/// Writes `message` to stderr unless `--quiet` is set.
+fn log(message: impl Display) {
+    if !ARGS.quiet {
+        eprintln!("{message}");
+    }
+}
@@ -0,0 +1,377 @@
+use async_graphql::{
+    dynamic::{
+        Field, FieldFuture, FieldValue, InputValue, Object, ResolverContext, Schema, TypeRef,
+    },
+    Value,
+};
+use datasheet::{
+    codegen::{self, ColumnKind},
+    Datasheet, Predicate,
+};
+use rusqlite::{params_from_iter, types::ValueRef, Connection};
+use std::{
+    io,
+    path::Path,
+    sync::{Arc, Mutex},
+};
+
+/// A row of a datasheet as its JSON export writes it, keyed by column name.
+type Row = serde_json::Map<String, serde_json::Value>;
+
+/// Name and columns, with their GraphQL scalar, of a datasheet.
+type Table = (String, Vec<(String, &'static str)>);
+
+/// Rows of a datasheet matching a query, with their count before paging.
+struct Page {
+    total: usize,
+    rows: Vec<Row>,
+}
+
+/// Where queries are resolved from.
+pub enum Source {
+    /// Datasheets parsed from the paks, kept in memory.
+    Memory(Vec<Datasheet<'static>>),
+    /// Tables of a `datasheets.sqlite` export, as written by `--datasheet sqlite`.
+    Sqlite(Mutex<Connection>),
+}
+
+impl Source {
+    pub fn sqlite<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let conn = Connection::open_with_flags(path, rusqlite::OpenFlags::SQLITE_OPEN_READ_ONLY)
+            .map_err(io::Error::other)?;
+        Ok(Self::Sqlite(Mutex::new(conn)))
+    }
+
+    fn tables(&self) -> io::Result<Vec<Table>> {
+        match self {
+            Source::Memory(sheets) => {
+                let mut tables = sheets
+                    .iter()
+                    .map(|sheet| {
+                        let schema = codegen::Schema::merge([sheet]).remove(0);
+                        let columns = schema
+                            .columns
+                            .into_iter()
+                            .map(|column| {
+                                let scalar = match column.kind {
+                                    ColumnKind::Boolean => TypeRef::BOOLEAN,
+                                    ColumnKind::String => TypeRef::STRING,
+                                    // numbers are stored as floats, integers may not fit an Int
+                                    ColumnKind::Integer | ColumnKind::Number => TypeRef::FLOAT,
+                                };
+                                (column.name, scalar)
+                            })
+                            .collect();
+                        (sheet.name.to_owned(), columns)
+                    })
+                    .collect::<Vec<_>>();
+                tables.sort_by(|a, b| a.0.cmp(&b.0));
+                Ok(tables)
+            }
+            Source::Sqlite(conn) => sqlite_tables(&conn.lock().unwrap()).map_err(io::Error::other),
+        }
+    }
+
+    /// Rows of `table` matching every predicate, skipping `offset` of them and keeping at most
+    /// `limit`. Like `--datasheet-where`, predicates on columns the table lacks are ignored.
+    fn page(
+        &self,
+        table: &str,
+        predicates: &[Predicate],
+        offset: usize,
+        limit: Option<usize>,
+    ) -> io::Result<Page> {
+        match self {
+            Source::Memory(sheets) => {
+                let mut sheet = sheets
+                    .iter()
+                    .find(|sheet| sheet.name == table)
+                    .ok_or_else(|| {
+                        io::Error::new(io::ErrorKind::NotFound, format!("No datasheet {table}"))
+                    })?
+                    .clone();
+                for predicate in predicates {
+                    sheet.retain(predicate);
+                }
+                let serde_json::Value::Array(rows) = sheet.to_json() else {
+                    return Ok(Page {
+                        total: 0,
+                        rows: vec![],
+                    });
+                };
+                Ok(Page {
+                    total: rows.len(),
+                    rows: rows
+                        .into_iter()
+                        .skip(offset)
+                        .take(limit.unwrap_or(usize::MAX))
+                        .filter_map(|row| match row {
+                            serde_json::Value::Object(row) => Some(row),
+                            _ => None,
+                        })
+                        .collect(),
+                })
+            }
+            Source::Sqlite(conn) => {
+                sqlite_page(&conn.lock().unwrap(), table, predicates, offset, limit)
+                    .map_err(io::Error::other)
+            }
+        }
+    }
+}
+
+/// Every table of a `datasheets.sqlite` export, with the scalar of each column from the type
+/// [`Datasheet::to_sqlite`] gave it.
+fn sqlite_tables(conn: &Connection) -> rusqlite::Result<Vec<Table>> {
+    let mut statement =
+        conn.prepare("SELECT name FROM sqlite_master WHERE type = 'table' ORDER BY name")?;
+    let names = statement
+        .query_map([], |row| row.get::<_, String>(0))?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+
+    names
+        .into_iter()
+        .map(|name| {
+            let columns = sqlite_columns(conn, &name)?;
+            Ok((name, columns))
+        })
+        .collect()
+}
+
+fn sqlite_columns(conn: &Connection, table: &str) -> rusqlite::Result<Vec<(String, &'static str)>> {
+    let mut statement = conn.prepare(&format!("PRAGMA table_info({})", quote(table)))?;
+    let columns = statement
+        .query_map([], |row| {
+            let scalar = match row.get::<_, String>(2)?.as_str() {
+                "REAL" => TypeRef::FLOAT,
+                // booleans are the only integers written
+                "INTEGER" => TypeRef::BOOLEAN,
+                _ => TypeRef::STRING,
+            };
+            Ok((row.get::<_, String>(1)?, scalar))
+        })?
+        .collect();
+    columns
+}
+
+fn sqlite_page(
+    conn: &Connection,
+    table: &str,
+    predicates: &[Predicate],
+    offset: usize,
+    limit: Option<usize>,
+) -> rusqlite::Result<Page> {
+    let columns = sqlite_columns(conn, table)?;
+    let (conditions, params): (Vec<_>, Vec<_>) = predicates
+        .iter()
+        .filter_map(|predicate| {
+            let (_, scalar) = columns
+                .iter()
+                .find(|(column, _)| column.eq_ignore_ascii_case(predicate.column()))?;
+            Some(predicate.to_sqlite(*scalar == TypeRef::BOOLEAN))
+        })
+        .unzip();
+    let filter = if conditions.is_empty() {
+        String::new()
+    } else {
+        format!(" WHERE {}", conditions.join(" AND "))
+    };
+    let table = quote(table);
+
+    let total = conn.query_row(
+        &format!("SELECT count(*) FROM {table}{filter}"),
+        params_from_iter(&params),
+        |row| row.get::<_, i64>(0),
+    )?;
+
+    // a negative limit is no limit
+    let limit = limit.map_or(-1, |limit| limit as i64);
+    let mut statement = conn.prepare(&format!(
+        "SELECT * FROM {table}{filter} LIMIT {limit} OFFSET {offset}"
+    ))?;
+    let rows = statement
+        .query_map(params_from_iter(&params), |row| {
+            columns
+                .iter()
+                .enumerate()
+                .map(|(i, (column, scalar))| Ok((column.to_owned(), json(row.get_ref(i)?, scalar))))
+                .collect::<rusqlite::Result<Row>>()
+        })?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+
+    Ok(Page {
+        total: total as usize,
+        rows,
+    })
+}
+
+/// A sqlite value as the JSON export writes the cell.
+fn json(value: ValueRef, scalar: &str) -> serde_json::Value {
+    match value {
+        ValueRef::Integer(value) if scalar == TypeRef::BOOLEAN => (value != 0).into(),
+        ValueRef::Integer(value) => value.into(),
+        ValueRef::Real(value) if value.fract() == 0.0 => (value as i64).into(),
+        ValueRef::Real(value) => serde_json::Number::from_f64(value)
+            .map_or(serde_json::Value::Null, serde_json::Value::Number),
+        ValueRef::Text(value) => String::from_utf8_lossy(value).into_owned().into(),
+        ValueRef::Null | ValueRef::Blob(_) => serde_json::Value::Null,
+    }
+}
+
+fn quote(ident: &str) -> String {
+    format!("\"{}\"", ident.replace('"', "\"\""))
+}
+
+/// `name` with the characters GraphQL doesn't allow in names replaced by `_`.
+fn graphql_name(name: &str) -> String {
+    let name = name
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect::<String>();
+    match name.chars().next() {
+        Some(c) if c.is_ascii_alphabetic() || c == '_' => name,
+        _ => format!("_{name}"),
+    }
+}
+
+/// A schema with a query field per datasheet, named after it, returning a page of its rows:
+///
+/// ```graphql
+/// {
+///   javelindata_itemdefinitions_master_common(where: ["Tier>=4", "ItemID~sword"], limit: 10) {
+///     total
+///     rows { ItemID Tier }
+///   }
+/// }
+/// ```
+///
+/// `where` takes the predicates of `--datasheet-where`, `offset` and `limit` page the matches.
+/// Columns keep their names unless GraphQL doesn't allow them, then invalid characters are
+/// replaced with `_`.
+pub fn schema(source: Source) -> io::Result<Schema> {
+    let tables = source.tables()?;
+    let source = Arc::new(source);
+
+    let names = tables
+        .iter()
+        .map(|(name, _)| name.to_owned())
+        .collect::<Vec<_>>();
+    let mut query = Object::new("Query").field(
+        Field::new(
+            "datasheets",
+            TypeRef::named_nn_list_nn(TypeRef::STRING),
+            move |_| {
+                let names = names.iter().map(|name| Value::from(name.as_str()));
+                FieldFuture::from_value(Some(Value::List(names.collect())))
+            },
+        )
+        .description("Names of every datasheet"),
+    );
+
+    let mut objects = vec![];
+    for (table, columns) in tables {
+        let type_name = codegen::pascal_case(&table);
+        let page_name = format!("{type_name}Page");
+
+        let mut row = Object::new(&type_name).description(format!("A row of {table}"));
+        for (column, scalar) in columns {
+            row = row.field(Field::new(
+                graphql_name(&column),
+                TypeRef::named(scalar),
+                move |ctx| {
+                    let column = column.clone();
+                    FieldFuture::new(async move {
+                        let row = ctx.parent_value.try_downcast_ref::<Row>()?;
+                        Ok(row
+                            .get(&column)
+                            .cloned()
+                            .map(Value::from_json)
+                            .transpose()?)
+                    })
+                },
+            ));
+        }
+
+        let page = Object::new(&page_name)
+            .field(Field::new(
+                "total",
+                TypeRef::named_nn(TypeRef::INT),
+                |ctx| {
+                    FieldFuture::new(async move {
+                        let page = ctx.parent_value.try_downcast_ref::<Page>()?;
+                        Ok(Some(Value::from(page.total as i32)))
+                    })
+                },
+            ))
+            .field(Field::new(
+                "rows",
+                TypeRef::named_nn_list_nn(&type_name),
+                |ctx| {
+                    FieldFuture::new(async move {
+                        let page = ctx.parent_value.try_downcast_ref::<Page>()?;
+                        Ok(Some(FieldValue::list(
+                            page.rows.iter().map(|row| FieldValue::borrowed_any(row)),
+                        )))
+                    })
+                },
+            ));
+
+        let source = source.clone();
+        let field = Field::new(
+            graphql_name(&table),
+            TypeRef::named_nn(&page_name),
+            move |ctx| {
+                let source = source.clone();
+                let table = table.clone();
+                FieldFuture::new(async move {
+                    let (predicates, offset, limit) = arguments(&ctx)?;
+                    let page = tokio::task::spawn_blocking(move || {
+                        source.page(&table, &predicates, offset, limit)
+                    })
+                    .await??;
+                    Ok(Some(FieldValue::owned_any(page)))
+                })
+            },
+        )
+        .argument(
+            InputValue::new("where", TypeRef::named_nn_list(TypeRef::STRING))
+                .description("Only rows matching all of these `column op value` predicates"),
+        )
+        .argument(InputValue::new("offset", TypeRef::named(TypeRef::INT)))
+        .argument(InputValue::new("limit", TypeRef::named(TypeRef::INT)));
+
+        query = query.field(field);
+        objects.push(row);
+        objects.push(page);
+    }
+
+    let mut builder = Schema::build(query.type_name(), None, None);
+    for object in objects {
+        builder = builder.register(object);
+    }
+    builder.register(query).finish().map_err(io::Error::other)
+}
+
+fn arguments(
+    ctx: &ResolverContext,
+) -> async_graphql::Result<(Vec<Predicate>, usize, Option<usize>)> {
+    let predicates = match ctx.args.get("where") {
+        Some(predicates) => predicates
+            .list()?
+            .iter()
+            .map(|predicate| Ok(predicate.string()?.parse::<Predicate>()?))
+            .collect::<async_graphql::Result<Vec<_>>>()?,
+        None => vec![],
+    };
+    let offset = ctx
+        .args
+        .get("offset")
+        .map(|offset| offset.u64())
+        .transpose()?;
+    let limit = ctx.args.get("limit").map(|limit| limit.u64()).transpose()?;
+    Ok((
+        predicates,
+        offset.unwrap_or(0) as usize,
+        limit.map(|limit| limit as usize),
+    ))
+}
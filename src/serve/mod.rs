@@ -0,0 +1,105 @@
+use async_graphql::{dynamic::Schema, http::GraphiQLSource};
+use async_graphql_axum::GraphQL;
+use axum::{
+    extract::{Path, Query, State},
+    http::{header, StatusCode},
+    response::{Html, IntoResponse, Response},
+    routing::get,
+    Router,
+};
+use file_system::FileSystem;
+use serde::Deserialize;
+use std::{io, path::PathBuf};
+
+#[derive(Debug, Deserialize)]
+struct Params {
+    /// Overrides the converted format, e.g. `pretty`, `yaml` or `png`
+    format: Option<String>,
+}
+
+pub mod graphql;
+
+/// Routes converting entries under `/assets/`, and answering GraphQL queries at `/graphql` when
+/// given a schema, with GraphiQL on GET.
+pub fn router(fs: &'static FileSystem, graphql: Option<Schema>) -> Router {
+    let mut router = Router::new().route("/assets/*path", get(asset));
+    if let Some(schema) = graphql {
+        router = router.route("/graphql", get(graphiql).post_service(GraphQL::new(schema)));
+    }
+    router.with_state(fs)
+}
+
+async fn graphiql() -> Html<String> {
+    Html(GraphiQLSource::build().endpoint("/graphql").finish())
+}
+
+async fn asset(
+    State(fs): State<&'static FileSystem>,
+    Path(path): Path<String>,
+    Query(params): Query<Params>,
+) -> Response {
+    let entry = PathBuf::from(path);
+    let converted =
+        tokio::task::spawn_blocking(move || fs.convert(&entry, params.format.as_deref())).await;
+
+    match converted {
+        Ok(Ok((buf, path))) => ([(header::CONTENT_TYPE, content_type(&path))], buf).into_response(),
+        Ok(Err(e)) => (status(&e), e.to_string()).into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
+
+fn status(e: &io::Error) -> StatusCode {
+    match e.kind() {
+        io::ErrorKind::NotFound => StatusCode::NOT_FOUND,
+        io::ErrorKind::InvalidInput => StatusCode::BAD_REQUEST,
+        _ => StatusCode::INTERNAL_SERVER_ERROR,
+    }
+}
+
+fn content_type(path: &std::path::Path) -> &'static str {
+    match path.extension().and_then(|ext| ext.to_str()) {
+        Some("json") => "application/json",
+        Some("xml") => "application/xml",
+        Some("yaml") => "application/yaml",
+        Some("csv") => "text/csv",
+        Some("html") => "text/html; charset=utf-8",
+        Some("sql") => "application/sql",
+        Some("xlsx") => "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet",
+        Some("lua") => "text/x-lua",
+        Some("png") => "image/png",
+        Some("jpeg") | Some("jpg") => "image/jpeg",
+        Some("webp") => "image/webp",
+        Some("dds") => "image/vnd.ms-dds",
+        Some("txt") | Some("cfg") | Some("ini") | Some("asm") => "text/plain",
+        _ => "application/octet-stream",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+
+    #[test]
+    fn content_types_by_extension() {
+        assert_eq!(
+            content_type(Path::new("sharedassets/items.json")),
+            "application/json"
+        );
+        assert_eq!(content_type(Path::new("icons/sword.jpg")), "image/jpeg");
+        assert_eq!(
+            content_type(Path::new("textures/rock.dds")),
+            "image/vnd.ms-dds"
+        );
+        assert_eq!(content_type(Path::new("scripts/ui.asm")), "text/plain");
+        assert_eq!(
+            content_type(Path::new("datasheets.sqlite")),
+            "application/octet-stream"
+        );
+        assert_eq!(
+            content_type(Path::new("README")),
+            "application/octet-stream"
+        );
+    }
+}
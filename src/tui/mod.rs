@@ -0,0 +1,244 @@
+//! Full screen progress for `--progress tui`: the entries extracted from every pak, a throughput
+//! graph and the warnings and errors logged while extracting.
+
+use crate::{app::App, progress::Snapshot};
+use ratatui::{
+    backend::CrosstermBackend,
+    crossterm::{
+        event::{self, Event, KeyCode, KeyEventKind, KeyModifiers},
+        execute,
+        terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
+    },
+    layout::{Constraint, Layout},
+    style::{Style, Stylize},
+    text::Line,
+    widgets::{Block, Gauge, List, Row, Sparkline, Table},
+    Frame, Terminal,
+};
+use std::{
+    collections::{BTreeMap, VecDeque},
+    io::{self, Stderr, Write},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        LazyLock, Mutex,
+    },
+    time::{Duration, Instant},
+};
+use utils::{format_bytes, format_duration};
+
+/// Lines kept for the error panel.
+const MAX_ERRORS: usize = 500;
+/// Throughput samples kept for the graph, one per tick.
+const MAX_SAMPLES: usize = 512;
+
+static ERRORS: LazyLock<Mutex<VecDeque<String>>> = LazyLock::new(Mutex::default);
+
+/// Log writer feeding the error panel, a line per event.
+pub struct ErrorPanel;
+
+impl Write for ErrorPanel {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let mut errors = ERRORS.lock().unwrap();
+        for line in String::from_utf8_lossy(buf).lines() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            if errors.len() == MAX_ERRORS {
+                errors.pop_front();
+            }
+            errors.push_back(line.to_string());
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+struct State {
+    /// Entries extracted and the total of every pak seen so far.
+    paks: BTreeMap<String, (usize, usize)>,
+    /// Bytes per second between ticks.
+    samples: VecDeque<u64>,
+    /// When the previous tick was and the bytes written by then.
+    last: (Instant, u64),
+}
+
+pub struct Tui {
+    terminal: Mutex<Terminal<CrosstermBackend<Stderr>>>,
+    state: Mutex<State>,
+    active: AtomicBool,
+}
+
+impl Tui {
+    /// Switches stderr to the alternate screen.
+    pub fn new() -> io::Result<Self> {
+        enable_raw_mode()?;
+        execute!(io::stderr(), EnterAlternateScreen)?;
+        let tui = Self {
+            terminal: Mutex::new(Terminal::new(CrosstermBackend::new(io::stderr()))?),
+            state: Mutex::new(State {
+                paks: BTreeMap::new(),
+                samples: VecDeque::with_capacity(MAX_SAMPLES),
+                last: (Instant::now(), 0),
+            }),
+            active: AtomicBool::new(true),
+        };
+        tui.terminal.lock().unwrap().clear()?;
+        Ok(tui)
+    }
+
+    /// Records that the `idx`th of the `len` entries being extracted from `pak` is done.
+    pub fn entry(&self, pak: &str, idx: usize, len: usize) {
+        let mut state = self.state.lock().unwrap();
+        let (done, total) = state.paks.entry(pak.to_string()).or_default();
+        *done = (*done).max(idx);
+        *total = len;
+    }
+
+    /// Redraws the screen. `q` and Ctrl+C cancel the extraction, letting the entries in flight
+    /// finish, and pressing either again aborts. Raw mode keeps Ctrl+C from raising SIGINT.
+    pub fn tick(&self, snapshot: &Snapshot) {
+        while event::poll(Duration::ZERO).unwrap_or(false) {
+            let Ok(Event::Key(key)) = event::read() else {
+                continue;
+            };
+            let ctrl_c =
+                key.code == KeyCode::Char('c') && key.modifiers.contains(KeyModifiers::CONTROL);
+            if key.kind == KeyEventKind::Press && (key.code == KeyCode::Char('q') || ctrl_c) {
+                if App::handle().cancel.is_cancelled() {
+                    self.restore();
+                    std::process::exit(130);
+                }
+                App::handle().cancel.cancel();
+            }
+        }
+
+        let mut state = self.state.lock().unwrap();
+        let (last, last_bytes) = state.last;
+        let now = Instant::now();
+        let secs = now.duration_since(last).as_secs_f64();
+        if secs > 0.0 {
+            if state.samples.len() == MAX_SAMPLES {
+                state.samples.pop_front();
+            }
+            let rate = snapshot.bytes.saturating_sub(last_bytes) as f64 / secs;
+            state.samples.push_back(rate as u64);
+        }
+        state.last = (now, snapshot.bytes);
+
+        if !self.active.load(Ordering::Relaxed) {
+            return;
+        }
+        let errors = ERRORS.lock().unwrap();
+        let _ = self
+            .terminal
+            .lock()
+            .unwrap()
+            .draw(|frame| draw(frame, snapshot, &state, &errors));
+    }
+
+    /// Leaves the alternate screen and prints the errors shown in the panel so they outlive it.
+    pub fn finish(&self) {
+        if !self.restore() {
+            return;
+        }
+        for line in ERRORS.lock().unwrap().iter() {
+            eprintln!("{line}");
+        }
+    }
+
+    /// Gives the terminal back, returning whether it was still taken.
+    fn restore(&self) -> bool {
+        if !self.active.swap(false, Ordering::Relaxed) {
+            return false;
+        }
+        let _ = disable_raw_mode();
+        let _ = execute!(io::stderr(), LeaveAlternateScreen);
+        true
+    }
+}
+
+impl Drop for Tui {
+    fn drop(&mut self) {
+        self.restore();
+    }
+}
+
+fn draw(frame: &mut Frame, snapshot: &Snapshot, state: &State, errors: &VecDeque<String>) {
+    let [overall, middle, bottom] = Layout::vertical([
+        Constraint::Length(3),
+        Constraint::Min(6),
+        Constraint::Length(10),
+    ])
+    .areas(frame.area());
+    let [paks, graph] =
+        Layout::horizontal([Constraint::Percentage(55), Constraint::Percentage(45)]).areas(middle);
+
+    frame.render_widget(
+        Gauge::default()
+            .block(Block::bordered().title(" Extracting (q to cancel) "))
+            .ratio(snapshot.ratio())
+            .label(format!(
+                "{} | {} | ETA: {}",
+                snapshot.amount(),
+                format_bytes(snapshot.bytes as f64),
+                format_duration(snapshot.eta),
+            )),
+        overall,
+    );
+
+    // unfinished paks first so the ones being extracted stay in view
+    let mut rows = state.paks.iter().collect::<Vec<_>>();
+    rows.sort_by_key(|(_, (done, total))| done >= total);
+    let rows = rows.into_iter().map(|(name, (done, total))| {
+        Row::new([
+            name.to_owned(),
+            format!("{done}/{total}"),
+            format!("{:.0}%", *done as f64 * 100.0 / (*total).max(1) as f64),
+        ])
+    });
+    frame.render_widget(
+        Table::new(
+            rows,
+            [
+                Constraint::Min(20),
+                Constraint::Length(15),
+                Constraint::Length(5),
+            ],
+        )
+        .header(Row::new(["Pak", "Entries", "%"]).style(Style::new().bold()))
+        .block(Block::bordered().title(format!(" Paks ({}) ", state.paks.len()))),
+        paks,
+    );
+
+    let width = graph.width.saturating_sub(2) as usize;
+    let samples = state
+        .samples
+        .iter()
+        .skip(state.samples.len().saturating_sub(width))
+        .copied()
+        .collect::<Vec<_>>();
+    frame.render_widget(
+        Sparkline::default()
+            .block(Block::bordered().title(format!(
+                " Throughput {}/s ",
+                format_bytes(snapshot.bytes_per_sec)
+            )))
+            .data(&samples),
+        graph,
+    );
+
+    let height = bottom.height.saturating_sub(2) as usize;
+    let lines = errors
+        .iter()
+        .skip(errors.len().saturating_sub(height))
+        .map(|line| Line::from(line.as_str()));
+    frame.render_widget(
+        List::new(lines)
+            .style(Style::new().red())
+            .block(Block::bordered().title(format!(" Errors ({}) ", errors.len()))),
+        bottom,
+    );
+}
@@ -1,19 +1,18 @@
 use std::{
     collections::HashMap,
     io::Cursor,
-    os::windows::fs::MetadataExt,
+    path::{Path, PathBuf},
     str::FromStr,
     sync::{Arc, OnceLock},
 };
 
 use crc32fast::hash;
-use rayon::prelude::*;
 use regex::Regex;
 use serde::{Deserialize, Serialize};
 use tokio::{
     fs,
-    io::{self, AsyncBufReadExt, AsyncReadExt},
-    sync::mpsc,
+    io::{self, AsyncBufReadExt},
+    sync::Semaphore,
     task::JoinSet,
 };
 use tracing::{info, info_span, Instrument};
@@ -24,90 +23,72 @@ static AZ_CRC: OnceLock<Regex> = OnceLock::new();
 static AZ_TYPE_INFO: OnceLock<Regex> = OnceLock::new();
 static ATTRIBUTE: OnceLock<Regex> = OnceLock::new();
 
-pub async fn parse_lumberyard_source() -> io::Result<()> {
-    tracing_subscriber::fmt::init();
-    AZ_CRC
-        .set(Regex::new(r#"AZ_CRC\("([^"]+)"(?:,\s*0x([0-9a-fA-F]+))?\)"#).unwrap())
-        .unwrap();
-    AZ_TYPE_INFO
-        .set(
-            Regex::new(r#"AZ_TYPE_INFO\(\s*(\w+)\s*,\s*\"(\{[0-9A-Fa-f\-]+\})\"\s*(?:,.*)?\)"#)
-                .unwrap(),
-        )
-        .unwrap();
-    ATTRIBUTE
-        .set(Regex::new(r#"(\w+)\s*=\s*"([^"]*)""#).unwrap())
-        .unwrap();
-
-    let mut tasks = JoinSet::new();
-    let (tx, mut rx) = mpsc::unbounded_channel::<(HashMap<u32, String>, HashMap<Uuid, String>)>();
-    let tx = Arc::new(tx);
-
-    tasks.spawn(async move {
-        let mut crcs: HashMap<u32, String> = HashMap::new();
-        let mut uuids: HashMap<Uuid, String> = HashMap::new();
-        while let Some((crc, uuid)) = rx.recv().await {
-            if !crc.is_empty() {
-                crcs.par_extend(crc);
-            }
-            if !uuid.is_empty() {
-                uuids.par_extend(uuid);
-            }
-        }
-
-        let ly = LumberyardSource { crcs, uuids };
+/// Where a dictionary built with `hashes build` is written by default and loaded from on top of
+/// the one bundled with the binary.
+pub fn dictionary_path() -> Option<PathBuf> {
+    dirs::config_local_dir().map(|dir| dir.join("nwtools-hashes.json"))
+}
 
-        let mut file = tokio::fs::File::create("E:/docs/nw-tools-rust/ly.json")
-            .await
-            .unwrap();
-        let data = serde_json::to_string_pretty(&ly).unwrap();
-        let mut data = data.as_bytes();
-        tokio::io::copy(&mut data, &mut file).await.unwrap();
+/// Scans a Lumberyard/O3DE source tree for `AZ_CRC` names, `AZ_TYPE_INFO` uuids and the class
+/// and field names of XML object streams.
+pub async fn parse_lumberyard_source<P: AsRef<Path>>(source: P) -> io::Result<LumberyardSource> {
+    AZ_CRC.get_or_init(|| Regex::new(r#"AZ_CRC\("([^"]+)"(?:,\s*0x([0-9a-fA-F]+))?\)"#).unwrap());
+    AZ_TYPE_INFO.get_or_init(|| {
+        Regex::new(r#"AZ_TYPE_INFO\(\s*(\w+)\s*,\s*\"(\{[0-9A-Fa-f\-]+\})\"\s*(?:,.*)?\)"#).unwrap()
     });
-
-    let (task_tx, mut task_rx) = mpsc::unbounded_channel();
-    let task_tx = Arc::new(task_tx);
+    ATTRIBUTE.get_or_init(|| Regex::new(r#"(\w+)\s*=\s*"([^"]*)""#).unwrap());
+
+    let source = source.as_ref().to_path_buf();
+    if !source.is_dir() {
+        return Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("{} is not a directory", source.display()),
+        ));
+    }
 
     info!("Starting to parse lumberyard");
 
-    tokio::task::spawn_blocking(move || {
-        WalkDir::new("e:/lumberyard/dev")
+    let files = tokio::task::spawn_blocking(move || {
+        WalkDir::new(source)
             .sort_by_file_name()
             .into_iter()
             .filter_map(|dir| dir.ok())
             .filter(|entry| entry.file_type().is_file())
-            .par_bridge()
-            .for_each(move |file| {
-                let file_name = file.file_name().to_str().unwrap();
-                let span = info_span!("File", name = %file_name);
-                let tx = tx.clone();
-                let fut = async move {
-                    let size = file.metadata().unwrap().file_size();
-                    let mut file = fs::File::open(file.path()).await.unwrap();
-                    let mut buf = Vec::with_capacity(size as usize);
-                    file.read_to_end(&mut buf).await.unwrap();
-                    let (crc, uuid) = parse(&mut buf).await;
-                    tx.send((crc, uuid)).unwrap();
-                }
-                .instrument(span);
-                task_tx.send(fut).unwrap();
-            });
+            .map(|entry| entry.into_path())
+            .collect::<Vec<_>>()
     })
     .await
-    .unwrap();
+    .map_err(io::Error::other)?;
 
-    while let Some(fut) = task_rx.recv().await {
-        tasks.spawn(fut);
+    // bounds the number of files read into memory at once
+    let permits = Arc::new(Semaphore::new(64));
+    let mut tasks = JoinSet::new();
+    for path in files {
+        let permits = permits.clone();
+        let span = info_span!("File", name = %path.display());
+        tasks.spawn(
+            async move {
+                let _permit = permits.acquire_owned().await.map_err(io::Error::other)?;
+                let mut buf = fs::read(&path).await?;
+                Ok::<_, io::Error>(parse(&mut buf).await)
+            }
+            .instrument(span),
+        );
     }
 
-    while let Some(_task) = tasks.join_next().await {
-        // let len = tasks.len();
-        // let is_err = task.is_err();
-        // info!("Tasks: {} | Err: {}", len, is_err);
+    let mut ly = LumberyardSource::default();
+    while let Some(task) = tasks.join_next().await {
+        match task.map_err(io::Error::other)? {
+            Ok((crcs, uuids)) => {
+                ly.crcs.extend(crcs);
+                ly.uuids.extend(uuids);
+            }
+            Err(e) => info!("Skipping file: {}", e),
+        }
     }
 
     info!("Done");
-    Ok(())
+    Ok(ly)
 }
 
 async fn parse(buf: &mut Vec<u8>) -> (HashMap<u32, String>, HashMap<Uuid, String>) {
@@ -145,7 +126,9 @@ async fn parse(buf: &mut Vec<u8>) -> (HashMap<u32, String>, HashMap<Uuid, String
             let class = captures.get(1).unwrap().as_str().to_string();
             let uuid = captures.get(2).unwrap().as_str().to_string();
             info!(uuid, class);
-            uuids.insert(Uuid::from_str(&uuid).unwrap(), class);
+            if let Ok(uuid) = Uuid::from_str(&uuid) {
+                uuids.insert(uuid, class);
+            }
         }
     }
 
@@ -187,7 +170,9 @@ async fn xml(buf: &mut Vec<u8>) -> (HashMap<u32, String>, HashMap<Uuid, String>)
             }
             if key == "type" && !name.is_empty() {
                 info!("value: {} name: {}", value, name);
-                uuids.insert(Uuid::parse_str(value).unwrap(), name.to_owned());
+                if let Ok(uuid) = Uuid::parse_str(value) {
+                    uuids.insert(uuid, name.to_owned());
+                }
             }
         }
     }
@@ -1,4 +1,4 @@
-use std::array::TryFromSliceError;
+use std::{array::TryFromSliceError, io};
 
 use serde_json::{json, Value};
 use uuid::Uuid;
@@ -64,6 +64,9 @@ pub const QUATERNION: Uuid = Uuid::from_u128(0x73103120_3DD3_4873_BAB3_9713FA280
 pub const COLOR: Uuid = Uuid::from_u128(0x7894072A_9050_4F0F_901B_34B1A0D29417);
 pub const MATRIX3X3: Uuid = Uuid::from_u128(0x15A4332F_7C3F_4A58_AC35_50E1CE53FB9C);
 
+/// Where the hint of `Asset<T>` data starts, after its id, type and the hint's length.
+const ASSET_HINT_OFFSET: usize = 56;
+
 pub fn uuid_data_to_serialize(
     id: &Uuid,
     data: &[u8],
@@ -94,14 +97,15 @@ pub fn uuid_data_to_serialize(
             let guid = Uuid::from_bytes(data[0..16].try_into()?)
                 .braced()
                 .encode_upper(&mut buf);
-            let sub_id = u64::from_be_bytes(data[16..32].try_into()?);
+            // the rest of the asset id's 32 bytes is padding
+            let sub_id = u32::from_be_bytes(data[16..20].try_into()?);
             let mut buf = Uuid::encode_buffer();
             let _type = Uuid::from_bytes(data[32..48].try_into()?)
                 .braced()
                 .encode_upper(&mut buf);
-            let size = u64::from_be_bytes(data[48..56].try_into()?);
-            let hint = String::from_utf8_lossy(&data[56..]);
-            assert_eq!(hint.len(), size as usize);
+            let size = u64::from_be_bytes(data[48..ASSET_HINT_OFFSET].try_into()?) as usize;
+            let hint = &data[ASSET_HINT_OFFSET..];
+            let hint = String::from_utf8_lossy(hint.get(..size).unwrap_or(hint));
             if is_json {
                 json!({"assetId": json!({ "guid": guid, "subId": sub_id}), "type": _type, "hint": hint})
             } else {
@@ -134,3 +138,108 @@ pub fn uuid_data_to_serialize(
     };
     Ok(res)
 }
+
+fn value_text(value: &Value) -> Option<String> {
+    match value {
+        Value::String(string) => Some(string.to_owned()),
+        Value::Number(number) => Some(number.to_string()),
+        Value::Bool(bool) => Some(bool.to_string()),
+        _ => None,
+    }
+}
+
+/// Encodes a value produced by [`uuid_data_to_serialize`] back into its binary form, failing on a
+/// value that doesn't parse as its type.
+pub fn uuid_data_from_value(id: &Uuid, value: &Value) -> io::Result<Vec<u8>> {
+    encode(id, value).ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("{value} is not a valid value of type {}", id.braced()),
+        )
+    })
+}
+
+fn encode(id: &Uuid, value: &Value) -> Option<Vec<u8>> {
+    let text = || value_text(value);
+    let data = match *id {
+        CHAR | AZ_S8 | SIGNED_CHAR => text()?.parse::<i8>().ok()?.to_be_bytes().to_vec(),
+        SHORT => text()?.parse::<i16>().ok()?.to_be_bytes().to_vec(),
+        INT => text()?.parse::<i32>().ok()?.to_be_bytes().to_vec(),
+        LONG | AZ_S64 => text()?.parse::<i64>().ok()?.to_be_bytes().to_vec(),
+
+        UNSIGNED_CHAR => text()?.parse::<u8>().ok()?.to_be_bytes().to_vec(),
+        UNSIGNED_SHORT => text()?.parse::<u16>().ok()?.to_be_bytes().to_vec(),
+        UNSIGNED_INT => text()?.parse::<u32>().ok()?.to_be_bytes().to_vec(),
+        UNSIGNED_LONG | AZ_U64 => text()?.parse::<u64>().ok()?.to_be_bytes().to_vec(),
+
+        FLOAT => text()?.parse::<f32>().ok()?.to_be_bytes().to_vec(),
+        DOUBLE => text()?.parse::<f64>().ok()?.to_be_bytes().to_vec(),
+
+        BOOL => vec![text()?.parse::<bool>().ok()? as u8],
+
+        AZ_UUID => Uuid::parse_str(&text()?).ok()?.as_bytes().to_vec(),
+
+        ASSET => encode_asset(value)?,
+
+        VECTOR2 | VECTOR3 | TRANSFORM | COLOR | MATRIX3X3 => {
+            // JSON stringifies the array, XML joins the components with spaces
+            let components = match value {
+                Value::Array(values) => {
+                    values.iter().map(value_text).collect::<Option<Vec<_>>>()?
+                }
+                Value::String(string) if string.starts_with('[') => {
+                    serde_json::from_str::<Vec<Value>>(string)
+                        .ok()?
+                        .iter()
+                        .map(value_text)
+                        .collect::<Option<Vec<_>>>()?
+                }
+                Value::String(string) => string.split_whitespace().map(str::to_owned).collect(),
+                _ => return None,
+            };
+            components
+                .iter()
+                .map(|component| component.parse::<f32>().ok().map(f32::to_be_bytes))
+                .collect::<Option<Vec<_>>>()?
+                .concat()
+        }
+
+        _ => text()?.into_bytes(),
+    };
+    Some(data)
+}
+
+/// `Asset<T>` as the engine stores it: the asset id, its guid and sub id padded to the id's 32
+/// bytes, the asset type, then the length of the hint and the hint.
+fn encode_asset(value: &Value) -> Option<Vec<u8>> {
+    let (guid, sub_id, asset_type, hint) = match value {
+        Value::Object(_) => (
+            value["assetId"]["guid"].as_str()?,
+            value["assetId"]["subId"].as_u64()?,
+            value["type"].as_str()?,
+            value["hint"].as_str()?,
+        ),
+        // JSON stringifies the object
+        Value::String(string) if string.starts_with('{') => {
+            return encode_asset(&serde_json::from_str(string).ok()?)
+        }
+        // id={GUID}:SUBID,type={TYPE},hint={HINT}
+        Value::String(string) => {
+            let (guid, rest) = string.strip_prefix("id=")?.split_once(':')?;
+            let (sub_id, rest) = rest.split_once(",type=")?;
+            let (asset_type, hint) = rest.split_once(",hint=")?;
+            let hint = hint.strip_prefix('{')?.strip_suffix('}')?;
+            (guid, sub_id.parse().ok()?, asset_type, hint)
+        }
+        _ => return None,
+    };
+
+    let mut data = Vec::with_capacity(ASSET_HINT_OFFSET + hint.len());
+    data.extend(Uuid::parse_str(guid).ok()?.as_bytes());
+    data.extend(u32::try_from(sub_id).ok()?.to_be_bytes());
+    data.resize(32, 0);
+    data.extend(Uuid::parse_str(asset_type).ok()?.as_bytes());
+    data.extend((hint.len() as u64).to_be_bytes());
+    data.extend(hint.as_bytes());
+    Some(data)
+}